@@ -0,0 +1,166 @@
+// adjacency.rs
+// This module compares each state's efficiency change with its bordering states' on the state adjacency graph,
+// and uses breadth-first search to average the changes 1, 2 and 3 borders away, summarizing how far a regional
+// pattern (spill-over) reaches.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::analysis::StateEfficiency;
+use crate::geography::state_adjacency;
+use crate::monthly::format_optional;
+use crate::stats::{linear_regression, mean};
+
+/// Rings of the breadth-first search that are averaged (1 = direct neighbors).
+pub const MAX_HOPS: usize = 3;
+
+/// One state's change against its neighbors'.
+#[derive(Debug, Clone, Serialize)]
+pub struct NeighborComparison {
+    pub state: String,
+    pub delta: f64,
+
+    /// Bordering states, alphabetically.
+    pub neighbors: Vec<String>,
+
+    /// Mean change of the bordering states with results, and the state's own change minus it.
+    pub neighbor_delta: Option<f64>,
+    pub difference: Option<f64>,
+
+    /// Mean change of the states exactly 1, 2, ... `MAX_HOPS` borders away.
+    pub ring_deltas: Vec<Option<f64>>,
+}
+
+/// How strongly a state's change goes with that of the states a given number of borders away.
+#[derive(Debug, Clone, Serialize)]
+pub struct Spillover {
+    pub hops: usize,
+
+    /// States with results in the ring.
+    pub states: usize,
+
+    /// Pearson correlation between the state's change and its ring mean.
+    pub correlation: Option<f64>,
+}
+
+/// Breadth-first search from `start`; returns each reachable state's distance in borders.
+fn hop_distances<'a>(graph: &BTreeMap<&'a str, Vec<&'a str>>, start: &'a str) -> HashMap<&'a str, usize> {
+    let mut distances = HashMap::from([(start, 0)]);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(state) = queue.pop_front() {
+        let distance = distances[state];
+        for neighbor in graph.get(state).into_iter().flatten() {
+            if !distances.contains_key(neighbor) {
+                distances.insert(neighbor, distance + 1);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    distances
+}
+
+/// Compares every state's change with its neighbors' and with the states 2 and 3 borders away.
+/// # Arguments
+/// * `changes` - The efficiency table
+/// # Returns
+/// * `Vec<NeighborComparison>` in table order (states without borders have no neighbor values)
+pub fn compare_neighbors(changes: &[StateEfficiency]) -> Vec<NeighborComparison> {
+    let graph = state_adjacency();
+    let deltas: HashMap<&str, f64> = changes.iter().map(|c| (c.state.as_str(), c.delta)).collect();
+
+    changes
+        .iter()
+        .map(|change| {
+            let neighbors: Vec<String> =
+                graph.get(change.state.as_str()).into_iter().flatten().map(|s| s.to_string()).collect();
+
+            let mut rings: Vec<Vec<f64>> = vec![Vec::new(); MAX_HOPS];
+            if let Some((start, _)) = graph.get_key_value(change.state.as_str()) {
+                for (state, distance) in hop_distances(&graph, start) {
+                    if (1..=MAX_HOPS).contains(&distance)
+                        && let Some(delta) = deltas.get(state)
+                    {
+                        rings[distance - 1].push(*delta);
+                    }
+                }
+            }
+
+            let ring_deltas: Vec<Option<f64>> = rings.iter().map(|r| mean(r)).collect();
+            NeighborComparison {
+                state: change.state.clone(),
+                delta: change.delta,
+                neighbors,
+                neighbor_delta: ring_deltas[0],
+                difference: ring_deltas[0].map(|n| change.delta - n),
+                ring_deltas,
+            }
+        })
+        .collect()
+}
+
+/// Correlation of the states' changes with their ring means, for each ring.
+pub fn spillover_summary(data: &[NeighborComparison]) -> Vec<Spillover> {
+    (0..MAX_HOPS)
+        .map(|ring| {
+            let (own, around): (Vec<f64>, Vec<f64>) =
+                data.iter().filter_map(|c| Some((c.delta, c.ring_deltas[ring]?))).unzip();
+            let correlation = linear_regression(&around, &own).map(|fit| fit.r_squared.sqrt().copysign(fit.slope));
+            Spillover { hops: ring + 1, states: own.len(), correlation }
+        })
+        .collect()
+}
+
+/// Writes each state's change next to its neighbors' to a CSV output file.
+pub fn write_neighbor_comparison_csv(path: &str, data: &[NeighborComparison]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    let mut header = vec!["State".to_string(), "Delta".to_string(), "Neighbors".to_string()];
+    header.extend(["Neighbor_Delta".to_string(), "Difference".to_string()]);
+    header.extend((1..=MAX_HOPS).map(|h| format!("Ring_{}_Delta", h)));
+    wtr.write_record(&header)?;
+
+    for item in data {
+        let mut row = vec![item.state.clone(), format!("{:.6}", item.delta), item.neighbors.join(" ")];
+        row.push(format_optional(item.neighbor_delta));
+        row.push(format_optional(item.difference));
+        row.extend(item.ring_deltas.iter().map(|d| format_optional(*d)));
+        wtr.write_record(&row)?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::{MegawattHours, Mmbtu};
+    use crate::analysis::compute_efficiency_changes;
+    use crate::cleaning::StateStats;
+
+    #[test]
+    fn test_neighbors_and_rings() {
+        // ME borders only NH; NH borders ME, VT and MA; HI has no borders
+        let states = [("ME", 8.0), ("NH", 10.0), ("VT", 12.0), ("MA", 11.0), ("NY", 9.0), ("HI", 10.0)];
+        let stats = |fuel: f64| StateStats { total_fuel: Mmbtu(fuel), total_gen: MegawattHours(1.0) };
+        let base: HashMap<String, StateStats> = states.iter().map(|(s, _)| (s.to_string(), stats(10.0))).collect();
+        let compare: HashMap<String, StateStats> = states.iter().map(|(s, f)| (s.to_string(), stats(*f))).collect();
+        let changes = compute_efficiency_changes(&base, &compare);
+
+        let result = compare_neighbors(&changes);
+        let me = result.iter().find(|c| c.state == "ME").unwrap();
+        assert_eq!(me.neighbors, ["NH"]);
+        assert_eq!((me.neighbor_delta, me.difference), (Some(0.0), Some(-2.0)));
+        assert_eq!(me.ring_deltas, [Some(0.0), Some(1.5), Some(-1.0)]);
+
+        let hi = result.iter().find(|c| c.state == "HI").unwrap();
+        assert!(hi.neighbors.is_empty() && hi.neighbor_delta.is_none());
+
+        let summary = spillover_summary(&result);
+        assert_eq!((summary[0].hops, summary[0].states), (1, 5));
+    }
+}
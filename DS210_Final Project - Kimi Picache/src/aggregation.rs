@@ -0,0 +1,138 @@
+// aggregation.rs
+// This module offers alternatives to summing a state's rows before taking the fuel / generation ratio. The ratio
+// of sums weights plants by output (the state's fleet heat rate); a trimmed sum drops the plants with the most
+// extreme heat rates first; the median of plant heat rates describes the typical plant instead.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+use crate::cleaning::{aggregate_by_state_plant, CleanRecord, StateStats, INCREMENT_PLANT_ID};
+use crate::parallel::{aggregate_by_state_parallel, Parallelism};
+use crate::quantity::{HeatRate, MegawattHours, Mmbtu};
+use crate::stats::median;
+
+/// How a state's rows are combined into its heat rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum Aggregation {
+    /// Total fuel / total generation (ratio of sums).
+    #[default]
+    Sum,
+    /// Ratio of sums after dropping the `--trim` share of plants with the lowest and the highest heat rates.
+    Trimmed,
+    /// Median of the plant heat rates (mean of ratios, robust version).
+    PlantMedian,
+}
+
+impl Aggregation {
+    /// Name as given on the command line.
+    pub fn label(self) -> &'static str {
+        match self {
+            Aggregation::Sum => "sum",
+            Aggregation::Trimmed => "trimmed",
+            Aggregation::PlantMedian => "plant-median",
+        }
+    }
+
+    /// Description for the methodology block and the CSV preamble.
+    pub fn describe(self, trim: f64) -> String {
+        match self {
+            Aggregation::Sum => "ratio of summed fuel and generation".to_string(),
+            Aggregation::Trimmed => format!(
+                "ratio of summed fuel and generation after dropping {:.0}% of plants at each end of the heat rate range",
+                trim * 100.0
+            ),
+            Aggregation::PlantMedian => "median of plant heat rates".to_string(),
+        }
+    }
+}
+
+/// Combines each state's rows with the chosen aggregation. Generation is always the state's total; for the
+/// trimmed and median versions fuel is set so that fuel / generation is the chosen heat rate, so generation
+/// weights and fuel-based figures elsewhere keep their units.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// * `aggregation` - How plants are combined
+/// * `trim` - Share of plants dropped at each end (0-0.5) for `Trimmed`
+/// * `parallelism` - Threads used for the state totals
+/// # Returns
+/// * `HashMap<String, StateStats>` keyed by state
+pub fn aggregate_states(
+    records: &[CleanRecord],
+    aggregation: Aggregation,
+    trim: f64,
+    parallelism: Parallelism,
+) -> HashMap<String, StateStats> {
+    let mut state_map = aggregate_by_state_parallel(records, parallelism);
+    if aggregation == Aggregation::Sum {
+        return state_map;
+    }
+
+    // Plant heat rates per state; increments and plants without positive generation are not ranked
+    let mut plants: HashMap<String, Vec<(f64, f64, f64)>> = HashMap::new();
+    for ((state, plant_id), plant) in aggregate_by_state_plant(records) {
+        if plant_id != INCREMENT_PLANT_ID && plant.total_gen > 0.0 {
+            plants.entry(state).or_default().push((plant.total_fuel / plant.total_gen, plant.total_fuel, plant.total_gen));
+        }
+    }
+
+    for (state, stats) in state_map.iter_mut() {
+        let Some(rates) = plants.get_mut(state) else {
+            continue;
+        };
+        rates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let heat_rate = match aggregation {
+            Aggregation::Sum => continue,
+            Aggregation::Trimmed => {
+                let cut = (rates.len() as f64 * trim.clamp(0.0, 0.5)).floor() as usize;
+                let dropped = rates.iter().take(cut).chain(rates.iter().rev().take(cut));
+                let totals = (stats.total_fuel, stats.total_gen);
+                let (fuel, r#gen) = dropped.fold(totals, |(f, g), p| (f - Mmbtu(p.1), g - MegawattHours(p.2)));
+                match HeatRate::of(fuel, r#gen) {
+                    Some(rate) => rate,
+                    None => continue,
+                }
+            }
+            Aggregation::PlantMedian => {
+                let Some(rate) = median(&rates.iter().map(|p| p.0).collect::<Vec<f64>>()) else {
+                    continue;
+                };
+                HeatRate(rate)
+            }
+        };
+        stats.total_fuel = heat_rate * stats.total_gen;
+    }
+
+    state_map
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_aggregations_disagree() {
+        // One large efficient plant and four small ones, one of them with an implausible heat rate
+        let records = [
+            record("1", "AA", "NG", 700.0, 100.0),
+            record("2", "AA", "NG", 100.0, 10.0),
+            record("3", "AA", "NG", 110.0, 10.0),
+            record("4", "AA", "NG", 120.0, 10.0),
+            record("5", "AA", "NG", 500.0, 10.0),
+        ];
+        let heat_rate = |aggregation, trim| {
+            let stats = &aggregate_states(&records, aggregation, trim, Parallelism::default())["AA"];
+            ((stats.total_fuel / stats.total_gen).0, stats.total_gen.0)
+        };
+
+        assert_eq!(heat_rate(Aggregation::Sum, 0.0), (1530.0 / 140.0, 140.0));
+        assert_eq!(heat_rate(Aggregation::Trimmed, 0.2), (330.0 / 30.0, 140.0));
+        assert_eq!(heat_rate(Aggregation::Trimmed, 0.0).0, 1530.0 / 140.0);
+        assert_eq!(heat_rate(Aggregation::PlantMedian, 0.0), (11.0, 140.0));
+    }
+}
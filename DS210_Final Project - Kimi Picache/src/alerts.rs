@@ -0,0 +1,129 @@
+// alerts.rs
+// This module flags states whose efficiency change exceeds a threshold, for automated monitoring of data revisions.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::analysis::StateEfficiency;
+
+/// Exit status used when at least one alert fires.
+pub const ALERT_EXIT_CODE: i32 = 3;
+
+/// Change threshold: absolute (MMBtu/MWh, e.g. "0.25") or relative to the base value (e.g. "5%").
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertThreshold {
+    Absolute(f64),
+    Percent(f64),
+}
+
+impl FromStr for AlertThreshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (number, percent) = match s.strip_suffix('%') {
+            Some(number) => (number, true),
+            None => (s, false),
+        };
+
+        let value: f64 = number.trim().parse().map_err(|_| format!("invalid threshold '{}'", s))?;
+        if !value.is_finite() || value <= 0.0 {
+            return Err(format!("threshold '{}' must be positive", s));
+        }
+        Ok(if percent { AlertThreshold::Percent(value) } else { AlertThreshold::Absolute(value) })
+    }
+}
+
+impl fmt::Display for AlertThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertThreshold::Absolute(value) => write!(f, "{} MMBtu/MWh", value),
+            AlertThreshold::Percent(value) => write!(f, "{}%", value),
+        }
+    }
+}
+
+/// A state whose change crossed the threshold. As in `StateEfficiency`, the `_2019` and `_2020` fields hold the
+/// base and compare inputs.
+#[derive(Debug, Serialize)]
+pub struct Alert {
+    pub state: String,
+    pub eff_2019: f64,
+    pub eff_2020: f64,
+    pub delta: f64,
+
+    /// Change relative to the base value, in percent.
+    pub pct_change: f64,
+}
+
+/// Returns the states whose change exceeds the threshold, largest absolute change first.
+pub fn find_alerts(changes: &[StateEfficiency], threshold: AlertThreshold) -> Vec<Alert> {
+    let mut alerts: Vec<Alert> = changes
+        .iter()
+        .map(|item| Alert {
+            state: item.state.clone(),
+            eff_2019: item.eff_2019,
+            eff_2020: item.eff_2020,
+            delta: item.delta,
+            pct_change: item.delta / item.eff_2019 * 100.0,
+        })
+        .filter(|alert| match threshold {
+            AlertThreshold::Absolute(limit) => alert.delta.abs() > limit,
+            AlertThreshold::Percent(limit) => alert.pct_change.abs() > limit,
+        })
+        .collect();
+
+    alerts.sort_by(|a, b| b.delta.abs().partial_cmp(&a.delta.abs()).unwrap());
+    alerts
+}
+
+/// Writes the alerts to a CSV output file, naming the efficiency columns after the input labels.
+pub fn write_alerts_csv(
+    path: &str,
+    data: &[Alert],
+    threshold: AlertThreshold,
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let [a, b] = labels;
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State".to_string(),
+        format!("Efficiency_{a}"),
+        format!("Efficiency_{b}"),
+        "Delta_Efficiency".to_string(),
+        "Pct_Change".to_string(),
+        "Threshold".to_string(),
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            &format!("{:.6}", item.eff_2019),
+            &format!("{:.6}", item.eff_2020),
+            &format!("{:.6}", item.delta),
+            &format!("{:.2}", item.pct_change),
+            &threshold.to_string(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_threshold() {
+        assert_eq!("0.25".parse(), Ok(AlertThreshold::Absolute(0.25)));
+        assert_eq!(" 5 %".parse(), Ok(AlertThreshold::Percent(5.0)));
+        assert!("0".parse::<AlertThreshold>().is_err());
+        assert!("five%".parse::<AlertThreshold>().is_err());
+    }
+}
@@ -0,0 +1,563 @@
+// analysis.rs
+// This module computes the year-over-year efficiency change per state and the benchmark, rank and grade columns
+// derived from it.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::builder::{AnalysisBuilder, AnalysisInput};
+use crate::cleaning::{aggregate_by_state, load_clean_records, StateStats};
+use crate::grading::assign_grades;
+use crate::palette::{TerminalColors, Tone};
+use crate::report::{Cell, Report};
+use crate::rounding::RoundingPolicy;
+use crate::sink::{OutputSink, OutputTable};
+use crate::stats::{median, percent_rank, percentile_rank, ranks};
+use crate::units::HeatRateUnit;
+
+/// Struct to hold the year-over-year efficiency data for a state.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateEfficiency {
+    /// State abbreviation (e.g., "CA", "TX").
+    pub state: String,
+
+    /// Efficiency in 2019 (fuel used per MWh).
+    pub eff_2019: f64,
+
+    /// Efficiency in 2020.
+    pub eff_2020: f64,
+
+    /// Change in efficiency (2020 - 2019).
+    pub delta: f64,
+
+    /// Absolute change in efficiency (magnitude only).
+    pub abs_delta: f64,
+
+    /// Change weighted by the state's size: delta × 2020 generation, i.e. the extra fuel (MMBtu) burned
+    /// for the 2020 output compared with 2019 efficiency.
+    pub weighted_delta: f64,
+
+    /// 2019 efficiency divided by the 2019 national median of state efficiencies.
+    pub rel_median_2019: f64,
+
+    /// 2020 efficiency divided by the 2020 national median of state efficiencies.
+    pub rel_median_2020: f64,
+
+    /// Percentile rank (0-100) of the 2019 efficiency among states; lower is more efficient.
+    pub pctile_2019: f64,
+
+    /// Percentile rank (0-100) of the 2020 efficiency among states.
+    pub pctile_2020: f64,
+
+    /// Change in percentile rank (2020 - 2019).
+    pub pctile_change: f64,
+
+    /// Efficiency rank among states in 2019 (1 = most efficient).
+    pub rank_2019: usize,
+
+    /// Efficiency rank among states in 2020.
+    pub rank_2020: usize,
+
+    /// Places gained between years (rank_2019 - rank_2020); positive means the state moved up.
+    pub rank_change: i64,
+
+    /// Percent rank (0-100) of the 2019 efficiency: 0 = most efficient state, 100 = least efficient.
+    pub pct_rank_2019: f64,
+
+    /// Percent rank (0-100) of the 2020 efficiency.
+    pub pct_rank_2020: f64,
+
+    /// Percent rank (0-100) of the change: 0 = largest improvement, 100 = largest deterioration.
+    pub pct_rank_improvement: f64,
+
+    /// Grade band of the 2020 efficiency (first band = most efficient).
+    pub eff_grade: String,
+
+    /// Grade band of the change in efficiency (first band = largest improvement).
+    pub improvement_grade: String,
+}
+
+/// Computes efficiency change metrics per state based on aggregated data.
+/// # Arguments
+/// * `stats_2019` - Map of 2019 state data
+/// * `stats_2020` - Map of 2020 state data
+/// # Returns
+/// * `Vec<StateEfficiency>` representing efficiency differences by state
+pub fn compute_efficiency_changes(
+    stats_2019: &HashMap<String, StateStats>,
+    stats_2020: &HashMap<String, StateStats>,
+) -> Vec<StateEfficiency> {
+    let mut output = Vec::new();
+
+    for (state, stat_2019) in stats_2019 {
+        if let Some(stat_2020) = stats_2020.get(state) {
+            if stat_2019.total_gen.0 == 0.0 || stat_2020.total_gen.0 == 0.0 {
+                continue;
+            }
+
+            // Calculate efficiency = fuel / generation
+            let eff_2019 = stat_2019.total_fuel / stat_2019.total_gen;
+            let eff_2020 = stat_2020.total_fuel / stat_2020.total_gen;
+            let delta = eff_2020 - eff_2019;
+            let weighted_delta = delta * stat_2020.total_gen;
+
+            output.push(StateEfficiency {
+                state: state.clone(),
+                eff_2019: eff_2019.0,
+                eff_2020: eff_2020.0,
+                delta: delta.0,
+                abs_delta: delta.abs().0,
+                weighted_delta: weighted_delta.0,
+                rel_median_2019: 0.0,
+                rel_median_2020: 0.0,
+                pctile_2019: 0.0,
+                pctile_2020: 0.0,
+                pctile_change: 0.0,
+                rank_2019: 0,
+                rank_2020: 0,
+                rank_change: 0,
+                pct_rank_2019: 0.0,
+                pct_rank_2020: 0.0,
+                pct_rank_improvement: 0.0,
+                eff_grade: String::new(),
+                improvement_grade: String::new(),
+            });
+        }
+    }
+
+    fill_benchmarks(&mut output);
+    output
+}
+
+/// Fills in each state's position relative to the national median, its percentile rank and its rank.
+fn fill_benchmarks(data: &mut [StateEfficiency]) {
+    let effs_2019: Vec<f64> = data.iter().map(|d| d.eff_2019).collect();
+    let effs_2020: Vec<f64> = data.iter().map(|d| d.eff_2020).collect();
+    let deltas: Vec<f64> = data.iter().map(|d| d.delta).collect();
+    let median_2019 = median(&effs_2019).unwrap_or(0.0);
+    let median_2020 = median(&effs_2020).unwrap_or(0.0);
+    let ranks_2019 = ranks(&effs_2019);
+    let ranks_2020 = ranks(&effs_2020);
+
+    for (i, item) in data.iter_mut().enumerate() {
+        item.rel_median_2019 = item.eff_2019 / median_2019;
+        item.rel_median_2020 = item.eff_2020 / median_2020;
+        item.pctile_2019 = percentile_rank(&effs_2019, item.eff_2019);
+        item.pctile_2020 = percentile_rank(&effs_2020, item.eff_2020);
+        item.pctile_change = item.pctile_2020 - item.pctile_2019;
+        item.rank_2019 = ranks_2019[i];
+        item.rank_2020 = ranks_2020[i];
+        item.rank_change = ranks_2019[i] as i64 - ranks_2020[i] as i64;
+        item.pct_rank_2019 = percent_rank(&effs_2019, item.eff_2019);
+        item.pct_rank_2020 = percent_rank(&effs_2020, item.eff_2020);
+        item.pct_rank_improvement = percent_rank(&deltas, item.delta);
+    }
+}
+
+/// Classifies every state into grade bands by 2020 efficiency and by improvement.
+pub fn apply_grades(data: &mut [StateEfficiency], labels: &[String]) {
+    let effs: Vec<f64> = data.iter().map(|d| d.eff_2020).collect();
+    let deltas: Vec<f64> = data.iter().map(|d| d.delta).collect();
+    let eff_grades = assign_grades(&effs, labels);
+    let improvement_grades = assign_grades(&deltas, labels);
+
+    for ((item, eff_grade), improvement_grade) in data.iter_mut().zip(eff_grades).zip(improvement_grades) {
+        item.eff_grade = eff_grade;
+        item.improvement_grade = improvement_grade;
+    }
+}
+
+/// Order of the top movers table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum RankBy {
+    /// Largest absolute change in heat rate first.
+    #[default]
+    Delta,
+    /// Largest absolute generation-weighted change (delta × compare-year generation) first, so small
+    /// states with volatile heat rates do not dominate.
+    Weighted,
+}
+
+impl RankBy {
+    /// Short description for table titles.
+    pub fn describe(self) -> &'static str {
+        match self {
+            RankBy::Delta => "Change in Fossil Fuel Efficiency",
+            RankBy::Weighted => "Generation-Weighted Change in Fossil Fuel Efficiency",
+        }
+    }
+}
+
+/// Sorts the table so the largest movers by `rank_by` come first.
+pub fn sort_changes(data: &mut [StateEfficiency], rank_by: RankBy) {
+    match rank_by {
+        RankBy::Delta => data.sort_by(|a, b| b.abs_delta.partial_cmp(&a.abs_delta).unwrap()),
+        RankBy::Weighted => data.sort_by(|a, b| b.weighted_delta.abs().partial_cmp(&a.weighted_delta.abs()).unwrap()),
+    }
+}
+
+/// Copies the table with the heat rate columns (efficiencies and their changes) converted from MMBtu/MWh
+/// to `unit`; the relative, percentile and rank columns do not depend on the unit.
+pub fn convert_units(data: &[StateEfficiency], unit: HeatRateUnit) -> Vec<StateEfficiency> {
+    data.iter()
+        .map(|item| StateEfficiency {
+            eff_2019: unit.convert(item.eff_2019),
+            eff_2020: unit.convert(item.eff_2020),
+            delta: unit.convert(item.delta),
+            abs_delta: unit.convert(item.abs_delta),
+            ..item.clone()
+        })
+        .collect()
+}
+
+/// Displays the top N improvers and top N regressors as two labeled sections.
+pub fn display_top_states(data: &[StateEfficiency], top_n: usize) {
+    print!("{}", format_top_states(data, top_n));
+}
+
+/// Formats the top N improvers and regressors as fixed-width text tables.
+pub fn format_top_states(data: &[StateEfficiency], top_n: usize) -> String {
+    format_top_states_labeled(data, top_n, ["2019", "2020"])
+}
+
+/// Columns shown in the top movers table.
+fn top_columns(labels: [&str; 2]) -> Vec<String> {
+    let [a, b] = labels;
+    vec![
+        "State".to_string(), format!("Efficiency_{a}"), format!("Efficiency_{b}"), "Delta_Efficiency".to_string(),
+        "Abs_Change".to_string(), format!("Rank_{a}"), format!("Rank_{b}"),
+    ]
+}
+
+/// Splits the table into its first `top_n` improvers (heat rate fell) and first `top_n` regressors (heat rate
+/// rose), each keeping the table's order, so both follow the `--rank-by` ranking. Unchanged states are in neither.
+pub fn split_movers(data: &[StateEfficiency], top_n: usize) -> (Vec<StateEfficiency>, Vec<StateEfficiency>) {
+    let improvers = data.iter().filter(|c| c.delta < 0.0).take(top_n).cloned().collect();
+    let regressors = data.iter().filter(|c| c.delta > 0.0).take(top_n).cloned().collect();
+    (improvers, regressors)
+}
+
+/// Headings of the improver and regressor sections.
+fn section_titles(top_n: usize) -> [String; 2] {
+    [
+        format!("Top {} improvers (heat rate fell)", top_n),
+        format!("Top {} regressors (heat rate rose)", top_n),
+    ]
+}
+
+/// Formats the top N improvers and regressors as two fixed-width text tables with a signed delta, naming the
+/// two inputs with `labels`.
+pub fn format_top_states_labeled(data: &[StateEfficiency], top_n: usize, labels: [&str; 2]) -> String {
+    format_top_states_colored(data, top_n, labels, TerminalColors::default(), &RoundingPolicy::default())
+}
+
+/// Formats the top movers like `format_top_states_labeled`, with the changes colored by `colors` and the numbers
+/// rounded by `rounding`.
+pub fn format_top_states_colored(
+    data: &[StateEfficiency],
+    top_n: usize,
+    labels: [&str; 2],
+    colors: TerminalColors,
+    rounding: &RoundingPolicy,
+) -> String {
+    let columns = top_columns(labels);
+    let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+    let (improvers, regressors) = split_movers(data, top_n);
+
+    let mut out = String::new();
+    for (title, section) in section_titles(top_n).iter().zip([improvers, regressors]) {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("{}:\n", title));
+        match section.is_empty() {
+            true => out.push_str("(none)\n"),
+            false => {
+                let table = efficiency_report(&section, labels, HeatRateUnit::MmbtuPerMwh)
+                    .with_rounding(rounding)
+                    .select(&columns);
+                let delta = table.columns.iter().position(|c| c.name == "Delta_Efficiency");
+                out.push_str(&table.to_terminal_painted(|i, cell, text| match cell {
+                    Cell::Float(value) if Some(i) == delta => colors.paint(text, Tone::of_delta(*value)),
+                    _ => text.to_string(),
+                }));
+            }
+        }
+    }
+    out
+}
+
+/// Formats the top N improvers and regressors as two HTML tables (used for notebook display).
+pub fn format_top_states_html(data: &[StateEfficiency], top_n: usize) -> String {
+    let labels = ["2019", "2020"];
+    let columns = top_columns(labels);
+    let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+    let (improvers, regressors) = split_movers(data, top_n);
+
+    let mut out = String::new();
+    for (title, section) in section_titles(top_n).iter().zip([improvers, regressors]) {
+        out.push_str(&format!("<h4>{}</h4>\n", title));
+        out.push_str(&efficiency_report(&section, labels, HeatRateUnit::MmbtuPerMwh).select(&columns).to_html());
+    }
+    out
+}
+
+/// High-level entry point for interactive use (e.g. evcxr notebooks): loads two files,
+/// computes the efficiency changes and displays as a table.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Analysis {
+    /// Aggregated state totals for the base file.
+    pub stats_base: HashMap<String, StateStats>,
+
+    /// Aggregated state totals for the compare file.
+    pub stats_compare: HashMap<String, StateStats>,
+
+    /// Efficiency changes, sorted by absolute change (largest first).
+    pub changes: Vec<StateEfficiency>,
+
+    /// Number of rows shown by `Display` and `to_html`.
+    pub display_rows: usize,
+}
+
+impl Analysis {
+    /// Loads both files and computes the efficiency changes with default (quartile) grades.
+    /// # Arguments
+    /// * `path_a` - Base-year EIA-923 CSV
+    /// * `path_b` - Compare-year EIA-923 CSV
+    pub fn quick(path_a: &str, path_b: &str) -> Result<Analysis, Box<dyn Error>> {
+        let stats_base = aggregate_by_state(&load_clean_records(path_a)?);
+        let stats_compare = aggregate_by_state(&load_clean_records(path_b)?);
+
+        let mut changes = compute_efficiency_changes(&stats_base, &stats_compare);
+        let labels: Vec<String> = ["Q1", "Q2", "Q3", "Q4"].iter().map(|s| s.to_string()).collect();
+        apply_grades(&mut changes, &labels);
+        sort_changes(&mut changes, RankBy::Delta);
+
+        Ok(Analysis { stats_base, stats_compare, changes, display_rows: 10 })
+    }
+
+    /// Configures an analysis of two inputs (file paths or cleaned rows); finish with `build`.
+    pub fn builder(base: impl AnalysisInput, compare: impl AnalysisInput) -> AnalysisBuilder {
+        AnalysisBuilder::new(base, compare)
+    }
+
+    /// Looks up one state's result.
+    pub fn state(&self, state: &str) -> Option<&StateEfficiency> {
+        self.changes.iter().find(|c| c.state == state)
+    }
+
+    /// The full efficiency table, for rendering as Markdown, CSV or JSON.
+    pub fn report(&self) -> Report {
+        efficiency_report(&self.changes, ["2019", "2020"], HeatRateUnit::MmbtuPerMwh)
+    }
+
+    /// Writes the full efficiency table through an output sink, e.g. one of your own for a storage service.
+    /// # Arguments
+    /// * `sink` - Where the table goes
+    /// * `name` - Table name, e.g. "efficiency_changes"
+    /// # Returns
+    /// * The local file written, if any
+    pub fn write_to(&self, sink: &mut dyn OutputSink, name: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let report = self.report();
+        let write_csv = |path: &str| report.write_csv(path);
+        sink.write_table(&OutputTable::new(name, &report)?.with_csv(&write_csv))
+    }
+
+    /// Renders the top movers as an HTML table.
+    pub fn to_html(&self) -> String {
+        format_top_states_html(&self.changes, self.display_rows)
+    }
+
+    /// Rich display hook picked up by the evcxr Jupyter kernel.
+    pub fn evcxr_display(&self) {
+        println!("EVCXR_BEGIN_CONTENT text/html\n{}\nEVCXR_END_CONTENT", self.to_html());
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_top_states(&self.changes, self.display_rows))
+    }
+}
+
+impl fmt::Display for StateEfficiency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:.3} → {:.3} MMBtu/MWh ({:+.3}), rank {} → {}",
+            self.state, self.eff_2019, self.eff_2020, self.delta, self.rank_2019, self.rank_2020
+        )
+    }
+}
+
+/// Writes the computed efficiency change data (in MMBtu/MWh) to a CSV output file.
+pub fn write_efficiency_csv(path: &str, data: &[StateEfficiency]) -> Result<(), Box<dyn Error>> {
+    write_efficiency_csv_labeled(path, data, ["2019", "2020"], HeatRateUnit::MmbtuPerMwh)
+}
+
+/// Builds the full efficiency table with the two inputs named by `labels` in the column headers
+/// (e.g. `Efficiency_early` / `Efficiency_final`). `unit` is the unit `data` is already in (see
+/// `convert_units`); it fills the `Units` column.
+pub fn efficiency_report(data: &[StateEfficiency], labels: [&str; 2], unit: HeatRateUnit) -> Report {
+    let [a, b] = labels;
+    let mut report = Report::new("Fossil fuel efficiency change by state")
+        .column("State", 0)
+        .column(format!("Efficiency_{a}"), 6)
+        .column(format!("Efficiency_{b}"), 6)
+        .column("Delta_Efficiency", 6)
+        .column("Abs_Change", 6)
+        .column("Weighted_Delta_MMBtu", 6)
+        .column(format!("Rel_Median_{a}"), 6)
+        .column(format!("Rel_Median_{b}"), 6)
+        .column(format!("Percentile_{a}"), 2)
+        .column(format!("Percentile_{b}"), 2)
+        .column("Percentile_Change", 2)
+        .column(format!("Rank_{a}"), 0)
+        .column(format!("Rank_{b}"), 0)
+        .column("Rank_Change", 0)
+        .column(format!("Percent_Rank_{a}"), 2)
+        .column(format!("Percent_Rank_{b}"), 2)
+        .column("Improvement_Percent_Rank", 2)
+        .column("Efficiency_Grade", 0)
+        .column("Improvement_Grade", 0)
+        .column("Units", 0);
+
+    for item in data {
+        report.push_row(vec![
+            item.state.as_str().into(),
+            item.eff_2019.into(),
+            item.eff_2020.into(),
+            item.delta.into(),
+            item.abs_delta.into(),
+            item.weighted_delta.into(),
+            item.rel_median_2019.into(),
+            item.rel_median_2020.into(),
+            item.pctile_2019.into(),
+            item.pctile_2020.into(),
+            item.pctile_change.into(),
+            item.rank_2019.into(),
+            item.rank_2020.into(),
+            item.rank_change.into(),
+            item.pct_rank_2019.into(),
+            item.pct_rank_2020.into(),
+            item.pct_rank_improvement.into(),
+            item.eff_grade.as_str().into(),
+            item.improvement_grade.as_str().into(),
+            unit.label().into(),
+        ]);
+    }
+
+    report
+}
+
+/// Writes the efficiency change data with the two inputs named by `labels` in the column headers
+/// (see `efficiency_report`).
+pub fn write_efficiency_csv_labeled(
+    path: &str,
+    data: &[StateEfficiency],
+    labels: [&str; 2],
+    unit: HeatRateUnit,
+) -> Result<(), Box<dyn Error>> {
+    efficiency_report(data, labels, unit).write_csv(path)
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::quantity::{MegawattHours, Mmbtu};
+
+
+   #[test]
+   fn test_efficiency_computation() {
+       let mut stats_2019 = HashMap::new();
+       let mut stats_2020 = HashMap::new();
+
+
+       stats_2019.insert(
+           "TX".to_string(),
+           StateStats {
+               total_fuel: Mmbtu(1000.0),
+               total_gen: MegawattHours(100.0),
+           },
+       );
+       stats_2020.insert(
+           "TX".to_string(),
+           StateStats {
+               total_fuel: Mmbtu(800.0),
+               total_gen: MegawattHours(100.0),
+           },
+       );
+
+
+       let results = compute_efficiency_changes(&stats_2019, &stats_2020);
+       assert_eq!(results.len(), 1);
+       let tx = &results[0];
+       assert_eq!(tx.state, "TX");
+       assert!((tx.eff_2019 - 10.0).abs() < 1e-6);
+       assert!((tx.eff_2020 - 8.0).abs() < 1e-6);
+       assert!((tx.delta + 2.0).abs() < 1e-6);
+       assert!((tx.abs_delta - 2.0).abs() < 1e-6);
+   }
+
+
+   #[test]
+   fn test_skipping_zero_generation() {
+       let mut stats_2019 = HashMap::new();
+       let mut stats_2020 = HashMap::new();
+
+
+       stats_2019.insert(
+           "CA".to_string(),
+           StateStats {
+               total_fuel: Mmbtu(500.0),
+               total_gen: MegawattHours(0.0),
+           },
+       );
+       stats_2020.insert(
+           "CA".to_string(),
+           StateStats {
+               total_fuel: Mmbtu(900.0),
+               total_gen: MegawattHours(0.0),
+           },
+       );
+
+
+       let results = compute_efficiency_changes(&stats_2019, &stats_2020);
+       assert_eq!(results.len(), 0);
+   }
+
+
+   #[test]
+   fn test_top_states_split_by_sign() {
+       let stats = |fuel: f64| StateStats { total_fuel: Mmbtu(fuel), total_gen: MegawattHours(100.0) };
+       let stats_2019: HashMap<String, StateStats> =
+           ["AA", "BB", "CC", "DD"].iter().map(|s| (s.to_string(), stats(1000.0))).collect();
+       let stats_2020 = HashMap::from([
+           ("AA".to_string(), stats(700.0)),
+           ("BB".to_string(), stats(1200.0)),
+           ("CC".to_string(), stats(900.0)),
+           ("DD".to_string(), stats(1000.0)),
+       ]);
+       let mut changes = compute_efficiency_changes(&stats_2019, &stats_2020);
+       sort_changes(&mut changes, RankBy::Delta);
+
+       let (improvers, regressors) = split_movers(&changes, 10);
+       let states = |section: &[StateEfficiency]| section.iter().map(|c| c.state.clone()).collect::<Vec<_>>();
+       assert_eq!(states(&improvers), ["AA", "CC"]);
+       assert_eq!(states(&regressors), ["BB"]);
+       assert_eq!(states(&split_movers(&changes, 1).0), ["AA"]);
+
+       let text = format_top_states(&changes, 10);
+       let regressors_at = text.find("regressors").unwrap();
+       assert!(text.find("AA").unwrap() < regressors_at && text.find("BB").unwrap() > regressors_at);
+       assert!(!text.contains("DD"));
+   }
+}
@@ -0,0 +1,20 @@
+// api.rs
+// The supported public surface of the library. Everything re-exported here follows semantic versioning:
+// it only changes incompatibly with a new major version. Types that may grow (`Analysis`, `AnalysisError`,
+// `ConfigProblem`) are `#[non_exhaustive]`, and `AnalysisInput` is sealed, so adding fields, variants or
+// input kinds is not a breaking change. The other modules are public for the `efficiency` binary and may
+// change in any release.
+
+pub use crate::aggregation::Aggregation;
+pub use crate::analysis::{Analysis, RankBy, StateEfficiency};
+pub use crate::builder::{AnalysisBuilder, AnalysisError, AnalysisInput, ConfigError, ConfigProblem};
+pub use crate::cancel::{CancellationToken, Cancelled};
+pub use crate::cleaning::{CleanRecord, LoadOptions, Record, StateStats};
+pub use crate::heatcontent::HeatContentPolicy;
+pub use crate::impute::ImputeStrategy;
+pub use crate::merge::DuplicatePolicy;
+pub use crate::report::Report;
+pub use crate::sink::{OutputSink, OutputTable};
+pub use crate::stats::{quantile, weighted_mean, weighted_median, weighted_quantile};
+pub use crate::territories::TerritoryPolicy;
+pub use crate::units::HeatRateUnit;
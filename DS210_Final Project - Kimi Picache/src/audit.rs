@@ -0,0 +1,214 @@
+// audit.rs
+// This module appends one JSON line per run to an audit log so results produced months apart can be traced
+// back to the exact inputs and options that made them. Each line carries the schema version it was written
+// with, and a log last written by a newer release is refused rather than mixed with older lines.
+
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::degraded::{DegradedFeature, MissingColumns};
+use crate::hooks::RunSummary;
+use crate::merge::input_parts;
+
+/// Version of the audit line layout. Bump it when a field is renamed, removed or changes meaning; adding an
+/// optional field does not need a bump. Lines without a `schema_version` are version 1; version 2 lines have
+/// `timestamp` in seconds since the Unix epoch.
+pub const AUDIT_SCHEMA_VERSION: u32 = 3;
+
+/// A file read or written by the run, with its SHA-256 digest.
+#[derive(Debug, Serialize)]
+pub struct AuditFile {
+    pub path: String,
+    pub sha256: String,
+
+    /// Valid rows read (inputs only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<usize>,
+
+    /// Blank monthly values filled by `--impute` (inputs only, when any).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imputed: Option<usize>,
+
+    /// Plant-months with implausible heat content (inputs only, when any).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anomalies: Option<usize>,
+
+    /// Optional columns the input lacks (inputs only, when any).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub missing_columns: Option<MissingColumns>,
+}
+
+/// One audit log line.
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    /// Layout version of this line (`AUDIT_SCHEMA_VERSION`).
+    pub schema_version: u32,
+
+    /// When the run started, RFC 3339 in UTC and with the local offset (see `RunTimestamp`).
+    pub timestamp: String,
+    pub local_timestamp: String,
+
+    /// Version of this tool.
+    pub version: &'static str,
+
+    /// Full command line, including every option.
+    pub args: Vec<String>,
+
+    pub command: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    pub states: usize,
+
+    /// Failed energy-unit sanity checks, when any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sanity_warnings: Option<usize>,
+
+    pub inputs: Vec<AuditFile>,
+    pub outputs: Vec<AuditFile>,
+
+    /// Metrics left empty or reports skipped for missing optional columns, when any.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub degraded: Vec<DegradedFeature>,
+}
+
+/// Hex-encoded SHA-256 digest of a file's contents.
+pub fn sha256_file(path: &str) -> Result<String, Box<dyn Error>> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Hex-encoded SHA-256 digest of an input; for a merged input (`a.csv+b.csv`) the digests of its files,
+/// joined with '+' in the same order.
+pub fn sha256_input(spec: &str) -> Result<String, Box<dyn Error>> {
+    let digests = input_parts(spec).into_iter().map(sha256_file).collect::<Result<Vec<String>, _>>()?;
+    Ok(digests.join("+"))
+}
+
+/// Builds the audit entry for a finished run, hashing every input and output file.
+pub fn audit_entry(summary: &RunSummary, args: Vec<String>) -> Result<AuditEntry, Box<dyn Error>> {
+    let mut inputs = Vec::new();
+    for input in &summary.inputs {
+        inputs.push(AuditFile {
+            path: input.path.clone(),
+            sha256: sha256_input(&input.path)?,
+            rows: Some(input.rows),
+            imputed: (input.imputed > 0).then_some(input.imputed),
+            anomalies: (input.anomalies > 0).then_some(input.anomalies),
+            missing_columns: (!input.missing_columns.is_empty()).then(|| input.missing_columns.clone()),
+        });
+    }
+
+    let mut outputs = Vec::new();
+    for path in &summary.outputs {
+        outputs.push(AuditFile {
+            path: path.clone(),
+            sha256: sha256_file(path)?,
+            rows: None,
+            imputed: None,
+            anomalies: None,
+            missing_columns: None,
+        });
+    }
+
+    Ok(AuditEntry {
+        schema_version: AUDIT_SCHEMA_VERSION,
+        timestamp: summary.timestamp.utc.clone(),
+        local_timestamp: summary.timestamp.local.clone(),
+        version: env!("CARGO_PKG_VERSION"),
+        args,
+        command: summary.command.clone(),
+        label: summary.label.clone(),
+        states: summary.states,
+        sanity_warnings: (summary.sanity_warnings > 0).then_some(summary.sanity_warnings),
+        inputs,
+        outputs,
+        degraded: summary.degraded.clone(),
+    })
+}
+
+/// Schema version of the last line of an existing audit log, or `None` when there is no log yet.
+pub fn audit_schema_version(path: &str) -> Result<Option<u32>, Box<dyn Error>> {
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+    parse_audit_schema_version(File::open(path)?, path)
+}
+
+/// Reads the schema version of the last non-blank line of an audit log; see `audit_schema_version`.
+/// # Returns
+/// * `Err` when the last line is not a JSON object
+pub fn parse_audit_schema_version(reader: impl Read, path: &str) -> Result<Option<u32>, Box<dyn Error>> {
+    let mut last = None;
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            last = Some(line);
+        }
+    }
+    let Some(last) = last else {
+        return Ok(None);
+    };
+
+    let entry: serde_json::Value = serde_json::from_str(&last)
+        .map_err(|e| format!("audit log '{}' ends with a line that is not JSON ({}); repair or move it aside", path, e))?;
+    let object = entry.as_object().ok_or_else(|| format!("audit log '{}' ends with a non-object line", path))?;
+    Ok(Some(object.get("schema_version").and_then(|v| v.as_u64()).map_or(1, |v| v as u32)))
+}
+
+/// Checks that entries of this release can be appended to an audit log whose last line has `version`. Logs
+/// from older releases are upgraded in place: lines are self-describing, so new lines simply carry the new
+/// version and earlier lines are left as written.
+/// # Returns
+/// * The log's version when it is older than `AUDIT_SCHEMA_VERSION`, to report the upgrade
+/// * `Err` when the log was last written by a newer release
+pub fn check_audit_schema(path: &str, version: Option<u32>) -> Result<Option<u32>, Box<dyn Error>> {
+    match version {
+        Some(version) if version > AUDIT_SCHEMA_VERSION => Err(format!(
+            "audit log '{}' uses schema version {}, newer than this release's {}; upgrade the tool or move the \
+             log aside before appending",
+            path, version, AUDIT_SCHEMA_VERSION
+        )
+        .into()),
+        Some(version) if version < AUDIT_SCHEMA_VERSION => Ok(Some(version)),
+        _ => Ok(None),
+    }
+}
+
+/// Appends the entry as one JSON line; earlier lines are never rewritten.
+pub fn append_audit_entry(path: &str, entry: &AuditEntry) -> Result<(), Box<dyn Error>> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_schema_guard() {
+        let log = "audit_log.jsonl";
+        assert_eq!(parse_audit_schema_version("".as_bytes(), log).unwrap(), None);
+        let old = "{\"timestamp\":1,\"version\":\"0.1.0\"}\n\n";
+        assert_eq!(parse_audit_schema_version(old.as_bytes(), log).unwrap(), Some(1));
+        let current = format!("{}{{\"schema_version\":{}}}\n", old, AUDIT_SCHEMA_VERSION);
+        assert_eq!(parse_audit_schema_version(current.as_bytes(), log).unwrap(), Some(AUDIT_SCHEMA_VERSION));
+        assert!(parse_audit_schema_version("{\"timestamp\":".as_bytes(), log).is_err());
+
+        assert_eq!(check_audit_schema(log, None).unwrap(), None);
+        assert_eq!(check_audit_schema(log, Some(1)).unwrap(), Some(1));
+        assert_eq!(check_audit_schema(log, Some(AUDIT_SCHEMA_VERSION)).unwrap(), None);
+        assert!(check_audit_schema(log, Some(AUDIT_SCHEMA_VERSION + 1)).is_err());
+    }
+}
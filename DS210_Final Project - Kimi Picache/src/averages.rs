@@ -0,0 +1,188 @@
+// averages.rs
+// This module summarizes the state heat rates across states, as plain (unweighted) and generation-weighted
+// means and medians. The two answer different questions: the unweighted figures describe the typical state,
+// the weighted ones the typical MWh, and they can move in opposite directions.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::analysis::StateEfficiency;
+use crate::cleaning::StateStats;
+use crate::stats::{mean, median, weighted_mean, weighted_median};
+use crate::units::HeatRateUnit;
+
+/// Which cross-state averages to report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum AverageWeighting {
+    /// Unweighted and generation-weighted figures side by side.
+    #[default]
+    Both,
+    /// Every state weighted by its generation.
+    Weighted,
+    /// Every state counts once.
+    Unweighted,
+}
+
+impl AverageWeighting {
+    fn includes(self, weighted: bool) -> bool {
+        match self {
+            AverageWeighting::Both => true,
+            AverageWeighting::Weighted => weighted,
+            AverageWeighting::Unweighted => !weighted,
+        }
+    }
+}
+
+/// One cross-state statistic of the state heat rates for both years and of the state changes.
+#[derive(Debug, Clone, Serialize)]
+pub struct CrossStateAverage {
+    /// "mean" or "median".
+    pub statistic: &'static str,
+
+    /// "unweighted" or "generation-weighted".
+    pub weighting: &'static str,
+
+    /// Statistic of the base-year and compare-year state heat rates (levels weighted by that year's generation).
+    pub base: f64,
+    pub compare: f64,
+
+    /// Compare minus base. With the weighted levels this is not the weighted mean of the state changes, since
+    /// each year's level has its own generation weights; for medians it is not the median change.
+    pub change: f64,
+}
+
+impl CrossStateAverage {
+    /// Row label, e.g. "Generation-weighted median".
+    pub fn label(&self) -> String {
+        let mut label = format!("{} {}", self.weighting, self.statistic);
+        label[..1].make_ascii_uppercase();
+        label
+    }
+}
+
+/// Computes the selected cross-state means and medians.
+/// # Arguments
+/// * `states` - Efficiency rows (in the output unit)
+/// * `stats_a` - Base-year totals per state, for the generation weights
+/// * `stats_b` - Compare-year totals per state
+/// * `weighting` - Which versions to include
+/// # Returns
+/// * `Vec<CrossStateAverage>`, unweighted rows first; empty without states
+pub fn cross_state_averages(
+    states: &[StateEfficiency],
+    stats_a: &HashMap<String, StateStats>,
+    stats_b: &HashMap<String, StateStats>,
+    weighting: AverageWeighting,
+) -> Vec<CrossStateAverage> {
+    let generation = |stats: &HashMap<String, StateStats>| -> Vec<f64> {
+        states.iter().map(|s| stats.get(&s.state).map_or(0.0, |st| st.total_gen.0)).collect()
+    };
+    let (gen_a, gen_b) = (generation(stats_a), generation(stats_b));
+    let base: Vec<f64> = states.iter().map(|s| s.eff_2019).collect();
+    let compare: Vec<f64> = states.iter().map(|s| s.eff_2020).collect();
+
+    let mut averages = Vec::new();
+    if weighting.includes(false) {
+        for (statistic, f) in [("mean", mean as fn(&[f64]) -> Option<f64>), ("median", median)] {
+            if let (Some(base), Some(compare)) = (f(&base), f(&compare)) {
+                let change = compare - base;
+                averages.push(CrossStateAverage { statistic, weighting: "unweighted", base, compare, change });
+            }
+        }
+    }
+    if weighting.includes(true) {
+        let statistics = [("mean", weighted_mean as fn(&[f64], &[f64]) -> Option<f64>), ("median", weighted_median)];
+        for (statistic, f) in statistics {
+            if let (Some(base), Some(compare)) = (f(&base, &gen_a), f(&compare, &gen_b)) {
+                let change = compare - base;
+                averages.push(CrossStateAverage { statistic, weighting: "generation-weighted", base, compare, change });
+            }
+        }
+    }
+    averages
+}
+
+/// Formats the averages as a small aligned table for the terminal.
+pub fn format_averages(data: &[CrossStateAverage], labels: [&str; 2]) -> String {
+    let mut output = format!("{:<28} {:>10} {:>10} {:>10}\n", "Across states", labels[0], labels[1], "Change");
+    for item in data {
+        output += &format!("{:<28} {:>10.3} {:>10.3} {:>+10.3}\n", item.label(), item.base, item.compare, item.change);
+    }
+    output
+}
+
+/// Writes the cross-state averages to a CSV output file.
+pub fn write_state_averages_csv(path: &str, data: &[CrossStateAverage], unit: HeatRateUnit) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Statistic", "Weighting", "Base", "Compare", "Change", "Units"])?;
+
+    for item in data {
+        wtr.write_record([
+            item.statistic,
+            item.weighting,
+            &format!("{:.6}", item.base),
+            &format!("{:.6}", item.compare),
+            &format!("{:.6}", item.change),
+            unit.label(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::{MegawattHours, Mmbtu};
+    use crate::analysis::compute_efficiency_changes;
+
+    fn stats(values: &[(&str, f64, f64)]) -> HashMap<String, StateStats> {
+        values
+            .iter()
+            .map(|(state, fuel, r#gen)| {
+                (state.to_string(), StateStats { total_fuel: Mmbtu(*fuel), total_gen: MegawattHours(*r#gen) })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_and_unweighted_disagree() {
+        // The small state gets much worse, the large one slightly better
+        let stats_a = stats(&[("AA", 100.0, 10.0), ("BB", 9000.0, 1000.0)]);
+        let stats_b = stats(&[("AA", 150.0, 10.0), ("BB", 8800.0, 1000.0)]);
+        let states = compute_efficiency_changes(&stats_a, &stats_b);
+
+        let averages = cross_state_averages(&states, &stats_a, &stats_b, AverageWeighting::Both);
+        assert_eq!(averages.len(), 4);
+        assert_eq!(averages[0].label(), "Unweighted mean");
+        assert!((averages[0].change - 2.4).abs() < 1e-9);
+        assert_eq!(averages[2].label(), "Generation-weighted mean");
+        assert!(averages[2].change < 0.0);
+
+        let weighted = cross_state_averages(&states, &stats_a, &stats_b, AverageWeighting::Weighted);
+        assert!(weighted.iter().all(|a| a.weighting == "generation-weighted"));
+    }
+
+    #[test]
+    fn test_change_is_difference_of_levels() {
+        // BB grows, so the compare level leans more on it than the base level does
+        let stats_a = stats(&[("AA", 1000.0, 100.0), ("BB", 800.0, 100.0)]);
+        let stats_b = stats(&[("AA", 900.0, 100.0), ("BB", 2400.0, 300.0)]);
+        let states = compute_efficiency_changes(&stats_a, &stats_b);
+
+        for average in cross_state_averages(&states, &stats_a, &stats_b, AverageWeighting::Both) {
+            assert!((average.change - (average.compare - average.base)).abs() < 1e-12);
+        }
+        // Weighted levels 9.0 → 8.25; the weighted mean of the changes (-1 x 100 + 0 x 300) / 400 would be -0.25
+        let weighted = &cross_state_averages(&states, &stats_a, &stats_b, AverageWeighting::Weighted)[0];
+        assert!((weighted.change + 0.75).abs() < 1e-12);
+    }
+}
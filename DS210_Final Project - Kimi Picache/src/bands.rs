@@ -0,0 +1,159 @@
+// bands.rs
+// This module classifies fossil plants into heat rate bands (by default <7, 7-9, 9-11 and >=11 MMBtu/MWh) and
+// counts the plants and their share of fossil generation in each band per state and year, a coarser and easier
+// to read view of the fleet than the histograms.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use csv::WriterBuilder;
+
+use crate::cleaning::{aggregate_by_plant, CleanRecord};
+use crate::fuels::FuelGroup;
+use crate::fueltrends::NATIONAL_SCOPE;
+
+/// Default band edges in MMBtu/MWh.
+pub const DEFAULT_BAND_EDGES: [f64; 3] = [7.0, 9.0, 11.0];
+
+/// Plants and generation per heat rate band for one scope and year.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandCounts {
+    /// "US" for the national counts, otherwise the state code.
+    pub scope: String,
+    pub year: String,
+
+    /// Plants per band; band `i` covers `[edges[i-1], edges[i])`, with open-ended first and last bands.
+    pub plants: Vec<usize>,
+
+    /// Net generation (MWh) of the plants in each band.
+    pub generation: Vec<f64>,
+}
+
+impl BandCounts {
+    /// Share (0-1) of the scope's fossil generation in each band.
+    pub fn generation_shares(&self) -> Vec<Option<f64>> {
+        let total: f64 = self.generation.iter().sum();
+        self.generation.iter().map(|g| (total > 0.0).then(|| g / total)).collect()
+    }
+}
+
+/// Band a heat rate falls in (see `BandCounts::plants`).
+pub fn band_of(edges: &[f64], heat_rate: f64) -> usize {
+    edges.partition_point(|edge| *edge <= heat_rate)
+}
+
+/// Label of band `band`, e.g. "<7", "7-9" or ">=11".
+pub fn band_label(edges: &[f64], band: usize) -> String {
+    match (band.checked_sub(1).map(|i| edges[i]), edges.get(band)) {
+        (None, Some(high)) => format!("<{}", high),
+        (Some(low), Some(high)) => format!("{}-{}", low, high),
+        (Some(low), None) => format!(">={}", low),
+        (None, None) => "all".to_string(),
+    }
+}
+
+/// Classifies the fossil plants of one year (all their fossil fuels pooled) into bands, nationally and per state.
+/// Plants without positive fossil generation are skipped.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// * `year` - Label of the year
+/// * `edges` - Band edges in MMBtu/MWh, increasing
+/// # Returns
+/// * `Vec<BandCounts>` with the national counts first, then states alphabetically
+pub fn heat_rate_bands(records: &[CleanRecord], year: &str, edges: &[f64]) -> Vec<BandCounts> {
+    let fossil = records.iter().filter(|r| FuelGroup::from_aer(&r.aer_fuel).is_fossil());
+    let plants = aggregate_by_plant(fossil);
+
+    let mut by_scope: BTreeMap<(bool, String), BandCounts> = BTreeMap::new();
+    for plant in plants.values().filter(|p| p.total_gen > 0.0) {
+        let band = band_of(edges, plant.total_fuel / plant.total_gen);
+        for scope in [NATIONAL_SCOPE, plant.state.as_str()] {
+            let counts = by_scope.entry((scope != NATIONAL_SCOPE, scope.to_string())).or_insert_with(|| BandCounts {
+                scope: scope.to_string(),
+                year: year.to_string(),
+                plants: vec![0; edges.len() + 1],
+                generation: vec![0.0; edges.len() + 1],
+            });
+            counts.plants[band] += 1;
+            counts.generation[band] += plant.total_gen;
+        }
+    }
+
+    by_scope.into_values().collect()
+}
+
+/// Formats the national counts of each year as a table, one row per band.
+pub fn format_national_bands(data: &[BandCounts], edges: &[f64]) -> String {
+    let national: Vec<&BandCounts> = data.iter().filter(|b| b.scope == NATIONAL_SCOPE).collect();
+    let mut out = format!("{:<12}", "Band");
+    for counts in &national {
+        out += &format!(" {:>24}", format!("{} plants (gen share)", counts.year));
+    }
+    out += "\n";
+    for band in 0..=edges.len() {
+        out += &format!("{:<12}", band_label(edges, band));
+        for counts in &national {
+            let share = counts.generation_shares()[band].map_or("-".to_string(), |s| format!("{:.1}%", s * 100.0));
+            out += &format!(" {:>24}", format!("{} ({})", counts.plants[band], share));
+        }
+        out += "\n";
+    }
+    out
+}
+
+/// Writes one row per scope, year and band to a CSV output file; open-ended bands leave the missing edge empty.
+pub fn write_bands_csv(path: &str, data: &[BandCounts], edges: &[f64]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "Scope", "Year", "Band", "Band_Low", "Band_High", "Plants", "Generation_MWh", "Generation_Share",
+    ])?;
+
+    for item in data {
+        for (band, share) in item.generation_shares().into_iter().enumerate() {
+            let low = band.checked_sub(1).map(|i| edges[i].to_string()).unwrap_or_default();
+            let high = edges.get(band).map(|e| e.to_string()).unwrap_or_default();
+            wtr.write_record([
+                &item.scope,
+                &item.year,
+                &band_label(edges, band),
+                &low,
+                &high,
+                &item.plants[band].to_string(),
+                &format!("{:.6}", item.generation[band]),
+                &share.map(|s| format!("{:.6}", s)).unwrap_or_default(),
+            ])?;
+        }
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_heat_rate_bands() {
+        // Heat rates 6.5, 10 (coal and gas pooled) and 12; the nuclear plant is not fossil
+        let records = [
+            record("1", "TX", "NG", 65.0, 10.0),
+            record("2", "TX", "COL", 110.0, 10.0),
+            record("2", "TX", "NG", 90.0, 10.0),
+            record("3", "OK", "COL", 360.0, 30.0),
+            record("4", "OK", "NUC", 500.0, 50.0),
+        ];
+        let bands = heat_rate_bands(&records, "2020", &DEFAULT_BAND_EDGES);
+        assert_eq!(bands.iter().map(|b| b.scope.as_str()).collect::<Vec<_>>(), [NATIONAL_SCOPE, "OK", "TX"]);
+        assert_eq!(bands[0].plants, [1, 0, 1, 1]);
+        let shares = [Some(10.0 / 60.0), Some(0.0), Some(20.0 / 60.0), Some(30.0 / 60.0)];
+        assert_eq!(bands[0].generation_shares(), shares);
+        assert_eq!(bands[2].plants, [1, 0, 1, 0]);
+
+        let labels: Vec<String> = (0..4).map(|b| band_label(&DEFAULT_BAND_EDGES, b)).collect();
+        assert_eq!(labels, ["<7", "7-9", "9-11", ">=11"]);
+        assert_eq!(band_of(&DEFAULT_BAND_EDGES, 9.0), 2);
+    }
+}
@@ -0,0 +1,148 @@
+// baseline.rs
+// This module joins user-supplied reference heat rates (e.g. from a prior study) to the computed state values
+// and reports the differences, for validating results against external sources.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use csv::{ReaderBuilder, WriterBuilder};
+use serde::Serialize;
+
+use crate::analysis::StateEfficiency;
+use crate::monthly::format_optional;
+
+/// Reference heat rates for one state: `[2019, 2020]`, either may be missing.
+pub type BaselineValues = [Option<f64>; 2];
+
+/// Reads a baseline CSV. It needs a `State` column and at least one of `Heat_Rate_2019`,
+/// `Heat_Rate_2020` or `Heat_Rate` (taken as the 2020 value). Header matching ignores case;
+/// blank or non-numeric cells count as missing.
+pub fn load_baseline(path: &str) -> Result<HashMap<String, BaselineValues>, Box<dyn Error>> {
+    let mut rdr = ReaderBuilder::new().comment(Some(b'#')).from_path(path)?;
+    let headers: Vec<String> = rdr.headers()?.iter().map(|h| h.trim().to_ascii_lowercase()).collect();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+
+    let state_col = column("state").ok_or_else(|| format!("baseline '{}' has no State column", path))?;
+    let cols = [column("heat_rate_2019"), column("heat_rate_2020").or_else(|| column("heat_rate"))];
+    if cols.iter().all(Option::is_none) {
+        return Err(format!("baseline '{}' has no Heat_Rate_2019, Heat_Rate_2020 or Heat_Rate column", path).into());
+    }
+
+    let mut baseline = HashMap::new();
+    for row in rdr.records() {
+        let row = row?;
+        let Some(state) = row.get(state_col).map(|s| s.trim().to_ascii_uppercase()) else {
+            continue;
+        };
+        let value = |col: Option<usize>| col.and_then(|c| row.get(c)).and_then(|v| v.trim().parse().ok());
+        baseline.insert(state, [value(cols[0]), value(cols[1])]);
+    }
+
+    Ok(baseline)
+}
+
+/// A state's computed heat rates next to the baseline.
+#[derive(Debug, Serialize)]
+pub struct BaselineDiff {
+    pub state: String,
+    pub eff_2019: f64,
+    pub baseline_2019: Option<f64>,
+    pub eff_2020: f64,
+    pub baseline_2020: Option<f64>,
+}
+
+impl BaselineDiff {
+    /// Computed minus baseline for year index 0 (2019) or 1 (2020).
+    pub fn diff(&self, year: usize) -> Option<f64> {
+        let (value, baseline) = if year == 0 { (self.eff_2019, self.baseline_2019) } else { (self.eff_2020, self.baseline_2020) };
+        baseline.map(|b| value - b)
+    }
+
+    /// Difference as a percentage of the baseline.
+    pub fn pct_diff(&self, year: usize) -> Option<f64> {
+        let baseline = if year == 0 { self.baseline_2019 } else { self.baseline_2020 };
+        self.diff(year).zip(baseline).filter(|(_, b)| *b != 0.0).map(|(d, b)| d / b * 100.0)
+    }
+}
+
+/// Joins the baseline to the efficiency table; states missing from the baseline are left out.
+pub fn compare_to_baseline(changes: &[StateEfficiency], baseline: &HashMap<String, BaselineValues>) -> Vec<BaselineDiff> {
+    changes
+        .iter()
+        .filter_map(|item| {
+            let [baseline_2019, baseline_2020] = *baseline.get(&item.state)?;
+            Some(BaselineDiff {
+                state: item.state.clone(),
+                eff_2019: item.eff_2019,
+                baseline_2019,
+                eff_2020: item.eff_2020,
+                baseline_2020,
+            })
+        })
+        .collect()
+}
+
+/// Writes the baseline comparison to a CSV output file.
+pub fn write_baseline_csv(path: &str, data: &[BaselineDiff]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State", "Efficiency_2019", "Baseline_2019", "Diff_2019", "Pct_Diff_2019",
+        "Efficiency_2020", "Baseline_2020", "Diff_2020", "Pct_Diff_2020",
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            item.state.clone(),
+            format!("{:.6}", item.eff_2019),
+            format_optional(item.baseline_2019),
+            format_optional(item.diff(0)),
+            format_optional(item.pct_diff(0)),
+            format!("{:.6}", item.eff_2020),
+            format_optional(item.baseline_2020),
+            format_optional(item.diff(1)),
+            format_optional(item.pct_diff(1)),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::analysis::compute_efficiency_changes;
+    use crate::cleaning::StateStats;
+    use crate::quantity::{MegawattHours, Mmbtu};
+
+    #[test]
+    fn test_compare_to_baseline() {
+        let path = std::env::temp_dir().join(format!("efficiency_baseline_{}.csv", std::process::id()));
+        std::fs::write(&path, "# prior study\nstate,HEAT_RATE\n aa ,8.0\nBB,n/a\n").unwrap();
+        let baseline = load_baseline(path.to_str().unwrap()).unwrap();
+        assert_eq!(baseline["AA"], [None, Some(8.0)]);
+        assert_eq!(baseline["BB"], [None, None]);
+        std::fs::write(&path, "State,Value\nAA,8.0\n").unwrap();
+        assert!(load_baseline(path.to_str().unwrap()).unwrap_err().to_string().contains("no Heat_Rate_2019"));
+        std::fs::remove_file(&path).unwrap();
+
+        let stats = |states: &[(&str, f64)]| -> HashMap<String, StateStats> {
+            states
+                .iter()
+                .map(|(state, fuel)| {
+                    (state.to_string(), StateStats { total_fuel: Mmbtu(*fuel), total_gen: MegawattHours(100.0) })
+                })
+                .collect()
+        };
+        let changes =
+            compute_efficiency_changes(&stats(&[("AA", 900.0), ("CC", 900.0)]), &stats(&[("AA", 1000.0), ("CC", 0.0)]));
+        let diffs = compare_to_baseline(&changes, &HashMap::from([("AA".to_string(), [Some(0.0), Some(8.0)])]));
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].diff(1), Some(2.0));
+        assert_eq!(diffs[0].pct_diff(1), Some(25.0));
+        // A zero baseline has a difference but no percentage
+        assert_eq!((diffs[0].diff(0), diffs[0].pct_diff(0)), (Some(9.0), None));
+    }
+}
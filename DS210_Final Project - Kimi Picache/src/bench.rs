@@ -0,0 +1,279 @@
+// bench.rs
+// This module times the loading and aggregation stages of a run on one input, so a user can see on their own
+// hardware and data which fast paths pay off: --fast-load (annual columns only) against a full clean, a cache hit
+// against cleaning again, and --parallel (with and without --deterministic) against summing on one thread. Each
+// stage runs a few times and its fastest run is kept, which is the least disturbed by other work on the machine.
+// Throughput is given in rows per second and in megabytes of the input file per second.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cache::{read_entry, save_entry};
+use crate::cancel::{CancellationToken, CHECK_INTERVAL};
+use crate::cleaning::{
+    aggregate_by_plant, aggregate_by_state, CleanRecord, ColumnProjection, LoadOptions, LoadedFile, RowCleaner,
+};
+use crate::heatcontent::check_heat_content;
+use crate::impute::impute_monthly;
+use crate::merge::is_merged;
+use crate::parallel::{aggregate_by_state_parallel, Parallelism};
+use crate::source::{DataSource, SourceRow};
+use crate::territories::apply_territory_policy;
+
+const BYTES_PER_MB: f64 = 1_000_000.0;
+
+/// Timing of one stage.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StageTiming {
+    pub stage: String,
+
+    /// Fast-path option the stage measures, empty for the stages every run goes through.
+    pub option: String,
+
+    pub rows: usize,
+    pub megabytes: f64,
+
+    /// Fastest run, seconds.
+    pub seconds: f64,
+
+    pub rows_per_sec: f64,
+    pub mb_per_sec: f64,
+
+    /// Time of the stage this one replaces over this one's (above 1 when the fast path helps).
+    pub speedup: Option<f64>,
+}
+
+impl StageTiming {
+    fn new(stage: &str, option: &str, rows: usize, bytes: u64, seconds: f64, baseline: Option<f64>) -> StageTiming {
+        let megabytes = bytes as f64 / BYTES_PER_MB;
+        // A stage too fast for the clock still gets a finite rate
+        let elapsed = seconds.max(1e-9);
+        StageTiming {
+            stage: stage.to_string(),
+            option: option.to_string(),
+            rows,
+            megabytes,
+            seconds,
+            rows_per_sec: rows as f64 / elapsed,
+            mb_per_sec: megabytes / elapsed,
+            speedup: baseline.map(|baseline| baseline / elapsed),
+        }
+    }
+}
+
+/// Runs a stage `runs` times on a fresh input from `setup` (not timed) and keeps the fastest run.
+/// # Returns
+/// * The fastest run's seconds and the last run's result
+fn best_of<S, T>(
+    runs: usize,
+    mut setup: impl FnMut() -> S,
+    mut stage: impl FnMut(S) -> Result<T, Box<dyn Error>>,
+) -> Result<(f64, T), Box<dyn Error>> {
+    let mut best = f64::INFINITY;
+    let mut result = None;
+    for _ in 0..runs.max(1) {
+        let input = setup();
+        let start = Instant::now();
+        result = Some(stage(input)?);
+        best = best.min(start.elapsed().as_secs_f64());
+    }
+    Ok((best, result.expect("at least one run")))
+}
+
+/// Rows read from a file, and cleaned given a projection.
+struct ReadRows {
+    rows: usize,
+    records: Vec<CleanRecord>,
+    cleaner: Option<RowCleaner>,
+}
+
+/// Reads the rows of a file and, given a projection, cleans them (without one, no rows are kept).
+fn read_rows(
+    path: &str,
+    columns: Option<ColumnProjection>,
+    cancel: &CancellationToken,
+) -> Result<ReadRows, Box<dyn Error>> {
+    let mut rows = 0;
+    let mut records = Vec::new();
+    let mut cleaner = None;
+    DataSource::new(path).read_rows(|source_row| {
+        match source_row {
+            SourceRow::Headers(headers) => cleaner = columns.map(|columns| RowCleaner::new(headers, columns)),
+            SourceRow::Data(raw) => {
+                if rows % CHECK_INTERVAL == 0 {
+                    cancel.check(&format!("benchmarking '{}'", path), || format!("{} rows read", rows))?;
+                }
+                rows += 1;
+                if let Some(record) = cleaner.as_mut().and_then(|c| c.clean(raw)) {
+                    records.push(record);
+                }
+            }
+        }
+        Ok(())
+    })?;
+    Ok(ReadRows { rows, records, cleaner })
+}
+
+/// Times every stage of loading and aggregating one input.
+/// # Arguments
+/// * `path` - A single raw export (CSV or XLSX)
+/// * `options` - Territory, imputation and heat content policies for the policy stage
+/// * `threads` - Threads of the parallel state totals
+/// * `runs` - Runs per stage; the fastest is kept
+/// * `cache_dir` - Where the cache entry stages write their scratch entry (removed afterwards)
+/// * `cancel` - Stops the benchmark between rows and stages
+/// # Returns
+/// * `Vec<StageTiming>` in pipeline order
+pub fn bench_input(
+    path: &str,
+    options: &LoadOptions,
+    threads: usize,
+    runs: usize,
+    cache_dir: &str,
+    cancel: &CancellationToken,
+) -> Result<Vec<StageTiming>, Box<dyn Error>> {
+    if is_merged(path) {
+        return Err(format!("'{}': bench takes a single file, not a merged input", path).into());
+    }
+    let bytes = fs::metadata(path).map_err(|e| format!("cannot read '{}': {}", path, e))?.len();
+    let mut timings = Vec::new();
+
+    let (seconds, ReadRows { rows, .. }) = best_of(runs, || (), |_| read_rows(path, None, cancel))?;
+    timings.push(StageTiming::new("read rows", "", rows, bytes, seconds, None));
+
+    let all = Some(ColumnProjection::All);
+    let (clean, ReadRows { records, cleaner, .. }) = best_of(runs, || (), |_| read_rows(path, all, cancel))?;
+    timings.push(StageTiming::new("read + clean", "", rows, bytes, clean, None));
+    let annual = Some(ColumnProjection::Annual);
+    let (seconds, _) = best_of(runs, || (), |_| read_rows(path, annual, cancel))?;
+    let stage = "read + clean, annual columns";
+    timings.push(StageTiming::new(stage, "--fast-load", rows, bytes, seconds, Some(clean)));
+
+    let (policies, (records, imputed, anomalies)) = best_of(
+        runs,
+        || records.clone(),
+        |mut records| {
+            apply_territory_policy(&mut records, options.territories);
+            let imputed = impute_monthly(&mut records, options.impute);
+            let anomalies = check_heat_content(&mut records, options.heat_content);
+            Ok((records, imputed, anomalies))
+        },
+    )?;
+    let cleaned = records.len();
+    timings.push(StageTiming::new("loading policies", "", cleaned, bytes, policies, None));
+    cancel.check("benchmarking the cache", || format!("{} stages timed", timings.len()))?;
+
+    let (skipped, years) = cleaner.map(|c| (c.skipped, c.years)).unwrap_or_default();
+    let loaded = LoadedFile { records, imputed, anomalies, skipped, years, missing: Default::default() };
+    fs::create_dir_all(cache_dir)?;
+    let entry = Path::new(cache_dir).join(format!("bench-{}.json", std::process::id()));
+    let cached = best_of(runs, || (), |_| save_entry(&entry, &loaded))
+        .and_then(|(write, _)| Ok((write, best_of(runs, || (), |_| read_entry(&entry))?.0)));
+    let _ = fs::remove_file(&entry);
+    let (write, read) = cached?;
+    timings.push(StageTiming::new("cache entry write", "cache", cleaned, bytes, write, None));
+    timings.push(StageTiming::new("cache entry read", "cache", cleaned, bytes, read, Some(clean + policies)));
+
+    let records = &loaded.records;
+    let (sequential, _) = best_of(runs, || (), |_| Ok(aggregate_by_state(records)))?;
+    timings.push(StageTiming::new("state totals", "", cleaned, bytes, sequential, None));
+    for deterministic in [false, true] {
+        let parallelism = Parallelism { threads, deterministic };
+        let (seconds, _) = best_of(runs, || (), |_| Ok(aggregate_by_state_parallel(records, parallelism)))?;
+        let threads = match threads {
+            1 => "1 thread".to_string(),
+            n => format!("{} threads", n),
+        };
+        let (stage, option) = match deterministic {
+            false => (format!("state totals, {}", threads), "--parallel"),
+            true => (format!("state totals, {}, fixed order", threads), "--parallel --deterministic"),
+        };
+        timings.push(StageTiming::new(&stage, option, cleaned, bytes, seconds, Some(sequential)));
+    }
+    let (seconds, _) = best_of(runs, || (), |_| Ok(aggregate_by_plant(records)))?;
+    timings.push(StageTiming::new("plant totals", "", cleaned, bytes, seconds, None));
+
+    Ok(timings)
+}
+
+/// Formats the timings as a table for the terminal.
+pub fn format_timings(timings: &[StageTiming]) -> String {
+    let mut out = format!(
+        "{:<40} {:<28} {:>10} {:>10} {:>14} {:>10} {:>8}\n",
+        "Stage", "Option", "Rows", "Seconds", "Rows/sec", "MB/sec", "Speedup"
+    );
+    out += &format!("{}\n", "-".repeat(126));
+    for timing in timings {
+        out += &format!(
+            "{:<40} {:<28} {:>10} {:>10.4} {:>14.0} {:>10.1} {:>8}\n",
+            timing.stage,
+            timing.option,
+            timing.rows,
+            timing.seconds,
+            timing.rows_per_sec,
+            timing.mb_per_sec,
+            timing.speedup.map(|s| format!("{:.2}x", s)).unwrap_or_default()
+        );
+    }
+    out
+}
+
+/// Writes the timings to a CSV output file.
+pub fn write_bench_csv(path: &str, timings: &[StageTiming]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Stage", "Option", "Rows", "Megabytes", "Seconds", "Rows_Per_Sec", "MB_Per_Sec", "Speedup"])?;
+
+    for timing in timings {
+        wtr.write_record([
+            &timing.stage,
+            &timing.option,
+            &timing.rows.to_string(),
+            &format!("{:.6}", timing.megabytes),
+            &format!("{:.6}", timing.seconds),
+            &format!("{:.6}", timing.rows_per_sec),
+            &format!("{:.6}", timing.mb_per_sec),
+            &timing.speedup.map(|s| format!("{:.6}", s)).unwrap_or_default(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench_input() {
+        let timing = StageTiming::new("state totals", "--parallel", 2000, 4_000_000, 0.5, Some(1.0));
+        assert_eq!((timing.rows_per_sec, timing.mb_per_sec, timing.speedup), (4000.0, 8.0, Some(2.0)));
+
+        let dir = std::env::temp_dir().join(format!("efficiency_bench_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("2020.csv");
+        let mut csv = "meta\n".repeat(5);
+        csv += "Plant Id,Plant Name,Plant State,\"AER\nFuel Type Code\",\"Total Fuel Consumption\nMMBtu\",";
+        csv += "\"Net Generation\n(Megawatthours)\"\n1,Alpha,TX,NG,1000,100\n2,Beta,TX,COL,500,0\n";
+        fs::write(&input, csv).unwrap();
+
+        let cache = dir.join("cache");
+        let (input, cache) = (input.to_str().unwrap(), cache.to_str().unwrap());
+        let timings = bench_input(input, &LoadOptions::default(), 2, 1, cache, &CancellationToken::new()).unwrap();
+        let stages: Vec<&str> = timings.iter().map(|t| t.stage.as_str()).collect();
+        assert_eq!(stages[..3], ["read rows", "read + clean", "read + clean, annual columns"]);
+        assert_eq!(stages.len(), 10);
+        assert_eq!((timings[0].rows, timings[3].rows), (2, 1));
+        assert!(timings[2].speedup.is_some() && timings[0].speedup.is_none());
+        // The scratch cache entry is removed
+        assert_eq!(fs::read_dir(cache).unwrap().count(), 0);
+        assert!(format_timings(&timings).contains("state totals, 2 threads, fixed order"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,335 @@
+// builder.rs
+// This module configures an `Analysis` step by step (inputs, loading options, aggregation, ordering, grades)
+// for library users, as the counterpart of the command-line options. `build` checks the whole configuration
+// first and reports every problem at once.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::aggregation::{aggregate_states, Aggregation};
+use crate::analysis::{apply_grades, compute_efficiency_changes, sort_changes, Analysis, RankBy};
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::cleaning::{load_records_with_cancel, CleanRecord, EmptyDataset, LoadOptions};
+use crate::parallel::Parallelism;
+
+mod sealed {
+    use crate::cleaning::CleanRecord;
+
+    /// Where an input's rows come from.
+    pub enum Source {
+        Path(String),
+        Records(Vec<CleanRecord>),
+    }
+
+    pub trait Sealed {
+        fn into_source(self) -> Source;
+    }
+}
+
+use sealed::Source;
+
+/// Something an analysis can read a year from: a file path (`&str`, `String`) or rows already cleaned
+/// (`Vec<CleanRecord>`). The trait is sealed so new kinds of input can be added without breaking callers.
+pub trait AnalysisInput: sealed::Sealed {}
+
+impl sealed::Sealed for &str {
+    fn into_source(self) -> Source {
+        Source::Path(self.to_string())
+    }
+}
+impl AnalysisInput for &str {}
+
+impl sealed::Sealed for String {
+    fn into_source(self) -> Source {
+        Source::Path(self)
+    }
+}
+impl AnalysisInput for String {}
+
+impl sealed::Sealed for Vec<CleanRecord> {
+    fn into_source(self) -> Source {
+        Source::Records(self)
+    }
+}
+impl AnalysisInput for Vec<CleanRecord> {}
+
+/// One problem with an analysis configuration.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ConfigProblem {
+    /// The trim share is outside 0-0.5.
+    TrimOutOfRange(f64),
+
+    /// A trim share was set but the aggregation is not `Trimmed`, so it would be ignored.
+    TrimWithoutTrimmedAggregation(Aggregation),
+
+    /// No grade labels were given.
+    NoGradeLabels,
+
+    /// A grade label appears more than once.
+    DuplicateGradeLabel(String),
+
+    /// Loading options were set but both inputs are cleaned rows, which are not loaded again.
+    LoadOptionsWithoutFiles,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigProblem::TrimOutOfRange(trim) => write!(f, "trim {} is outside 0-0.5", trim),
+            ConfigProblem::TrimWithoutTrimmedAggregation(aggregation) => {
+                write!(f, "trim is only used by the trimmed aggregation, not '{}'", aggregation.label())
+            }
+            ConfigProblem::NoGradeLabels => f.write_str("at least one grade label is needed"),
+            ConfigProblem::DuplicateGradeLabel(label) => write!(f, "grade label '{}' is given more than once", label),
+            ConfigProblem::LoadOptionsWithoutFiles => {
+                f.write_str("loading options have no effect when both inputs are cleaned rows")
+            }
+        }
+    }
+}
+
+/// Every problem found in a configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub problems: Vec<ConfigProblem>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid analysis configuration ({} problems)", self.problems.len())?;
+        for problem in &self.problems {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Why an analysis could not be built.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AnalysisError {
+    /// The options do not fit together; nothing was loaded.
+    Config(ConfigError),
+
+    /// An input file could not be read.
+    Load { path: String, message: String },
+
+    /// An input file has no valid data rows (see `LoadOptions::allow_empty`).
+    EmptyDataset(EmptyDataset),
+
+    /// The cancellation token was set before the analysis finished.
+    Cancelled(Cancelled),
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisError::Config(error) => error.fmt(f),
+            AnalysisError::Load { path, message } => write!(f, "cannot load '{}': {}", path, message),
+            AnalysisError::EmptyDataset(empty) => empty.fmt(f),
+            AnalysisError::Cancelled(cancelled) => cancelled.fmt(f),
+        }
+    }
+}
+
+impl Error for AnalysisError {}
+
+/// Step-by-step configuration of an `Analysis`; start with `Analysis::builder`.
+#[must_use]
+pub struct AnalysisBuilder {
+    base: Source,
+    compare: Source,
+    load: Option<LoadOptions>,
+    aggregation: Aggregation,
+    trim: Option<f64>,
+    rank_by: RankBy,
+    grade_labels: Vec<String>,
+    display_rows: usize,
+    cancel: CancellationToken,
+}
+
+impl AnalysisBuilder {
+    /// Starts from the defaults: summed aggregation, ordered by change, quartile grades, 10 rows shown.
+    pub fn new(base: impl AnalysisInput, compare: impl AnalysisInput) -> AnalysisBuilder {
+        AnalysisBuilder {
+            base: base.into_source(),
+            compare: compare.into_source(),
+            load: None,
+            aggregation: Aggregation::Sum,
+            trim: None,
+            rank_by: RankBy::Delta,
+            grade_labels: ["Q1", "Q2", "Q3", "Q4"].iter().map(|s| s.to_string()).collect(),
+            display_rows: 10,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Territory, imputation and heat content options for inputs given as paths.
+    pub fn load_options(mut self, options: LoadOptions) -> Self {
+        self.load = Some(options);
+        self
+    }
+
+    /// How each state's plants are combined.
+    pub fn aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// Share of plants dropped at each end of the heat rate range with `Aggregation::Trimmed` (default 0.05).
+    pub fn trim(mut self, trim: f64) -> Self {
+        self.trim = Some(trim);
+        self
+    }
+
+    /// Order of the results.
+    pub fn rank_by(mut self, rank_by: RankBy) -> Self {
+        self.rank_by = rank_by;
+        self
+    }
+
+    /// Grade labels from best to worst (default quartiles Q1-Q4).
+    pub fn grade_labels(mut self, labels: &[&str]) -> Self {
+        self.grade_labels = labels.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Rows shown by `Display` and `to_html`.
+    pub fn display_rows(mut self, rows: usize) -> Self {
+        self.display_rows = rows;
+        self
+    }
+
+    /// Token that stops `build` while it loads or aggregates; keep a clone to cancel from another thread.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Checks that the options fit together, without loading anything.
+    /// # Returns
+    /// * `ConfigError` listing every problem found
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if let Some(trim) = self.trim {
+            if !(0.0..=0.5).contains(&trim) {
+                problems.push(ConfigProblem::TrimOutOfRange(trim));
+            }
+            if self.aggregation != Aggregation::Trimmed {
+                problems.push(ConfigProblem::TrimWithoutTrimmedAggregation(self.aggregation));
+            }
+        }
+
+        if self.grade_labels.is_empty() {
+            problems.push(ConfigProblem::NoGradeLabels);
+        }
+        for (i, label) in self.grade_labels.iter().enumerate() {
+            // Reported at its second occurrence only
+            if self.grade_labels[..i].iter().filter(|l| *l == label).count() == 1 {
+                problems.push(ConfigProblem::DuplicateGradeLabel(label.clone()));
+            }
+        }
+
+        let from_files = |source: &Source| matches!(source, Source::Path(_));
+        if self.load.is_some() && !from_files(&self.base) && !from_files(&self.compare) {
+            problems.push(ConfigProblem::LoadOptionsWithoutFiles);
+        }
+
+        if problems.is_empty() { Ok(()) } else { Err(ConfigError { problems }) }
+    }
+
+    /// Validates the configuration, loads both inputs and computes the efficiency changes.
+    /// # Returns
+    /// * `Analysis`, or `AnalysisError` for invalid options (all of them), an unreadable input or a cancelled run
+    pub fn build(self) -> Result<Analysis, AnalysisError> {
+        self.validate().map_err(AnalysisError::Config)?;
+
+        let load = self.load.unwrap_or_default();
+        let trim = self.trim.unwrap_or(0.05);
+        let cancel = &self.cancel;
+        let base = records(self.base, &load, cancel)?;
+        let compare = records(self.compare, &load, cancel)?;
+        let check = |stage: &str, done: usize| {
+            cancel.check(stage, || format!("{} of 2 years aggregated", done)).map_err(AnalysisError::Cancelled)
+        };
+        check("aggregating the base year", 0)?;
+        let stats_base = aggregate_states(&base, self.aggregation, trim, Parallelism::default());
+        check("aggregating the comparison year", 1)?;
+        let stats_compare = aggregate_states(&compare, self.aggregation, trim, Parallelism::default());
+
+        let mut changes = compute_efficiency_changes(&stats_base, &stats_compare);
+        apply_grades(&mut changes, &self.grade_labels);
+        sort_changes(&mut changes, self.rank_by);
+
+        Ok(Analysis { stats_base, stats_compare, changes, display_rows: self.display_rows })
+    }
+}
+
+fn records(
+    source: Source,
+    options: &LoadOptions,
+    cancel: &CancellationToken,
+) -> Result<Vec<CleanRecord>, AnalysisError> {
+    match source {
+        Source::Records(records) => Ok(records),
+        Source::Path(path) => load_records_with_cancel(&path, options, cancel)
+            .map(|loaded| loaded.records)
+            .map_err(|e| match e.downcast::<Cancelled>() {
+                Ok(cancelled) => AnalysisError::Cancelled(*cancelled),
+                Err(e) => match e.downcast::<EmptyDataset>() {
+                    Ok(empty) => AnalysisError::EmptyDataset(*empty),
+                    Err(e) => AnalysisError::Load { path, message: e.to_string() },
+                },
+            }),
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_builder_from_records() {
+        let base = vec![record("1", "AA", "NG", 10.0, 1.0), record("1", "BB", "NG", 10.0, 1.0)];
+        let compare = vec![record("1", "AA", "NG", 9.0, 1.0), record("1", "BB", "NG", 12.0, 1.0)];
+        let analysis = Analysis::builder(base, compare).grade_labels(&["good", "bad"]).build().unwrap();
+        assert_eq!(analysis.changes[0].state, "BB");
+        assert_eq!(analysis.state("AA").unwrap().delta, -1.0);
+    }
+
+    #[test]
+    fn test_builder_reports_every_problem() {
+        let base = vec![record("1", "AA", "NG", 10.0, 1.0)];
+        let builder = Analysis::builder(base.clone(), base)
+            .trim(0.7)
+            .grade_labels(&["A", "B", "A"])
+            .load_options(LoadOptions::default());
+        let problems = builder.validate().unwrap_err().problems;
+        assert_eq!(
+            problems,
+            [
+                ConfigProblem::TrimOutOfRange(0.7),
+                ConfigProblem::TrimWithoutTrimmedAggregation(Aggregation::Sum),
+                ConfigProblem::DuplicateGradeLabel("A".to_string()),
+                ConfigProblem::LoadOptionsWithoutFiles,
+            ]
+        );
+        assert!(matches!(builder.build(), Err(AnalysisError::Config(e)) if e.problems.len() == 4));
+        let missing = Analysis::builder("missing.csv", "missing.csv").build().unwrap_err();
+        assert!(missing.to_string().starts_with("cannot load 'missing.csv'"));
+    }
+
+    #[test]
+    fn test_builder_cancelled() {
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let cancelled = Analysis::builder(vec![record("1", "AA", "NG", 1.0, 1.0)], vec![]).cancellation(cancel).build();
+        assert!(matches!(cancelled, Err(AnalysisError::Cancelled(c)) if c.progress == "0 of 2 years aggregated"));
+    }
+}
@@ -0,0 +1,126 @@
+// cache.rs
+// This module keeps cleaned inputs in a content-addressed directory. Each entry is keyed by the SHA-256 of the
+// input file and a hash of the loading options (and cleaning schema version), so any run with the same file
+// contents and options reuses the cleaning work, whatever the file is called or wherever it lives. Entries are
+// written under a lock file and renamed into place, so parallel runs sharing the directory wait for each other
+// instead of cleaning (or downloading) the same file twice or reading a half-written entry.
+
+use std::error::Error;
+use std::fs::{self, File, TryLockError};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::audit::sha256_input;
+use crate::cancel::CancellationToken;
+use crate::cleaning::{load_records_with_cancel, LoadOptions, LoadedFile, CLEANING_SCHEMA_VERSION};
+use crate::merge::is_merged;
+
+/// Cache directory used unless `--cache-dir` names another one.
+pub const DEFAULT_CACHE_DIR: &str = ".efficiency-cache";
+
+/// Cache key for an input: `<input sha256>-<options hash>`. The options hash covers `CLEANING_SCHEMA_VERSION`,
+/// so entries written by an older cleaning step are not reused, while releases that leave the cleaning alone
+/// keep the cache.
+pub fn cache_key(input_sha256: &str, options: &LoadOptions) -> Result<String, Box<dyn Error>> {
+    let mut hasher = Sha256::new();
+    hasher.update(CLEANING_SCHEMA_VERSION.to_le_bytes());
+    hasher.update(serde_json::to_vec(options)?);
+    let options_hash: String = hasher.finalize().iter().take(8).map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("{}-{}", input_sha256, options_hash))
+}
+
+/// Takes an exclusive lock on `<path>.lock` (created if needed), waiting while another run holds it. The lock
+/// is released when the returned file is dropped.
+pub fn lock_entry(path: &Path) -> Result<File, Box<dyn Error>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let lock = File::options().create(true).truncate(false).write(true).open(path.with_extension("lock"))?;
+
+    match lock.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            println!("Waiting for another run using '{}'...", path.display());
+            lock.lock()?;
+        }
+        Err(TryLockError::Error(e)) => return Err(e.into()),
+    }
+    Ok(lock)
+}
+
+fn entry_path(dir: &str, key: &str) -> PathBuf {
+    Path::new(dir).join(format!("{}.json", key))
+}
+
+/// Loads a file with `load_records`, reusing the cached result when the same contents were cleaned with the
+/// same options before.
+/// # Arguments
+/// * `file_path` - EIA-923 CSV to load
+/// * `options` - Territory, imputation and heat content options
+/// * `cache_dir` - Cache directory; newly cleaned files are stored there
+/// * `reuse` - Whether cached entries are read (false with `--no-cache`)
+/// * `cancel` - Stops the cleaning early; nothing is cached for a cancelled load
+/// # Returns
+/// * The cleaned rows with the imputed values and heat content anomalies
+pub fn load_records_cached(
+    file_path: &str,
+    options: &LoadOptions,
+    cache_dir: &str,
+    reuse: bool,
+    cancel: &CancellationToken,
+) -> Result<LoadedFile, Box<dyn Error>> {
+    // A merged input's joined digests are hashed again, so the entry name stays short however many files
+    let mut digest = sha256_input(file_path)?;
+    if is_merged(file_path) {
+        digest = Sha256::digest(digest.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect();
+    }
+    let key = cache_key(&digest, options)?;
+    let path = entry_path(cache_dir, &key);
+    let _lock = lock_entry(&path)?;
+    if reuse && let Ok(loaded) = read_entry(&path) {
+        println!("Reusing cleaned records from cache ({})", path.display());
+        return Ok(loaded);
+    }
+
+    let loaded = load_records_with_cancel(file_path, options, cancel)?;
+    save_entry(&path, &loaded)?;
+    Ok(loaded)
+}
+
+/// Reads a cache entry.
+pub(crate) fn read_entry(path: &Path) -> Result<LoadedFile, Box<dyn Error>> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+/// Writes an entry under a temporary name and renames it, so a reader never sees a partial entry.
+pub(crate) fn save_entry(path: &Path, loaded: &LoadedFile) -> Result<(), Box<dyn Error>> {
+    let tmp_path = path.with_extension("json.tmp");
+
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    serde_json::to_writer(&mut writer, loaded)?;
+    writer.into_inner()?.sync_all()?;
+
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::impute::ImputeStrategy;
+
+    #[test]
+    fn test_key_follows_contents_and_options() {
+        let options = LoadOptions::default();
+        let imputed = LoadOptions { impute: ImputeStrategy::Proportional, ..options };
+
+        let key = cache_key("abc", &options).unwrap();
+        assert!(key.starts_with("abc-") && key.len() == 4 + 16);
+        assert_eq!(key, cache_key("abc", &options).unwrap());
+        assert_ne!(key, cache_key("abd", &options).unwrap());
+        assert_ne!(key, cache_key("abc", &imputed).unwrap());
+    }
+}
@@ -0,0 +1,188 @@
+// capacityweight.rs
+// This module weights plant heat rates by EIA-860 nameplate capacity instead of generation. The usual state heat
+// rate (fuel / generation summed over plants) weights each plant by its output, so it describes the energy that
+// was produced; weighting by capacity describes the fleet that stands ready, including peakers and reserve units
+// that ran little, which is the view of reserve-margin analyses. Both rates are taken over the same plants (fossil
+// plants with positive generation and generators in service), so their difference is down to the weighting alone.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{aggregate_by_state_plant, CleanRecord, INCREMENT_PLANT_ID};
+use crate::eia860::Generator;
+use crate::fuels::FuelGroup;
+use crate::join::{JoinKey, JoinReport, JoinType, KeyIndex};
+use crate::monthly::format_optional;
+
+/// A state's fossil heat rate in one year, weighted by capacity and by generation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CapacityWeightedRate {
+    pub state: String,
+    pub year: i32,
+
+    /// Fossil plants with positive generation, and those with EIA-860 generators in service by `year`.
+    pub plants: usize,
+    pub matched_plants: usize,
+
+    /// Nameplate capacity of the matched plants (MW).
+    pub capacity_mw: f64,
+
+    /// Capacity-weighted mean of the matched plants' heat rates (MMBtu/MWh), or `None` without matches.
+    pub capacity_weighted: Option<f64>,
+
+    /// Fuel / generation summed over the same plants (MMBtu/MWh).
+    pub generation_weighted: Option<f64>,
+}
+
+impl CapacityWeightedRate {
+    /// Capacity-weighted minus generation-weighted heat rate; positive when the little-used capacity is the less
+    /// efficient part of the fleet.
+    pub fn difference(&self) -> Option<f64> {
+        Some(self.capacity_weighted? - self.generation_weighted?)
+    }
+}
+
+/// Computes each state's capacity-weighted fossil heat rate in `year`, each plant weighted by the nameplate
+/// capacity of its generators in service by then (those without an operating year count as in service).
+/// # Arguments
+/// * `records` - Cleaned rows for the year
+/// * `year` - Year of the rows
+/// * `generators` - EIA-860 generators
+/// * `join_type` - Whether plants without any generator still count towards `plants` (left) or not (inner)
+/// # Returns
+/// * `Vec<CapacityWeightedRate>` sorted by state, and the report of the plant join
+pub fn capacity_weighted_heat_rates(
+    records: &[CleanRecord],
+    year: i32,
+    generators: &[Generator],
+    join_type: JoinType,
+) -> (Vec<CapacityWeightedRate>, JoinReport) {
+    let by_plant = KeyIndex::new(JoinKey::PlantId, generators, |g| &g.plant_id);
+    let fossil: Vec<CleanRecord> = records
+        .iter()
+        .filter(|r| r.plant_id != INCREMENT_PLANT_ID && FuelGroup::from_aer(&r.aer_fuel).is_fossil())
+        .cloned()
+        .collect();
+    let plants: HashMap<(String, String), _> =
+        aggregate_by_state_plant(&fossil).into_iter().filter(|(_, plant)| plant.total_gen > 0.0).collect();
+    let report = by_plant.report(
+        &format!("EIA-860 capacities ({})", year),
+        plants.keys().map(|(_, plant_id)| plant_id.as_str()),
+        join_type,
+    );
+
+    // (plants, matched, MW, MW x heat rate, fuel, generation) per state
+    let mut sums: BTreeMap<&str, (usize, usize, f64, f64, f64, f64)> = BTreeMap::new();
+    for ((state, plant_id), plant) in plants.iter().filter(|((_, id), _)| report.keeps(id)) {
+        let entry = sums.entry(state).or_default();
+        entry.0 += 1;
+        let capacity: f64 = by_plant
+            .get(plant_id)
+            .iter()
+            .filter(|g| g.operating_year.is_none_or(|y| y <= year))
+            .map(|g| g.capacity_mw)
+            .sum();
+        if capacity > 0.0 {
+            entry.1 += 1;
+            entry.2 += capacity;
+            entry.3 += capacity * (plant.total_fuel / plant.total_gen);
+            entry.4 += plant.total_fuel;
+            entry.5 += plant.total_gen;
+        }
+    }
+
+    let rates = sums
+        .into_iter()
+        .map(|(state, (plants, matched, capacity, weighted, fuel, r#gen))| CapacityWeightedRate {
+            state: state.to_string(),
+            year,
+            plants,
+            matched_plants: matched,
+            capacity_mw: capacity,
+            capacity_weighted: (capacity > 0.0).then(|| weighted / capacity),
+            generation_weighted: (r#gen > 0.0).then(|| fuel / r#gen),
+        })
+        .collect();
+    (rates, report)
+}
+
+/// Mean difference between the capacity- and generation-weighted rates across the states with both, and the
+/// state with the largest difference.
+pub fn weighting_gap(rates: &[CapacityWeightedRate]) -> Option<(f64, &CapacityWeightedRate)> {
+    let differences: Vec<(f64, &CapacityWeightedRate)> =
+        rates.iter().filter_map(|rate| Some((rate.difference()?, rate))).collect();
+    let largest = differences.iter().max_by(|a, b| a.0.abs().total_cmp(&b.0.abs()))?.1;
+    let mean = differences.iter().map(|(difference, _)| difference).sum::<f64>() / differences.len() as f64;
+    Some((mean, largest))
+}
+
+/// Writes the capacity-weighted heat rates to a CSV output file.
+pub fn write_capacity_weighted_csv(path: &str, data: &[CapacityWeightedRate]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State", "Year", "Plants", "Matched_Plants", "Capacity_MW", "Capacity_Weighted_Heat_Rate",
+        "Generation_Weighted_Heat_Rate", "Difference",
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            &item.year.to_string(),
+            &item.plants.to_string(),
+            &item.matched_plants.to_string(),
+            &format!("{:.6}", item.capacity_mw),
+            &format_optional(item.capacity_weighted),
+            &format_optional(item.generation_weighted),
+            &format_optional(item.difference()),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    fn generator(plant_id: &str, capacity_mw: f64, operating_year: Option<i32>) -> Generator {
+        Generator {
+            plant_id: plant_id.to_string(),
+            utility_id: "1".to_string(),
+            generator_id: "1".to_string(),
+            capacity_mw,
+            operating_year,
+        }
+    }
+
+    #[test]
+    fn test_capacity_weighted_heat_rates() {
+        // A busy efficient plant and a large peaker that ran little; wind and plant 4 (no generators) are left out
+        let records = [
+            record("1", "AA", "NG", 700.0, 100.0),
+            record("2", "AA", "NG", 120.0, 10.0),
+            record("3", "AA", "WND", 0.0, 50.0),
+            record("4", "AA", "COL", 100.0, 10.0),
+        ];
+        let generators = [
+            generator("1", 100.0, Some(2005)),
+            generator("2", 300.0, None),
+            generator("2", 500.0, Some(2021)),
+            generator("3", 50.0, Some(2015)),
+        ];
+
+        let (rates, report) = capacity_weighted_heat_rates(&records, 2020, &generators, JoinType::Left);
+        let aa = &rates[0];
+        assert_eq!((aa.plants, aa.matched_plants, aa.capacity_mw), (3, 2, 400.0));
+        assert_eq!(aa.capacity_weighted, Some((100.0 * 7.0 + 300.0 * 12.0) / 400.0));
+        assert_eq!(aa.generation_weighted, Some(820.0 / 110.0));
+        assert!(aa.difference().unwrap() > 0.0);
+        assert_eq!(weighting_gap(&rates).unwrap().1.state, "AA");
+        assert_eq!(report.unmatched_right.len(), 1);
+    }
+}
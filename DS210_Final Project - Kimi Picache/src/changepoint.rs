@@ -0,0 +1,223 @@
+// changepoint.rs
+// This module looks for structural breaks in each state's multi-year heat rate series using binary segmentation,
+// and tells one-time shifts (e.g. plant retirements) apart from gradual trends.
+
+use std::error::Error;
+
+use csv::WriterBuilder;
+
+use crate::panel::YearData;
+use crate::stats::linear_regression;
+
+/// Series shorter than this are not tested; there is not enough data to tell a shift from noise.
+const MIN_YEARS: usize = 4;
+
+/// Floor on the per-point residual variance so perfect fits don't produce infinite scores.
+const MIN_VARIANCE: f64 = 1e-12;
+
+/// Shape of a state's heat rate series.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeriesPattern {
+    /// Fewer than `MIN_YEARS` years of data.
+    Insufficient,
+    /// No break or trend beats a constant level.
+    Stable,
+    /// One or more abrupt level shifts.
+    Shift,
+    /// A gradual linear trend.
+    Trend,
+}
+
+impl SeriesPattern {
+    pub fn label(self) -> &'static str {
+        match self {
+            SeriesPattern::Insufficient => "insufficient data",
+            SeriesPattern::Stable => "stable",
+            SeriesPattern::Shift => "shift",
+            SeriesPattern::Trend => "trend",
+        }
+    }
+}
+
+/// Change-point result for one state.
+#[derive(Debug)]
+pub struct ChangePoints {
+    pub state: String,
+    pub pattern: SeriesPattern,
+
+    /// First year of each new regime detected.
+    pub breakpoints: Vec<i32>,
+
+    /// Linear trend slope (MMBtu/MWh per year), reported for every tested series.
+    pub trend_slope: Option<f64>,
+}
+
+/// Sum of squared deviations from the mean.
+fn sse_around_mean(xs: &[f64]) -> f64 {
+    if xs.is_empty() {
+        return 0.0;
+    }
+    let mean = xs.iter().sum::<f64>() / xs.len() as f64;
+    xs.iter().map(|x| (x - mean) * (x - mean)).sum()
+}
+
+/// Bayesian information criterion for a Gaussian model with `k` parameters.
+fn bic(sse: f64, n: usize, k: usize) -> f64 {
+    let n_f = n as f64;
+    n_f * (sse / n_f).max(MIN_VARIANCE).ln() + k as f64 * n_f.ln()
+}
+
+/// Best single split of a segment into two mean levels: (index of first point after the break, total SSE).
+fn best_split(xs: &[f64]) -> Option<(usize, f64)> {
+    (1..xs.len())
+        .map(|i| (i, sse_around_mean(&xs[..i]) + sse_around_mean(&xs[i..])))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+}
+
+/// Recursively splits `xs` while a two-level model beats a one-level model by BIC.
+/// # Arguments
+/// * `xs` - Series values
+/// * `offset` - Index of `xs[0]` in the full series
+/// * `breaks` - Collected break indices (first index of each new segment)
+fn segment(xs: &[f64], offset: usize, breaks: &mut Vec<usize>) {
+    if xs.len() < 2 {
+        return;
+    }
+
+    let Some((split, split_sse)) = best_split(xs) else {
+        return;
+    };
+
+    // One level: mean (1 parameter). Two levels: two means + break location (3 parameters).
+    if bic(split_sse, xs.len(), 3) < bic(sse_around_mean(xs), xs.len(), 1) {
+        breaks.push(offset + split);
+        segment(&xs[..split], offset, breaks);
+        segment(&xs[split..], offset + split, breaks);
+    }
+}
+
+/// Runs binary segmentation over a series, returning the sorted break indices.
+pub fn binary_segmentation(xs: &[f64]) -> Vec<usize> {
+    let mut breaks = Vec::new();
+    segment(xs, 0, &mut breaks);
+    breaks.sort();
+    breaks
+}
+
+/// Classifies one series as stable, shifted or trending by comparing model BICs.
+/// # Arguments
+/// * `years` - Year of each observation, ascending
+/// * `values` - Heat rate for each year
+/// # Returns
+/// * `(SeriesPattern, break indices, trend slope)`
+pub fn classify_series(years: &[f64], values: &[f64]) -> (SeriesPattern, Vec<usize>, Option<f64>) {
+    let n = values.len();
+    if n < MIN_YEARS {
+        return (SeriesPattern::Insufficient, Vec::new(), None);
+    }
+
+    let sst = sse_around_mean(values);
+    let fit = linear_regression(years, values);
+    let trend_sse = fit.map(|f| (1.0 - f.r_squared) * sst).unwrap_or(sst);
+    let step_sse = best_split(values).map(|(_, sse)| sse).unwrap_or(sst);
+
+    let bic_stable = bic(sst, n, 1);
+    let bic_trend = bic(trend_sse, n, 2);
+    let bic_shift = bic(step_sse, n, 3);
+    let slope = fit.map(|f| f.slope);
+
+    if bic_shift < bic_trend && bic_shift < bic_stable {
+        (SeriesPattern::Shift, binary_segmentation(values), slope)
+    } else if bic_trend < bic_stable {
+        (SeriesPattern::Trend, Vec::new(), slope)
+    } else {
+        (SeriesPattern::Stable, Vec::new(), slope)
+    }
+}
+
+/// Runs change-point detection on every state's annual heat rate series.
+/// # Arguments
+/// * `panel` - Loaded years, ascending
+/// # Returns
+/// * `Vec<ChangePoints>` sorted by state
+pub fn detect_change_points(panel: &[YearData]) -> Vec<ChangePoints> {
+    let mut states: Vec<&String> = panel.iter().flat_map(|y| y.stats.keys()).collect();
+    states.sort();
+    states.dedup();
+
+    let mut output = Vec::new();
+    for state in states {
+        let mut years = Vec::new();
+        let mut values = Vec::new();
+        for year_data in panel {
+            if let Some(stats) = year_data.stats.get(state)
+                && stats.total_gen.0 != 0.0
+            {
+                years.push(year_data.year);
+                values.push((stats.total_fuel / stats.total_gen).0);
+            }
+        }
+
+        let year_values: Vec<f64> = years.iter().map(|&y| y as f64).collect();
+        let (pattern, breaks, trend_slope) = classify_series(&year_values, &values);
+
+        output.push(ChangePoints {
+            state: state.clone(),
+            pattern,
+            breakpoints: breaks.into_iter().map(|i| years[i]).collect(),
+            trend_slope,
+        });
+    }
+
+    output
+}
+
+/// Writes the change-point results to a CSV output file.
+pub fn write_change_points_csv(path: &str, data: &[ChangePoints]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["State", "Pattern", "Breakpoint_Years", "Trend_Slope"])?;
+
+    for item in data {
+        let breakpoints: Vec<String> = item.breakpoints.iter().map(|y| y.to_string()).collect();
+        wtr.write_record([
+            &item.state,
+            item.pattern.label(),
+            &breakpoints.join(";"),
+            &item.trend_slope.map(|s| format!("{:.6}", s)).unwrap_or_default(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YEARS: [f64; 8] = [2013.0, 2014.0, 2015.0, 2016.0, 2017.0, 2018.0, 2019.0, 2020.0];
+
+    #[test]
+    fn test_step_series_is_a_shift() {
+        let values = [10.5, 10.4, 10.6, 10.5, 9.1, 9.0, 9.2, 9.1];
+        let (pattern, breaks, _) = classify_series(&YEARS, &values);
+        assert_eq!(pattern, SeriesPattern::Shift);
+        assert_eq!(breaks, [4]);
+    }
+
+    #[test]
+    fn test_linear_series_is_a_trend() {
+        let values = [10.7, 10.5, 10.3, 10.1, 9.9, 9.7, 9.5, 9.3];
+        let (pattern, breaks, slope) = classify_series(&YEARS, &values);
+        assert_eq!(pattern, SeriesPattern::Trend);
+        assert!(breaks.is_empty());
+        assert!((slope.unwrap() + 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_short_series_is_not_tested() {
+        let (pattern, _, _) = classify_series(&YEARS[..2], &[10.0, 9.0]);
+        assert_eq!(pattern, SeriesPattern::Insufficient);
+    }
+}
@@ -0,0 +1,146 @@
+// changesummary.rs
+// This module condenses the state changes into the handful of figures printed under the top movers table: how
+// many states improved or worsened, the national change (all reported states pooled, so weighted by
+// generation), the spread of the state changes and the most extreme states.
+
+use std::collections::HashMap;
+
+use crate::analysis::StateEfficiency;
+use crate::cleaning::StateStats;
+use crate::quantity::{HeatRate, MegawattHours, Mmbtu};
+use crate::stats::{mean, median, std_dev};
+use crate::units::HeatRateUnit;
+
+/// Summary figures of the state changes, in the unit of the efficiency rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeSummary {
+    pub states: usize,
+
+    /// States whose heat rate fell, rose or stayed the same.
+    pub improved: usize,
+    pub worsened: usize,
+    pub unchanged: usize,
+
+    /// Heat rate of the reported states pooled (total fuel over total generation) in both years.
+    pub national: Option<[f64; 2]>,
+
+    /// Sum of the states' `weighted_delta`: fuel (MMBtu) burned beyond base-year efficiency.
+    pub extra_fuel: f64,
+
+    /// Unweighted mean, median and sample standard deviation of the state changes.
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    pub std_dev: Option<f64>,
+
+    /// State with the largest fall and the largest rise of its heat rate, with the change.
+    pub largest_improvement: Option<(String, f64)>,
+    pub largest_deterioration: Option<(String, f64)>,
+}
+
+/// Summarizes the state changes.
+/// # Arguments
+/// * `states` - Efficiency rows (in the output unit)
+/// * `stats` - Base- and compare-year totals per state, for the national heat rate
+/// * `unit` - Unit of the rows
+pub fn summarize_changes(
+    states: &[StateEfficiency],
+    stats: [&HashMap<String, StateStats>; 2],
+    unit: HeatRateUnit,
+) -> ChangeSummary {
+    let deltas: Vec<f64> = states.iter().map(|s| s.delta).collect();
+    let national = stats.map(|year| {
+        let (fuel, generation) = states
+            .iter()
+            .filter_map(|s| year.get(&s.state))
+            .fold((Mmbtu(0.0), MegawattHours(0.0)), |(fuel, generation), st| {
+                (fuel + st.total_fuel, generation + st.total_gen)
+            });
+        HeatRate::of(fuel, generation).map(|rate| unit.convert(rate.0))
+    });
+    let extreme = |pick: fn(f64, f64) -> bool| {
+        states
+            .iter()
+            .filter(|s| pick(s.delta, 0.0))
+            .fold(None::<&StateEfficiency>, |best, s| match best {
+                Some(b) if !pick(s.delta, b.delta) => Some(b),
+                _ => Some(s),
+            })
+            .map(|s| (s.state.clone(), s.delta))
+    };
+
+    ChangeSummary {
+        states: states.len(),
+        improved: deltas.iter().filter(|&&d| d < 0.0).count(),
+        worsened: deltas.iter().filter(|&&d| d > 0.0).count(),
+        unchanged: deltas.iter().filter(|&&d| d == 0.0).count(),
+        national: national[0].zip(national[1]).map(|(a, b)| [a, b]),
+        extra_fuel: states.iter().map(|s| s.weighted_delta).sum(),
+        mean: mean(&deltas),
+        median: median(&deltas),
+        std_dev: std_dev(&deltas),
+        largest_improvement: extreme(|a, b| a < b),
+        largest_deterioration: extreme(|a, b| a > b),
+    }
+}
+
+/// Formats the summary as an aligned block for the terminal.
+pub fn format_change_summary(summary: &ChangeSummary, labels: [&str; 2], unit: HeatRateUnit) -> String {
+    let value = |v: Option<f64>| v.map_or("n/a".to_string(), |v| format!("{:+.3}", v));
+    let state = |v: &Option<(String, f64)>| v.as_ref().map_or("n/a".to_string(), |(s, d)| format!("{} ({:+.3})", s, d));
+
+    let mut output = format!("Summary of {} states ({}):\n", summary.states, unit.label());
+    output += &format!("  {:<26} {}\n", "Improved (heat rate fell)", summary.improved);
+    output += &format!("  {:<26} {}\n", "Worsened (heat rate rose)", summary.worsened);
+    if summary.unchanged > 0 {
+        output += &format!("  {:<26} {}\n", "Unchanged", summary.unchanged);
+    }
+    if let Some([a, b]) = summary.national {
+        let [base, compare] = labels;
+        let line = format!("{:.3} ({}) → {:.3} ({}), {:+.3}", a, base, b, compare, b - a);
+        output += &format!("  {:<26} {}\n", "National heat rate", line);
+    }
+    output += &format!("  {:<26} {:+.0} MMBtu\n", "Fuel vs base efficiency", summary.extra_fuel);
+    output += &format!(
+        "  {:<26} mean {}, median {}, std dev {}\n",
+        "State changes",
+        value(summary.mean),
+        value(summary.median),
+        summary.std_dev.map_or("n/a".to_string(), |v| format!("{:.3}", v))
+    );
+    output += &format!("  {:<26} {}\n", "Largest improvement", state(&summary.largest_improvement));
+    output += &format!("  {:<26} {}\n", "Largest deterioration", state(&summary.largest_deterioration));
+    output
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::compute_efficiency_changes;
+
+    #[test]
+    fn test_summarize_changes() {
+        let stats = |rates: &[(&str, f64)]| -> HashMap<String, StateStats> {
+            let totals = |hr: f64| StateStats { total_fuel: Mmbtu(hr * 100.0), total_gen: MegawattHours(100.0) };
+            rates.iter().map(|(s, hr)| (s.to_string(), totals(*hr))).collect()
+        };
+        let base = stats(&[("AA", 10.0), ("BB", 10.0), ("CC", 10.0)]);
+        let compare = stats(&[("AA", 9.0), ("BB", 10.5), ("CC", 12.0)]);
+        let changes = compute_efficiency_changes(&base, &compare);
+        let summary = summarize_changes(&changes, [&base, &compare], HeatRateUnit::MmbtuPerMwh);
+
+        assert_eq!((summary.states, summary.improved, summary.worsened, summary.unchanged), (3, 1, 2, 0));
+        let [a, b] = summary.national.unwrap();
+        assert!((a - 10.0).abs() < 1e-9 && (b - 10.5).abs() < 1e-9);
+        assert!((summary.extra_fuel - 150.0).abs() < 1e-9);
+        assert_eq!((summary.mean, summary.median), (Some(0.5), Some(0.5)));
+        assert!((summary.std_dev.unwrap() - 1.5).abs() < 1e-9);
+        assert_eq!(summary.largest_improvement, Some(("AA".to_string(), -1.0)));
+        assert_eq!(summary.largest_deterioration, Some(("CC".to_string(), 2.0)));
+
+        let empty = summarize_changes(&[], [&base, &compare], HeatRateUnit::MmbtuPerMwh);
+        assert_eq!((empty.national, empty.mean), (None, None));
+        assert!(empty.largest_improvement.is_none());
+        assert!(format_change_summary(&empty, ["2019", "2020"], HeatRateUnit::MmbtuPerMwh).contains("n/a"));
+    }
+}
@@ -0,0 +1,372 @@
+// charts.rs
+// This module writes interactive Plotly HTML charts: a hoverable bar chart of state efficiency changes,
+// a choropleth map of the same changes, and a per-year bar chart with a year slider for panel data. The map
+// places Alaska and Hawaii as insets, at their true position or not at all, and can call out DC, which is too
+// small to see at national scale.
+
+use std::error::Error;
+use std::fs;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+use crate::geography::state_centroid;
+use crate::monthly::NationalMonth;
+use crate::palette::{Palette, Tone};
+use crate::panel::YearData;
+use crate::analysis::StateEfficiency;
+use crate::units::HeatRateUnit;
+
+/// Plotly bundle loaded by the generated pages unless a local one is embedded.
+const PLOTLY_CDN: &str = "https://cdn.plot.ly/plotly-2.35.2.min.js";
+
+/// Where the chart pages get plotly.js from.
+#[derive(Debug, Clone, Default)]
+pub enum PlotlyScript {
+    /// Loaded from the Plotly CDN when the page is opened, so the page needs a network connection.
+    #[default]
+    Cdn,
+    /// A local bundle embedded into every page, which then renders offline (about 3.5 MB per page).
+    Inline(String),
+}
+
+impl PlotlyScript {
+    /// Reads the plotly.js bundle at `path` for embedding, or uses the CDN without one.
+    pub fn load(path: Option<&str>) -> Result<PlotlyScript, Box<dyn Error>> {
+        let Some(path) = path else {
+            return Ok(PlotlyScript::Cdn);
+        };
+        let bundle = fs::read_to_string(path).map_err(|e| format!("cannot read plotly.js bundle '{}': {}", path, e))?;
+        if !bundle.contains("Plotly") {
+            return Err(format!("'{}' does not look like a plotly.js bundle", path).into());
+        }
+        Ok(PlotlyScript::Inline(bundle))
+    }
+
+    /// The `<script>` element loading the bundle. An embedded bundle has any closing tag in its text escaped,
+    /// so it cannot end the element early.
+    fn tag(&self) -> String {
+        match self {
+            PlotlyScript::Cdn => format!(r#"<script src="{}"></script>"#, PLOTLY_CDN),
+            PlotlyScript::Inline(bundle) => {
+                format!("<script>\n{}\n</script>", bundle.replace("</script", "<\\/script"))
+            }
+        }
+    }
+}
+
+/// Wraps Plotly traces and layout in a standalone HTML page, with the palette's series colors.
+fn html_page(title: &str, traces: &Value, layout: &Value, palette: Palette, plotly: &PlotlyScript) -> String {
+    let mut layout = layout.clone();
+    if let Some(colorway) = palette.colorway() {
+        layout["colorway"] = json!(colorway);
+    }
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+{script}
+</head>
+<body>
+<div id="chart" style="width:100%;height:90vh;"></div>
+<script>
+Plotly.newPlot("chart", {traces}, {layout}, {{responsive: true}});
+</script>
+</body>
+</html>
+"#,
+        title = title,
+        script = plotly.tag(),
+        traces = traces,
+        layout = layout,
+    )
+}
+
+/// Hover text of a state's change, with both inputs' heat rates.
+fn delta_hover(d: &StateEfficiency, labels: [&str; 2], unit: HeatRateUnit) -> String {
+    format!(
+        "{}<br>{}: {:.3}<br>{}: {:.3}<br>Change: {:+.3} {}<br>Rank: {} → {}",
+        d.state, labels[0], d.eff_2019, labels[1], d.eff_2020, d.delta, unit.label(), d.rank_2019, d.rank_2020
+    )
+}
+
+/// Title of the change chart and map.
+fn delta_title(labels: [&str; 2], unit: HeatRateUnit) -> String {
+    format!("Change in Heat Rate, {} → {} ({}; negative = more efficient)", labels[0], labels[1], unit.label())
+}
+
+/// Writes a bar chart of each state's change in heat rate, with both inputs' values on hover.
+/// # Arguments
+/// * `path` - Output HTML path
+/// * `data` - Efficiency changes per state, in `unit`
+/// * `labels` - Names of the base and compare inputs
+/// * `unit` - Unit of the heat rates
+/// * `palette` - Colors of the improving and deteriorating states' bars
+/// * `plotly` - Where the page gets plotly.js from
+pub fn write_delta_chart_html(
+    path: &str,
+    data: &[StateEfficiency],
+    labels: [&str; 2],
+    unit: HeatRateUnit,
+    palette: Palette,
+    plotly: &PlotlyScript,
+) -> Result<(), Box<dyn Error>> {
+    let mut sorted: Vec<&StateEfficiency> = data.iter().collect();
+    sorted.sort_by(|a, b| a.delta.partial_cmp(&b.delta).unwrap());
+
+    let hover: Vec<String> = sorted.iter().map(|d| delta_hover(d, labels, unit)).collect();
+
+    // Unchanged states take the improvers' color
+    let tone = |delta: f64| Tone::of_delta(delta).unwrap_or(Tone::Improved);
+    let traces = json!([{
+        "type": "bar",
+        "x": sorted.iter().map(|d| d.state.as_str()).collect::<Vec<_>>(),
+        "y": sorted.iter().map(|d| d.delta).collect::<Vec<_>>(),
+        "text": hover,
+        "hoverinfo": "text",
+        "textposition": "none",
+        "marker": {"color": sorted.iter().map(|d| palette.hex(tone(d.delta))).collect::<Vec<_>>()},
+    }]);
+    let layout = json!({
+        "title": delta_title(labels, unit),
+        "xaxis": {"title": "State"},
+        "yaxis": {"title": format!("Change in heat rate ({})", unit.label())},
+    });
+
+    fs::write(path, html_page("Fossil Fuel Efficiency Change by State", &traces, &layout, palette, plotly))?;
+    Ok(())
+}
+
+/// Placement of Alaska and Hawaii on the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum NonContiguous {
+    /// Scaled-down insets below the lower 48 (Albers USA projection).
+    Inset,
+    /// Lower 48 only; Alaska and Hawaii are left off the map.
+    Omit,
+    /// Where they are, on a map of North America and the Pacific.
+    TruePosition,
+}
+
+/// Alaska and Hawaii, placed by `NonContiguous`.
+const NON_CONTIGUOUS_STATES: [&str; 2] = ["AK", "HI"];
+
+/// Map layout options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapLayout {
+    pub non_contiguous: NonContiguous,
+
+    /// Marks DC with a labeled circle in its change's color.
+    pub dc_callout: bool,
+}
+
+/// Writes a choropleth map of each state's change in heat rate, on a diverging scale centered on no change.
+/// # Arguments
+/// * `path` - Output HTML path
+/// * `data` - Efficiency changes per state, in `unit`
+/// * `labels` - Names of the base and compare inputs
+/// * `unit` - Unit of the heat rates
+/// * `palette` - Colors of the improving and deteriorating ends of the scale
+/// * `map` - Placement of Alaska and Hawaii, and the DC call-out
+/// * `plotly` - Where the page gets plotly.js from
+pub fn write_delta_map_html(
+    path: &str,
+    data: &[StateEfficiency],
+    labels: [&str; 2],
+    unit: HeatRateUnit,
+    palette: Palette,
+    map: MapLayout,
+    plotly: &PlotlyScript,
+) -> Result<(), Box<dyn Error>> {
+    let shown: Vec<&StateEfficiency> = data
+        .iter()
+        .filter(|d| map.non_contiguous != NonContiguous::Omit || !NON_CONTIGUOUS_STATES.contains(&d.state.as_str()))
+        .collect();
+    let hover = |d: &StateEfficiency| delta_hover(d, labels, unit);
+
+    // Symmetric range, so white is no change whichever way the states moved
+    let limit = shown.iter().map(|d| d.delta.abs()).fold(0.0, f64::max).max(1e-9);
+    let colorscale = json!([
+        [0.0, palette.hex(Tone::Improved)],
+        [0.5, "#f7f7f7"],
+        [1.0, palette.hex(Tone::Worsened)],
+    ]);
+    let mut traces = vec![json!({
+        "type": "choropleth",
+        "locationmode": "USA-states",
+        "locations": shown.iter().map(|d| d.state.as_str()).collect::<Vec<_>>(),
+        "z": shown.iter().map(|d| d.delta).collect::<Vec<_>>(),
+        "zmin": -limit,
+        "zmax": limit,
+        "colorscale": colorscale,
+        "colorbar": {"title": format!("Change<br>({})", unit.label())},
+        "text": shown.iter().map(|d| hover(d)).collect::<Vec<_>>(),
+        "hoverinfo": "text",
+        "marker": {"line": {"color": "#ffffff", "width": 0.5}},
+    })];
+
+    if map.dc_callout
+        && let Some(dc) = shown.iter().find(|d| d.state == "DC")
+        && let Some((lat, lon)) = state_centroid("DC")
+    {
+        traces.push(json!({
+            "type": "scattergeo",
+            "locationmode": "USA-states",
+            "lat": [lat],
+            "lon": [lon],
+            "mode": "markers+text",
+            "text": ["DC"],
+            "textposition": "middle right",
+            "hovertext": [hover(dc)],
+            "hoverinfo": "text",
+            "showlegend": false,
+            "marker": {
+                "size": 14,
+                "color": [dc.delta],
+                "cmin": -limit,
+                "cmax": limit,
+                "colorscale": colorscale,
+                "line": {"color": "#333333", "width": 1},
+            },
+        }));
+    }
+
+    let geo = match map.non_contiguous {
+        NonContiguous::Inset | NonContiguous::Omit => json!({"scope": "usa", "projection": {"type": "albers usa"}}),
+        NonContiguous::TruePosition => json!({
+            "scope": "world",
+            "projection": {"type": "mercator"},
+            "lataxis": {"range": [17, 72]},
+            "lonaxis": {"range": [-180, -60]},
+            "showcountries": true,
+            "showsubunits": true,
+        }),
+    };
+    let layout = json!({
+        "title": delta_title(labels, unit),
+        "geo": geo,
+    });
+
+    let page = html_page("Fossil Fuel Efficiency Change by State", &Value::Array(traces), &layout, palette, plotly);
+    fs::write(path, page)?;
+    Ok(())
+}
+
+/// Writes a bar chart of state heat rates with a slider to step through the loaded years.
+/// # Arguments
+/// * `path` - Output HTML path
+/// * `panel` - Loaded years, ascending
+/// * `palette` - Series colors
+/// * `plotly` - Where the page gets plotly.js from
+pub fn write_panel_chart_html(
+    path: &str,
+    panel: &[YearData],
+    palette: Palette,
+    plotly: &PlotlyScript,
+) -> Result<(), Box<dyn Error>> {
+    let mut traces = Vec::new();
+    for (i, year_data) in panel.iter().enumerate() {
+        let mut states: Vec<(&String, f64)> = year_data
+            .stats
+            .iter()
+            .filter(|(_, s)| s.total_gen.0 != 0.0)
+            .map(|(state, s)| (state, (s.total_fuel / s.total_gen).0))
+            .collect();
+        states.sort_by(|a, b| a.0.cmp(b.0));
+
+        traces.push(json!({
+            "type": "bar",
+            "name": year_data.year.to_string(),
+            "visible": i + 1 == panel.len(),
+            "x": states.iter().map(|(state, _)| state.as_str()).collect::<Vec<_>>(),
+            "y": states.iter().map(|(_, rate)| *rate).collect::<Vec<_>>(),
+            "hovertemplate": format!("%{{x}} {}: %{{y:.3}} MMBtu/MWh<extra></extra>", year_data.year),
+        }));
+    }
+
+    // One slider step per year, each showing only that year's trace
+    let steps: Vec<Value> = panel
+        .iter()
+        .enumerate()
+        .map(|(i, year_data)| {
+            let visible: Vec<bool> = (0..panel.len()).map(|j| j == i).collect();
+            json!({"label": year_data.year.to_string(), "method": "update", "args": [{"visible": visible}]})
+        })
+        .collect();
+
+    let layout = json!({
+        "title": "State Heat Rate by Year (MMBtu/MWh)",
+        "xaxis": {"title": "State"},
+        "yaxis": {"title": "Heat rate"},
+        "sliders": [{"active": panel.len().saturating_sub(1), "currentvalue": {"prefix": "Year: "}, "steps": steps}],
+    });
+
+    fs::write(path, html_page("State Heat Rate by Year", &Value::Array(traces), &layout, palette, plotly))?;
+    Ok(())
+}
+
+/// Writes the national monthly series as three stacked line charts: heat rate, net generation and fuel.
+/// # Arguments
+/// * `path` - Output HTML path
+/// * `data` - National monthly totals, in time order
+/// * `palette` - Series colors
+/// * `plotly` - Where the page gets plotly.js from
+pub fn write_national_monthly_chart_html(
+    path: &str,
+    data: &[NationalMonth],
+    palette: Palette,
+    plotly: &PlotlyScript,
+) -> Result<(), Box<dyn Error>> {
+    let months: Vec<String> = data.iter().map(|m| format!("{}-{:02}", m.year, m.month)).collect();
+
+    let traces = json!([
+        {
+            "type": "scatter", "mode": "lines+markers", "name": "Heat rate (MMBtu/MWh)",
+            "x": months, "y": data.iter().map(|m| m.heat_rate()).collect::<Vec<_>>(), "yaxis": "y",
+        },
+        {
+            "type": "scatter", "mode": "lines", "name": "Net generation (MWh)",
+            "x": months, "y": data.iter().map(|m| m.r#gen).collect::<Vec<_>>(), "yaxis": "y2",
+        },
+        {
+            "type": "scatter", "mode": "lines", "name": "Fuel (MMBtu)",
+            "x": months, "y": data.iter().map(|m| m.fuel).collect::<Vec<_>>(), "yaxis": "y3",
+        },
+    ]);
+    let layout = json!({
+        "title": "U.S. Monthly Heat Rate, Generation and Fuel",
+        "xaxis": {"title": "Month", "anchor": "y3"},
+        "yaxis": {"title": "Heat rate", "domain": [0.68, 1.0]},
+        "yaxis2": {"title": "Generation", "domain": [0.34, 0.64]},
+        "yaxis3": {"title": "Fuel", "domain": [0.0, 0.30]},
+    });
+
+    fs::write(path, html_page("U.S. Monthly Heat Rate", &traces, &layout, palette, plotly))?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plotly_script() {
+        assert!(PlotlyScript::load(None).unwrap().tag().contains(PLOTLY_CDN));
+
+        let path = std::env::temp_dir().join(format!("efficiency_plotly_{}.js", std::process::id()));
+        fs::write(&path, "window.Plotly = {html: '</script>'};").unwrap();
+        let plotly = PlotlyScript::load(path.to_str()).unwrap();
+        let page = html_page("Chart", &json!([]), &json!({}), Palette::Standard, &plotly);
+        assert!(!page.contains(PLOTLY_CDN) && page.contains("window.Plotly"));
+        // Only the element's own closing tag is left
+        assert_eq!(page.matches("</script>").count(), 2);
+        fs::write(&path, "not a bundle").unwrap();
+        assert!(PlotlyScript::load(path.to_str()).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+// checkpoint.rs
+// This module saves and restores per-year aggregates so long panel runs can resume after an interruption.
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::merge::input_parts;
+use crate::panel::YearData;
+
+/// Identifies the exact source file a checkpoint was computed from.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SourceStamp {
+    path: String,
+    len: u64,
+    modified_secs: u64,
+}
+
+impl SourceStamp {
+    /// For a merged input, the total length and the latest modification of its files.
+    fn of(file_path: &str) -> Result<SourceStamp, Box<dyn Error>> {
+        let mut stamp = SourceStamp { path: file_path.to_string(), len: 0, modified_secs: 0 };
+        for part in input_parts(file_path) {
+            let meta = fs::metadata(part)?;
+            stamp.len += meta.len();
+            stamp.modified_secs = stamp.modified_secs.max(meta.modified()?.duration_since(UNIX_EPOCH)?.as_secs());
+        }
+        Ok(stamp)
+    }
+}
+
+/// On-disk checkpoint: the source stamp plus the aggregates computed from it.
+#[derive(Serialize, Deserialize)]
+struct Checkpoint<D> {
+    source: SourceStamp,
+    data: D,
+}
+
+/// Checkpoints are named after the source file (`2019.csv` → `2019.json`), since the year is only known
+/// once the file has been read. A merged input joins the names of its files (`2020-a+2020-b.json`).
+fn checkpoint_path(dir: &str, file_path: &str) -> PathBuf {
+    let stems: Vec<String> = input_parts(file_path)
+        .into_iter()
+        .map(|part| Path::new(part).file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default())
+        .collect();
+    Path::new(dir).join(format!("{}.json", stems.join("+")))
+}
+
+/// Returns the saved aggregates for `file_path`, or `None` if there is no checkpoint, it cannot be read,
+/// or the source file has changed since it was written.
+pub fn load_checkpoint(dir: &str, file_path: &str) -> Option<YearData> {
+    let file = File::open(checkpoint_path(dir, file_path)).ok()?;
+    let checkpoint: Checkpoint<YearData> = serde_json::from_reader(BufReader::new(file)).ok()?;
+
+    let current = SourceStamp::of(file_path).ok()?;
+    (checkpoint.source == current).then_some(checkpoint.data)
+}
+
+/// Saves one year's aggregates. The file is written under a temporary name and then renamed,
+/// so an interrupted save never leaves a truncated checkpoint behind.
+pub fn save_checkpoint(dir: &str, file_path: &str, data: &YearData) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    let path = checkpoint_path(dir, file_path);
+    let tmp_path = path.with_extension("json.tmp");
+
+    let checkpoint = Checkpoint { source: SourceStamp::of(file_path)?, data };
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    serde_json::to_writer(&mut writer, &checkpoint)?;
+    writer.into_inner()?.sync_all()?;
+
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let dir = std::env::temp_dir().join(format!("efficiency_checkpoint_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("2019.csv");
+        fs::write(&source, "rows").unwrap();
+        let (dir_path, source_path) = (dir.join("checkpoints"), source.to_str().unwrap());
+        let dir_path = dir_path.to_str().unwrap();
+
+        assert!(load_checkpoint(dir_path, source_path).is_none());
+        let data = YearData {
+            year: 2019,
+            source: source_path.to_string(),
+            rows: 3,
+            options: Default::default(),
+            imputed: 1,
+            anomalies: 0,
+            monthly: HashMap::new(),
+            stats: HashMap::new(),
+            fuels: Default::default(),
+            technologies: Default::default(),
+        };
+        save_checkpoint(dir_path, source_path, &data).unwrap();
+        let loaded = load_checkpoint(dir_path, source_path).unwrap();
+        assert_eq!((loaded.year, loaded.rows, loaded.imputed), (2019, 3, 1));
+
+        // A changed source invalidates the checkpoint
+        fs::write(&source, "more rows").unwrap();
+        assert!(load_checkpoint(dir_path, source_path).is_none());
+
+        assert_eq!(checkpoint_path("out", "data/2020-a.csv+data/2020-b.csv"), Path::new("out/2020-a+2020-b.json"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+// cleaned.rs
+// This module exports the cleaned row-level records (after validation, territory handling, imputation and the
+// heat content policy) so other tools can reuse the cleaning without running the analysis, as one CSV file or
+// partitioned by state or fuel group.
+
+use std::error::Error;
+
+use csv::WriterBuilder;
+
+use crate::cleaning::{CleanRecord, MONTHS};
+use crate::fuels::FuelGroup;
+use crate::partition::{PartitionBy, PartitionedWriter};
+
+/// Columns of the cleaned records.
+pub fn cleaned_header() -> Vec<String> {
+    let mut header: Vec<String> = [
+        "Input", "Plant_Id", "Plant_Name", "State", "AER_Fuel", "Fuel_Group", "Reported_Fuel", "Prime_Mover",
+        "Fuel_MMBtu", "Net_Generation_MWh", "Blank_Months",
+    ]
+    .iter()
+    .map(|c| c.to_string())
+    .collect();
+    header.extend(MONTHS.iter().map(|m| format!("Fuel_MMBtu_{}", m)));
+    header.extend(MONTHS.iter().map(|m| format!("Net_Generation_MWh_{}", m)));
+    header.extend(MONTHS.iter().map(|m| format!("Quantity_{}", m)));
+    header
+}
+
+/// Months whose fuel or generation value was blank before imputation.
+pub fn blank_months(record: &CleanRecord) -> usize {
+    (0..12).filter(|m| record.missing_fuel[*m] || record.missing_gen[*m]).count()
+}
+
+/// One output row: identifiers, fuel group, annual totals, number of blank months and the monthly values.
+fn cleaned_row(label: &str, record: &CleanRecord) -> Vec<String> {
+    let mut row = vec![
+        label.to_string(),
+        record.plant_id.clone(),
+        record.plant_name.clone(),
+        record.state.clone(),
+        record.aer_fuel.clone(),
+        FuelGroup::from_aer(&record.aer_fuel).label().to_string(),
+        record.reported_fuel.clone(),
+        record.prime_mover.clone(),
+        format!("{:.6}", record.fuel),
+        format!("{:.6}", record.r#gen),
+        blank_months(record).to_string(),
+    ];
+    row.extend(record.monthly_fuel.iter().map(|v| format!("{:.6}", v)));
+    row.extend(record.monthly_gen.iter().map(|v| format!("{:.6}", v)));
+    row.extend(record.monthly_quantity.iter().map(|v| format!("{:.6}", v)));
+    row
+}
+
+/// Writes the cleaned records of each input, one row per plant, fuel and prime mover, to a CSV file.
+/// # Arguments
+/// * `path` - Output file; must end in `.csv`
+/// * `inputs` - `(label, records)` per input, e.g. `("2019", &records_2019)`
+/// # Returns
+/// * `Err` for other extensions (the `convert` subcommand writes Parquet)
+pub fn write_cleaned_csv(path: &str, inputs: &[(&str, &[CleanRecord])]) -> Result<(), Box<dyn Error>> {
+    if !path.to_lowercase().ends_with(".csv") {
+        let message = "only CSV output is supported (use a .csv file name, or the convert subcommand for Parquet)";
+        return Err(format!("--emit-cleaned '{}': {}", path, message).into());
+    }
+
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(cleaned_header())?;
+
+    for (label, records) in inputs {
+        for record in records.iter() {
+            wtr.write_record(cleaned_row(label, record))?;
+        }
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Writes the cleaned records as a partitioned directory (e.g. 'State=TX/part-00000.csv'), the partition column
+/// moving from the rows to the directory names.
+/// # Arguments
+/// * `dir` - Output directory
+/// * `inputs` - `(label, records)` per input
+/// * `by` - Partition column
+/// * `chunk_rows` - Rows per part file
+/// # Returns
+/// * The part files written
+pub fn write_cleaned_partitioned(
+    dir: &str,
+    inputs: &[(&str, &[CleanRecord])],
+    by: PartitionBy,
+    chunk_rows: usize,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut writer = PartitionedWriter::new(dir, &cleaned_header(), by.column(), chunk_rows)?;
+    for (label, records) in inputs {
+        for record in records.iter() {
+            writer.write(cleaned_row(label, record))?;
+        }
+    }
+    writer.finish()
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_cleaned_rows_and_extension() {
+        let mut record = CleanRecord {
+            monthly_fuel: [10.0; 12],
+            monthly_gen: [1.0; 12],
+            reported_fuel: "NG".to_string(),
+            prime_mover: "CT".to_string(),
+            ..record("7", "AA", "NG", 120.0, 12.0)
+        };
+        record.missing_gen[3] = true;
+
+        let row = cleaned_row("2020", &record);
+        assert_eq!((row.len(), cleaned_header().len()), (11 + 36, 11 + 36));
+        assert_eq!((row[0].as_str(), row[5].as_str(), row[10].as_str()), ("2020", "Natural Gas", "1"));
+
+        assert!(write_cleaned_csv("cleaned.parquet", &[]).is_err());
+    }
+}
@@ -3,19 +3,47 @@
 
 use std::collections::HashMap;
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-use csv::ReaderBuilder;
-use serde::Deserialize;
+use csv::StringRecord;
+use serde::{Deserialize, Serialize};
+
+use crate::cancel::{CancellationToken, CHECK_INTERVAL};
+use crate::degraded::{read_missing_columns, MissingColumns, OptionalColumn};
+use crate::heatcontent::{check_heat_content, HeatContentAnomaly, HeatContentPolicy};
+use crate::impute::{impute_monthly, ImputeStrategy, ImputedValue};
+use crate::merge::{is_merged, read_merged, DuplicatePolicy};
+use crate::quantity::{HeatRate, MegawattHours, Mmbtu};
+use crate::source::{DataSource, SourceRow};
+use crate::stats::CompensatedSum;
+use crate::territories::{apply_territory_policy, TerritoryPolicy};
+use crate::years::DataYears;
 
 /// Struct representing a deserialized row from the CSV file.
-/// Fields are mapped to exact CSV column headers.
+/// Fields are mapped to exact CSV column headers. Only the state and the annual totals are required; the other
+/// columns are blank when a file lacks them (see `degraded`).
 #[derive(Debug, Deserialize)]
 pub struct Record {
+    #[serde(rename = "Plant Id", default)]
+    pub plant_id: String,
+
+    #[serde(rename = "Plant Name", default)]
+    pub plant_name: String,
+
     #[serde(rename = "Plant State")]
     pub state: String,
 
+    #[serde(rename = "AER\nFuel Type Code", default)]
+    pub aer_fuel: String,
+
+    #[serde(rename = "Reported\nFuel Type Code", default)]
+    pub reported_fuel: String,
+
+    #[serde(rename = "Reported\nPrime Mover", default)]
+    pub prime_mover: String,
+
     #[serde(rename = "Total Fuel Consumption\nMMBtu")]
     pub fuel: String,
 
@@ -23,88 +51,598 @@ pub struct Record {
     pub r#gen: String,
 }
 
-/// Aggregated totals for each state.
-#[derive(Debug, Default)]
-pub struct StateStats {
+/// A cleaned row with numeric values parsed and the zero-generation rows removed. `Default` is derived only for the
+/// test fixtures (see `fixtures::record`); an empty row is not a record the loaders produce.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CleanRecord {
+    /// EIA plant identifier (99999 marks state-level estimated increments).
+    pub plant_id: String,
+
+    /// Plant name as reported.
+    pub plant_name: String,
+
+    /// State abbreviation (e.g., "CA", "TX").
+    pub state: String,
+
+    /// AER fuel type code (e.g., "COL", "NG", "SUN").
+    pub aer_fuel: String,
+
+    /// Total fuel consumption in MMBtu.
+    pub fuel: f64,
+
+    /// Net generation in MWh.
+    pub r#gen: f64,
+
+    /// Total fuel consumption per month (January first), in MMBtu.
+    pub monthly_fuel: [f64; 12],
+
+    /// Net generation per month (January first), in MWh.
+    pub monthly_gen: [f64; 12],
+
+    /// Months whose fuel value was blank or missing (filled by `impute_monthly`).
+    pub missing_fuel: [bool; 12],
+
+    /// Months whose generation value was blank or missing.
+    pub missing_gen: [bool; 12],
+
+    /// Fuel type code as reported by the plant (e.g., "BIT", "SUB", "NG"); finer than the AER code.
+    pub reported_fuel: String,
+
+    /// Physical quantity of fuel burned per month, in the fuel's own unit (tons, barrels or Mcf).
+    pub monthly_quantity: [f64; 12],
+
+    /// Prime mover code as reported (e.g., "ST" steam turbine, "CT" combustion turbine, "CA"/"CS" combined cycle).
+    pub prime_mover: String,
+}
+
+/// Plant id used by EIA for state-fuel level increments that are not real plants.
+pub const INCREMENT_PLANT_ID: &str = "99999";
+
+/// Aggregated totals for a single plant.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PlantStats {
+    pub plant_id: String,
+    pub plant_name: String,
+    pub state: String,
     pub total_fuel: f64,
     pub total_gen: f64,
 }
 
-/// Reads and cleans a CSV file, returning a HashMap of state statistics.
-/// 
+/// Month names as they appear in the EIA-923 monthly column headers.
+pub const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+/// Aggregated totals for each state.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StateStats {
+    pub total_fuel: Mmbtu,
+    pub total_gen: MegawattHours,
+}
+
+impl StateStats {
+    /// Heat rate of the totals, or `None` without positive generation.
+    pub fn heat_rate(&self) -> Option<HeatRate> {
+        HeatRate::of(self.total_fuel, self.total_gen)
+    }
+}
+
+/// How fuel enters the heat rate; combined heat and power plants are not separated, so their fuel includes
+/// what went to useful thermal output.
+pub const FUEL_BASIS: &str =
+    "total fuel consumption (MMBtu); at combined heat and power plants this includes fuel for useful thermal output";
+
+/// Which rows `load_clean_records` drops for their generation.
+pub const ZERO_GENERATION_POLICY: &str =
+    "rows with zero annual net generation are dropped; negative net generation is kept";
+
+/// Version of the cleaned rows, hashed into the cache keys (see `cache::cache_key`). Bump it whenever a change to
+/// the cleaning (parsing, filters, imputation, the `CleanRecord` fields) would give different rows for the same
+/// file and options, so cached entries from before the change are not reused.
+pub const CLEANING_SCHEMA_VERSION: u32 = 1;
+
+/// Rows dropped while reading a file, by reason.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct SkipCounts {
+    /// Rows that could not be read into a `Record` (missing or malformed columns).
+    pub unreadable: usize,
+
+    /// Rows whose annual fuel or generation is not a number.
+    pub unparsable_values: usize,
+
+    /// Rows with zero annual net generation.
+    pub zero_generation: usize,
+}
+
+impl SkipCounts {
+    pub fn total(&self) -> usize {
+        self.unreadable + self.unparsable_values + self.zero_generation
+    }
+}
+
+/// Reads and cleans a CSV file, returning one `CleanRecord` per valid row.
+///
 /// # Arguments
 /// * `file_path` - The path to the input CSV file
 ///
 /// # Returns
-/// * `HashMap<String, StateStats>` where the key is the state code
-pub fn load_state_efficiency(file_path: &str) -> Result<HashMap<String, StateStats>, Box<dyn Error>> {
+/// * `Vec<CleanRecord>` with unparsable and zero-generation rows removed
+pub fn load_clean_records(file_path: &str) -> Result<Vec<CleanRecord>, Box<dyn Error>> {
+    Ok(read_clean_records(file_path)?.0)
+}
+
+/// `load_clean_records`, also returning how many rows were dropped for each reason.
+pub fn read_clean_records(file_path: &str) -> Result<(Vec<CleanRecord>, SkipCounts), Box<dyn Error>> {
+    let (records, skipped, _) = read_clean_records_with_cancel(file_path, &CancellationToken::new())?;
+    Ok((records, skipped))
+}
+
+/// `read_clean_records` that stops with a `Cancelled` error once `cancel` is set, also counting the rows per
+/// value of the YEAR column.
+pub fn read_clean_records_with_cancel(
+    file_path: &str,
+    cancel: &CancellationToken,
+) -> Result<(Vec<CleanRecord>, SkipCounts, DataYears), Box<dyn Error>> {
+    read_clean_records_projected(file_path, ColumnProjection::All, cancel)
+}
+
+/// `read_clean_records_with_cancel` parsing only the columns of `columns`.
+pub fn read_clean_records_projected(
+    file_path: &str,
+    columns: ColumnProjection,
+    cancel: &CancellationToken,
+) -> Result<(Vec<CleanRecord>, SkipCounts, DataYears), Box<dyn Error>> {
     println!("Attempting to open file: {}", file_path);
+    let mut records = Vec::new();
+    let mut cleaner: Option<RowCleaner> = None;
+    let mut row = 0;
 
-    let file = File::open(file_path)?;
-    let mut lines = BufReader::new(file).lines();
+    DataSource::new(file_path).read_rows(|source_row| {
+        match source_row {
+            SourceRow::Headers(headers) => {
+                println!("🟢 Actual headers: {:?}", headers);
+                cleaner = Some(RowCleaner::new(headers, columns));
+            }
+            SourceRow::Data(raw) => {
+                if row % CHECK_INTERVAL == 0 {
+                    cancel.check(&format!("reading '{}'", file_path), || format!("{} rows read", row))?;
+                }
+                row += 1;
+                if let Some(record) = cleaner.as_mut().and_then(|c| c.clean(raw)) {
+                    records.push(record);
+                }
+            }
+        }
+        Ok(())
+    })?;
 
-    // Skip metadata header lines (non-CSV rows)
-    for _ in 0..5 {
-        lines.next();
-    }
+    let (skipped, years) = cleaner.map(|c| (c.skipped, c.years)).unwrap_or_default();
+    println!("✅ Parsed: {} valid rows | ❌ Skipped: {} rows", records.len(), skipped.total());
+    Ok((records, skipped, years))
+}
 
-    // Join remaining lines to form valid CSV content
-    let csv_data: Vec<u8> = lines
-        .filter_map(Result::ok)
-        .collect::<Vec<String>>()
-        .join("\n")
-        .into_bytes();
+/// Turns the raw rows of one file into `CleanRecord`s, counting the rows dropped and the rows per data year.
+#[derive(Debug)]
+pub struct RowCleaner {
+    headers: StringRecord,
+    columns: ColumnProjection,
+    fuel_columns: [Option<usize>; 12],
+    gen_columns: [Option<usize>; 12],
+    quantity_columns: [Option<usize>; 12],
+    year_column: Option<usize>,
 
-    let mut rdr = ReaderBuilder::new()
-        .has_headers(true)
-        .from_reader(csv_data.as_slice());
+    /// Rows dropped so far, by reason.
+    pub skipped: SkipCounts,
 
-    let headers = rdr.headers()?.clone();
-    println!("🟢 Actual headers: {:?}", headers);
+    /// Rows read so far per value of the YEAR column.
+    pub years: DataYears,
+}
 
-    let mut state_map: HashMap<String, StateStats> = HashMap::new();
-    let mut valid_rows = 0;
-    let mut skipped_rows = 0;
+impl RowCleaner {
+    /// A cleaner for rows under `headers`, parsing the columns of `columns`. Without any monthly column the
+    /// months are left zero as with `ColumnProjection::Annual`, rather than all flagged blank and imputed.
+    pub fn new(headers: &StringRecord, columns: ColumnProjection) -> RowCleaner {
+        let monthly = !MissingColumns::from_headers(headers).contains(OptionalColumn::Monthly);
+        RowCleaner {
+            headers: headers.clone(),
+            columns: if monthly { columns } else { ColumnProjection::Annual },
+            fuel_columns: monthly_column_indices(headers, "Tot_MMBtu"),
+            gen_columns: monthly_column_indices(headers, "Netgen"),
+            quantity_columns: monthly_column_indices(headers, "Quantity"),
+            year_column: headers.iter().position(|h| h == "YEAR"),
+            skipped: SkipCounts::default(),
+            years: DataYears::default(),
+        }
+    }
 
-    for result in rdr.deserialize::<Record>() {
-        let record = match result {
+    /// Cleans one raw row, or returns `None` (and counts the reason) when the row is dropped.
+    pub fn clean(&mut self, row: csv::Result<StringRecord>) -> Option<CleanRecord> {
+        let (raw, record) = match row.and_then(|raw| {
+            let record: Record = raw.deserialize(Some(&self.headers))?;
+            Ok((raw, record))
+        }) {
             Ok(r) => r,
             Err(_) => {
-                skipped_rows += 1;
-                continue;
+                self.skipped.unreadable += 1;
+                return None;
             }
         };
 
+        if let Some(year) = self.year_column.and_then(|i| raw.get(i)).and_then(|v| v.trim().parse().ok()) {
+            self.years.add(year);
+        }
+
         // Parse and clean fuel and generation values
-        let fuel_val: f64 = match record.fuel.replace(",", "").parse() {
-            Ok(v) => v,
-            Err(_) => {
-                skipped_rows += 1;
-                continue;
-            }
+        let (Ok(fuel_val), Ok(gen_val)) =
+            (record.fuel.replace(",", "").parse::<f64>(), record.r#gen.replace(",", "").parse::<f64>())
+        else {
+            self.skipped.unparsable_values += 1;
+            return None;
         };
 
-        let gen_val: f64 = match record.r#gen.replace(",", "").parse() {
-            Ok(v) => v,
-            Err(_) => {
-                skipped_rows += 1;
-                continue;
-            }
+        if gen_val == 0.0 {
+            self.skipped.zero_generation += 1;
+            return None;
+        }
+
+        let ((monthly_fuel, missing_fuel), (monthly_gen, missing_gen), (monthly_quantity, _)) = match self.columns {
+            ColumnProjection::All => (
+                parse_monthly(&raw, &self.fuel_columns),
+                parse_monthly(&raw, &self.gen_columns),
+                parse_monthly(&raw, &self.quantity_columns),
+            ),
+            // Skipped months are zero and not flagged as missing
+            ColumnProjection::Annual => Default::default(),
         };
 
-        if gen_val == 0.0 {
-            skipped_rows += 1;
+        Some(CleanRecord {
+            plant_id: record.plant_id,
+            plant_name: record.plant_name,
+            state: record.state,
+            aer_fuel: record.aer_fuel,
+            fuel: fuel_val,
+            r#gen: gen_val,
+            monthly_fuel,
+            monthly_gen,
+            missing_fuel,
+            missing_gen,
+            reported_fuel: record.reported_fuel,
+            monthly_quantity,
+            prime_mover: record.prime_mover,
+        })
+    }
+}
+
+/// Reads an EIA-923 CSV export without its metadata header lines, leaving the column header row first.
+/// A line that cannot be read (e.g. not valid UTF-8) is an error naming its line number, rather than the end of
+/// the file.
+pub fn read_csv_body(file_path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let file = File::open(file_path)?;
+    let mut body = Vec::new();
+
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("cannot read '{}' line {}: {}", file_path, i + 1, e))?;
+        // Skip metadata header lines (non-CSV rows)
+        if i >= 5 {
+            body.push(line);
+        }
+    }
+
+    // Join remaining lines to form valid CSV content
+    Ok(body.join("\n").into_bytes())
+}
+
+/// Finds the column index of each month for a monthly column group (e.g., "Netgen").
+fn monthly_column_indices(headers: &StringRecord, prefix: &str) -> [Option<usize>; 12] {
+    MONTHS.map(|month| {
+        let name = format!("{}\n{}", prefix, month);
+        headers.iter().position(|h| h == name)
+    })
+}
+
+/// Parses the monthly values of a row; blank (".") or missing months count as zero and are flagged.
+fn parse_monthly(raw: &StringRecord, columns: &[Option<usize>; 12]) -> ([f64; 12], [bool; 12]) {
+    let values: [Option<f64>; 12] = columns.map(|column| {
+        column
+            .and_then(|i| raw.get(i))
+            .and_then(|v| v.replace(",", "").parse().ok())
+    });
+    (values.map(|v| v.unwrap_or(0.0)), values.map(|v| v.is_none()))
+}
+
+/// Columns the reader parses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColumnProjection {
+    /// Every column a `CleanRecord` holds.
+    #[default]
+    All,
+    /// The plant, state, fuel code, prime mover and annual total columns; the 36 monthly fuel, generation and
+    /// quantity columns are not parsed, so the monthly values are zero and heat content cannot be checked.
+    Annual,
+}
+
+impl ColumnProjection {
+    pub fn is_all(&self) -> bool {
+        *self == ColumnProjection::All
+    }
+}
+
+/// Options applied to every loaded file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct LoadOptions {
+    /// Treatment of DC and the territories.
+    pub territories: TerritoryPolicy,
+
+    /// How blank monthly values are filled.
+    pub impute: ImputeStrategy,
+
+    /// Treatment of plant-months with implausible heat content.
+    pub heat_content: HeatContentPolicy,
+
+    /// Treatment of plant rows found in more than one file of a merged input (`a.csv+b.csv`).
+    pub duplicates: DuplicatePolicy,
+
+    /// Accept a file without valid data rows instead of failing with `EmptyDataset`. Left out of the
+    /// serialized options when off, so cache keys of earlier releases stay valid.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_empty: bool,
+
+    /// Columns parsed from single-file inputs (merged inputs are always read in full). Left out of the
+    /// serialized options when all columns are read, like `allow_empty`.
+    #[serde(default, skip_serializing_if = "ColumnProjection::is_all")]
+    pub columns: ColumnProjection,
+}
+
+/// Exit status of a run stopped by an `EmptyDataset`.
+pub const EMPTY_DATASET_EXIT_CODE: i32 = 4;
+
+/// An input without a single valid data row: empty, only the metadata lines and header, or every row dropped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmptyDataset {
+    pub path: String,
+
+    /// Rows that were read and dropped; zero for an empty or header-only file.
+    pub skipped: usize,
+}
+
+impl fmt::Display for EmptyDataset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.skipped {
+            0 => write!(f, "'{}' has no data rows (empty or header only)", self.path)?,
+            n => write!(f, "'{}' has no valid data rows ({} rows dropped while cleaning)", self.path, n)?,
+        }
+        f.write_str("; --allow-empty accepts it")
+    }
+}
+
+impl Error for EmptyDataset {}
+
+/// Refuses a file that yielded no rows, unless `allow_empty` is set.
+fn check_not_empty(
+    file_path: &str,
+    records: &[CleanRecord],
+    skipped: &SkipCounts,
+    allow_empty: bool,
+) -> Result<(), EmptyDataset> {
+    if records.is_empty() && !allow_empty {
+        return Err(EmptyDataset { path: file_path.to_string(), skipped: skipped.total() });
+    }
+    Ok(())
+}
+
+/// A loaded file and what was changed or flagged while loading it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoadedFile {
+    pub records: Vec<CleanRecord>,
+
+    /// Blank monthly values that were filled.
+    pub imputed: Vec<ImputedValue>,
+
+    /// Plant-months whose implied heat content was implausible.
+    pub anomalies: Vec<HeatContentAnomaly>,
+
+    /// Rows dropped while reading the file.
+    pub skipped: SkipCounts,
+
+    /// Rows per value of the YEAR column.
+    pub years: DataYears,
+
+    /// Optional columns the file lacks. Missing in cache entries of earlier releases, which only held complete
+    /// files.
+    #[serde(default)]
+    pub missing: MissingColumns,
+}
+
+/// Loads a file with `load_clean_records`, applies the territory policy, fills blank months and checks
+/// heat content, printing what was done.
+/// # Returns
+/// * The cleaned rows with the imputed values and heat content anomalies
+pub fn load_records(file_path: &str, options: &LoadOptions) -> Result<LoadedFile, Box<dyn Error>> {
+    load_records_with_cancel(file_path, options, &CancellationToken::new())
+}
+
+/// `load_records` that stops with a `Cancelled` error once `cancel` is set.
+/// # Returns
+/// * `Err(EmptyDataset)` when the file has no valid rows and `options.allow_empty` is off
+pub fn load_records_with_cancel(
+    file_path: &str,
+    options: &LoadOptions,
+    cancel: &CancellationToken,
+) -> Result<LoadedFile, Box<dyn Error>> {
+    let (mut records, skipped, years) = if is_merged(file_path) {
+        read_merged(file_path, options.duplicates, cancel)?
+    } else {
+        read_clean_records_projected(file_path, options.columns, cancel)?
+    };
+    cancel.check(&format!("cleaning '{}'", file_path), || format!("{} rows read", records.len()))?;
+    check_not_empty(file_path, &records, &skipped, options.allow_empty)?;
+
+    let found = apply_territory_policy(&mut records, options.territories);
+    if !found.is_empty() {
+        let codes: Vec<String> = found.into_iter().collect();
+        println!("Non-state rows ({}): {}", codes.join(", "), options.territories.describe());
+    }
+
+    let imputed = impute_monthly(&mut records, options.impute);
+    if !imputed.is_empty() {
+        println!("Imputed {} blank monthly values ({})", imputed.len(), options.impute.label());
+    }
+
+    let anomalies = check_heat_content(&mut records, options.heat_content);
+    if !anomalies.is_empty() {
+        println!(
+            "{} plant-months with implausible heat content ({})",
+            anomalies.len(),
+            options.heat_content.label()
+        );
+    }
+
+    let missing = read_missing_columns(file_path)?;
+    if !missing.is_empty() {
+        println!("Warning: '{}' has no {}; the metrics that need them are left out", file_path, missing);
+    }
+
+    Ok(LoadedFile { records, imputed, anomalies, skipped, years, missing })
+}
+
+/// Sums fuel and generation by state.
+///
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+///
+/// # Returns
+/// * `HashMap<String, StateStats>` where the key is the state code
+pub fn aggregate_by_state(records: &[CleanRecord]) -> HashMap<String, StateStats> {
+    // Compensated sums, so a state's total does not lose its many small plants next to a few large ones
+    let mut sums: HashMap<String, (CompensatedSum, CompensatedSum)> = HashMap::new();
+
+    for record in records {
+        let entry = sums.entry(record.state.clone()).or_default();
+        entry.0.add(record.fuel);
+        entry.1.add(record.r#gen);
+    }
+
+    sums.into_iter()
+        .map(|(state, (fuel, r#gen))| {
+            (state, StateStats { total_fuel: Mmbtu(fuel.total()), total_gen: MegawattHours(r#gen.total()) })
+        })
+        .collect()
+}
+
+/// Sums fuel and generation by plant, skipping the state-level estimated increments.
+///
+/// # Arguments
+/// * `records` - Cleaned rows for a single year (optionally pre-filtered, e.g. by fuel)
+///
+/// # Returns
+/// * `HashMap<String, PlantStats>` where the key is the plant id
+pub fn aggregate_by_plant<'a>(records: impl IntoIterator<Item = &'a CleanRecord>) -> HashMap<String, PlantStats> {
+    let mut plant_map: HashMap<String, PlantStats> = HashMap::new();
+
+    for record in records {
+        if record.plant_id == INCREMENT_PLANT_ID {
             continue;
         }
 
-        // Accumulate data by state
-        let entry = state_map.entry(record.state.clone()).or_default();
-        entry.total_fuel += fuel_val;
-        entry.total_gen += gen_val;
-        valid_rows += 1;
+        let entry = plant_map.entry(record.plant_id.clone()).or_insert_with(|| PlantStats {
+            plant_id: record.plant_id.clone(),
+            plant_name: record.plant_name.clone(),
+            state: record.state.clone(),
+            ..Default::default()
+        });
+        entry.total_fuel += record.fuel;
+        entry.total_gen += record.r#gen;
     }
 
-    println!("✅ Parsed: {} valid rows | ❌ Skipped: {} rows", valid_rows, skipped_rows);
-    Ok(state_map)
+    plant_map
 }
 
+/// Sums fuel and generation by state and plant, keeping the state-level increments as one pseudo-plant per
+/// state so the totals still add up to the state totals.
+///
+/// # Returns
+/// * `HashMap<(String, String), PlantStats>` keyed by (state, plant id)
+pub fn aggregate_by_state_plant(records: &[CleanRecord]) -> HashMap<(String, String), PlantStats> {
+    let mut plant_map: HashMap<(String, String), PlantStats> = HashMap::new();
+
+    for record in records {
+        let entry = plant_map
+            .entry((record.state.clone(), record.plant_id.clone()))
+            .or_insert_with(|| PlantStats {
+                plant_id: record.plant_id.clone(),
+                plant_name: record.plant_name.clone(),
+                state: record.state.clone(),
+                ..Default::default()
+            });
+        entry.total_fuel += record.fuel;
+        entry.total_gen += record.r#gen;
+    }
+
+    plant_map
+}
+
+/// Rows shared by the modules' tests.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::CleanRecord;
+
+    /// A row of plant `plant_id` (named "Plant <id>") burning `aer_fuel`, with its annual fuel (MMBtu) and net
+    /// generation (MWh). Tests set any other field with struct update syntax.
+    pub(crate) fn record(plant_id: &str, state: &str, aer_fuel: &str, fuel: f64, r#gen: f64) -> CleanRecord {
+        CleanRecord {
+            plant_id: plant_id.to_string(),
+            plant_name: format!("Plant {}", plant_id),
+            state: state.to_string(),
+            aer_fuel: aer_fuel.to_string(),
+            fuel,
+            r#gen,
+            ..Default::default()
+        }
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_dataset() {
+        let skipped = SkipCounts { unreadable: 0, unparsable_values: 2, zero_generation: 1 };
+        let error = check_not_empty("2021.csv", &[], &SkipCounts::default(), false).unwrap_err();
+        assert_eq!(error, EmptyDataset { path: "2021.csv".to_string(), skipped: 0 });
+        assert!(error.to_string().starts_with("'2021.csv' has no data rows"));
+        assert_eq!(check_not_empty("2021.csv", &[], &skipped, false).unwrap_err().skipped, 3);
+        assert!(check_not_empty("2021.csv", &[], &skipped, true).is_ok());
+
+        // The switches are left out of cache keys while off, so earlier cache entries stay valid
+        let options = serde_json::to_value(LoadOptions::default()).unwrap();
+        assert_eq!((options.get("allow_empty"), options.get("columns")), (None, None));
+        let annual = LoadOptions { columns: ColumnProjection::Annual, ..Default::default() };
+        assert_eq!(serde_json::to_value(annual).unwrap()["columns"], "annual");
+    }
+
+    #[test]
+    fn test_read_csv_body() {
+        let path = std::env::temp_dir().join(format!("efficiency_body_{}.csv", std::process::id()));
+        let mut csv = "meta\n".repeat(5).into_bytes();
+        csv.extend_from_slice(b"Plant Id,Plant Name\n1,Alpha\n");
+        std::fs::write(&path, &csv).unwrap();
+        assert_eq!(read_csv_body(path.to_str().unwrap()).unwrap(), b"Plant Id,Plant Name\n1,Alpha");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_csv_body_malformed_line() {
+        // A line that is not UTF-8 stops the read with its number instead of silently dropping the rest of the file
+        let path = std::env::temp_dir().join(format!("efficiency_malformed_{}.csv", std::process::id()));
+        let mut csv = "meta\n".repeat(5).into_bytes();
+        csv.extend_from_slice(b"Plant Id,Plant Name\n1,Alpha\n2,Caf\xe9\n3,Gamma\n");
+        std::fs::write(&path, &csv).unwrap();
+        let error = read_csv_body(path.to_str().unwrap()).unwrap_err().to_string();
+        assert!(error.contains("line 8"), "{}", error);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,802 @@
+// cli.rs
+// This module defines the command-line options for the efficiency analysis.
+
+use std::error::Error;
+use std::num::NonZeroUsize;
+use std::thread;
+
+use clap::{Parser, Subcommand};
+
+use efficiency::aggregation::Aggregation;
+use efficiency::alerts::AlertThreshold;
+use efficiency::analysis::RankBy;
+use efficiency::averages::AverageWeighting;
+use efficiency::cache::DEFAULT_CACHE_DIR;
+use efficiency::cancel::CancellationToken;
+use efficiency::charts::{NonContiguous, PlotlyScript};
+use efficiency::cleaning::{ColumnProjection, LoadOptions};
+use efficiency::coverage::CoverageRule;
+use efficiency::download::DownloadOptions;
+use efficiency::export::OutputFormat;
+use efficiency::heatcontent::HeatContentPolicy;
+use efficiency::heatmap::{ColorScale, HeatmapMetric};
+use efficiency::impute::ImputeStrategy;
+use efficiency::join::JoinType;
+use efficiency::limits::MemoryLimit;
+use efficiency::merge::DuplicatePolicy;
+use efficiency::methodology::MethodologyOptions;
+use efficiency::palette::{ColorChoice, Palette, TerminalColors};
+use efficiency::parallel::Parallelism;
+use efficiency::partition::PartitionBy;
+use efficiency::recording::Recording;
+use efficiency::rounding::{RoundingMode, RoundingPolicy};
+use efficiency::statefilter::StateFilter;
+use efficiency::territories::TerritoryPolicy;
+use efficiency::timestamp::RunTimestamp;
+use efficiency::units::HeatRateUnit;
+use efficiency::years::YearOverrides;
+
+/// Compute fossil fuel efficiency change between 2019 and 2020 across U.S. states using EIA-923 data.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Runs the default 2019 vs 2020 analysis when no subcommand is given.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Tag for this run (e.g. "covid-sensitivity-v2"); added to every output file name
+    /// (efficiency_changes_<label>.csv), the CSV preamble, the audit log and the completion hook payload.
+    #[arg(long, global = true, value_parser = parse_label)]
+    pub label: Option<String>,
+
+    /// Record this RFC 3339 time (e.g. "2024-01-01T00:00:00Z") as the run's timestamp instead of the clock, in
+    /// the CSV preamble, the audit log, the results database and the hook payload, for reproducible artifacts.
+    #[arg(long, global = true)]
+    pub timestamp_override: Option<RunTimestamp>,
+
+    /// Named option profile to apply from the config file (e.g. "monthly").
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Config file holding the profiles.
+    #[arg(long, global = true, default_value = "efficiency.toml")]
+    pub config: String,
+
+    /// Labels for the grade bands, best first; states are split into equally sized bands
+    /// (e.g. "A,B,C,D,F" for five letter grades).
+    #[arg(long, global = true, value_delimiter = ',', default_value = "Q1,Q2,Q3,Q4")]
+    pub grade_labels: Vec<String>,
+
+    /// Panel mode: load one EIA-923 CSV per year (named after the year, e.g. 2018.csv) and
+    /// write per-state heat rates for every year instead of the two-year comparison.
+    #[arg(long, global = true, num_args = 1..)]
+    pub panel: Vec<String>,
+
+    /// Save each loaded year's aggregates in this directory and reuse them on later runs while the source
+    /// file is unchanged, so an interrupted multi-year run resumes after the last completed year.
+    #[arg(long, global = true)]
+    pub checkpoint_dir: Option<String>,
+
+    /// Directory of cleaned inputs keyed by file contents and loading options; a file already cleaned with the
+    /// same options is read from there instead of being cleaned again.
+    #[arg(long, global = true, default_value = DEFAULT_CACHE_DIR)]
+    pub cache_dir: String,
+
+    /// Clean every input again instead of reading the cache (the cache entries are rewritten).
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
+    /// PEM file of CA certificates to trust when downloading URL inputs (e.g. a corporate proxy's root
+    /// certificate). Proxies are taken from the HTTPS_PROXY, HTTP_PROXY and NO_PROXY variables.
+    #[arg(long, global = true)]
+    pub ca_cert: Option<String>,
+
+    /// Copy every file fetched for a URL input into this directory, with a manifest of URLs and checksums,
+    /// so the run can later be repeated offline with --replay.
+    #[arg(long, global = true, conflicts_with = "replay")]
+    pub record: Option<String>,
+
+    /// Serve URL inputs from a directory written by --record instead of the network; URLs it does not hold,
+    /// or whose file changed since, stop the run.
+    #[arg(long, global = true)]
+    pub replay: Option<String>,
+
+    /// Write the cleaned row-level records of every input (typed numbers, normalized state codes, fuel groups,
+    /// monthly values after imputation) to this CSV file, with an Input column naming the year or file.
+    #[arg(long, global = true)]
+    pub emit_cleaned: Option<String>,
+
+    /// With --emit-cleaned, write a Hive-style directory partitioned by state or fuel group instead of one file
+    /// (e.g. 'cleaned/State=TX/part-00000.csv'), so other tools can read only the partitions they need; the
+    /// --emit-cleaned path then names the directory.
+    #[arg(long, global = true, value_enum)]
+    pub partition_by: Option<PartitionBy>,
+
+    /// Rows per part file of a partitioned export; larger partitions are split into several parts.
+    #[arg(long, global = true, default_value = "100000")]
+    pub chunk_rows: NonZeroUsize,
+
+    /// Formats for the main result tables (e.g. "csv,json" or "csv,parquet,sqlite"); every format is written
+    /// from the same in-memory results. Optional reports are always written in their own format.
+    /// JSON output also writes 'methodology.json', the run's assumptions (fuel codes, fuel basis, row filters
+    /// and skip counts).
+    #[arg(long = "format", global = true, value_enum, value_delimiter = ',', default_value = "csv")]
+    pub formats: Vec<OutputFormat>,
+
+    /// Also POST every main result table as JSON ({"table": <name>, "rows": [...]}) to this URL, e.g. an
+    /// ingestion endpoint; uses the system curl and --ca-cert.
+    #[arg(long, global = true)]
+    pub post_url: Option<String>,
+
+    /// Also copy every output file of the run under this S3 prefix (s3://bucket/prefix) once it finishes,
+    /// keeping their relative paths; needs the 's3' feature and the AWS_* credential variables.
+    #[arg(long, global = true)]
+    pub upload_to: Option<String>,
+
+    /// Order of the top movers table and the main CSV: by change in heat rate (delta) or by change
+    /// weighted by compare-year generation (weighted).
+    #[arg(long, global = true, value_enum, default_value = "delta")]
+    pub rank_by: RankBy,
+
+    /// Heat rate unit for the printed tables and the efficiency, comparison, state summary and fuel trend
+    /// outputs (which also get a Units column). Other reports and thresholds stay in MMBtu/MWh.
+    #[arg(long, global = true, value_enum, default_value = "mmbtu-mwh")]
+    pub units: HeatRateUnit,
+
+    /// How the result tables round ties: half-up (away from zero, as in spreadsheets) or half-even. Values are
+    /// rounded on their decimal digits, once to each column's published precision, and the terminal,
+    /// Markdown and HTML views round that published value again, so every view and file agrees.
+    #[arg(long, global = true, value_enum, default_value = "half-up")]
+    pub rounding: RoundingMode,
+
+    /// Decimals of the result tables' decimal columns: "COLUMN=DIGITS" for one column (e.g.
+    /// "Delta_Efficiency=3"), or "DIGITS" for all of them. Columns default to 6 decimals (2 for percentiles).
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub precision: Vec<String>,
+
+    /// Cross-state means and medians printed under the top movers table and saved to 'state_averages.csv':
+    /// unweighted (every state counts once), weighted (by generation) or both, clearly labeled.
+    #[arg(long, global = true, value_enum, default_value = "both")]
+    pub averages: AverageWeighting,
+
+    /// How a state's plants are combined before the fuel / generation ratio: sum (the state's fleet heat rate),
+    /// trimmed (sum after dropping the --trim share of plants with the most extreme heat rates at each end) or
+    /// plant-median (the typical plant's heat rate).
+    #[arg(long, global = true, value_enum, default_value = "sum")]
+    pub aggregation: Aggregation,
+
+    /// Share of plants (0-0.5) dropped at each end of the heat rate range with --aggregation trimmed.
+    #[arg(long, global = true, default_value_t = 0.05)]
+    pub trim: f64,
+
+    /// Sum the state totals on all available cores (or on --threads of them).
+    #[arg(long, global = true)]
+    pub parallel: bool,
+
+    /// Sum the state totals on at most this many threads, to share a server politely; implies --parallel when
+    /// above 1.
+    #[arg(long, global = true)]
+    pub threads: Option<NonZeroUsize>,
+
+    /// Refuse to load inputs whose estimated memory footprint (about 4x the CSV size) exceeds this ceiling,
+    /// e.g. "512M" or "2G", instead of running the machine into swap.
+    #[arg(long, global = true)]
+    pub max_memory: Option<MemoryLimit>,
+
+    /// With --parallel, sum in fixed-size chunks merged in a fixed order, so results are bit-identical across
+    /// runs and machines (for audited outputs); slightly slower.
+    #[arg(long, global = true)]
+    pub deterministic: bool,
+
+    /// Treatment of DC, Puerto Rico and the other territories: their own rows (separate), one combined
+    /// "DC+TERR" row (merge) or left out (exclude). Applies to every loaded file.
+    #[arg(long, global = true, value_enum, default_value = "separate")]
+    pub territories: TerritoryPolicy,
+
+    /// How blank monthly values are filled: zero, carry-forward (repeat the previous month) or proportional
+    /// (share out the rest of the annual total). Filled values are listed in 'imputed_values.csv' and
+    /// counted per input in the audit log.
+    #[arg(long, global = true, value_enum, default_value = "zero")]
+    pub impute: ImputeStrategy,
+
+    /// Plant-months whose heat content (fuel MMBtu / physical quantity) is implausible for the reported fuel are
+    /// listed in 'heat_content_anomalies.csv' (flag), dropped (exclude) or given the fuel's typical heat
+    /// content (correct).
+    #[arg(long, global = true, value_enum, default_value = "flag")]
+    pub heat_content: HeatContentPolicy,
+
+    /// Plant rows found in more than one file of a merged input ('a.csv+b.csv') are added month by month
+    /// (sum), taken from the first or last file that has them (keep-first, keep-last), or stop the run (error).
+    #[arg(long, global = true, value_enum, default_value = "sum")]
+    pub duplicates: DuplicatePolicy,
+
+    /// Skip parsing the 36 monthly columns of each input when nothing in the run needs them: `stats`, `fuels`
+    /// and multi-year runs without monthly reports, --impute or a --heat-content other than flag. Loads faster;
+    /// heat content is then not checked.
+    #[arg(long, global = true)]
+    pub fast_load: bool,
+
+    /// Accept an input without valid data rows (empty or header only) and carry on with an empty result
+    /// instead of stopping with an error.
+    #[arg(long, global = true)]
+    pub allow_empty: bool,
+
+    /// States with fewer reporting plants than this in either year are left out of the results and listed in
+    /// 'insufficient_data.csv' instead.
+    #[arg(long, global = true, default_value_t = 0)]
+    pub min_plants: usize,
+
+    /// States with less net generation (MWh) than this in either year are left out of the results and listed
+    /// in 'insufficient_data.csv' instead.
+    #[arg(long, global = true, default_value_t = 0.0)]
+    pub min_generation: f64,
+
+    /// Only report these states (e.g. "TX,CA,NY"); applied to the state totals after aggregation, so ranks,
+    /// percentiles and averages are among these states.
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub states: Vec<String>,
+
+    /// Leave these states out of the reports (e.g. "AK,HI"); applied after aggregation like --states.
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub exclude_states: Vec<String>,
+
+    /// Add each state's nuclear and renewable generation (MWh) and fossil share of generation for both years
+    /// to the main result table, as context for the heat rate change.
+    #[arg(long, global = true)]
+    pub context_columns: bool,
+
+    /// Add each state's share of national fossil generation and of national fossil fuel consumption for both
+    /// years to the main result table, to weigh each mover by its size.
+    #[arg(long, global = true)]
+    pub national_shares: bool,
+
+    /// Add the variance and coefficient of variation of each state's twelve monthly heat rates for both years,
+    /// and the change in the coefficient of variation, to the main result table.
+    #[arg(long, global = true)]
+    pub monthly_variability: bool,
+
+    /// Drop each plant in turn (jackknife) to see how far it moves its state in the top movers table; adds a
+    /// Rank_Stability column (1 = no single plant moves the state) and writes 'ranking_stability.csv'.
+    #[arg(long, global = true)]
+    pub stability: bool,
+
+    /// Length of the top lists compared by the rank-biased overlap between the two years' state rankings.
+    #[arg(long, global = true, default_value_t = 10)]
+    pub rbo_depth: usize,
+
+    /// Drop plants whose state, primary fuel or prime movers differ between the two compared files (they are
+    /// always listed in 'plant_identity_changes.csv').
+    #[arg(long, global = true)]
+    pub exclude_inconsistent: bool,
+
+    /// Start the main CSV tables with '#'-prefixed lines giving the tool version, command, inputs and options.
+    #[arg(long, global = true)]
+    pub preamble: bool,
+
+    /// Never write the CSV preamble, even if the selected profile enables it (for strict CSV readers).
+    #[arg(long, global = true, conflicts_with = "preamble")]
+    pub no_preamble: bool,
+
+    /// CSV of reference heat rates (State plus Heat_Rate_2019 / Heat_Rate_2020, or Heat_Rate for the compare
+    /// year) to check the results against; differences are written to 'baseline_comparison.csv'.
+    #[arg(long, global = true)]
+    pub baseline: Option<String>,
+
+    /// CSV export of the EIA-860 generator schedule (3_1_Generator); when given, the capacity-weighted age of
+    /// each state's fossil fleet is written to 'fleet_age.csv' with its heat rate, and the correlation between
+    /// the two across states is printed.
+    #[arg(long, global = true)]
+    pub eia860_generators: Option<String>,
+
+    /// CSV export of the EIA-860 utility schedule (1___Utility); with --eia860-generators, each state's heat
+    /// rate is broken out by the ownership type of its plants' utilities (investor-owned, municipal, cooperative,
+    /// federal, ...) in 'ownership_efficiency.csv'.
+    #[arg(long, global = true)]
+    pub eia860_utilities: Option<String>,
+
+    /// With --eia860-generators, also weight each state's fossil plant heat rates by nameplate capacity rather
+    /// than generation (the fleet standing ready, as in reserve-margin analyses) and write both to
+    /// 'capacity_weighted_heat_rates.csv'.
+    #[arg(long, global = true)]
+    pub capacity_weighted: bool,
+
+    /// CSV export of EIA-923 Page 5 (Fuel Receipts and Costs) for the compare year; when given, the fuel each
+    /// state saved (or burned extra) through its efficiency change is priced at its delivered fossil fuel costs
+    /// and written to 'fuel_cost_avoided.csv'. May be an http(s) URL.
+    #[arg(long, global = true)]
+    pub fuel_prices: Option<String>,
+
+    /// How the EIA-860 schedules are joined onto the plants: left keeps plants without a match (as an unknown
+    /// owner, or in the plant counts of the fleet ages), inner drops them. Either way the match counts are printed
+    /// and the unmatched keys of both sides written to 'join_unmatched.csv'.
+    #[arg(long, global = true, value_enum, default_value = "left")]
+    pub join: JoinType,
+
+    /// CSV mapping AER fuel codes (optionally per prime mover) to your own fuel groups, with columns AER_Fuel,
+    /// Prime_Mover and Fuel_Group (see 'mappings/fuel_groups.csv' for the built-in one); each group's heat rate,
+    /// nationally and per state, is written to 'custom_fuel_groups.csv' and unmapped codes are reported.
+    #[arg(long, global = true)]
+    pub fuel_mapping: Option<String>,
+
+    /// Flag states whose change exceeds this threshold, absolute ("0.25" MMBtu/MWh) or relative ("5%").
+    /// Flagged states are printed and written to 'alerts.csv', and the program exits with status 3.
+    #[arg(long, global = true)]
+    pub alert_threshold: Option<AlertThreshold>,
+
+    /// Extra output column defined as NAME=EXPRESSION over the state's values, e.g.
+    /// "gap=heat_rate_2020 - national_median_2020"; appended as a column to the per-state table. Repeatable.
+    /// In compare mode the _2019/_2020 variables refer to the first and second file.
+    #[arg(long = "metric", global = true)]
+    pub metrics: Vec<String>,
+
+    /// What-if scenario FUEL=TARGET bringing every plant of a fuel group (coal, natural-gas, petroleum,
+    /// other-gas) down to a target heat rate: a value in MMBtu/MWh ("7.5"), a percentile of the national fleet
+    /// ("p25") or "best-quartile"; the fuel and CO2 saved per state in the compare year are written to
+    /// 'scenarios.csv'. Repeatable.
+    #[arg(long = "scenario", global = true)]
+    pub scenarios: Vec<String>,
+
+    /// Tera template rendered with the efficiency rows (`states`), cross-state averages (`averages`), states
+    /// left out for insufficient data (`insufficient`), the assumptions block (`methodology`), run metadata
+    /// (`run`) and `version`;
+    /// written next to it without the `.tera` suffix (e.g. report.md.tera → report.md). Repeatable.
+    #[arg(long = "template", global = true)]
+    pub templates: Vec<String>,
+
+    /// Shell command to run when the run finishes, with a JSON summary (command, inputs, state count,
+    /// outputs) on its stdin; e.g. `curl -d @- <webhook URL>` to notify a chat channel.
+    #[arg(long, global = true)]
+    pub on_complete: Option<String>,
+
+    /// URL to POST the same JSON summary to when the run finishes, e.g. a chat or CI webhook; uses the system
+    /// curl and --ca-cert. Sent after the --on-complete command when both are given.
+    #[arg(long, global = true)]
+    pub webhook_url: Option<String>,
+
+    /// SQLite database that accumulates runs: each two-input run adds a row to its `runs` table and one row
+    /// per state to `state_results` (created on first use). `query-db` reads it.
+    #[arg(long, global = true)]
+    pub results_db: Option<String>,
+
+    /// Also write state × fuel group matrices of heat rate and generation share for each year.
+    #[arg(long, global = true)]
+    pub fuel_pivot: bool,
+
+    /// Also write a state × month series of fuel, generation and heat rate for each loaded year.
+    #[arg(long, global = true)]
+    pub monthly_series: bool,
+
+    /// Also write the all-states monthly series of fuel, generation and heat rate as CSV and an HTML chart.
+    #[arg(long, global = true)]
+    pub national_monthly: bool,
+
+    /// Also write a summer (June–September) vs winter (December–March) heat rate report.
+    #[arg(long, global = true)]
+    pub seasonal: bool,
+
+    /// Also write each state's best and worst month by heat rate in every year, to surface events such as cold
+    /// snaps.
+    #[arg(long, global = true)]
+    pub extreme_months: bool,
+
+    /// Also compare each state's heat rate in its highest-generation month with its annual heat rate (the peak
+    /// penalty) in every year.
+    #[arg(long, global = true)]
+    pub peak_penalty: bool,
+
+    /// Also write each state's elasticity of heat rate with respect to generation (from monthly data).
+    #[arg(long, global = true)]
+    pub elasticity: bool,
+
+    /// Also write per-plant (generation, fuel) pairs by state and year, with a fitted line per state.
+    #[arg(long, global = true)]
+    pub scatter: bool,
+
+    /// Also split each state's change into additive per-plant contributions (rate and generation-mix effects)
+    /// and write the largest contributors per state to 'plant_contributions.csv'.
+    #[arg(long, global = true)]
+    pub plant_contributions: bool,
+
+    /// Also describe each top mover in a few sentences (how far its heat rate moved, the plants that drove it, heat
+    /// rates versus generation shifts, any marked change in fuel mix), print them and write 'top_movers.md'.
+    #[arg(long, global = true)]
+    pub narratives: bool,
+
+    /// Number of contributors listed per state with --plant-contributions.
+    #[arg(long, global = true, default_value_t = 5)]
+    pub contributors: usize,
+
+    /// Also split each state's compare-year plants into the existing fleet (generating in both years) and
+    /// new entrants, with the heat rate of each, to separate fleet turnover from operational change.
+    #[arg(long, global = true)]
+    pub fleet_split: bool,
+
+    /// Also write each state's compare-year heat rate against a counterfactual in which plants that stopped
+    /// generating kept running at base-year output and heat rate, to 'retirement_impact.csv'.
+    #[arg(long, global = true)]
+    pub retirements: bool,
+
+    /// Also recompute each state's change counting fossil fuels only, fossil fuels plus biomass, and all fuels,
+    /// and report how much the change ranking depends on the definition.
+    #[arg(long, global = true)]
+    pub fuel_sensitivity: bool,
+
+    /// Also test whether the changes cluster geographically (Moran's I over each state's nearest states by
+    /// great-circle distance between state centers) and write each state's neighbors to 'spatial_autocorrelation.csv'.
+    #[arg(long, global = true)]
+    pub spatial: bool,
+
+    /// Number of nearest states counted as neighbors with --spatial.
+    #[arg(long, global = true, default_value_t = 5)]
+    pub neighbors: usize,
+
+    /// Also compare each state's change with its bordering states' (and, by breadth-first search, with the
+    /// states 2 and 3 borders away) and write 'state_neighbors.csv'.
+    #[arg(long, global = true)]
+    pub adjacency: bool,
+
+    /// Also write histograms of plant heat rates by state, fossil fuel group and year.
+    #[arg(long, global = true)]
+    pub histogram: bool,
+
+    /// Histogram bin edges in MMBtu/MWh, ascending; plants outside them fall in open-ended end bins.
+    #[arg(long, global = true, value_delimiter = ',', default_value = "6,7,8,9,10,11,12,13,14,16,20")]
+    pub histogram_bins: Vec<f64>,
+
+    /// Also classify fossil plants into heat rate bands and write the plant count and generation share of each
+    /// band per state and input to 'heat_rate_bands.csv', printing the national bands.
+    #[arg(long, global = true)]
+    pub bands: bool,
+
+    /// Heat rate band edges in MMBtu/MWh, ascending; the first and last bands are open-ended.
+    #[arg(long, global = true, value_delimiter = ',', default_value = "7,9,11")]
+    pub band_edges: Vec<f64>,
+
+    /// Also balance fuel energy in against electricity out (3.412 MMBtu/MWh) for every state and fossil plant,
+    /// write 'energy_balance.csv' (every state, and the plants that fail) and print the states and plants whose
+    /// implied thermal efficiency is above 65% (or 100%), below 2%, or that generate without fuel.
+    #[arg(long, global = true)]
+    pub energy_balance: bool,
+
+    /// Also write fuel group → net generation flows per state and year as d3-sankey/Plotly JSON,
+    /// with a JSON Schema describing the file.
+    #[arg(long, global = true)]
+    pub sankey: bool,
+
+    /// Also write an interactive Plotly HTML chart (state bars; a year slider in panel mode).
+    /// The page loads plotly.js from its CDN unless --inline-js is given.
+    #[arg(long, global = true)]
+    pub html: bool,
+
+    /// Embed this plotly.js bundle (e.g. a downloaded plotly-2.35.2.min.js) into the HTML charts instead of
+    /// loading it from the CDN, so they render offline.
+    #[arg(long, global = true, value_name = "PATH")]
+    pub inline_js: Option<String>,
+
+    /// Placement of Alaska and Hawaii on the --html change map: inset, omit or true-position.
+    #[arg(long, global = true, value_enum, default_value = "inset")]
+    pub map_noncontiguous: NonContiguous,
+
+    /// Mark DC on the --html change map with a labeled circle, as its area is too small to see.
+    #[arg(long, global = true)]
+    pub dc_callout: bool,
+
+    /// Also write every computed metric as a tidy long table (state, year, metric, value).
+    #[arg(long, global = true)]
+    pub tidy: bool,
+
+    /// In panel mode, also run change-point detection on each state's annual heat rate series.
+    #[arg(long, global = true)]
+    pub change_points: bool,
+
+    /// In panel mode, also forecast each state's next-year heat rate (linear trend and exponential smoothing).
+    #[arg(long, global = true)]
+    pub forecast: bool,
+
+    /// In panel mode, also test whether state heat rates are converging: the cross-state dispersion per year
+    /// ('sigma_convergence.csv') and a regression of each state's heat rate growth on its initial level
+    /// ('convergence.csv').
+    #[arg(long, global = true)]
+    pub convergence: bool,
+
+    /// In panel mode, also look for fossil generation moving across state lines: bordering states whose fossil
+    /// generation fell and rose between consecutive years, with the size of the possible shift, in
+    /// 'generation_shifts.csv'.
+    #[arg(long, global = true)]
+    pub migration: bool,
+
+    /// In panel mode, also render a state × year heat map as 'heatmap.svg'.
+    #[arg(long, global = true)]
+    pub heatmap: bool,
+
+    /// Value shaded in the heat map.
+    #[arg(long, global = true, value_enum, default_value = "heat-rate")]
+    pub heatmap_metric: HeatmapMetric,
+
+    /// Color scale used for the heat map.
+    #[arg(long, global = true, value_enum, default_value = "viridis")]
+    pub color_scale: ColorScale,
+
+    /// Colors marking improvements and deteriorations in the terminal tables and the HTML charts: standard
+    /// (green/red, blue/red bars) or colorblind (Okabe-Ito blue/vermillion, also for the chart series).
+    #[arg(long, global = true, value_enum, default_value = "standard")]
+    pub palette: Palette,
+
+    /// Color the terminal tables: auto (when writing to a terminal and NO_COLOR is not set), always or never.
+    #[arg(long, global = true, value_enum, default_value = "auto")]
+    pub color: ColorChoice,
+
+    /// Year of an input whose YEAR column is missing or wrong: "FILE=YEAR" for one input (matched by path or
+    /// file name), or "YEAR" for every input. Otherwise each input's year is read from its YEAR column.
+    #[arg(long, global = true)]
+    pub assume_year: Vec<String>,
+
+    /// In panel mode, also write a wide table with one heat rate column per year.
+    #[arg(long, global = true)]
+    pub wide: bool,
+
+    /// Set by the Ctrl-C handler; loading and saving stop at their next check.
+    #[arg(skip)]
+    pub cancel: CancellationToken,
+}
+
+impl Cli {
+    /// Options applied to every loaded file.
+    pub fn load_options(&self) -> LoadOptions {
+        LoadOptions {
+            territories: self.territories,
+            impute: self.impute,
+            heat_content: self.heat_content,
+            duplicates: self.duplicates,
+            allow_empty: self.allow_empty,
+            columns: self.column_projection(),
+        }
+    }
+
+    /// Columns to parse: the annual ones only with --fast-load when no enabled output needs monthly values.
+    /// The two-input analyses always need them for the marginal heat rates.
+    pub fn column_projection(&self) -> ColumnProjection {
+        let annual_command = match &self.command {
+            Some(
+                Command::Stats { .. }
+                | Command::Fuels { .. }
+                | Command::State { .. }
+                | Command::FindPlant { .. }
+                | Command::Run { .. },
+            ) => true,
+            None => !self.panel.is_empty(),
+            _ => false,
+        };
+        let monthly_reports = self.monthly_series || self.national_monthly || self.extreme_months || self.peak_penalty;
+        let changes_totals = self.impute != ImputeStrategy::Zero || self.heat_content != HeatContentPolicy::Flag;
+
+        if self.fast_load && annual_command && !monthly_reports && !changes_totals {
+            ColumnProjection::Annual
+        } else {
+            ColumnProjection::All
+        }
+    }
+
+    /// Where the HTML charts get plotly.js from: the `--inline-js` bundle or the CDN.
+    pub fn plotly_script(&self) -> Result<PlotlyScript, Box<dyn Error>> {
+        PlotlyScript::load(self.inline_js.as_deref())
+    }
+
+    /// Colors of the terminal tables for `--palette` and `--color`.
+    pub fn terminal_colors(&self) -> TerminalColors {
+        TerminalColors::new(self.palette, self.color)
+    }
+
+    /// Threads and summation order for the state totals.
+    pub fn parallelism(&self) -> Parallelism {
+        let threads = match self.threads {
+            Some(threads) => threads.get(),
+            None if self.parallel => thread::available_parallelism().map_or(1, |n| n.get()),
+            None => 1,
+        };
+        Parallelism { threads, deterministic: self.deterministic }
+    }
+
+    /// `--rounding` mode and `--precision` overrides for the result tables.
+    pub fn rounding_policy(&self) -> Result<RoundingPolicy, Box<dyn Error>> {
+        RoundingPolicy::parse(self.rounding, &self.precision)
+    }
+
+    /// `--assume-year` overrides.
+    pub fn year_overrides(&self) -> Result<YearOverrides, Box<dyn Error>> {
+        YearOverrides::parse(&self.assume_year)
+    }
+
+    /// Where URL inputs are downloaded.
+    pub fn download_options(&self) -> DownloadOptions {
+        let recording = match (&self.record, &self.replay) {
+            (_, Some(dir)) => Recording::Replay(dir.clone()),
+            (Some(dir), None) => Recording::Record(dir.clone()),
+            (None, None) => Recording::Off,
+        };
+        DownloadOptions { dir: self.cache_dir.clone(), reuse: !self.no_cache, ca_cert: self.ca_cert.clone(), recording }
+    }
+
+    /// Options recorded in the methodology block.
+    pub fn methodology_options(&self) -> MethodologyOptions {
+        MethodologyOptions {
+            load: self.load_options(),
+            coverage: self.coverage_rule(),
+            exclude_inconsistent: self.exclude_inconsistent,
+            unit: self.units,
+            aggregation: self.aggregation,
+            trim: self.trim,
+        }
+    }
+
+    /// Minimum data a state needs to be reported.
+    pub fn coverage_rule(&self) -> CoverageRule {
+        CoverageRule { min_plants: self.min_plants, min_gen: self.min_generation }
+    }
+
+    /// States kept in the reports.
+    pub fn state_filter(&self) -> StateFilter {
+        StateFilter::new(&self.states, &self.exclude_states)
+    }
+
+    /// Output file name with the run label inserted before the extension
+    /// (`efficiency_changes.csv` → `efficiency_changes_<label>.csv`).
+    pub fn output_path(&self, name: &str) -> String {
+        let Some(label) = &self.label else {
+            return name.to_string();
+        };
+
+        let dir_end = name.rfind('/').map_or(0, |i| i + 1);
+        match name[dir_end..].find('.') {
+            Some(dot) => format!("{}_{}{}", &name[..dir_end + dot], label, &name[dir_end + dot..]),
+            None => format!("{}_{}", name, label),
+        }
+    }
+}
+
+/// Accepts labels that are safe inside file names.
+pub fn parse_label(label: &str) -> Result<String, String> {
+    if !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c)) {
+        Ok(label.to_string())
+    } else {
+        Err("labels may only contain letters, digits, '-', '_' and '.'".to_string())
+    }
+}
+
+/// Subcommands other than the default two-year analysis.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Compare any two EIA-923 CSVs (e.g. an early release against the final file, or two
+    /// regional extracts) and write the same delta report to 'comparison.csv', along with every
+    /// other report of the default analysis. Every input file argument may also be an http(s) URL
+    /// of a CSV or of a zip holding one; it is downloaded into the cache directory first.
+    Compare {
+        /// Base file.
+        file_a: String,
+
+        /// File compared against the base.
+        file_b: String,
+    },
+
+    /// Summarize a single EIA-923 CSV per state (totals, heat rate, plant count, fuel mix)
+    /// and write it to 'state_summary.csv'.
+    Stats {
+        /// EIA-923 CSV to summarize.
+        file: String,
+    },
+
+    /// Profile every column of a raw EIA-923 CSV (min, max, mean and blank share of numeric columns, distinct
+    /// values of text columns) and write it to 'column_profile.csv', to catch schema surprises before a run.
+    Inspect {
+        /// EIA-923 CSV to inspect.
+        file: String,
+    },
+
+    /// Write national and per-state heat rates by fuel group for every loaded year to 'fuel_trends.csv'.
+    Fuels {
+        /// One EIA-923 CSV per year, named after the year (defaults to the 2019 and 2020 files).
+        files: Vec<String>,
+    },
+
+    /// Print a dashboard for one state: both years' totals, heat rate, ranks and fuel mix, the plants that
+    /// moved its heat rate most and its bordering states' changes.
+    State {
+        /// State code, e.g. "TX".
+        state: String,
+
+        /// Base and compare EIA-923 CSVs (defaults to the 2019 and 2020 files).
+        files: Vec<String>,
+    },
+
+    /// Look plants up by code or name (typos and partial names allowed, e.g. "Martin Lake" or "martn") and
+    /// print each match's state and its fuel, generation and heat rate in every input.
+    FindPlant {
+        /// Plant code or (part of a) plant name.
+        query: String,
+
+        /// EIA-923 CSVs to search, one per year (defaults to the 2019 and 2020 files).
+        files: Vec<String>,
+    },
+
+    /// Run a read-only SQL query against the results database (--results-db, default 'results.sqlite') and
+    /// print the rows as a table, e.g. "SELECT state, delta FROM state_results WHERE run_id = 3".
+    QueryDb {
+        /// SQL statement; statements that would modify the database are refused.
+        sql: String,
+    },
+
+    /// Chart how one state's published change moved across the runs recorded in the results database
+    /// (--results-db, default 'results.sqlite'), labeling each run with what changed since the previous one:
+    /// tool version, input files, input contents (from the audit log's digests) or options. Also writes
+    /// 'history_<STATE>.csv'.
+    History {
+        /// State code, e.g. "TX".
+        state: String,
+    },
+
+    /// Convert a raw EIA-923 export (CSV or XLSX) into a Parquet file of cleaned records, with the columns of
+    /// --emit-cleaned and the loading policies (--territories, --impute, --heat-content) applied. The input is
+    /// streamed in row groups, so files of any size convert in bounded memory.
+    Convert {
+        /// EIA-923 CSV or XLSX file.
+        input: String,
+
+        /// Parquet file to write.
+        output: String,
+    },
+
+    /// Time the loading and aggregation stages on one input (reading, cleaning, the loading policies, a cache
+    /// entry write and read, state and plant totals), printing rows/sec and MB/sec per stage and the speedup of
+    /// each fast path (--fast-load, the cache, --parallel) on this machine. Also writes 'bench.csv'.
+    Bench {
+        /// EIA-923 CSV or XLSX file.
+        file: String,
+
+        /// Runs per stage; the fastest is reported.
+        #[arg(long, default_value_t = 3)]
+        runs: usize,
+    },
+
+    /// Write the comparison as a static website for GitHub Pages or any web server: 'index.html' with the
+    /// national summary and the state table, a page per state with its dashboard, the bar chart and map, and the
+    /// state and plant results as CSV downloads.
+    Site {
+        /// Folder the site is written to (created if missing).
+        #[arg(long, default_value = "docs")]
+        out: String,
+
+        /// Base and compare EIA-923 CSVs (defaults to the 2019 and 2020 files).
+        files: Vec<String>,
+    },
+
+    /// Run the panel analysis for a range of years (e.g. "2018..=2022", "2018..2021" or "2019,2020"),
+    /// reading '<year>.csv' from the data directory and writing every requested report. A year missing there
+    /// is downloaded from EIA (its archive is cached and its Generation and Fuel workbook read).
+    Run {
+        /// Years to analyze.
+        #[arg(long)]
+        years: String,
+
+        /// Directory holding one EIA-923 CSV per year.
+        #[arg(long, default_value = "../data_csv_files")]
+        data_dir: String,
+
+        /// Fail on a year missing from the data directory instead of downloading its EIA-923 archive.
+        #[arg(long)]
+        no_download: bool,
+    },
+
+    /// Check EIA for newer EIA-923 releases than the local data: each year's archive on eia.gov is asked for its
+    /// release date and size, and compared with the archive downloaded by an earlier run (or, without one, with
+    /// '<year>.csv' in the data directory). Each year is reported as current, revised, new (released but not
+    /// held locally) or not published. Also writes 'updates.csv'.
+    CheckUpdates {
+        /// Years to check (e.g. "2018..=2022"); defaults to the years with a '<year>.csv' in the data directory.
+        #[arg(long)]
+        years: Option<String>,
+
+        /// Directory holding one EIA-923 CSV per year.
+        #[arg(long, default_value = "../data_csv_files")]
+        data_dir: String,
+    },
+}
@@ -0,0 +1,551 @@
+// config.rs
+// This module reads named option profiles from the config file and applies the selected one to the command line.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::num::NonZeroUsize;
+
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use serde::Deserialize;
+
+use crate::cli::{parse_label, Cli};
+
+/// Config file contents: named profiles, e.g. `[profiles.monthly]`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// A bundle of options. Report switches in a profile can only turn reports on; valued options apply
+/// only when they were not given on the command line. Every global option but `--profile` and `--config` has a
+/// field here, named like the option; a test fails when a new option is added without one.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Profile {
+    pub label: Option<String>,
+    pub timestamp_override: Option<String>,
+    pub grade_labels: Option<Vec<String>>,
+    pub panel: Option<Vec<String>>,
+    pub checkpoint_dir: Option<String>,
+    pub cache_dir: Option<String>,
+    pub ca_cert: Option<String>,
+    pub record: Option<String>,
+    pub replay: Option<String>,
+    pub emit_cleaned: Option<String>,
+    pub post_url: Option<String>,
+    pub upload_to: Option<String>,
+    pub baseline: Option<String>,
+    pub fuel_prices: Option<String>,
+    pub alert_threshold: Option<String>,
+    pub on_complete: Option<String>,
+    pub webhook_url: Option<String>,
+    pub inline_js: Option<String>,
+    pub results_db: Option<String>,
+    pub partition_by: Option<String>,
+    pub chunk_rows: Option<NonZeroUsize>,
+    pub eia860_generators: Option<String>,
+    pub eia860_utilities: Option<String>,
+    pub join: Option<String>,
+    pub fuel_mapping: Option<String>,
+    pub max_memory: Option<String>,
+    pub heatmap_metric: Option<String>,
+    pub color_scale: Option<String>,
+    pub map_noncontiguous: Option<String>,
+    pub palette: Option<String>,
+    pub color: Option<String>,
+    pub rank_by: Option<String>,
+    pub units: Option<String>,
+    pub rounding: Option<String>,
+    pub precision: Option<Vec<String>>,
+    pub averages: Option<String>,
+    pub aggregation: Option<String>,
+    pub territories: Option<String>,
+    pub impute: Option<String>,
+    pub heat_content: Option<String>,
+    pub duplicates: Option<String>,
+    pub formats: Option<Vec<String>>,
+    pub histogram_bins: Option<Vec<f64>>,
+    pub band_edges: Option<Vec<f64>>,
+    pub rbo_depth: Option<usize>,
+    pub min_plants: Option<usize>,
+    pub min_generation: Option<f64>,
+    pub states: Option<Vec<String>>,
+    pub exclude_states: Option<Vec<String>>,
+    pub neighbors: Option<usize>,
+    pub contributors: Option<usize>,
+    pub threads: Option<NonZeroUsize>,
+    pub trim: Option<f64>,
+    pub assume_year: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub metrics: Vec<String>,
+    #[serde(default)]
+    pub scenarios: Vec<String>,
+    #[serde(default)]
+    pub templates: Vec<String>,
+
+    #[serde(default)]
+    pub no_cache: bool,
+    #[serde(default)]
+    pub fast_load: bool,
+    #[serde(default)]
+    pub allow_empty: bool,
+    #[serde(default)]
+    pub context_columns: bool,
+    #[serde(default)]
+    pub national_shares: bool,
+    #[serde(default)]
+    pub monthly_variability: bool,
+    #[serde(default)]
+    pub exclude_inconsistent: bool,
+    #[serde(default)]
+    pub stability: bool,
+    #[serde(default)]
+    pub preamble: bool,
+    #[serde(default)]
+    pub no_preamble: bool,
+    #[serde(default)]
+    pub fuel_pivot: bool,
+    #[serde(default)]
+    pub monthly_series: bool,
+    #[serde(default)]
+    pub national_monthly: bool,
+    #[serde(default)]
+    pub seasonal: bool,
+    #[serde(default)]
+    pub extreme_months: bool,
+    #[serde(default)]
+    pub peak_penalty: bool,
+    #[serde(default)]
+    pub elasticity: bool,
+    #[serde(default)]
+    pub scatter: bool,
+    #[serde(default)]
+    pub plant_contributions: bool,
+    #[serde(default)]
+    pub narratives: bool,
+    #[serde(default)]
+    pub fleet_split: bool,
+    #[serde(default)]
+    pub capacity_weighted: bool,
+    #[serde(default)]
+    pub retirements: bool,
+    #[serde(default)]
+    pub fuel_sensitivity: bool,
+    #[serde(default)]
+    pub spatial: bool,
+    #[serde(default)]
+    pub adjacency: bool,
+    #[serde(default)]
+    pub histogram: bool,
+    #[serde(default)]
+    pub bands: bool,
+    #[serde(default)]
+    pub energy_balance: bool,
+    #[serde(default)]
+    pub sankey: bool,
+    #[serde(default)]
+    pub html: bool,
+    #[serde(default)]
+    pub dc_callout: bool,
+    #[serde(default)]
+    pub tidy: bool,
+    #[serde(default)]
+    pub change_points: bool,
+    #[serde(default)]
+    pub forecast: bool,
+    #[serde(default)]
+    pub convergence: bool,
+    #[serde(default)]
+    pub migration: bool,
+    #[serde(default)]
+    pub heatmap: bool,
+    #[serde(default)]
+    pub wide: bool,
+    #[serde(default)]
+    pub parallel: bool,
+    #[serde(default)]
+    pub deterministic: bool,
+}
+
+/// Reads and parses a config file.
+pub fn load_config(path: &str) -> Result<Config, Box<dyn Error>> {
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read config file '{}': {}", path, e))?;
+    toml::from_str(&text).map_err(|e| format!("invalid config file '{}': {}", path, e).into())
+}
+
+/// Whether an option was left at its default (not given on the command line).
+fn is_default(matches: &ArgMatches, id: &str) -> bool {
+    !matches!(matches.value_source(id), Some(ValueSource::CommandLine | ValueSource::EnvVariable))
+}
+
+/// Applies the profile named `--profile` from `--config` to the parsed options.
+pub fn apply_profile(cli: &mut Cli, matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let Some(name) = cli.profile.clone() else {
+        return Ok(());
+    };
+
+    let mut config = load_config(&cli.config)?;
+    let Some(profile) = config.profiles.remove(&name) else {
+        let known: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+        return Err(format!("no profile '{}' in '{}' (available: {})", name, cli.config, known.join(", ")).into());
+    };
+
+    if cli.label.is_none()
+        && let Some(label) = profile.label
+    {
+        cli.label = Some(parse_label(&label).map_err(|e| format!("profile '{}': {}", name, e))?);
+    }
+    if cli.timestamp_override.is_none()
+        && let Some(timestamp) = profile.timestamp_override
+    {
+        cli.timestamp_override = Some(timestamp.parse().map_err(|e| format!("profile '{}': {}", name, e))?);
+    }
+    if let Some(labels) = profile.grade_labels
+        && is_default(matches, "grade_labels")
+    {
+        cli.grade_labels = labels;
+    }
+    if let Some(panel) = profile.panel
+        && cli.panel.is_empty()
+    {
+        cli.panel = panel;
+    }
+    if cli.checkpoint_dir.is_none() {
+        cli.checkpoint_dir = profile.checkpoint_dir;
+    }
+    if let Some(cache_dir) = profile.cache_dir
+        && is_default(matches, "cache_dir")
+    {
+        cli.cache_dir = cache_dir;
+    }
+    if cli.ca_cert.is_none() {
+        cli.ca_cert = profile.ca_cert;
+    }
+    if cli.record.is_none() && cli.replay.is_none() {
+        cli.record = profile.record;
+        cli.replay = profile.replay;
+        if cli.record.is_some() && cli.replay.is_some() {
+            return Err(format!("profile '{}' sets both record and replay", name).into());
+        }
+    }
+    if cli.emit_cleaned.is_none() {
+        cli.emit_cleaned = profile.emit_cleaned;
+    }
+    if cli.post_url.is_none() {
+        cli.post_url = profile.post_url;
+    }
+    if cli.upload_to.is_none() {
+        cli.upload_to = profile.upload_to;
+    }
+    if cli.baseline.is_none() {
+        cli.baseline = profile.baseline;
+    }
+    if cli.fuel_prices.is_none() {
+        cli.fuel_prices = profile.fuel_prices;
+    }
+    if cli.alert_threshold.is_none()
+        && let Some(threshold) = profile.alert_threshold
+    {
+        cli.alert_threshold = Some(threshold.parse().map_err(|e| format!("profile '{}': {}", name, e))?);
+    }
+    if cli.on_complete.is_none() {
+        cli.on_complete = profile.on_complete;
+    }
+    if cli.webhook_url.is_none() {
+        cli.webhook_url = profile.webhook_url;
+    }
+    if cli.inline_js.is_none() {
+        cli.inline_js = profile.inline_js;
+    }
+    if cli.results_db.is_none() {
+        cli.results_db = profile.results_db;
+    }
+    if cli.eia860_generators.is_none() {
+        cli.eia860_generators = profile.eia860_generators;
+    }
+    if cli.eia860_utilities.is_none() {
+        cli.eia860_utilities = profile.eia860_utilities;
+    }
+    if cli.fuel_mapping.is_none() {
+        cli.fuel_mapping = profile.fuel_mapping;
+    }
+    if cli.max_memory.is_none()
+        && let Some(limit) = profile.max_memory
+    {
+        cli.max_memory = Some(limit.parse().map_err(|e| format!("profile '{}': {}", name, e))?);
+    }
+    if cli.threads.is_none() {
+        cli.threads = profile.threads;
+    }
+    if cli.partition_by.is_none()
+        && let Some(by) = profile.partition_by
+    {
+        cli.partition_by = Some(ValueEnum::from_str(&by, true).map_err(|e| format!("profile '{}': {}", name, e))?);
+    }
+    if let Some(chunk_rows) = profile.chunk_rows
+        && is_default(matches, "chunk_rows")
+    {
+        cli.chunk_rows = chunk_rows;
+    }
+    if let Some(metric) = profile.heatmap_metric
+        && is_default(matches, "heatmap_metric")
+    {
+        cli.heatmap_metric = ValueEnum::from_str(&metric, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(scale) = profile.color_scale
+        && is_default(matches, "color_scale")
+    {
+        cli.color_scale = ValueEnum::from_str(&scale, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(placement) = profile.map_noncontiguous
+        && is_default(matches, "map_noncontiguous")
+    {
+        cli.map_noncontiguous =
+            ValueEnum::from_str(&placement, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(join) = profile.join
+        && is_default(matches, "join")
+    {
+        cli.join = ValueEnum::from_str(&join, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(palette) = profile.palette
+        && is_default(matches, "palette")
+    {
+        cli.palette = ValueEnum::from_str(&palette, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(color) = profile.color
+        && is_default(matches, "color")
+    {
+        cli.color = ValueEnum::from_str(&color, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(rank_by) = profile.rank_by
+        && is_default(matches, "rank_by")
+    {
+        cli.rank_by = ValueEnum::from_str(&rank_by, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(units) = profile.units
+        && is_default(matches, "units")
+    {
+        cli.units = ValueEnum::from_str(&units, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(rounding) = profile.rounding
+        && is_default(matches, "rounding")
+    {
+        cli.rounding = ValueEnum::from_str(&rounding, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(precision) = profile.precision
+        && is_default(matches, "precision")
+    {
+        cli.precision = precision;
+    }
+    if let Some(averages) = profile.averages
+        && is_default(matches, "averages")
+    {
+        cli.averages = ValueEnum::from_str(&averages, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(aggregation) = profile.aggregation
+        && is_default(matches, "aggregation")
+    {
+        cli.aggregation = ValueEnum::from_str(&aggregation, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(territories) = profile.territories
+        && is_default(matches, "territories")
+    {
+        cli.territories = ValueEnum::from_str(&territories, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(impute) = profile.impute
+        && is_default(matches, "impute")
+    {
+        cli.impute = ValueEnum::from_str(&impute, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(heat_content) = profile.heat_content
+        && is_default(matches, "heat_content")
+    {
+        cli.heat_content = ValueEnum::from_str(&heat_content, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(duplicates) = profile.duplicates
+        && is_default(matches, "duplicates")
+    {
+        cli.duplicates = ValueEnum::from_str(&duplicates, true).map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+
+    if let Some(formats) = profile.formats
+        && is_default(matches, "formats")
+    {
+        cli.formats = formats
+            .iter()
+            .map(|f| ValueEnum::from_str(f, true))
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("profile '{}': {}", name, e))?;
+    }
+    if let Some(rbo_depth) = profile.rbo_depth
+        && is_default(matches, "rbo_depth")
+    {
+        cli.rbo_depth = rbo_depth;
+    }
+    if let Some(min_plants) = profile.min_plants
+        && is_default(matches, "min_plants")
+    {
+        cli.min_plants = min_plants;
+    }
+    if let Some(min_generation) = profile.min_generation
+        && is_default(matches, "min_generation")
+    {
+        cli.min_generation = min_generation;
+    }
+    if let Some(states) = profile.states
+        && is_default(matches, "states")
+    {
+        cli.states = states;
+    }
+    if let Some(exclude_states) = profile.exclude_states
+        && is_default(matches, "exclude_states")
+    {
+        cli.exclude_states = exclude_states;
+    }
+    if let Some(neighbors) = profile.neighbors
+        && is_default(matches, "neighbors")
+    {
+        cli.neighbors = neighbors;
+    }
+    if let Some(contributors) = profile.contributors
+        && is_default(matches, "contributors")
+    {
+        cli.contributors = contributors;
+    }
+    if let Some(trim) = profile.trim
+        && is_default(matches, "trim")
+    {
+        cli.trim = trim;
+    }
+    if let Some(assume_year) = profile.assume_year
+        && cli.assume_year.is_empty()
+    {
+        cli.assume_year = assume_year;
+    }
+    if let Some(edges) = profile.histogram_bins
+        && is_default(matches, "histogram_bins")
+    {
+        cli.histogram_bins = edges;
+    }
+    if let Some(edges) = profile.band_edges
+        && is_default(matches, "band_edges")
+    {
+        cli.band_edges = edges;
+    }
+    cli.metrics.extend(profile.metrics);
+    cli.scenarios.extend(profile.scenarios);
+    cli.templates.extend(profile.templates);
+    cli.no_cache |= profile.no_cache;
+    cli.fast_load |= profile.fast_load;
+    cli.allow_empty |= profile.allow_empty;
+    cli.context_columns |= profile.context_columns;
+    cli.national_shares |= profile.national_shares;
+    cli.monthly_variability |= profile.monthly_variability;
+    cli.exclude_inconsistent |= profile.exclude_inconsistent;
+    cli.stability |= profile.stability;
+    cli.no_preamble |= profile.no_preamble && !cli.preamble;
+    cli.preamble |= profile.preamble && !cli.no_preamble;
+    cli.fuel_pivot |= profile.fuel_pivot;
+    cli.monthly_series |= profile.monthly_series;
+    cli.national_monthly |= profile.national_monthly;
+    cli.seasonal |= profile.seasonal;
+    cli.extreme_months |= profile.extreme_months;
+    cli.peak_penalty |= profile.peak_penalty;
+    cli.elasticity |= profile.elasticity;
+    cli.scatter |= profile.scatter;
+    cli.plant_contributions |= profile.plant_contributions;
+    cli.narratives |= profile.narratives;
+    cli.fleet_split |= profile.fleet_split;
+    cli.capacity_weighted |= profile.capacity_weighted;
+    cli.retirements |= profile.retirements;
+    cli.fuel_sensitivity |= profile.fuel_sensitivity;
+    cli.spatial |= profile.spatial;
+    cli.adjacency |= profile.adjacency;
+    cli.histogram |= profile.histogram;
+    cli.bands |= profile.bands;
+    cli.energy_balance |= profile.energy_balance;
+    cli.sankey |= profile.sankey;
+    cli.html |= profile.html;
+    cli.dc_callout |= profile.dc_callout;
+    cli.tidy |= profile.tidy;
+    cli.change_points |= profile.change_points;
+    cli.forecast |= profile.forecast;
+    cli.convergence |= profile.convergence;
+    cli.migration |= profile.migration;
+    cli.heatmap |= profile.heatmap;
+    cli.wide |= profile.wide;
+    cli.parallel |= profile.parallel;
+    cli.deterministic |= profile.deterministic;
+
+    println!("Using profile '{}' from '{}'", name, cli.config);
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::{CommandFactory, FromArgMatches};
+    use efficiency::units::HeatRateUnit;
+
+    /// Parses `args` and applies the profile `test` from a config file holding `profile`.
+    fn profiled(profile: &str, args: &[&str]) -> Result<Cli, Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!("efficiency_config_{}.toml", std::process::id()));
+        fs::write(&path, format!("[profiles.test]\n{}", profile))?;
+        let mut argv = vec!["efficiency", "--config", path.to_str().unwrap(), "--profile", "test"];
+        argv.extend(args);
+        let matches = Cli::command().try_get_matches_from(argv)?;
+        let mut cli = Cli::from_arg_matches(&matches)?;
+        let applied = apply_profile(&mut cli, &matches);
+        fs::remove_file(&path)?;
+        applied.map(|_| cli)
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let profile = "units = \"btu-kwh\"\nmin-plants = 3\nscatter = true\npreamble = true\nmetrics = [\"x=delta\"]\n";
+        let cli = profiled(profile, &[]).unwrap();
+        assert_eq!((cli.units, cli.min_plants, cli.scatter, cli.preamble), (HeatRateUnit::BtuPerKwh, 3, true, true));
+        assert_eq!(cli.metrics, ["x=delta"]);
+
+        // The command line wins over valued options, and --no-preamble over the profile's switch
+        let cli = profiled(profile, &["--min-plants", "5", "--metric", "y=abs_delta", "--no-preamble"]).unwrap();
+        assert_eq!((cli.min_plants, cli.preamble), (5, false));
+        assert_eq!(cli.metrics, ["y=abs_delta", "x=delta"]);
+
+        assert!(profiled("units = \"furlongs\"\n", &[]).unwrap_err().to_string().contains("profile 'test'"));
+        assert!(profiled("unknown-option = 1\n", &[]).unwrap_err().to_string().contains("invalid config file"));
+    }
+
+    #[test]
+    fn test_profile_covers_every_option() {
+        // Selecting the profile is all a profile cannot do
+        let not_profiled = ["help", "version", "profile", "config"];
+        let missing: Vec<String> = Cli::command()
+            .get_arguments()
+            .map(|arg| arg.get_id().as_str())
+            .filter(|id| !not_profiled.contains(id))
+            .map(|id| id.replace('_', "-"))
+            .filter(|key| {
+                let error = toml::from_str::<Profile>(&format!("{} = 0", key)).err();
+                error.is_some_and(|e| e.to_string().contains("unknown field"))
+            })
+            .collect();
+        assert!(missing.is_empty(), "options without a profile field: {}", missing.join(", "));
+    }
+
+    #[test]
+    fn test_profile_record_and_label() {
+        let cli = profiled("label = \"nightly\"\nno-preamble = true\npreamble = true\n", &[]).unwrap();
+        assert_eq!((cli.label.as_deref(), cli.preamble), (Some("nightly"), false));
+        assert!(profiled("label = \"a/b\"\n", &[]).is_err());
+        assert!(profiled("record = \"a\"\nreplay = \"b\"\n", &[]).unwrap_err().to_string().contains("both"));
+    }
+}
@@ -0,0 +1,104 @@
+// context.rs
+// This module adds each state's nuclear and renewable generation and fossil share to a result table, so the heat
+// rate change can be read next to the shift in the wider generation mix.
+
+use std::collections::HashMap;
+
+use crate::cleaning::CleanRecord;
+use crate::fuels::FuelGroup;
+use crate::report::{Cell, Report};
+
+/// A state's generation by broad source for one year (MWh).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GenerationContext {
+    pub fossil: f64,
+    pub nuclear: f64,
+    pub renewable: f64,
+    pub total: f64,
+}
+
+impl GenerationContext {
+    /// Fossil share of total generation (0-1), or `None` without generation.
+    pub fn fossil_share(&self) -> Option<f64> {
+        (self.total > 0.0).then(|| self.fossil / self.total)
+    }
+}
+
+/// Sums each state's generation by broad source; every fuel group counts toward the total.
+pub fn generation_context(records: &[CleanRecord]) -> HashMap<String, GenerationContext> {
+    let mut context: HashMap<String, GenerationContext> = HashMap::new();
+
+    for record in records {
+        let group = FuelGroup::from_aer(&record.aer_fuel);
+        let entry = context.entry(record.state.clone()).or_default();
+        entry.total += record.r#gen;
+        if group.is_fossil() {
+            entry.fossil += record.r#gen;
+        } else if group == FuelGroup::Nuclear {
+            entry.nuclear += record.r#gen;
+        } else if group.is_renewable() {
+            entry.renewable += record.r#gen;
+        }
+    }
+
+    context
+}
+
+/// Appends nuclear and renewable generation and fossil share columns for both years to a table keyed by its
+/// `State` column; states missing from a year get empty cells.
+/// # Arguments
+/// * `report` - Table with a `State` column
+/// * `labels` - Names of the two inputs used in the column headers (e.g. "2019", "2020")
+/// * `records` - Cleaned rows of the two inputs
+pub fn append_context_columns(report: &mut Report, labels: [&str; 2], records: [&[CleanRecord]; 2]) {
+    let states: Vec<String> = report.text_column("State").into_iter().map(String::from).collect();
+
+    let contexts = records.map(generation_context);
+    let mut columns: Vec<(String, usize, Vec<Cell>)> = Vec::new();
+    for (label, context) in labels.iter().zip(&contexts) {
+        let lookup = |value: fn(&GenerationContext) -> Option<f64>| -> Vec<Cell> {
+            states
+                .iter()
+                .map(|state| context.get(state).and_then(value).map_or(Cell::from(""), Cell::from))
+                .collect()
+        };
+        columns.push((format!("Nuclear_MWh_{label}"), 6, lookup(|c| Some(c.nuclear))));
+        columns.push((format!("Renewable_MWh_{label}"), 6, lookup(|c| Some(c.renewable))));
+        columns.push((format!("Fossil_Share_{label}"), 6, lookup(GenerationContext::fossil_share)));
+    }
+
+    for (name, decimals, values) in columns {
+        report.push_column(name, decimals, values);
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_context_columns() {
+        let base = [
+            record("1", "AA", "NG", 0.0, 60.0),
+            record("1", "AA", "NUC", 0.0, 30.0),
+            record("1", "AA", "WND", 0.0, 10.0),
+        ];
+        let compare = [
+            record("1", "AA", "NG", 0.0, 40.0),
+            record("1", "AA", "SUN", 0.0, 40.0),
+            record("1", "AA", "OTH", 0.0, 20.0),
+        ];
+
+        let mut report = Report::new("Test").column("State", 0).column("Delta", 6);
+        report.push_row(vec!["AA".into(), 1.0.into()]);
+        report.push_row(vec!["BB".into(), 2.0.into()]);
+        append_context_columns(&mut report, ["2019", "2020"], [&base, &compare]);
+
+        assert_eq!(report.columns.len(), 8);
+        assert_eq!(report.columns[7].name, "Fossil_Share_2020");
+        assert_eq!(&report.rows[0][2..], [Cell::Float(30.0), Cell::Float(10.0), Cell::Float(0.6), Cell::Float(0.0), Cell::Float(40.0), Cell::Float(0.4)]);
+        assert_eq!(report.rows[1][2], Cell::from(""));
+    }
+}
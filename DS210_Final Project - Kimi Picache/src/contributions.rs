@@ -0,0 +1,221 @@
+// contributions.rs
+// This module splits each state's change in heat rate into additive per-plant contributions, so the drill-down
+// into a state says how much of its change each plant accounts for.
+//
+// A state's heat rate is the sum over its plants of fuel / state generation, i.e. each plant's heat rate times its
+// generation share. A plant's contribution is the change in that term, so the contributions add up to the state's
+// change exactly. For plants running in both years it is further split into a rate effect (change in the plant's
+// heat rate at its average share) and a mix effect (change in its share at its average heat rate).
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{aggregate_by_state_plant, CleanRecord};
+use crate::monthly::format_optional;
+
+/// One plant's part of its state's change in heat rate.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlantContribution {
+    pub state: String,
+    pub plant_id: String,
+    pub plant_name: String,
+
+    /// "both", "entered" (no base-year generation), "exited" (no compare-year generation) or "idle" (neither).
+    pub status: &'static str,
+
+    /// Position within the state by size of contribution (1 = largest in magnitude).
+    pub rank: usize,
+
+    /// Plant heat rate in the base and compare inputs, `None` without generation.
+    pub heat_rate_2019: Option<f64>,
+    pub heat_rate_2020: Option<f64>,
+
+    /// Plant generation as a share of state generation (0-1).
+    pub share_2019: f64,
+    pub share_2020: f64,
+
+    /// Part of the contribution due to the plant's own heat rate change.
+    pub rate_effect: f64,
+
+    /// Part due to the plant's change in generation share (all of it for entering and exiting plants).
+    pub mix_effect: f64,
+
+    /// `rate_effect + mix_effect` (MMBtu/MWh of the state's change).
+    pub contribution: f64,
+
+    /// The state's total change, the sum of its plants' contributions.
+    pub state_delta: f64,
+}
+
+impl PlantContribution {
+    /// Contribution as a percentage of the state's change.
+    pub fn share_of_delta(&self) -> Option<f64> {
+        (self.state_delta != 0.0).then(|| self.contribution / self.state_delta * 100.0)
+    }
+}
+
+/// Decomposes every state's change into plant contributions.
+/// # Arguments
+/// * `records_2019` - Cleaned rows for the base year
+/// * `records_2020` - Cleaned rows for the compare year
+/// # Returns
+/// * `Vec<PlantContribution>` for states with generation in both years, sorted by state and then rank
+pub fn compute_plant_contributions(records_2019: &[CleanRecord], records_2020: &[CleanRecord]) -> Vec<PlantContribution> {
+    let (plants_2019, plants_2020) = (aggregate_by_state_plant(records_2019), aggregate_by_state_plant(records_2020));
+
+    let mut state_gen: HashMap<&str, [f64; 2]> = HashMap::new();
+    for (year, plants) in [&plants_2019, &plants_2020].into_iter().enumerate() {
+        for ((state, _), plant) in plants {
+            state_gen.entry(state).or_default()[year] += plant.total_gen;
+        }
+    }
+
+    let mut by_state: BTreeMap<String, Vec<PlantContribution>> = BTreeMap::new();
+    let keys: BTreeSet<&(String, String)> = plants_2019.keys().chain(plants_2020.keys()).collect();
+    for key in keys {
+        let [gen_2019, gen_2020] = state_gen[key.0.as_str()];
+        if gen_2019 <= 0.0 || gen_2020 <= 0.0 {
+            continue;
+        }
+
+        let plant_2019 = plants_2019.get(key).cloned().unwrap_or_default();
+        let plant_2020 = plants_2020.get(key).cloned().unwrap_or_default();
+        let (fuel_2019, plant_gen_2019) = (plant_2019.total_fuel, plant_2019.total_gen);
+        let (fuel_2020, plant_gen_2020) = (plant_2020.total_fuel, plant_2020.total_gen);
+        let heat_rate_2019 = (plant_gen_2019 > 0.0).then(|| fuel_2019 / plant_gen_2019);
+        let heat_rate_2020 = (plant_gen_2020 > 0.0).then(|| fuel_2020 / plant_gen_2020);
+        let (share_2019, share_2020) = (plant_gen_2019 / gen_2019, plant_gen_2020 / gen_2020);
+        let contribution = fuel_2020 / gen_2020 - fuel_2019 / gen_2019;
+
+        let (status, rate_effect, mix_effect) = match (heat_rate_2019, heat_rate_2020) {
+            (Some(h_2019), Some(h_2020)) => {
+                let rate = (share_2019 + share_2020) / 2.0 * (h_2020 - h_2019);
+                ("both", rate, contribution - rate)
+            }
+            (None, Some(_)) => ("entered", 0.0, contribution),
+            (Some(_), None) => ("exited", 0.0, contribution),
+            (None, None) => ("idle", 0.0, contribution),
+        };
+
+        by_state.entry(key.0.clone()).or_default().push(PlantContribution {
+            state: key.0.clone(),
+            plant_id: key.1.clone(),
+            plant_name: if plant_2020.plant_name.is_empty() { plant_2019.plant_name } else { plant_2020.plant_name },
+            status,
+            rank: 0,
+            heat_rate_2019,
+            heat_rate_2020,
+            share_2019,
+            share_2020,
+            rate_effect,
+            mix_effect,
+            contribution,
+            state_delta: 0.0,
+        });
+    }
+
+    let mut output = Vec::new();
+    for mut plants in by_state.into_values() {
+        let state_delta: f64 = plants.iter().map(|p| p.contribution).sum();
+        plants.sort_by(|a, b| b.contribution.abs().total_cmp(&a.contribution.abs()).then_with(|| a.plant_id.cmp(&b.plant_id)));
+        for (i, plant) in plants.iter_mut().enumerate() {
+            plant.rank = i + 1;
+            plant.state_delta = state_delta;
+        }
+        output.extend(plants);
+    }
+    output
+}
+
+/// Keeps each state's `n` largest contributors.
+pub fn top_contributors(data: &[PlantContribution], n: usize) -> Vec<PlantContribution> {
+    data.iter().filter(|p| p.rank <= n).cloned().collect()
+}
+
+/// Writes the plant contributions to a CSV output file, naming the per-input columns after the input labels.
+pub fn write_plant_contributions_csv(
+    path: &str,
+    data: &[PlantContribution],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let [a, b] = labels;
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State".to_string(),
+        "Rank".to_string(),
+        "Plant_Id".to_string(),
+        "Plant_Name".to_string(),
+        "Status".to_string(),
+        format!("Heat_Rate_{a}"),
+        format!("Heat_Rate_{b}"),
+        format!("Gen_Share_{a}"),
+        format!("Gen_Share_{b}"),
+        "Rate_Effect".to_string(),
+        "Mix_Effect".to_string(),
+        "Contribution".to_string(),
+        "State_Delta".to_string(),
+        "Pct_Of_State_Delta".to_string(),
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            item.state.clone(),
+            item.rank.to_string(),
+            item.plant_id.clone(),
+            item.plant_name.clone(),
+            item.status.to_string(),
+            format_optional(item.heat_rate_2019),
+            format_optional(item.heat_rate_2020),
+            format!("{:.6}", item.share_2019),
+            format!("{:.6}", item.share_2020),
+            format!("{:.6}", item.rate_effect),
+            format!("{:.6}", item.mix_effect),
+            format!("{:.6}", item.contribution),
+            format!("{:.6}", item.state_delta),
+            format_optional(item.share_of_delta()),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_contributions_add_up_to_state_delta() {
+        // Heat rate 9.0 in 2019, (80 + 30 + 14) / 12 in 2020
+        let base = [record("1", "AA", "NG", 100.0, 10.0), record("2", "AA", "NG", 80.0, 10.0)];
+        let compare = [
+            record("1", "AA", "NG", 80.0, 10.0),
+            record("3", "AA", "NG", 30.0, 0.0),
+            record("4", "AA", "NG", 14.0, 2.0),
+        ];
+
+        let result = compute_plant_contributions(&base, &compare);
+        let delta = 124.0 / 12.0 - 9.0;
+        let total: f64 = result.iter().map(|p| p.contribution).sum();
+        assert!((total - delta).abs() < 1e-9);
+        assert!(result.iter().all(|p| (p.state_delta - delta).abs() < 1e-9));
+
+        let exited = result.iter().find(|p| p.plant_id == "2").unwrap();
+        assert_eq!((exited.status, exited.rank, exited.contribution), ("exited", 1, -4.0));
+
+        let both = result.iter().find(|p| p.plant_id == "1").unwrap();
+        assert_eq!(both.status, "both");
+        assert!((both.rate_effect + both.mix_effect - both.contribution).abs() < 1e-9);
+        assert!((both.rate_effect - (0.5 + 10.0 / 12.0) / 2.0 * -2.0).abs() < 1e-9);
+
+        let idle = result.iter().find(|p| p.plant_id == "3").unwrap();
+        assert_eq!((idle.status, idle.rank, idle.contribution), ("idle", 2, 2.5));
+
+        assert_eq!(top_contributors(&result, 2).len(), 2);
+    }
+}
@@ -0,0 +1,227 @@
+// convergence.rs
+// This module tests whether state heat rates are converging over the panel. Sigma-convergence: the cross-state
+// dispersion of log heat rates shrinks from year to year. Beta-convergence: states that started with high heat
+// rates improved faster, i.e. the growth rate of the heat rate regressed on its initial log level has a negative
+// slope.
+
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::panel::YearData;
+use crate::stats::{linear_regression, mean, std_dev, t_critical_95};
+
+/// Cross-state dispersion of heat rates in one year.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SigmaPoint {
+    pub year: i32,
+    pub states: usize,
+
+    /// Unweighted mean of the state heat rates (MMBtu/MWh).
+    pub mean: f64,
+
+    /// Sample standard deviation of the log heat rates.
+    pub log_std_dev: f64,
+
+    /// Standard deviation of the heat rates relative to their mean.
+    pub cv: f64,
+}
+
+/// Regression of each state's average annual log heat rate growth on its initial log heat rate.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BetaConvergence {
+    pub first_year: i32,
+    pub last_year: i32,
+
+    /// States with a heat rate in both years.
+    pub states: usize,
+
+    /// Slope of the growth on the initial level; negative means convergence.
+    pub beta: f64,
+    pub std_error: f64,
+
+    /// Whether the 95% confidence interval of `beta` excludes zero.
+    pub significant: bool,
+    pub r_squared: f64,
+
+    /// Implied yearly speed of convergence, -ln(1 + beta * T) / T, when `1 + beta * T` is positive.
+    pub speed: Option<f64>,
+}
+
+/// Sigma- and beta-convergence of a panel.
+#[derive(Debug, Default, Serialize)]
+pub struct ConvergenceReport {
+    pub sigma: Vec<SigmaPoint>,
+
+    /// Yearly change of the log dispersion (OLS slope over the years); negative means sigma-convergence.
+    pub sigma_trend: Option<f64>,
+    pub beta: Option<BetaConvergence>,
+}
+
+/// Heat rates of the states with positive generation and fuel in one year.
+fn state_heat_rates(year: &YearData) -> Vec<(&str, f64)> {
+    let mut rates: Vec<(&str, f64)> = year
+        .stats
+        .iter()
+        .filter(|(_, s)| s.total_gen.0 > 0.0 && s.total_fuel.0 > 0.0)
+        .map(|(state, s)| (state.as_str(), (s.total_fuel / s.total_gen).0))
+        .collect();
+    rates.sort_by(|a, b| a.0.cmp(b.0));
+    rates
+}
+
+/// Tests the panel for sigma- and beta-convergence of state heat rates.
+/// # Arguments
+/// * `panel` - Loaded years, ascending
+/// # Returns
+/// * `ConvergenceReport`; the trend and beta need at least two years (and beta three states)
+pub fn compute_convergence(panel: &[YearData]) -> ConvergenceReport {
+    let mut report = ConvergenceReport::default();
+    for year in panel {
+        let rates: Vec<f64> = state_heat_rates(year).into_iter().map(|(_, hr)| hr).collect();
+        let logs: Vec<f64> = rates.iter().map(|hr| hr.ln()).collect();
+        if let (Some(mean), Some(log_std_dev), Some(std_dev)) = (mean(&rates), std_dev(&logs), std_dev(&rates)) {
+            report.sigma.push(SigmaPoint { year: year.year, states: rates.len(), mean, log_std_dev, cv: std_dev / mean });
+        }
+    }
+
+    let years: Vec<f64> = report.sigma.iter().map(|p| p.year as f64).collect();
+    let dispersion: Vec<f64> = report.sigma.iter().map(|p| p.log_std_dev).collect();
+    report.sigma_trend = linear_regression(&years, &dispersion).map(|fit| fit.slope);
+
+    if let (Some(first), Some(last)) = (panel.first(), panel.last())
+        && last.year > first.year
+    {
+        report.beta = beta_convergence(first, last);
+    }
+    report
+}
+
+/// Beta-convergence between the first and last year of the panel.
+fn beta_convergence(first: &YearData, last: &YearData) -> Option<BetaConvergence> {
+    let span = (last.year - first.year) as f64;
+    let end = state_heat_rates(last);
+    let (initial, growth): (Vec<f64>, Vec<f64>) = state_heat_rates(first)
+        .into_iter()
+        .filter_map(|(state, start)| {
+            let (_, finish) = end.iter().find(|(s, _)| *s == state)?;
+            Some((start.ln(), (finish.ln() - start.ln()) / span))
+        })
+        .unzip();
+    let n = initial.len();
+    if n < 3 {
+        return None;
+    }
+
+    let fit = linear_regression(&initial, &growth)?;
+    let mean_x = mean(&initial)?;
+    let sxx: f64 = initial.iter().map(|x| (x - mean_x).powi(2)).sum();
+    let sse: f64 = initial.iter().zip(&growth).map(|(x, y)| (y - fit.intercept - fit.slope * x).powi(2)).sum();
+    let std_error = (sse / (n - 2) as f64 / sxx).sqrt();
+    let shrink = 1.0 + fit.slope * span;
+
+    Some(BetaConvergence {
+        first_year: first.year,
+        last_year: last.year,
+        states: n,
+        beta: fit.slope,
+        std_error,
+        significant: fit.slope.abs() > t_critical_95(n - 2) * std_error,
+        r_squared: fit.r_squared,
+        speed: (shrink > 0.0).then(|| -shrink.ln() / span),
+    })
+}
+
+/// Writes the per-year dispersion to a CSV output file.
+pub fn write_sigma_convergence_csv(path: &str, data: &[SigmaPoint]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Year", "States", "Mean_Heat_Rate", "Log_Std_Dev", "CV"])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.year.to_string(),
+            &item.states.to_string(),
+            &format!("{:.6}", item.mean),
+            &format!("{:.6}", item.log_std_dev),
+            &format!("{:.6}", item.cv),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Writes the sigma trend and the beta regression as statistic/value rows to a CSV output file.
+pub fn write_convergence_summary_csv(path: &str, report: &ConvergenceReport) -> Result<(), Box<dyn Error>> {
+    let fmt = |v: Option<f64>| v.map(|v| format!("{:.6}", v)).unwrap_or_default();
+    let beta = report.beta.as_ref();
+
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Statistic", "Value"])?;
+    let rows = [
+        ("sigma_trend_per_year", fmt(report.sigma_trend)),
+        ("beta_first_year", beta.map(|b| b.first_year.to_string()).unwrap_or_default()),
+        ("beta_last_year", beta.map(|b| b.last_year.to_string()).unwrap_or_default()),
+        ("beta_states", beta.map(|b| b.states.to_string()).unwrap_or_default()),
+        ("beta", fmt(beta.map(|b| b.beta))),
+        ("beta_std_error", fmt(beta.map(|b| b.std_error))),
+        ("beta_significant_95", beta.map(|b| b.significant.to_string()).unwrap_or_default()),
+        ("beta_r_squared", fmt(beta.map(|b| b.r_squared))),
+        ("convergence_speed_per_year", fmt(beta.and_then(|b| b.speed))),
+    ];
+    for (statistic, value) in rows {
+        wtr.write_record([statistic, &value])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::{MegawattHours, Mmbtu};
+    use std::collections::HashMap;
+
+    use crate::cleaning::StateStats;
+
+    fn year(year: i32, heat_rates: &[(&str, f64)]) -> YearData {
+        YearData {
+            year,
+            source: format!("{}.csv", year),
+            rows: 0,
+            options: Default::default(),
+            imputed: 0,
+            anomalies: 0,
+            monthly: HashMap::new(),
+            stats: heat_rates
+                .iter()
+                .map(|(state, hr)| {
+                    (state.to_string(), StateStats { total_fuel: Mmbtu(hr * 100.0), total_gen: MegawattHours(100.0) })
+                })
+                .collect(),
+            fuels: Default::default(),
+            technologies: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_converging_panel() {
+        // The worst states improve fastest, so the spread halves over four years
+        let panel = [
+            year(2016, &[("AA", 8.0), ("BB", 10.0), ("CC", 12.0), ("DD", 14.0)]),
+            year(2020, &[("AA", 9.0), ("BB", 10.0), ("CC", 11.0), ("DD", 12.0)]),
+        ];
+        let report = compute_convergence(&panel);
+        assert_eq!(report.sigma.len(), 2);
+        assert!(report.sigma[1].log_std_dev < report.sigma[0].log_std_dev);
+        assert!(report.sigma_trend.unwrap() < 0.0);
+
+        let beta = report.beta.unwrap();
+        assert_eq!((beta.first_year, beta.last_year, beta.states), (2016, 2020, 4));
+        assert!(beta.beta < 0.0 && beta.significant);
+        assert!(beta.speed.unwrap() > 0.0);
+    }
+}
@@ -0,0 +1,275 @@
+// convert.rs
+// This module converts a raw EIA-923 export (CSV or XLSX) into a Parquet file of cleaned records, with the
+// columns of the `--emit-cleaned` CSV. Rows stream from the source through the cleaning (validation, territory
+// handling, imputation and the heat content policy all work row by row) into row groups of a fixed size, so
+// memory holds one row group at most, whatever the size of the input.
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs::{self, File};
+use std::sync::Arc;
+
+use parquet::basic::Compression;
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int32Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+
+use crate::cancel::{CancellationToken, CHECK_INTERVAL};
+use crate::cleaned::{blank_months, cleaned_header};
+use crate::cleaning::{CleanRecord, ColumnProjection, EmptyDataset, LoadOptions, RowCleaner, SkipCounts};
+use crate::fuels::FuelGroup;
+use crate::heatcontent::check_heat_content;
+use crate::impute::impute_monthly;
+use crate::merge::is_merged;
+use crate::source::{DataSource, SourceRow};
+use crate::territories::apply_territory_policy;
+use crate::years::DataYears;
+
+/// Cleaned rows per Parquet row group, and so the most rows held in memory.
+pub const ROW_GROUP_ROWS: usize = 10_000;
+
+/// What a conversion read and wrote.
+#[derive(Debug, Clone, Default)]
+pub struct ConvertSummary {
+    /// Cleaned rows written.
+    pub rows: usize,
+    pub row_groups: usize,
+
+    /// Rows dropped while reading, by reason.
+    pub skipped: SkipCounts,
+
+    /// Rows read per value of the YEAR column.
+    pub years: DataYears,
+
+    /// Non-state codes found (DC and the territories), handled by the territory policy.
+    pub territories: BTreeSet<String>,
+
+    /// Blank monthly values filled and plant-months with implausible heat content.
+    pub imputed: usize,
+    pub anomalies: usize,
+}
+
+/// Physical type of an output column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Text,
+    Count,
+    Number,
+}
+
+/// Type of the cleaned column at `index` (see `cleaned_header`).
+fn column_kind(index: usize) -> ColumnKind {
+    match index {
+        0..=7 => ColumnKind::Text,
+        10 => ColumnKind::Count,
+        _ => ColumnKind::Number,
+    }
+}
+
+/// Parquet schema of the cleaned records; every column is required.
+fn parquet_schema() -> String {
+    let fields: String = cleaned_header()
+        .iter()
+        .enumerate()
+        .map(|(i, name)| match column_kind(i) {
+            ColumnKind::Text => format!("  REQUIRED BYTE_ARRAY {} (UTF8);\n", name),
+            ColumnKind::Count => format!("  REQUIRED INT32 {};\n", name),
+            ColumnKind::Number => format!("  REQUIRED DOUBLE {};\n", name),
+        })
+        .collect();
+    format!("message cleaned_records {{\n{}}}", fields)
+}
+
+/// Value of a text column.
+fn text_value<'a>(index: usize, label: &'a str, record: &'a CleanRecord) -> &'a str {
+    match index {
+        0 => label,
+        1 => &record.plant_id,
+        2 => &record.plant_name,
+        3 => &record.state,
+        4 => &record.aer_fuel,
+        5 => FuelGroup::from_aer(&record.aer_fuel).label(),
+        6 => &record.reported_fuel,
+        _ => &record.prime_mover,
+    }
+}
+
+/// Value of a number column: the annual totals, then the monthly fuel, generation and quantity.
+fn number_value(index: usize, record: &CleanRecord) -> f64 {
+    match index {
+        8 => record.fuel,
+        9 => record.r#gen,
+        11..=22 => record.monthly_fuel[index - 11],
+        23..=34 => record.monthly_gen[index - 23],
+        _ => record.monthly_quantity[index - 35],
+    }
+}
+
+/// Writes one row group, column by column.
+fn write_row_group(
+    writer: &mut SerializedFileWriter<File>,
+    label: &str,
+    records: &[CleanRecord],
+) -> Result<(), Box<dyn Error>> {
+    let mut group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = group.next_column()? {
+        match column_kind(index) {
+            ColumnKind::Text => {
+                let values: Vec<ByteArray> = records.iter().map(|r| text_value(index, label, r).into()).collect();
+                column.typed::<ByteArrayType>().write_batch(&values, None, None)?;
+            }
+            ColumnKind::Count => {
+                let values: Vec<i32> = records.iter().map(|r| blank_months(r) as i32).collect();
+                column.typed::<Int32Type>().write_batch(&values, None, None)?;
+            }
+            ColumnKind::Number => {
+                let values: Vec<f64> = records.iter().map(|r| number_value(index, r)).collect();
+                column.typed::<DoubleType>().write_batch(&values, None, None)?;
+            }
+        }
+        column.close()?;
+        index += 1;
+    }
+    group.close()?;
+    Ok(())
+}
+
+/// Applies the row-level cleaning policies to a chunk of rows and writes it as a row group.
+fn flush_chunk(
+    writer: &mut SerializedFileWriter<File>,
+    chunk: &mut Vec<CleanRecord>,
+    label: &str,
+    options: &LoadOptions,
+    summary: &mut ConvertSummary,
+) -> Result<(), Box<dyn Error>> {
+    summary.territories.extend(apply_territory_policy(chunk, options.territories));
+    summary.imputed += impute_monthly(chunk, options.impute).len();
+    summary.anomalies += check_heat_content(chunk, options.heat_content).len();
+    if !chunk.is_empty() {
+        write_row_group(writer, label, chunk)?;
+        summary.rows += chunk.len();
+        summary.row_groups += 1;
+    }
+    chunk.clear();
+    Ok(())
+}
+
+/// Streams the source into the Parquet writer.
+fn write_parquet(
+    input: &str,
+    output: &str,
+    label: &str,
+    options: &LoadOptions,
+    cancel: &CancellationToken,
+) -> Result<ConvertSummary, Box<dyn Error>> {
+    let schema = Arc::new(parse_message_type(&parquet_schema())?);
+    let properties = Arc::new(WriterProperties::builder().set_compression(Compression::SNAPPY).build());
+    let mut writer = SerializedFileWriter::new(File::create(output)?, schema, properties)?;
+
+    let mut summary = ConvertSummary::default();
+    let mut cleaner: Option<RowCleaner> = None;
+    let mut chunk = Vec::with_capacity(ROW_GROUP_ROWS);
+    let mut row = 0;
+    DataSource::new(input).read_rows(|source_row| {
+        match source_row {
+            // The monthly columns are part of the output, so they are always parsed
+            SourceRow::Headers(headers) => cleaner = Some(RowCleaner::new(headers, ColumnProjection::All)),
+            SourceRow::Data(raw) => {
+                if row % CHECK_INTERVAL == 0 {
+                    cancel.check(&format!("converting '{}'", input), || format!("{} rows read", row))?;
+                }
+                row += 1;
+                if let Some(record) = cleaner.as_mut().and_then(|c| c.clean(raw)) {
+                    chunk.push(record);
+                }
+                if chunk.len() == ROW_GROUP_ROWS {
+                    flush_chunk(&mut writer, &mut chunk, label, options, &mut summary)?;
+                }
+            }
+        }
+        Ok(())
+    })?;
+    flush_chunk(&mut writer, &mut chunk, label, options, &mut summary)?;
+    writer.close()?;
+
+    if let Some(cleaner) = cleaner {
+        summary.skipped = cleaner.skipped;
+        summary.years = cleaner.years;
+    }
+    if summary.rows == 0 && !options.allow_empty {
+        return Err(EmptyDataset { path: input.to_string(), skipped: summary.skipped.total() }.into());
+    }
+    Ok(summary)
+}
+
+/// Converts a raw EIA-923 export into a Parquet file of cleaned records, streaming it row group by row group.
+/// # Arguments
+/// * `input` - EIA-923 CSV or XLSX file (a single file, not a merged input)
+/// * `output` - Parquet file to write; removed again if the conversion fails
+/// * `label` - Value of the `Input` column, e.g. "2020"
+/// * `options` - Territory, imputation and heat content policies (the column projection is ignored)
+/// * `cancel` - Stops the conversion once set
+/// # Returns
+/// * `ConvertSummary` of the rows read, dropped and written
+pub fn convert_to_parquet(
+    input: &str,
+    output: &str,
+    label: &str,
+    options: &LoadOptions,
+    cancel: &CancellationToken,
+) -> Result<ConvertSummary, Box<dyn Error>> {
+    if is_merged(input) {
+        return Err(format!("convert '{}': merged inputs are not supported, convert each file", input).into());
+    }
+    let result = write_parquet(input, output, label, options, cancel);
+    if result.is_err() {
+        let _ = fs::remove_file(output);
+    }
+    result
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+
+    #[test]
+    fn test_convert_to_parquet() {
+        let dir = std::env::temp_dir().join(format!("efficiency_convert_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (input, output) = (dir.join("2020.csv"), dir.join("2020.parquet"));
+
+        let mut csv = "meta\n".repeat(5);
+        csv += "Plant Id,Plant Name,Plant State,\"AER\nFuel Type Code\",\"Total Fuel Consumption\nMMBtu\",";
+        csv += "\"Net Generation\n(Megawatthours)\",\"Netgen\nMarch\"\n";
+        csv += "1,Alpha,TX,NG,\"1,000\",100,.\n2,Beta,PR,COL,500,0,0\n3,Gamma,CA,NG,x,5,5\n";
+        fs::write(&input, csv).unwrap();
+
+        let (input, output) = (input.to_str().unwrap(), output.to_str().unwrap());
+        let summary = convert_to_parquet(input, output, "2020", &LoadOptions::default(), &CancellationToken::new());
+        let summary = summary.unwrap();
+        assert_eq!((summary.rows, summary.row_groups), (1, 1));
+        assert_eq!((summary.skipped.zero_generation, summary.skipped.unparsable_values), (1, 1));
+
+        let reader = SerializedFileReader::new(File::open(output).unwrap()).unwrap();
+        let schema = reader.metadata().file_metadata().schema_descr();
+        assert_eq!(schema.num_columns(), cleaned_header().len());
+        let row = reader.get_row_iter(None).unwrap().next().unwrap().unwrap();
+        let fields: Vec<(&String, &Field)> = row.get_column_iter().collect();
+        assert_eq!(fields[0], (&"Input".to_string(), &Field::Str("2020".to_string())));
+        assert_eq!(fields[8], (&"Fuel_MMBtu".to_string(), &Field::Double(1000.0)));
+        assert_eq!(fields[10].1, &Field::Int(12));
+
+        // Without a data row the half-written output is removed
+        let empty = dir.join("empty.csv");
+        fs::write(&empty, "meta\n".repeat(5)).unwrap();
+        let empty = empty.to_str().unwrap();
+        let result = convert_to_parquet(empty, output, "x", &LoadOptions::default(), &CancellationToken::new());
+        assert!(result.is_err() && !std::path::Path::new(output).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
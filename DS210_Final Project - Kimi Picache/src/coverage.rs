@@ -0,0 +1,157 @@
+// coverage.rs
+// This module keeps states with too little data out of the main results: a state needs a minimum number of
+// reporting plants and a minimum net generation in both years, otherwise its heat rate is listed separately
+// as insufficient data instead of being ranked.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{CleanRecord, StateStats, INCREMENT_PLANT_ID};
+
+/// Minimum plants and generation a state needs in each year to be reported.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CoverageRule {
+    pub min_plants: usize,
+
+    /// Net generation (MWh).
+    pub min_gen: f64,
+}
+
+impl CoverageRule {
+    /// Whether any minimum is set.
+    pub fn is_active(&self) -> bool {
+        self.min_plants > 0 || self.min_gen > 0.0
+    }
+}
+
+/// A state left out of the results for lack of data.
+#[derive(Debug, Clone, Serialize)]
+pub struct LowCoverage {
+    pub state: String,
+
+    /// Reporting plants (increment rows not counted) and net generation (MWh) in each year.
+    pub plants: [usize; 2],
+    pub generation: [f64; 2],
+
+    /// Which minimum was missed, e.g. "3 plants in 2019 (min 5)".
+    pub reason: String,
+}
+
+fn plant_counts(records: &[CleanRecord]) -> HashMap<&str, usize> {
+    let mut plants: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for record in records.iter().filter(|r| r.plant_id != INCREMENT_PLANT_ID) {
+        plants.entry(&record.state).or_default().insert(&record.plant_id);
+    }
+    plants.into_iter().map(|(state, ids)| (state, ids.len())).collect()
+}
+
+/// Finds the states below the coverage minimum in either year.
+/// # Arguments
+/// * `records` - Cleaned rows for the base and compare year
+/// * `stats` - State totals for the base and compare year
+/// * `labels` - Year labels used in the reason text
+/// * `rule` - Minimum plants and generation
+/// # Returns
+/// * `Vec<LowCoverage>` sorted by state, for states present in both years
+pub fn low_coverage_states(
+    records: [&[CleanRecord]; 2],
+    stats: [&HashMap<String, StateStats>; 2],
+    labels: [&str; 2],
+    rule: CoverageRule,
+) -> Vec<LowCoverage> {
+    let counts = records.map(plant_counts);
+    let mut low = BTreeMap::new();
+
+    for state in stats[0].keys().filter(|s| stats[1].contains_key(*s)) {
+        let plants = [0, 1].map(|i| counts[i].get(state.as_str()).copied().unwrap_or(0));
+        let generation = [0, 1].map(|i| stats[i][state].total_gen.0);
+
+        let mut reasons = Vec::new();
+        for i in 0..2 {
+            if plants[i] < rule.min_plants {
+                reasons.push(format!("{} plants in {} (min {})", plants[i], labels[i], rule.min_plants));
+            }
+            if generation[i] < rule.min_gen {
+                reasons.push(format!("{:.0} MWh in {} (min {:.0})", generation[i], labels[i], rule.min_gen));
+            }
+        }
+
+        if !reasons.is_empty() {
+            low.insert(state.clone(), LowCoverage { state: state.clone(), plants, generation, reason: reasons.join("; ") });
+        }
+    }
+
+    low.into_values().collect()
+}
+
+/// Removes the low-coverage states from both years' totals so they drop out of every state table.
+pub fn remove_states(stats: [&mut HashMap<String, StateStats>; 2], low: &[LowCoverage]) {
+    for map in stats {
+        for item in low {
+            map.remove(&item.state);
+        }
+    }
+}
+
+/// Writes the insufficient-data states to a CSV output file.
+pub fn write_insufficient_data_csv(path: &str, data: &[LowCoverage], labels: [&str; 2]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State".to_string(),
+        format!("Plants_{}", labels[0]),
+        format!("Plants_{}", labels[1]),
+        format!("Generation_MWh_{}", labels[0]),
+        format!("Generation_MWh_{}", labels[1]),
+        "Reason".to_string(),
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            item.state.clone(),
+            item.plants[0].to_string(),
+            item.plants[1].to_string(),
+            format!("{:.6}", item.generation[0]),
+            format!("{:.6}", item.generation[1]),
+            item.reason.clone(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::aggregate_by_state;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_low_coverage_states_are_parked() {
+        let base = [
+            record("1", "AA", "NG", 800.0, 100.0),
+            record("2", "AA", "NG", 800.0, 100.0),
+            record("3", "BB", "NG", 4000.0, 500.0),
+        ];
+        let compare = [
+            record("1", "AA", "NG", 800.0, 100.0),
+            record("2", "AA", "NG", 800.0, 100.0),
+            record("3", "BB", "NG", 400.0, 50.0),
+        ];
+        let (mut stats_a, mut stats_b) = (aggregate_by_state(&base), aggregate_by_state(&compare));
+
+        let rule = CoverageRule { min_plants: 2, min_gen: 100.0 };
+        let low = low_coverage_states([&base, &compare], [&stats_a, &stats_b], ["2019", "2020"], rule);
+        assert_eq!(low.len(), 1);
+        assert_eq!(low[0].state, "BB");
+        assert_eq!(low[0].reason, "1 plants in 2019 (min 2); 1 plants in 2020 (min 2); 50 MWh in 2020 (min 100)");
+
+        remove_states([&mut stats_a, &mut stats_b], &low);
+        assert!(!stats_a.contains_key("BB") && !stats_b.contains_key("BB"));
+        assert!(stats_a.contains_key("AA"));
+    }
+}
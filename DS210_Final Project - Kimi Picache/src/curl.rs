@@ -0,0 +1,101 @@
+// curl.rs
+// This module builds the system `curl` commands behind every HTTP request the tool makes (URL inputs and release
+// checks, the webhook and the HTTP output sink), so they all fail on HTTP errors, take their proxy from the
+// standard HTTPS_PROXY / HTTP_PROXY / ALL_PROXY / NO_PROXY variables and trust the same custom CA bundle.
+
+use std::error::Error;
+use std::io::{self, ErrorKind, Write};
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+
+/// Proxy for a URL from the environment: `HTTPS_PROXY` for https URLs, `HTTP_PROXY` for http URLs (either in
+/// upper or lower case), falling back to `ALL_PROXY`.
+pub fn proxy_for(url: &str, var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let scheme = if url.starts_with("https://") { "HTTPS_PROXY" } else { "HTTP_PROXY" };
+    [scheme, "ALL_PROXY"]
+        .iter()
+        .flat_map(|name| [name.to_string(), name.to_lowercase()])
+        .find_map(|name| var(&name).filter(|v| !v.is_empty()))
+}
+
+/// curl with the settings shared by every request to `url`; the caller adds the rest of the request and the URL.
+/// # Arguments
+/// * `url` - URL the request goes to, which picks the proxy
+/// * `ca_cert` - PEM bundle of CA certificates to trust instead of the system ones
+pub fn curl_request(url: &str, ca_cert: Option<&str>) -> Result<Command, Box<dyn Error>> {
+    let mut curl = Command::new("curl");
+    curl.args(["--fail", "--silent", "--show-error"]);
+    let var = |name: &str| std::env::var(name).ok();
+    if let Some(proxy) = proxy_for(url, var) {
+        curl.arg("--proxy").arg(proxy);
+    }
+    if let Some(no_proxy) = var("NO_PROXY").or_else(|| var("no_proxy")) {
+        curl.arg("--noproxy").arg(no_proxy);
+    }
+    if let Some(ca_cert) = ca_cert {
+        if !Path::new(ca_cert).is_file() {
+            return Err(format!("--ca-cert '{}' is not a file", ca_cert).into());
+        }
+        curl.arg("--cacert").arg(ca_cert);
+    }
+    Ok(curl)
+}
+
+/// POSTs `body` as JSON to `url`, discarding the response.
+/// # Arguments
+/// * `purpose` - What needs the request, for the error when curl cannot be run
+/// # Returns
+/// * curl's exit status
+pub fn post_json(
+    url: &str,
+    body: &str,
+    ca_cert: Option<&str>,
+    purpose: &str,
+) -> Result<ExitStatus, Box<dyn Error>> {
+    let mut curl = curl_request(url, ca_cert)?;
+    curl.args(["--output", "/dev/null", "-X", "POST"]);
+    curl.args(["-H", "Content-Type: application/json", "--data-binary", "@-"]);
+    let status = run_with_input(curl.arg(url), body)
+        .map_err(|e| format!("could not run curl, which {} needs: {}", purpose, e))?;
+    Ok(status)
+}
+
+/// Runs `command` with `input` on its stdin and waits for it to exit. A command that exits without reading all
+/// of its input is judged by its exit status alone.
+pub(crate) fn run_with_input(command: &mut Command, input: &str) -> io::Result<ExitStatus> {
+    let mut child = command.stdin(Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = child.stdin.take()
+        && let Err(e) = stdin.write_all(input.as_bytes())
+        && e.kind() != ErrorKind::BrokenPipe
+    {
+        return Err(e);
+    }
+    child.wait()
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proxy_for() {
+        let env = |name: &str| match name {
+            "https_proxy" => Some("http://proxy.corp:3128".to_string()),
+            "ALL_PROXY" => Some("http://fallback:8080".to_string()),
+            _ => None,
+        };
+        assert_eq!(proxy_for("https://eia.gov/2020.csv", env).as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(proxy_for("http://eia.gov/2020.csv", env).as_deref(), Some("http://fallback:8080"));
+        assert_eq!(proxy_for("https://eia.gov/2020.csv", |_| None), None);
+    }
+
+    #[test]
+    fn test_curl_request() {
+        let curl = curl_request("https://eia.gov/2020.csv", None).unwrap();
+        assert!(curl.get_args().any(|arg| arg == "--fail"));
+
+        let error = curl_request("https://eia.gov/2020.csv", Some("no_such_bundle.pem")).unwrap_err().to_string();
+        assert!(error.contains("--ca-cert"));
+    }
+}
@@ -0,0 +1,176 @@
+// custom.rs
+// This module evaluates user-defined metric columns (`name=expression`) for every state and appends them to the
+// efficiency table.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+
+use crate::analysis::StateEfficiency;
+use crate::cleaning::StateStats;
+use crate::expr::{parse_expr, Expr};
+use crate::report::{Cell, Report};
+use crate::stats::median;
+
+/// Variables available to custom metric expressions.
+pub const VARIABLES: [&str; 22] = [
+    "heat_rate_2019", "heat_rate_2020", "delta", "abs_delta", "fuel_2019", "fuel_2020", "gen_2019", "gen_2020",
+    "rel_median_2019", "rel_median_2020", "percentile_2019", "percentile_2020", "percentile_change", "rank_2019",
+    "rank_2020", "rank_change", "percent_rank_2019", "percent_rank_2020", "improvement_percent_rank",
+    "national_median_2019", "national_median_2020", "states",
+];
+
+/// A named output column defined by an expression.
+#[derive(Debug)]
+pub struct CustomMetric {
+    pub name: String,
+    pub expr: Expr,
+}
+
+/// Parses a `name=expression` definition and checks that it only uses known variables.
+pub fn parse_metric(spec: &str) -> Result<CustomMetric, Box<dyn Error>> {
+    let (name, expression) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("metric '{}' must look like name=expression", spec))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("metric '{}' has no name", spec).into());
+    }
+
+    let expr = parse_expr(expression).map_err(|e| format!("metric '{}': {}", name, e))?;
+    if let Some(unknown) = expr.variables().into_iter().find(|v| !VARIABLES.contains(v)) {
+        return Err(format!("metric '{}': unknown variable '{}' (available: {})", name, unknown, VARIABLES.join(", ")).into());
+    }
+
+    Ok(CustomMetric { name: name.to_string(), expr })
+}
+
+/// Builds the variable values for one state.
+fn state_variables<'a>(
+    item: &StateEfficiency,
+    stat_2019: &StateStats,
+    stat_2020: &StateStats,
+    national: &BTreeMap<&'a str, f64>,
+) -> BTreeMap<&'a str, f64> {
+    let mut vars = national.clone();
+    vars.extend([
+        ("heat_rate_2019", item.eff_2019),
+        ("heat_rate_2020", item.eff_2020),
+        ("delta", item.delta),
+        ("abs_delta", item.abs_delta),
+        ("fuel_2019", stat_2019.total_fuel.0),
+        ("fuel_2020", stat_2020.total_fuel.0),
+        ("gen_2019", stat_2019.total_gen.0),
+        ("gen_2020", stat_2020.total_gen.0),
+        ("rel_median_2019", item.rel_median_2019),
+        ("rel_median_2020", item.rel_median_2020),
+        ("percentile_2019", item.pctile_2019),
+        ("percentile_2020", item.pctile_2020),
+        ("percentile_change", item.pctile_change),
+        ("rank_2019", item.rank_2019 as f64),
+        ("rank_2020", item.rank_2020 as f64),
+        ("rank_change", item.rank_change as f64),
+        ("percent_rank_2019", item.pct_rank_2019),
+        ("percent_rank_2020", item.pct_rank_2020),
+        ("improvement_percent_rank", item.pct_rank_improvement),
+    ]);
+    vars
+}
+
+/// Appends a column per custom metric to the per-state efficiency table, matched on its `State` column; states
+/// missing from either year's totals and values that are not finite (e.g. 0 / 0) get empty cells.
+/// # Arguments
+/// * `report` - Table with a `State` column
+/// * `metrics` - Parsed metric definitions
+/// * `changes` - Efficiency changes of every state, which the national variables are computed over
+/// * `stats_2019` / `stats_2020` - Aggregated state totals for both years
+pub fn append_custom_columns(
+    report: &mut Report,
+    metrics: &[CustomMetric],
+    changes: &[StateEfficiency],
+    stats_2019: &HashMap<String, StateStats>,
+    stats_2020: &HashMap<String, StateStats>,
+) -> Result<(), Box<dyn Error>> {
+    let effs_2019: Vec<f64> = changes.iter().map(|c| c.eff_2019).collect();
+    let effs_2020: Vec<f64> = changes.iter().map(|c| c.eff_2020).collect();
+    let national = BTreeMap::from([
+        ("national_median_2019", median(&effs_2019).unwrap_or(f64::NAN)),
+        ("national_median_2020", median(&effs_2020).unwrap_or(f64::NAN)),
+        ("states", changes.len() as f64),
+    ]);
+
+    let by_state: HashMap<&str, &StateEfficiency> = changes.iter().map(|c| (c.state.as_str(), c)).collect();
+    let variables: Vec<Option<BTreeMap<&str, f64>>> = report
+        .text_column("State")
+        .into_iter()
+        .map(|state| {
+            let item = by_state.get(state)?;
+            Some(state_variables(item, stats_2019.get(state)?, stats_2020.get(state)?, &national))
+        })
+        .collect();
+
+    for metric in metrics {
+        let mut values = Vec::with_capacity(variables.len());
+        for vars in &variables {
+            let value = match vars {
+                Some(vars) => metric.expr.eval(vars)?,
+                None => f64::NAN,
+            };
+            values.push(if value.is_finite() { Cell::from(value) } else { Cell::from("") });
+        }
+        report.push_column(metric.name.clone(), 6, values);
+    }
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::analysis::compute_efficiency_changes;
+    use crate::quantity::{MegawattHours, Mmbtu};
+
+    fn stats(states: &[(&str, f64)]) -> HashMap<String, StateStats> {
+        states
+            .iter()
+            .map(|(state, fuel)| {
+                (state.to_string(), StateStats { total_fuel: Mmbtu(*fuel), total_gen: MegawattHours(100.0) })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_metric() {
+        let metric = parse_metric(" savings = (heat_rate_2019 - heat_rate_2020) * gen_2020").unwrap();
+        assert_eq!(metric.name, "savings");
+        assert!(parse_metric("heat_rate_2019").unwrap_err().to_string().contains("name=expression"));
+        assert!(parse_metric("=delta").unwrap_err().to_string().contains("has no name"));
+        assert!(parse_metric("x=delta * price").unwrap_err().to_string().contains("unknown variable 'price'"));
+    }
+
+    #[test]
+    fn test_custom_columns() {
+        let stats_2019 = stats(&[("AA", 1000.0), ("BB", 900.0)]);
+        let stats_2020 = stats(&[("AA", 800.0), ("BB", 900.0)]);
+        let changes = compute_efficiency_changes(&stats_2019, &stats_2020);
+        let metrics = [
+            parse_metric("savings=(heat_rate_2019 - heat_rate_2020) * gen_2020").unwrap(),
+            parse_metric("share=delta / states").unwrap(),
+            parse_metric("ratio=delta / delta").unwrap(),
+        ];
+
+        let mut report = Report::new("Efficiency").column("State", 0);
+        for state in ["BB", "AA", "CC"] {
+            report.push_row(vec![state.into()]);
+        }
+        append_custom_columns(&mut report, &metrics, &changes, &stats_2019, &stats_2020).unwrap();
+
+        let names: Vec<&str> = report.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["State", "savings", "share", "ratio"]);
+        assert_eq!(&report.rows[1][1..], [Cell::Float(200.0), Cell::Float(-1.0), Cell::Float(1.0)]);
+        // 0 / 0 is not finite, so the cell is left empty
+        assert_eq!(&report.rows[0][1..], [Cell::Float(0.0), Cell::Float(0.0), Cell::from("")]);
+        // States without totals for both years get empty cells
+        assert_eq!(report.rows[2][1], Cell::from(""));
+    }
+}
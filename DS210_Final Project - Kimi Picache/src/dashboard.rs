@@ -0,0 +1,204 @@
+// dashboard.rs
+// This module gathers the figures about one state that are otherwise spread over several reports: both years'
+// totals and fuel mix, its heat rate change and ranks, the plants that moved it most and how its bordering
+// states fared, and formats them as a one-screen dashboard.
+
+use std::collections::BTreeMap;
+
+use crate::analysis::StateEfficiency;
+use crate::cleaning::CleanRecord;
+use crate::contributions::{compute_plant_contributions, PlantContribution};
+use crate::fuels::FuelGroup;
+use crate::geography::state_adjacency;
+use crate::stats::mean;
+use crate::summary::{summarize_states, StateSummary};
+use crate::units::HeatRateUnit;
+
+/// Everything the dashboard shows about one state.
+#[derive(Debug)]
+pub struct StateDashboard {
+    /// The state's efficiency row (in the output unit).
+    pub change: StateEfficiency,
+
+    /// States in the efficiency table, for "rank of N".
+    pub states: usize,
+
+    /// Totals, plant count and fuel mix of both years, `None` for a year without rows.
+    pub years: [Option<StateSummary>; 2],
+
+    /// Plants with the largest contributions to the change, largest first.
+    pub top_plants: Vec<PlantContribution>,
+
+    /// Efficiency rows of the bordering states that have results, alphabetically.
+    pub neighbors: Vec<StateEfficiency>,
+}
+
+impl StateDashboard {
+    /// Mean change of the bordering states, or `None` without any.
+    pub fn neighbor_delta(&self) -> Option<f64> {
+        mean(&self.neighbors.iter().map(|n| n.delta).collect::<Vec<_>>())
+    }
+}
+
+/// Collects the dashboard of one state.
+/// # Arguments
+/// * `state` - State code, in any case
+/// * `records` - Cleaned rows of both years
+/// * `changes` - The efficiency table (in the output unit)
+/// * `top_n` - Plants listed
+/// # Returns
+/// * `Err` naming the known states when `state` has no result
+pub fn build_state_dashboard(
+    state: &str,
+    records: [&[CleanRecord]; 2],
+    changes: &[StateEfficiency],
+    top_n: usize,
+) -> Result<StateDashboard, String> {
+    let state = state.trim().to_ascii_uppercase();
+    let Some(change) = changes.iter().find(|c| c.state == state) else {
+        let mut known: Vec<&str> = changes.iter().map(|c| c.state.as_str()).collect();
+        known.sort_unstable();
+        return Err(format!("no results for state '{}' (states with results: {})", state, known.join(", ")));
+    };
+
+    let own: [Vec<CleanRecord>; 2] = records.map(|year| year.iter().filter(|r| r.state == state).cloned().collect());
+    let years = [0, 1].map(|i| summarize_states(&own[i]).into_iter().next());
+    let mut top_plants = compute_plant_contributions(&own[0], &own[1]);
+    top_plants.truncate(top_n);
+
+    let borders: Vec<&str> = state_adjacency().get(state.as_str()).cloned().unwrap_or_default();
+    let neighbors = borders.iter().filter_map(|b| changes.iter().find(|c| c.state == *b).cloned()).collect();
+
+    Ok(StateDashboard { change: change.clone(), states: changes.len(), years, top_plants, neighbors })
+}
+
+/// Formats the dashboard for the terminal.
+/// # Arguments
+/// * `dashboard` - The state's figures
+/// * `labels` - Names of the two inputs
+/// * `unit` - Unit of the heat rates
+pub fn format_state_dashboard(dashboard: &StateDashboard, labels: [&str; 2], unit: HeatRateUnit) -> String {
+    let change = &dashboard.change;
+    let [a, b] = labels;
+    let rate = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.3}", unit.convert(v)));
+
+    let mut out = format!(
+        "{} ({}; rank 1 = most efficient of {} states)\n\n",
+        change.state,
+        unit.label(),
+        dashboard.states
+    );
+    out += &format!("{:<22} {:>16} {:>16} {:>12}\n", "", a, b, "Change");
+    let heat_rates = (change.eff_2019, change.eff_2020, change.delta);
+    out += &format!("{:<22} {:>16.3} {:>16.3} {:>+12.3}\n", "Heat rate", heat_rates.0, heat_rates.1, heat_rates.2);
+    out += &format!(
+        "{:<22} {:>16} {:>16} {:>+12}\n",
+        "Rank",
+        change.rank_2019,
+        change.rank_2020,
+        -change.rank_change
+    );
+    let total = |f: fn(&StateSummary) -> f64| dashboard.years.each_ref().map(|y| y.as_ref().map_or(0.0, f));
+    for (name, [x, y]) in [
+        ("Fuel (MMBtu)", total(|s| s.total_fuel)),
+        ("Generation (MWh)", total(|s| s.total_gen)),
+        ("Plants", total(|s| s.plants as f64)),
+    ] {
+        out += &format!("{:<22} {:>16.0} {:>16.0} {:>+12.0}\n", name, x, y, y - x);
+    }
+
+    out += "\nGeneration share by fuel:\n";
+    let mut mix: BTreeMap<FuelGroup, [f64; 2]> = BTreeMap::new();
+    for (i, year) in dashboard.years.iter().enumerate() {
+        for (group, share) in year.iter().flat_map(|y| &y.fuel_mix) {
+            mix.entry(*group).or_default()[i] = *share;
+        }
+    }
+    for (group, [x, y]) in mix.iter().filter(|(_, shares)| shares.iter().any(|s| *s > 0.0005)) {
+        let (x, y) = (x * 100.0, y * 100.0);
+        out += &format!("  {:<20} {:>15.1}% {:>15.1}% {:>+11.1}%\n", group.label(), x, y, y - x);
+    }
+
+    out += &format!("\nPlants that moved the heat rate most ({} → {}):\n", a, b);
+    out += &format!("  {:<32} {:>8} {:>8} {:>9}  generation share\n", "Plant", "Status", a, b);
+    if dashboard.top_plants.is_empty() {
+        out += "  (none)\n";
+    }
+    for plant in &dashboard.top_plants {
+        out += &format!(
+            "  {:<32} {:>8} {:>8} {:>9}  {:>5.1}% → {:>5.1}%  contribution {:+.3}\n",
+            truncate(&format!("{} ({})", plant.plant_name, plant.plant_id), 32),
+            plant.status,
+            rate(plant.heat_rate_2019),
+            rate(plant.heat_rate_2020),
+            plant.share_2019 * 100.0,
+            plant.share_2020 * 100.0,
+            unit.convert(plant.contribution)
+        );
+    }
+
+    out += "\nBordering states:\n";
+    if dashboard.neighbors.is_empty() {
+        out += "  (none with results)\n";
+    }
+    for neighbor in &dashboard.neighbors {
+        out += &format!(
+            "  {:<20} {:>16.3} {:>16.3} {:>+12.3}\n",
+            neighbor.state, neighbor.eff_2019, neighbor.eff_2020, neighbor.delta
+        );
+    }
+    if let Some(neighbor_delta) = dashboard.neighbor_delta() {
+        out += &format!(
+            "  {:<20} {:>46.3}  ({} {:+.3} against them)\n",
+            "Mean change",
+            neighbor_delta,
+            change.state,
+            change.delta - neighbor_delta
+        );
+    }
+    out
+}
+
+/// Shortens text to `width` characters, ending in "…" when cut.
+fn truncate(text: &str, width: usize) -> String {
+    match text.chars().count() > width {
+        true => text.chars().take(width - 1).chain(['…']).collect(),
+        false => text.to_string(),
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::analysis::compute_efficiency_changes;
+    use crate::cleaning::aggregate_by_state;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_state_dashboard() {
+        let year = |fuel: [f64; 3]| {
+            [
+                record("1", "TX", "NG", fuel[0], 100.0),
+                record("2", "TX", "NG", fuel[1], 100.0),
+                record("3", "OK", "NG", fuel[2], 100.0),
+            ]
+        };
+        let (base, compare) = (year([1000.0, 900.0, 800.0]), year([800.0, 900.0, 900.0]));
+        let stats: [HashMap<_, _>; 2] = [aggregate_by_state(&base), aggregate_by_state(&compare)];
+        let changes = compute_efficiency_changes(&stats[0], &stats[1]);
+
+        let dashboard = build_state_dashboard("tx", [&base, &compare], &changes, 1).unwrap();
+        assert_eq!((dashboard.change.state.as_str(), dashboard.states), ("TX", 2));
+        assert_eq!(dashboard.years[1].as_ref().map(|y| y.plants), Some(2));
+        assert_eq!(dashboard.top_plants.iter().map(|p| p.plant_id.as_str()).collect::<Vec<_>>(), ["1"]);
+        assert_eq!(dashboard.neighbors.iter().map(|n| n.state.as_str()).collect::<Vec<_>>(), ["OK"]);
+        assert_eq!(dashboard.neighbor_delta(), Some(1.0));
+
+        let text = format_state_dashboard(&dashboard, ["2019", "2020"], HeatRateUnit::MmbtuPerMwh);
+        assert!(text.starts_with("TX (MMBtu/MWh") && text.contains("Plant 1 (1)"));
+        assert!(build_state_dashboard("ZZ", [&base, &compare], &changes, 1).unwrap_err().contains("OK, TX"));
+    }
+}
@@ -0,0 +1,161 @@
+// degraded.rs
+// This module notices which optional columns an input lacks (plant codes, fuel type codes, monthly values) and
+// describes the metrics a run could not compute because of it. The state totals need none of these columns, so
+// such a run still completes: metrics that need a missing column are left empty (null) or their reports are
+// skipped, and the run summary lists them instead of the run failing.
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+
+use csv::StringRecord;
+use serde::{Deserialize, Serialize};
+
+use crate::merge::input_parts;
+use crate::source::DataSource;
+
+/// A group of columns the state totals do not need.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OptionalColumn {
+    /// "Plant Id", for the plant-level reports.
+    PlantId,
+    /// "AER Fuel Type Code", for fuel groups and emission factors.
+    FuelType,
+    /// The monthly "Tot_MMBtu" and "Netgen" columns, for the monthly and marginal metrics.
+    Monthly,
+}
+
+impl OptionalColumn {
+    /// Name used in messages.
+    pub fn label(self) -> &'static str {
+        match self {
+            OptionalColumn::PlantId => "Plant Id column",
+            OptionalColumn::FuelType => "fuel type column",
+            OptionalColumn::Monthly => "monthly columns",
+        }
+    }
+
+    /// Whether a header row has the column (for monthly values, any month of fuel or generation).
+    fn present(self, headers: &StringRecord) -> bool {
+        match self {
+            OptionalColumn::PlantId => headers.iter().any(|h| h == "Plant Id"),
+            OptionalColumn::FuelType => headers.iter().any(|h| h == "AER\nFuel Type Code"),
+            OptionalColumn::Monthly => {
+                headers.iter().any(|h| h.starts_with("Tot_MMBtu\n") || h.starts_with("Netgen\n"))
+            }
+        }
+    }
+}
+
+/// The optional columns an input lacks.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MissingColumns(pub BTreeSet<OptionalColumn>);
+
+impl MissingColumns {
+    /// The optional columns missing from a header row.
+    pub fn from_headers(headers: &StringRecord) -> MissingColumns {
+        let all = [OptionalColumn::PlantId, OptionalColumn::FuelType, OptionalColumn::Monthly];
+        MissingColumns(all.into_iter().filter(|column| !column.present(headers)).collect())
+    }
+
+    pub fn contains(&self, column: OptionalColumn) -> bool {
+        self.0.contains(&column)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Adds the columns another input lacks.
+    pub fn extend(&mut self, other: &MissingColumns) {
+        self.0.extend(other.0.iter().copied());
+    }
+}
+
+impl fmt::Display for MissingColumns {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels: Vec<&str> = self.0.iter().map(|column| column.label()).collect();
+        write!(f, "{}", labels.join(", "))
+    }
+}
+
+/// Reads the optional columns an input lacks from its header row; a merged input (`a.csv+b.csv`) lacks the
+/// columns any of its files lacks.
+pub fn read_missing_columns(file_path: &str) -> Result<MissingColumns, Box<dyn Error>> {
+    let mut missing = MissingColumns::default();
+    for part in input_parts(file_path) {
+        missing.extend(&MissingColumns::from_headers(&DataSource::new(part).headers()?));
+    }
+    Ok(missing)
+}
+
+/// What a run did with a metric it could not compute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Degradation {
+    /// The output is written with the metric's cells empty (null in JSON).
+    LeftEmpty,
+    /// The output is not written.
+    Skipped,
+}
+
+/// A metric or report a run could not compute, and the columns it lacked.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DegradedFeature {
+    pub feature: String,
+    pub missing: Vec<OptionalColumn>,
+    pub outcome: Degradation,
+}
+
+impl fmt::Display for DegradedFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = match self.outcome {
+            Degradation::LeftEmpty => "left empty",
+            Degradation::Skipped => "skipped",
+        };
+        let missing: Vec<&str> = self.missing.iter().map(|column| column.label()).collect();
+        write!(f, "{} {} (no {})", self.feature, outcome, missing.join(" or "))
+    }
+}
+
+/// Checks whether a feature can be computed from the loaded columns.
+/// # Arguments
+/// * `missing` - Optional columns missing from any input
+/// * `needs` - Columns the feature needs
+/// * `feature` - Name of the metric or report, e.g. "frontier plants"
+/// * `outcome` - What the run does without it
+/// # Returns
+/// * `None` when every needed column is present, else the degraded feature
+pub fn degrade(
+    missing: &MissingColumns,
+    needs: &[OptionalColumn],
+    feature: &str,
+    outcome: Degradation,
+) -> Option<DegradedFeature> {
+    let lacking: Vec<OptionalColumn> = needs.iter().copied().filter(|column| missing.contains(*column)).collect();
+    (!lacking.is_empty()).then(|| DegradedFeature { feature: feature.to_string(), missing: lacking, outcome })
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_columns() {
+        let headers = StringRecord::from(vec!["Plant State", "AER\nFuel Type Code", "Netgen\nMarch"]);
+        let missing = MissingColumns::from_headers(&headers);
+        assert_eq!(missing.0.into_iter().collect::<Vec<_>>(), [OptionalColumn::PlantId]);
+
+        let mut missing = MissingColumns::from_headers(&StringRecord::from(vec!["Plant Id"]));
+        missing.extend(&MissingColumns::from_headers(&headers));
+        assert_eq!(missing.to_string(), "Plant Id column, fuel type column, monthly columns");
+
+        let needs = [OptionalColumn::PlantId, OptionalColumn::FuelType];
+        assert_eq!(degrade(&MissingColumns::default(), &needs, "frontier", Degradation::Skipped), None);
+        let degraded = degrade(&missing, &needs, "frontier plants", Degradation::Skipped).unwrap();
+        assert_eq!(degraded.to_string(), "frontier plants skipped (no Plant Id column or fuel type column)");
+    }
+}
@@ -0,0 +1,317 @@
+// download.rs
+// This module lets an http(s) URL stand in for an input path. The file is fetched with the system `curl` into
+// the cache directory, under a folder named after the URL so the original file name (and the year in it) is
+// kept; for a .zip URL the data file inside the archive is extracted next to it: a CSV, or in EIA's yearly
+// archives of XLSX workbooks the Generation and Fuel workbook. Proxies come from the standard HTTPS_PROXY /
+// HTTP_PROXY / NO_PROXY variables, and a custom CA bundle can be given for intercepting firewalls. Interrupted
+// downloads keep their partial file and resume with an HTTP range request on the next run; a `#sha256=<hex>`
+// suffix on the URL makes the finished file's checksum part of the check. A finished download keeps the server's
+// Last-Modified time, so a later release check can tell whether it was revised since. An `s3://` path is fetched
+// through the objectstore module into the same folders and otherwise handled like a URL.
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::audit::sha256_file;
+use crate::cache::lock_entry;
+use crate::curl::curl_request;
+use crate::merge::{input_parts, is_merged, PART_SEPARATOR};
+use crate::objectstore::{fetch_object, is_object_url};
+use crate::recording::{record_response, replay_response, Recording};
+
+/// curl exit code when the server ignores the range request of a resumed download.
+const CURL_RANGE_ERROR: i32 = 33;
+
+/// curl exit code (with `--fail`) when the server answers with an HTTP error such as 404.
+const CURL_HTTP_ERROR: i32 = 22;
+
+/// Name part of the Generation and Fuel workbook in an EIA-923 archive
+/// (e.g. `EIA923_Schedules_2_3_4_5_M_12_2020_Final_Revision.xlsx`), whose first sheet is page 1.
+const GENERATION_AND_FUEL_WORKBOOK: &str = "schedules_2_3_4_5";
+
+/// Where downloads go and whether earlier downloads are reused.
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Cache directory; downloads are kept in its `downloads` folder.
+    pub dir: String,
+
+    /// Reuse a file downloaded by an earlier run (false with `--no-cache`).
+    pub reuse: bool,
+
+    /// PEM bundle of CA certificates to trust instead of the system ones (`--ca-cert`).
+    pub ca_cert: Option<String>,
+
+    /// Recording or replay of the fetched files (`--record`, `--replay`).
+    pub recording: Recording,
+}
+
+/// What a server says about a file without sending it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteFile {
+    /// Last-Modified time, if sent.
+    pub last_modified: Option<DateTime<Utc>>,
+
+    /// Content-Length in bytes, if sent.
+    pub size: Option<u64>,
+}
+
+/// URL of EIA's EIA-923 archive for a year (a zip of the year's XLSX workbooks).
+pub fn eia923_archive_url(year: i32) -> String {
+    format!("https://www.eia.gov/electricity/data/eia923/xls/f923_{}.zip", year)
+}
+
+/// Whether an input path is a URL to download.
+pub fn is_url(path: &str) -> bool {
+    path.starts_with("https://") || path.starts_with("http://")
+}
+
+/// Splits the expected checksum off a URL: `https://…/2020.zip#sha256=<hex>` → (`https://…/2020.zip`, `<hex>`).
+fn split_checksum(url: &str) -> (&str, Option<&str>) {
+    match url.split_once("#sha256=") {
+        Some((url, digest)) => (url, Some(digest)),
+        None => (url, None),
+    }
+}
+
+/// Last segment of a URL's path, without query string or fragment.
+fn url_file_name(url: &str) -> Option<&str> {
+    let path = url.split(['?', '#']).next()?;
+    path.rsplit('/').next().filter(|name| !name.is_empty())
+}
+
+/// Short hash of a URL, naming its folder in the downloads cache.
+fn url_hash(url: &str) -> String {
+    Sha256::digest(url.as_bytes()).iter().take(8).map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Where the download of a URL is kept in the cache directory `dir` (whether or not it was downloaded yet).
+pub fn download_path(url: &str, dir: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let (url, _) = split_checksum(url);
+    let name = url_file_name(url).ok_or_else(|| format!("URL '{}' does not name a file", url))?;
+    Ok(Path::new(dir).join("downloads").join(url_hash(url)).join(name))
+}
+
+/// Returns a local path for an input: the path itself, or for a URL the downloaded (and, for a .zip, extracted)
+/// CSV file.
+/// # Arguments
+/// * `path` - Input path, http(s) URL or `s3://` object; each file of a merged input (`a.csv+b.csv`) is resolved
+/// * `options` - Download folder and reuse setting
+/// # Returns
+/// * Local file path to read
+pub fn resolve_input(path: &str, options: &DownloadOptions) -> Result<String, Box<dyn Error>> {
+    if is_merged(path) {
+        let parts = input_parts(path).into_iter().map(|part| resolve_input(part, options));
+        return Ok(parts.collect::<Result<Vec<String>, _>>()?.join(&PART_SEPARATOR.to_string()));
+    }
+    if !is_url(path) && !is_object_url(path) {
+        return Ok(path.to_string());
+    }
+
+    let (url, expected) = split_checksum(path);
+    let target = download_path(url, &options.dir)?;
+    let folder = target.parent().ok_or("download path has no folder")?;
+
+    // Held until the file is downloaded, checked and extracted, so a parallel run waits and then reuses it
+    let _lock = lock_entry(&target)?;
+    if let Recording::Replay(dir) = &options.recording {
+        fs::create_dir_all(folder)?;
+        fs::copy(replay_response(dir, url)?, &target)?;
+    } else if options.reuse && target.exists() {
+        println!("Reusing download of '{}' ({})", url, target.display());
+    } else if is_object_url(url) {
+        fetch_object(url, &target)?;
+    } else {
+        download(url, &target, options)?;
+    }
+
+    if let Some(expected) = expected {
+        let actual = sha256_file(&target.to_string_lossy())?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            fs::remove_file(&target)?;
+            return Err(format!("'{}' has SHA-256 {}, expected {}; the file was removed", url, actual, expected).into());
+        }
+    }
+
+    if let Recording::Record(dir) = &options.recording {
+        record_response(dir, url, &url_hash(url), &target)?;
+    }
+
+    let local = if url.to_lowercase().ends_with(".zip") { extract_data_file(&target)? } else { target };
+    Ok(local.to_string_lossy().into_owned())
+}
+
+/// Fetches `url` to `target`, via a `.part` file so a failed download is never mistaken for a finished one. A
+/// `.part` file left by an earlier attempt is resumed where it stopped; if the server cannot resume, the
+/// download starts over. curl rejects a body shorter than the announced length, so a finished file has the
+/// full size.
+fn download(url: &str, target: &Path, options: &DownloadOptions) -> Result<(), Box<dyn Error>> {
+    let partial = target.with_extension("part");
+    let done = fs::metadata(&partial).map_or(0, |m| m.len());
+    if done > 0 {
+        println!("Resuming download of '{}' after {} bytes...", url, done);
+    } else {
+        println!("Downloading '{}'...", url);
+    }
+
+    let mut status = curl_command(url, &partial, options)?.status();
+    if let Ok(code) = &status
+        && code.code() == Some(CURL_RANGE_ERROR)
+    {
+        println!("The server cannot resume downloads; starting over...");
+        fs::remove_file(&partial)?;
+        status = curl_command(url, &partial, options)?.status();
+    }
+
+    let status = status.map_err(|e| format!("could not run curl, which URL inputs need: {}", e))?;
+    if !status.success() {
+        let kept = fs::metadata(&partial).map_or(0, |m| m.len());
+        let hint = if kept > 0 { format!("; {} bytes kept, run again to resume", kept) } else { String::new() };
+        return Err(format!("download of '{}' failed (curl {}){}", url, status, hint).into());
+    }
+
+    fs::rename(partial, target)?;
+    Ok(())
+}
+
+/// curl invocation that writes (or appends) to `partial` and gives it the server's modification time.
+fn curl_command(url: &str, partial: &Path, options: &DownloadOptions) -> Result<Command, Box<dyn Error>> {
+    let mut curl = curl_base(url, options)?;
+    curl.args(["--continue-at", "-", "--remote-time"]).arg("--output").arg(partial).arg(url);
+    Ok(curl)
+}
+
+/// curl for a download from `url`, following redirects and retrying; the caller adds the URL.
+fn curl_base(url: &str, options: &DownloadOptions) -> Result<Command, Box<dyn Error>> {
+    let mut curl = curl_request(url, options.ca_cert.as_deref())?;
+    curl.args(["--location", "--retry", "3"]);
+    Ok(curl)
+}
+
+/// Asks the server about `url` with a HEAD request, following redirects.
+/// # Returns
+/// * The file's modification time and size, or `None` when the server has no such file (an HTTP error)
+pub fn remote_file(url: &str, options: &DownloadOptions) -> Result<Option<RemoteFile>, Box<dyn Error>> {
+    if let Recording::Replay(_) = &options.recording {
+        return Err(format!("cannot ask about '{}' in --replay mode, which only has the recorded files", url).into());
+    }
+    let output = curl_base(url, options)?
+        .arg("--head")
+        .arg(url)
+        .output()
+        .map_err(|e| format!("could not run curl, which release checks need: {}", e))?;
+    match output.status.code() {
+        Some(0) => Ok(Some(parse_head_response(&String::from_utf8_lossy(&output.stdout)))),
+        Some(CURL_HTTP_ERROR) => Ok(None),
+        _ => Err(format!(
+            "could not reach '{}' (curl {}): {}",
+            url,
+            output.status,
+            // curl reports every retry; the last line is enough
+            String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or_default()
+        )
+        .into()),
+    }
+}
+
+/// Reads Last-Modified and Content-Length from the headers of a HEAD request's final response (after any
+/// redirects, each of which comes with its own block of headers).
+fn parse_head_response(text: &str) -> RemoteFile {
+    let last_block = text.trim_end().rsplit("\r\n\r\n").next().unwrap_or_default();
+    let mut remote = RemoteFile::default();
+    for line in last_block.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_lowercase().as_str() {
+            "last-modified" => {
+                remote.last_modified = DateTime::parse_from_rfc2822(value).ok().map(|t| t.with_timezone(&Utc));
+            }
+            "content-length" => remote.size = value.parse().ok(),
+            _ => {}
+        }
+    }
+    remote
+}
+
+/// The member of a zip archive to read: its first CSV file, or else the Generation and Fuel workbook of an
+/// EIA-923 archive (any XLSX workbook if none is named so).
+fn data_member(members: &str) -> Option<&str> {
+    let lines = || members.lines().filter(|m| !m.ends_with('/'));
+    let named = |m: &str, suffix: &str| m.to_lowercase().ends_with(suffix);
+    lines()
+        .find(|m| named(m, ".csv"))
+        .or_else(|| lines().find(|m| named(m, ".xlsx") && m.to_lowercase().contains(GENERATION_AND_FUEL_WORKBOOK)))
+        .or_else(|| lines().find(|m| named(m, ".xlsx")))
+}
+
+/// Extracts the data file of a zip archive (see `data_member`) next to it, with `unzip`.
+fn extract_data_file(archive: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let listing = Command::new("unzip")
+        .arg("-Z1")
+        .arg(archive)
+        .output()
+        .map_err(|e| format!("could not run unzip, which zip URL inputs need: {}", e))?;
+    let members = String::from_utf8_lossy(&listing.stdout);
+    let member = data_member(&members)
+        .ok_or_else(|| format!("'{}' has no CSV file or XLSX workbook", archive.display()))?;
+
+    let file_name = Path::new(member).file_name().ok_or("empty zip member name")?;
+    let target = archive.with_file_name(file_name);
+    let status = Command::new("unzip").arg("-p").arg(archive).arg(member).stdout(File::create(&target)?).status()?;
+    if !status.success() {
+        return Err(format!("could not extract '{}' from '{}' ({})", member, archive.display(), status).into());
+    }
+    Ok(target)
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_inputs() {
+        assert!(is_url("https://www.eia.gov/electricity/data/eia923/2020.csv"));
+        assert!(!is_url("../data_csv_files/2020.csv"));
+        assert_eq!(url_file_name("https://example.org/data/2020.csv?download=1"), Some("2020.csv"));
+        assert_eq!(url_file_name("https://example.org/data/"), None);
+        assert_eq!(split_checksum("https://example.org/f.zip#sha256=ab12"), ("https://example.org/f.zip", Some("ab12")));
+        assert_eq!(split_checksum("https://example.org/f.zip"), ("https://example.org/f.zip", None));
+        assert_eq!(eia923_archive_url(2018), "https://www.eia.gov/electricity/data/eia923/xls/f923_2018.zip");
+    }
+
+    #[test]
+    fn test_data_member() {
+        let archive = "EIA923_Schedule_8_Annual_Environmental_Information_2020_Final_Revision.xlsx\n\
+                       EIA923_Schedules_2_3_4_5_M_12_2020_Final_Revision.xlsx\n";
+        assert_eq!(data_member(archive), Some("EIA923_Schedules_2_3_4_5_M_12_2020_Final_Revision.xlsx"));
+        assert_eq!(data_member("notes/\nnotes/2020.xlsx\n2020.csv\n"), Some("2020.csv"));
+        assert_eq!(data_member("readme.txt\n"), None);
+    }
+
+    #[test]
+    fn test_parse_head_response() {
+        let head = "HTTP/1.1 301 Moved Permanently\r\nLocation: /xls/f923_2020.zip\r\nContent-Length: 10\r\n\r\n\
+                    HTTP/1.1 200 OK\r\nlast-modified: Tue, 13 Jul 2021 15:04:05 GMT\r\nContent-Length: 9876\r\n\r\n";
+        let remote = parse_head_response(head);
+        assert_eq!(remote.size, Some(9876));
+        assert_eq!(remote.last_modified.unwrap().to_rfc3339(), "2021-07-13T15:04:05+00:00");
+        assert_eq!(parse_head_response("HTTP/1.1 200 OK\r\n\r\n"), RemoteFile::default());
+    }
+
+    #[test]
+    fn test_local_paths() {
+        let path = download_path("https://example.org/a/f923_2020.zip#sha256=ab", "cache").unwrap();
+        assert!(path.starts_with("cache/downloads") && path.ends_with("f923_2020.zip"));
+
+        let options =
+            DownloadOptions { dir: "unused".to_string(), reuse: true, ca_cert: None, recording: Recording::Off };
+        assert_eq!(resolve_input("2020.csv", &options).unwrap(), "2020.csv");
+    }
+}
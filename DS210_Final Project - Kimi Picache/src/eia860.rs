@@ -0,0 +1,199 @@
+// eia860.rs
+// This module reads generator and utility attributes from EIA-860 CSV exports (the annual survey of generators that
+// EIA-923 operating data is usually joined with). The exports start with a title line above the column headers,
+// so the header row is found by name; headers are matched ignoring case and line breaks.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+
+use csv::{ReaderBuilder, StringRecord};
+use serde::Serialize;
+
+/// One generator from the EIA-860 generator schedule (3_1_Generator).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generator {
+    /// EIA plant code, as in EIA-923's "Plant Id".
+    pub plant_id: String,
+    pub utility_id: String,
+    pub generator_id: String,
+
+    /// Nameplate capacity (MW).
+    pub capacity_mw: f64,
+
+    /// Year the generator entered commercial operation, if reported.
+    pub operating_year: Option<i32>,
+}
+
+/// Header as matched: lower case, line breaks and repeated spaces collapsed.
+fn normalize(header: &str) -> String {
+    header.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Reads an EIA-860 CSV export whose header row names `key_column`, skipping any title lines above it.
+/// # Arguments
+/// * `reader` - CSV contents
+/// * `key_column` - A column the header row must have
+/// * `path` - File name for the messages
+/// # Returns
+/// * The normalized headers and the data rows below them
+pub fn read_eia860_table(
+    reader: impl Read,
+    key_column: &str,
+    path: &str,
+) -> Result<(Vec<String>, Vec<StringRecord>), Box<dyn Error>> {
+    let mut rdr = ReaderBuilder::new().has_headers(false).flexible(true).from_reader(reader);
+    let mut rows = rdr.records();
+
+    let key = normalize(key_column);
+    let headers: Vec<String> = loop {
+        match rows.next() {
+            Some(row) => {
+                let headers: Vec<String> = row?.iter().map(normalize).collect();
+                if headers.contains(&key) {
+                    break headers;
+                }
+            }
+            None => return Err(format!("'{}' has no '{}' column; is it an EIA-860 export?", path, key_column).into()),
+        }
+    };
+
+    let data = rows.filter_map(Result::ok).filter(|row| row.iter().any(|cell| !cell.trim().is_empty())).collect();
+    Ok((headers, data))
+}
+
+/// Position of a named column, or an error naming the file.
+pub fn column_index(headers: &[String], name: &str, path: &str) -> Result<usize, Box<dyn Error>> {
+    let name = normalize(name);
+    headers.iter().position(|h| *h == name).ok_or_else(|| format!("'{}' has no '{}' column", path, name).into())
+}
+
+/// A number cell of an EIA-860 export (thousands separators and blanks allowed).
+pub fn parse_number(cell: &str) -> Option<f64> {
+    cell.trim().replace(',', "").parse().ok()
+}
+
+/// Reads the generators of an EIA-860 generator schedule export.
+/// # Arguments
+/// * `path` - CSV export of the 3_1_Generator sheet (operable, proposed or retired units)
+/// # Returns
+/// * One `Generator` per row with a plant code
+pub fn read_generators(path: &str) -> Result<Vec<Generator>, Box<dyn Error>> {
+    parse_generators(File::open(path)?, path)
+}
+
+/// Parses a generator schedule export; see `read_generators`.
+pub fn parse_generators(reader: impl Read, path: &str) -> Result<Vec<Generator>, Box<dyn Error>> {
+    let (headers, rows) = read_eia860_table(reader, "Plant Code", path)?;
+    let plant = column_index(&headers, "Plant Code", path)?;
+    let utility = column_index(&headers, "Utility ID", path)?;
+    let generator = column_index(&headers, "Generator ID", path)?;
+    let capacity = column_index(&headers, "Nameplate Capacity (MW)", path)?;
+    let operating_year = column_index(&headers, "Operating Year", path)?;
+
+    let cell = |row: &StringRecord, i: usize| row.get(i).unwrap_or("").trim().to_string();
+    Ok(rows
+        .iter()
+        .filter(|row| !cell(row, plant).is_empty())
+        .map(|row| Generator {
+            plant_id: cell(row, plant),
+            utility_id: cell(row, utility),
+            generator_id: cell(row, generator),
+            capacity_mw: parse_number(&cell(row, capacity)).unwrap_or(0.0),
+            operating_year: cell(row, operating_year).parse().ok(),
+        })
+        .collect())
+}
+
+/// Ownership type of a utility, from the EIA-860 utility schedule's "Entity Type" code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OwnershipType {
+    InvestorOwned,
+    Municipal,
+    Cooperative,
+    Federal,
+    State,
+    PoliticalSubdivision,
+    /// Independent power producers, industrial and commercial generators (codes Q, IND, COM).
+    NonUtility,
+    Unknown,
+}
+
+impl OwnershipType {
+    /// Maps an "Entity Type" code; unrecognized or blank codes are `Unknown`.
+    pub fn from_code(code: &str) -> OwnershipType {
+        match code.trim().to_ascii_uppercase().as_str() {
+            "I" => OwnershipType::InvestorOwned,
+            "M" => OwnershipType::Municipal,
+            "C" => OwnershipType::Cooperative,
+            "F" => OwnershipType::Federal,
+            "S" => OwnershipType::State,
+            "P" => OwnershipType::PoliticalSubdivision,
+            "Q" | "IND" | "COM" => OwnershipType::NonUtility,
+            _ => OwnershipType::Unknown,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OwnershipType::InvestorOwned => "investor-owned",
+            OwnershipType::Municipal => "municipal",
+            OwnershipType::Cooperative => "cooperative",
+            OwnershipType::Federal => "federal",
+            OwnershipType::State => "state",
+            OwnershipType::PoliticalSubdivision => "political subdivision",
+            OwnershipType::NonUtility => "non-utility",
+            OwnershipType::Unknown => "unknown",
+        }
+    }
+}
+
+/// Reads the ownership type of each utility from an EIA-860 utility schedule export (1___Utility).
+/// # Returns
+/// * Ownership type keyed by utility ID
+pub fn read_utility_types(path: &str) -> Result<HashMap<String, OwnershipType>, Box<dyn Error>> {
+    parse_utility_types(File::open(path)?, path)
+}
+
+/// Parses a utility schedule export; see `read_utility_types`.
+pub fn parse_utility_types(reader: impl Read, path: &str) -> Result<HashMap<String, OwnershipType>, Box<dyn Error>> {
+    let (headers, rows) = read_eia860_table(reader, "Entity Type", path)?;
+    let utility = column_index(&headers, "Utility ID", path)?;
+    let entity_type = column_index(&headers, "Entity Type", path)?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let id = row.get(utility)?.trim();
+            (!id.is_empty()).then(|| (id.to_string(), OwnershipType::from_code(row.get(entity_type).unwrap_or(""))))
+        })
+        .collect())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_generator_export_below_title_line() {
+        let export = "Form EIA-860 Data - Schedule 3, 'Generator Data' (Operable Units Only)\n\
+             Utility ID,Utility Name,Plant Code,Plant Name,Generator ID,\"Nameplate Capacity\n(MW)\",Operating Year\n\
+             195,Alabama Power Co,3,Barry,1,\"1,153.5\",1954\n\
+             195,Alabama Power Co,3,Barry,ST2,,\n\
+             ,,,,,,\n";
+
+        let generators = parse_generators(export.as_bytes(), "3_1_Generator.csv").unwrap();
+        assert_eq!(generators.len(), 2);
+        assert_eq!((generators[0].capacity_mw, generators[0].operating_year), (1153.5, Some(1954)));
+        assert_eq!((generators[1].generator_id.as_str(), generators[1].operating_year), ("ST2", None));
+        assert!(read_eia860_table(export.as_bytes(), "Entity Type", "3_1_Generator.csv").is_err());
+
+        let utilities = "Utility ID,Utility Name,Entity Type\n195,Alabama Power Co,I\n1234,Rural Electric,C\n99,Mill,IND\n";
+        let types = parse_utility_types(utilities.as_bytes(), "1___Utility.csv").unwrap();
+        assert_eq!((types["195"], types["1234"]), (OwnershipType::InvestorOwned, OwnershipType::Cooperative));
+        assert_eq!(types["99"].label(), "non-utility");
+    }
+}
@@ -0,0 +1,323 @@
+// emissions.rs
+// This module converts efficiency changes into estimated CO2 emissions avoided, and fuel use into
+// SO2/NOx intensities, using per-fuel emission factors.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{CleanRecord, StateStats};
+use crate::analysis::StateEfficiency;
+use crate::monthly::format_optional;
+use crate::units::KG_PER_TONNE;
+
+/// Emission factors per MMBtu of fuel burned for a single fuel.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmissionFactors {
+    /// CO2 in kg per MMBtu.
+    pub co2_kg: f64,
+
+    /// SO2 in lb per MMBtu.
+    pub so2_lb: f64,
+
+    /// NOx in lb per MMBtu.
+    pub nox_lb: f64,
+}
+
+/// Returns the emission factors for an AER fuel type code.
+/// CO2 values follow the EPA GHG Emission Factors Hub; biomass, renewables and nuclear
+/// are treated as zero CO2 (biogenic CO2 is excluded, matching EIA convention).
+/// SO2/NOx values are approximate fleet-average rates derived from AP-42, suitable for
+/// screening-level comparisons rather than compliance reporting.
+pub fn emission_factors(aer_fuel: &str) -> EmissionFactors {
+    let (co2_kg, so2_lb, nox_lb) = match aer_fuel {
+        "COL" | "WOC" => (95.52, 0.45, 0.30),
+        "NG" => (53.06, 0.0006, 0.06),
+        "DFO" => (73.96, 0.05, 0.14),
+        "RFO" => (75.10, 1.05, 0.31),
+        "PC" => (102.41, 1.20, 0.35),
+        "OOG" => (59.00, 0.01, 0.10),
+        "WOO" => (74.00, 0.50, 0.13),
+        "WWW" | "ORW" => (0.0, 0.025, 0.22),
+        "MLG" => (0.0, 0.02, 0.15),
+        _ => (0.0, 0.0, 0.0),
+    };
+
+    EmissionFactors { co2_kg, so2_lb, nox_lb }
+}
+
+/// Computes each state's fuel-weighted CO2 intensity (kg CO2 per MMBtu of fuel burned).
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// # Returns
+/// * `HashMap<String, f64>` mapping state code to kg CO2 / MMBtu
+pub fn state_co2_intensity(records: &[CleanRecord]) -> HashMap<String, f64> {
+    let mut totals: HashMap<String, (f64, f64)> = HashMap::new();
+
+    for record in records {
+        let entry = totals.entry(record.state.clone()).or_default();
+        entry.0 += record.fuel * emission_factors(&record.aer_fuel).co2_kg;
+        entry.1 += record.fuel;
+    }
+
+    totals
+        .into_iter()
+        .filter(|(_, (_, fuel))| *fuel != 0.0)
+        .map(|(state, (co2, fuel))| (state, co2 / fuel))
+        .collect()
+}
+
+/// Estimated CO2 avoided in the compare year relative to base-year efficiency.
+#[derive(Debug, Serialize)]
+pub struct AvoidedEmissions {
+    /// State abbreviation (e.g., "CA", "TX").
+    pub state: String,
+
+    /// Net generation in the compare year (MWh).
+    pub gen_compare: f64,
+
+    /// Fuel saved by the efficiency change (MMBtu); negative means extra fuel burned.
+    pub avoided_fuel: f64,
+
+    /// Fuel-weighted CO2 intensity in the compare year (kg CO2 / MMBtu); `None` without fuel type codes.
+    pub co2_intensity: Option<f64>,
+
+    /// CO2 avoided in metric tons; negative means extra emissions.
+    pub avoided_co2_tons: Option<f64>,
+}
+
+/// Estimates CO2 avoided per state: the fuel that would have been burned at base-year
+/// efficiency minus the fuel actually burned, priced at the compare-year fuel mix.
+/// # Arguments
+/// * `changes` - Efficiency changes per state
+/// * `stats_compare` - Aggregated compare-year state data
+/// * `intensity` - Compare-year CO2 intensity per state (kg / MMBtu), or `None` when it cannot be known (an
+///   input without fuel type codes); the CO2 columns are then empty
+/// # Returns
+/// * `Vec<AvoidedEmissions>` in the same order as `changes`
+pub fn compute_avoided_emissions(
+    changes: &[StateEfficiency],
+    stats_compare: &HashMap<String, StateStats>,
+    intensity: Option<&HashMap<String, f64>>,
+) -> Vec<AvoidedEmissions> {
+    let mut output = Vec::new();
+
+    for change in changes {
+        let Some(stats) = stats_compare.get(&change.state) else {
+            continue;
+        };
+
+        let co2_intensity = intensity.map(|intensity| intensity.get(&change.state).copied().unwrap_or(0.0));
+        let avoided_fuel = (change.eff_2019 - change.eff_2020) * stats.total_gen.0;
+
+        output.push(AvoidedEmissions {
+            state: change.state.clone(),
+            gen_compare: stats.total_gen.0,
+            avoided_fuel,
+            co2_intensity,
+            avoided_co2_tons: co2_intensity.map(|intensity| avoided_fuel * intensity / KG_PER_TONNE),
+        });
+    }
+
+    output
+}
+
+/// Writes the avoided-emissions table to a CSV output file; the generation column is named after the compare
+/// input's label.
+pub fn write_avoided_emissions_csv(
+    path: &str,
+    data: &[AvoidedEmissions],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let [_, b] = labels;
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State".to_string(),
+        format!("Generation_{b}"),
+        "Avoided_Fuel_MMBtu".to_string(),
+        "CO2_Intensity_kg_per_MMBtu".to_string(),
+        "Avoided_CO2_Tons".to_string(),
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            &format!("{:.6}", item.gen_compare),
+            &format!("{:.6}", item.avoided_fuel),
+            &format_optional(item.co2_intensity),
+            &format_optional(item.avoided_co2_tons),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// SO2 and NOx emitted per MWh generated in the base and compare inputs for a state.
+#[derive(Debug, Serialize)]
+pub struct PollutantIntensity {
+    /// State abbreviation (e.g., "CA", "TX").
+    pub state: String,
+
+    /// SO2 intensity in the base input (lb / MWh).
+    pub so2_base: f64,
+
+    /// SO2 intensity in the compare input (lb / MWh).
+    pub so2_compare: f64,
+
+    /// NOx intensity in the base input (lb / MWh).
+    pub nox_base: f64,
+
+    /// NOx intensity in the compare input (lb / MWh).
+    pub nox_compare: f64,
+}
+
+/// Sums estimated SO2 and NOx (lb) and net generation (MWh) by state.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// # Returns
+/// * `HashMap<String, (f64, f64, f64)>` mapping state code to (SO2 lb, NOx lb, generation MWh)
+fn state_pollutant_totals(records: &[CleanRecord]) -> HashMap<String, (f64, f64, f64)> {
+    let mut totals: HashMap<String, (f64, f64, f64)> = HashMap::new();
+
+    for record in records {
+        let factors = emission_factors(&record.aer_fuel);
+        let entry = totals.entry(record.state.clone()).or_default();
+        entry.0 += record.fuel * factors.so2_lb;
+        entry.1 += record.fuel * factors.nox_lb;
+        entry.2 += record.r#gen;
+    }
+
+    totals
+}
+
+/// Computes per-state SO2 and NOx intensities (lb / MWh) for both inputs.
+/// # Arguments
+/// * `records_base` - Cleaned base rows
+/// * `records_compare` - Cleaned compare rows
+/// # Returns
+/// * `Vec<PollutantIntensity>` for states present in both inputs, sorted by state
+pub fn compute_pollutant_intensity(
+    records_base: &[CleanRecord],
+    records_compare: &[CleanRecord],
+) -> Vec<PollutantIntensity> {
+    let totals_base = state_pollutant_totals(records_base);
+    let totals_compare = state_pollutant_totals(records_compare);
+    let mut output = Vec::new();
+
+    for (state, (so2_a, nox_a, gen_a)) in &totals_base {
+        if let Some((so2_b, nox_b, gen_b)) = totals_compare.get(state) {
+            if *gen_a == 0.0 || *gen_b == 0.0 {
+                continue;
+            }
+
+            output.push(PollutantIntensity {
+                state: state.clone(),
+                so2_base: so2_a / gen_a,
+                so2_compare: so2_b / gen_b,
+                nox_base: nox_a / gen_a,
+                nox_compare: nox_b / gen_b,
+            });
+        }
+    }
+
+    output.sort_by(|a, b| a.state.cmp(&b.state));
+    output
+}
+
+/// Writes the SO2/NOx intensity table to a CSV output file, naming the columns after the input labels.
+pub fn write_pollutant_intensity_csv(
+    path: &str,
+    data: &[PollutantIntensity],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let [a, b] = labels;
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State".to_string(),
+        format!("SO2_lb_per_MWh_{a}"),
+        format!("SO2_lb_per_MWh_{b}"),
+        format!("NOx_lb_per_MWh_{a}"),
+        format!("NOx_lb_per_MWh_{b}"),
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            &format!("{:.6}", item.so2_base),
+            &format!("{:.6}", item.so2_compare),
+            &format!("{:.6}", item.nox_base),
+            &format!("{:.6}", item.nox_compare),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::analysis::compute_efficiency_changes;
+    use crate::cleaning::fixtures::record;
+    use crate::quantity::{MegawattHours, Mmbtu};
+
+    fn stats(fuel: f64, r#gen: f64) -> StateStats {
+        StateStats { total_fuel: Mmbtu(fuel), total_gen: MegawattHours(r#gen) }
+    }
+
+    #[test]
+    fn test_avoided_emissions() {
+        // AA improved from 10 to 8 MMBtu/MWh on 100 MWh; BB worsened from 8 to 9 on 50 MWh
+        let base = HashMap::from([("AA".to_string(), stats(1000.0, 100.0)), ("BB".to_string(), stats(400.0, 50.0))]);
+        let compare = HashMap::from([("AA".to_string(), stats(800.0, 100.0)), ("BB".to_string(), stats(450.0, 50.0))]);
+        let changes = compute_efficiency_changes(&base, &compare);
+        let intensity = HashMap::from([("AA".to_string(), 53.06)]);
+
+        let avoided = compute_avoided_emissions(&changes, &compare, Some(&intensity));
+        let aa = avoided.iter().find(|a| a.state == "AA").unwrap();
+        assert!((aa.avoided_fuel - 200.0).abs() < 1e-9);
+        assert!((aa.avoided_co2_tons.unwrap() - 200.0 * 53.06 / KG_PER_TONNE).abs() < 1e-9);
+        // Extra fuel is negative, and a state without an intensity counts as zero CO2
+        let bb = avoided.iter().find(|a| a.state == "BB").unwrap();
+        assert!((bb.avoided_fuel + 50.0).abs() < 1e-9);
+        assert_eq!((bb.co2_intensity, bb.avoided_co2_tons), (Some(0.0), Some(0.0)));
+
+        // Without intensities the CO2 columns are empty; states missing from the compare year are left out
+        let compare = HashMap::from([("AA".to_string(), stats(800.0, 100.0))]);
+        let avoided = compute_avoided_emissions(&changes, &compare, None);
+        assert_eq!(avoided.len(), 1);
+        assert_eq!((avoided[0].co2_intensity, avoided[0].avoided_co2_tons), (None, None));
+    }
+
+    #[test]
+    fn test_pollutant_intensity() {
+        let records_2019 = [
+            record("1", "AA", "COL", 1000.0, 100.0),
+            record("1", "AA", "NG", 1000.0, 100.0),
+            record("1", "BB", "NG", 500.0, 50.0),
+            // Generation without fuel is counted, but a state with no generation in a year has no intensity
+            record("1", "CC", "SUN", 0.0, 0.0),
+        ];
+        let records_2020 = [
+            record("1", "AA", "NG", 2000.0, 200.0),
+            record("1", "BB", "NG", 500.0, 50.0),
+            record("1", "CC", "SUN", 0.0, 10.0),
+            record("1", "DD", "COL", 100.0, 10.0),
+        ];
+
+        let intensity = compute_pollutant_intensity(&records_2019, &records_2020);
+        let states: Vec<&str> = intensity.iter().map(|i| i.state.as_str()).collect();
+        assert_eq!(states, ["AA", "BB"]);
+        let aa = &intensity[0];
+        assert!((aa.so2_base - (1000.0 * 0.45 + 1000.0 * 0.0006) / 200.0).abs() < 1e-9);
+        assert!((aa.nox_base - (1000.0 * 0.30 + 1000.0 * 0.06) / 200.0).abs() < 1e-9);
+        assert!((aa.so2_compare - 2000.0 * 0.0006 / 200.0).abs() < 1e-9);
+        assert!((aa.nox_compare - 0.6).abs() < 1e-9);
+        assert!((intensity[1].nox_base - intensity[1].nox_compare).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,257 @@
+// energybalance.rs
+// This module balances energy in against energy out for every state and plant: the electricity reported (3.412
+// MMBtu per MWh) cannot hold more energy than the fuel burned to make it, and even the best combined cycle plants
+// turn only about 60% of their fuel into electricity. A state or plant whose implied thermal efficiency falls
+// outside these bounds points to a data error (generation in kWh, fuel left off a row, a row under the wrong
+// plant) rather than a remarkable plant. States are balanced on the totals the heat rates come from; plants on
+// their fossil rows only, since the fuel EIA reports for hydro, wind and solar is a fossil equivalent and storage
+// reports none.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{aggregate_by_plant, aggregate_by_state, CleanRecord};
+use crate::fuels::FuelGroup;
+use crate::units::MMBTU_PER_MWH;
+
+/// Plausible thermal efficiency (electricity out / fuel in) of a state or plant. The ceiling is a little above
+/// the best combined cycle plants; the floor is low on purpose, since the fuel of combined heat and power plants
+/// also covers the heat they sell.
+pub const THERMAL_EFFICIENCY_BOUNDS: (f64, f64) = (0.02, 0.65);
+
+/// Why a state or plant fails the energy balance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BalanceFlag {
+    /// Generation reported without any fuel.
+    NoFuel,
+    /// More energy out than in (efficiency above 100%).
+    ExceedsFuel,
+    /// Above the efficiency ceiling but below 100%.
+    AboveCeiling,
+    /// Below the efficiency floor.
+    BelowFloor,
+}
+
+impl BalanceFlag {
+    /// Short label used in output files.
+    pub fn label(self) -> &'static str {
+        match self {
+            BalanceFlag::NoFuel => "generation without fuel",
+            BalanceFlag::ExceedsFuel => "more energy out than in",
+            BalanceFlag::AboveCeiling => "above the efficiency ceiling",
+            BalanceFlag::BelowFloor => "below the efficiency floor",
+        }
+    }
+}
+
+/// Checks fuel and generation totals against `THERMAL_EFFICIENCY_BOUNDS`.
+/// # Arguments
+/// * `fuel` - Fuel energy in, MMBtu
+/// * `generation` - Net generation, MWh
+/// # Returns
+/// * `None` when the balance is plausible or there is no positive generation to check
+pub fn check_balance(fuel: f64, generation: f64) -> Option<BalanceFlag> {
+    if generation <= 0.0 {
+        return None;
+    }
+    if fuel <= 0.0 {
+        return Some(BalanceFlag::NoFuel);
+    }
+    let (floor, ceiling) = THERMAL_EFFICIENCY_BOUNDS;
+    match generation * MMBTU_PER_MWH / fuel {
+        efficiency if efficiency > 1.0 => Some(BalanceFlag::ExceedsFuel),
+        efficiency if efficiency > ceiling => Some(BalanceFlag::AboveCeiling),
+        efficiency if efficiency < floor => Some(BalanceFlag::BelowFloor),
+        _ => None,
+    }
+}
+
+/// Energy in and out of one state or plant in one year.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnergyBalance {
+    pub year: String,
+    pub state: String,
+
+    /// Plant code and name, `None` for a state total.
+    pub plant: Option<(String, String)>,
+
+    /// Fuel energy in (MMBtu) and net generation (MWh).
+    pub fuel: f64,
+    pub generation: f64,
+
+    pub flag: Option<BalanceFlag>,
+}
+
+impl EnergyBalance {
+    /// Electricity out in MMBtu.
+    pub fn output(&self) -> f64 {
+        self.generation * MMBTU_PER_MWH
+    }
+
+    /// Implied thermal efficiency (0-1), or `None` without fuel.
+    pub fn efficiency(&self) -> Option<f64> {
+        (self.fuel > 0.0).then(|| self.output() / self.fuel)
+    }
+}
+
+/// Balances every state of one year, and the fossil plants failing the balance.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year (every fuel, like the state totals)
+/// * `year` - Label of the year
+/// * `plants` - Whether to check plants too (needs the Plant Id and fuel type columns)
+/// # Returns
+/// * `Vec<EnergyBalance>` with every state alphabetically, then the flagged plants by state and plant code
+pub fn energy_balance(records: &[CleanRecord], year: &str, plants: bool) -> Vec<EnergyBalance> {
+    let mut states: Vec<EnergyBalance> = aggregate_by_state(records)
+        .into_iter()
+        .map(|(state, stats)| EnergyBalance {
+            year: year.to_string(),
+            state,
+            plant: None,
+            fuel: stats.total_fuel.0,
+            generation: stats.total_gen.0,
+            flag: check_balance(stats.total_fuel.0, stats.total_gen.0),
+        })
+        .collect();
+    states.sort_by(|a, b| a.state.cmp(&b.state));
+
+    let mut flagged: Vec<EnergyBalance> = match plants {
+        true => aggregate_by_plant(records.iter().filter(|r| FuelGroup::from_aer(&r.aer_fuel).is_fossil()))
+            .into_values()
+            .filter_map(|plant| {
+                let flag = check_balance(plant.total_fuel, plant.total_gen)?;
+                Some(EnergyBalance {
+                    year: year.to_string(),
+                    state: plant.state,
+                    plant: Some((plant.plant_id, plant.plant_name)),
+                    fuel: plant.total_fuel,
+                    generation: plant.total_gen,
+                    flag: Some(flag),
+                })
+            })
+            .collect(),
+        false => Vec::new(),
+    };
+    flagged.sort_by(|a, b| a.state.cmp(&b.state).then_with(|| a.plant.cmp(&b.plant)));
+
+    states.extend(flagged);
+    states
+}
+
+/// Formats the failing states and plants of every year, with a count per flag, for the terminal.
+pub fn format_flagged(balances: &[EnergyBalance]) -> String {
+    let flagged: Vec<&EnergyBalance> = balances.iter().filter(|b| b.flag.is_some()).collect();
+    if flagged.is_empty() {
+        return "Every state and plant is within the bounds.\n".to_string();
+    }
+
+    let mut counts: HashMap<(&str, &str), usize> = HashMap::new();
+    for balance in &flagged {
+        let flag = balance.flag.map_or("", BalanceFlag::label);
+        *counts.entry((balance.year.as_str(), flag)).or_default() += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort();
+
+    let mut out = String::new();
+    for ((year, flag), count) in counts {
+        out += &format!("{}: {} {}\n", year, count, flag);
+    }
+    out += &format!(
+        "\n{:<6} {:<6} {:<40} {:>16} {:>16} {:>10}  {}\n",
+        "Year", "State", "Plant", "Fuel (MMBtu)", "Output (MMBtu)", "Efficiency", "Flag"
+    );
+    for balance in flagged {
+        let plant = match &balance.plant {
+            Some((id, name)) => format!("{} ({})", name.chars().take(30).collect::<String>(), id),
+            None => "(state total)".to_string(),
+        };
+        let efficiency = balance.efficiency().map_or("-".to_string(), |e| format!("{:.1}%", e * 100.0));
+        out += &format!(
+            "{:<6} {:<6} {:<40} {:>16.0} {:>16.0} {:>10}  {}\n",
+            balance.year,
+            balance.state,
+            plant,
+            balance.fuel,
+            balance.output(),
+            efficiency,
+            balance.flag.map_or("", BalanceFlag::label)
+        );
+    }
+    out
+}
+
+/// Writes the balances to a CSV output file; state rows leave the plant columns empty.
+pub fn write_energy_balance_csv(path: &str, balances: &[EnergyBalance]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "Year", "State", "Plant_Id", "Plant_Name", "Fuel_MMBtu", "Net_Generation_MWh", "Output_MMBtu",
+        "Implied_Efficiency", "Flag",
+    ])?;
+
+    for balance in balances {
+        let (plant_id, plant_name) = balance.plant.clone().unwrap_or_default();
+        wtr.write_record([
+            &balance.year,
+            &balance.state,
+            &plant_id,
+            &plant_name,
+            &format!("{:.6}", balance.fuel),
+            &format!("{:.6}", balance.generation),
+            &format!("{:.6}", balance.output()),
+            &balance.efficiency().map(|e| format!("{:.6}", e)).unwrap_or_default(),
+            &balance.flag.map(|f| f.label().to_string()).unwrap_or_default(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_check_balance() {
+        assert_eq!(check_balance(1000.0, 100.0), None);
+        assert_eq!(check_balance(0.0, 0.0), None);
+        assert_eq!(check_balance(0.0, 10.0), Some(BalanceFlag::NoFuel));
+        assert_eq!(check_balance(300.0, 100.0), Some(BalanceFlag::ExceedsFuel));
+        assert_eq!(check_balance(500.0, 100.0), Some(BalanceFlag::AboveCeiling));
+        assert_eq!(check_balance(20_000.0, 100.0), Some(BalanceFlag::BelowFloor));
+    }
+
+    #[test]
+    fn test_energy_balance_by_plant() {
+        // Plant 2's generation looks like kWh; TX as a whole still balances
+        let records = [
+            record("1", "TX", "NG", 10_000.0, 1000.0),
+            record("2", "TX", "NG", 100.0, 50.0),
+            record("3", "OK", "NG", 900.0, 100.0),
+        ];
+        let balances = energy_balance(&records, "2020", true);
+        assert_eq!(balances.len(), 3);
+        assert_eq!((balances[0].state.as_str(), balances[0].flag), ("OK", None));
+        assert_eq!(balances[1].flag, None);
+        assert_eq!(balances[2].plant, Some(("2".to_string(), "Plant 2".to_string())));
+        assert_eq!(balances[2].flag, Some(BalanceFlag::ExceedsFuel));
+        assert!((balances[2].efficiency().unwrap() - 1.706).abs() < 1e-9);
+        assert_eq!(energy_balance(&records, "2020", false).len(), 2);
+    }
+
+    #[test]
+    fn test_format_flagged() {
+        let records = [record("1", "TX", "NG", 10_000.0, 1000.0), record("2", "TX", "NG", 100.0, 50.0)];
+        let balances = energy_balance(&records, "2020", true);
+        let text = format_flagged(&balances);
+        assert!(text.starts_with("2020: 1 more energy out than in\n"), "{}", text);
+        assert!(text.contains("Plant 2 (2)"), "{}", text);
+    }
+}
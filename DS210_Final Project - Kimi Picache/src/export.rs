@@ -0,0 +1,59 @@
+// export.rs
+// This module lists the formats the main result tables can be written in (each written by a sink, see `sink`),
+// writes JSON files and adds the optional metadata preamble to CSV files.
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufWriter;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// File format for the main result tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum OutputFormat {
+    /// Comma-separated values (the default).
+    Csv,
+    /// A JSON array of row objects.
+    Json,
+    /// A Parquet file with one column per field (needs the `parquet` feature).
+    Parquet,
+    /// A SQLite database holding the rows in a table named after the file (needs the `sqlite` feature).
+    Sqlite,
+}
+
+impl OutputFormat {
+    /// File extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Csv => "csv",
+            OutputFormat::Json => "json",
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Sqlite => "sqlite",
+        }
+    }
+}
+
+/// Writes any serializable table to `path` as pretty-printed JSON.
+pub fn write_json<T: Serialize + ?Sized>(path: &str, data: &T) -> Result<(), Box<dyn Error>> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, data)?;
+    Ok(())
+}
+
+/// Prepends `# `-prefixed comment lines to an already written file. Readers that do not skip comments
+/// (e.g. `csv::ReaderBuilder` without `.comment(Some(b'#'))`) will see them, hence the preamble is optional.
+pub fn prepend_preamble(path: &str, lines: &[String]) -> Result<(), Box<dyn Error>> {
+    let body = fs::read_to_string(path)?;
+    let mut text = String::new();
+    for line in lines {
+        text.push_str("# ");
+        text.push_str(line);
+        text.push('\n');
+    }
+    text.push_str(&body);
+    fs::write(path, text)?;
+    Ok(())
+}
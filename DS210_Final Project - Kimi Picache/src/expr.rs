@@ -0,0 +1,220 @@
+// expr.rs
+// This module parses and evaluates the small arithmetic expressions used to define custom metric columns,
+// e.g. `heat_rate_2020 - national_median_2020` or `abs(delta) / heat_rate_2019 * 100`.
+
+use std::collections::BTreeMap;
+
+/// A parsed expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// Functions available in expressions, with their argument counts.
+const FUNCTIONS: [(&str, usize); 6] = [("abs", 1), ("sqrt", 1), ("ln", 1), ("exp", 1), ("min", 2), ("max", 2)];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = input.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse().map_err(|_| format!("invalid number '{}'", text))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if "+-*/(),".contains(c) {
+            tokens.push(Token::Op(c));
+            i += 1;
+        } else {
+            return Err(format!("unexpected character '{}'", c));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive-descent parser over the token list; `+ -` bind looser than `* /`, unary minus tightest.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, op: char) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Op(c)) if c == op => Ok(()),
+            _ => Err(format!("expected '{}'", op)),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut left = self.product()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/'))) = self.peek().cloned() {
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Op('-')) {
+            self.pos += 1;
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::Op('(')) => {
+                self.pos += 1;
+                let mut args = vec![self.sum()?];
+                while self.peek() == Some(&Token::Op(',')) {
+                    self.pos += 1;
+                    args.push(self.sum()?);
+                }
+                self.expect(')')?;
+
+                match FUNCTIONS.iter().find(|(f, _)| *f == name) {
+                    Some((_, arity)) if *arity == args.len() => Ok(Expr::Call(name, args)),
+                    Some((_, arity)) => Err(format!("{}() takes {} argument(s)", name, arity)),
+                    None => Err(format!("unknown function '{}'", name)),
+                }
+            }
+            Some(Token::Ident(name)) => Ok(Expr::Variable(name)),
+            Some(Token::Op('(')) => {
+                let inner = self.sum()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Some(Token::Op(c)) => Err(format!("unexpected '{}'", c)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+/// Parses an expression.
+pub fn parse_expr(input: &str) -> Result<Expr, String> {
+    let mut parser = Parser { tokens: tokenize(input)?, pos: 0 };
+    let expr = parser.sum()?;
+    if parser.pos < parser.tokens.len() {
+        return Err(format!("unexpected trailing input in '{}'", input));
+    }
+    Ok(expr)
+}
+
+impl Expr {
+    /// Names of every variable the expression refers to.
+    pub fn variables(&self) -> Vec<&str> {
+        match self {
+            Expr::Number(_) => Vec::new(),
+            Expr::Variable(name) => vec![name.as_str()],
+            Expr::Negate(inner) => inner.variables(),
+            Expr::Binary(_, left, right) => {
+                let mut names = left.variables();
+                names.extend(right.variables());
+                names
+            }
+            Expr::Call(_, args) => args.iter().flat_map(|a| a.variables()).collect(),
+        }
+    }
+
+    /// Evaluates the expression; unknown variables are an error, division by zero gives infinity or NaN.
+    pub fn eval(&self, vars: &BTreeMap<&str, f64>) -> Result<f64, String> {
+        Ok(match self {
+            Expr::Number(value) => *value,
+            Expr::Variable(name) => *vars.get(name.as_str()).ok_or_else(|| format!("unknown variable '{}'", name))?,
+            Expr::Negate(inner) => -inner.eval(vars)?,
+            Expr::Binary(op, left, right) => {
+                let (a, b) = (left.eval(vars)?, right.eval(vars)?);
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    _ => a / b,
+                }
+            }
+            Expr::Call(name, args) => {
+                let values = args.iter().map(|a| a.eval(vars)).collect::<Result<Vec<f64>, String>>()?;
+                match name.as_str() {
+                    "abs" => values[0].abs(),
+                    "sqrt" => values[0].sqrt(),
+                    "ln" => values[0].ln(),
+                    "exp" => values[0].exp(),
+                    "min" => values[0].min(values[1]),
+                    _ => values[0].max(values[1]),
+                }
+            }
+        })
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence_and_functions() {
+        let vars = BTreeMap::from([("a", 2.0), ("b", 3.0)]);
+
+        assert_eq!(parse_expr("a + b * 4").unwrap().eval(&vars), Ok(14.0));
+        assert_eq!(parse_expr("(a + b) * 4").unwrap().eval(&vars), Ok(20.0));
+        assert_eq!(parse_expr("-a - -b").unwrap().eval(&vars), Ok(1.0));
+        assert_eq!(parse_expr("max(a, b) / abs(-2)").unwrap().eval(&vars), Ok(1.5));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse_expr("a +").is_err());
+        assert!(parse_expr("a b").is_err());
+        assert!(parse_expr("foo(1)").is_err());
+        assert!(parse_expr("min(1)").is_err());
+        assert_eq!(parse_expr("pop * 2").unwrap().eval(&BTreeMap::new()), Err("unknown variable 'pop'".to_string()));
+    }
+}
@@ -0,0 +1,292 @@
+// fleet.rs
+// This module separates fleet turnover from operational change: it splits each state's compare-year plants into
+// those that already reported in the base year and new entrants, and estimates how much of each state's change
+// came from plants that retired.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{aggregate_by_state_plant, CleanRecord, PlantStats, StateStats, INCREMENT_PLANT_ID};
+use crate::monthly::format_optional;
+use crate::quantity::{MegawattHours, Mmbtu};
+
+/// A state's compare-year plants split into the existing fleet and new entrants.
+#[derive(Debug, Default, Serialize)]
+pub struct FleetSplit {
+    pub state: String,
+
+    /// Plants reporting generation in both years (the state-level increment counts as one).
+    pub existing_plants: usize,
+
+    /// Plants with compare-year generation but none in the base year.
+    pub new_plants: usize,
+
+    /// Base-year totals of the existing plants.
+    pub existing_2019: StateStats,
+
+    /// Compare-year totals of the existing plants.
+    pub existing_2020: StateStats,
+
+    /// Compare-year totals of the new plants.
+    pub new_2020: StateStats,
+}
+
+/// Fuel used per MWh generated, or `None` without positive generation.
+fn heat_rate(stats: &StateStats) -> Option<f64> {
+    stats.heat_rate().map(|rate| rate.0)
+}
+
+impl FleetSplit {
+    /// Operational change: the existing plants' compare-year minus base-year heat rate.
+    pub fn existing_delta(&self) -> Option<f64> {
+        Some(heat_rate(&self.existing_2020)? - heat_rate(&self.existing_2019)?)
+    }
+
+    /// Share of the state's compare-year generation from new plants (0-1).
+    pub fn new_gen_share(&self) -> f64 {
+        let total = self.existing_2020.total_gen + self.new_2020.total_gen;
+        if total.0 > 0.0 { self.new_2020.total_gen / total } else { 0.0 }
+    }
+}
+
+fn add(stats: &mut StateStats, plant: &PlantStats) {
+    stats.total_fuel += Mmbtu(plant.total_fuel);
+    stats.total_gen += MegawattHours(plant.total_gen);
+}
+
+/// Splits each state's compare-year fleet by whether the plant generated in the base year.
+/// # Arguments
+/// * `records_2019` - Cleaned rows for the base year
+/// * `records_2020` - Cleaned rows for the compare year
+/// # Returns
+/// * `Vec<FleetSplit>` sorted by state, for states with compare-year generation
+pub fn split_fleet(records_2019: &[CleanRecord], records_2020: &[CleanRecord]) -> Vec<FleetSplit> {
+    let plants_2019 = aggregate_by_state_plant(records_2019);
+    let plants_2020 = aggregate_by_state_plant(records_2020);
+    let mut splits: BTreeMap<String, FleetSplit> = BTreeMap::new();
+
+    for (key, plant) in &plants_2020 {
+        if plant.total_gen <= 0.0 {
+            continue;
+        }
+
+        let split = splits
+            .entry(key.0.clone())
+            .or_insert_with(|| FleetSplit { state: key.0.clone(), ..Default::default() });
+
+        match existing_plant(&plants_2019, key) {
+            Some(base) => {
+                split.existing_plants += 1;
+                add(&mut split.existing_2019, base);
+                add(&mut split.existing_2020, plant);
+            }
+            None => {
+                split.new_plants += 1;
+                add(&mut split.new_2020, plant);
+            }
+        }
+    }
+
+    splits.into_values().collect()
+}
+
+/// The plant's base-year totals if it generated then; the state-level increment always counts as existing.
+fn existing_plant<'a>(plants_2019: &'a HashMap<(String, String), PlantStats>, key: &(String, String)) -> Option<&'a PlantStats> {
+    plants_2019
+        .get(key)
+        .filter(|base| base.total_gen > 0.0 || key.1 == INCREMENT_PLANT_ID)
+}
+
+/// A state's actual compare-year heat rate next to a counterfactual in which its retired plants kept running.
+#[derive(Debug, Default, Serialize)]
+pub struct RetirementImpact {
+    pub state: String,
+
+    /// Plants with base-year generation and none in the compare year.
+    pub retired_plants: usize,
+
+    /// Base-year totals of the retired plants.
+    pub retired_2019: StateStats,
+
+    /// State totals for both years.
+    pub state_2019: StateStats,
+    pub state_2020: StateStats,
+}
+
+impl RetirementImpact {
+    /// Compare-year heat rate if the retired plants had generated as in the base year, at their base-year
+    /// heat rate.
+    pub fn counterfactual_2020(&self) -> Option<f64> {
+        heat_rate(&StateStats {
+            total_fuel: self.state_2020.total_fuel + self.retired_2019.total_fuel,
+            total_gen: self.state_2020.total_gen + self.retired_2019.total_gen,
+        })
+    }
+
+    /// Actual change in the state's heat rate.
+    pub fn delta(&self) -> Option<f64> {
+        Some(heat_rate(&self.state_2020)? - heat_rate(&self.state_2019)?)
+    }
+
+    /// Part of the change due to retirements alone: actual minus counterfactual compare-year heat rate
+    /// (negative when the retired plants were less efficient than the rest).
+    pub fn retirement_effect(&self) -> Option<f64> {
+        Some(heat_rate(&self.state_2020)? - self.counterfactual_2020()?)
+    }
+
+    /// Retirement effect as a percentage of the actual change.
+    pub fn pct_of_delta(&self) -> Option<f64> {
+        let delta = self.delta().filter(|d| *d != 0.0)?;
+        Some(self.retirement_effect()? / delta * 100.0)
+    }
+}
+
+/// Estimates for every state how much of its change came from plants that stopped generating.
+/// # Arguments
+/// * `records_2019` - Cleaned rows for the base year
+/// * `records_2020` - Cleaned rows for the compare year
+/// # Returns
+/// * `Vec<RetirementImpact>` sorted by state, for states with generation in both years
+pub fn retirement_impacts(records_2019: &[CleanRecord], records_2020: &[CleanRecord]) -> Vec<RetirementImpact> {
+    let plants_2019 = aggregate_by_state_plant(records_2019);
+    let plants_2020 = aggregate_by_state_plant(records_2020);
+    let mut impacts: BTreeMap<String, RetirementImpact> = BTreeMap::new();
+
+    for (year, plants) in [&plants_2019, &plants_2020].into_iter().enumerate() {
+        for ((state, _), plant) in plants {
+            let impact = impacts
+                .entry(state.clone())
+                .or_insert_with(|| RetirementImpact { state: state.clone(), ..Default::default() });
+            add(if year == 0 { &mut impact.state_2019 } else { &mut impact.state_2020 }, plant);
+        }
+    }
+
+    for (key, plant) in &plants_2019 {
+        let still_running = plants_2020.get(key).is_some_and(|p| p.total_gen > 0.0);
+        if plant.total_gen <= 0.0 || still_running || key.1 == INCREMENT_PLANT_ID {
+            continue;
+        }
+        if let Some(impact) = impacts.get_mut(&key.0) {
+            impact.retired_plants += 1;
+            add(&mut impact.retired_2019, plant);
+        }
+    }
+
+    impacts
+        .into_values()
+        .filter(|i| i.state_2019.total_gen.0 > 0.0 && i.state_2020.total_gen.0 > 0.0)
+        .collect()
+}
+
+/// Writes the retirement counterfactual to a CSV output file, naming the per-input columns after the input labels.
+pub fn write_retirement_impact_csv(
+    path: &str,
+    data: &[RetirementImpact],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let [a, b] = labels;
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State".to_string(),
+        "Retired_Plants".to_string(),
+        format!("Retired_Gen_{a}"),
+        format!("Retired_Heat_Rate_{a}"),
+        format!("Heat_Rate_{a}"),
+        format!("Heat_Rate_{b}"),
+        format!("Counterfactual_Heat_Rate_{b}"),
+        "Delta".to_string(),
+        "Retirement_Effect".to_string(),
+        "Pct_Of_Delta".to_string(),
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            item.state.clone(),
+            item.retired_plants.to_string(),
+            format!("{:.6}", item.retired_2019.total_gen),
+            format_optional(heat_rate(&item.retired_2019)),
+            format_optional(heat_rate(&item.state_2019)),
+            format_optional(heat_rate(&item.state_2020)),
+            format_optional(item.counterfactual_2020()),
+            format_optional(item.delta()),
+            format_optional(item.retirement_effect()),
+            format_optional(item.pct_of_delta()),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Writes the existing vs new fleet split to a CSV output file, naming the per-input columns after the input
+/// labels.
+pub fn write_fleet_split_csv(path: &str, data: &[FleetSplit], labels: [&str; 2]) -> Result<(), Box<dyn Error>> {
+    let [a, b] = labels;
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State".to_string(),
+        "Existing_Plants".to_string(),
+        "New_Plants".to_string(),
+        format!("Existing_Heat_Rate_{a}"),
+        format!("Existing_Heat_Rate_{b}"),
+        "Existing_Delta".to_string(),
+        format!("New_Heat_Rate_{b}"),
+        format!("New_Gen_Share_{b}"),
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            item.state.clone(),
+            item.existing_plants.to_string(),
+            item.new_plants.to_string(),
+            format_optional(heat_rate(&item.existing_2019)),
+            format_optional(heat_rate(&item.existing_2020)),
+            format_optional(item.existing_delta()),
+            format_optional(heat_rate(&item.new_2020)),
+            format!("{:.6}", item.new_gen_share()),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_split_existing_and_new_plants() {
+        let base = [record("1", "AA", "NG", 100.0, 10.0), record("2", "AA", "NG", 50.0, 0.0)];
+        let compare = [
+            record("1", "AA", "NG", 90.0, 10.0),
+            record("2", "AA", "NG", 70.0, 10.0),
+            record("3", "AA", "NG", 0.0, 5.0),
+        ];
+
+        let split = &split_fleet(&base, &compare)[0];
+        assert_eq!((split.existing_plants, split.new_plants), (1, 2));
+        assert_eq!(split.existing_delta(), Some(-1.0));
+        assert_eq!(heat_rate(&split.new_2020), Some(70.0 / 15.0));
+        assert!((split.new_gen_share() - 0.6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_retirement_counterfactual() {
+        // Plant 2 (heat rate 12) retires; plant 1 keeps its heat rate of 8
+        let base = [record("1", "AA", "NG", 80.0, 10.0), record("2", "AA", "NG", 120.0, 10.0)];
+        let compare = [record("1", "AA", "NG", 160.0, 20.0), record("2", "AA", "NG", 0.0, 0.0)];
+
+        let impact = &retirement_impacts(&base, &compare)[0];
+        assert_eq!(impact.retired_plants, 1);
+        assert_eq!(impact.delta(), Some(-2.0));
+        assert_eq!(impact.counterfactual_2020(), Some(280.0 / 30.0));
+        assert!((impact.retirement_effect().unwrap() - (8.0 - 280.0 / 30.0)).abs() < 1e-12);
+        assert!((impact.pct_of_delta().unwrap() - (280.0 / 30.0 - 8.0) / 2.0 * 100.0).abs() < 1e-9);
+    }
+}
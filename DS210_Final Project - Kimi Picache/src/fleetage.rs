@@ -0,0 +1,173 @@
+// fleetage.rs
+// This module joins EIA-860 generator operating years onto the plants of each state and reports the
+// capacity-weighted age of each state's fossil fleet next to its heat rate, with the correlation between the two
+// across states; older units are usually less efficient, so age explains part of the state ranking.
+
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{aggregate_by_state, CleanRecord, StateStats, INCREMENT_PLANT_ID};
+use crate::eia860::Generator;
+use crate::fuels::FuelGroup;
+use crate::join::{JoinKey, JoinReport, JoinType, KeyIndex};
+use crate::monthly::format_optional;
+use crate::stats::{linear_regression, spearman};
+
+/// Capacity-weighted fleet age of a state's fossil plants in one year.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FleetAge {
+    pub state: String,
+    pub year: i32,
+
+    /// Fossil plants in the data, and those with EIA-860 generators in service by `year`.
+    pub plants: usize,
+    pub matched_plants: usize,
+
+    /// Nameplate capacity of the matched generators (MW).
+    pub capacity_mw: f64,
+
+    /// Capacity-weighted mean of `year` minus each generator's operating year, or `None` without matches.
+    pub age: Option<f64>,
+
+    /// The state's heat rate that year (MMBtu/MWh), as in the main results.
+    pub heat_rate: Option<f64>,
+}
+
+/// Computes each state's fossil fleet age in `year` from the generators of its fossil plants (by plant code).
+/// Generators without an operating year, or entering service after `year`, are left out.
+/// # Arguments
+/// * `records` - Cleaned rows for the year
+/// * `year` - Year the ages are measured in
+/// * `generators` - EIA-860 generators
+/// * `join_type` - Whether plants without any generator still count towards `plants` (left) or not (inner)
+/// # Returns
+/// * `Vec<FleetAge>` sorted by state, and the report of the plant join
+pub fn fleet_ages(
+    records: &[CleanRecord],
+    year: i32,
+    generators: &[Generator],
+    join_type: JoinType,
+) -> (Vec<FleetAge>, JoinReport) {
+    let by_plant = KeyIndex::new(JoinKey::PlantId, generators, |g| &g.plant_id);
+    let fossil: Vec<&CleanRecord> = records
+        .iter()
+        .filter(|r| r.plant_id != INCREMENT_PLANT_ID && FuelGroup::from_aer(&r.aer_fuel).is_fossil())
+        .collect();
+    let report = by_plant.report(
+        &format!("EIA-860 generators ({})", year),
+        fossil.iter().map(|r| r.plant_id.as_str()),
+        join_type,
+    );
+
+    let mut plants: BTreeMap<&str, HashSet<&str>> = BTreeMap::new();
+    for record in fossil.into_iter().filter(|r| report.keeps(&r.plant_id)) {
+        plants.entry(&record.state).or_default().insert(&record.plant_id);
+    }
+    let stats = aggregate_by_state(records);
+
+    let ages = plants
+        .into_iter()
+        .map(|(state, ids)| {
+            let (mut matched, mut capacity, mut weighted_age) = (0, 0.0, 0.0);
+            for id in &ids {
+                let in_service: Vec<(&Generator, i32)> = by_plant
+                    .get(id)
+                    .iter()
+                    .filter_map(|g| g.operating_year.filter(|y| *y <= year).map(|y| (*g, y)))
+                    .collect();
+                if !in_service.is_empty() {
+                    matched += 1;
+                }
+                for (generator, operating_year) in in_service {
+                    capacity += generator.capacity_mw;
+                    weighted_age += generator.capacity_mw * (year - operating_year) as f64;
+                }
+            }
+
+            FleetAge {
+                state: state.to_string(),
+                year,
+                plants: ids.len(),
+                matched_plants: matched,
+                capacity_mw: capacity,
+                age: (capacity > 0.0).then(|| weighted_age / capacity),
+                heat_rate: stats.get(state).and_then(StateStats::heat_rate).map(|rate| rate.0),
+            }
+        })
+        .collect();
+    (ages, report)
+}
+
+/// Pearson and Spearman correlation between fleet age and heat rate across the states with both.
+pub fn age_heat_rate_correlation(ages: &[FleetAge]) -> (Option<f64>, Option<f64>) {
+    let (age, heat_rate): (Vec<f64>, Vec<f64>) = ages.iter().filter_map(|a| a.age.zip(a.heat_rate)).unzip();
+    let pearson = linear_regression(&age, &heat_rate).map(|fit| fit.r_squared.sqrt().copysign(fit.slope));
+    (pearson, spearman(&age, &heat_rate))
+}
+
+/// Writes the fleet ages to a CSV output file.
+pub fn write_fleet_age_csv(path: &str, data: &[FleetAge]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["State", "Year", "Plants", "Matched_Plants", "Capacity_MW", "Fleet_Age_Years", "Heat_Rate"])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            &item.year.to_string(),
+            &item.plants.to_string(),
+            &item.matched_plants.to_string(),
+            &format!("{:.6}", item.capacity_mw),
+            &format_optional(item.age),
+            &format_optional(item.heat_rate),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    fn generator(plant_id: &str, capacity_mw: f64, operating_year: Option<i32>) -> Generator {
+        Generator {
+            plant_id: plant_id.to_string(),
+            utility_id: "1".to_string(),
+            generator_id: "1".to_string(),
+            capacity_mw,
+            operating_year,
+        }
+    }
+
+    #[test]
+    fn test_capacity_weighted_fleet_age() {
+        let records = [
+            record("1", "AA", "COL", 1100.0, 100.0),
+            record("2", "AA", "NG", 700.0, 100.0),
+            record("3", "AA", "WND", 0.0, 100.0),
+            record("4", "BB", "NG", 800.0, 100.0),
+        ];
+        // Plant 2 has a unit still under construction in 2020; the wind plant is not part of the fossil fleet
+        let generators = [
+            generator("1", 300.0, Some(1970)),
+            generator("2", 100.0, Some(2010)),
+            generator("2", 500.0, Some(2021)),
+            generator("3", 50.0, Some(2015)),
+            generator("4", 200.0, None),
+        ];
+
+        let (ages, report) = fleet_ages(&records, 2020, &generators, JoinType::Left);
+        assert_eq!((ages[0].plants, ages[0].matched_plants, ages[0].capacity_mw), (2, 2, 400.0));
+        assert_eq!(ages[0].age, Some((300.0 * 50.0 + 100.0 * 10.0) / 400.0));
+        assert_eq!(ages[0].heat_rate, Some(6.0));
+        assert_eq!((ages[1].matched_plants, ages[1].age), (0, None));
+        assert_eq!(age_heat_rate_correlation(&ages), (None, None));
+        assert_eq!((report.matched(), report.unmatched_right.len()), (3, 1));
+    }
+}
@@ -0,0 +1,174 @@
+// forecast.rs
+// This module forecasts each state's next-year heat rate from the panel using a linear trend and
+// simple exponential smoothing, each with a 95% prediction interval.
+
+use std::error::Error;
+
+use csv::WriterBuilder;
+
+use crate::panel::YearData;
+use crate::stats::{linear_regression, t_critical_95};
+
+/// Smoothing factors tried when fitting exponential smoothing.
+const ALPHA_GRID: [f64; 9] = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9];
+
+/// A point forecast with its 95% prediction interval.
+#[derive(Debug, Clone, Copy)]
+pub struct Forecast {
+    pub value: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Next-year forecasts for one state.
+#[derive(Debug)]
+pub struct StateForecast {
+    pub state: String,
+    pub next_year: i32,
+    pub trend: Option<Forecast>,
+    pub smoothing_alpha: Option<f64>,
+    pub smoothing: Option<Forecast>,
+}
+
+/// Forecasts `years` one step ahead with an OLS trend; needs at least three points for an interval.
+pub fn trend_forecast(years: &[f64], values: &[f64], next_year: f64) -> Option<Forecast> {
+    let n = values.len();
+    if n < 3 {
+        return None;
+    }
+
+    let fit = linear_regression(years, values)?;
+    let mean_x = years.iter().sum::<f64>() / n as f64;
+    let sxx: f64 = years.iter().map(|x| (x - mean_x) * (x - mean_x)).sum();
+    let sse: f64 = years
+        .iter()
+        .zip(values)
+        .map(|(x, y)| {
+            let residual = y - (fit.intercept + fit.slope * x);
+            residual * residual
+        })
+        .sum();
+
+    let s = (sse / (n - 2) as f64).sqrt();
+    let value = fit.intercept + fit.slope * next_year;
+    let margin = t_critical_95(n - 2) * s * (1.0 + 1.0 / n as f64 + (next_year - mean_x).powi(2) / sxx).sqrt();
+
+    Some(Forecast { value, lower: value - margin, upper: value + margin })
+}
+
+/// Runs simple exponential smoothing, returning (final level, one-step-ahead SSE).
+fn smooth(values: &[f64], alpha: f64) -> (f64, f64) {
+    let mut level = values[0];
+    let mut sse = 0.0;
+    for value in &values[1..] {
+        let error = value - level;
+        sse += error * error;
+        level += alpha * error;
+    }
+    (level, sse)
+}
+
+/// Forecasts one step ahead with simple exponential smoothing, choosing alpha by one-step SSE.
+/// # Returns
+/// * `(alpha, Forecast)`, or `None` with fewer than three points
+pub fn smoothing_forecast(values: &[f64]) -> Option<(f64, Forecast)> {
+    if values.len() < 3 {
+        return None;
+    }
+
+    let (alpha, (level, sse)) = ALPHA_GRID
+        .iter()
+        .map(|&alpha| (alpha, smooth(values, alpha)))
+        .min_by(|a, b| a.1.1.partial_cmp(&b.1.1).unwrap())?;
+
+    // One-step errors are available for all but the first observation
+    let sigma = (sse / (values.len() - 1) as f64).sqrt();
+    let margin = 1.96 * sigma;
+
+    Some((alpha, Forecast { value: level, lower: level - margin, upper: level + margin }))
+}
+
+/// Forecasts every state's heat rate for the year after the last loaded year.
+/// # Arguments
+/// * `panel` - Loaded years, ascending
+/// # Returns
+/// * `Vec<StateForecast>` sorted by state
+pub fn forecast_states(panel: &[YearData]) -> Vec<StateForecast> {
+    let Some(last_year) = panel.last().map(|y| y.year) else {
+        return Vec::new();
+    };
+    let next_year = last_year + 1;
+
+    let mut states: Vec<&String> = panel.iter().flat_map(|y| y.stats.keys()).collect();
+    states.sort();
+    states.dedup();
+
+    let mut output = Vec::new();
+    for state in states {
+        let mut years = Vec::new();
+        let mut values = Vec::new();
+        for year_data in panel {
+            if let Some(stats) = year_data.stats.get(state)
+                && stats.total_gen.0 != 0.0
+            {
+                years.push(year_data.year as f64);
+                values.push((stats.total_fuel / stats.total_gen).0);
+            }
+        }
+
+        let smoothing = smoothing_forecast(&values);
+        output.push(StateForecast {
+            state: state.clone(),
+            next_year,
+            trend: trend_forecast(&years, &values, next_year as f64),
+            smoothing_alpha: smoothing.map(|(alpha, _)| alpha),
+            smoothing: smoothing.map(|(_, forecast)| forecast),
+        });
+    }
+
+    output
+}
+
+/// Writes the forecasts to a CSV output file; forecasts that need more years are left empty.
+pub fn write_forecast_csv(path: &str, data: &[StateForecast]) -> Result<(), Box<dyn Error>> {
+    let fmt = |v: Option<f64>| v.map(|v| format!("{:.6}", v)).unwrap_or_default();
+
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State", "Forecast_Year", "Trend_Forecast", "Trend_Lower_95", "Trend_Upper_95", "Smoothing_Alpha",
+        "Smoothing_Forecast", "Smoothing_Lower_95", "Smoothing_Upper_95",
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            &item.next_year.to_string(),
+            &fmt(item.trend.map(|f| f.value)),
+            &fmt(item.trend.map(|f| f.lower)),
+            &fmt(item.trend.map(|f| f.upper)),
+            &item.smoothing_alpha.map(|a| format!("{:.1}", a)).unwrap_or_default(),
+            &fmt(item.smoothing.map(|f| f.value)),
+            &fmt(item.smoothing.map(|f| f.lower)),
+            &fmt(item.smoothing.map(|f| f.upper)),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trend_forecast_extends_line() {
+        let years = [2016.0, 2017.0, 2018.0, 2019.0, 2020.0];
+        let values = [10.4, 10.3, 10.2, 10.1, 10.0];
+
+        let forecast = trend_forecast(&years, &values, 2021.0).unwrap();
+        assert!((forecast.value - 9.9).abs() < 1e-9);
+        assert!(forecast.lower <= forecast.value && forecast.value <= forecast.upper);
+    }
+}
@@ -0,0 +1,250 @@
+// frontier.rs
+// This module finds the best-performing (lowest heat rate) plants per fuel group and each state's gap to that frontier.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{aggregate_by_plant, CleanRecord, PlantStats};
+use crate::fuels::FuelGroup;
+use crate::units::MMBTU_PER_MWH;
+
+/// Plants generating less than this (MWh) are left out of the frontier; their heat rates are too noisy.
+const MIN_PLANT_GEN: f64 = 1000.0;
+
+/// Heat rate of a perfectly efficient plant (MMBtu/MWh); anything lower is a data error.
+const MIN_HEAT_RATE: f64 = MMBTU_PER_MWH;
+
+/// Number of best plants listed per scope and fuel group.
+const TOP_PLANTS: usize = 5;
+
+/// One of the best-performing plants for a fuel group, nationally or within a state.
+#[derive(Debug, Serialize)]
+pub struct FrontierPlant {
+    /// "US" for the national list, otherwise the state code.
+    pub scope: String,
+    pub fuel_group: FuelGroup,
+    pub rank: usize,
+    pub plant: PlantStats,
+    pub heat_rate: f64,
+}
+
+/// A state's heat rate for a fuel group compared with the best plants.
+#[derive(Debug, Serialize)]
+pub struct FrontierGap {
+    pub state: String,
+    pub fuel_group: FuelGroup,
+
+    /// Generation-weighted heat rate of the state's eligible plants burning this fuel.
+    pub state_heat_rate: f64,
+
+    /// Heat rate of the state's best plant for this fuel.
+    pub state_best: f64,
+
+    /// Heat rate of the best plant nationally for this fuel.
+    pub national_frontier: f64,
+
+    /// `state_heat_rate - national_frontier` (MMBtu/MWh of improvement potential).
+    pub gap: f64,
+
+    /// Generation from the state's plants burning this fuel (MWh).
+    pub total_gen: f64,
+}
+
+/// Frontier plants and state gaps for a single year.
+#[derive(Debug, Default)]
+pub struct FrontierReport {
+    pub plants: Vec<FrontierPlant>,
+    pub gaps: Vec<FrontierGap>,
+}
+
+/// Returns a plant's heat rate if it is large and plausible enough to be compared.
+pub fn eligible_heat_rate(plant: &PlantStats) -> Option<f64> {
+    if plant.total_gen < MIN_PLANT_GEN {
+        return None;
+    }
+
+    let heat_rate = plant.total_fuel / plant.total_gen;
+    (heat_rate >= MIN_HEAT_RATE).then_some(heat_rate)
+}
+
+/// Builds the frontier report for each fossil fuel group.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// # Returns
+/// * `FrontierReport` with the top plants per scope and each state's gap to the national frontier
+pub fn compute_frontier(records: &[CleanRecord]) -> FrontierReport {
+    let mut report = FrontierReport::default();
+
+    let mut groups: Vec<FuelGroup> = records
+        .iter()
+        .map(|r| FuelGroup::from_aer(&r.aer_fuel))
+        .filter(|g| g.is_fossil())
+        .collect();
+    groups.sort();
+    groups.dedup();
+
+    for group in groups {
+        let plants = aggregate_by_plant(records.iter().filter(|r| FuelGroup::from_aer(&r.aer_fuel) == group));
+
+        let mut ranked: Vec<(PlantStats, f64)> = plants
+            .into_values()
+            .filter_map(|p| eligible_heat_rate(&p).map(|hr| (p, hr)))
+            .collect();
+        ranked.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then_with(|| a.0.plant_id.cmp(&b.0.plant_id)));
+
+        let Some(national_frontier) = ranked.first().map(|(_, hr)| *hr) else {
+            continue;
+        };
+
+        // National list, then one list per state (ranked is already sorted by heat rate)
+        let mut by_state: BTreeMap<String, Vec<&(PlantStats, f64)>> = BTreeMap::new();
+        for entry in &ranked {
+            by_state.entry(entry.0.state.clone()).or_default().push(entry);
+        }
+
+        for (rank, (plant, heat_rate)) in ranked.iter().take(TOP_PLANTS).enumerate() {
+            report.plants.push(FrontierPlant {
+                scope: "US".to_string(),
+                fuel_group: group,
+                rank: rank + 1,
+                plant: plant.clone(),
+                heat_rate: *heat_rate,
+            });
+        }
+
+        for (state, state_plants) in &by_state {
+            for (rank, (plant, heat_rate)) in state_plants.iter().take(TOP_PLANTS).enumerate() {
+                report.plants.push(FrontierPlant {
+                    scope: state.clone(),
+                    fuel_group: group,
+                    rank: rank + 1,
+                    plant: plant.clone(),
+                    heat_rate: *heat_rate,
+                });
+            }
+
+            let total_fuel: f64 = state_plants.iter().map(|(p, _)| p.total_fuel).sum();
+            let total_gen: f64 = state_plants.iter().map(|(p, _)| p.total_gen).sum();
+            let state_heat_rate = total_fuel / total_gen;
+
+            report.gaps.push(FrontierGap {
+                state: state.clone(),
+                fuel_group: group,
+                state_heat_rate,
+                state_best: state_plants[0].1,
+                national_frontier,
+                gap: state_heat_rate - national_frontier,
+                total_gen,
+            });
+        }
+    }
+
+    report
+}
+
+/// Writes the frontier plant lists to a CSV output file.
+pub fn write_frontier_plants_csv(path: &str, data: &[FrontierPlant]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "Scope", "Fuel_Group", "Rank", "Plant_Id", "Plant_Name", "State", "Heat_Rate", "Generation",
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.scope,
+            item.fuel_group.label(),
+            &item.rank.to_string(),
+            &item.plant.plant_id,
+            &item.plant.plant_name,
+            &item.plant.state,
+            &format!("{:.6}", item.heat_rate),
+            &format!("{:.6}", item.plant.total_gen),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Writes each state's gap to the national frontier to a CSV output file.
+pub fn write_frontier_gaps_csv(path: &str, data: &[FrontierGap]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State", "Fuel_Group", "State_Heat_Rate", "State_Best_Heat_Rate", "National_Frontier", "Gap_To_Frontier",
+        "Generation",
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            item.fuel_group.label(),
+            &format!("{:.6}", item.state_heat_rate),
+            &format!("{:.6}", item.state_best),
+            &format!("{:.6}", item.national_frontier),
+            &format!("{:.6}", item.gap),
+            &format!("{:.6}", item.total_gen),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_frontier_membership() {
+        let records = [
+            record("1", "AA", "NG", 70_000.0, 10_000.0),
+            record("2", "BB", "NG", 90_000.0, 10_000.0),
+            // Too small, and implausibly efficient: neither is on the frontier
+            record("3", "AA", "NG", 500.0, 100.0),
+            record("4", "BB", "NG", 20_000.0, 10_000.0),
+            // Not a fossil fuel
+            record("5", "AA", "SUN", 0.0, 50_000.0),
+        ];
+        let report = compute_frontier(&records);
+
+        let national: Vec<&str> =
+            report.plants.iter().filter(|p| p.scope == "US").map(|p| p.plant.plant_id.as_str()).collect();
+        assert_eq!(national, ["1", "2"]);
+        assert!(report.plants.iter().all(|p| p.fuel_group == FuelGroup::NaturalGas));
+
+        let bb = report.gaps.iter().find(|g| g.state == "BB").unwrap();
+        assert_eq!((bb.state_best, bb.national_frontier, bb.gap), (9.0, 7.0, 2.0));
+        assert_eq!(bb.total_gen, 10_000.0);
+    }
+
+    #[test]
+    fn test_frontier_ties_and_single_state() {
+        // Plants with the same heat rate are ranked by plant id; one state is its own national frontier
+        let records = [
+            record("20", "AA", "COL", 100_000.0, 10_000.0),
+            record("10", "AA", "COL", 100_000.0, 10_000.0),
+            record("30", "AA", "COL", 120_000.0, 10_000.0),
+        ];
+        let report = compute_frontier(&records);
+        let national: Vec<(&str, usize)> = report
+            .plants
+            .iter()
+            .filter(|p| p.scope == "US")
+            .map(|p| (p.plant.plant_id.as_str(), p.rank))
+            .collect();
+        assert_eq!(national, [("10", 1), ("20", 2), ("30", 3)]);
+        assert_eq!(report.plants.len(), 6);
+
+        assert_eq!(report.gaps.len(), 1);
+        let gap = &report.gaps[0];
+        assert_eq!((gap.state_best, gap.national_frontier), (10.0, 10.0));
+        assert!((gap.gap - (320_000.0 / 30_000.0 - 10.0)).abs() < 1e-12);
+
+        assert!(compute_frontier(&[]).gaps.is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+// fuelmap.rs
+// This module maps AER fuel type codes, optionally narrowed by prime mover, to named fuel groups. The built-in
+// mapping ships as data ('mappings/fuel_groups.csv') and backs `FuelGroup::from_aer`; a mapping file of the same
+// shape can regroup the codes for the custom fuel group report, e.g. natural gas split into combined cycle and
+// peaking turbines.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+use std::sync::LazyLock;
+
+use csv::{ReaderBuilder, Trim, WriterBuilder};
+use serde::Deserialize;
+
+use crate::cleaning::{CleanRecord, StateStats};
+use crate::fueltrends::NATIONAL_SCOPE;
+use crate::monthly::format_optional;
+use crate::quantity::{MegawattHours, Mmbtu};
+
+/// The built-in mapping: one row per AER code with a blank prime mover.
+pub const DEFAULT_FUEL_MAPPING: &str = include_str!("../mappings/fuel_groups.csv");
+
+/// Group of the rows whose fuel code a mapping does not cover.
+pub const UNMAPPED_GROUP: &str = "Unmapped";
+
+static DEFAULT_MAPPING: LazyLock<FuelMapping> = LazyLock::new(|| {
+    parse_fuel_mapping(DEFAULT_FUEL_MAPPING.as_bytes(), "mappings/fuel_groups.csv")
+        .expect("built-in fuel mapping is valid")
+});
+
+#[derive(Debug, Deserialize)]
+struct MappingRow {
+    #[serde(rename = "AER_Fuel")]
+    aer_fuel: String,
+    #[serde(rename = "Prime_Mover", default)]
+    prime_mover: String,
+    #[serde(rename = "Fuel_Group")]
+    fuel_group: String,
+}
+
+/// Rules for one AER code.
+#[derive(Debug, Clone, Default)]
+struct CodeRules {
+    /// Group for any prime mover without a rule of its own.
+    any: Option<String>,
+    by_prime_mover: HashMap<String, String>,
+}
+
+/// AER code (and prime mover) to fuel group mapping.
+#[derive(Debug, Clone, Default)]
+pub struct FuelMapping {
+    rules: HashMap<String, CodeRules>,
+}
+
+impl FuelMapping {
+    /// Group of a row: the rule for its code and prime mover, else the rule for its code alone.
+    pub fn group(&self, aer_fuel: &str, prime_mover: &str) -> Option<&str> {
+        let rules = self.rules.get(aer_fuel)?;
+        rules.by_prime_mover.get(prime_mover).or(rules.any.as_ref()).map(String::as_str)
+    }
+
+    /// Distinct group names, sorted.
+    pub fn groups(&self) -> Vec<&str> {
+        let mut groups: Vec<&str> = self
+            .rules
+            .values()
+            .flat_map(|rules| rules.any.iter().chain(rules.by_prime_mover.values()))
+            .map(String::as_str)
+            .collect();
+        groups.sort_unstable();
+        groups.dedup();
+        groups
+    }
+}
+
+/// The built-in mapping, parsed once.
+pub fn default_fuel_mapping() -> &'static FuelMapping {
+    &DEFAULT_MAPPING
+}
+
+/// Reads a fuel mapping file: a CSV with AER_Fuel, Fuel_Group and optionally Prime_Mover columns. A blank prime
+/// mover matches any; a rule naming a prime mover takes precedence over the code's blank one.
+/// # Arguments
+/// * `path` - Mapping file
+/// # Returns
+/// * `Err` for a row without a code or group, or a code and prime mover mapped twice
+pub fn read_fuel_mapping(path: &str) -> Result<FuelMapping, Box<dyn Error>> {
+    parse_fuel_mapping(File::open(path)?, path)
+}
+
+/// Parses a fuel mapping; see `read_fuel_mapping`.
+pub fn parse_fuel_mapping(reader: impl Read, path: &str) -> Result<FuelMapping, Box<dyn Error>> {
+    let mut rdr = ReaderBuilder::new().trim(Trim::All).from_reader(reader);
+    let mut mapping = FuelMapping::default();
+
+    for (i, row) in rdr.deserialize::<MappingRow>().enumerate() {
+        let line = i + 2;
+        let row = row.map_err(|e| format!("'{}' line {}: {}", path, line, e))?;
+        let code = row.aer_fuel.to_ascii_uppercase();
+        if code.is_empty() || row.fuel_group.is_empty() {
+            return Err(format!("'{}' line {}: AER_Fuel and Fuel_Group must not be blank", path, line).into());
+        }
+
+        let rules = mapping.rules.entry(code.clone()).or_default();
+        let prime_mover = row.prime_mover.to_ascii_uppercase();
+        let previous = if prime_mover.is_empty() {
+            rules.any.replace(row.fuel_group)
+        } else {
+            rules.by_prime_mover.insert(prime_mover.clone(), row.fuel_group)
+        };
+        if previous.is_some() {
+            let mover = if prime_mover.is_empty() { "any prime mover".to_string() } else { prime_mover };
+            return Err(format!("'{}' line {}: {} ({}) is mapped twice", path, line, code, mover).into());
+        }
+    }
+    Ok(mapping)
+}
+
+/// Fuel codes of rows the mapping has no group for, with their row counts.
+pub fn unmapped_codes(records: &[CleanRecord], mapping: &FuelMapping) -> BTreeMap<String, usize> {
+    let mut codes = BTreeMap::new();
+    for record in records.iter().filter(|r| mapping.group(&r.aer_fuel, &r.prime_mover).is_none()) {
+        *codes.entry(record.aer_fuel.clone()).or_default() += 1;
+    }
+    codes
+}
+
+/// Totals of one mapped fuel group within a scope, for both years.
+#[derive(Debug)]
+pub struct MappedFuelEfficiency {
+    /// "US" for the national total, otherwise the state code.
+    pub scope: String,
+    pub group: String,
+    pub stats: [StateStats; 2],
+}
+
+impl MappedFuelEfficiency {
+    /// Heat rate in year index 0 or 1, or `None` without positive generation.
+    pub fn heat_rate(&self, year: usize) -> Option<f64> {
+        self.stats[year].heat_rate().map(|rate| rate.0)
+    }
+}
+
+/// Sums both years' fuel and generation by scope and mapped fuel group; rows the mapping does not cover are
+/// grouped as `UNMAPPED_GROUP`.
+/// # Arguments
+/// * `records` - Cleaned rows of the two years
+/// * `mapping` - Fuel mapping
+/// # Returns
+/// * `Vec<MappedFuelEfficiency>` with the national rows first, then states alphabetically, each by group
+pub fn efficiency_by_fuel_mapping(records: [&[CleanRecord]; 2], mapping: &FuelMapping) -> Vec<MappedFuelEfficiency> {
+    let mut groups: BTreeMap<(String, String), [StateStats; 2]> = BTreeMap::new();
+    for (year, year_records) in records.iter().enumerate() {
+        for record in year_records.iter() {
+            let group = mapping.group(&record.aer_fuel, &record.prime_mover).unwrap_or(UNMAPPED_GROUP);
+            for scope in [NATIONAL_SCOPE, record.state.as_str()] {
+                let stats = &mut groups.entry((scope.to_string(), group.to_string())).or_default()[year];
+                stats.total_fuel += Mmbtu(record.fuel);
+                stats.total_gen += MegawattHours(record.r#gen);
+            }
+        }
+    }
+
+    let mut output: Vec<MappedFuelEfficiency> = groups
+        .into_iter()
+        .map(|((scope, group), stats)| MappedFuelEfficiency { scope, group, stats })
+        .collect();
+    output.sort_by_key(|item| item.scope != NATIONAL_SCOPE);
+    output
+}
+
+/// Writes the mapped fuel group heat rates to a CSV output file.
+pub fn write_mapped_fuel_groups_csv(
+    path: &str,
+    data: &[MappedFuelEfficiency],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "Scope".to_string(),
+        "Fuel_Group".to_string(),
+        format!("Heat_Rate_{}", labels[0]),
+        format!("Heat_Rate_{}", labels[1]),
+        "Delta".to_string(),
+        format!("Generation_MWh_{}", labels[1]),
+    ])?;
+
+    for item in data {
+        let delta = item.heat_rate(0).zip(item.heat_rate(1)).map(|(a, b)| b - a);
+        wtr.write_record([
+            item.scope.clone(),
+            item.group.clone(),
+            format_optional(item.heat_rate(0)),
+            format_optional(item.heat_rate(1)),
+            format_optional(delta),
+            format!("{:.6}", item.stats[1].total_gen),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+    use crate::fuels::FuelGroup;
+
+    #[test]
+    fn test_gas_split_by_prime_mover() {
+        // Every built-in group is a fuel group label
+        for group in default_fuel_mapping().groups() {
+            assert_eq!(FuelGroup::from_label(group).map(FuelGroup::label), Some(group));
+        }
+
+        let file = "AER_Fuel,Prime_Mover,Fuel_Group\nNG,CT,Gas CC\nng,ca,Gas CC\nNG,GT,Gas peaker\nNG,,Gas other\n";
+        let mapping = parse_fuel_mapping(file.as_bytes(), "gas.csv").unwrap();
+        assert_eq!((mapping.group("NG", "CA"), mapping.group("NG", "IC")), (Some("Gas CC"), Some("Gas other")));
+        assert_eq!(mapping.group("COL", "ST"), None);
+        assert!(parse_fuel_mapping("AER_Fuel,Fuel_Group\nNG,A\nNG,B\n".as_bytes(), "dup.csv").is_err());
+
+        let records = [
+            CleanRecord { prime_mover: "CT".to_string(), ..record("1", "AA", "NG", 700.0, 100.0) },
+            CleanRecord { prime_mover: "GT".to_string(), ..record("1", "AA", "NG", 1100.0, 100.0) },
+            CleanRecord { prime_mover: "ST".to_string(), ..record("1", "AA", "COL", 10.0, 1.0) },
+        ];
+        assert_eq!(unmapped_codes(&records, &mapping), BTreeMap::from([("COL".to_string(), 1)]));
+        let groups = efficiency_by_fuel_mapping([&records, &records[..1]], &mapping);
+        let names: Vec<(&str, &str)> = groups.iter().map(|g| (g.scope.as_str(), g.group.as_str())).collect();
+        assert_eq!(&names[..3], [("US", "Gas CC"), ("US", "Gas peaker"), ("US", UNMAPPED_GROUP)]);
+        assert_eq!((groups[1].heat_rate(0), groups[1].heat_rate(1)), (Some(11.0), None));
+    }
+}
@@ -0,0 +1,247 @@
+// fuelprices.rs
+// This module puts a dollar figure on each state's efficiency change: the fuel it saved (or burned extra) in the
+// compare year, priced at the delivered fuel costs plants reported on EIA-923 Page 5 (Fuel Receipts and Costs).
+// Page 5 gives each delivery's quantity, heat content and cost in cents per MMBtu; deliveries are weighted by
+// their heat to a price per state and fuel group. A state's price is then the average over its compare-year
+// fossil fuel mix, using the national price of a fuel group where the state has no priced delivery of it (EIA
+// withholds the costs of many non-utility plants). Page 5 covers fossil fuel deliveries only, so the saved fuel
+// is priced as fossil fuel.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::analysis::StateEfficiency;
+use crate::cleaning::{CleanRecord, StateStats};
+use crate::eia860::{column_index, parse_number, read_eia860_table};
+use crate::emissions::compute_avoided_emissions;
+use crate::fuels::FuelGroup;
+use crate::monthly::format_optional;
+
+/// Page 5 reports fuel costs in cents per MMBtu.
+const CENTS_PER_DOLLAR: f64 = 100.0;
+
+/// Delivered fuel prices ($/MMBtu) by state and fuel group, and nationally.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FuelPrices {
+    pub by_state: HashMap<(String, FuelGroup), f64>,
+    pub national: HashMap<FuelGroup, f64>,
+
+    /// Deliveries that were priced, and those without a cost (withheld) or without a quantity.
+    pub priced: usize,
+    pub unpriced: usize,
+}
+
+/// Fuel group of a Page 5 "FUEL_GROUP" value; petroleum coke is grouped with petroleum, as in the AER mapping.
+fn page5_group(label: &str) -> Option<FuelGroup> {
+    match label.trim() {
+        "Petroleum Coke" => Some(FuelGroup::Petroleum),
+        label => FuelGroup::from_label(label).filter(|group| group.is_fossil()),
+    }
+}
+
+/// Reads the delivered fuel prices of a Page 5 export.
+/// # Arguments
+/// * `path` - CSV export of EIA-923 Page 5 (Fuel Receipts and Costs), title lines above the header allowed
+/// # Returns
+/// * Heat-weighted prices per state and fuel group, and per fuel group nationally
+pub fn read_fuel_prices(path: &str) -> Result<FuelPrices, Box<dyn Error>> {
+    parse_fuel_prices(File::open(path)?, path)
+}
+
+/// Parses a Page 5 export; see `read_fuel_prices`.
+pub fn parse_fuel_prices(reader: impl Read, path: &str) -> Result<FuelPrices, Box<dyn Error>> {
+    let (headers, rows) = read_eia860_table(reader, "FUEL_COST", path)?;
+    let state = column_index(&headers, "Plant State", path)?;
+    let group = column_index(&headers, "FUEL_GROUP", path)?;
+    let quantity = column_index(&headers, "QUANTITY", path)?;
+    let heat_content = column_index(&headers, "Average Heat Content", path)?;
+    let cost = column_index(&headers, "FUEL_COST", path)?;
+
+    // (cost in cents, MMBtu) per state and group
+    let mut sums: HashMap<(String, FuelGroup), (f64, f64)> = HashMap::new();
+    let mut prices = FuelPrices::default();
+    for row in &rows {
+        let cell = |i: usize| row.get(i).unwrap_or("");
+        let Some(group) = page5_group(cell(group)) else {
+            continue;
+        };
+        let mmbtu = parse_number(cell(quantity)).zip(parse_number(cell(heat_content))).map(|(q, h)| q * h);
+        match (mmbtu, parse_number(cell(cost))) {
+            (Some(mmbtu), Some(cents)) if mmbtu > 0.0 => {
+                let entry = sums.entry((cell(state).trim().to_string(), group)).or_default();
+                entry.0 += cents * mmbtu;
+                entry.1 += mmbtu;
+                prices.priced += 1;
+            }
+            _ => prices.unpriced += 1,
+        }
+    }
+
+    let mut national: HashMap<FuelGroup, (f64, f64)> = HashMap::new();
+    for ((state, group), (cents, mmbtu)) in sums {
+        let entry = national.entry(group).or_default();
+        entry.0 += cents;
+        entry.1 += mmbtu;
+        prices.by_state.insert((state, group), cents / mmbtu / CENTS_PER_DOLLAR);
+    }
+    prices.national =
+        national.into_iter().map(|(group, (cents, mmbtu))| (group, cents / mmbtu / CENTS_PER_DOLLAR)).collect();
+    Ok(prices)
+}
+
+/// A state's average delivered price over its fossil fuel mix.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatePrice {
+    /// $/MMBtu.
+    pub price: f64,
+
+    /// Share (0-1) of the state's fossil fuel priced with national rather than its own prices.
+    pub national_share: f64,
+}
+
+/// Averages the delivered prices over each state's fossil fuel use.
+/// # Arguments
+/// * `records` - Cleaned rows for the compare year
+/// * `prices` - Page 5 prices of the same year
+/// # Returns
+/// * `HashMap<String, StatePrice>` keyed by state; states burning no priced fossil fuel are left out
+pub fn state_fuel_prices(records: &[CleanRecord], prices: &FuelPrices) -> HashMap<String, StatePrice> {
+    let mut fuel: HashMap<(String, FuelGroup), f64> = HashMap::new();
+    for record in records {
+        let group = FuelGroup::from_aer(&record.aer_fuel);
+        if group.is_fossil() && record.fuel > 0.0 {
+            *fuel.entry((record.state.clone(), group)).or_default() += record.fuel;
+        }
+    }
+
+    // (cost in $, priced MMBtu, MMBtu priced nationally) per state
+    let mut sums: HashMap<String, (f64, f64, f64)> = HashMap::new();
+    for ((state, group), mmbtu) in fuel {
+        let own = prices.by_state.get(&(state.clone(), group));
+        let Some(price) = own.or_else(|| prices.national.get(&group)) else {
+            continue;
+        };
+        let entry = sums.entry(state).or_default();
+        entry.0 += price * mmbtu;
+        entry.1 += mmbtu;
+        if own.is_none() {
+            entry.2 += mmbtu;
+        }
+    }
+
+    sums.into_iter()
+        .map(|(state, (cost, mmbtu, national))| {
+            (state, StatePrice { price: cost / mmbtu, national_share: national / mmbtu })
+        })
+        .collect()
+}
+
+/// Fuel cost avoided by a state's efficiency change at compare-year prices.
+#[derive(Debug, Serialize)]
+pub struct FuelCostAvoided {
+    /// State abbreviation (e.g., "CA", "TX").
+    pub state: String,
+
+    /// Fuel saved by the efficiency change (MMBtu); negative means extra fuel burned.
+    pub avoided_fuel: f64,
+
+    /// Average delivered price of the state's fossil fuel ($/MMBtu); `None` without a priced fuel.
+    pub fuel_price: Option<f64>,
+
+    /// Share of the state's fossil fuel priced with national prices.
+    pub national_price_share: Option<f64>,
+
+    /// Fuel cost avoided ($); negative means extra cost.
+    pub avoided_cost: Option<f64>,
+}
+
+/// Prices the fuel each state saved: the fuel that would have been burned at base-year efficiency minus the fuel
+/// actually burned (as for the avoided CO2), times the state's compare-year price.
+/// # Arguments
+/// * `changes` - Efficiency changes per state
+/// * `stats_compare` - Aggregated compare-year state data
+/// * `prices` - Compare-year price per state, from `state_fuel_prices`
+/// # Returns
+/// * `Vec<FuelCostAvoided>` in the same order as `changes`
+pub fn compute_fuel_cost_avoided(
+    changes: &[StateEfficiency],
+    stats_compare: &HashMap<String, StateStats>,
+    prices: &HashMap<String, StatePrice>,
+) -> Vec<FuelCostAvoided> {
+    compute_avoided_emissions(changes, stats_compare, None)
+        .into_iter()
+        .map(|avoided| {
+            let price = prices.get(&avoided.state);
+            FuelCostAvoided {
+                avoided_fuel: avoided.avoided_fuel,
+                fuel_price: price.map(|p| p.price),
+                national_price_share: price.map(|p| p.national_share),
+                avoided_cost: price.map(|p| p.price * avoided.avoided_fuel),
+                state: avoided.state,
+            }
+        })
+        .collect()
+}
+
+/// Writes the fuel cost table to a CSV output file.
+pub fn write_fuel_cost_avoided_csv(path: &str, data: &[FuelCostAvoided]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State", "Avoided_Fuel_MMBtu", "Fuel_Price_USD_per_MMBtu", "National_Price_Share", "Avoided_Fuel_Cost_USD",
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            &format!("{:.6}", item.avoided_fuel),
+            &format_optional(item.fuel_price),
+            &format_optional(item.national_price_share),
+            &format_optional(item.avoided_cost),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    /// Receipts of TX gas and unpriced TX coal, and priced OK coal and petroleum coke.
+    fn prices() -> FuelPrices {
+        let export = "EIA-923 Page 5: Fuel Receipts and Costs\n\
+             YEAR,Plant State,FUEL_GROUP,QUANTITY,\"Average Heat\nContent\",FUEL_COST\n\
+             2020,TX,Natural Gas,\"1,000\",1.0,300\n\
+             2020,TX,Natural Gas,3000,1.0,200\n\
+             2020,TX,Coal,100,20,\n\
+             2020,OK,Coal,100,10,150\n\
+             2020,OK,Petroleum Coke,10,30,90\n";
+        parse_fuel_prices(export.as_bytes(), "page5.csv").unwrap()
+    }
+
+    #[test]
+    fn test_parse_fuel_prices() {
+        let prices = prices();
+        assert_eq!((prices.priced, prices.unpriced), (4, 1));
+        assert!((prices.by_state[&("TX".to_string(), FuelGroup::NaturalGas)] - 2.25).abs() < 1e-9);
+        assert!((prices.national[&FuelGroup::Coal] - 1.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_state_fuel_prices() {
+        // TX coal has no priced delivery in TX, so it takes the national coal price
+        let fuel = |aer_fuel, fuel: f64| record("1", "TX", aer_fuel, fuel, fuel / 10.0);
+        let records = [fuel("NG", 300.0), fuel("COL", 100.0), fuel("NUC", 500.0)];
+        let tx = state_fuel_prices(&records, &prices())["TX"];
+        assert!((tx.price - (2.25 * 300.0 + 1.5 * 100.0) / 400.0).abs() < 1e-9);
+        assert!((tx.national_share - 0.25).abs() < 1e-9);
+    }
+}
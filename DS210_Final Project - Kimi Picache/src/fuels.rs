@@ -0,0 +1,145 @@
+// fuels.rs
+// This module groups EIA-923 AER fuel type codes into the broader fuel categories used in the reports, and fossil
+// rows into generating technologies.
+
+use serde::{Deserialize, Serialize};
+
+use crate::fuelmap::default_fuel_mapping;
+
+/// Broad fuel category for an AER fuel type code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum FuelGroup {
+    Coal,
+    NaturalGas,
+    Petroleum,
+    OtherGas,
+    Nuclear,
+    Hydro,
+    Wind,
+    Solar,
+    Geothermal,
+    Biomass,
+    Other,
+}
+
+impl FuelGroup {
+    /// Maps an AER fuel type code (e.g., "COL", "NG") to its fuel group, using the built-in mapping in
+    /// 'mappings/fuel_groups.csv'; codes it does not list are `Other`.
+    pub fn from_aer(aer_fuel: &str) -> FuelGroup {
+        default_fuel_mapping().group(aer_fuel, "").and_then(FuelGroup::from_label).unwrap_or(FuelGroup::Other)
+    }
+
+    /// The group with this label (see `label`), if any.
+    pub fn from_label(label: &str) -> Option<FuelGroup> {
+        let groups = [
+            FuelGroup::Coal,
+            FuelGroup::NaturalGas,
+            FuelGroup::Petroleum,
+            FuelGroup::OtherGas,
+            FuelGroup::Nuclear,
+            FuelGroup::Hydro,
+            FuelGroup::Wind,
+            FuelGroup::Solar,
+            FuelGroup::Geothermal,
+            FuelGroup::Biomass,
+            FuelGroup::Other,
+        ];
+        groups.into_iter().find(|group| group.label() == label)
+    }
+
+    /// Whether the group is a fossil fuel (combustion heat rates are meaningful).
+    pub fn is_fossil(self) -> bool {
+        matches!(
+            self,
+            FuelGroup::Coal | FuelGroup::NaturalGas | FuelGroup::Petroleum | FuelGroup::OtherGas
+        )
+    }
+
+    /// Whether the group is a renewable source.
+    pub fn is_renewable(self) -> bool {
+        matches!(
+            self,
+            FuelGroup::Hydro | FuelGroup::Wind | FuelGroup::Solar | FuelGroup::Geothermal | FuelGroup::Biomass
+        )
+    }
+
+    /// Short label used in output files.
+    pub fn label(self) -> &'static str {
+        match self {
+            FuelGroup::Coal => "Coal",
+            FuelGroup::NaturalGas => "Natural Gas",
+            FuelGroup::Petroleum => "Petroleum",
+            FuelGroup::OtherGas => "Other Gas",
+            FuelGroup::Nuclear => "Nuclear",
+            FuelGroup::Hydro => "Hydro",
+            FuelGroup::Wind => "Wind",
+            FuelGroup::Solar => "Solar",
+            FuelGroup::Geothermal => "Geothermal",
+            FuelGroup::Biomass => "Biomass",
+            FuelGroup::Other => "Other",
+        }
+    }
+}
+
+/// Fossil generating technology: the fuel group, with natural gas split by prime mover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Technology {
+    Coal,
+    GasCombinedCycle,
+    GasCombustionTurbine,
+    GasSteam,
+    GasOther,
+    Oil,
+    OtherGases,
+}
+
+impl Technology {
+    /// Technology of a row from its AER fuel code and reported prime mover, or `None` for non-fossil rows.
+    /// Combined cycle parts are reported as CA (steam part), CT (turbine part) or CS (single shaft);
+    /// simple cycle turbines as GT.
+    pub fn classify(aer_fuel: &str, prime_mover: &str) -> Option<Technology> {
+        match FuelGroup::from_aer(aer_fuel) {
+            FuelGroup::Coal => Some(Technology::Coal),
+            FuelGroup::Petroleum => Some(Technology::Oil),
+            FuelGroup::OtherGas => Some(Technology::OtherGases),
+            FuelGroup::NaturalGas => Some(match prime_mover {
+                "CA" | "CS" | "CT" => Technology::GasCombinedCycle,
+                "GT" => Technology::GasCombustionTurbine,
+                "ST" => Technology::GasSteam,
+                _ => Technology::GasOther,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Short label used in output files.
+    pub fn label(self) -> &'static str {
+        match self {
+            Technology::Coal => "Coal",
+            Technology::GasCombinedCycle => "Gas CC",
+            Technology::GasCombustionTurbine => "Gas CT",
+            Technology::GasSteam => "Gas steam",
+            Technology::GasOther => "Gas other",
+            Technology::Oil => "Oil",
+            Technology::OtherGases => "Other gases",
+        }
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_technology_classification() {
+        assert_eq!(Technology::classify("NG", "CT"), Some(Technology::GasCombinedCycle));
+        assert_eq!(Technology::classify("NG", "GT"), Some(Technology::GasCombustionTurbine));
+        assert_eq!(Technology::classify("NG", "IC"), Some(Technology::GasOther));
+        assert_eq!(Technology::classify("COL", "ST"), Some(Technology::Coal));
+        assert_eq!(Technology::classify("DFO", "GT"), Some(Technology::Oil));
+        assert_eq!(Technology::classify("WND", "WT"), None);
+        assert_eq!(Technology::classify("WOO", "IC"), Some(Technology::Oil));
+        assert_eq!(Technology::classify("XYZ", "ST"), None);
+    }
+}
@@ -0,0 +1,159 @@
+// fueltrends.rs
+// This module computes national and per-state heat rates by fuel group for every loaded year, and national heat
+// rates by fossil technology.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::StateStats;
+use crate::fuels::{FuelGroup, Technology};
+use crate::panel::YearData;
+use crate::units::HeatRateUnit;
+
+/// Scope used for the national rows.
+pub const NATIONAL_SCOPE: &str = "US";
+
+/// Fuel and generation for one fuel group, scope and year.
+#[derive(Debug, Serialize)]
+pub struct FuelTrend {
+    /// "US" for the national total, otherwise the state code.
+    pub scope: String,
+    pub fuel_group: FuelGroup,
+    pub year: i32,
+    pub stats: StateStats,
+}
+
+impl FuelTrend {
+    /// Fuel used per MWh generated, or `None` without positive generation.
+    pub fn heat_rate(&self) -> Option<f64> {
+        self.stats.heat_rate().map(|rate| rate.0)
+    }
+}
+
+/// Builds the fuel group series for the nation and every state.
+/// # Arguments
+/// * `panel` - Loaded years, in ascending order
+/// # Returns
+/// * `Vec<FuelTrend>` with the national rows first, then states alphabetically; each scope is ordered by
+///   fuel group, then year
+pub fn compute_fuel_trends(panel: &[YearData]) -> Vec<FuelTrend> {
+    let mut by_scope: BTreeMap<(String, FuelGroup, i32), StateStats> = BTreeMap::new();
+
+    for year_data in panel {
+        for (state, fuels) in &year_data.fuels {
+            for (group, stats) in fuels {
+                for scope in [NATIONAL_SCOPE, state.as_str()] {
+                    let entry = by_scope.entry((scope.to_string(), *group, year_data.year)).or_default();
+                    entry.total_fuel += stats.total_fuel;
+                    entry.total_gen += stats.total_gen;
+                }
+            }
+        }
+    }
+
+    let mut output: Vec<FuelTrend> = by_scope
+        .into_iter()
+        .map(|((scope, fuel_group, year), stats)| FuelTrend { scope, fuel_group, year, stats })
+        .collect();
+
+    // Stable sort keeps the BTreeMap order within each group
+    output.sort_by_key(|t| t.scope != NATIONAL_SCOPE);
+    output
+}
+
+/// Writes the fuel group series to a CSV output file, with heat rates converted to `unit`.
+pub fn write_fuel_trends_csv(path: &str, data: &[FuelTrend], unit: HeatRateUnit) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Scope", "Fuel_Group", "Year", "Fuel_MMBtu", "Generation_MWh", "Heat_Rate", "Units"])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.scope,
+            item.fuel_group.label(),
+            &item.year.to_string(),
+            &format!("{:.6}", item.stats.total_fuel),
+            &format!("{:.6}", item.stats.total_gen),
+            &item.heat_rate().map(|v| format!("{:.6}", unit.convert(v))).unwrap_or_default(),
+            unit.label(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// National fuel and generation for one fossil technology and year.
+#[derive(Debug, Serialize)]
+pub struct TechnologyTrend {
+    pub technology: Technology,
+    pub year: i32,
+    pub stats: StateStats,
+
+    /// Heat rate change since the technology's first loaded year with generation (MMBtu/MWh).
+    pub change: Option<f64>,
+}
+
+impl TechnologyTrend {
+    /// Fuel used per MWh generated, or `None` without positive generation.
+    pub fn heat_rate(&self) -> Option<f64> {
+        self.stats.heat_rate().map(|rate| rate.0)
+    }
+}
+
+/// Builds the national series per fossil technology (coal, gas by prime mover, oil).
+/// # Arguments
+/// * `panel` - Loaded years, in ascending order
+/// # Returns
+/// * `Vec<TechnologyTrend>` ordered by technology, then year
+pub fn compute_technology_trends(panel: &[YearData]) -> Vec<TechnologyTrend> {
+    let mut by_technology: BTreeMap<(Technology, i32), &StateStats> = BTreeMap::new();
+    for year_data in panel {
+        for (technology, stats) in &year_data.technologies {
+            by_technology.insert((*technology, year_data.year), stats);
+        }
+    }
+
+    let mut output: Vec<TechnologyTrend> = Vec::new();
+    for ((technology, year), stats) in by_technology {
+        let mut trend = TechnologyTrend {
+            technology,
+            year,
+            stats: StateStats { total_fuel: stats.total_fuel, total_gen: stats.total_gen },
+            change: None,
+        };
+        let first = output.iter().find(|t| t.technology == technology && t.heat_rate().is_some());
+        trend.change = match first {
+            Some(first) => trend.heat_rate().zip(first.heat_rate()).map(|(now, then)| now - then),
+            None => trend.heat_rate().map(|_| 0.0),
+        };
+        output.push(trend);
+    }
+
+    output
+}
+
+/// Writes the technology series to a CSV output file, with heat rates converted to `unit`.
+pub fn write_technology_trends_csv(path: &str, data: &[TechnologyTrend], unit: HeatRateUnit) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "Technology", "Year", "Fuel_MMBtu", "Generation_MWh", "Heat_Rate", "Change_Since_First_Year", "Units",
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            item.technology.label(),
+            &item.year.to_string(),
+            &format!("{:.6}", item.stats.total_fuel),
+            &format!("{:.6}", item.stats.total_gen),
+            &item.heat_rate().map(|v| format!("{:.6}", unit.convert(v))).unwrap_or_default(),
+            &item.change.map(|v| format!("{:.6}", unit.convert(v))).unwrap_or_default(),
+            unit.label(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
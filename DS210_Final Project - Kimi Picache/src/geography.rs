@@ -0,0 +1,135 @@
+// geography.rs
+// This module holds the geographic centers of the states, great-circle distances between them and the
+// state adjacency graph (shared land borders).
+
+use std::collections::BTreeMap;
+
+/// Mean Earth radius (km).
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Approximate geographic center `(code, latitude, longitude)` of each state, DC and Puerto Rico, in degrees.
+const STATE_CENTROIDS: [(&str, f64, f64); 52] = [
+    ("AK", 61.3707, -152.4044),
+    ("AL", 32.8067, -86.7911),
+    ("AR", 34.9697, -92.3731),
+    ("AZ", 33.7298, -111.4312),
+    ("CA", 36.1162, -119.6816),
+    ("CO", 39.0598, -105.3111),
+    ("CT", 41.5978, -72.7554),
+    ("DC", 38.8974, -77.0268),
+    ("DE", 39.3185, -75.5071),
+    ("FL", 27.7663, -81.6868),
+    ("GA", 33.0406, -83.6431),
+    ("HI", 21.0943, -157.4983),
+    ("IA", 42.0115, -93.2105),
+    ("ID", 44.2405, -114.4788),
+    ("IL", 40.3495, -88.9861),
+    ("IN", 39.8494, -86.2583),
+    ("KS", 38.5266, -96.7265),
+    ("KY", 37.6681, -84.6701),
+    ("LA", 31.1695, -91.8678),
+    ("MA", 42.2302, -71.5301),
+    ("MD", 39.0639, -76.8021),
+    ("ME", 44.6939, -69.3819),
+    ("MI", 43.3266, -84.5361),
+    ("MN", 45.6945, -93.9002),
+    ("MO", 38.4561, -92.2884),
+    ("MS", 32.7416, -89.6787),
+    ("MT", 46.9219, -110.4544),
+    ("NC", 35.6301, -79.8064),
+    ("ND", 47.5289, -99.7840),
+    ("NE", 41.1254, -98.2681),
+    ("NH", 43.4525, -71.5639),
+    ("NJ", 40.2989, -74.5210),
+    ("NM", 34.8405, -106.2485),
+    ("NV", 38.3135, -117.0554),
+    ("NY", 42.1657, -74.9481),
+    ("OH", 40.3888, -82.7649),
+    ("OK", 35.5653, -96.9289),
+    ("OR", 44.5720, -122.0709),
+    ("PA", 40.5908, -77.2098),
+    ("PR", 18.2208, -66.5901),
+    ("RI", 41.6809, -71.5118),
+    ("SC", 33.8569, -80.9450),
+    ("SD", 44.2998, -99.4388),
+    ("TN", 35.7478, -86.6923),
+    ("TX", 31.0545, -97.5635),
+    ("UT", 40.1500, -111.8624),
+    ("VA", 37.7693, -78.1700),
+    ("VT", 44.0459, -72.7107),
+    ("WA", 47.4009, -121.4905),
+    ("WI", 44.2685, -89.6165),
+    ("WV", 38.4912, -80.9545),
+    ("WY", 42.7560, -107.3025),
+];
+
+/// Pairs of states (and DC) sharing a land border, each listed once in alphabetical order. Alaska, Hawaii and
+/// Puerto Rico have none.
+const STATE_BORDERS: [(&str, &str); 109] = [
+    ("AL", "FL"), ("AL", "GA"), ("AL", "MS"), ("AL", "TN"), ("AR", "LA"), ("AR", "MO"), ("AR", "MS"), ("AR", "OK"),
+    ("AR", "TN"), ("AR", "TX"), ("AZ", "CA"), ("AZ", "CO"), ("AZ", "NM"), ("AZ", "NV"), ("AZ", "UT"), ("CA", "NV"),
+    ("CA", "OR"), ("CO", "KS"), ("CO", "NE"), ("CO", "NM"), ("CO", "OK"), ("CO", "UT"), ("CO", "WY"), ("CT", "MA"),
+    ("CT", "NY"), ("CT", "RI"), ("DC", "MD"), ("DC", "VA"), ("DE", "MD"), ("DE", "NJ"), ("DE", "PA"), ("FL", "GA"),
+    ("GA", "NC"), ("GA", "SC"), ("GA", "TN"), ("IA", "IL"), ("IA", "MN"), ("IA", "MO"), ("IA", "NE"), ("IA", "SD"),
+    ("IA", "WI"), ("ID", "MT"), ("ID", "NV"), ("ID", "OR"), ("ID", "UT"), ("ID", "WA"), ("ID", "WY"), ("IL", "IN"),
+    ("IL", "KY"), ("IL", "MO"), ("IL", "WI"), ("IN", "KY"), ("IN", "MI"), ("IN", "OH"), ("KS", "MO"), ("KS", "NE"),
+    ("KS", "OK"), ("KY", "MO"), ("KY", "OH"), ("KY", "TN"), ("KY", "VA"), ("KY", "WV"), ("LA", "MS"), ("LA", "TX"),
+    ("MA", "NH"), ("MA", "NY"), ("MA", "RI"), ("MA", "VT"), ("MD", "PA"), ("MD", "VA"), ("MD", "WV"), ("ME", "NH"),
+    ("MI", "OH"), ("MI", "WI"), ("MN", "ND"), ("MN", "SD"), ("MN", "WI"), ("MO", "NE"), ("MO", "OK"), ("MO", "TN"),
+    ("MS", "TN"), ("MT", "ND"), ("MT", "SD"), ("MT", "WY"), ("NC", "SC"), ("NC", "TN"), ("NC", "VA"), ("ND", "SD"),
+    ("NE", "SD"), ("NE", "WY"), ("NH", "VT"), ("NJ", "NY"), ("NJ", "PA"), ("NM", "OK"), ("NM", "TX"), ("NM", "UT"),
+    ("NV", "OR"), ("NV", "UT"), ("NY", "PA"), ("NY", "VT"), ("OH", "PA"), ("OH", "WV"), ("OK", "TX"), ("OR", "WA"),
+    ("PA", "WV"), ("SD", "WY"), ("TN", "VA"), ("UT", "WY"), ("VA", "WV"),
+];
+
+/// Geographic center `(latitude, longitude)` of a state, if known.
+pub fn state_centroid(state: &str) -> Option<(f64, f64)> {
+    STATE_CENTROIDS
+        .iter()
+        .find(|(code, ..)| *code == state)
+        .map(|(_, lat, lon)| (*lat, *lon))
+}
+
+/// The adjacency graph: every state with a land border and its neighbors, sorted.
+pub fn state_adjacency() -> BTreeMap<&'static str, Vec<&'static str>> {
+    let mut graph: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (a, b) in STATE_BORDERS {
+        graph.entry(a).or_default().push(b);
+        graph.entry(b).or_default().push(a);
+    }
+    for neighbors in graph.values_mut() {
+        neighbors.sort_unstable();
+    }
+    graph
+}
+
+/// Great-circle (haversine) distance in km between two `(latitude, longitude)` points in degrees.
+pub fn great_circle_km(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat_a, lat_b) = (a.0.to_radians(), b.0.to_radians());
+    let d_lat = lat_b - lat_a;
+    let d_lon = (b.1 - a.1).to_radians();
+
+    let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distances_and_borders() {
+        // New York to Pennsylvania centers, and a quarter of the equator
+        let distance = great_circle_km(state_centroid("NY").unwrap(), state_centroid("PA").unwrap());
+        assert!((200.0..260.0).contains(&distance));
+        assert!((great_circle_km((0.0, 0.0), (0.0, 90.0)) - EARTH_RADIUS_KM * std::f64::consts::FRAC_PI_2).abs() < 1e-6);
+        assert!(state_centroid("DC+TERR").is_none());
+
+        let graph = state_adjacency();
+        assert_eq!(graph.len(), 49);
+        assert_eq!(graph["ME"], ["NH"]);
+        assert_eq!(graph["DC"], ["MD", "VA"]);
+        assert_eq!(graph["TN"].len(), 8);
+    }
+}
@@ -0,0 +1,41 @@
+// grading.rs
+// This module classifies states into quantile-based grade bands (quartiles by default).
+
+/// Assigns each value a grade label by splitting the sorted values into equally sized bands.
+/// Lower values are treated as better and receive the first label.
+/// # Arguments
+/// * `values` - Metric per state (e.g., heat rate or change in heat rate)
+/// * `labels` - Band labels, best first
+/// # Returns
+/// * `Vec<String>` with one label per input value, in input order
+pub fn assign_grades(values: &[f64], labels: &[String]) -> Vec<String> {
+    if labels.is_empty() {
+        return vec![String::new(); values.len()];
+    }
+
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].partial_cmp(&values[b]).unwrap());
+
+    let mut grades = vec![String::new(); values.len()];
+    for (position, index) in order.into_iter().enumerate() {
+        let band = position * labels.len() / values.len();
+        grades[index] = labels[band].clone();
+    }
+
+    grades
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assign_grades_quartiles() {
+        let labels: Vec<String> = ["Q1", "Q2", "Q3", "Q4"].iter().map(|s| s.to_string()).collect();
+        let values = [8.0, 1.0, 7.0, 2.0, 6.0, 3.0, 5.0, 4.0];
+
+        let grades = assign_grades(&values, &labels);
+        assert_eq!(grades, ["Q4", "Q1", "Q4", "Q1", "Q3", "Q2", "Q3", "Q2"]);
+    }
+}
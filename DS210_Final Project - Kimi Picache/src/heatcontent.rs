@@ -0,0 +1,234 @@
+// heatcontent.rs
+// This module checks the heat content implied by each plant-month (fuel MMBtu / physical quantity) against the
+// plausible range for the reported fuel, and flags, drops or corrects the months that fall outside it.
+
+use std::error::Error;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use csv::WriterBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::cleaning::{CleanRecord, MONTHS};
+
+/// Plausible heat content (MMBtu per physical unit) for a reported fuel code: `(code, low, high, typical)`.
+/// Solids are per short ton, liquids per barrel and gases per thousand cubic feet. The ranges are wide on
+/// purpose: they catch unit and keying errors, not ordinary variation in fuel quality.
+const HEAT_CONTENT_RANGES: [(&str, f64, f64, f64); 22] = [
+    ("ANT", 18.0, 30.0, 25.0),
+    ("BIT", 17.0, 30.0, 24.9),
+    ("SUB", 12.0, 26.0, 17.3),
+    ("LIG", 8.0, 18.0, 13.0),
+    ("RC", 8.0, 28.0, 18.0),
+    ("WC", 5.0, 20.0, 11.0),
+    ("SC", 12.0, 28.0, 22.0),
+    ("PC", 18.0, 36.0, 28.5),
+    ("NG", 0.8, 1.3, 1.037),
+    ("BFG", 0.05, 0.15, 0.09),
+    ("LFG", 0.2, 0.7, 0.5),
+    ("OBG", 0.3, 1.1, 0.6),
+    ("DFO", 5.0, 6.5, 5.8),
+    ("RFO", 5.3, 7.0, 6.3),
+    ("JF", 5.0, 6.0, 5.7),
+    ("KER", 5.0, 6.2, 5.7),
+    ("PG", 2.3, 4.2, 2.5),
+    ("WDS", 5.0, 24.0, 11.5),
+    ("BLQ", 5.0, 20.0, 11.8),
+    ("MSW", 5.0, 16.0, 10.0),
+    ("TDF", 14.0, 36.0, 28.0),
+    ("AB", 5.0, 18.0, 12.0),
+];
+
+/// Months burning less than this quantity are not checked: quantities are reported in whole units, so the
+/// ratio of small ones is mostly rounding.
+const MIN_QUANTITY: f64 = 100.0;
+
+/// What to do with a plant-month whose implied heat content is implausible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum HeatContentPolicy {
+    /// Only list the month; its values are kept.
+    #[default]
+    Flag,
+    /// Drop the month's fuel and generation.
+    Exclude,
+    /// Replace the month's fuel with quantity × the fuel's typical heat content.
+    Correct,
+}
+
+impl HeatContentPolicy {
+    /// Name as given on the command line.
+    pub fn label(self) -> &'static str {
+        match self {
+            HeatContentPolicy::Flag => "flag",
+            HeatContentPolicy::Exclude => "exclude",
+            HeatContentPolicy::Correct => "correct",
+        }
+    }
+}
+
+/// A plant-month whose implied heat content is outside the range for its fuel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatContentAnomaly {
+    pub plant_id: String,
+    pub state: String,
+    pub reported_fuel: String,
+
+    /// Month number (1-12).
+    pub month: usize,
+
+    /// Physical quantity burned and the fuel reported for it (MMBtu).
+    pub quantity: f64,
+    pub fuel: f64,
+
+    /// `fuel / quantity` and the plausible range it fell outside.
+    pub implied: f64,
+    pub low: f64,
+    pub high: f64,
+
+    /// Policy applied to the month.
+    pub action: HeatContentPolicy,
+}
+
+/// Plausible `(low, high, typical)` heat content for a reported fuel code, if it is one we check.
+pub fn heat_content_range(reported_fuel: &str) -> Option<(f64, f64, f64)> {
+    HEAT_CONTENT_RANGES
+        .iter()
+        .find(|(code, ..)| *code == reported_fuel)
+        .map(|(_, low, high, typical)| (*low, *high, *typical))
+}
+
+/// Finds the implausible plant-months and applies `policy` to them, keeping annual totals equal to the
+/// sum of the months that changed.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year; updated in place unless the policy is `Flag`
+/// * `policy` - Treatment of the anomalous months
+/// # Returns
+/// * `Vec<HeatContentAnomaly>` in row and month order
+pub fn check_heat_content(records: &mut [CleanRecord], policy: HeatContentPolicy) -> Vec<HeatContentAnomaly> {
+    let mut anomalies = Vec::new();
+
+    for record in records.iter_mut() {
+        let Some((low, high, typical)) = heat_content_range(&record.reported_fuel) else {
+            continue;
+        };
+
+        for month in 0..12 {
+            let (quantity, fuel) = (record.monthly_quantity[month], record.monthly_fuel[month]);
+            if quantity < MIN_QUANTITY || fuel <= 0.0 {
+                continue;
+            }
+
+            let implied = fuel / quantity;
+            if (low..=high).contains(&implied) {
+                continue;
+            }
+
+            match policy {
+                HeatContentPolicy::Flag => {}
+                HeatContentPolicy::Exclude => {
+                    record.fuel -= fuel;
+                    record.r#gen -= record.monthly_gen[month];
+                    record.monthly_fuel[month] = 0.0;
+                    record.monthly_gen[month] = 0.0;
+                }
+                HeatContentPolicy::Correct => {
+                    record.monthly_fuel[month] = quantity * typical;
+                    record.fuel += record.monthly_fuel[month] - fuel;
+                }
+            }
+
+            anomalies.push(HeatContentAnomaly {
+                plant_id: record.plant_id.clone(),
+                state: record.state.clone(),
+                reported_fuel: record.reported_fuel.clone(),
+                month: month + 1,
+                quantity,
+                fuel,
+                implied,
+                low,
+                high,
+                action: policy,
+            });
+        }
+    }
+
+    anomalies
+}
+
+/// Writes the anomalous plant-months to a CSV output file.
+pub fn write_heat_content_anomalies_csv(path: &str, data: &[HeatContentAnomaly]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "Plant_Id", "State", "Reported_Fuel", "Month", "Quantity", "Fuel_MMBtu", "Implied_Heat_Content", "Low", "High",
+        "Action",
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.plant_id,
+            &item.state,
+            &item.reported_fuel,
+            MONTHS[item.month - 1],
+            &format!("{:.6}", item.quantity),
+            &format!("{:.6}", item.fuel),
+            &format!("{:.6}", item.implied),
+            &format!("{:.6}", item.low),
+            &format!("{:.6}", item.high),
+            item.action.label(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    /// A gas plant whose February fuel implies an implausible heat content.
+    fn mismeasured_plant() -> CleanRecord {
+        let mut monthly_quantity = [0.0; 12];
+        let mut monthly_fuel = [0.0; 12];
+        let mut monthly_gen = [0.0; 12];
+        // January is plausible natural gas, February implies 10 MMBtu per Mcf
+        monthly_quantity[..2].copy_from_slice(&[100.0, 100.0]);
+        monthly_fuel[..2].copy_from_slice(&[103.0, 1000.0]);
+        monthly_gen[..2].copy_from_slice(&[10.0, 10.0]);
+
+        CleanRecord {
+            monthly_fuel,
+            monthly_gen,
+            reported_fuel: "NG".to_string(),
+            monthly_quantity,
+            ..record("1", "AA", "NG", 1103.0, 20.0)
+        }
+    }
+
+    #[test]
+    fn test_flag_keeps_the_month() {
+        let mut records = [mismeasured_plant()];
+        let anomalies = check_heat_content(&mut records, HeatContentPolicy::Flag);
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!((anomalies[0].month, anomalies[0].implied), (2, 10.0));
+        assert_eq!(records[0].fuel, 1103.0);
+    }
+
+    #[test]
+    fn test_exclude_drops_the_month() {
+        let mut records = [mismeasured_plant()];
+        check_heat_content(&mut records, HeatContentPolicy::Exclude);
+        assert_eq!((records[0].fuel, records[0].r#gen), (103.0, 10.0));
+    }
+
+    #[test]
+    fn test_correct_rescales_the_month() {
+        let mut records = [mismeasured_plant()];
+        check_heat_content(&mut records, HeatContentPolicy::Correct);
+        assert!((records[0].fuel - 206.7).abs() < 1e-9);
+        assert!(heat_content_range("SUN").is_none());
+    }
+}
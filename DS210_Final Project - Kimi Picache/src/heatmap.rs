@@ -0,0 +1,225 @@
+// heatmap.rs
+// This module renders a state × year heat map of heat rate (or year-over-year change) from the panel as an SVG image.
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+use crate::panel::YearData;
+
+const CELL_WIDTH: usize = 64;
+const CELL_HEIGHT: usize = 16;
+const LEFT_MARGIN: usize = 48;
+const TOP_MARGIN: usize = 32;
+const LEGEND_HEIGHT: usize = 48;
+
+/// Color scale used to shade the cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum ColorScale {
+    /// Perceptually uniform dark purple → yellow.
+    Viridis,
+    /// Light → dark blue.
+    Blues,
+    /// Diverging blue → white → red, centered on zero for changes.
+    RedBlue,
+}
+
+impl ColorScale {
+    /// Color stops from low to high values.
+    fn stops(self) -> &'static [(u8, u8, u8)] {
+        match self {
+            ColorScale::Viridis => &[(68, 1, 84), (59, 82, 139), (33, 145, 140), (94, 201, 98), (253, 231, 37)],
+            ColorScale::Blues => &[(239, 243, 255), (189, 215, 231), (107, 174, 214), (49, 130, 189), (8, 81, 156)],
+            ColorScale::RedBlue => &[(33, 102, 172), (146, 197, 222), (247, 247, 247), (244, 165, 130), (178, 24, 43)],
+        }
+    }
+
+    /// Interpolates the scale at `t` in [0, 1] and returns a hex color.
+    fn color(self, t: f64) -> String {
+        let stops = self.stops();
+        let t = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let i = (t.floor() as usize).min(stops.len() - 2);
+        let f = t - i as f64;
+        let lerp = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * f).round() as u8;
+        let (a, b) = (stops[i], stops[i + 1]);
+        format!("#{:02x}{:02x}{:02x}", lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2))
+    }
+}
+
+/// Value shown in each cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum HeatmapMetric {
+    /// Heat rate (MMBtu/MWh).
+    HeatRate,
+    /// Change in heat rate from the previous loaded year.
+    Delta,
+}
+
+/// Value of a cell, or `None` when the state lacks data for the year (or the previous year, for deltas).
+fn cell_value(panel: &[YearData], index: usize, state: &str, metric: HeatmapMetric) -> Option<f64> {
+    let rate = |i: usize| {
+        let stats = panel[i].stats.get(state)?;
+        (stats.total_gen.0 != 0.0).then(|| (stats.total_fuel / stats.total_gen).0)
+    };
+
+    match metric {
+        HeatmapMetric::HeatRate => rate(index),
+        HeatmapMetric::Delta => Some(rate(index)? - rate(index.checked_sub(1)?)?),
+    }
+}
+
+/// Renders the heat map as an SVG document.
+/// # Arguments
+/// * `panel` - Loaded years, ascending
+/// * `metric` - Value to shade
+/// * `scale` - Color scale
+/// # Returns
+/// * SVG markup
+pub fn render_heatmap_svg(panel: &[YearData], metric: HeatmapMetric, scale: ColorScale) -> String {
+    let states: BTreeSet<&str> = panel.iter().flat_map(|y| y.stats.keys().map(String::as_str)).collect();
+    let first_column = if metric == HeatmapMetric::Delta { 1 } else { 0 };
+    let columns: Vec<usize> = (first_column..panel.len()).collect();
+
+    let values: Vec<f64> = states
+        .iter()
+        .flat_map(|state| columns.iter().filter_map(|&i| cell_value(panel, i, state, metric)))
+        .collect();
+    let mut min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let mut max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if metric == HeatmapMetric::Delta && scale == ColorScale::RedBlue {
+        // Keep zero at the neutral midpoint of the diverging scale
+        let bound = min.abs().max(max.abs());
+        min = -bound;
+        max = bound;
+    }
+    let span = if max > min { max - min } else { 1.0 };
+
+    let width = LEFT_MARGIN + columns.len() * CELL_WIDTH + 8;
+    let height = TOP_MARGIN + states.len() * CELL_HEIGHT + LEGEND_HEIGHT;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="11">"#,
+        width, height
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+
+    for (col, &i) in columns.iter().enumerate() {
+        let x = LEFT_MARGIN + col * CELL_WIDTH + CELL_WIDTH / 2;
+        let _ = writeln!(svg, r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#, x, TOP_MARGIN - 8, panel[i].year);
+    }
+
+    for (row, state) in states.iter().enumerate() {
+        let y = TOP_MARGIN + row * CELL_HEIGHT;
+        let _ = writeln!(svg, r#"<text x="{}" y="{}" text-anchor="end">{}</text>"#, LEFT_MARGIN - 6, y + 12, state);
+
+        for (col, &i) in columns.iter().enumerate() {
+            let x = LEFT_MARGIN + col * CELL_WIDTH;
+            let (fill, title) = match cell_value(panel, i, state, metric) {
+                Some(v) => (scale.color((v - min) / span), format!("{} {}: {:.3}", state, panel[i].year, v)),
+                None => ("#dddddd".to_string(), format!("{} {}: no data", state, panel[i].year)),
+            };
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"><title>{}</title></rect>"#,
+                x, y, CELL_WIDTH, CELL_HEIGHT, fill, title
+            );
+        }
+    }
+
+    // Legend: a ten-step ramp labeled with the min and max values
+    let legend_y = TOP_MARGIN + states.len() * CELL_HEIGHT + 12;
+    let step_width = (columns.len() * CELL_WIDTH).max(120) / 10;
+    for step in 0..10 {
+        let x = LEFT_MARGIN + step * step_width;
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="10" fill="{}"/>"#,
+            x, legend_y, step_width, scale.color(step as f64 / 9.0)
+        );
+    }
+    let _ = writeln!(svg, r#"<text x="{}" y="{}">{:.2}</text>"#, LEFT_MARGIN, legend_y + 24, min);
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="end">{:.2}</text>"#,
+        LEFT_MARGIN + step_width * 10, legend_y + 24, max
+    );
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// Renders the heat map and writes it to `path`.
+pub fn write_heatmap_svg(
+    path: &str,
+    panel: &[YearData],
+    metric: HeatmapMetric,
+    scale: ColorScale,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(path, render_heatmap_svg(panel, metric, scale))?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use crate::cleaning::StateStats;
+    use crate::quantity::{MegawattHours, Mmbtu};
+
+    fn year(year: i32, heat_rates: &[(&str, f64)]) -> YearData {
+        YearData {
+            year,
+            source: format!("{}.csv", year),
+            rows: 0,
+            options: Default::default(),
+            imputed: 0,
+            anomalies: 0,
+            monthly: HashMap::new(),
+            stats: heat_rates
+                .iter()
+                .map(|(state, hr)| {
+                    (state.to_string(), StateStats { total_fuel: Mmbtu(hr * 100.0), total_gen: MegawattHours(100.0) })
+                })
+                .collect(),
+            fuels: Default::default(),
+            technologies: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_color_scale() {
+        assert_eq!(ColorScale::Viridis.color(0.0), "#440154");
+        assert_eq!(ColorScale::Viridis.color(1.0), "#fde725");
+        assert_eq!(ColorScale::RedBlue.color(0.5), "#f7f7f7");
+        assert_eq!(ColorScale::Blues.color(2.0), ColorScale::Blues.color(1.0));
+    }
+
+    #[test]
+    fn test_render_heatmap() {
+        let panel = [year(2019, &[("AA", 10.0), ("BB", 8.0)]), year(2020, &[("AA", 9.0)])];
+        assert_eq!(cell_value(&panel, 1, "AA", HeatmapMetric::Delta), Some(-1.0));
+        assert_eq!(cell_value(&panel, 0, "AA", HeatmapMetric::Delta), None);
+
+        let svg = render_heatmap_svg(&panel, HeatmapMetric::HeatRate, ColorScale::Viridis);
+        assert_eq!(svg.matches("<title>").count(), 4);
+        assert!(svg.contains("<title>BB 2020: no data</title>"));
+        assert!(svg.contains("<title>AA 2019: 10.000</title>"));
+
+        // Changes start from the second year; the diverging scale is centered on zero
+        let svg = render_heatmap_svg(&panel, HeatmapMetric::Delta, ColorScale::RedBlue);
+        assert_eq!(svg.matches("<title>").count(), 2);
+        assert!(!svg.contains(">2019<"));
+        assert!(svg.contains(">-1.00</text>") && svg.contains(">1.00</text>"));
+    }
+}
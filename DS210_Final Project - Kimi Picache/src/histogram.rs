@@ -0,0 +1,128 @@
+// histogram.rs
+// This module bins plant heat rates by state, fuel group and year, so distribution shifts between years
+// can be plotted without exporting every plant.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{aggregate_by_plant, CleanRecord};
+use crate::fuels::FuelGroup;
+use crate::fueltrends::NATIONAL_SCOPE;
+
+/// Default bin edges in MMBtu/MWh; values below the first or above the last edge go to open-ended bins.
+pub const DEFAULT_BIN_EDGES: [f64; 11] = [6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 16.0, 20.0];
+
+/// Plant counts and generation per heat rate bin for one scope, fuel group and year.
+#[derive(Debug, Serialize)]
+pub struct Histogram {
+    /// "US" for the national histogram, otherwise the state code.
+    pub scope: String,
+    pub fuel_group: FuelGroup,
+    pub year: i32,
+
+    /// Plants per bin; bin `i` covers `[edges[i-1], edges[i])`, with open-ended first and last bins.
+    pub plants: Vec<usize>,
+
+    /// Net generation (MWh) of the plants in each bin.
+    pub generation: Vec<f64>,
+}
+
+/// Checks that bin edges are finite and strictly increasing.
+pub fn check_bin_edges(edges: &[f64]) -> Result<(), String> {
+    if edges.is_empty() {
+        return Err("at least one bin edge is needed".to_string());
+    }
+    if edges.iter().any(|e| !e.is_finite()) || edges.windows(2).any(|w| w[0] >= w[1]) {
+        return Err(format!("bin edges must be increasing numbers, got {:?}", edges));
+    }
+    Ok(())
+}
+
+/// Builds the histograms for the fossil fuel groups of one year. Plants without positive generation are skipped.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// * `year` - Year the rows belong to
+/// * `edges` - Bin edges (see `check_bin_edges`)
+/// # Returns
+/// * `Vec<Histogram>` with the national histograms first, then states alphabetically, each by fuel group
+pub fn plant_heat_rate_histograms(records: &[CleanRecord], year: i32, edges: &[f64]) -> Vec<Histogram> {
+    let mut by_scope: BTreeMap<(bool, String, FuelGroup), Histogram> = BTreeMap::new();
+
+    for group in [FuelGroup::Coal, FuelGroup::NaturalGas, FuelGroup::Petroleum, FuelGroup::OtherGas] {
+        let plants = aggregate_by_plant(records.iter().filter(|r| FuelGroup::from_aer(&r.aer_fuel) == group));
+
+        for plant in plants.values().filter(|p| p.total_gen > 0.0) {
+            let bin = edges.partition_point(|edge| *edge <= plant.total_fuel / plant.total_gen);
+
+            for scope in [NATIONAL_SCOPE, plant.state.as_str()] {
+                let histogram = by_scope
+                    .entry((scope != NATIONAL_SCOPE, scope.to_string(), group))
+                    .or_insert_with(|| Histogram {
+                        scope: scope.to_string(),
+                        fuel_group: group,
+                        year,
+                        plants: vec![0; edges.len() + 1],
+                        generation: vec![0.0; edges.len() + 1],
+                    });
+                histogram.plants[bin] += 1;
+                histogram.generation[bin] += plant.total_gen;
+            }
+        }
+    }
+
+    by_scope.into_values().collect()
+}
+
+/// Writes one row per histogram bin to a CSV output file; open-ended bins leave the missing edge empty.
+pub fn write_histograms_csv(path: &str, data: &[Histogram], edges: &[f64]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Scope", "Fuel_Group", "Year", "Bin_Low", "Bin_High", "Plants", "Generation_MWh"])?;
+
+    for item in data {
+        for (bin, (plants, generation)) in item.plants.iter().zip(&item.generation).enumerate() {
+            let low = bin.checked_sub(1).map(|i| edges[i].to_string()).unwrap_or_default();
+            let high = edges.get(bin).map(|e| e.to_string()).unwrap_or_default();
+            wtr.write_record([
+                &item.scope,
+                item.fuel_group.label(),
+                &item.year.to_string(),
+                &low,
+                &high,
+                &plants.to_string(),
+                &format!("{:.6}", generation),
+            ])?;
+        }
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_histogram_bins() {
+        let records = [
+            record("1", "TX", "NG", 50.0, 10.0),
+            record("2", "TX", "NG", 75.0, 10.0),
+            record("3", "CA", "NG", 200.0, 10.0),
+        ];
+        let histograms = plant_heat_rate_histograms(&records, 2020, &[6.0, 8.0]);
+
+        // Heat rates 5, 7.5 and 20: one plant in each of (-inf, 6), [6, 8) and [8, inf)
+        assert_eq!(histograms[0].scope, NATIONAL_SCOPE);
+        assert_eq!(histograms[0].plants, [1, 1, 1]);
+        assert_eq!(histograms[1].scope, "CA");
+        assert_eq!(histograms[1].plants, [0, 0, 1]);
+        assert_eq!(histograms[2].plants, [1, 1, 0]);
+
+        assert!(check_bin_edges(&[8.0, 6.0]).is_err());
+    }
+}
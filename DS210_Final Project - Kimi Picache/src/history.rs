@@ -0,0 +1,294 @@
+// history.rs
+// This module traces one state's published result across the runs recorded in the results database, so a
+// moved number can be explained: each run is matched to its audit log line (by start time) for the digests of
+// its inputs and its command line, and every step is labeled with what changed since the previous run (tool
+// version, input files, input contents or options).
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use csv::WriterBuilder;
+use rusqlite::{params, Connection, OpenFlags};
+use serde_json::Value;
+
+use crate::units::HeatRateUnit;
+
+/// What the audit log says about one run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditRun {
+    /// SHA-256 digests of the inputs, in input order.
+    pub digests: Vec<String>,
+
+    /// Command line without the program name.
+    pub args: Vec<String>,
+}
+
+/// One recorded run's result for the state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryPoint {
+    pub run_id: i64,
+
+    /// Start of the run, RFC 3339 in UTC.
+    pub timestamp: String,
+    pub version: String,
+    pub label: Option<String>,
+    pub inputs: [String; 2],
+
+    /// Heat rates and change in MMBtu/MWh.
+    pub heat_rates: [f64; 2],
+    pub delta: f64,
+
+    /// The run's audit log line, when one was found.
+    pub audit: Option<AuditRun>,
+
+    /// What differs from the previous run, e.g. "version 0.1.0 → 0.2.0" or "input contents".
+    pub changes: Vec<String>,
+}
+
+impl HistoryPoint {
+    /// Short form of the input digests ("a1b2c3d4/e5f6a7b8"), or "-" without an audit line.
+    pub fn data_revision(&self) -> String {
+        match &self.audit {
+            Some(audit) if !audit.digests.is_empty() => {
+                audit.digests.iter().map(|d| d.chars().take(8).collect::<String>()).collect::<Vec<_>>().join("/")
+            }
+            _ => "-".to_string(),
+        }
+    }
+}
+
+/// Reads the audit log into a map from run start time to the run's inputs and options. Lines of older layouts
+/// without an RFC 3339 timestamp are skipped; a missing log gives an empty map.
+pub fn read_audit_runs(path: &str) -> Result<HashMap<String, AuditRun>, Box<dyn Error>> {
+    let mut runs = HashMap::new();
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(runs),
+        Err(e) => return Err(e.into()),
+    };
+
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let Ok(entry) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let Some(timestamp) = entry["timestamp"].as_str() else {
+            continue;
+        };
+        let strings = |value: &Value, field: Option<&str>| -> Vec<String> {
+            let items = value.as_array().map_or(&[][..], Vec::as_slice);
+            items
+                .iter()
+                .filter_map(|item| field.map_or(item, |f| &item[f]).as_str().map(str::to_string))
+                .collect()
+        };
+        let args = strings(&entry["args"], None).into_iter().skip(1).collect();
+        let run = AuditRun { digests: strings(&entry["inputs"], Some("sha256")), args };
+        runs.entry(timestamp.to_string()).or_insert(run);
+    }
+    Ok(runs)
+}
+
+/// Command line arguments other than the input paths.
+fn options<'a>(audit: &'a AuditRun, inputs: &[String; 2]) -> Vec<&'a String> {
+    audit.args.iter().filter(|arg| !inputs.contains(arg)).collect()
+}
+
+/// Labels each point with what changed since the previous one.
+fn label_changes(points: &mut [HistoryPoint]) {
+    for i in 1..points.len() {
+        let (previous, point) = (&points[i - 1], &points[i]);
+        let mut changes = Vec::new();
+        if previous.version != point.version {
+            changes.push(format!("version {} → {}", previous.version, point.version));
+        }
+        if previous.inputs != point.inputs {
+            changes.push("input files".to_string());
+        }
+        match (&previous.audit, &point.audit) {
+            (Some(a), Some(b)) => {
+                if a.digests != b.digests {
+                    changes.push("input contents".to_string());
+                }
+                if options(a, &previous.inputs) != options(b, &point.inputs) {
+                    changes.push("options".to_string());
+                }
+            }
+            _ => changes.push("unknown (no audit line)".to_string()),
+        }
+        points[i].changes = changes;
+    }
+}
+
+/// Collects a state's result in every recorded run, oldest first.
+/// # Arguments
+/// * `conn` - Results database
+/// * `state` - State code, in any case
+/// * `audit` - Runs of the audit log by start time (see `read_audit_runs`)
+pub fn state_history(
+    conn: &Connection,
+    state: &str,
+    audit: &HashMap<String, AuditRun>,
+) -> Result<Vec<HistoryPoint>, Box<dyn Error>> {
+    let mut stmt = conn.prepare(
+        "SELECT r.id, r.timestamp_utc, r.version, r.label, r.base_input, r.compare_input, s.heat_rate_base,
+         s.heat_rate_compare, s.delta FROM runs r JOIN state_results s ON s.run_id = r.id WHERE s.state = ?1
+         ORDER BY r.id",
+    )?;
+    let rows = stmt.query_map(params![state.trim().to_ascii_uppercase()], |row| {
+        let timestamp: Option<String> = row.get(1)?;
+        Ok(HistoryPoint {
+            run_id: row.get(0)?,
+            timestamp: timestamp.unwrap_or_default(),
+            version: row.get(2)?,
+            label: row.get(3)?,
+            inputs: [row.get(4)?, row.get(5)?],
+            heat_rates: [row.get(6)?, row.get(7)?],
+            delta: row.get(8)?,
+            audit: None,
+            changes: Vec::new(),
+        })
+    })?;
+
+    let mut points = rows.collect::<Result<Vec<_>, _>>()?;
+    for point in &mut points {
+        point.audit = audit.get(&point.timestamp).cloned();
+    }
+    label_changes(&mut points);
+    Ok(points)
+}
+
+/// Opens a results database read-only and collects a state's history; see `state_history`.
+pub fn read_state_history(
+    path: &str,
+    state: &str,
+    audit: &HashMap<String, AuditRun>,
+) -> Result<Vec<HistoryPoint>, Box<dyn Error>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("cannot open results database '{}': {}", path, e))?;
+    state_history(&conn, state, audit)
+}
+
+/// Formats the history as a table with a bar chart of the change, one row per run.
+/// # Arguments
+/// * `points` - The state's history, oldest first
+/// * `unit` - Unit of the heat rates
+/// * `width` - Characters of the longest bar on either side of zero
+pub fn format_history(points: &[HistoryPoint], unit: HeatRateUnit, width: usize) -> String {
+    let largest = points.iter().map(|p| p.delta.abs()).fold(0.0, f64::max);
+    let bar = |delta: f64| {
+        let length = if largest > 0.0 { (delta.abs() / largest * width as f64).round() as usize } else { 0 };
+        match delta < 0.0 {
+            true => format!("{:>width$}|{:width$}", "#".repeat(length), "", width = width),
+            false => format!("{:>width$}|{:<width$}", "", "#".repeat(length), width = width),
+        }
+    };
+
+    let mut out = format!(
+        "{:>4}  {:<20}  {:<8}  {:<17}  {:>9}  {:>9}  {:>9}  {:^w$}  {}\n",
+        "Run",
+        "Started (UTC)",
+        "Version",
+        "Data revision",
+        "Base",
+        "Compare",
+        "Change",
+        "Change (negative = improved)",
+        "What changed",
+        w = 2 * width + 1
+    );
+    for point in points {
+        let row = format!(
+            "{:>4}  {:<20}  {:<8}  {:<17}  {:>9.3}  {:>9.3}  {:>+9.3}  {}  {}",
+            point.run_id,
+            point.timestamp,
+            point.version,
+            point.data_revision(),
+            unit.convert(point.heat_rates[0]),
+            unit.convert(point.heat_rates[1]),
+            unit.convert(point.delta),
+            bar(point.delta),
+            point.changes.join(", ")
+        );
+        out += row.trim_end();
+        out += "\n";
+    }
+    out
+}
+
+/// Writes the history to a CSV output file (heat rates in MMBtu/MWh).
+pub fn write_history_csv(path: &str, state: &str, points: &[HistoryPoint]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State", "Run_Id", "Timestamp_UTC", "Version", "Label", "Base_Input", "Compare_Input", "Data_Revision",
+        "Heat_Rate_Base", "Heat_Rate_Compare", "Delta", "Delta_Move", "Changes",
+    ])?;
+
+    let mut previous: Option<f64> = None;
+    for point in points {
+        wtr.write_record([
+            state,
+            &point.run_id.to_string(),
+            &point.timestamp,
+            &point.version,
+            point.label.as_deref().unwrap_or(""),
+            &point.inputs[0],
+            &point.inputs[1],
+            &point.audit.as_ref().map(|a| a.digests.join("+")).unwrap_or_default(),
+            &format!("{:.6}", point.heat_rates[0]),
+            &format!("{:.6}", point.heat_rates[1]),
+            &format!("{:.6}", point.delta),
+            &previous.map(|p| format!("{:.6}", point.delta - p)).unwrap_or_default(),
+            &point.changes.join("; "),
+        ])?;
+        previous = Some(point.delta);
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::compute_efficiency_changes;
+    use crate::cleaning::StateStats;
+    use crate::hooks::RunSummary;
+    use crate::quantity::{MegawattHours, Mmbtu};
+    use crate::resultsdb::{prepare_schema, record_run};
+
+    #[test]
+    fn test_state_history() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        prepare_schema(&conn, ":memory:").unwrap();
+        let stats = |fuel: f64| {
+            HashMap::from([("TX".to_string(), StateStats { total_fuel: Mmbtu(fuel), total_gen: MegawattHours(100.0) })])
+        };
+
+        // The second run reads revised data, the third drops an option
+        let mut audit = HashMap::new();
+        let runs = [(1, 950.0, "a", "--units btu-kwh"), (2, 960.0, "b", "--units btu-kwh"), (3, 960.0, "b", "")];
+        for (day, fuel, digest, args) in runs {
+            let mut summary = RunSummary { command: "analyze".to_string(), ..Default::default() };
+            summary.timestamp.utc = format!("2024-03-0{}T00:00:00Z", day);
+            summary.input("2019.csv", 10);
+            summary.input("2020.csv", 12);
+            record_run(&mut conn, &summary, &compute_efficiency_changes(&stats(1000.0), &stats(fuel))).unwrap();
+            let args = ["efficiency"].into_iter().chain(args.split_whitespace()).map(String::from).collect();
+            audit.insert(summary.timestamp.utc, AuditRun { digests: vec![digest.repeat(64)], args });
+        }
+
+        let points = state_history(&conn, "tx", &audit).unwrap();
+        assert_eq!(points.iter().map(|p| p.run_id).collect::<Vec<_>>(), [1, 2, 3]);
+        assert!((points[1].delta + 0.4).abs() < 1e-9);
+        assert_eq!(points[1].changes, ["input contents"]);
+        assert_eq!(points[2].changes, ["options"]);
+        assert_eq!(points[0].data_revision(), "aaaaaaaa");
+
+        let text = format_history(&points, HeatRateUnit::MmbtuPerMwh, 5);
+        assert!(text.lines().nth(1).unwrap().ends_with("-0.500  #####|"), "{}", text);
+    }
+}
@@ -0,0 +1,235 @@
+// hooks.rs
+// This module describes what a finished run did and passes that summary to a user-supplied completion command
+// or POSTs it to a webhook URL.
+
+use std::error::Error;
+use std::process::Command;
+
+use serde::Serialize;
+
+use crate::cleaning::SkipCounts;
+use crate::curl::{post_json, run_with_input};
+use crate::degraded::{DegradedFeature, MissingColumns};
+use crate::heatcontent::HeatContentAnomaly;
+use crate::impute::ImputedValue;
+use crate::overlap::RankingAgreement;
+use crate::timestamp::RunTimestamp;
+
+/// What a run read and wrote; sent to the completion hook as JSON and recorded in the audit log.
+#[derive(Debug, Default, Serialize)]
+pub struct RunSummary {
+    /// Subcommand that ran ("analyze" for the default two-year analysis).
+    pub command: String,
+
+    /// Run label given with `--label`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    /// When the run started, or the `--timestamp-override`.
+    pub timestamp: RunTimestamp,
+
+    /// Input files, in the order they were loaded.
+    pub inputs: Vec<InputSummary>,
+
+    /// Number of states in the main result table.
+    pub states: usize,
+
+    /// Number of states that crossed `--alert-threshold`.
+    pub alerts: usize,
+
+    /// Number of failed energy-unit sanity checks (see `sanity::run_sanity_checks`).
+    #[serde(skip_serializing_if = "is_zero")]
+    pub sanity_warnings: usize,
+
+    /// Agreement between the two inputs' state efficiency rankings, for two-input commands.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ranking: Option<RankingAgreement>,
+
+    /// Output files written, in order.
+    pub outputs: Vec<String>,
+
+    /// Metrics left empty or reports skipped because an input lacks optional columns.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub degraded: Vec<DegradedFeature>,
+
+    /// Monthly values filled by `--impute` (counted per input in the payload).
+    #[serde(skip)]
+    pub imputed_values: Vec<ImputedValue>,
+
+    /// Plant-months with implausible heat content (counted per input in the payload).
+    #[serde(skip)]
+    pub heat_content_anomalies: Vec<HeatContentAnomaly>,
+}
+
+/// An input file, the number of valid rows read from it, how many blank monthly values were imputed and
+/// how many plant-months had implausible heat content.
+#[derive(Debug, Serialize)]
+pub struct InputSummary {
+    pub path: String,
+    pub rows: usize,
+
+    /// Year the data holds (see `years::infer_year`), if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+
+    #[serde(skip_serializing_if = "is_zero")]
+    pub imputed: usize,
+
+    #[serde(skip_serializing_if = "is_zero")]
+    pub anomalies: usize,
+
+    /// Rows dropped while reading, by reason (not known for checkpointed panel years).
+    #[serde(skip)]
+    pub skipped: SkipCounts,
+
+    /// Optional columns the file lacks.
+    #[serde(skip_serializing_if = "MissingColumns::is_empty")]
+    pub missing_columns: MissingColumns,
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+impl RunSummary {
+    pub fn new(command: &str) -> RunSummary {
+        RunSummary { command: command.to_string(), timestamp: RunTimestamp::now(), ..RunSummary::default() }
+    }
+
+    /// Records an input file.
+    pub fn input(&mut self, path: &str, rows: usize) {
+        self.inputs.push(InputSummary {
+            path: path.to_string(),
+            rows,
+            year: None,
+            imputed: 0,
+            anomalies: 0,
+            skipped: SkipCounts::default(),
+            missing_columns: MissingColumns::default(),
+        });
+    }
+
+    /// Records the optional columns the last input file lacks.
+    pub fn missing_columns(&mut self, missing: MissingColumns) {
+        if let Some(input) = self.inputs.last_mut() {
+            input.missing_columns = missing;
+        }
+    }
+
+    /// Optional columns missing from any input.
+    pub fn missing_in_any_input(&self) -> MissingColumns {
+        let mut missing = MissingColumns::default();
+        for input in &self.inputs {
+            missing.extend(&input.missing_columns);
+        }
+        missing
+    }
+
+    /// Records the year of the last input file.
+    pub fn year(&mut self, year: Option<i32>) {
+        if let Some(input) = self.inputs.last_mut() {
+            input.year = year;
+        }
+    }
+
+    /// Records the values imputed in the last input file.
+    pub fn imputed(&mut self, count: usize) {
+        if let Some(input) = self.inputs.last_mut() {
+            input.imputed += count;
+        }
+    }
+
+    /// Records the heat content anomalies found in the last input file.
+    pub fn anomalies(&mut self, count: usize) {
+        if let Some(input) = self.inputs.last_mut() {
+            input.anomalies += count;
+        }
+    }
+
+    /// Records the rows dropped while reading the last input file.
+    pub fn skipped(&mut self, skipped: SkipCounts) {
+        if let Some(input) = self.inputs.last_mut() {
+            input.skipped = skipped;
+        }
+    }
+
+    /// Records an output file.
+    pub fn output(&mut self, path: &str) {
+        self.outputs.push(path.to_string());
+    }
+}
+
+/// Runs `command` through the shell with the run summary as JSON on stdin (e.g.
+/// `curl -X POST -H 'Content-Type: application/json' -d @- https://hooks.example.com/...` to post it to a webhook).
+/// A hook that cannot be started or exits unsuccessfully is reported as an error.
+pub fn run_completion_hook(command: &str, summary: &RunSummary) -> Result<(), Box<dyn Error>> {
+    let payload = serde_json::to_string(summary)?;
+
+    let status = run_with_input(Command::new("sh").arg("-c").arg(command), &payload)?;
+    if !status.success() {
+        return Err(format!("completion hook '{}' failed ({})", command, status).into());
+    }
+    Ok(())
+}
+
+/// POSTs the run summary as JSON to a webhook URL (e.g. a chat channel's incoming webhook or a CI trigger) with
+/// the system `curl`, which follows the HTTPS_PROXY / HTTP_PROXY variables like the downloads do.
+/// # Arguments
+/// * `url` - Webhook URL
+/// * `summary` - The finished run
+/// * `ca_cert` - PEM bundle of CA certificates to trust instead of the system ones
+pub fn post_run_summary(url: &str, summary: &RunSummary, ca_cert: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let payload = serde_json::to_string(summary)?;
+
+    let status = post_json(url, &payload, ca_cert, "--webhook-url")?;
+    if !status.success() {
+        return Err(format!("posting the run summary to '{}' failed (curl {})", url, status).into());
+    }
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_summary() {
+        let mut summary = RunSummary::new("analyze");
+        summary.input("2019.csv", 10);
+        summary.year(Some(2019));
+        summary.imputed(2);
+        summary.imputed(1);
+        summary.input("2020.csv", 12);
+        summary.output("efficiency_changes.csv");
+
+        let payload = serde_json::to_value(&summary).unwrap();
+        assert_eq!(payload["command"], "analyze");
+        assert_eq!(payload["inputs"][0]["imputed"], 3);
+        // Zero counts, unknown years and unset options are left out of the payload
+        assert!(payload["inputs"][1].get("imputed").is_none() && payload["inputs"][1].get("year").is_none());
+        assert!(payload.get("label").is_none() && payload.get("sanity_warnings").is_none());
+        assert_eq!(payload["outputs"][0], "efficiency_changes.csv");
+    }
+
+    #[test]
+    fn test_run_completion_hook() {
+        let path = std::env::temp_dir().join(format!("efficiency_hook_{}.json", std::process::id()));
+        let summary = RunSummary::new("panel");
+        run_completion_hook(&format!("cat > '{}'", path.display()), &summary).unwrap();
+        let payload: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(payload["command"], "panel");
+        std::fs::remove_file(&path).unwrap();
+
+        let error = run_completion_hook("exit 3", &summary).unwrap_err().to_string();
+        assert!(error.contains("completion hook 'exit 3' failed"));
+    }
+
+    #[test]
+    fn test_post_run_summary() {
+        // Nothing listens on port 1, so curl cannot connect
+        let summary = RunSummary::new("analyze");
+        let error = post_run_summary("http://127.0.0.1:1/hook", &summary, None).unwrap_err().to_string();
+        assert!(error.contains("curl"));
+    }
+}
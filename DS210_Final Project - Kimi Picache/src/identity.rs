@@ -0,0 +1,153 @@
+// identity.rs
+// This module checks that each plant looks like the same plant in both years: same state, same primary fuel and
+// same prime movers. Changes are often data errors or reclassifications and can be excluded from the comparison.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{CleanRecord, INCREMENT_PLANT_ID};
+
+/// What identifies a plant in one year.
+#[derive(Debug, Default)]
+struct PlantProfile {
+    name: String,
+    states: BTreeSet<String>,
+
+    /// Fuel consumed per AER fuel code (MMBtu), used to find the primary fuel.
+    fuels: HashMap<String, f64>,
+    prime_movers: BTreeSet<String>,
+}
+
+impl PlantProfile {
+    /// AER fuel code with the most fuel consumed (ties broken by code).
+    fn primary_fuel(&self) -> String {
+        self.fuels
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(code, _)| code.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// A plant whose state, primary fuel or prime movers differ between the two years.
+#[derive(Debug, Serialize)]
+pub struct IdentityChange {
+    pub plant_id: String,
+    pub plant_name: String,
+
+    /// "state", "primary_fuel" or "prime_mover".
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+fn profiles(records: &[CleanRecord]) -> BTreeMap<&str, PlantProfile> {
+    let mut profiles: BTreeMap<&str, PlantProfile> = BTreeMap::new();
+
+    for record in records.iter().filter(|r| r.plant_id != INCREMENT_PLANT_ID) {
+        let profile = profiles.entry(&record.plant_id).or_default();
+        profile.name.clone_from(&record.plant_name);
+        profile.states.insert(record.state.clone());
+        *profile.fuels.entry(record.aer_fuel.clone()).or_default() += record.fuel;
+        if !record.prime_mover.is_empty() {
+            profile.prime_movers.insert(record.prime_mover.clone());
+        }
+    }
+
+    profiles
+}
+
+fn joined(values: &BTreeSet<String>) -> String {
+    values.iter().cloned().collect::<Vec<_>>().join("/")
+}
+
+/// Lists every plant reporting in both years whose identity changed.
+/// # Arguments
+/// * `records_a` - Cleaned rows for the base year
+/// * `records_b` - Cleaned rows for the compare year
+/// # Returns
+/// * `Vec<IdentityChange>` sorted by plant id, one row per changed field
+pub fn check_plant_identity(records_a: &[CleanRecord], records_b: &[CleanRecord]) -> Vec<IdentityChange> {
+    let profiles_b = profiles(records_b);
+    let mut changes = Vec::new();
+
+    for (plant_id, before) in profiles(records_a) {
+        let Some(after) = profiles_b.get(plant_id) else {
+            continue;
+        };
+
+        let fields = [
+            ("state", joined(&before.states), joined(&after.states)),
+            ("primary_fuel", before.primary_fuel(), after.primary_fuel()),
+            ("prime_mover", joined(&before.prime_movers), joined(&after.prime_movers)),
+        ];
+        for (field, old, new) in fields {
+            if old != new {
+                changes.push(IdentityChange {
+                    plant_id: plant_id.to_string(),
+                    plant_name: after.name.clone(),
+                    field,
+                    before: old,
+                    after: new,
+                });
+            }
+        }
+    }
+
+    changes
+}
+
+/// Removes the rows of the listed plants; returns how many rows were dropped.
+pub fn exclude_plants(records: &mut Vec<CleanRecord>, plant_ids: &BTreeSet<&str>) -> usize {
+    let before = records.len();
+    records.retain(|r| !plant_ids.contains(r.plant_id.as_str()));
+    before - records.len()
+}
+
+/// Writes the identity changes to a CSV output file.
+pub fn write_identity_changes_csv(path: &str, data: &[IdentityChange]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Plant_Id", "Plant_Name", "Field", "Before", "After"])?;
+
+    for item in data {
+        wtr.write_record([&item.plant_id, &item.plant_name, item.field, &item.before, &item.after])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_identity_changes() {
+        let base = [
+            CleanRecord { prime_mover: "ST".to_string(), ..record("1", "AA", "COL", 100.0, 1.0) },
+            CleanRecord { prime_mover: "ST".to_string(), ..record("1", "AA", "NG", 10.0, 1.0) },
+            CleanRecord { prime_mover: "CT".to_string(), ..record("2", "AA", "NG", 10.0, 1.0) },
+            CleanRecord { prime_mover: "CT".to_string(), ..record("3", "AA", "NG", 10.0, 1.0) },
+        ];
+        let mut compare = vec![
+            CleanRecord { prime_mover: "ST".to_string(), ..record("1", "AA", "COL", 10.0, 1.0) },
+            CleanRecord { prime_mover: "ST".to_string(), ..record("1", "AA", "NG", 100.0, 1.0) },
+            CleanRecord { prime_mover: "CT".to_string(), ..record("2", "BB", "NG", 10.0, 1.0) },
+            CleanRecord { prime_mover: "CA".to_string(), ..record("2", "BB", "NG", 10.0, 1.0) },
+            CleanRecord { prime_mover: "CT".to_string(), ..record("3", "AA", "NG", 10.0, 1.0) },
+        ];
+
+        let changes = check_plant_identity(&base, &compare);
+        let fields: Vec<(&str, &str, &str)> = changes.iter().map(|c| (c.plant_id.as_str(), c.field, c.after.as_str())).collect();
+        assert_eq!(fields, [("1", "primary_fuel", "NG"), ("2", "state", "BB"), ("2", "prime_mover", "CA/CT")]);
+
+        let ids: BTreeSet<&str> = changes.iter().map(|c| c.plant_id.as_str()).collect();
+        assert_eq!(exclude_plants(&mut compare, &ids), 4);
+        assert_eq!(compare.len(), 1);
+    }
+}
@@ -0,0 +1,154 @@
+// impute.rs
+// This module fills blank monthly fuel and generation values ("." in EIA-923) with a chosen strategy and
+// records every value it filled, so imputed months can be traced in the outputs and the audit log.
+
+use std::error::Error;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use csv::WriterBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::cleaning::{CleanRecord, MONTHS};
+
+/// How blank monthly values are filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum ImputeStrategy {
+    /// Blank months count as zero (no imputation is recorded).
+    #[default]
+    Zero,
+    /// Repeat the last reported month of the same row; leading blanks stay zero.
+    CarryForward,
+    /// Split the part of the annual total not covered by the reported months evenly over the blank months.
+    Proportional,
+}
+
+impl ImputeStrategy {
+    /// Name as given on the command line.
+    pub fn label(self) -> &'static str {
+        match self {
+            ImputeStrategy::Zero => "zero",
+            ImputeStrategy::CarryForward => "carry-forward",
+            ImputeStrategy::Proportional => "proportional",
+        }
+    }
+}
+
+/// One filled monthly value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImputedValue {
+    pub plant_id: String,
+    pub state: String,
+    pub aer_fuel: String,
+
+    /// Month number, 1 = January.
+    pub month: usize,
+
+    /// "fuel" (MMBtu) or "generation" (MWh).
+    pub field: String,
+    pub value: f64,
+}
+
+/// Fills one row's monthly series in place and returns the (month, value) pairs it filled.
+fn impute_series(values: &mut [f64; 12], missing: &[bool; 12], annual: f64, strategy: ImputeStrategy) -> Vec<(usize, f64)> {
+    let blank: Vec<usize> = (0..12).filter(|m| missing[*m]).collect();
+    if blank.is_empty() {
+        return Vec::new();
+    }
+
+    match strategy {
+        ImputeStrategy::Zero => return Vec::new(),
+        ImputeStrategy::CarryForward => {
+            for month in 1..12 {
+                if missing[month] {
+                    values[month] = values[month - 1];
+                }
+            }
+        }
+        ImputeStrategy::Proportional => {
+            let reported: f64 = (0..12).filter(|m| !missing[*m]).map(|m| values[m]).sum();
+            let share = (annual - reported) / blank.len() as f64;
+            for month in &blank {
+                values[*month] = share;
+            }
+        }
+    }
+
+    blank.into_iter().map(|month| (month, values[month])).collect()
+}
+
+/// Fills the blank monthly values of every row.
+/// # Arguments
+/// * `records` - Cleaned rows with their blank months flagged
+/// * `strategy` - How to fill them
+/// # Returns
+/// * `Vec<ImputedValue>` listing each filled value (empty for `ImputeStrategy::Zero`)
+pub fn impute_monthly(records: &mut [CleanRecord], strategy: ImputeStrategy) -> Vec<ImputedValue> {
+    let mut imputed = Vec::new();
+
+    for record in records.iter_mut() {
+        let fuel = impute_series(&mut record.monthly_fuel, &record.missing_fuel, record.fuel, strategy);
+        let r#gen = impute_series(&mut record.monthly_gen, &record.missing_gen, record.r#gen, strategy);
+
+        for (field, filled) in [("fuel", fuel), ("generation", r#gen)] {
+            imputed.extend(filled.into_iter().map(|(month, value)| ImputedValue {
+                plant_id: record.plant_id.clone(),
+                state: record.state.clone(),
+                aer_fuel: record.aer_fuel.clone(),
+                month: month + 1,
+                field: field.to_string(),
+                value,
+            }));
+        }
+    }
+
+    imputed
+}
+
+/// Writes the filled values to a CSV output file.
+pub fn write_imputed_csv(path: &str, data: &[ImputedValue]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Plant_Id", "State", "AER_Fuel", "Month", "Field", "Imputed_Value"])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.plant_id,
+            &item.state,
+            &item.aer_fuel,
+            MONTHS[item.month - 1],
+            &item.field,
+            &format!("{:.6}", item.value),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impute_strategies() {
+        let mut missing = [false; 12];
+        missing[0] = true;
+        missing[5] = true;
+        let reported = [0.0, 10.0, 10.0, 10.0, 10.0, 0.0, 10.0, 10.0, 10.0, 10.0, 10.0, 10.0];
+
+        let mut values = reported;
+        assert!(impute_series(&mut values, &missing, 130.0, ImputeStrategy::Zero).is_empty());
+        assert_eq!(values, reported);
+
+        let mut values = reported;
+        let filled = impute_series(&mut values, &missing, 130.0, ImputeStrategy::CarryForward);
+        assert_eq!(filled, [(0, 0.0), (5, 10.0)]);
+
+        let mut values = reported;
+        let filled = impute_series(&mut values, &missing, 130.0, ImputeStrategy::Proportional);
+        assert_eq!(filled, [(0, 15.0), (5, 15.0)]);
+        assert_eq!(values.iter().sum::<f64>(), 130.0);
+    }
+}
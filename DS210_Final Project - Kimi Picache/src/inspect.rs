@@ -0,0 +1,211 @@
+// inspect.rs
+// This module profiles every column of a raw EIA-923 CSV before any cleaning: min / max / mean and blank share
+// for numeric columns, number of distinct values for text columns. Wrong units, shifted or swapped columns and
+// text in numeric fields show up here before a full run is spent on the file.
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use serde::Serialize;
+
+use crate::cleaning::read_csv_body;
+use crate::stats::CompensatedSum;
+
+/// Share of the filled cells that must parse as numbers for a column to count as numeric.
+const NUMERIC_SHARE: f64 = 0.95;
+
+/// Columns the cleaning step reads as numbers, by header prefix (line breaks as spaces, so the monthly
+/// columns are covered too).
+const NUMERIC_COLUMNS: [&str; 5] =
+    ["Total Fuel Consumption MMBtu", "Net Generation (Megawatthours)", "Tot_MMBtu ", "Netgen ", "Quantity "];
+
+/// Whether a column holds numbers or text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColumnKind {
+    Numeric,
+    Categorical,
+}
+
+impl ColumnKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ColumnKind::Numeric => "numeric",
+            ColumnKind::Categorical => "categorical",
+        }
+    }
+}
+
+/// Statistics for one column of the raw file.
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnProfile {
+    /// Column header, with line breaks replaced by spaces.
+    pub column: String,
+
+    pub kind: ColumnKind,
+
+    /// Data rows read.
+    pub rows: usize,
+
+    /// Empty cells and EIA's "." blanks.
+    pub blanks: usize,
+
+    /// Filled cells that are not numbers (for a numeric column, the cells that break the pattern).
+    pub non_numeric: usize,
+
+    /// Range and mean of the numeric cells, for numeric columns.
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+
+    /// Distinct filled values.
+    pub distinct: usize,
+}
+
+impl ColumnProfile {
+    /// Share of blank cells (0-1).
+    pub fn blank_rate(&self) -> f64 {
+        if self.rows == 0 { 0.0 } else { self.blanks as f64 / self.rows as f64 }
+    }
+
+    /// Whether the cleaning step expects numbers in this column but it holds mostly text.
+    pub fn unexpected_text(&self) -> bool {
+        self.kind == ColumnKind::Categorical
+            && NUMERIC_COLUMNS.iter().any(|prefix| self.column.starts_with(prefix))
+    }
+}
+
+/// Running statistics for one column.
+#[derive(Default)]
+struct ColumnAccumulator {
+    blanks: usize,
+    non_numeric: usize,
+    numbers: usize,
+    sum: CompensatedSum,
+    min: Option<f64>,
+    max: Option<f64>,
+    distinct: HashSet<String>,
+}
+
+impl ColumnAccumulator {
+    fn add(&mut self, cell: &str) {
+        let cell = cell.trim();
+        if cell.is_empty() || cell == "." {
+            self.blanks += 1;
+            return;
+        }
+        if !self.distinct.contains(cell) {
+            self.distinct.insert(cell.to_string());
+        }
+        match cell.replace(',', "").parse::<f64>() {
+            Ok(value) => {
+                self.numbers += 1;
+                self.sum.add(value);
+                self.min = Some(self.min.map_or(value, |m| m.min(value)));
+                self.max = Some(self.max.map_or(value, |m| m.max(value)));
+            }
+            Err(_) => self.non_numeric += 1,
+        }
+    }
+
+    fn finish(self, column: &str, rows: usize) -> ColumnProfile {
+        let filled = self.numbers + self.non_numeric;
+        let numeric = filled > 0 && self.numbers as f64 >= NUMERIC_SHARE * filled as f64;
+        ColumnProfile {
+            column: column.replace('\n', " "),
+            kind: if numeric { ColumnKind::Numeric } else { ColumnKind::Categorical },
+            rows,
+            blanks: self.blanks,
+            non_numeric: self.non_numeric,
+            min: self.min.filter(|_| numeric),
+            max: self.max.filter(|_| numeric),
+            mean: numeric.then(|| self.sum.total() / self.numbers as f64),
+            distinct: self.distinct.len(),
+        }
+    }
+}
+
+/// Profiles the columns of parsed rows.
+/// # Arguments
+/// * `headers` - Column header row
+/// * `rows` - Data rows; short rows count as blank in their missing columns
+/// # Returns
+/// * One `ColumnProfile` per header, in file order
+pub fn profile_columns(headers: &StringRecord, rows: impl IntoIterator<Item = StringRecord>) -> Vec<ColumnProfile> {
+    let mut columns: Vec<ColumnAccumulator> = headers.iter().map(|_| ColumnAccumulator::default()).collect();
+    let mut count = 0;
+    for row in rows {
+        count += 1;
+        for (i, column) in columns.iter_mut().enumerate() {
+            column.add(row.get(i).unwrap_or(""));
+        }
+    }
+    columns.into_iter().zip(headers.iter()).map(|(column, name)| column.finish(name, count)).collect()
+}
+
+/// Reads an EIA-923 CSV export (after its metadata lines) and profiles every column.
+/// # Returns
+/// * One `ColumnProfile` per column; unreadable rows are skipped
+pub fn inspect_file(file_path: &str) -> Result<Vec<ColumnProfile>, Box<dyn Error>> {
+    let csv_data = read_csv_body(file_path)?;
+    let mut rdr = ReaderBuilder::new().has_headers(true).flexible(true).from_reader(csv_data.as_slice());
+    let headers = rdr.headers()?.clone();
+    Ok(profile_columns(&headers, rdr.records().filter_map(Result::ok)))
+}
+
+/// Writes the column profiles to a CSV file.
+/// # Arguments
+/// * `path` - Output file path
+/// * `data` - Profiles from `inspect_file`
+pub fn write_column_profile_csv(path: &str, data: &[ColumnProfile]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Column", "Kind", "Rows", "Blank_Rate", "Non_Numeric", "Min", "Max", "Mean", "Distinct"])?;
+
+    let number = |v: Option<f64>| v.map(|v| format!("{:.6}", v)).unwrap_or_default();
+    for item in data {
+        wtr.write_record([
+            item.column.clone(),
+            item.kind.label().to_string(),
+            item.rows.to_string(),
+            format!("{:.6}", item.blank_rate()),
+            item.non_numeric.to_string(),
+            number(item.min),
+            number(item.max),
+            number(item.mean),
+            item.distinct.to_string(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_numeric_and_text_columns() {
+        let headers = StringRecord::from(vec!["Plant State", "Netgen\nJanuary", "Net Generation\n(Megawatthours)"]);
+        let rows = [
+            StringRecord::from(vec!["TX", "1,000", "12"]),
+            StringRecord::from(vec!["TX", ".", "MWh"]),
+            StringRecord::from(vec!["CA", "-50.5", "MWh"]),
+            StringRecord::from(vec!["NY", ""]),
+        ];
+
+        let profiles = profile_columns(&headers, rows);
+        assert_eq!((profiles[0].kind, profiles[0].distinct, profiles[0].mean), (ColumnKind::Categorical, 3, None));
+
+        let netgen = &profiles[1];
+        assert_eq!((netgen.column.as_str(), netgen.kind), ("Netgen January", ColumnKind::Numeric));
+        assert_eq!((netgen.min, netgen.max, netgen.mean), (Some(-50.5), Some(1000.0), Some(474.75)));
+        assert_eq!(netgen.blank_rate(), 0.5);
+
+        // Mostly text where the cleaning expects numbers: a shifted or mislabeled column
+        assert_eq!((profiles[2].kind, profiles[2].non_numeric), (ColumnKind::Categorical, 2));
+        assert!(profiles[2].unexpected_text() && !profiles[0].unexpected_text());
+    }
+}
@@ -0,0 +1,215 @@
+// jackknife.rs
+// This module estimates how fragile each state's place in the top movers table is: every plant of the state is
+// dropped in turn (from both years), the state's change is recomputed, and we record how far its position moves
+// while the other states keep their full-sample values.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::analysis::{RankBy, StateEfficiency};
+use crate::cleaning::{aggregate_by_state_plant, CleanRecord, PlantStats};
+use crate::report::{Cell, Report};
+
+/// Drop-one-plant results for a state.
+#[derive(Debug, Clone, Serialize)]
+pub struct RankStability {
+    pub state: String,
+
+    /// Plants dropped in turn (the state-level increment counts as one); replicates that leave the state
+    /// without generation in a year are skipped.
+    pub replicates: usize,
+
+    /// Position in the top movers table (1 = largest mover) and its range over the replicates.
+    pub position: usize,
+    pub position_min: usize,
+    pub position_max: usize,
+
+    /// Jackknife standard error of the state's change (MMBtu/MWh).
+    pub delta_se: f64,
+
+    /// 1 - (position range / (states - 1)): 1 when no single plant moves the state, 0 when one plant can
+    /// move it from the top of the table to the bottom.
+    pub stability: f64,
+}
+
+/// Value the table is sorted by (descending).
+fn sort_key(delta: f64, gen_compare: f64, rank_by: RankBy) -> f64 {
+    match rank_by {
+        RankBy::Delta => delta.abs(),
+        RankBy::Weighted => (delta * gen_compare).abs(),
+    }
+}
+
+/// Fuel and generation of each plant, grouped by state.
+fn by_state(plants: &HashMap<(String, String), PlantStats>) -> BTreeMap<&str, HashMap<&str, (f64, f64)>> {
+    let mut states: BTreeMap<&str, HashMap<&str, (f64, f64)>> = BTreeMap::new();
+    for ((state, plant_id), stats) in plants {
+        states.entry(state).or_default().insert(plant_id, (stats.total_fuel, stats.total_gen));
+    }
+    states
+}
+
+fn total(plants: &HashMap<&str, (f64, f64)>) -> (f64, f64) {
+    plants.values().fold((0.0, 0.0), |(fuel, r#gen), p| (fuel + p.0, r#gen + p.1))
+}
+
+/// Runs the drop-one-plant jackknife for every state in the table.
+/// # Arguments
+/// * `records_a` - Cleaned rows for the base year
+/// * `records_b` - Cleaned rows for the compare year
+/// * `changes` - The efficiency table, sorted with `sort_changes` by `rank_by`
+/// * `rank_by` - Order of the table
+/// # Returns
+/// * `Vec<RankStability>` in table order
+pub fn jackknife_rank_stability(
+    records_a: &[CleanRecord],
+    records_b: &[CleanRecord],
+    changes: &[StateEfficiency],
+    rank_by: RankBy,
+) -> Vec<RankStability> {
+    let (plants_a, plants_b) = (aggregate_by_state_plant(records_a), aggregate_by_state_plant(records_b));
+    let (states_a, states_b) = (by_state(&plants_a), by_state(&plants_b));
+    let empty = HashMap::new();
+
+    let keys: Vec<f64> = changes
+        .iter()
+        .map(|c| match rank_by {
+            RankBy::Delta => c.abs_delta,
+            RankBy::Weighted => c.weighted_delta.abs(),
+        })
+        .collect();
+    let span = changes.len().saturating_sub(1).max(1) as f64;
+
+    changes
+        .iter()
+        .enumerate()
+        .map(|(index, change)| {
+            let rows_a = states_a.get(change.state.as_str()).unwrap_or(&empty);
+            let rows_b = states_b.get(change.state.as_str()).unwrap_or(&empty);
+            let (total_a, total_b) = (total(rows_a), total(rows_b));
+
+            let mut plant_ids: Vec<&str> = rows_a.keys().chain(rows_b.keys()).copied().collect();
+            plant_ids.sort_unstable();
+            plant_ids.dedup();
+
+            let mut deltas = Vec::new();
+            let (mut position_min, mut position_max) = (index + 1, index + 1);
+            for id in plant_ids {
+                let (fuel_a, gen_a) = rows_a.get(id).copied().unwrap_or_default();
+                let (fuel_b, gen_b) = rows_b.get(id).copied().unwrap_or_default();
+                let (rest_gen_a, rest_gen_b) = (total_a.1 - gen_a, total_b.1 - gen_b);
+                if rest_gen_a <= 0.0 || rest_gen_b <= 0.0 {
+                    continue;
+                }
+
+                let delta = (total_b.0 - fuel_b) / rest_gen_b - (total_a.0 - fuel_a) / rest_gen_a;
+                let key = sort_key(delta, rest_gen_b, rank_by);
+                let position = 1 + keys.iter().enumerate().filter(|(i, k)| *i != index && **k > key).count();
+                position_min = position_min.min(position);
+                position_max = position_max.max(position);
+                deltas.push(delta);
+            }
+
+            let n = deltas.len() as f64;
+            let mean = deltas.iter().sum::<f64>() / n.max(1.0);
+            let variance = deltas.iter().map(|d| (d - mean).powi(2)).sum::<f64>() * (n - 1.0).max(0.0) / n.max(1.0);
+
+            RankStability {
+                state: change.state.clone(),
+                replicates: deltas.len(),
+                position: index + 1,
+                position_min,
+                position_max,
+                delta_se: variance.sqrt(),
+                stability: 1.0 - (position_max - position_min) as f64 / span,
+            }
+        })
+        .collect()
+}
+
+/// Adds a `Rank_Stability` column to the efficiency table, matching rows by state.
+pub fn append_stability_column(report: &mut Report, data: &[RankStability]) {
+    let values: Vec<Cell> = report
+        .text_column("State")
+        .iter()
+        .map(|state| data.iter().find(|s| s.state == *state).map_or(Cell::from(""), |s| Cell::from(s.stability)))
+        .collect();
+    report.push_column("Rank_Stability", 6, values);
+}
+
+/// Writes the jackknife results to a CSV output file.
+pub fn write_rank_stability_csv(path: &str, data: &[RankStability]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["State", "Position", "Position_Min", "Position_Max", "Replicates", "Delta_SE", "Stability"])?;
+
+    for item in data {
+        wtr.write_record([
+            item.state.clone(),
+            item.position.to_string(),
+            item.position_min.to_string(),
+            item.position_max.to_string(),
+            item.replicates.to_string(),
+            format!("{:.6}", item.delta_se),
+            format!("{:.6}", item.stability),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{compute_efficiency_changes, sort_changes};
+    use crate::cleaning::aggregate_by_state;
+    use crate::cleaning::fixtures::record;
+
+    /// AA's change comes from plant 2 alone; BB's two plants improve alike; CC is flat.
+    fn stability() -> Vec<RankStability> {
+        let base = [
+            record("1", "AA", "NG", 80.0, 10.0),
+            record("2", "AA", "NG", 80.0, 10.0),
+            record("3", "BB", "NG", 100.0, 10.0),
+            record("4", "BB", "NG", 100.0, 10.0),
+            record("5", "CC", "NG", 90.0, 10.0),
+        ];
+        let compare = [
+            record("1", "AA", "NG", 80.0, 10.0),
+            record("2", "AA", "NG", 120.0, 10.0),
+            record("3", "BB", "NG", 90.0, 10.0),
+            record("4", "BB", "NG", 90.0, 10.0),
+            record("5", "CC", "NG", 90.0, 10.0),
+        ];
+        let mut changes = compute_efficiency_changes(&aggregate_by_state(&base), &aggregate_by_state(&compare));
+        sort_changes(&mut changes, RankBy::Delta);
+
+        jackknife_rank_stability(&base, &compare, &changes, RankBy::Delta)
+    }
+
+    #[test]
+    fn test_one_plant_drives_the_change() {
+        let result = stability();
+        let aa = &result[0];
+        assert_eq!((aa.state.as_str(), aa.replicates, aa.position_min, aa.position_max), ("AA", 2, 1, 2));
+        assert_eq!(aa.stability, 0.5);
+        assert!((aa.delta_se - 2.0).abs() < 1e-9);
+
+        let bb = &result[1];
+        assert_eq!((bb.position_min, bb.position_max, bb.delta_se, bb.stability), (2, 2, 0.0, 1.0));
+        assert_eq!(result[2].replicates, 0);
+    }
+
+    #[test]
+    fn test_stability_column() {
+        let result = stability();
+        let mut report = Report::new("Test").column("State", 0);
+        report.push_row(vec!["BB".into()]);
+        append_stability_column(&mut report, &result);
+        assert_eq!(report.rows[0][1], Cell::Float(1.0));
+    }
+}
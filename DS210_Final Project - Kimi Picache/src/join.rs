@@ -0,0 +1,218 @@
+// join.rs
+// This module joins rows of another dataset (EIA-860 schedules today; eGRID, Census or NOAA tables the same way)
+// onto the EIA-923 data by a shared key: a state code, an EIA plant or utility code, or a FIPS code. Keys are
+// normalized per kind before matching ("003" and "3" are the same plant, "06" and "6" the same FIPS state), the
+// join is left (unmatched rows kept, with nothing joined) or inner (unmatched rows dropped), and every join
+// reports the keys that found no partner on either side, so silent losses in a merge show up in the output.
+
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use csv::WriterBuilder;
+
+/// Kind of key two datasets are joined on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JoinKey {
+    /// Two-letter state code, matched in upper case.
+    State,
+    /// EIA plant code, matched as a number (leading zeros and thousands separators ignored).
+    PlantId,
+    /// EIA utility code, matched like plant codes.
+    UtilityId,
+    /// FIPS code: two digits for a state, five for a county, zero-padded.
+    Fips,
+}
+
+impl JoinKey {
+    /// Canonical form of a key of this kind; blank keys stay blank and never match.
+    pub fn normalize(self, key: &str) -> String {
+        let key = key.trim();
+        match self {
+            JoinKey::State => key.to_ascii_uppercase(),
+            JoinKey::PlantId | JoinKey::UtilityId => {
+                let digits = key.replace(',', "");
+                match digits.trim_start_matches('0') {
+                    "" if !digits.is_empty() => "0".to_string(),
+                    trimmed => trimmed.to_string(),
+                }
+            }
+            JoinKey::Fips if key.is_empty() || !key.chars().all(|c| c.is_ascii_digit()) => key.to_string(),
+            JoinKey::Fips if key.len() <= 2 => format!("{:0>2}", key),
+            JoinKey::Fips => format!("{:0>5}", key),
+        }
+    }
+
+    /// Name for messages and the unmatched key report.
+    pub fn label(self) -> &'static str {
+        match self {
+            JoinKey::State => "state",
+            JoinKey::PlantId => "plant id",
+            JoinKey::UtilityId => "utility id",
+            JoinKey::Fips => "FIPS",
+        }
+    }
+}
+
+/// Which rows of the left side a join keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum JoinType {
+    /// Every left row; unmatched ones are reported and get nothing joined (e.g. an "unknown" group).
+    #[default]
+    Left,
+    /// Only left rows with a match; unmatched ones are reported and dropped.
+    Inner,
+}
+
+/// Rows of the right side of a join, grouped by normalized key.
+#[derive(Debug)]
+pub struct KeyIndex<'a, R> {
+    pub key: JoinKey,
+    rows: HashMap<String, Vec<&'a R>>,
+}
+
+impl<'a, R> KeyIndex<'a, R> {
+    /// Indexes `rows` by the key `key_of` reads from each; rows with a blank key are left out.
+    pub fn new(key: JoinKey, rows: &'a [R], key_of: impl Fn(&R) -> &str) -> KeyIndex<'a, R> {
+        let mut index: HashMap<String, Vec<&'a R>> = HashMap::new();
+        for row in rows {
+            let normalized = key.normalize(key_of(row));
+            if !normalized.is_empty() {
+                index.entry(normalized).or_default().push(row);
+            }
+        }
+        KeyIndex { key, rows: index }
+    }
+
+    /// Rows matching a key (normalized first); empty without a match.
+    pub fn get(&self, key: &str) -> &[&'a R] {
+        self.rows.get(&self.key.normalize(key)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether any row matches a key.
+    pub fn contains(&self, key: &str) -> bool {
+        !self.get(key).is_empty()
+    }
+
+    /// Joins left keys against the index and reports the keys matched and unmatched on each side.
+    /// # Arguments
+    /// * `dataset` - Name of the right side for the report, e.g. "EIA-860 generators"
+    /// * `left_keys` - Keys of the left side; duplicates count once
+    /// * `join_type` - Left or inner join
+    pub fn report<'k>(
+        &self,
+        dataset: &str,
+        left_keys: impl IntoIterator<Item = &'k str>,
+        join_type: JoinType,
+    ) -> JoinReport {
+        let left: BTreeSet<String> =
+            left_keys.into_iter().map(|k| self.key.normalize(k)).filter(|k| !k.is_empty()).collect();
+        let unmatched_left: BTreeSet<String> = left.iter().filter(|k| !self.rows.contains_key(*k)).cloned().collect();
+        let unmatched_right: BTreeSet<String> = self.rows.keys().filter(|k| !left.contains(*k)).cloned().collect();
+        JoinReport {
+            dataset: dataset.to_string(),
+            key: self.key,
+            join_type,
+            left_keys: left.len(),
+            right_keys: self.rows.len(),
+            unmatched_left,
+            unmatched_right,
+        }
+    }
+}
+
+/// Outcome of a join: how many keys each side had and which found no partner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JoinReport {
+    pub dataset: String,
+    pub key: JoinKey,
+    pub join_type: JoinType,
+    pub left_keys: usize,
+    pub right_keys: usize,
+
+    /// Keys of the data with no row in the dataset (kept with nothing joined in a left join, dropped in an inner one).
+    pub unmatched_left: BTreeSet<String>,
+
+    /// Keys of the dataset with no row in the data.
+    pub unmatched_right: BTreeSet<String>,
+}
+
+impl JoinReport {
+    /// Left keys with a match.
+    pub fn matched(&self) -> usize {
+        self.left_keys - self.unmatched_left.len()
+    }
+
+    /// Whether a left row with this key is kept by the join.
+    pub fn keeps(&self, key: &str) -> bool {
+        self.join_type == JoinType::Left || !self.unmatched_left.contains(&self.key.normalize(key))
+    }
+}
+
+impl fmt::Display for JoinReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sample = |keys: &BTreeSet<String>| {
+            let mut text = keys.iter().take(5).cloned().collect::<Vec<_>>().join(", ");
+            if keys.len() > 5 {
+                text += ", ...";
+            }
+            text
+        };
+        write!(f, "{} joined by {}: {} of {} matched", self.dataset, self.key.label(), self.matched(), self.left_keys)?;
+        if !self.unmatched_left.is_empty() {
+            let fate = if self.join_type == JoinType::Left { "kept unmatched" } else { "dropped" };
+            write!(f, "; {} {} ({})", self.unmatched_left.len(), fate, sample(&self.unmatched_left))?;
+        }
+        if !self.unmatched_right.is_empty() {
+            write!(f, "; {} of {} dataset keys unused", self.unmatched_right.len(), self.right_keys)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the unmatched keys of every join to a CSV output file, one row per key and side.
+pub fn write_unmatched_keys_csv(path: &str, reports: &[JoinReport]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Dataset", "Key_Type", "Side", "Key"])?;
+
+    for report in reports {
+        for (side, keys) in [("data", &report.unmatched_left), ("dataset", &report.unmatched_right)] {
+            for key in keys {
+                wtr.write_record([report.dataset.as_str(), report.key.label(), side, key])?;
+            }
+        }
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_and_join_report() {
+        assert_eq!(JoinKey::PlantId.normalize(" 003 "), "3");
+        assert_eq!(JoinKey::UtilityId.normalize("1,234"), "1234");
+        assert_eq!(JoinKey::PlantId.normalize("000"), "0");
+        assert_eq!((JoinKey::Fips.normalize("6"), JoinKey::Fips.normalize("6037")), ("06".into(), "06037".into()));
+        assert_eq!(JoinKey::State.normalize(" tx"), "TX");
+
+        let rows = [("3", 'a'), ("003", 'b'), ("10", 'c'), ("", 'd')];
+        let index = KeyIndex::new(JoinKey::PlantId, &rows, |row| row.0);
+        assert_eq!(index.get("03").iter().map(|row| row.1).collect::<String>(), "ab");
+        assert!(!index.contains(""));
+
+        let report = index.report("test rows", ["3", "7", "7"], JoinType::Inner);
+        assert_eq!((report.left_keys, report.matched(), report.right_keys), (2, 1, 2));
+        assert_eq!(report.unmatched_right, BTreeSet::from(["10".to_string()]));
+        assert!(report.keeps("3") && !report.keeps("07"));
+        let text = "test rows joined by plant id: 1 of 2 matched; 1 dropped (7); 1 of 2 dataset keys unused";
+        assert_eq!(report.to_string(), text);
+    }
+}
@@ -0,0 +1,114 @@
+// lib.rs
+// Library entry point: the loading, cleaning and analysis modules, usable from the CLI or from notebooks (evcxr).
+// Optional subsystems sit behind Cargo features (all on by default): `cli` (clap value enums), `templates`
+// (Tera reports), `schema` (JSON Schema output), `charts` (HTML charts), `network` (URL inputs), `sqlite`
+// (the results database), `parquet` (the `convert` subcommand's output) and `xlsx` (workbook inputs).
+// `api` lists the types covered by semantic versioning; the other modules serve the binary and may change.
+
+pub mod adjacency;
+pub mod api;
+pub mod aggregation;
+pub mod alerts;
+pub mod analysis;
+pub mod averages;
+pub mod bands;
+pub mod audit;
+pub mod baseline;
+pub mod bench;
+pub mod builder;
+pub mod cache;
+pub mod cancel;
+pub mod capacityweight;
+pub mod changepoint;
+pub mod changesummary;
+#[cfg(feature = "charts")]
+pub mod charts;
+pub mod checkpoint;
+pub mod cleaned;
+pub mod context;
+pub mod convergence;
+#[cfg(feature = "parquet")]
+pub mod convert;
+pub mod contributions;
+pub mod coverage;
+pub mod cleaning;
+pub mod curl;
+pub mod custom;
+pub mod dashboard;
+pub mod degraded;
+#[cfg(feature = "network")]
+pub mod download;
+pub mod eia860;
+pub mod emissions;
+pub mod energybalance;
+pub mod export;
+pub mod expr;
+pub mod forecast;
+pub mod fleet;
+pub mod fleetage;
+pub mod frontier;
+pub mod fuelmap;
+pub mod fuelprices;
+pub mod fuels;
+pub mod fueltrends;
+pub mod geography;
+pub mod grading;
+pub mod heatcontent;
+pub mod heatmap;
+pub mod histogram;
+#[cfg(feature = "sqlite")]
+pub mod history;
+pub mod hooks;
+pub mod identity;
+pub mod impute;
+pub mod inspect;
+pub mod jackknife;
+pub mod join;
+pub mod limits;
+pub mod merge;
+pub mod methodology;
+pub mod migration;
+pub mod monthly;
+pub mod narrative;
+pub mod objectstore;
+pub mod overlap;
+pub mod ownership;
+pub mod palette;
+pub mod panel;
+pub mod parallel;
+pub mod partition;
+pub mod pivot;
+pub mod quantity;
+#[cfg(feature = "network")]
+pub mod recording;
+pub mod report;
+#[cfg(feature = "sqlite")]
+pub mod resultsdb;
+pub mod rounding;
+pub mod sanity;
+pub mod sankey;
+pub mod scatter;
+pub mod scenarios;
+pub mod search;
+pub mod sensitivity;
+pub mod shares;
+#[cfg(feature = "charts")]
+pub mod site;
+pub mod sink;
+pub mod source;
+pub mod spatial;
+pub mod statefilter;
+pub mod stats;
+pub mod summary;
+#[cfg(feature = "templates")]
+pub mod template;
+pub mod territories;
+pub mod timestamp;
+pub mod tidy;
+pub mod units;
+#[cfg(feature = "network")]
+pub mod updates;
+pub mod variability;
+pub mod years;
+
+pub use analysis::{Analysis, StateEfficiency};
@@ -0,0 +1,105 @@
+// limits.rs
+// This module keeps a run within a memory ceiling, so it behaves on shared analysis servers: the memory the inputs
+// will take once loaded is estimated from their file sizes and the run stops before reading them if the estimate
+// is over the ceiling, instead of pushing the machine into swap halfway through.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+
+use crate::merge::input_parts;
+
+/// Peak memory of a loaded input per byte of its CSV file: the file body, the cleaned rows and their monthly
+/// arrays (measured at about 3.3 on EIA-923 exports, rounded up).
+pub const LOAD_MEMORY_FACTOR: u64 = 4;
+
+/// A memory ceiling, parsed from a byte count with an optional K, M, G or T suffix (powers of 1024; "B" and
+/// "iB" are accepted after it), e.g. "512M" or "1.5GiB".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryLimit {
+    pub bytes: u64,
+}
+
+impl FromStr for MemoryLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.trim().to_ascii_uppercase();
+        let number = upper.trim_end_matches("IB").trim_end_matches('B');
+        let (digits, scale) = match number.char_indices().last() {
+            Some((i, 'K')) => (&number[..i], 1u64 << 10),
+            Some((i, 'M')) => (&number[..i], 1 << 20),
+            Some((i, 'G')) => (&number[..i], 1 << 30),
+            Some((i, 'T')) => (&number[..i], 1 << 40),
+            _ => (number, 1),
+        };
+        match digits.trim().parse::<f64>() {
+            Ok(value) if value > 0.0 && value.is_finite() => Ok(MemoryLimit { bytes: (value * scale as f64) as u64 }),
+            _ => Err(format!("invalid memory limit '{}' (e.g. 512M, 2G)", s)),
+        }
+    }
+}
+
+impl fmt::Display for MemoryLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_bytes(self.bytes))
+    }
+}
+
+/// Byte count in MiB or GiB, for the messages.
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes >= 1 << 30 {
+        format!("{:.1} GiB", bytes as f64 / (1u64 << 30) as f64)
+    } else {
+        format!("{:.1} MiB", bytes as f64 / (1u64 << 20) as f64)
+    }
+}
+
+/// Estimated peak memory of holding the given inputs loaded at the same time.
+/// # Arguments
+/// * `paths` - Local input files; merged inputs (`a.csv+b.csv`) count every part
+pub fn estimate_load_memory(paths: &[&str]) -> Result<u64, Box<dyn Error>> {
+    let mut bytes = 0;
+    for path in paths {
+        for part in input_parts(path) {
+            let size = fs::metadata(part).map_err(|e| format!("cannot read '{}': {}", part, e))?.len();
+            bytes += size * LOAD_MEMORY_FACTOR;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Stops the run when the inputs held at once would need more memory than `limit`.
+/// # Returns
+/// * The estimate, or `Err` naming it and the limit
+pub fn check_memory(paths: &[&str], limit: MemoryLimit) -> Result<u64, Box<dyn Error>> {
+    let estimate = estimate_load_memory(paths)?;
+    if estimate > limit.bytes {
+        return Err(format!(
+            "loading {} needs about {}, over the --max-memory limit of {}",
+            paths.join(", "),
+            format_bytes(estimate),
+            limit
+        )
+        .into());
+    }
+    Ok(estimate)
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_memory_limits() {
+        assert_eq!("512M".parse::<MemoryLimit>().unwrap().bytes, 512 << 20);
+        assert_eq!("1.5GiB".parse::<MemoryLimit>().unwrap().bytes, 3 << 29);
+        assert_eq!(" 2gb ".parse::<MemoryLimit>().unwrap().bytes, 2 << 30);
+        assert_eq!("4096".parse::<MemoryLimit>().unwrap().bytes, 4096);
+        assert!("lots".parse::<MemoryLimit>().is_err());
+        assert!("0G".parse::<MemoryLimit>().is_err());
+        assert_eq!(MemoryLimit { bytes: 3 << 29 }.to_string(), "1.5 GiB");
+    }
+}
@@ -1,198 +1,2009 @@
 // Main.rs
 // This is the main program logic for computing fossil fuel efficiency change between 2019 and 2020 across U.S. states using EIA-923 data.
 
-mod cleaning;
+mod cli;
+mod config;
 
-use cleaning::{load_state_efficiency, StateStats};
-use std::collections::HashMap;
+use clap::{CommandFactory, FromArgMatches};
+use signal_hook::consts::SIGINT;
+use cli::{Cli, Command};
+use config::apply_profile;
+use efficiency::adjacency::{compare_neighbors, spillover_summary, write_neighbor_comparison_csv};
+use efficiency::aggregation::{aggregate_states, Aggregation};
+use efficiency::alerts::{find_alerts, write_alerts_csv, ALERT_EXIT_CODE};
+use efficiency::analysis::{
+    apply_grades, compute_efficiency_changes, convert_units, efficiency_report, format_top_states_colored,
+    sort_changes, StateEfficiency,
+};
+use efficiency::audit::{
+    append_audit_entry, audit_entry, audit_schema_version, check_audit_schema, AUDIT_SCHEMA_VERSION,
+};
+use efficiency::averages::{cross_state_averages, format_averages, write_state_averages_csv};
+use efficiency::bands::{format_national_bands, heat_rate_bands, write_bands_csv, BandCounts};
+use efficiency::baseline::{compare_to_baseline, load_baseline, write_baseline_csv};
+use efficiency::bench::{bench_input, format_timings, write_bench_csv};
+use efficiency::cache::load_records_cached;
+use efficiency::cancel::{Cancelled, CANCELLED_EXIT_CODE};
+use efficiency::capacityweight::{capacity_weighted_heat_rates, weighting_gap, write_capacity_weighted_csv};
+use efficiency::changepoint::{detect_change_points, write_change_points_csv};
+use efficiency::changesummary::{format_change_summary, summarize_changes};
+use efficiency::charts::{
+    write_delta_chart_html, write_delta_map_html, write_national_monthly_chart_html, write_panel_chart_html, MapLayout,
+};
+use efficiency::cleaned::{write_cleaned_csv, write_cleaned_partitioned};
+use efficiency::cleaning::{
+    aggregate_by_state, CleanRecord, ColumnProjection, EmptyDataset, StateStats, EMPTY_DATASET_EXIT_CODE,
+};
+use efficiency::context::append_context_columns;
+use efficiency::contributions::{compute_plant_contributions, top_contributors, write_plant_contributions_csv};
+use efficiency::convert::convert_to_parquet;
+use efficiency::degraded::{degrade, Degradation, OptionalColumn};
+use efficiency::convergence::{compute_convergence, write_convergence_summary_csv, write_sigma_convergence_csv};
+use efficiency::coverage::{low_coverage_states, remove_states, write_insufficient_data_csv, LowCoverage};
+use efficiency::custom::{append_custom_columns, parse_metric, CustomMetric};
+use efficiency::dashboard::{build_state_dashboard, format_state_dashboard};
+use efficiency::download::resolve_input;
+use efficiency::updates::{check_updates, write_updates_csv, UpdateStatus};
+use efficiency::eia860::{read_generators, read_utility_types, OwnershipType};
+use efficiency::emissions::{
+    compute_avoided_emissions, compute_pollutant_intensity, state_co2_intensity,
+    write_avoided_emissions_csv, write_pollutant_intensity_csv,
+};
+use efficiency::forecast::{forecast_states, write_forecast_csv};
+use efficiency::fleet::{retirement_impacts, split_fleet, write_fleet_split_csv, write_retirement_impact_csv};
+use efficiency::fleetage::{age_heat_rate_correlation, fleet_ages, write_fleet_age_csv};
+use efficiency::frontier::{compute_frontier, write_frontier_gaps_csv, write_frontier_plants_csv};
+use efficiency::fuelmap::{
+    efficiency_by_fuel_mapping, read_fuel_mapping, unmapped_codes, write_mapped_fuel_groups_csv, UNMAPPED_GROUP,
+};
+use efficiency::energybalance::{energy_balance, format_flagged, write_energy_balance_csv, EnergyBalance};
+use efficiency::export::{write_json, OutputFormat};
+use efficiency::fuelprices::{
+    compute_fuel_cost_avoided, read_fuel_prices, state_fuel_prices, write_fuel_cost_avoided_csv,
+};
+use efficiency::fueltrends::{
+    compute_fuel_trends, compute_technology_trends, write_fuel_trends_csv, write_technology_trends_csv, NATIONAL_SCOPE,
+};
+use efficiency::heatcontent::write_heat_content_anomalies_csv;
+use efficiency::heatmap::write_heatmap_svg;
+use efficiency::histogram::{check_bin_edges, plant_heat_rate_histograms, write_histograms_csv};
+use efficiency::history::{format_history, read_audit_runs, read_state_history, write_history_csv};
+use efficiency::hooks::{post_run_summary, run_completion_hook, RunSummary};
+use efficiency::identity::{check_plant_identity, exclude_plants, write_identity_changes_csv};
+use efficiency::impute::write_imputed_csv;
+use efficiency::inspect::{inspect_file, write_column_profile_csv};
+use efficiency::jackknife::{append_stability_column, jackknife_rank_stability, write_rank_stability_csv};
+use efficiency::join::{write_unmatched_keys_csv, JoinType};
+use efficiency::limits::{check_memory, estimate_load_memory, format_bytes};
+use efficiency::merge::{input_parts, is_merged};
+use efficiency::methodology::{describe_methodology, Methodology};
+use efficiency::migration::{detect_generation_shifts, format_top_shifts, write_generation_shifts_csv};
+use efficiency::monthly::{
+    aggregate_monthly_by_state, aggregate_national_monthly, compute_elasticities, compute_marginal_heat_rates, compute_seasonal_heat_rates,
+    compute_peak_penalties, find_extreme_months, write_elasticities_csv, write_extreme_months_csv,
+    write_marginal_heat_rates_csv, write_monthly_series_csv, write_national_monthly_csv, write_peak_penalties_csv,
+    write_seasonal_heat_rates_csv, MonthlyStats, PeakPenalty,
+};
+use efficiency::objectstore::{check_upload_prefix, upload_files};
+use efficiency::overlap::year_ranking_agreement;
+use efficiency::ownership::{efficiency_by_ownership, plant_ownership, write_ownership_efficiency_csv};
+use efficiency::narrative::{format_narratives_markdown, narrate_top_movers};
+use efficiency::panel::{
+    files_for_years, load_panel, panel_long_rows, panel_wide_rows, parse_year_range, write_panel_long_csv,
+    write_panel_wide_csv, years_in_data_dir, YearData,
+};
+use efficiency::pivot::write_fuel_pivots;
+use efficiency::quantity::MegawattHours;
+use efficiency::resultsdb::{open_results_db, query_results_db, record_run, DEFAULT_RESULTS_DB};
+use efficiency::sanity::{run_sanity_checks, SanityIssue};
+use efficiency::sankey::{build_sankeys, write_sankey_json, write_sankey_schema};
+use efficiency::scatter::write_scatter_csvs;
+use efficiency::scenarios::{run_scenario, target_heat_rate, write_scenarios_csv, Scenario};
+use efficiency::search::{find_plants, format_plant_matches};
+use efficiency::sensitivity::{compute_fuel_sensitivity, write_fuel_sensitivity_csv, DEFINITIONS};
+use efficiency::shares::append_national_share_columns;
+use efficiency::site::{write_site, SiteData};
+use efficiency::sink::{format_sink, HttpSink, OutputSink, OutputTable};
+use efficiency::spatial::{spatial_autocorrelation, write_spatial_csv};
+use efficiency::summary::{summarize_states, write_state_summary_csv};
+use efficiency::template::{render_template, template_output_path};
+use efficiency::variability::append_variability_columns;
+use efficiency::years::infer_year;
+use efficiency::tidy::{tidy_avoided_emissions, tidy_efficiency, tidy_marginal, tidy_panel, tidy_pollutants, write_tidy_csv};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::error::Error;
-use csv::WriterBuilder;
-
-/// Struct to hold the year-over-year efficiency data for a state.
-#[derive(Debug)]
-struct StateEfficiency {
-    /// State abbreviation (e.g., "CA", "TX").
-    state: String,
-
-    /// Efficiency in 2019 (fuel used per MWh).
-    eff_2019: f64,
-
-    /// Efficiency in 2020.
-    eff_2020: f64,
-
-    /// Change in efficiency (2020 - 2019).
-    delta: f64,
-
-    /// Absolute change in efficiency (magnitude only).
-    abs_delta: f64,
-}
-
-/// Computes efficiency change metrics per state based on aggregated data.
-/// # Arguments
-/// * `stats_2019` - Map of 2019 state data
-/// * `stats_2020` - Map of 2020 state data
-/// # Returns
-/// * `Vec<StateEfficiency>` representing efficiency differences by state
-fn compute_efficiency_changes(
-    stats_2019: &HashMap<String, StateStats>,
-    stats_2020: &HashMap<String, StateStats>,
-) -> Vec<StateEfficiency> {
-    let mut output = Vec::new();
-
-    for (state, stat_2019) in stats_2019 {
-        if let Some(stat_2020) = stats_2020.get(state) {
-            if stat_2019.total_gen == 0.0 || stat_2020.total_gen == 0.0 {
-                continue;
+use std::fs;
+use std::path::Path;
+
+/// The 2019 and 2020 EIA-923 CSVs used when no files are given.
+const DEFAULT_FILES: [&str; 2] = ["../data_csv_files/2019.csv", "../data_csv_files/2020.csv"];
+
+/// Plants listed on the state dashboard.
+const DASHBOARD_PLANTS: usize = 5;
+
+/// Characters of the longest bar in the `history` chart.
+const HISTORY_BAR_WIDTH: usize = 12;
+
+/// Most plants `find-plant` lists.
+const PLANT_MATCHES: usize = 10;
+
+/// What a run without monthly columns leaves empty.
+const MONTHLY_METRICS: &str = "monthly metrics (marginal heat rates, monthly variability, series and extremes)";
+
+/// Run records are appended here, in the working directory.
+const AUDIT_LOG: &str = "audit_log.jsonl";
+
+/// Main program entry point: parses the command line and runs the subcommand (see `Command`).
+fn main() -> Result<(), Box<dyn Error>> {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    apply_profile(&mut cli, &matches)?;
+    // The first Ctrl-C stops the run at its next check; a second one exits at once
+    signal_hook::flag::register_conditional_shutdown(SIGINT, CANCELLED_EXIT_CODE, cli.cancel.flag())?;
+    signal_hook::flag::register(SIGINT, cli.cancel.flag())?;
+    println!("Running from: {}", std::env::current_dir()?.display());
+    // Refuse before the run rather than after it when the audit log cannot take this release's lines
+    if let Some(version) = check_audit_schema(AUDIT_LOG, audit_schema_version(AUDIT_LOG)?)? {
+        println!("Upgrading '{}' from schema version {} to {}", AUDIT_LOG, version, AUDIT_SCHEMA_VERSION);
+    }
+    if let Some(path) = &cli.results_db
+        && !matches!(cli.command, Some(Command::QueryDb { .. }))
+    {
+        open_results_db(path)?;
+    }
+    if let Some(prefix) = &cli.upload_to {
+        check_upload_prefix(prefix)?;
+    }
+
+    if cli.column_projection() == ColumnProjection::Annual {
+        println!("Fast load: monthly columns are skipped, so heat content is not checked");
+    } else if cli.fast_load {
+        println!("Warning: --fast-load has no effect, this run needs the monthly columns");
+    }
+
+    let run = match &cli.command {
+        Some(Command::Compare { file_a, file_b }) => run_compare(&cli, file_a, file_b),
+        Some(Command::Stats { file }) => run_stats(&cli, file),
+        Some(Command::Inspect { file }) => run_inspect(&cli, file),
+        Some(Command::Fuels { files }) => run_fuels(&cli, files),
+        Some(Command::State { state, files }) => run_state(&cli, state, files),
+        Some(Command::FindPlant { query, files }) => run_find_plant(&cli, query, files),
+        Some(Command::QueryDb { sql }) => run_query_db(&cli, sql),
+        Some(Command::History { state }) => run_history(&cli, state),
+        Some(Command::Convert { input, output }) => run_convert(&cli, input, output),
+        Some(Command::Bench { file, runs }) => run_bench(&cli, file, *runs),
+        Some(Command::Site { out, files }) => run_site(&cli, out, files),
+        Some(Command::Run { years, data_dir, no_download }) => {
+            let files = files_for_years(data_dir, &parse_year_range(years)?, !no_download)?;
+            run_panel(&cli, "run", &files)
+        }
+        Some(Command::CheckUpdates { years, data_dir }) => run_check_updates(&cli, years.as_deref(), data_dir),
+        None if !cli.panel.is_empty() => run_panel(&cli, "panel", &cli.panel),
+        None => run_analysis(&cli),
+    };
+    let mut summary = match run.map_err(|e| e.downcast::<Cancelled>()) {
+        Ok(summary) => summary,
+        Err(Ok(cancelled)) => {
+            eprintln!("Run {}", cancelled);
+            std::process::exit(CANCELLED_EXIT_CODE);
+        }
+        Err(Err(e)) => match e.downcast::<EmptyDataset>() {
+            Ok(empty) => {
+                eprintln!("Error: {}", empty);
+                std::process::exit(EMPTY_DATASET_EXIT_CODE);
             }
+            Err(e) => return Err(e),
+        },
+    };
 
-            // Calculate efficiency = fuel / generation
-            let eff_2019 = stat_2019.total_fuel / stat_2019.total_gen;
-            let eff_2020 = stat_2020.total_fuel / stat_2020.total_gen;
-            let delta = eff_2020 - eff_2019;
-            let abs_delta = delta.abs();
+    if !summary.imputed_values.is_empty() {
+        let imputed = std::mem::take(&mut summary.imputed_values);
+        save(&cli, &mut summary, "imputed monthly values", "imputed_values.csv", |path| {
+            write_imputed_csv(path, &imputed)
+        })?;
+    }
 
-            output.push(StateEfficiency {
-                state: state.clone(),
-                eff_2019,
-                eff_2020,
-                delta,
-                abs_delta,
-            });
+    if !summary.heat_content_anomalies.is_empty() {
+        let anomalies = std::mem::take(&mut summary.heat_content_anomalies);
+        save(&cli, &mut summary, "heat content anomalies", "heat_content_anomalies.csv", |path| {
+            write_heat_content_anomalies_csv(path, &anomalies)
+        })?;
+    }
+
+    if !summary.degraded.is_empty() {
+        println!("\nComputed without some optional columns:");
+        for degraded in &summary.degraded {
+            println!("  {}", degraded);
         }
     }
 
-    output
+    println!("Appending run record to '{}'...", AUDIT_LOG);
+    append_audit_entry(AUDIT_LOG, &audit_entry(&summary, std::env::args().collect())?)?;
+
+    if let Some(prefix) = &cli.upload_to {
+        println!("Uploading {} output files to '{}'...", summary.outputs.len(), prefix);
+        for url in upload_files(prefix, &summary.outputs)? {
+            println!("  {}", url);
+        }
+    }
+
+    if let Some(command) = &cli.on_complete {
+        println!("Running completion hook...");
+        run_completion_hook(command, &summary)?;
+    }
+
+    if let Some(url) = &cli.webhook_url {
+        println!("Posting the run summary to '{}'...", url);
+        post_run_summary(url, &summary, cli.ca_cert.as_deref())?;
+    }
+
+    if summary.alerts > 0 {
+        std::process::exit(ALERT_EXIT_CODE);
+    }
+
+    Ok(())
+}
+
+/// Default mode: the 2019 vs 2020 state efficiency analysis and its reports.
+fn run_analysis(cli: &Cli) -> Result<RunSummary, Box<dyn Error>> {
+    let analysis = Analysis {
+        command: "analyze",
+        table: "efficiency_changes",
+        files: DEFAULT_FILES,
+        labels: ["2019", "2020"],
+        years: Some([2019, 2020]),
+    };
+    analyze(cli, analysis)
+}
+
+/// Compare mode: the same analysis for two arbitrary files, labeled by file name.
+fn run_compare(cli: &Cli, file_a: &str, file_b: &str) -> Result<RunSummary, Box<dyn Error>> {
+    let [label_a, label_b] = input_labels(file_a, file_b);
+    let analysis = Analysis {
+        command: "compare",
+        table: "comparison",
+        files: [file_a, file_b],
+        labels: [&label_a, &label_b],
+        years: None,
+    };
+    analyze(cli, analysis)
 }
 
-/// Displays top N states with the largest changes in efficiency.
-fn display_top_states(data: &[StateEfficiency], top_n: usize) {
+/// The two inputs of an analysis and how its run is named.
+struct Analysis<'a> {
+    /// Subcommand recorded in the run summary.
+    command: &'a str,
+
+    /// Stem of the main result table.
+    table: &'a str,
+
+    /// Base and compare inputs, and their names in the tables and messages.
+    files: [&'a str; 2],
+    labels: [&'a str; 2],
+
+    /// Years the inputs must hold, or `None` to accept any two inputs.
+    years: Option<[i32; 2]>,
+}
+
+/// Loads a base and a compare input, compares their state efficiencies and writes every enabled report. Reports
+/// keyed by year (monthly series, scatter, histograms, sankey, fuel pivots, tidy metrics) need the inputs to hold
+/// two different known years and are skipped otherwise.
+fn analyze(cli: &Cli, analysis: Analysis) -> Result<RunSummary, Box<dyn Error>> {
+    let Analysis { command, table, files, labels, years } = analysis;
+    let mut summary = start_summary(cli, command);
+    let metrics = parse_metrics(&cli.metrics)?;
+    let scenarios = parse_scenarios(&cli.scenarios)?;
+    check_bin_edges(&cli.histogram_bins).map_err(|e| format!("--histogram-bins: {}", e))?;
+    check_bin_edges(&cli.band_edges).map_err(|e| format!("--band-edges: {}", e))?;
+
+    println!("Loading {} ({})...", labels[0], files[0]);
+    let mut records_a = load_input(cli, &mut summary, files[0])?;
+
+    println!("Loading {} ({})...", labels[1], files[1]);
+    let mut records_b = load_input(cli, &mut summary, files[1])?;
+
+    let input_years = [0, 1].map(|i| summary.inputs[i].year);
+    let years = match (years, input_years) {
+        (Some(expected), _) => {
+            check_years(&summary, expected)?;
+            Some(expected)
+        }
+        (None, [Some(year_a), Some(year_b)]) if year_a != year_b => Some([year_a, year_b]),
+        (None, [Some(year), Some(_)]) => {
+            println!("Warning: both inputs hold {} data", year);
+            None
+        }
+        (None, _) => None,
+    };
+    check_sanity(&mut summary, &[(labels[0], &records_a), (labels[1], &records_b)]);
+    check_identity(cli, &mut summary, &mut records_a, &mut records_b)?;
+    save_cleaned(cli, &mut summary, &[(labels[0], &records_a), (labels[1], &records_b)])?;
+    let methodology = save_methodology(cli, &mut summary, &[&records_a, &records_b])?;
+    let Changes { stats: [stats_a, stats_b], changes, insufficient } =
+        compute_changes(cli, &mut summary, [&records_a, &records_b], labels)?;
+
+    let output = convert_units(&changes, cli.units);
     println!(
-        "{:<10} {:>15} {:>15} {:>15} {:>15}",
-        "State", "Eff_2019", "Eff_2020", "Change", "Abs Change"
+        "\nTop 10 States by {} ({} → {}, {}):\n",
+        cli.rank_by.describe(),
+        labels[0],
+        labels[1],
+        cli.units.label()
     );
-    println!("{}", "-".repeat(75));
+    let rounding = cli.rounding_policy()?;
+    print!("{}", format_top_states_colored(&output, 10, labels, cli.terminal_colors(), &rounding));
+    print_change_summary(cli, &output, [&stats_a, &stats_b], labels);
+    save_narratives(cli, &mut summary, [&records_a, &records_b], &changes, labels)?;
+    report_ranking_agreement(cli, &mut summary, &changes, labels);
+    summary.states = changes.len();
+    save_averages(cli, &mut summary, &output, [&stats_a, &stats_b], labels)?;
+
+    let monthly = available(&mut summary, &[OptionalColumn::Monthly], MONTHLY_METRICS, Degradation::LeftEmpty);
+    let monthly_a = monthly_by_state(cli, &records_a, monthly);
+    let monthly_b = monthly_by_state(cli, &records_b, monthly);
+
+    println!();
+    let mut report = efficiency_report(&output, labels, cli.units);
+    if cli.context_columns
+        && available(&mut summary, &[OptionalColumn::FuelType], "context columns", Degradation::Skipped)
+    {
+        append_context_columns(&mut report, labels, [&records_a, &records_b]);
+    }
+    if cli.national_shares {
+        append_national_share_columns(&mut report, labels, [&records_a, &records_b]);
+    }
+    if cli.monthly_variability {
+        append_variability_columns(&mut report, labels, [&monthly_a, &monthly_b], cli.units);
+    }
+    let stability = (cli.stability
+        && available(&mut summary, &[OptionalColumn::PlantId], "ranking stability", Degradation::Skipped))
+    .then(|| jackknife_rank_stability(&records_a, &records_b, &changes, cli.rank_by));
+    if let Some(stability) = &stability {
+        append_stability_column(&mut report, stability);
+    }
+    append_custom_columns(&mut report, &metrics, &changes, &stats_a, &stats_b)?;
+    let report = report.with_rounding(&rounding);
+    export_table(cli, &mut summary, "full results", table, &report, |path| report.write_csv(path))?;
+    if let Some(stability) = &stability {
+        save(cli, &mut summary, "ranking stability", "ranking_stability.csv", |path| {
+            write_rank_stability_csv(path, stability)
+        })?;
+    }
+
+    println!("Estimating avoided CO2 emissions...");
+    let co2 = available(&mut summary, &[OptionalColumn::FuelType], "avoided CO2", Degradation::LeftEmpty);
+    let intensity_b = co2.then(|| state_co2_intensity(&records_b));
+    let avoided = compute_avoided_emissions(&changes, &stats_b, intensity_b.as_ref());
+
+    export_table(cli, &mut summary, "avoided emissions", "avoided_emissions", &avoided, |path| {
+        write_avoided_emissions_csv(path, &avoided, labels)
+    })?;
+    save_scenarios(cli, &mut summary, &scenarios, labels[1], &records_b)?;
+    save_fuel_cost_avoided(cli, &mut summary, &changes, &stats_b, &records_b)?;
+
+    let mut pollutants = Vec::new();
+    if available(&mut summary, &[OptionalColumn::FuelType], "SO2/NOx intensities", Degradation::Skipped) {
+        pollutants = compute_pollutant_intensity(&records_a, &records_b);
+        export_table(cli, &mut summary, "SO2/NOx intensities", "pollutant_intensity", &pollutants, |path| {
+            write_pollutant_intensity_csv(path, &pollutants, labels)
+        })?;
+    }
+
+    let marginal = compute_marginal_heat_rates(&stats_a, &stats_b, &monthly_a, &monthly_b);
+    export_table(cli, &mut summary, "average vs marginal heat rates", "marginal_heat_rates", &marginal, |path| {
+        write_marginal_heat_rates_csv(path, &marginal, labels)
+    })?;
+
+    let needs = [OptionalColumn::PlantId, OptionalColumn::FuelType];
+    if available(&mut summary, &needs, "frontier plants and gaps", Degradation::Skipped) {
+        let frontier = compute_frontier(&records_b);
+        let description = format!("{} frontier plants", labels[1]);
+        export_table(cli, &mut summary, &description, "frontier_plants", &frontier.plants, |path| {
+            write_frontier_plants_csv(path, &frontier.plants)
+        })?;
+        let description = format!("{} state gaps to the frontier", labels[1]);
+        export_table(cli, &mut summary, &description, "frontier_gaps", &frontier.gaps, |path| {
+            write_frontier_gaps_csv(path, &frontier.gaps)
+        })?;
+    }
+
+    check_baseline(cli, &mut summary, &changes)?;
+    check_alerts(cli, &mut summary, &changes, labels)?;
+    let [year_a, year_b] = years.map_or(input_years, |years| years.map(Some));
+    save_eia860_reports(cli, &mut summary, [(year_a, &records_a), (year_b, &records_b)], labels)?;
+    save_fuel_mapping(cli, &mut summary, [&records_a, &records_b], labels)?;
+
+    if cli.plant_contributions
+        && available(&mut summary, &[OptionalColumn::PlantId], "plant contributions", Degradation::Skipped)
+    {
+        let contributions = top_contributors(&compute_plant_contributions(&records_a, &records_b), cli.contributors);
+        save(cli, &mut summary, "top plant contributions", "plant_contributions.csv", |path| {
+            write_plant_contributions_csv(path, &contributions, labels)
+        })?;
+    }
+    save_bands(cli, &mut summary, [(labels[0], &records_a), (labels[1], &records_b)])?;
+    save_energy_balance(cli, &mut summary, [(labels[0], &records_a), (labels[1], &records_b)])?;
+
+    if cli.fleet_split && available(&mut summary, &[OptionalColumn::PlantId], "fleet split", Degradation::Skipped) {
+        let fleet = split_fleet(&records_a, &records_b);
+        save(cli, &mut summary, "existing vs new fleet", "fleet_split.csv", |path| {
+            write_fleet_split_csv(path, &fleet, labels)
+        })?;
+    }
+
+    if cli.retirements && available(&mut summary, &[OptionalColumn::PlantId], "retirements", Degradation::Skipped) {
+        let impacts = retirement_impacts(&records_a, &records_b);
+        save(cli, &mut summary, "retirement counterfactual", "retirement_impact.csv", |path| {
+            write_retirement_impact_csv(path, &impacts, labels)
+        })?;
+    }
+
+    if cli.fuel_sensitivity {
+        save_fuel_sensitivity(cli, &mut summary, &records_a, &records_b)?;
+    }
+
+    if cli.spatial {
+        save_spatial(cli, &mut summary, &changes)?;
+    }
+
+    if cli.adjacency {
+        save_adjacency(cli, &mut summary, &changes)?;
+    }
+
+    match years {
+        Some(years) => {
+            save_yearly_reports(cli, &mut summary, years, labels, [&records_a, &records_b], [&monthly_a, &monthly_b])?;
+        }
+        None => note_yearly_reports_skipped(cli),
+    }
+
+    if cli.html {
+        let plotly = cli.plotly_script()?;
+        save(cli, &mut summary, "interactive chart", "efficiency_chart.html", |path| {
+            write_delta_chart_html(path, &output, labels, cli.units, cli.palette, &plotly)
+        })?;
+        let map = MapLayout { non_contiguous: cli.map_noncontiguous, dc_callout: cli.dc_callout };
+        save(cli, &mut summary, "change map", "efficiency_map.html", |path| {
+            write_delta_map_html(path, &output, labels, cli.units, cli.palette, map, &plotly)
+        })?;
+    }
+
+    if cli.tidy
+        && let Some(years) = years
+    {
+        let mut rows = tidy_efficiency(&changes, years);
+        rows.extend(tidy_avoided_emissions(&avoided, years[1]));
+        rows.extend(tidy_pollutants(&pollutants, years));
+        rows.extend(tidy_marginal(&marginal, years));
+        save(cli, &mut summary, "all metrics in long format", "tidy_metrics.csv", |path| {
+            write_tidy_csv(path, &mut rows)
+        })?;
+    }
+
+    render_templates(cli, &changes, [&stats_a, &stats_b], &insufficient, &methodology, &mut summary)?;
+    record_results(cli, &summary, &changes)?;
+
+    println!("Done.");
+    Ok(summary)
+}
+
+/// State totals and efficiency changes of a base and a compare input.
+struct Changes {
+    stats: [HashMap<String, StateStats>; 2],
+    changes: Vec<StateEfficiency>,
+
+    /// States parked by the coverage rule.
+    insufficient: Vec<LowCoverage>,
+}
+
+/// Aggregates both inputs by state, parks the states below `--min-plants` / `--min-generation`, applies
+/// `--states` / `--exclude-states`, and computes the efficiency changes with their grades, sorted by `--rank-by`.
+fn compute_changes(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    records: [&[CleanRecord]; 2],
+    labels: [&str; 2],
+) -> Result<Changes, Box<dyn Error>> {
+    let mut stats = records.map(|records| aggregate_states(records, cli.aggregation, cli.trim, cli.parallelism()));
+    let [stats_a, stats_b] = &mut stats;
+    let insufficient = check_coverage(cli, summary, records, [stats_a, stats_b], labels)?;
+    let [stats_a, stats_b] = &mut stats;
+    filter_states(cli, [stats_a, stats_b]);
+
+    println!("Computing efficiency changes...");
+    let mut changes = compute_efficiency_changes(&stats[0], &stats[1]);
+    apply_grades(&mut changes, &cli.grade_labels);
+    sort_changes(&mut changes, cli.rank_by);
+    Ok(Changes { stats, changes, insufficient })
+}
+
+/// Writes the enabled reports keyed by year: monthly series and charts, peak penalties, elasticities, the plant
+/// scatter and histograms, the sankey flows and the fuel pivots.
+fn save_yearly_reports(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    years: [i32; 2],
+    labels: [&str; 2],
+    records: [&[CleanRecord]; 2],
+    monthly: [&HashMap<String, MonthlyStats>; 2],
+) -> Result<(), Box<dyn Error>> {
+    let [year_a, year_b] = years;
+    let [monthly_a, monthly_b] = monthly;
+    let by_year = [(year_a, monthly_a), (year_b, monthly_b)];
+
+    if cli.monthly_series {
+        save(cli, summary, "monthly state series", "monthly_series.csv", |path| {
+            write_monthly_series_csv(path, &by_year)
+        })?;
+    }
+
+    if cli.national_monthly {
+        save_national_monthly(cli, summary, &by_year)?;
+    }
+
+    if cli.seasonal {
+        let seasonal = compute_seasonal_heat_rates(monthly_a, monthly_b);
+        save(cli, summary, "summer vs winter heat rates", "seasonal_heat_rates.csv", |path| {
+            write_seasonal_heat_rates_csv(path, &seasonal, labels)
+        })?;
+    }
+
+    if cli.extreme_months {
+        let extremes = find_extreme_months(&by_year);
+        save(cli, summary, "best and worst months", "extreme_months.csv", |path| {
+            write_extreme_months_csv(path, &extremes)
+        })?;
+    }
+
+    if cli.peak_penalty {
+        save_peak_penalties(cli, summary, &by_year)?;
+    }
+
+    if cli.elasticity {
+        let elasticities = compute_elasticities(monthly_a, monthly_b);
+        save(cli, summary, "heat rate elasticities", "heat_rate_elasticity.csv", |path| {
+            write_elasticities_csv(path, &elasticities, labels)
+        })?;
+    }
+
+    let records_by_year = [(year_a, records[0]), (year_b, records[1])];
+    if cli.scatter && available(summary, &[OptionalColumn::PlantId], "plant scatter", Degradation::Skipped) {
+        let (points, fits) = (cli.output_path("plant_scatter.csv"), cli.output_path("plant_scatter_fits.csv"));
+        println!("Saving plant scatter data to '{}' and '{}'...", points, fits);
+        write_scatter_csvs(&points, &fits, &records_by_year)?;
+        summary.output(&points);
+        summary.output(&fits);
+    }
+
+    if cli.histogram && available(summary, &[OptionalColumn::PlantId], "histograms", Degradation::Skipped) {
+        let histograms: Vec<_> = records_by_year
+            .iter()
+            .flat_map(|(year, records)| plant_heat_rate_histograms(records, *year, &cli.histogram_bins))
+            .collect();
+        save(cli, summary, "plant heat rate histograms", "plant_heat_rate_histogram.csv", |path| {
+            write_histograms_csv(path, &histograms, &cli.histogram_bins)
+        })?;
+    }
 
-    for item in data.iter().take(top_n) {
+    if cli.sankey && available(summary, &[OptionalColumn::FuelType], "fuel flow sankey", Degradation::Skipped) {
+        let sankeys: Vec<_> =
+            records_by_year.iter().flat_map(|(year, records)| build_sankeys(records, *year)).collect();
+        save(cli, summary, "fuel flow sankey data", "sankey.json", |path| write_sankey_json(path, &sankeys))?;
+        save(cli, summary, "sankey JSON Schema", "sankey.schema.json", write_sankey_schema)?;
+    }
+
+    if cli.fuel_pivot && available(summary, &[OptionalColumn::FuelType], "fuel pivots", Degradation::Skipped) {
+        for (year, records) in records_by_year {
+            let heat_rates = cli.output_path(&format!("fuel_heat_rates_{}.csv", year));
+            let shares = cli.output_path(&format!("fuel_generation_shares_{}.csv", year));
+            write_fuel_pivots(records, &heat_rates, &shares)?;
+            println!("Saved {} state x fuel matrices to '{}' and '{}'", year, heat_rates, shares);
+            summary.output(&heat_rates);
+            summary.output(&shares);
+        }
+    }
+
+    Ok(())
+}
+
+/// Says which requested reports keyed by year were skipped because the inputs' years are unknown or the same.
+fn note_yearly_reports_skipped(cli: &Cli) {
+    let requested: Vec<&str> = [
+        (cli.monthly_series, "--monthly-series"),
+        (cli.national_monthly, "--national-monthly"),
+        (cli.seasonal, "--seasonal"),
+        (cli.extreme_months, "--extreme-months"),
+        (cli.peak_penalty, "--peak-penalty"),
+        (cli.elasticity, "--elasticity"),
+        (cli.scatter, "--scatter"),
+        (cli.histogram, "--histogram"),
+        (cli.sankey, "--sankey"),
+        (cli.fuel_pivot, "--fuel-pivot"),
+        (cli.tidy, "--tidy"),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, flag)| enabled.then_some(flag))
+    .collect();
+    if !requested.is_empty() {
+        println!("Note: {} skipped, the inputs do not hold two different known years", requested.join(", "));
+    }
+}
+
+/// Stats mode: per-state totals, heat rate, plant count and fuel mix for a single file.
+fn run_stats(cli: &Cli, file: &str) -> Result<RunSummary, Box<dyn Error>> {
+    let mut summary = start_summary(cli, "stats");
+    println!("Loading {}...", file);
+    let records = load_input(cli, &mut summary, file)?;
+    let mut summaries = summarize_states(&records);
+    let filter = cli.state_filter();
+    summaries.retain(|item| filter.keeps(&item.state));
+    summary.states = summaries.len();
+
+    println!("\nHeat rates in {}", cli.units.label());
+    println!(
+        "{:<10} {:>8} {:>18} {:>18} {:>10}  Largest Fuel",
+        "State", "Plants", "Fuel (MMBtu)", "Generation (MWh)", "Heat Rate"
+    );
+    println!("{}", "-".repeat(86));
+    for item in &summaries {
+        let largest = item
+            .fuel_mix
+            .iter()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map(|(group, share)| format!("{} ({:.0}%)", group.label(), share * 100.0))
+            .unwrap_or_default();
         println!(
-            "{:<10} {:>15.3} {:>15.3} {:>15.3} {:>15.3}",
-            item.state, item.eff_2019, item.eff_2020, item.delta, item.abs_delta
+            "{:<10} {:>8} {:>18.0} {:>18.0} {:>10}  {}",
+            item.state,
+            item.plants,
+            item.total_fuel,
+            item.total_gen,
+            item.heat_rate().map(|v| format!("{:.3}", cli.units.convert(v))).unwrap_or_default(),
+            largest
         );
     }
+
+    println!();
+    export_table(cli, &mut summary, "state summary", "state_summary", &summaries, |path| {
+        write_state_summary_csv(path, &summaries, cli.units)
+    })?;
+
+    println!("Done.");
+    Ok(summary)
 }
 
-/// Writes the computed efficiency change data to a CSV output file.
-fn write_efficiency_csv(path: &str, data: &[StateEfficiency]) -> Result<(), Box<dyn Error>> {
-    let mut wtr = WriterBuilder::new().from_path(path)?;
-    wtr.write_record(&[
-        "State", "Efficiency_2019", "Efficiency_2020", "Delta_Efficiency", "Abs_Change",
-    ])?;
+/// State mode: the dashboard of one state for two inputs.
+fn run_state(cli: &Cli, state: &str, files: &[String]) -> Result<RunSummary, Box<dyn Error>> {
+    let [file_a, file_b] = match files {
+        [] => DEFAULT_FILES.map(String::from),
+        [a, b] => [a.clone(), b.clone()],
+        _ => return Err("state takes a base and a compare file, or none for the 2019 and 2020 files".into()),
+    };
+    let mut summary = start_summary(cli, "state");
+    let labels = input_labels(&file_a, &file_b);
+
+    println!("Loading {} ({})...", labels[0], file_a);
+    let records_a = load_input(cli, &mut summary, &file_a)?;
+    println!("Loading {} ({})...", labels[1], file_b);
+    let records_b = load_input(cli, &mut summary, &file_b)?;
+
+    let stats_a = aggregate_states(&records_a, cli.aggregation, cli.trim, cli.parallelism());
+    let stats_b = aggregate_states(&records_b, cli.aggregation, cli.trim, cli.parallelism());
+    let changes = convert_units(&compute_efficiency_changes(&stats_a, &stats_b), cli.units);
+    summary.states = changes.len();
+
+    let dashboard = build_state_dashboard(state, [&records_a, &records_b], &changes, DASHBOARD_PLANTS)?;
+    println!("\n{}", format_state_dashboard(&dashboard, [&labels[0], &labels[1]], cli.units));
+    Ok(summary)
+}
 
-    for item in data {
-        wtr.write_record(&[
-            &item.state,
-            &format!("{:.6}", item.eff_2019),
-            &format!("{:.6}", item.eff_2020),
-            &format!("{:.6}", item.delta),
-            &format!("{:.6}", item.abs_delta),
-        ])?;
+/// Find-plant mode: looks plants up by code or name in every input and prints their yearly totals.
+fn run_find_plant(cli: &Cli, query: &str, files: &[String]) -> Result<RunSummary, Box<dyn Error>> {
+    let files: Vec<String> = if files.is_empty() {
+        DEFAULT_FILES.iter().map(|f| f.to_string()).collect()
+    } else {
+        files.to_vec()
+    };
+    let mut summary = start_summary(cli, "find-plant");
+
+    let mut years = Vec::new();
+    for file in &files {
+        println!("Loading {}...", file);
+        years.push(load_input(cli, &mut summary, file)?);
     }
+    let years: Vec<&[CleanRecord]> = years.iter().map(Vec::as_slice).collect();
+    let labels: Vec<String> = files.iter().map(|f| file_label(f)).collect();
 
-    wtr.flush()?;
-    Ok(())
+    let matches = find_plants(query, &years, PLANT_MATCHES);
+    if matches.is_empty() {
+        println!("\nNo plant matches '{}'.", query);
+    } else {
+        println!("\nPlants matching '{}' (best first):\n", query);
+        print!("{}", format_plant_matches(&matches, &labels, cli.units));
+    }
+    Ok(summary)
 }
 
-/// Main program entry point:
-/// - Loads the 2019 and 2020 CSVs
-/// - Computes fossil fuel efficiency per state
-/// - Outputs top movers and saves results to CSV
-fn main() -> Result<(), Box<dyn Error>> {
-    println!("Running from: {}", std::env::current_dir()?.display());
+/// Inspect mode: per-column statistics of a raw input file, flagging numeric columns that hold text.
+fn run_inspect(cli: &Cli, file: &str) -> Result<RunSummary, Box<dyn Error>> {
+    let mut summary = start_summary(cli, "inspect");
+    let path = resolve_input(file, &cli.download_options())?;
+    println!("Inspecting {}...", path);
+    let profiles = inspect_file(&path)?;
+    summary.input(&path, profiles.first().map_or(0, |p| p.rows));
+
+    println!(
+        "\n{:<40} {:<12} {:>7} {:>14} {:>14} {:>14} {:>9}",
+        "Column", "Kind", "Blank %", "Min", "Max", "Mean", "Distinct"
+    );
+    println!("{}", "-".repeat(116));
+    let number = |v: Option<f64>| v.map(|v| format!("{:.2}", v)).unwrap_or_default();
+    for item in &profiles {
+        println!(
+            "{:<40} {:<12} {:>7.1} {:>14} {:>14} {:>14} {:>9}",
+            item.column,
+            item.kind.label(),
+            item.blank_rate() * 100.0,
+            number(item.min),
+            number(item.max),
+            number(item.mean),
+            item.distinct
+        );
+    }
+    for item in profiles.iter().filter(|p| p.unexpected_text()) {
+        println!("⚠️  '{}' should hold numbers but {} of its cells are text", item.column, item.non_numeric);
+    }
+
+    println!();
+    export_table(cli, &mut summary, "column profile", "column_profile", &profiles, |path| {
+        write_column_profile_csv(path, &profiles)
+    })?;
 
-    let file_2019 = "../data_csv_files/2019.csv";
-    let file_2020 = "../data_csv_files/2020.csv";
+    println!("Done.");
+    Ok(summary)
+}
 
-    println!("Loading 2019 data...");
-    let stats_2019 = load_state_efficiency(file_2019)?;
+/// Query mode: runs a read-only SQL statement against the results database and prints the rows.
+fn run_query_db(cli: &Cli, sql: &str) -> Result<RunSummary, Box<dyn Error>> {
+    let summary = start_summary(cli, "query-db");
+    let path = cli.results_db.as_deref().unwrap_or(DEFAULT_RESULTS_DB);
+    let report = query_results_db(path, sql)?.with_rounding(&cli.rounding_policy()?);
 
-    println!("Loading 2020 data...");
-    let stats_2020 = load_state_efficiency(file_2020)?;
+    println!();
+    if report.columns.is_empty() {
+        println!("(statement returned no columns)");
+    } else {
+        print!("{}", report.to_terminal());
+        println!("({} rows)", report.rows.len());
+    }
+    Ok(summary)
+}
 
-    println!("Computing efficiency changes...");
-    let mut changes = compute_efficiency_changes(&stats_2019, &stats_2020);
-    changes.sort_by(|a, b| b.abs_delta.partial_cmp(&a.abs_delta).unwrap());
+/// History mode: one state's result in every run of the results database, with what changed between runs.
+fn run_history(cli: &Cli, state: &str) -> Result<RunSummary, Box<dyn Error>> {
+    let mut summary = start_summary(cli, "history");
+    let path = cli.results_db.as_deref().unwrap_or(DEFAULT_RESULTS_DB);
+    let state = state.trim().to_ascii_uppercase();
+    let points = read_state_history(path, &state, &read_audit_runs(AUDIT_LOG)?)?;
+    if points.is_empty() {
+        return Err(format!("no runs in '{}' have results for state '{}'", path, state).into());
+    }
 
-    println!("\nTop 10 States by Change in Fossil Fuel Efficiency:\n");
-    display_top_states(&changes, 10);
+    println!("\n{} heat rate change by run ({}):\n", state, cli.units.label());
+    print!("{}", format_history(&points, cli.units, HISTORY_BAR_WIDTH));
+    save(cli, &mut summary, "state history", &format!("history_{}.csv", state), |path| {
+        write_history_csv(path, &state, &points)
+    })?;
+    Ok(summary)
+}
 
-    println!("\nSaving full results to 'efficiency_changes.csv'...");
-    write_efficiency_csv("efficiency_changes.csv", &changes)?;
+/// Convert mode: streams a raw export through the cleaning into a Parquet file of cleaned records.
+fn run_convert(cli: &Cli, input: &str, output: &str) -> Result<RunSummary, Box<dyn Error>> {
+    let mut summary = start_summary(cli, "convert");
+    let path = resolve_input(input, &cli.download_options())?;
+    println!("Converting {} to '{}'...", path, output);
+    let converted = convert_to_parquet(&path, output, &file_label(input), &cli.load_options(), &cli.cancel)?;
+
+    if !converted.territories.is_empty() {
+        let codes: Vec<&str> = converted.territories.iter().map(String::as_str).collect();
+        println!("Non-state rows ({}): {}", codes.join(", "), cli.territories.describe());
+    }
+    println!(
+        "Wrote {} cleaned rows in {} row groups ({} rows skipped, {} blank monthly values imputed, {} heat \
+         content anomalies)",
+        converted.rows,
+        converted.row_groups,
+        converted.skipped.total(),
+        converted.imputed,
+        converted.anomalies
+    );
+    summary.input(&path, converted.rows);
+    summary.year(infer_year(input, &converted.years, cli.year_overrides()?.for_file(input)).map(|y| y.year));
+    summary.imputed(converted.imputed);
+    summary.anomalies(converted.anomalies);
+    summary.skipped(converted.skipped);
+    summary.output(output);
+    Ok(summary)
+}
+
+/// Bench mode: times each loading and aggregation stage on one input and the fast paths against them.
+fn run_bench(cli: &Cli, file: &str, runs: usize) -> Result<RunSummary, Box<dyn Error>> {
+    let mut summary = start_summary(cli, "bench");
+    let path = resolve_input(file, &cli.download_options())?;
+    // Without --parallel or --threads the parallel stages use every core
+    let threads = match cli.parallelism().threads {
+        1 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        threads => threads,
+    };
+    println!("Benchmarking {} ({} runs per stage, fastest kept)...", path, runs.max(1));
+    let timings = bench_input(&path, &cli.load_options(), threads, runs, &cli.cache_dir, &cli.cancel)?;
+    summary.input(&path, timings.first().map_or(0, |t| t.rows));
+
+    println!("\n{}", format_timings(&timings));
+    export_table(cli, &mut summary, "benchmark", "bench", &timings, |path| write_bench_csv(path, &timings))?;
 
     println!("Done.");
+    Ok(summary)
+}
+
+/// Site mode: compares two inputs and writes the results as a static website.
+fn run_site(cli: &Cli, out: &str, files: &[String]) -> Result<RunSummary, Box<dyn Error>> {
+    let [file_a, file_b] = match files {
+        [] => DEFAULT_FILES.map(String::from),
+        [a, b] => [a.clone(), b.clone()],
+        _ => return Err("site takes a base and a compare file, or none for the 2019 and 2020 files".into()),
+    };
+    let mut summary = start_summary(cli, "site");
+    let labels = input_labels(&file_a, &file_b);
+
+    println!("Loading {} ({})...", labels[0], file_a);
+    let records_a = load_input(cli, &mut summary, &file_a)?;
+    println!("Loading {} ({})...", labels[1], file_b);
+    let records_b = load_input(cli, &mut summary, &file_b)?;
+
+    let records = [records_a.as_slice(), records_b.as_slice()];
+    let Changes { stats: [stats_a, stats_b], changes, .. } =
+        compute_changes(cli, &mut summary, records, [&labels[0], &labels[1]])?;
+    let changes = convert_units(&changes, cli.units);
+    summary.states = changes.len();
+
+    let dashboards = changes
+        .iter()
+        .map(|change| build_state_dashboard(&change.state, records, &changes, DASHBOARD_PLANTS))
+        .collect::<Result<Vec<_>, String>>()?;
+    let plants = compute_plant_contributions(&records_a, &records_b);
+    let data = SiteData {
+        labels: [&labels[0], &labels[1]],
+        changes: &changes,
+        summary: &summarize_changes(&changes, [&stats_a, &stats_b], cli.units),
+        dashboards: &dashboards,
+        plants: &plants,
+        unit: cli.units,
+    };
+
+    check_cancelled(cli, &summary, "the site")?;
+    let map = MapLayout { non_contiguous: cli.map_noncontiguous, dc_callout: cli.dc_callout };
+    let written = write_site(out, &data, cli.palette, map, &cli.plotly_script()?)?;
+    println!("Wrote the site ({} pages and files) to '{}'; open '{}/index.html'", written.len(), out, out);
+    for file in &written {
+        summary.output(file);
+    }
+    Ok(summary)
+}
+
+/// Check-updates mode: compares EIA's release of each year with the local copy and reports the stale years.
+fn run_check_updates(cli: &Cli, years: Option<&str>, data_dir: &str) -> Result<RunSummary, Box<dyn Error>> {
+    let years = match years {
+        Some(years) => parse_year_range(years)?,
+        None => years_in_data_dir(data_dir)?,
+    };
+    if years.is_empty() {
+        return Err(format!("no '<year>.csv' files in '{}'; give the years to check with --years", data_dir).into());
+    }
+    let mut summary = start_summary(cli, "check-updates");
+
+    println!("Checking {} year(s) against the EIA-923 releases...", years.len());
+    let checks = check_updates(&years, data_dir, &cli.download_options())?;
+    println!("\n{:<6} {:<14} {:<22} {:<22} Local copy", "Year", "Status", "Released", "Local date");
+    println!("{}", "-".repeat(100));
+    for check in &checks {
+        println!(
+            "{:<6} {:<14} {:<22} {:<22} {}",
+            check.year,
+            check.status.label(),
+            check.released.as_deref().unwrap_or("-"),
+            check.local_modified.as_deref().unwrap_or("-"),
+            check.local_path.as_deref().unwrap_or("-")
+        );
+    }
+
+    let stale: Vec<String> = checks
+        .iter()
+        .filter(|c| matches!(c.status, UpdateStatus::Revised | UpdateStatus::New))
+        .map(|c| c.year.to_string())
+        .collect();
+    if stale.is_empty() {
+        println!("\nThe local data holds the latest release of every published year.");
+    } else {
+        println!(
+            "\nNewer data at EIA for {}: fetch it with 'run --years' (--no-cache downloads a cached archive again; \
+             a hand-saved '<year>.csv' has to be removed or replaced first).",
+            stale.join(", ")
+        );
+    }
+
+    println!();
+    export_table(cli, &mut summary, "release checks", "updates", &checks, |path| write_updates_csv(path, &checks))?;
+    Ok(summary)
+}
+
+/// Whether a metric can be computed from the loaded columns; if not, says so and notes it in the run summary as
+/// left empty or skipped.
+fn available(summary: &mut RunSummary, needs: &[OptionalColumn], feature: &str, outcome: Degradation) -> bool {
+    let Some(degraded) = degrade(&summary.missing_in_any_input(), needs, feature, outcome) else {
+        return true;
+    };
+    println!("Note: {}", degraded);
+    summary.degraded.push(degraded);
+    false
+}
+
+/// Adds the run and its state results to the `--results-db` database, if any.
+fn record_results(cli: &Cli, summary: &RunSummary, changes: &[StateEfficiency]) -> Result<(), Box<dyn Error>> {
+    let Some(path) = &cli.results_db else {
+        return Ok(());
+    };
+    let mut conn = open_results_db(path)?;
+    let run_id = record_run(&mut conn, summary, changes)?;
+    println!("Recorded run {} ({} states) in '{}'", run_id, changes.len(), path);
     Ok(())
 }
 
-// Cargo Tests
-#[cfg(test)]
-mod tests {
-   use super::*;
-
-
-   #[test]
-   fn test_efficiency_computation() {
-       let mut stats_2019 = HashMap::new();
-       let mut stats_2020 = HashMap::new();
-
-
-       stats_2019.insert(
-           "TX".to_string(),
-           StateStats {
-               total_fuel: 1000.0,
-               total_gen: 100.0,
-           },
-       );
-       stats_2020.insert(
-           "TX".to_string(),
-           StateStats {
-               total_fuel: 800.0,
-               total_gen: 100.0,
-           },
-       );
-
-
-       let results = compute_efficiency_changes(&stats_2019, &stats_2020);
-       assert_eq!(results.len(), 1);
-       let tx = &results[0];
-       assert_eq!(tx.state, "TX");
-       assert!((tx.eff_2019 - 10.0).abs() < 1e-6);
-       assert!((tx.eff_2020 - 8.0).abs() < 1e-6);
-       assert!((tx.delta + 2.0).abs() < 1e-6);
-       assert!((tx.abs_delta - 2.0).abs() < 1e-6);
-   }
-
-
-   #[test]
-   fn test_skipping_zero_generation() {
-       let mut stats_2019 = HashMap::new();
-       let mut stats_2020 = HashMap::new();
-
-
-       stats_2019.insert(
-           "CA".to_string(),
-           StateStats {
-               total_fuel: 500.0,
-               total_gen: 0.0,
-           },
-       );
-       stats_2020.insert(
-           "CA".to_string(),
-           StateStats {
-               total_fuel: 900.0,
-               total_gen: 0.0,
-           },
-       );
-
-
-       let results = compute_efficiency_changes(&stats_2019, &stats_2020);
-       assert_eq!(results.len(), 0);
-   }
+/// Fuels mode: heat rate by fuel group, nationally and per state, for every loaded year.
+fn run_fuels(cli: &Cli, files: &[String]) -> Result<RunSummary, Box<dyn Error>> {
+    let files: Vec<String> = if files.is_empty() {
+        DEFAULT_FILES.iter().map(|f| f.to_string()).collect()
+    } else {
+        files.to_vec()
+    };
+    let panel = load_years(cli, &files)?;
+    let trends = compute_fuel_trends(&panel);
+    let mut summary = panel_summary(cli, "fuels", &panel);
+
+    println!("\nNational heat rate by fuel group ({}):\n", cli.units.label());
+    print!("{:<14}", "Fuel Group");
+    for year_data in &panel {
+        print!(" {:>10}", year_data.year);
+    }
+    println!();
+    println!("{}", "-".repeat(14 + 11 * panel.len()));
+
+    let national: Vec<_> = trends.iter().filter(|t| t.scope == NATIONAL_SCOPE).collect();
+    for chunk in national.chunk_by(|a, b| a.fuel_group == b.fuel_group) {
+        print!("{:<14}", chunk[0].fuel_group.label());
+        for year_data in &panel {
+            let rate = chunk
+                .iter()
+                .find(|t| t.year == year_data.year)
+                .and_then(|t| t.heat_rate())
+                .map(|v| format!("{:.3}", cli.units.convert(v)))
+                .unwrap_or_default();
+            print!(" {:>10}", rate);
+        }
+        println!();
+    }
+
+    let technologies = compute_technology_trends(&panel);
+    println!("\nNational heat rate by fossil technology ({}):\n", cli.units.label());
+    print!("{:<14}", "Technology");
+    for year_data in &panel {
+        print!(" {:>10}", year_data.year);
+    }
+    println!(" {:>10}", "Change");
+    println!("{}", "-".repeat(25 + 11 * panel.len()));
+
+    for chunk in technologies.chunk_by(|a, b| a.technology == b.technology) {
+        print!("{:<14}", chunk[0].technology.label());
+        for year_data in &panel {
+            let rate = chunk
+                .iter()
+                .find(|t| t.year == year_data.year)
+                .and_then(|t| t.heat_rate())
+                .map(|v| format!("{:.3}", cli.units.convert(v)))
+                .unwrap_or_default();
+            print!(" {:>10}", rate);
+        }
+        let change = chunk.last().and_then(|t| t.change).map(|v| format!("{:+.3}", cli.units.convert(v)));
+        println!(" {:>10}", change.unwrap_or_default());
+    }
+
+    println!();
+    export_table(cli, &mut summary, "fuel group heat rates", "fuel_trends", &trends, |path| {
+        write_fuel_trends_csv(path, &trends, cli.units)
+    })?;
+    export_table(cli, &mut summary, "national technology heat rates", "technology_trends", &technologies, |path| {
+        write_technology_trends_csv(path, &technologies, cli.units)
+    })?;
+
+    println!("Done.");
+    Ok(summary)
+}
+
+/// Writes a main result table through every output sink: one per `--format`, and `--post-url` if given. Each
+/// local file written is recorded in the run summary.
+fn export_table<T: Serialize + ?Sized>(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    description: &str,
+    stem: &str,
+    data: &T,
+    write_csv: impl Fn(&str) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    check_cancelled(cli, summary, description)?;
+    let preamble = if cli.preamble { preamble_lines(cli, summary) } else { Vec::new() };
+    let mut sinks: Vec<Box<dyn OutputSink>> =
+        cli.formats.iter().map(|format| format_sink(*format, preamble.clone())).collect();
+    if let Some(url) = &cli.post_url {
+        sinks.push(Box::new(HttpSink { url: url.clone(), ca_cert: cli.ca_cert.clone() }));
+    }
+
+    let table = OutputTable::new(&cli.output_path(stem), data)?.with_csv(&write_csv);
+    let locations: Vec<String> = sinks.iter().map(|sink| sink.location(&table)).collect();
+    println!("Saving {} to '{}'...", description, locations.join("', '"));
+
+    for sink in &mut sinks {
+        if let Some(path) = sink.write_table(&table)? {
+            summary.output(&path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes one output file under its run-labeled name (see `--label`) and records it in the run summary.
+fn save(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    description: &str,
+    name: &str,
+    write: impl FnOnce(&str) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    check_cancelled(cli, summary, description)?;
+    let path = cli.output_path(name);
+    println!("Saving {} to '{}'...", description, path);
+    write(&path)?;
+    summary.output(&path);
+    Ok(())
+}
+
+/// Stops the run before saving `description` if Ctrl-C was pressed; the files already written are kept.
+fn check_cancelled(cli: &Cli, summary: &RunSummary, description: &str) -> Result<(), Box<dyn Error>> {
+    let progress = || match summary.outputs.len() {
+        0 => "no files written yet".to_string(),
+        n => format!("{} files written: {}", n, summary.outputs.join(", ")),
+    };
+    Ok(cli.cancel.check(&format!("saving {}", description), progress)?)
+}
+
+/// Metadata lines for the CSV preamble.
+fn preamble_lines(cli: &Cli, summary: &RunSummary) -> Vec<String> {
+    let mut lines = vec![
+        format!("efficiency {}", env!("CARGO_PKG_VERSION")),
+        format!("command: {}", summary.command),
+        format!("timestamp: {}", summary.timestamp),
+    ];
+    if let Some(label) = &summary.label {
+        lines.push(format!("label: {}", label));
+    }
+    for input in &summary.inputs {
+        lines.push(format!("input: {} ({} valid rows)", input.path, input.rows));
+    }
+    lines.push(format!("heat rate units: {}", cli.units.label()));
+    if cli.aggregation != Aggregation::Sum {
+        lines.push(format!("aggregation: {}", cli.aggregation.describe(cli.trim)));
+    }
+    if cli.parallel || cli.threads.is_some() {
+        lines.push(format!("state totals: {}", cli.parallelism().describe()));
+    }
+    if let Some(limit) = cli.max_memory {
+        lines.push(format!("memory limit: {}", limit));
+    }
+    if cli.column_projection() == ColumnProjection::Annual {
+        lines.push("monthly columns: skipped (--fast-load)".to_string());
+    }
+    lines.push(format!("DC and territories: {}", cli.territories.describe()));
+    lines.push(format!("blank months: {}", cli.impute.label()));
+    lines.push(format!("implausible heat content: {}", cli.heat_content.label()));
+    if summary.inputs.iter().any(|input| is_merged(&input.path)) {
+        lines.push(format!("plant rows in several merged files: {}", cli.duplicates.label()));
+    }
+    if cli.coverage_rule().is_active() {
+        lines.push(format!("minimum per state and year: {} plants, {} MWh", cli.min_plants, cli.min_generation));
+    }
+    if cli.state_filter().is_active() {
+        lines.push(format!("states: {}", cli.state_filter().describe()));
+    }
+    if cli.exclude_inconsistent {
+        lines.push("plants that changed state, primary fuel or prime mover: excluded".to_string());
+    }
+    lines.push(format!("options: {}", std::env::args().skip(1).collect::<Vec<_>>().join(" ")));
+    lines
+}
+
+/// Compares the results with the `--baseline` file, if any, and reports how far they are apart.
+fn check_baseline(cli: &Cli, summary: &mut RunSummary, changes: &[StateEfficiency]) -> Result<(), Box<dyn Error>> {
+    let Some(baseline_path) = &cli.baseline else {
+        return Ok(());
+    };
+
+    let baseline = load_baseline(baseline_path)?;
+    let diffs = compare_to_baseline(changes, &baseline);
+    println!("Baseline '{}' covers {} of {} states", baseline_path, diffs.len(), changes.len());
+
+    for (year, label) in [(0, "2019"), (1, "2020")] {
+        let pct: Vec<(&str, f64)> = diffs.iter().filter_map(|d| Some((d.state.as_str(), d.pct_diff(year)?))).collect();
+        if let Some((worst_state, worst_pct)) = pct.iter().max_by(|a, b| a.1.abs().partial_cmp(&b.1.abs()).unwrap()) {
+            println!(
+                "  {}: mean |difference| {:.2}% over {} states, largest {} ({:+.2}%)",
+                label,
+                pct.iter().map(|(_, p)| p.abs()).sum::<f64>() / pct.len() as f64,
+                pct.len(),
+                worst_state,
+                worst_pct
+            );
+        }
+    }
+
+    save(cli, summary, "baseline comparison", "baseline_comparison.csv", |path| write_baseline_csv(path, &diffs))
+}
+
+/// Writes the national monthly series (`--national-monthly`) as CSV and chart.
+fn save_national_monthly(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    years: &[(i32, &HashMap<String, MonthlyStats>)],
+) -> Result<(), Box<dyn Error>> {
+    let national = aggregate_national_monthly(years);
+    save(cli, summary, "national monthly series", "national_monthly.csv", |path| {
+        write_national_monthly_csv(path, &national)
+    })?;
+    save(cli, summary, "national monthly chart", "national_monthly.html", |path| {
+        write_national_monthly_chart_html(path, &national, cli.palette, &cli.plotly_script()?)
+    })
+}
+
+/// Flags states whose change crosses `--alert-threshold`, if set.
+fn check_alerts(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    changes: &[StateEfficiency],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let Some(threshold) = cli.alert_threshold else {
+        return Ok(());
+    };
+
+    let alerts = find_alerts(changes, threshold);
+    summary.alerts = alerts.len();
+    if alerts.is_empty() {
+        println!("No state changed by more than {}", threshold);
+        return Ok(());
+    }
+
+    println!("\n{}", "!".repeat(60));
+    println!("ALERT: {} state(s) changed by more than {}", alerts.len(), threshold);
+    for alert in &alerts {
+        println!(
+            "  {:<4} {:>8.3} → {:>8.3}  ({:+.3}, {:+.2}%)",
+            alert.state, alert.eff_2019, alert.eff_2020, alert.delta, alert.pct_change
+        );
+    }
+    println!("{}\n", "!".repeat(60));
+
+    save(cli, summary, "alerts", "alerts.csv", |path| write_alerts_csv(path, &alerts, threshold, labels))
+}
+
+/// Warns about plants whose state, primary fuel or prime movers changed between the two files, saves the list
+/// and, with `--exclude-inconsistent`, drops those plants from both years.
+fn check_identity(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    records_a: &mut Vec<CleanRecord>,
+    records_b: &mut Vec<CleanRecord>,
+) -> Result<(), Box<dyn Error>> {
+    if !available(summary, &[OptionalColumn::PlantId], "plant identity check", Degradation::Skipped) {
+        return Ok(());
+    }
+    let changes = check_plant_identity(records_a, records_b);
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let plant_ids: BTreeSet<&str> = changes.iter().map(|c| c.plant_id.as_str()).collect();
+    println!("Warning: {} plant(s) changed state, primary fuel or prime mover between years:", plant_ids.len());
+    for change in changes.iter().take(10) {
+        println!("  {:<8} {:<32} {:<13} {} → {}", change.plant_id, change.plant_name, change.field, change.before, change.after);
+    }
+    if changes.len() > 10 {
+        println!("  ... and {} more", changes.len() - 10);
+    }
+
+    if cli.exclude_inconsistent {
+        let dropped = exclude_plants(records_a, &plant_ids) + exclude_plants(records_b, &plant_ids);
+        println!("Excluded these plants from both years ({} rows)", dropped);
+    }
+
+    save(cli, summary, "plant identity changes", "plant_identity_changes.csv", |path| {
+        write_identity_changes_csv(path, &changes)
+    })
+}
+
+/// Recomputes the changes under each fuel definition, prints how well the rankings agree and saves the table.
+fn save_fuel_sensitivity(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    records_a: &[CleanRecord],
+    records_b: &[CleanRecord],
+) -> Result<(), Box<dyn Error>> {
+    if !available(summary, &[OptionalColumn::FuelType], "fuel definition sensitivity", Degradation::Skipped) {
+        return Ok(());
+    }
+    let sensitivity = compute_fuel_sensitivity(records_a, records_b);
+    let shifted = sensitivity.states.iter().filter(|s| s.rank_range().is_some_and(|r| r >= 5)).count();
+
+    println!("Rank agreement of the change with the all-fuels definition (Spearman):");
+    for (definition, correlation) in DEFINITIONS.iter().zip(&sensitivity.correlations) {
+        println!("  {:<16} {}", definition.label(), correlation.map(|c| format!("{:.3}", c)).unwrap_or_default());
+    }
+    println!("{} state(s) move 5 or more places between definitions", shifted);
+
+    save(cli, summary, "fuel definition sensitivity", "fuel_sensitivity.csv", |path| {
+        write_fuel_sensitivity_csv(path, &sensitivity)
+    })
+}
+
+/// Renders every `--template` with the efficiency rows and the run so far.
+fn render_templates(
+    cli: &Cli,
+    changes: &[StateEfficiency],
+    stats: [&HashMap<String, StateStats>; 2],
+    insufficient: &[LowCoverage],
+    methodology: &Methodology,
+    summary: &mut RunSummary,
+) -> Result<(), Box<dyn Error>> {
+    let averages = cross_state_averages(changes, stats[0], stats[1], cli.averages);
+    for template in &cli.templates {
+        let output = cli.output_path(&template_output_path(template));
+        println!("Rendering template '{}' to '{}'...", template, output);
+        render_template(template, &output, changes, &averages, insufficient, methodology, summary)?;
+        summary.output(&output);
+    }
+    Ok(())
+}
+
+/// Describes the run's assumptions and, with JSON output, writes them to 'methodology.json'.
+fn save_methodology(cli: &Cli, summary: &mut RunSummary, records: &[&[CleanRecord]]) -> Result<Methodology, Box<dyn Error>> {
+    let methodology = describe_methodology(records, &summary.inputs, cli.methodology_options());
+    if cli.formats.contains(&OutputFormat::Json) {
+        save(cli, summary, "methodology", "methodology.json", |path| write_json(path, &methodology))?;
+    }
+    Ok(methodology)
+}
+
+/// Compares each state's change with its neighbors', prints how far the regional pattern reaches and saves
+/// the per-state comparison.
+fn save_adjacency(cli: &Cli, summary: &mut RunSummary, changes: &[StateEfficiency]) -> Result<(), Box<dyn Error>> {
+    let comparisons = compare_neighbors(changes);
+    println!("Correlation of a state's change with the mean change of states n borders away:");
+    for ring in spillover_summary(&comparisons) {
+        let correlation = ring.correlation.map_or("n/a".to_string(), |r| format!("{:+.3}", r));
+        println!("  {} border(s): {} ({} states)", ring.hops, correlation, ring.states);
+    }
+    save(cli, summary, "neighbor comparison", "state_neighbors.csv", |path| {
+        write_neighbor_comparison_csv(path, &comparisons)
+    })
+}
+
+/// Tests whether the changes cluster geographically, prints Moran's I and saves each state's neighbors.
+fn save_spatial(cli: &Cli, summary: &mut RunSummary, changes: &[StateEfficiency]) -> Result<(), Box<dyn Error>> {
+    let Some(result) = spatial_autocorrelation(changes, cli.neighbors) else {
+        println!("Too few states with a known location for the spatial analysis");
+        return Ok(());
+    };
+
+    println!(
+        "Moran's I of the change ({} nearest states): {:.3} (expected {:.3}, z {:.2}, p {:.3}): {}",
+        result.k,
+        result.morans_i,
+        result.expected,
+        result.z_score,
+        result.p_value,
+        result.interpretation()
+    );
+    save(cli, summary, "spatial autocorrelation", "spatial_autocorrelation.csv", |path| {
+        write_spatial_csv(path, &result)
+    })
+}
+
+/// Parks the states below `--min-plants` / `--min-generation` in either year: they are removed from the state
+/// totals, printed and written to 'insufficient_data.csv'.
+fn check_coverage(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    records: [&[CleanRecord]; 2],
+    stats: [&mut HashMap<String, StateStats>; 2],
+    labels: [&str; 2],
+) -> Result<Vec<LowCoverage>, Box<dyn Error>> {
+    let rule = cli.coverage_rule();
+    if !rule.is_active() {
+        return Ok(Vec::new());
+    }
+
+    let low = low_coverage_states(records, [&*stats[0], &*stats[1]], labels, rule);
+    if low.is_empty() {
+        return Ok(low);
+    }
+
+    println!("Insufficient data: {} state(s) left out of the results:", low.len());
+    for item in &low {
+        println!("  {:<8} {}", item.state, item.reason);
+    }
+    remove_states(stats, &low);
+    save(cli, summary, "insufficient-data states", "insufficient_data.csv", |path| {
+        write_insufficient_data_csv(path, &low, labels)
+    })?;
+    Ok(low)
+}
+
+/// Drops the states left out by `--states` and `--exclude-states` from both years' totals, warning about codes
+/// that match no state in the data.
+fn filter_states(cli: &Cli, stats: [&mut HashMap<String, StateStats>; 2]) {
+    let filter = cli.state_filter();
+    if !filter.is_active() {
+        return;
+    }
+
+    let present: BTreeSet<&str> = stats.iter().flat_map(|map| map.keys()).map(String::as_str).collect();
+    let unknown = filter.unknown_codes(&present);
+    if !unknown.is_empty() {
+        println!("Warning: no data for state code(s) {} in --states/--exclude-states", unknown.join(", "));
+    }
+    for map in stats {
+        filter.retain(map);
+    }
+    println!("States: {}", filter.describe());
+}
+
+/// Monthly totals by state, limited to the states kept by `--states` and `--exclude-states`; none when the
+/// monthly values are not `available`, so the metrics built on them are left empty.
+fn monthly_by_state(cli: &Cli, records: &[CleanRecord], available: bool) -> HashMap<String, MonthlyStats> {
+    if !available {
+        return HashMap::new();
+    }
+    let mut monthly = aggregate_monthly_by_state(records);
+    cli.state_filter().retain(&mut monthly);
+    monthly
+}
+
+/// Prints the summary of the state changes under the top movers table.
+fn print_change_summary(
+    cli: &Cli,
+    output: &[StateEfficiency],
+    stats: [&HashMap<String, StateStats>; 2],
+    labels: [&str; 2],
+) {
+    let changes = summarize_changes(output, stats, cli.units);
+    println!("\n{}", format_change_summary(&changes, labels, cli.units).trim_end());
+}
+
+/// Prints the cross-state means and medians under the top movers table and saves them.
+fn save_averages(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    output: &[StateEfficiency],
+    stats: [&HashMap<String, StateStats>; 2],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let averages = cross_state_averages(output, stats[0], stats[1], cli.averages);
+    println!("\n{}", format_averages(&averages, labels));
+    export_table(cli, summary, "cross-state averages", "state_averages", &averages, |path| {
+        write_state_averages_csv(path, &averages, cli.units)
+    })
+}
+
+/// Parses every `--metric` definition before any data is loaded.
+fn parse_metrics(specs: &[String]) -> Result<Vec<CustomMetric>, Box<dyn Error>> {
+    specs.iter().map(|spec| parse_metric(spec)).collect()
+}
+
+/// Parses the `--scenario` specs.
+fn parse_scenarios(specs: &[String]) -> Result<Vec<Scenario>, Box<dyn Error>> {
+    specs.iter().map(|spec| Ok(spec.parse::<Scenario>()?)).collect()
+}
+
+/// Runs the what-if scenarios on one input, prints their national savings and writes the savings per state.
+fn save_scenarios(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    scenarios: &[Scenario],
+    label: &str,
+    records: &[CleanRecord],
+) -> Result<(), Box<dyn Error>> {
+    let needs = [OptionalColumn::PlantId, OptionalColumn::FuelType];
+    if scenarios.is_empty() || !available(summary, &needs, "what-if scenarios", Degradation::Skipped) {
+        return Ok(());
+    }
+
+    let mut savings = Vec::new();
+    for scenario in scenarios {
+        let Some(target) = target_heat_rate(records, scenario) else {
+            println!("Warning: scenario '{}' has no eligible plants in {}", scenario, label);
+            continue;
+        };
+        let states = run_scenario(records, scenario);
+        let fuel: f64 = states.iter().map(|s| s.fuel_saved).sum();
+        let co2: f64 = states.iter().map(|s| s.co2_saved_tonnes).sum();
+        println!(
+            "Scenario {} ({}, target {:.3} MMBtu/MWh): {:.1} million MMBtu and {:.2} million t CO2 saved",
+            scenario,
+            label,
+            target,
+            fuel / 1e6,
+            co2 / 1e6
+        );
+        savings.extend(states);
+    }
+    save(cli, summary, "what-if scenarios", "scenarios.csv", |path| write_scenarios_csv(path, &savings))
+}
+
+/// Regroups both inputs' rows with the `--fuel-mapping` file, if any, reports the fuel codes it does not cover
+/// and writes each mapped group's heat rate nationally and per state.
+fn save_fuel_mapping(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    records: [&[CleanRecord]; 2],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let Some(mapping_path) = &cli.fuel_mapping else {
+        return Ok(());
+    };
+    if !available(summary, &[OptionalColumn::FuelType], "--fuel-mapping groups", Degradation::Skipped) {
+        return Ok(());
+    }
+
+    let mapping = read_fuel_mapping(mapping_path)?;
+    println!("Fuel groups from '{}': {}", mapping_path, mapping.groups().join(", "));
+    let mut unmapped: BTreeMap<String, usize> = BTreeMap::new();
+    for year_records in records {
+        for (code, rows) in unmapped_codes(year_records, &mapping) {
+            *unmapped.entry(code).or_default() += rows;
+        }
+    }
+    if !unmapped.is_empty() {
+        let codes: Vec<String> = unmapped.iter().map(|(code, rows)| format!("{} ({} rows)", code, rows)).collect();
+        println!("Warning: fuel codes not in '{}', grouped as {}: {}", mapping_path, UNMAPPED_GROUP, codes.join(", "));
+    }
+
+    let filter = cli.state_filter();
+    let mut groups = efficiency_by_fuel_mapping(records, &mapping);
+    groups.retain(|item| item.scope == NATIONAL_SCOPE || filter.keeps(&item.scope));
+    save(cli, summary, "custom fuel group heat rates", "custom_fuel_groups.csv", |path| {
+        write_mapped_fuel_groups_csv(path, &groups, labels)
+    })
+}
+
+/// Short label for an input file: its name without directory or extension (the names joined with '+' for a
+/// merged input).
+fn file_label(path: &str) -> String {
+    let stems: Vec<String> = input_parts(path)
+        .into_iter()
+        .map(|part| {
+            std::path::Path::new(part)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| part.to_string())
+        })
+        .collect();
+    stems.join("+")
+}
+
+/// Names of a base and a compare input for tables and messages: their file labels, or "A" and "B" when these
+/// are the same.
+fn input_labels(file_a: &str, file_b: &str) -> [String; 2] {
+    match (file_label(file_a), file_label(file_b)) {
+        (a, b) if a != b => [a, b],
+        _ => ["A".to_string(), "B".to_string()],
+    }
+}
+
+/// Prints how far the two inputs' state rankings agree, overall and at the top, and keeps it in the run summary.
+fn report_ranking_agreement(cli: &Cli, summary: &mut RunSummary, changes: &[StateEfficiency], labels: [&str; 2]) {
+    let agreement = year_ranking_agreement(changes, cli.rbo_depth);
+    let spearman = agreement.spearman.map_or("n/a".to_string(), |rho| format!("{:.3}", rho));
+    println!(
+        "\nRanking agreement {} vs {}: Spearman {}, rank-biased overlap of the top {} {:.3} ({} states in both)",
+        labels[0], labels[1], spearman, agreement.depth, agreement.rbo, agreement.shared
+    );
+    summary.ranking = Some(agreement);
+}
+
+/// Joins the `--eia860-generators` file, if any, onto the plants of the two inputs: prints how fleet age and
+/// heat rate correlate across states and writes the fleet ages, then with `--eia860-utilities` writes the
+/// efficiency of each ownership type within each state. Prints each join's match counts and writes the
+/// unmatched keys.
+fn save_eia860_reports(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    inputs: [(Option<i32>, &[CleanRecord]); 2],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let Some(generators_path) = &cli.eia860_generators else {
+        if cli.eia860_utilities.is_some() {
+            return Err("--eia860-utilities needs --eia860-generators to link plants to their utilities".into());
+        }
+        if cli.capacity_weighted {
+            return Err("--capacity-weighted needs --eia860-generators for the plant capacities".into());
+        }
+        return Ok(());
+    };
+    if !available(summary, &[OptionalColumn::PlantId], "EIA-860 reports", Degradation::Skipped) {
+        return Ok(());
+    }
+
+    let generators = read_generators(generators_path)?;
+    println!("Read {} generators from '{}'", generators.len(), generators_path);
+    let mut joins = Vec::new();
+    match inputs.map(|(year, _)| year) {
+        [Some(year_a), Some(year_b)] => {
+            let mut ages = Vec::new();
+            for (year, records) in [(year_a, inputs[0].1), (year_b, inputs[1].1)] {
+                let (year_ages, join) = fleet_ages(records, year, &generators, cli.join);
+                println!("{}", join);
+                joins.push(join);
+                let (pearson, spearman) = age_heat_rate_correlation(&year_ages);
+                let show = |r: Option<f64>| r.map_or("n/a".to_string(), |r| format!("{:.3}", r));
+                println!(
+                    "{}: fleet age vs heat rate across states: Pearson {}, Spearman {}",
+                    year,
+                    show(pearson),
+                    show(spearman)
+                );
+                ages.extend(year_ages);
+            }
+            save(cli, summary, "fleet ages", "fleet_age.csv", |path| write_fleet_age_csv(path, &ages))?;
+
+            if cli.capacity_weighted {
+                let mut rates = Vec::new();
+                for (year, records) in [(year_a, inputs[0].1), (year_b, inputs[1].1)] {
+                    let (year_rates, join) = capacity_weighted_heat_rates(records, year, &generators, cli.join);
+                    println!("{}", join);
+                    joins.push(join);
+                    if let Some((mean, largest)) = weighting_gap(&year_rates) {
+                        println!(
+                            "{}: capacity-weighted heat rates differ from generation-weighted by {:+.3} MMBtu/MWh \
+                             on average, most in {} ({:+.3})",
+                            year,
+                            mean,
+                            largest.state,
+                            largest.difference().unwrap_or_default()
+                        );
+                    }
+                    rates.extend(year_rates);
+                }
+                save(cli, summary, "capacity-weighted heat rates", "capacity_weighted_heat_rates.csv", |path| {
+                    write_capacity_weighted_csv(path, &rates)
+                })?;
+            }
+        }
+        _ => println!("Warning: fleet ages need each input's year; use --assume-year"),
+    }
+
+    if let Some(utilities_path) = &cli.eia860_utilities {
+        let utility_types = read_utility_types(utilities_path)?;
+        let (ownership, utility_join) = plant_ownership(&generators, &utility_types, cli.join);
+        let (groups, plant_join) = efficiency_by_ownership(inputs.map(|(_, records)| records), &ownership, cli.join);
+        for join in [utility_join, plant_join] {
+            println!("{}", join);
+            joins.push(join);
+        }
+        let unknown = groups.iter().filter(|g| g.ownership == OwnershipType::Unknown).map(|g| g.stats[1].total_gen);
+        let total: MegawattHours = groups.iter().map(|g| g.stats[1].total_gen).sum();
+        if total.0 > 0.0 && cli.join == JoinType::Left {
+            println!(
+                "Ownership types from '{}': {:.1}% of compare-year generation has no known owner",
+                utilities_path,
+                100.0 * (unknown.sum::<MegawattHours>() / total)
+            );
+        }
+        save(cli, summary, "efficiency by ownership type", "ownership_efficiency.csv", |path| {
+            write_ownership_efficiency_csv(path, &groups, labels)
+        })?;
+    }
+
+    if joins.iter().any(|j| !j.unmatched_left.is_empty() || !j.unmatched_right.is_empty()) {
+        save(cli, summary, "unmatched join keys", "join_unmatched.csv", |path| write_unmatched_keys_csv(path, &joins))?;
+    }
+    Ok(())
+}
+
+/// Prints whether state heat rates converge over the panel and writes the sigma and beta statistics.
+fn save_convergence(cli: &Cli, summary: &mut RunSummary, panel: &[YearData]) -> Result<(), Box<dyn Error>> {
+    let report = compute_convergence(panel);
+    if let Some(trend) = report.sigma_trend {
+        let verdict = if trend < 0.0 { "narrowing" } else { "widening" };
+        println!("Sigma-convergence: cross-state dispersion of log heat rates {} by {:.4} per year", verdict, trend.abs());
+    }
+    match &report.beta {
+        Some(beta) => println!(
+            "Beta-convergence {}-{}: beta {:.4} (s.e. {:.4}, {}) over {} states",
+            beta.first_year,
+            beta.last_year,
+            beta.beta,
+            beta.std_error,
+            if beta.significant { "significant at 95%" } else { "not significant" },
+            beta.states
+        ),
+        None => println!("Beta-convergence needs at least two years and three states"),
+    }
+
+    save(cli, summary, "sigma-convergence by year", "sigma_convergence.csv", |path| {
+        write_sigma_convergence_csv(path, &report.sigma)
+    })?;
+    save(cli, summary, "convergence statistics", "convergence.csv", |path| {
+        write_convergence_summary_csv(path, &report)
+    })
+}
+
+/// Prints how many states ran less efficiently in their peak generation month each year and writes the
+/// per-state peak penalties.
+fn save_peak_penalties(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    years: &[(i32, &HashMap<String, MonthlyStats>)],
+) -> Result<(), Box<dyn Error>> {
+    let penalties = compute_peak_penalties(years);
+    for (year, _) in years {
+        let in_year: Vec<&PeakPenalty> = penalties.iter().filter(|p| p.year == *year).collect();
+        let penalized = in_year.iter().filter(|p| p.penalty() > 0.0).count();
+        println!("{}: {} of {} states had a higher heat rate in their peak month", year, penalized, in_year.len());
+    }
+    save(cli, summary, "peak month penalties", "peak_penalty.csv", |path| write_peak_penalties_csv(path, &penalties))
+}
+
+/// Runs the energy-unit sanity checks on the plain state sums of each input (oldest first).
+fn check_sanity(summary: &mut RunSummary, inputs: &[(&str, &[CleanRecord])]) {
+    let totals: Vec<HashMap<String, StateStats>> = inputs.iter().map(|(_, records)| aggregate_by_state(records)).collect();
+    let years: Vec<(&str, &HashMap<String, StateStats>)> =
+        inputs.iter().zip(&totals).map(|((label, _), stats)| (*label, stats)).collect();
+    report_sanity_issues(summary, run_sanity_checks(&years));
+}
+
+/// Prints failed sanity checks in a banner that is hard to scroll past, and counts them in the run summary.
+fn report_sanity_issues(summary: &mut RunSummary, issues: Vec<SanityIssue>) {
+    if issues.is_empty() {
+        return;
+    }
+    let rule = "!".repeat(100);
+    println!("\n{}", rule);
+    println!("WARNING: {} sanity checks failed; the results are probably wrong (unit or column mix-up?)", issues.len());
+    for issue in &issues {
+        println!("  - {}", issue.message);
+    }
+    println!("{}\n", rule);
+    summary.sanity_warnings += issues.len();
+}
+
+/// Empty run summary carrying the run label.
+fn start_summary(cli: &Cli, command: &str) -> RunSummary {
+    let mut summary = RunSummary::new(command);
+    summary.label = cli.label.clone();
+    if let Some(timestamp) = &cli.timestamp_override {
+        summary.timestamp = timestamp.clone();
+    }
+    summary
+}
+
+/// Loads an input file with the `--territories`, `--impute` and `--heat-content` options and records it in the
+/// run summary.
+fn load_input(cli: &Cli, summary: &mut RunSummary, path: &str) -> Result<Vec<CleanRecord>, Box<dyn Error>> {
+    let local = &resolve_input(path, &cli.download_options())?;
+    let mut held: Vec<&str> = summary.inputs.iter().map(|input| input.path.as_str()).collect();
+    held.push(local);
+    check_memory_limit(cli, &held)?;
+    let loaded = load_records_cached(local, &cli.load_options(), &cli.cache_dir, !cli.no_cache, &cli.cancel)?;
+    let year = infer_year(path, &loaded.years, cli.year_overrides()?.for_file(path));
+    match &year {
+        Some(year) => {
+            for warning in &year.warnings {
+                println!("Warning: {}", warning);
+            }
+            println!("{} data (from {})", year.year, year.source.label());
+        }
+        None => println!("Data year unknown (no YEAR column or year in the file name)"),
+    }
+    summary.input(local, loaded.records.len());
+    summary.year(year.map(|y| y.year));
+    summary.imputed(loaded.imputed.len());
+    summary.anomalies(loaded.anomalies.len());
+    summary.skipped(loaded.skipped);
+    summary.missing_columns(loaded.missing);
+    summary.imputed_values.extend(loaded.imputed);
+    summary.heat_content_anomalies.extend(loaded.anomalies);
+    Ok(loaded.records)
+}
+
+/// Stops before loading when the inputs held at once would exceed `--max-memory`.
+fn check_memory_limit(cli: &Cli, paths: &[&str]) -> Result<(), Box<dyn Error>> {
+    if let Some(limit) = cli.max_memory {
+        let estimate = check_memory(paths, limit)?;
+        println!("Estimated memory for {}: {} (limit {})", paths.join(", "), format_bytes(estimate), limit);
+    }
+    Ok(())
+}
+
+/// Stops if either input holds another year than it is labeled with (2019 and 2020 in the default analysis).
+fn check_years(summary: &RunSummary, years: [i32; 2]) -> Result<(), Box<dyn Error>> {
+    for (input, expected) in summary.inputs.iter().zip(years) {
+        if let Some(year) = input.year
+            && year != expected
+        {
+            let name = Path::new(&input.path).file_name().unwrap_or_default().to_string_lossy();
+            return Err(format!(
+                "'{}' holds {} data, not {}; pass --assume-year {}={} if the file is right",
+                input.path, year, expected, name, expected
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// With `--narratives`, prints a short narrative of each of the top 10 improvers and regressors and writes them
+/// as Markdown.
+fn save_narratives(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    records: [&[CleanRecord]; 2],
+    changes: &[StateEfficiency],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    if !cli.narratives || !available(summary, &[OptionalColumn::PlantId], "narratives", Degradation::Skipped) {
+        return Ok(());
+    }
+    let narratives = narrate_top_movers(records, changes, 10, labels, cli.units);
+    println!("\nTop movers in brief:");
+    for narrative in &narratives {
+        println!("\n{}", narrative.text);
+    }
+    println!();
+    save(cli, summary, "top mover narratives", "top_movers.md", |path| {
+        Ok(fs::write(path, format_narratives_markdown(&narratives, labels))?)
+    })
+}
+
+/// With `--bands`, prints the national heat rate bands of both inputs and writes the bands of every state.
+fn save_bands(cli: &Cli, summary: &mut RunSummary, inputs: [(&str, &[CleanRecord]); 2]) -> Result<(), Box<dyn Error>> {
+    let needs = [OptionalColumn::PlantId, OptionalColumn::FuelType];
+    if !cli.bands || !available(summary, &needs, "heat rate bands", Degradation::Skipped) {
+        return Ok(());
+    }
+    let bands: Vec<BandCounts> =
+        inputs.iter().flat_map(|(label, records)| heat_rate_bands(records, label, &cli.band_edges)).collect();
+    println!("\nFossil plants by heat rate band (MMBtu/MWh):\n{}", format_national_bands(&bands, &cli.band_edges));
+    save(cli, summary, "heat rate bands", "heat_rate_bands.csv", |path| write_bands_csv(path, &bands, &cli.band_edges))
+}
+
+/// With `--fuel-prices`, prices the fuel each state saved at the compare year's delivered fuel costs, prints the
+/// total and writes the table.
+fn save_fuel_cost_avoided(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    changes: &[StateEfficiency],
+    stats_compare: &HashMap<String, StateStats>,
+    records_compare: &[CleanRecord],
+) -> Result<(), Box<dyn Error>> {
+    let Some(path) = &cli.fuel_prices else {
+        return Ok(());
+    };
+    if !available(summary, &[OptionalColumn::FuelType], "fuel cost avoided", Degradation::Skipped) {
+        return Ok(());
+    }
+
+    let path = resolve_input(path, &cli.download_options())?;
+    let prices = read_fuel_prices(&path)?;
+    println!(
+        "Read delivered fuel prices from '{}' ({} priced deliveries, {} without a cost)",
+        path, prices.priced, prices.unpriced
+    );
+    let costs = compute_fuel_cost_avoided(changes, stats_compare, &state_fuel_prices(records_compare, &prices));
+    let total: f64 = costs.iter().filter_map(|c| c.avoided_cost).sum();
+    let national = costs.iter().filter(|c| c.national_price_share.is_some_and(|share| share > 0.0)).count();
+    println!(
+        "Fuel cost avoided at compare-year prices: ${:.1} million across {} states ({} priced partly at national \
+         averages)",
+        total / 1e6,
+        costs.iter().filter(|c| c.avoided_cost.is_some()).count(),
+        national
+    );
+    export_table(cli, summary, "fuel cost avoided", "fuel_cost_avoided", &costs, |path| {
+        write_fuel_cost_avoided_csv(path, &costs)
+    })
+}
+
+/// With `--energy-balance`, prints the states and plants of both inputs that fail the energy balance and writes
+/// the balance of every state.
+fn save_energy_balance(
+    cli: &Cli,
+    summary: &mut RunSummary,
+    inputs: [(&str, &[CleanRecord]); 2],
+) -> Result<(), Box<dyn Error>> {
+    if !cli.energy_balance {
+        return Ok(());
+    }
+    let needs = [OptionalColumn::PlantId, OptionalColumn::FuelType];
+    let plants = available(summary, &needs, "energy balance of plants", Degradation::Skipped);
+    let balances: Vec<EnergyBalance> =
+        inputs.iter().flat_map(|(label, records)| energy_balance(records, label, plants)).collect();
+    println!("\nEnergy balance (electricity out / fuel in):\n{}", format_flagged(&balances));
+    save(cli, summary, "energy balance", "energy_balance.csv", |path| write_energy_balance_csv(path, &balances))
+}
+
+/// Writes the cleaned records to the `--emit-cleaned` file, or with `--partition-by` its directory, if any.
+fn save_cleaned(cli: &Cli, summary: &mut RunSummary, inputs: &[(&str, &[CleanRecord])]) -> Result<(), Box<dyn Error>> {
+    let Some(path) = &cli.emit_cleaned else {
+        if cli.partition_by.is_some() {
+            return Err("--partition-by applies to --emit-cleaned; name its output directory".into());
+        }
+        return Ok(());
+    };
+
+    if let Some(by) = cli.partition_by {
+        println!("Saving cleaned records partitioned by {} to '{}'...", by.column(), path);
+        let files = write_cleaned_partitioned(path, inputs, by, cli.chunk_rows.get())?;
+        println!("Wrote {} part files", files.len());
+        files.iter().for_each(|file| summary.output(file));
+        return Ok(());
+    }
+    println!("Saving cleaned records to '{}'...", path);
+    write_cleaned_csv(path, inputs)?;
+    summary.output(path);
+    Ok(())
+}
+
+/// Local paths for the input files, downloading any URLs.
+fn resolve_inputs(cli: &Cli, files: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    files.iter().map(|f| resolve_input(f, &cli.download_options())).collect()
+}
+
+/// Loads one input per year with the loading, checkpoint and cache options.
+fn load_years(cli: &Cli, files: &[String]) -> Result<Vec<YearData>, Box<dyn Error>> {
+    let files = resolve_inputs(cli, files)?;
+    // The years are loaded one at a time and reduced to state totals, so only the largest file counts
+    if cli.max_memory.is_some() {
+        let largest = files.iter().max_by_key(|file| estimate_load_memory(&[file.as_str()]).unwrap_or(u64::MAX));
+        check_memory_limit(cli, &largest.map(String::as_str).into_iter().collect::<Vec<_>>())?;
+    }
+    let options = cli.load_options();
+    let overrides = cli.year_overrides()?;
+    let checkpoint_dir = cli.checkpoint_dir.as_deref();
+    let mut panel = load_panel(&files, checkpoint_dir, &options, &cli.cache_dir, !cli.no_cache, &cli.cancel, &overrides)?;
+
+    let filter = cli.state_filter();
+    if filter.is_active() {
+        for year in &mut panel {
+            filter.retain(&mut year.stats);
+            filter.retain(&mut year.monthly);
+            year.fuels.retain(|state, _| filter.keeps(state));
+        }
+        println!("States: {}", filter.describe());
+    }
+    Ok(panel)
+}
+
+/// Run summary for a multi-year command; `states` counts every state seen in any year.
+fn panel_summary(cli: &Cli, command: &str, panel: &[YearData]) -> RunSummary {
+    let mut summary = start_summary(cli, command);
+    for year_data in panel {
+        summary.input(&year_data.source, year_data.rows);
+        summary.imputed(year_data.imputed);
+        summary.anomalies(year_data.anomalies);
+    }
+    summary.states = panel.iter().flat_map(|y| y.stats.keys()).collect::<HashSet<_>>().len();
+
+    let labels: Vec<String> = panel.iter().map(|y| y.year.to_string()).collect();
+    let years: Vec<(&str, &HashMap<String, StateStats>)> =
+        labels.iter().zip(panel).map(|(label, y)| (label.as_str(), &y.stats)).collect();
+    report_sanity_issues(&mut summary, run_sanity_checks(&years));
+    summary
+}
+
+/// Panel mode: loads every requested year and writes per-state heat rates in long (and optionally wide) form.
+fn run_panel(cli: &Cli, command: &str, files: &[String]) -> Result<RunSummary, Box<dyn Error>> {
+    let panel = load_years(cli, files)?;
+    let mut summary = panel_summary(cli, command, &panel);
+
+    let long = panel_long_rows(&panel);
+    export_table(cli, &mut summary, "per-year state heat rates", "panel_heat_rates", &long, |path| {
+        write_panel_long_csv(path, &panel)
+    })?;
+
+    if cli.wide {
+        let wide = panel_wide_rows(&panel);
+        export_table(cli, &mut summary, "state x year heat rate matrix", "panel_heat_rates_wide", &wide, |path| {
+            write_panel_wide_csv(path, &panel)
+        })?;
+    }
+
+    if cli.change_points {
+        save(cli, &mut summary, "change-point detection results", "change_points.csv", |path| {
+            write_change_points_csv(path, &detect_change_points(&panel))
+        })?;
+    }
+
+    if cli.forecast {
+        save(cli, &mut summary, "next-year heat rate forecasts", "forecast.csv", |path| {
+            write_forecast_csv(path, &forecast_states(&panel))
+        })?;
+    }
+
+    if cli.convergence {
+        save_convergence(cli, &mut summary, &panel)?;
+    }
+
+    if cli.migration {
+        let shifts = detect_generation_shifts(&panel);
+        println!("\nCandidate fossil generation shifts between bordering states:");
+        print!("{}", format_top_shifts(&shifts, 10));
+        save(cli, &mut summary, "candidate generation shifts", "generation_shifts.csv", |path| {
+            write_generation_shifts_csv(path, &shifts)
+        })?;
+    }
+
+    if cli.heatmap {
+        save(cli, &mut summary, "state x year heat map", "heatmap.svg", |path| {
+            write_heatmap_svg(path, &panel, cli.heatmap_metric, cli.color_scale)
+        })?;
+    }
+
+    if cli.monthly_series {
+        let years: Vec<(i32, &HashMap<String, MonthlyStats>)> = panel.iter().map(|y| (y.year, &y.monthly)).collect();
+        save(cli, &mut summary, "monthly state series", "monthly_series.csv", |path| {
+            write_monthly_series_csv(path, &years)
+        })?;
+    }
+
+    if cli.national_monthly {
+        let years: Vec<(i32, &HashMap<String, MonthlyStats>)> = panel.iter().map(|y| (y.year, &y.monthly)).collect();
+        save_national_monthly(cli, &mut summary, &years)?;
+    }
+
+    if cli.extreme_months {
+        let years: Vec<(i32, &HashMap<String, MonthlyStats>)> = panel.iter().map(|y| (y.year, &y.monthly)).collect();
+        let extremes = find_extreme_months(&years);
+        save(cli, &mut summary, "best and worst months", "extreme_months.csv", |path| {
+            write_extreme_months_csv(path, &extremes)
+        })?;
+    }
+
+    if cli.peak_penalty {
+        let years: Vec<(i32, &HashMap<String, MonthlyStats>)> = panel.iter().map(|y| (y.year, &y.monthly)).collect();
+        save_peak_penalties(cli, &mut summary, &years)?;
+    }
+
+    if cli.html {
+        let plotly = cli.plotly_script()?;
+        save(cli, &mut summary, "interactive chart", "panel_chart.html", |path| {
+            write_panel_chart_html(path, &panel, cli.palette, &plotly)
+        })?;
+    }
+
+    if cli.tidy {
+        save(cli, &mut summary, "all metrics in long format", "tidy_metrics.csv", |path| {
+            write_tidy_csv(path, &mut tidy_panel(&panel))
+        })?;
+    }
+
+    println!("Done.");
+    Ok(summary)
 }
@@ -0,0 +1,222 @@
+// merge.rs
+// This module lets several files stand in for one year, written as one input joined with '+'
+// (`2020-utility.csv+2020-nonutility.csv`, or one file per month). The parts are read separately and their rows
+// combined before the territory, imputation and heat content steps, so a month left blank in one part is filled
+// from the others rather than imputed. A plant row found in more than one part is handled by `--duplicates`.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::cancel::CancellationToken;
+use crate::cleaning::{read_clean_records_with_cancel, CleanRecord, SkipCounts};
+use crate::years::DataYears;
+
+/// Joins the files of a merged input.
+pub const PART_SEPARATOR: char = '+';
+
+/// Treatment of a plant row (same plant, state, fuel codes and prime mover) found in more than one part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum DuplicatePolicy {
+    /// Values are added, month by month (parts covering different months, or different units of a plant).
+    #[default]
+    Sum,
+    /// The row from the earliest part is kept.
+    KeepFirst,
+    /// The row from the latest part is kept (e.g. a revised extract listed last).
+    KeepLast,
+    /// Duplicates stop the run.
+    Error,
+}
+
+impl DuplicatePolicy {
+    /// Name as given on the command line.
+    pub fn label(self) -> &'static str {
+        match self {
+            DuplicatePolicy::Sum => "sum",
+            DuplicatePolicy::KeepFirst => "keep-first",
+            DuplicatePolicy::KeepLast => "keep-last",
+            DuplicatePolicy::Error => "error",
+        }
+    }
+}
+
+/// The files of an input: the parts of `a.csv+b.csv`, or the input itself.
+pub fn input_parts(spec: &str) -> Vec<&str> {
+    spec.split(PART_SEPARATOR).map(str::trim).filter(|part| !part.is_empty()).collect()
+}
+
+/// Whether an input names several files.
+pub fn is_merged(spec: &str) -> bool {
+    input_parts(spec).len() > 1
+}
+
+type RowKey = (String, String, String, String, String);
+
+fn row_key(record: &CleanRecord) -> RowKey {
+    (
+        record.plant_id.clone(),
+        record.state.clone(),
+        record.aer_fuel.clone(),
+        record.reported_fuel.clone(),
+        record.prime_mover.clone(),
+    )
+}
+
+/// Adds `part`'s values into `into`; a month stays blank only if it is blank in both.
+fn add_row(into: &mut CleanRecord, part: &CleanRecord) {
+    into.fuel += part.fuel;
+    into.r#gen += part.r#gen;
+    for m in 0..12 {
+        into.monthly_fuel[m] += part.monthly_fuel[m];
+        into.monthly_gen[m] += part.monthly_gen[m];
+        into.monthly_quantity[m] += part.monthly_quantity[m];
+        into.missing_fuel[m] &= part.missing_fuel[m];
+        into.missing_gen[m] &= part.missing_gen[m];
+    }
+}
+
+/// Combines the rows of several parts. Rows repeated within one part are kept as they are; only rows that
+/// reappear in a later part count as duplicates.
+/// # Arguments
+/// * `parts` - `(file, rows)` per part, in the order given
+/// * `policy` - Treatment of duplicates
+/// # Returns
+/// * The combined rows and the number of duplicate rows, or `Err` under `DuplicatePolicy::Error`
+pub fn merge_rows(
+    parts: Vec<(&str, Vec<CleanRecord>)>,
+    policy: DuplicatePolicy,
+) -> Result<(Vec<CleanRecord>, usize), Box<dyn Error>> {
+    let mut merged: Vec<CleanRecord> = Vec::new();
+    let mut index: HashMap<RowKey, usize> = HashMap::new();
+    let mut duplicates = Vec::new();
+
+    for (file, records) in parts {
+        let mut added = Vec::new();
+        for record in records {
+            let key = row_key(&record);
+            match index.get(&key) {
+                Some(&i) => {
+                    duplicates.push(format!("plant {} {} in '{}'", record.plant_id, record.aer_fuel, file));
+                    match policy {
+                        DuplicatePolicy::Sum => add_row(&mut merged[i], &record),
+                        DuplicatePolicy::KeepFirst | DuplicatePolicy::Error => {}
+                        DuplicatePolicy::KeepLast => merged[i] = record,
+                    }
+                }
+                None => {
+                    added.push((key, merged.len()));
+                    merged.push(record);
+                }
+            }
+        }
+        index.extend(added);
+    }
+
+    if policy == DuplicatePolicy::Error && !duplicates.is_empty() {
+        let shown: Vec<&str> = duplicates.iter().take(5).map(String::as_str).collect();
+        return Err(format!(
+            "{} plant rows appear in more than one part ({}{}); choose --duplicates sum, keep-first or keep-last",
+            duplicates.len(),
+            shown.join(", "),
+            if duplicates.len() > shown.len() { ", ..." } else { "" }
+        )
+        .into());
+    }
+    Ok((merged, duplicates.len()))
+}
+
+/// Reads every part of a merged input and combines their rows, skip counts and YEAR values.
+/// # Arguments
+/// * `spec` - Files joined with '+'
+/// * `policy` - Treatment of rows found in more than one part
+/// * `cancel` - Stops the reading early
+pub fn read_merged(
+    spec: &str,
+    policy: DuplicatePolicy,
+    cancel: &CancellationToken,
+) -> Result<(Vec<CleanRecord>, SkipCounts, DataYears), Box<dyn Error>> {
+    let mut parts = Vec::new();
+    let mut skipped = SkipCounts::default();
+    let mut years = DataYears::default();
+
+    for part in input_parts(spec) {
+        let (records, part_skipped, part_years) = read_clean_records_with_cancel(part, cancel)?;
+        skipped.unreadable += part_skipped.unreadable;
+        skipped.unparsable_values += part_skipped.unparsable_values;
+        skipped.zero_generation += part_skipped.zero_generation;
+        for (year, rows) in part_years.0 {
+            *years.0.entry(year).or_default() += rows;
+        }
+        parts.push((part, records));
+    }
+
+    let count = parts.len();
+    let (records, duplicates) = merge_rows(parts, policy)?;
+    println!(
+        "Merged {} files into {} rows ({} plant rows in more than one file, {})",
+        count,
+        records.len(),
+        duplicates,
+        policy.label()
+    );
+    Ok((records, skipped, years))
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    /// A row reporting only `month`, at 10 MMBtu/MWh.
+    fn month_record(plant_id: &str, month: usize, r#gen: f64) -> CleanRecord {
+        let mut missing = [true; 12];
+        missing[month] = false;
+        let mut monthly_gen = [0.0; 12];
+        monthly_gen[month] = r#gen;
+        CleanRecord {
+            monthly_fuel: monthly_gen.map(|g| 10.0 * g),
+            monthly_gen,
+            missing_fuel: missing,
+            missing_gen: missing,
+            reported_fuel: "NG".to_string(),
+            prime_mover: "CT".to_string(),
+            ..record(plant_id, "AA", "NG", 10.0 * r#gen, r#gen)
+        }
+    }
+
+    /// Plant 1 reports January in the first file and February in the second; plant 2 only in the first.
+    fn parts() -> Vec<(&'static str, Vec<CleanRecord>)> {
+        vec![
+            ("jan.csv", vec![month_record("1", 0, 5.0), month_record("2", 0, 1.0)]),
+            ("feb.csv", vec![month_record("1", 1, 7.0)]),
+        ]
+    }
+
+    #[test]
+    fn test_input_parts() {
+        assert_eq!(input_parts("2020-a.csv + 2020-b.csv"), ["2020-a.csv", "2020-b.csv"]);
+        assert!(!is_merged("2020.csv"));
+    }
+
+    #[test]
+    fn test_sum_combines_the_months() {
+        let (summed, duplicates) = merge_rows(parts(), DuplicatePolicy::Sum).unwrap();
+        assert_eq!((summed.len(), duplicates), (2, 1));
+        assert_eq!((summed[0].r#gen, summed[0].monthly_gen[1]), (12.0, 7.0));
+        assert!(!summed[0].missing_gen[0] && !summed[0].missing_gen[1] && summed[0].missing_gen[2]);
+    }
+
+    #[test]
+    fn test_keep_and_error_policies() {
+        assert_eq!(merge_rows(parts(), DuplicatePolicy::KeepFirst).unwrap().0[0].r#gen, 5.0);
+        assert_eq!(merge_rows(parts(), DuplicatePolicy::KeepLast).unwrap().0[0].r#gen, 7.0);
+        let error = merge_rows(parts(), DuplicatePolicy::Error).unwrap_err().to_string();
+        assert!(error.starts_with("1 plant rows appear in more than one part (plant 1 NG in 'feb.csv')"));
+    }
+}
@@ -0,0 +1,125 @@
+// methodology.rs
+// This module describes the assumptions behind a run — fuel codes, fuel basis, row filters and the options that
+// change the numbers — built from the runtime configuration so the written methodology matches the code.
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use crate::aggregation::Aggregation;
+use crate::cleaning::{CleanRecord, LoadOptions, SkipCounts, FUEL_BASIS, INCREMENT_PLANT_ID, ZERO_GENERATION_POLICY};
+use crate::coverage::CoverageRule;
+use crate::hooks::InputSummary;
+use crate::units::HeatRateUnit;
+
+/// Options of the run that affect the methodology, besides the load options.
+#[derive(Debug, Clone, Copy)]
+pub struct MethodologyOptions {
+    pub load: LoadOptions,
+    pub coverage: CoverageRule,
+    pub exclude_inconsistent: bool,
+    pub unit: HeatRateUnit,
+    pub aggregation: Aggregation,
+    pub trim: f64,
+}
+
+/// An input file and the rows dropped while reading it.
+#[derive(Debug, Serialize)]
+pub struct InputAssumptions {
+    pub path: String,
+    pub rows: usize,
+    pub skipped: SkipCounts,
+}
+
+/// The assumptions block written to 'methodology.json' and exposed to templates.
+#[derive(Debug, Serialize)]
+pub struct Methodology {
+    pub heat_rate: &'static str,
+
+    /// How each state's plants are combined before the ratio.
+    pub aggregation: String,
+    pub units: &'static str,
+    pub fuel_basis: &'static str,
+
+    /// AER fuel codes present in the analysed rows; every one counts toward the state totals.
+    pub fuel_codes: Vec<String>,
+    pub zero_generation: &'static str,
+    pub increment_rows: String,
+    pub territories: &'static str,
+    pub blank_months: &'static str,
+    pub implausible_heat_content: &'static str,
+    pub inconsistent_plants: &'static str,
+
+    /// States need at least this many plants and MWh in both years (0 = no minimum).
+    pub min_plants: usize,
+    pub min_generation_mwh: f64,
+
+    pub inputs: Vec<InputAssumptions>,
+}
+
+/// Builds the assumptions block for a run.
+/// # Arguments
+/// * `records` - Cleaned rows of every compared file
+/// * `inputs` - Input files as recorded in the run summary
+/// * `options` - Options that change the numbers
+/// # Returns
+/// * `Methodology` describing the run
+pub fn describe_methodology(records: &[&[CleanRecord]], inputs: &[InputSummary], options: MethodologyOptions) -> Methodology {
+    let fuel_codes: BTreeSet<&str> = records.iter().flat_map(|r| r.iter().map(|r| r.aer_fuel.as_str())).collect();
+
+    Methodology {
+        heat_rate: "state fuel consumption / state net generation",
+        aggregation: options.aggregation.describe(options.trim),
+        units: options.unit.label(),
+        fuel_basis: FUEL_BASIS,
+        fuel_codes: fuel_codes.into_iter().map(String::from).collect(),
+        zero_generation: ZERO_GENERATION_POLICY,
+        increment_rows: format!("rows with plant id {} (EIA state-level estimates) count toward state totals", INCREMENT_PLANT_ID),
+        territories: options.load.territories.describe(),
+        blank_months: options.load.impute.label(),
+        implausible_heat_content: options.load.heat_content.label(),
+        inconsistent_plants: if options.exclude_inconsistent { "excluded" } else { "kept (listed only)" },
+        min_plants: options.coverage.min_plants,
+        min_generation_mwh: options.coverage.min_gen,
+        inputs: inputs
+            .iter()
+            .map(|i| InputAssumptions { path: i.path.clone(), rows: i.rows, skipped: i.skipped })
+            .collect(),
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_methodology_follows_options() {
+        let fuel = |aer_fuel| record("1", "AA", aer_fuel, 10.0, 1.0);
+        let (base, compare) = ([fuel("NG"), fuel("COL")], [fuel("NG"), fuel("SUN")]);
+        let inputs = [InputSummary {
+            path: "2019.csv".to_string(),
+            rows: 2,
+            year: Some(2019),
+            imputed: 0,
+            anomalies: 0,
+            skipped: SkipCounts { unreadable: 1, unparsable_values: 0, zero_generation: 3 },
+            missing_columns: Default::default(),
+        }];
+        let options = MethodologyOptions {
+            load: LoadOptions::default(),
+            coverage: CoverageRule { min_plants: 3, min_gen: 0.0 },
+            exclude_inconsistent: true,
+            unit: HeatRateUnit::BtuPerKwh,
+            aggregation: Aggregation::PlantMedian,
+            trim: 0.05,
+        };
+
+        let methodology = describe_methodology(&[&base, &compare], &inputs, options);
+        assert_eq!(methodology.fuel_codes, ["COL", "NG", "SUN"]);
+        assert_eq!((methodology.units, methodology.inconsistent_plants, methodology.min_plants), ("Btu/kWh", "excluded", 3));
+        assert_eq!(methodology.inputs[0].skipped.total(), 4);
+        assert_eq!(methodology.aggregation, "median of plant heat rates");
+    }
+}
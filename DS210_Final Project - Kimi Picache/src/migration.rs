@@ -0,0 +1,230 @@
+// migration.rs
+// This module looks for generation moving across state lines over the panel: between consecutive years, a state
+// whose fossil generation fell next to a bordering state whose fossil generation rose is a candidate for a shift
+// of dispatch (more imports from the neighbor, or a plant's output replaced by one across the border). EIA-923
+// has no flows between states, so the pairs are candidates only; the shift of a pair is the most that could have
+// moved between the two, the smaller of the fall and the rise. Both states' fossil heat rates in the later year
+// are given alongside, so a shift toward a more (or less) efficient fleet shows in the efficiency story.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::StateStats;
+use crate::geography::state_adjacency;
+use crate::monthly::format_optional;
+use crate::panel::YearData;
+
+/// Smallest change, as a share of the state's earlier fossil generation, counted as a fall or a rise; smaller
+/// changes are year-to-year noise.
+pub const MIN_CHANGE_SHARE: f64 = 0.01;
+
+/// A bordering pair of states whose fossil generation moved in opposite directions between two years.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GenerationShift {
+    pub from_year: i32,
+    pub to_year: i32,
+
+    /// State whose fossil generation fell, and the change (MWh, negative).
+    pub falling: String,
+    pub falling_change: f64,
+
+    /// Bordering state whose fossil generation rose, and the change (MWh).
+    pub rising: String,
+    pub rising_change: f64,
+
+    /// The smaller of the fall and the rise (MWh).
+    pub shift: f64,
+
+    /// Shift as a share of the falling state's earlier fossil generation.
+    pub share: f64,
+
+    /// Fossil heat rates of both states in the later year (MMBtu/MWh).
+    pub falling_heat_rate: Option<f64>,
+    pub rising_heat_rate: Option<f64>,
+}
+
+/// Fossil fuel and generation totals per state in one year.
+fn fossil_totals(year: &YearData) -> HashMap<&str, StateStats> {
+    year.fuels
+        .iter()
+        .map(|(state, groups)| {
+            let mut totals = StateStats::default();
+            for (_, stats) in groups.iter().filter(|(group, _)| group.is_fossil()) {
+                totals.total_fuel += stats.total_fuel;
+                totals.total_gen += stats.total_gen;
+            }
+            (state.as_str(), totals)
+        })
+        .collect()
+}
+
+/// Finds the candidate shifts between each pair of consecutive years.
+/// # Arguments
+/// * `panel` - Years sorted by year, with fuel group totals
+/// # Returns
+/// * `Vec<GenerationShift>` by year, then largest shift first
+pub fn detect_generation_shifts(panel: &[YearData]) -> Vec<GenerationShift> {
+    let graph = state_adjacency();
+    let mut shifts = Vec::new();
+
+    for pair in panel.windows(2) {
+        let (before, after) = (fossil_totals(&pair[0]), fossil_totals(&pair[1]));
+        // Change of each state's fossil generation, where it is large enough to count
+        let changes: HashMap<&str, f64> = before
+            .iter()
+            .filter_map(|(state, earlier)| {
+                let change = (after.get(state)?.total_gen - earlier.total_gen).0;
+                (earlier.total_gen.0 > 0.0 && change.abs() >= MIN_CHANGE_SHARE * earlier.total_gen.0)
+                    .then_some((*state, change))
+            })
+            .collect();
+        let heat_rate = |state: &str| after.get(state)?.heat_rate().map(|rate| rate.0);
+
+        let mut year_shifts = Vec::new();
+        for (falling, neighbors) in &graph {
+            let Some(&falling_change) = changes.get(falling).filter(|change| **change < 0.0) else {
+                continue;
+            };
+            for rising in neighbors {
+                let Some(&rising_change) = changes.get(rising).filter(|change| **change > 0.0) else {
+                    continue;
+                };
+                let shift = rising_change.min(-falling_change);
+                year_shifts.push(GenerationShift {
+                    from_year: pair[0].year,
+                    to_year: pair[1].year,
+                    falling: falling.to_string(),
+                    falling_change,
+                    rising: rising.to_string(),
+                    rising_change,
+                    shift,
+                    share: shift / before[falling].total_gen.0,
+                    falling_heat_rate: heat_rate(falling),
+                    rising_heat_rate: heat_rate(rising),
+                });
+            }
+        }
+        year_shifts.sort_by(|a, b| {
+            b.shift.total_cmp(&a.shift).then_with(|| (&a.falling, &a.rising).cmp(&(&b.falling, &b.rising)))
+        });
+        shifts.extend(year_shifts);
+    }
+    shifts
+}
+
+/// Formats the largest shifts of each pair of years for the terminal.
+pub fn format_top_shifts(shifts: &[GenerationShift], count: usize) -> String {
+    if shifts.is_empty() {
+        return "No bordering states moved in opposite directions.\n".to_string();
+    }
+
+    let mut out = String::new();
+    let mut years: Vec<(i32, i32)> = shifts.iter().map(|s| (s.from_year, s.to_year)).collect();
+    years.dedup();
+    for (from_year, to_year) in years {
+        let pairs: Vec<&GenerationShift> =
+            shifts.iter().filter(|s| (s.from_year, s.to_year) == (from_year, to_year)).collect();
+        out += &format!("\n{} → {}: {} candidate pairs\n", from_year, to_year, pairs.len());
+        out += &format!(
+            "{:<8} {:>16} {:<8} {:>16} {:>16} {:>8}  {}\n",
+            "Falling", "Change (MWh)", "Rising", "Change (MWh)", "Shift (MWh)", "Share", "Heat rates (falling/rising)"
+        );
+        for shift in pairs.into_iter().take(count) {
+            let rate = |rate: Option<f64>| rate.map_or("-".to_string(), |r| format!("{:.3}", r));
+            out += &format!(
+                "{:<8} {:>16.0} {:<8} {:>16.0} {:>16.0} {:>7.1}%  {} / {}\n",
+                shift.falling,
+                shift.falling_change,
+                shift.rising,
+                shift.rising_change,
+                shift.shift,
+                shift.share * 100.0,
+                rate(shift.falling_heat_rate),
+                rate(shift.rising_heat_rate)
+            );
+        }
+    }
+    out
+}
+
+/// Writes the candidate shifts to a CSV output file.
+pub fn write_generation_shifts_csv(path: &str, shifts: &[GenerationShift]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "From_Year", "To_Year", "Falling_State", "Falling_Change_MWh", "Rising_State", "Rising_Change_MWh",
+        "Shift_MWh", "Share_Of_Falling", "Falling_Heat_Rate", "Rising_Heat_Rate",
+    ])?;
+
+    for shift in shifts {
+        wtr.write_record([
+            &shift.from_year.to_string(),
+            &shift.to_year.to_string(),
+            &shift.falling,
+            &format!("{:.6}", shift.falling_change),
+            &shift.rising,
+            &format!("{:.6}", shift.rising_change),
+            &format!("{:.6}", shift.shift),
+            &format!("{:.6}", shift.share),
+            &format_optional(shift.falling_heat_rate),
+            &format_optional(shift.rising_heat_rate),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use crate::cleaning::LoadOptions;
+    use crate::fuels::FuelGroup;
+    use crate::quantity::{MegawattHours, Mmbtu};
+
+    fn year(year: i32, generation: &[(&str, FuelGroup, f64)]) -> YearData {
+        let mut fuels: BTreeMap<String, BTreeMap<FuelGroup, StateStats>> = BTreeMap::new();
+        for (state, group, r#gen) in generation {
+            let stats = StateStats { total_fuel: Mmbtu(r#gen * 10.0), total_gen: MegawattHours(*r#gen) };
+            fuels.entry(state.to_string()).or_default().insert(*group, stats);
+        }
+        YearData {
+            year,
+            source: format!("{}.csv", year),
+            rows: 0,
+            options: LoadOptions::default(),
+            imputed: 0,
+            anomalies: 0,
+            monthly: HashMap::new(),
+            stats: HashMap::new(),
+            fuels,
+            technologies: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_generation_shifts() {
+        use FuelGroup::{Coal, NaturalGas, Wind};
+        // TX coal fell by 300, OK gas rose by 200 and NM barely moved; LA rose but borders TX only
+        let panel = [
+            year(2019, &[("TX", Coal, 1000.0), ("OK", NaturalGas, 500.0), ("NM", Coal, 400.0), ("LA", Coal, 100.0)]),
+            year(2020, &[("TX", Coal, 700.0), ("OK", NaturalGas, 700.0), ("NM", Coal, 401.0), ("LA", Coal, 500.0)]),
+        ];
+        let shifts = detect_generation_shifts(&panel);
+        let pairs: Vec<(&str, &str, f64)> =
+            shifts.iter().map(|s| (s.falling.as_str(), s.rising.as_str(), s.shift)).collect();
+        assert_eq!(pairs, [("TX", "LA", 300.0), ("TX", "OK", 200.0)]);
+        assert!((shifts[1].share - 0.2).abs() < 1e-12);
+        assert_eq!(shifts[1].rising_heat_rate, Some(10.0));
+
+        // Wind is not fossil generation
+        let panel = [year(2019, &[("TX", Wind, 1000.0)]), year(2020, &[("TX", Wind, 10.0)])];
+        assert!(detect_generation_shifts(&panel).is_empty());
+        assert!(format_top_shifts(&[], 10).starts_with("No bordering"));
+    }
+}
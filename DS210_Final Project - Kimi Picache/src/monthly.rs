@@ -0,0 +1,645 @@
+// monthly.rs
+// This module aggregates the monthly EIA-923 columns by state and derives month-based metrics such as the marginal heat rate.
+
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::cleaning::{CleanRecord, StateStats, MONTHS};
+use crate::stats::{linear_regression, LinearFit};
+
+/// Monthly fuel and generation totals for a state (January first).
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MonthlyStats {
+    /// Fuel consumed per month in MMBtu.
+    pub fuel: [f64; 12],
+
+    /// Net generation per month in MWh.
+    pub r#gen: [f64; 12],
+}
+
+/// Sums monthly fuel and generation by state.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// # Returns
+/// * `HashMap<String, MonthlyStats>` where the key is the state code
+pub fn aggregate_monthly_by_state(records: &[CleanRecord]) -> HashMap<String, MonthlyStats> {
+    let mut state_map: HashMap<String, MonthlyStats> = HashMap::new();
+
+    for record in records {
+        let entry = state_map.entry(record.state.clone()).or_default();
+        for month in 0..12 {
+            entry.fuel[month] += record.monthly_fuel[month];
+            entry.r#gen[month] += record.monthly_gen[month];
+        }
+    }
+
+    state_map
+}
+
+/// All states' fuel and generation for one month.
+#[derive(Debug, Serialize)]
+pub struct NationalMonth {
+    pub year: i32,
+
+    /// Month number, 1 = January.
+    pub month: usize,
+
+    /// Fuel consumed in MMBtu.
+    pub fuel: f64,
+
+    /// Net generation in MWh.
+    pub r#gen: f64,
+}
+
+impl NationalMonth {
+    /// Fuel used per MWh generated, or `None` without generation.
+    pub fn heat_rate(&self) -> Option<f64> {
+        (self.r#gen != 0.0).then(|| self.fuel / self.r#gen)
+    }
+}
+
+/// Sums every state's monthly totals into a national series.
+/// # Arguments
+/// * `years` - (year, monthly state totals) pairs, in the order the series should run
+/// # Returns
+/// * `Vec<NationalMonth>` with twelve entries per year
+pub fn aggregate_national_monthly(years: &[(i32, &HashMap<String, MonthlyStats>)]) -> Vec<NationalMonth> {
+    let mut output = Vec::new();
+
+    for (year, monthly) in years {
+        for month in 0..12 {
+            output.push(NationalMonth {
+                year: *year,
+                month: month + 1,
+                fuel: monthly.values().map(|s| s.fuel[month]).sum(),
+                r#gen: monthly.values().map(|s| s.r#gen[month]).sum(),
+            });
+        }
+    }
+
+    output
+}
+
+/// Writes the national monthly series to a CSV output file.
+pub fn write_national_monthly_csv(path: &str, data: &[NationalMonth]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Year", "Month", "Total_Fuel_MMBtu", "Net_Generation_MWh", "Heat_Rate"])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.year.to_string(),
+            MONTHS[item.month - 1],
+            &format!("{:.6}", item.fuel),
+            &format!("{:.6}", item.r#gen),
+            &format_optional(item.heat_rate()),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Average and marginal heat rates for a state in the base and compare inputs.
+#[derive(Debug, Serialize)]
+pub struct MarginalHeatRate {
+    /// State abbreviation (e.g., "CA", "TX").
+    pub state: String,
+
+    /// Average heat rate in the base input (total fuel / total generation).
+    pub avg_base: f64,
+
+    /// Marginal heat rate fit for the base input (slope of monthly fuel on monthly generation).
+    pub marginal_base: Option<LinearFit>,
+
+    /// Average heat rate in the compare input.
+    pub avg_compare: f64,
+
+    /// Marginal heat rate fit for the compare input.
+    pub marginal_compare: Option<LinearFit>,
+}
+
+/// Estimates a state's marginal heat rate as the regression slope ΔFuel/ΔGen across months.
+fn marginal_heat_rate(monthly: Option<&MonthlyStats>) -> Option<LinearFit> {
+    let monthly = monthly?;
+    linear_regression(&monthly.r#gen, &monthly.fuel)
+}
+
+/// Computes average and marginal heat rates per state for both inputs.
+/// # Arguments
+/// * `stats_base` / `stats_compare` - Annual state totals
+/// * `monthly_base` / `monthly_compare` - Monthly state totals
+/// # Returns
+/// * `Vec<MarginalHeatRate>` for states with generation in both inputs, sorted by state
+pub fn compute_marginal_heat_rates(
+    stats_base: &HashMap<String, StateStats>,
+    stats_compare: &HashMap<String, StateStats>,
+    monthly_base: &HashMap<String, MonthlyStats>,
+    monthly_compare: &HashMap<String, MonthlyStats>,
+) -> Vec<MarginalHeatRate> {
+    let mut output = Vec::new();
+
+    for (state, stat_base) in stats_base {
+        if let Some(stat_compare) = stats_compare.get(state) {
+            if stat_base.total_gen.0 == 0.0 || stat_compare.total_gen.0 == 0.0 {
+                continue;
+            }
+
+            output.push(MarginalHeatRate {
+                state: state.clone(),
+                avg_base: (stat_base.total_fuel / stat_base.total_gen).0,
+                marginal_base: marginal_heat_rate(monthly_base.get(state)),
+                avg_compare: (stat_compare.total_fuel / stat_compare.total_gen).0,
+                marginal_compare: marginal_heat_rate(monthly_compare.get(state)),
+            });
+        }
+    }
+
+    output.sort_by(|a, b| a.state.cmp(&b.state));
+    output
+}
+
+/// Formats an optional value for CSV output, leaving the cell empty when missing.
+pub fn format_optional(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.6}", v)).unwrap_or_default()
+}
+
+/// Writes the average vs marginal heat rate table to a CSV output file, naming the columns after the input labels.
+pub fn write_marginal_heat_rates_csv(
+    path: &str,
+    data: &[MarginalHeatRate],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let [a, b] = labels;
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State".to_string(),
+        format!("Avg_Heat_Rate_{a}"),
+        format!("Marginal_Heat_Rate_{a}"),
+        format!("Marginal_R2_{a}"),
+        format!("Avg_Heat_Rate_{b}"),
+        format!("Marginal_Heat_Rate_{b}"),
+        format!("Marginal_R2_{b}"),
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            &format!("{:.6}", item.avg_base),
+            &format_optional(item.marginal_base.map(|fit| fit.slope)),
+            &format_optional(item.marginal_base.map(|fit| fit.r_squared)),
+            &format!("{:.6}", item.avg_compare),
+            &format_optional(item.marginal_compare.map(|fit| fit.slope)),
+            &format_optional(item.marginal_compare.map(|fit| fit.r_squared)),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Writes a state × month series of fuel, generation and heat rate for each loaded year.
+/// # Arguments
+/// * `path` - Output CSV path
+/// * `years` - (year, monthly state totals) pairs in the order they should be written
+pub fn write_monthly_series_csv(
+    path: &str,
+    years: &[(i32, &HashMap<String, MonthlyStats>)],
+) -> Result<(), Box<dyn Error>> {
+    let states: BTreeSet<&String> = years.iter().flat_map(|(_, monthly)| monthly.keys()).collect();
+
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["State", "Year", "Month", "Total_Fuel_MMBtu", "Net_Generation_MWh", "Heat_Rate"])?;
+
+    for state in states {
+        for (year, monthly) in years {
+            let Some(stats) = monthly.get(state) else {
+                continue;
+            };
+
+            for (month, name) in MONTHS.iter().enumerate() {
+                let heat_rate = (stats.r#gen[month] != 0.0).then(|| stats.fuel[month] / stats.r#gen[month]);
+                wtr.write_record([
+                    state.as_str(),
+                    &year.to_string(),
+                    name,
+                    &format!("{:.6}", stats.fuel[month]),
+                    &format!("{:.6}", stats.r#gen[month]),
+                    &format_optional(heat_rate),
+                ])?;
+            }
+        }
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Month indices (January = 0) of the summer peak season, June–September.
+const SUMMER_MONTHS: [usize; 4] = [5, 6, 7, 8];
+
+/// Month indices of the winter season, December–March (same calendar year).
+const WINTER_MONTHS: [usize; 4] = [11, 0, 1, 2];
+
+/// Summer and winter heat rates for a state in the base and compare inputs.
+#[derive(Debug)]
+pub struct SeasonalHeatRate {
+    pub state: String,
+    pub summer_base: Option<f64>,
+    pub summer_compare: Option<f64>,
+    pub winter_base: Option<f64>,
+    pub winter_compare: Option<f64>,
+}
+
+/// Heat rate over a set of months, or `None` without generation in those months.
+fn season_heat_rate(monthly: Option<&MonthlyStats>, months: &[usize]) -> Option<f64> {
+    let monthly = monthly?;
+    let fuel: f64 = months.iter().map(|&m| monthly.fuel[m]).sum();
+    let r#gen: f64 = months.iter().map(|&m| monthly.r#gen[m]).sum();
+    (r#gen != 0.0).then(|| fuel / r#gen)
+}
+
+/// Computes summer (June–September) and winter (December–March) heat rates per state.
+/// # Arguments
+/// * `monthly_base` / `monthly_compare` - Monthly state totals
+/// # Returns
+/// * `Vec<SeasonalHeatRate>` for states present in both inputs, sorted by state
+pub fn compute_seasonal_heat_rates(
+    monthly_base: &HashMap<String, MonthlyStats>,
+    monthly_compare: &HashMap<String, MonthlyStats>,
+) -> Vec<SeasonalHeatRate> {
+    let mut output: Vec<SeasonalHeatRate> = monthly_base
+        .keys()
+        .filter(|state| monthly_compare.contains_key(*state))
+        .map(|state| SeasonalHeatRate {
+            state: state.clone(),
+            summer_base: season_heat_rate(monthly_base.get(state), &SUMMER_MONTHS),
+            summer_compare: season_heat_rate(monthly_compare.get(state), &SUMMER_MONTHS),
+            winter_base: season_heat_rate(monthly_base.get(state), &WINTER_MONTHS),
+            winter_compare: season_heat_rate(monthly_compare.get(state), &WINTER_MONTHS),
+        })
+        .collect();
+
+    output.sort_by(|a, b| a.state.cmp(&b.state));
+    output
+}
+
+/// Writes the summer vs winter heat rate table, including the changes between the inputs, to a CSV output file,
+/// naming the columns after the input labels.
+pub fn write_seasonal_heat_rates_csv(
+    path: &str,
+    data: &[SeasonalHeatRate],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let [a, b] = labels;
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State".to_string(),
+        format!("Summer_{a}"),
+        format!("Summer_{b}"),
+        "Summer_Change".to_string(),
+        format!("Winter_{a}"),
+        format!("Winter_{b}"),
+        "Winter_Change".to_string(),
+    ])?;
+
+    for item in data {
+        let summer_change = item.summer_base.zip(item.summer_compare).map(|(a, b)| b - a);
+        let winter_change = item.winter_base.zip(item.winter_compare).map(|(a, b)| b - a);
+        wtr.write_record([
+            &item.state,
+            &format_optional(item.summer_base),
+            &format_optional(item.summer_compare),
+            &format_optional(summer_change),
+            &format_optional(item.winter_base),
+            &format_optional(item.winter_compare),
+            &format_optional(winter_change),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Elasticity of heat rate with respect to generation for a state.
+#[derive(Debug)]
+pub struct HeatRateElasticity {
+    pub state: String,
+
+    /// Log-log fit of monthly heat rate on monthly generation in the base input.
+    pub fit_base: Option<LinearFit>,
+
+    /// Log-log fit for the compare input.
+    pub fit_compare: Option<LinearFit>,
+
+    /// Log-log fit over the months of both inputs together.
+    pub fit_pooled: Option<LinearFit>,
+}
+
+/// Collects (ln generation, ln heat rate) points for months with positive fuel and generation.
+fn log_points(monthly: Option<&MonthlyStats>, xs: &mut Vec<f64>, ys: &mut Vec<f64>) {
+    let Some(monthly) = monthly else {
+        return;
+    };
+
+    for month in 0..12 {
+        if monthly.r#gen[month] > 0.0 && monthly.fuel[month] > 0.0 {
+            xs.push(monthly.r#gen[month].ln());
+            ys.push((monthly.fuel[month] / monthly.r#gen[month]).ln());
+        }
+    }
+}
+
+/// Log-log regression of heat rate on generation over the given years' months.
+fn elasticity_fit(months: &[Option<&MonthlyStats>]) -> Option<LinearFit> {
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    for monthly in months {
+        log_points(*monthly, &mut xs, &mut ys);
+    }
+    linear_regression(&xs, &ys)
+}
+
+/// Estimates how each state's heat rate responds to its generation level: the slope of
+/// ln(heat rate) on ln(generation) across months. A negative value means the fleet runs more
+/// efficiently at higher output, so part of a year-over-year change can be load-driven.
+/// # Arguments
+/// * `monthly_base` / `monthly_compare` - Monthly state totals
+/// # Returns
+/// * `Vec<HeatRateElasticity>` for states present in both inputs, sorted by state
+pub fn compute_elasticities(
+    monthly_base: &HashMap<String, MonthlyStats>,
+    monthly_compare: &HashMap<String, MonthlyStats>,
+) -> Vec<HeatRateElasticity> {
+    let mut output: Vec<HeatRateElasticity> = monthly_base
+        .keys()
+        .filter(|state| monthly_compare.contains_key(*state))
+        .map(|state| {
+            let a = monthly_base.get(state);
+            let b = monthly_compare.get(state);
+            HeatRateElasticity {
+                state: state.clone(),
+                fit_base: elasticity_fit(&[a]),
+                fit_compare: elasticity_fit(&[b]),
+                fit_pooled: elasticity_fit(&[a, b]),
+            }
+        })
+        .collect();
+
+    output.sort_by(|a, b| a.state.cmp(&b.state));
+    output
+}
+
+/// Writes the heat rate elasticities to a CSV output file, naming the columns after the input labels.
+pub fn write_elasticities_csv(
+    path: &str,
+    data: &[HeatRateElasticity],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let [a, b] = labels;
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State".to_string(),
+        format!("Elasticity_{a}"),
+        format!("R2_{a}"),
+        format!("Elasticity_{b}"),
+        format!("R2_{b}"),
+        "Elasticity_Pooled".to_string(),
+        "R2_Pooled".to_string(),
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            &format_optional(item.fit_base.map(|fit| fit.slope)),
+            &format_optional(item.fit_base.map(|fit| fit.r_squared)),
+            &format_optional(item.fit_compare.map(|fit| fit.slope)),
+            &format_optional(item.fit_compare.map(|fit| fit.r_squared)),
+            &format_optional(item.fit_pooled.map(|fit| fit.slope)),
+            &format_optional(item.fit_pooled.map(|fit| fit.r_squared)),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// A state's most and least efficient month of one year.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtremeMonths {
+    pub state: String,
+    pub year: i32,
+
+    /// Month index (January = 0) and heat rate (MMBtu/MWh) of the lowest monthly heat rate.
+    pub best: (usize, f64),
+
+    /// Month index and heat rate of the highest monthly heat rate.
+    pub worst: (usize, f64),
+}
+
+/// Finds each state's best and worst month by heat rate in every year; months without positive generation are
+/// skipped.
+/// # Arguments
+/// * `years` - (year, monthly state totals) pairs
+/// # Returns
+/// * `Vec<ExtremeMonths>` sorted by state, then in the order of `years`
+pub fn find_extreme_months(years: &[(i32, &HashMap<String, MonthlyStats>)]) -> Vec<ExtremeMonths> {
+    let states: BTreeSet<&String> = years.iter().flat_map(|(_, monthly)| monthly.keys()).collect();
+
+    let mut output = Vec::new();
+    for state in states {
+        for (year, monthly) in years {
+            let Some(stats) = monthly.get(state) else {
+                continue;
+            };
+            let heat_rates: Vec<(usize, f64)> =
+                (0..12).filter(|&m| stats.r#gen[m] > 0.0).map(|m| (m, stats.fuel[m] / stats.r#gen[m])).collect();
+            let best = heat_rates.iter().copied().min_by(|a, b| a.1.total_cmp(&b.1));
+            let worst = heat_rates.iter().copied().max_by(|a, b| a.1.total_cmp(&b.1));
+            if let (Some(best), Some(worst)) = (best, worst) {
+                output.push(ExtremeMonths { state: state.clone(), year: *year, best, worst });
+            }
+        }
+    }
+    output
+}
+
+/// Writes each state's best and worst month per year, with the spread between them, to a CSV output file.
+pub fn write_extreme_months_csv(path: &str, data: &[ExtremeMonths]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["State", "Year", "Best_Month", "Best_Heat_Rate", "Worst_Month", "Worst_Heat_Rate", "Spread"])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            &item.year.to_string(),
+            MONTHS[item.best.0],
+            &format!("{:.6}", item.best.1),
+            MONTHS[item.worst.0],
+            &format!("{:.6}", item.worst.1),
+            &format!("{:.6}", item.worst.1 - item.best.1),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// How a state's heat rate in its highest-generation month compares with its annual heat rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeakPenalty {
+    pub state: String,
+    pub year: i32,
+
+    /// Month index (January = 0) with the most net generation.
+    pub peak_month: usize,
+    pub peak_gen: f64,
+
+    /// Heat rate in the peak month and over the whole year (MMBtu/MWh).
+    pub peak_heat_rate: f64,
+    pub annual_heat_rate: f64,
+}
+
+impl PeakPenalty {
+    /// Peak month heat rate minus the annual heat rate; positive when the fleet runs less efficiently at peak.
+    pub fn penalty(&self) -> f64 {
+        self.peak_heat_rate - self.annual_heat_rate
+    }
+
+    /// The penalty as a percentage of the annual heat rate.
+    pub fn penalty_percent(&self) -> f64 {
+        self.penalty() / self.annual_heat_rate * 100.0
+    }
+}
+
+/// Computes each state's peak penalty in every year: the heat rate in its highest-generation month against its
+/// annual heat rate. States without positive annual or peak-month generation are skipped.
+/// # Arguments
+/// * `years` - (year, monthly state totals) pairs
+/// # Returns
+/// * `Vec<PeakPenalty>` sorted by state, then in the order of `years`
+pub fn compute_peak_penalties(years: &[(i32, &HashMap<String, MonthlyStats>)]) -> Vec<PeakPenalty> {
+    let states: BTreeSet<&String> = years.iter().flat_map(|(_, monthly)| monthly.keys()).collect();
+
+    let mut output = Vec::new();
+    for state in states {
+        for (year, monthly) in years {
+            let Some(stats) = monthly.get(state) else {
+                continue;
+            };
+            let annual_gen: f64 = stats.r#gen.iter().sum();
+            let peak_month = (0..12).max_by(|&a, &b| stats.r#gen[a].total_cmp(&stats.r#gen[b])).unwrap_or(0);
+            if annual_gen <= 0.0 || stats.r#gen[peak_month] <= 0.0 {
+                continue;
+            }
+            output.push(PeakPenalty {
+                state: state.clone(),
+                year: *year,
+                peak_month,
+                peak_gen: stats.r#gen[peak_month],
+                peak_heat_rate: stats.fuel[peak_month] / stats.r#gen[peak_month],
+                annual_heat_rate: stats.fuel.iter().sum::<f64>() / annual_gen,
+            });
+        }
+    }
+    output
+}
+
+/// Writes the peak penalty table to a CSV output file.
+pub fn write_peak_penalties_csv(path: &str, data: &[PeakPenalty]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State", "Year", "Peak_Month", "Peak_Generation_MWh", "Peak_Heat_Rate", "Annual_Heat_Rate", "Peak_Penalty",
+        "Peak_Penalty_Percent",
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.state,
+            &item.year.to_string(),
+            MONTHS[item.peak_month],
+            &format!("{:.6}", item.peak_gen),
+            &format!("{:.6}", item.peak_heat_rate),
+            &format!("{:.6}", item.annual_heat_rate),
+            &format!("{:.6}", item.penalty()),
+            &format!("{:.6}", item.penalty_percent()),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cleaning::fixtures::record;
+    use crate::quantity::{MegawattHours, Mmbtu};
+
+    /// A state whose fuel is 100 MMBtu plus 10 MMBtu per MWh each month, generating (month + 1) × 10 MWh.
+    fn linear_state() -> MonthlyStats {
+        let r#gen: [f64; 12] = std::array::from_fn(|m| (m + 1) as f64 * 10.0);
+        MonthlyStats { fuel: r#gen.map(|g| 100.0 + 10.0 * g), r#gen }
+    }
+
+    #[test]
+    fn test_aggregate_monthly() {
+        let mut monthly_gen = [0.0; 12];
+        monthly_gen[6] = 5.0;
+        let row = |fuel: f64| CleanRecord {
+            monthly_fuel: [fuel; 12],
+            monthly_gen,
+            ..record("1", "AA", "NG", 0.0, 0.0)
+        };
+        let monthly = aggregate_monthly_by_state(&[row(1.0), row(2.0)]);
+        assert_eq!((monthly["AA"].fuel[0], monthly["AA"].r#gen[6]), (3.0, 10.0));
+
+        let year = HashMap::from([("AA".to_string(), linear_state())]);
+        let national = aggregate_national_monthly(&[(2019, &year), (2020, &year)]);
+        assert_eq!(national.len(), 24);
+        assert_eq!((national[12].year, national[12].month, national[12].heat_rate()), (2020, 1, Some(20.0)));
+    }
+
+    #[test]
+    fn test_marginal_and_seasonal_heat_rates() {
+        let year = HashMap::from([("AA".to_string(), linear_state())]);
+        // The marginal heat rate is the slope of fuel on generation, below the average heat rate
+        let annual = |m: &MonthlyStats| StateStats {
+            total_fuel: Mmbtu(m.fuel.iter().sum()),
+            total_gen: MegawattHours(m.r#gen.iter().sum()),
+        };
+        let stats = HashMap::from([("AA".to_string(), annual(&year["AA"]))]);
+        let marginal = compute_marginal_heat_rates(&stats, &stats, &year, &year);
+        let fit = marginal[0].marginal_base.as_ref().unwrap();
+        assert!((fit.slope - 10.0).abs() < 1e-9 && (fit.intercept - 100.0).abs() < 1e-9);
+        assert!(marginal[0].avg_base > fit.slope);
+
+        let seasonal = compute_seasonal_heat_rates(&year, &year);
+        assert_eq!(seasonal[0].summer_base, Some((4 * 100 + 10 * 300) as f64 / 300.0));
+    }
+
+    #[test]
+    fn test_extreme_months_and_peak_penalty() {
+        let year = HashMap::from([("AA".to_string(), linear_state())]);
+        // The fixed cost spreads over more generation late in the year, so December is best and January worst
+        let extremes = find_extreme_months(&[(2019, &year)]);
+        assert_eq!((extremes[0].best.0, extremes[0].worst), (11, (0, 20.0)));
+        let penalty = &compute_peak_penalties(&[(2019, &year)])[0];
+        assert_eq!((penalty.peak_month, penalty.peak_heat_rate), (11, 100.0 / 120.0 + 10.0));
+        assert!(penalty.penalty() < 0.0);
+    }
+
+    #[test]
+    fn test_elasticities() {
+        // Heat rate falling as generation grows gives a negative elasticity; a state without months has no fit
+        let year = HashMap::from([("AA".to_string(), linear_state()), ("BB".to_string(), MonthlyStats::default())]);
+        let elasticities = compute_elasticities(&year, &year);
+        assert!(elasticities[0].fit_pooled.as_ref().unwrap().slope < 0.0);
+        assert!(elasticities[1].fit_base.is_none());
+        assert_eq!(format_optional(None), "");
+    }
+}
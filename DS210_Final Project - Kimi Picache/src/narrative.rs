@@ -0,0 +1,181 @@
+// narrative.rs
+// This module turns the decomposition of each top mover's change into a few sentences for reports: how far the
+// state's heat rate moved, the plants that drove it (from the plant contributions), how much came from plant
+// heat rates versus generation shifting between plants, and any marked shift in the fuel mix.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::analysis::{split_movers, StateEfficiency};
+use crate::cleaning::CleanRecord;
+use crate::contributions::{compute_plant_contributions, PlantContribution};
+use crate::fuels::FuelGroup;
+use crate::summary::summarize_states;
+use crate::units::HeatRateUnit;
+
+/// Smallest change in a fuel group's generation share (0-1) worth mentioning.
+pub const MIX_SHIFT_THRESHOLD: f64 = 0.02;
+
+/// Plants named as drivers of a change.
+const DRIVERS: usize = 2;
+
+/// The narrative of one top mover.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateNarrative {
+    pub state: String,
+
+    /// Whether the state is among the improvers (heat rate fell) rather than the regressors.
+    pub improved: bool,
+
+    pub text: String,
+}
+
+/// Writes the narrative of one state.
+/// # Arguments
+/// * `change` - The state's efficiency row (MMBtu/MWh)
+/// * `plants` - The state's plant contributions, largest first
+/// * `mix` - The state's generation share by fuel group in both years
+/// * `labels` - Names of the two inputs
+/// * `unit` - Unit the heat rates are written in
+pub fn narrate_state(
+    change: &StateEfficiency,
+    plants: &[&PlantContribution],
+    mix: [&BTreeMap<FuelGroup, f64>; 2],
+    labels: [&str; 2],
+    unit: HeatRateUnit,
+) -> String {
+    let verb = if change.delta < 0.0 { "improved" } else { "worsened" };
+    let percent = (change.eff_2019 != 0.0).then(|| change.delta / change.eff_2019 * 100.0);
+    let mut text = format!(
+        "{} {}{}: its heat rate went from {:.3} to {:.3} {} ({} → {}).",
+        change.state,
+        verb,
+        percent.map_or(String::new(), |p| format!(" by {:.1}%", p.abs())),
+        unit.convert(change.eff_2019),
+        unit.convert(change.eff_2020),
+        unit.label(),
+        labels[0],
+        labels[1]
+    );
+
+    let drivers: Vec<String> = plants
+        .iter()
+        .filter(|p| p.contribution * change.delta > 0.0)
+        .take(DRIVERS)
+        .map(|p| format!("{} ({:+.3})", p.plant_name, unit.convert(p.contribution)))
+        .collect();
+    if !drivers.is_empty() {
+        text += &format!(" The change was driven mainly by {}.", drivers.join(" and "));
+    }
+    if !plants.is_empty() {
+        let rate: f64 = plants.iter().map(|p| p.rate_effect).sum();
+        let shift: f64 = plants.iter().map(|p| p.mix_effect).sum();
+        let main = if rate.abs() >= shift.abs() { "plant heat rates" } else { "generation shifting between plants" };
+        text += &format!(
+            " It came mostly from {} ({:+.3} from heat rates, {:+.3} from shifts in generation).",
+            main,
+            unit.convert(rate),
+            unit.convert(shift)
+        );
+    }
+
+    let shifts: Vec<(FuelGroup, f64, f64)> = mix[0]
+        .keys()
+        .chain(mix[1].keys())
+        .map(|group| (*group, mix[0].get(group).copied().unwrap_or(0.0), mix[1].get(group).copied().unwrap_or(0.0)))
+        .collect();
+    let by_change = |a: &&(FuelGroup, f64, f64), b: &&(FuelGroup, f64, f64)| (a.2 - a.1).total_cmp(&(b.2 - b.1));
+    let share = |(group, a, b): &(FuelGroup, f64, f64)| {
+        format!("{} ({:.1}% → {:.1}%)", group.label().to_lowercase(), a * 100.0, b * 100.0)
+    };
+    if let (Some(from), Some(to)) = (shifts.iter().min_by(by_change), shifts.iter().max_by(by_change))
+        && from.1 - from.2 >= MIX_SHIFT_THRESHOLD
+        && to.2 - to.1 >= MIX_SHIFT_THRESHOLD
+    {
+        text += &format!(" Generation shifted from {} to {}.", share(from), share(to));
+    }
+    text
+}
+
+/// Writes the narratives of the top movers, in the order of the top movers table.
+/// # Arguments
+/// * `records` - Cleaned rows of the two inputs
+/// * `changes` - The efficiency table (MMBtu/MWh), sorted like the top movers table
+/// * `top_n` - Improvers and regressors narrated
+/// * `labels` - Names of the two inputs
+/// * `unit` - Unit the heat rates are written in
+pub fn narrate_top_movers(
+    records: [&[CleanRecord]; 2],
+    changes: &[StateEfficiency],
+    top_n: usize,
+    labels: [&str; 2],
+    unit: HeatRateUnit,
+) -> Vec<StateNarrative> {
+    let contributions = compute_plant_contributions(records[0], records[1]);
+    let mut plants: HashMap<&str, Vec<&PlantContribution>> = HashMap::new();
+    for contribution in &contributions {
+        plants.entry(&contribution.state).or_default().push(contribution);
+    }
+    let mixes = records.map(|year| {
+        summarize_states(year).into_iter().map(|s| (s.state, s.fuel_mix)).collect::<HashMap<_, _>>()
+    });
+
+    let empty = BTreeMap::new();
+    let (improvers, regressors) = split_movers(changes, top_n);
+    improvers
+        .iter()
+        .chain(&regressors)
+        .map(|change| {
+            let mix = [0, 1].map(|i| mixes[i].get(&change.state).unwrap_or(&empty));
+            let state_plants = plants.get(change.state.as_str()).map_or(&[][..], Vec::as_slice);
+            StateNarrative {
+                state: change.state.clone(),
+                improved: change.delta < 0.0,
+                text: narrate_state(change, state_plants, mix, labels, unit),
+            }
+        })
+        .collect()
+}
+
+/// Formats the narratives as a Markdown document with an improvers and a regressors section.
+pub fn format_narratives_markdown(narratives: &[StateNarrative], labels: [&str; 2]) -> String {
+    let mut out = format!("# Top movers, {} → {}\n", labels[0], labels[1]);
+    for (title, improved) in [("Improvers (heat rate fell)", true), ("Regressors (heat rate rose)", false)] {
+        out += &format!("\n## {}\n", title);
+        let section: Vec<&StateNarrative> = narratives.iter().filter(|n| n.improved == improved).collect();
+        if section.is_empty() {
+            out += "\nNone.\n";
+        }
+        for narrative in section {
+            out += &format!("\n**{}.** {}\n", narrative.state, narrative.text);
+        }
+    }
+    out
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::compute_efficiency_changes;
+    use crate::cleaning::aggregate_by_state;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_narrate_top_movers() {
+        // The coal plant runs less and the gas plant more and a little better: the heat rate falls from 10 to 9.35
+        let base = [record("1", "TX", "COL", 1100.0, 100.0), record("2", "TX", "NG", 900.0, 100.0)];
+        let compare = [record("1", "TX", "COL", 550.0, 50.0), record("2", "TX", "NG", 1320.0, 150.0)];
+        let changes = compute_efficiency_changes(&aggregate_by_state(&base), &aggregate_by_state(&compare));
+        let narratives = narrate_top_movers([&base, &compare], &changes, 10, ["2019", "2020"], HeatRateUnit::MmbtuPerMwh);
+
+        assert_eq!(narratives.len(), 1);
+        let text = &narratives[0].text;
+        assert!(narratives[0].improved);
+        assert!(text.starts_with("TX improved by 6.5%: its heat rate went from 10.000 to 9.350 MMBtu/MWh"), "{}", text);
+        assert!(text.contains("driven mainly by Plant 1 (-2.750). It came mostly from generation shifting"), "{}", text);
+        assert!(text.contains("Generation shifted from coal (50.0% → 25.0%) to natural gas (50.0% → 75.0%)."));
+
+        let markdown = format_narratives_markdown(&narratives, ["2019", "2020"]);
+        assert!(markdown.contains("**TX.** TX improved") && markdown.contains("## Regressors (heat rate rose)\n\nNone."));
+    }
+}
@@ -0,0 +1,166 @@
+// objectstore.rs
+// This module reads raw inputs from and writes result files to S3 object storage (`s3://bucket/key`) through
+// the object_store crate. An `s3://` input is fetched into the download folder of the cache like a URL input
+// and read from there; `--upload-to s3://bucket/prefix` copies every output file of a run under the prefix once
+// the run finishes. Credentials, region and endpoint (for S3-compatible stores) come from the standard
+// AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY, AWS_SESSION_TOKEN, AWS_REGION and AWS_ENDPOINT variables. Without the
+// `s3` feature, `s3://` paths are refused with an error naming it.
+
+use std::error::Error;
+use std::path::Path;
+
+/// Scheme of object storage paths.
+pub const S3_SCHEME: &str = "s3://";
+
+/// Whether a path names an object in S3.
+pub fn is_object_url(path: &str) -> bool {
+    path.starts_with(S3_SCHEME)
+}
+
+/// A bucket and the key (or key prefix) in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectUrl {
+    pub bucket: String,
+    pub key: String,
+}
+
+impl ObjectUrl {
+    /// Parses `s3://bucket/key`; the key may be empty (the bucket's root).
+    pub fn parse(url: &str) -> Result<ObjectUrl, String> {
+        let rest = url.strip_prefix(S3_SCHEME).ok_or_else(|| format!("'{}' is not an s3:// URL", url))?;
+        let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(format!("'{}' names no bucket (expected s3://bucket/key)", url));
+        }
+        Ok(ObjectUrl { bucket: bucket.to_string(), key: key.trim_matches('/').to_string() })
+    }
+
+    /// Key of a local output file under this prefix, keeping its relative directories
+    /// (`cleaned/State=TX/part-00000.csv`).
+    pub fn child_key(&self, file: &str) -> String {
+        let relative = file.trim_start_matches("./").trim_start_matches('/');
+        match self.key.is_empty() {
+            true => relative.to_string(),
+            false => format!("{}/{}", self.key, relative),
+        }
+    }
+}
+
+#[cfg(feature = "s3")]
+mod store {
+    use std::error::Error;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::Path;
+
+    use futures::StreamExt;
+    use object_store::aws::{AmazonS3, AmazonS3Builder};
+    use object_store::path::Path as ObjectPath;
+    use object_store::{ObjectStore, PutPayload};
+    use tokio::runtime::{Builder, Runtime};
+
+    use super::ObjectUrl;
+
+    /// The bucket's store, configured from the AWS environment variables, and a runtime to drive it.
+    fn open(url: &ObjectUrl) -> Result<(AmazonS3, Runtime), Box<dyn Error>> {
+        let store = AmazonS3Builder::from_env().with_bucket_name(&url.bucket).build()?;
+        Ok((store, Builder::new_current_thread().enable_all().build()?))
+    }
+
+    /// Streams an object to `target` through a `.part` file, so a failed fetch is never mistaken for a
+    /// finished one.
+    pub fn fetch(url: &ObjectUrl, target: &Path) -> Result<(), Box<dyn Error>> {
+        let (store, runtime) = open(url)?;
+        let partial = target.with_extension("part");
+        runtime.block_on(async {
+            let mut stream = store.get(&ObjectPath::from(url.key.as_str())).await?.into_stream();
+            let mut file = File::create(&partial)?;
+            while let Some(chunk) = stream.next().await {
+                file.write_all(&chunk?)?;
+            }
+            Ok::<(), Box<dyn Error>>(())
+        })?;
+        fs::rename(partial, target)?;
+        Ok(())
+    }
+
+    /// Uploads local files under the prefix.
+    pub fn upload(prefix: &ObjectUrl, files: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+        let (store, runtime) = open(prefix)?;
+        let mut uploaded = Vec::new();
+        for file in files {
+            let key = prefix.child_key(file);
+            let payload = PutPayload::from(fs::read(file)?);
+            runtime.block_on(store.put(&ObjectPath::from(key.as_str()), payload))?;
+            uploaded.push(format!("{}{}/{}", super::S3_SCHEME, prefix.bucket, key));
+        }
+        Ok(uploaded)
+    }
+}
+
+/// Without the `s3` feature object storage is refused.
+#[cfg(not(feature = "s3"))]
+mod store {
+    use std::error::Error;
+    use std::path::Path;
+
+    use super::ObjectUrl;
+
+    pub fn fetch(_url: &ObjectUrl, _target: &Path) -> Result<(), Box<dyn Error>> {
+        Err("S3 input needs the 's3' feature".into())
+    }
+
+    pub fn upload(_prefix: &ObjectUrl, _files: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+        Err("S3 output needs the 's3' feature".into())
+    }
+}
+
+/// Fetches an object to a local file.
+/// # Arguments
+/// * `url` - `s3://bucket/key` of the object
+/// * `target` - Local file to write
+pub fn fetch_object(url: &str, target: &Path) -> Result<(), Box<dyn Error>> {
+    let object = ObjectUrl::parse(url)?;
+    println!("Downloading '{}'...", url);
+    store::fetch(&object, target).map_err(|e| format!("download of '{}' failed: {}", url, e).into())
+}
+
+/// Checks an upload prefix before a run, so a bad prefix or a build without the `s3` feature fails at once rather
+/// than after the run.
+pub fn check_upload_prefix(prefix: &str) -> Result<(), Box<dyn Error>> {
+    ObjectUrl::parse(prefix)?;
+    match cfg!(feature = "s3") {
+        true => Ok(()),
+        false => Err(format!("'{}': S3 output needs the 's3' feature", prefix).into()),
+    }
+}
+
+/// Uploads output files under an S3 prefix, each at its path relative to the working directory.
+/// # Arguments
+/// * `prefix` - `s3://bucket/prefix` to upload under
+/// * `files` - Local files to upload
+/// # Returns
+/// * The `s3://` URL of every uploaded file, in order
+pub fn upload_files(prefix: &str, files: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+    let object = ObjectUrl::parse(prefix)?;
+    store::upload(&object, files).map_err(|e| format!("upload to '{}' failed: {}", prefix, e).into())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_url() {
+        let url = ObjectUrl::parse("s3://eia-data/raw/2020.csv").unwrap();
+        assert_eq!((url.bucket.as_str(), url.key.as_str()), ("eia-data", "raw/2020.csv"));
+        assert!(is_object_url("s3://eia-data/raw/2020.csv") && !is_object_url("https://eia.gov/2020.csv"));
+        assert!(ObjectUrl::parse("s3:///2020.csv").is_err());
+
+        let prefix = ObjectUrl::parse("s3://results/runs/2024/").unwrap();
+        let key = prefix.child_key("./cleaned/State=TX/part-00000.csv");
+        assert_eq!(key, "runs/2024/cleaned/State=TX/part-00000.csv");
+        assert_eq!(ObjectUrl::parse("s3://results").unwrap().child_key("alerts.csv"), "alerts.csv");
+    }
+}
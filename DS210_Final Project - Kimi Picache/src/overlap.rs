@@ -0,0 +1,101 @@
+// overlap.rs
+// This module measures how much the top of the state efficiency ranking churns between two years. Spearman
+// correlation weighs a swap at the bottom of the table as much as one at the top; rank-biased overlap (Webber,
+// Moffat and Zobel, 2010) compares only the top-k lists and weighs agreement near the top most.
+
+use serde::Serialize;
+
+use crate::analysis::StateEfficiency;
+use crate::stats::spearman;
+
+/// RBO persistence: the chance a reader goes on from one rank to the next. At 0.9 the first 10 ranks carry
+/// about 86% of the weight.
+pub const RBO_PERSISTENCE: f64 = 0.9;
+
+/// Agreement between the two years' state efficiency rankings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct RankingAgreement {
+    /// Length of the top lists compared by `rbo`.
+    pub depth: usize,
+
+    /// Spearman correlation of the full rankings, or `None` with fewer than two states.
+    pub spearman: Option<f64>,
+
+    /// Extrapolated rank-biased overlap of the top-`depth` lists (0 = disjoint, 1 = identical).
+    pub rbo: f64,
+
+    /// States in both top-`depth` lists.
+    pub shared: usize,
+}
+
+/// Extrapolated rank-biased overlap (RBO_EXT) of two rankings cut at `depth`.
+/// # Arguments
+/// * `a`, `b` - Rankings, best first; only the first `depth` entries are read
+/// * `depth` - Evaluation depth k
+/// * `p` - Persistence, in (0, 1)
+/// # Returns
+/// * 1 for identical top lists, 0 for disjoint ones
+pub fn rank_biased_overlap<T: PartialEq>(a: &[T], b: &[T], depth: usize, p: f64) -> f64 {
+    let depth = depth.min(a.len()).min(b.len());
+    if depth == 0 {
+        return 0.0;
+    }
+
+    let mut weighted = 0.0;
+    let mut overlap = 0;
+    for d in 1..=depth {
+        // Items newly seen at depth d on one side that the other side already had (or has at d)
+        if a[..d - 1].contains(&b[d - 1]) {
+            overlap += 1;
+        }
+        if b[..d].contains(&a[d - 1]) {
+            overlap += 1;
+        }
+        weighted += overlap as f64 / d as f64 * p.powi(d as i32);
+    }
+    overlap as f64 / depth as f64 * p.powi(depth as i32) + (1.0 - p) / p * weighted
+}
+
+/// Compares the states' efficiency rankings (most efficient first) of the two years.
+/// # Arguments
+/// * `changes` - Efficiency changes in any order
+/// * `depth` - Top-list length for the rank-biased overlap
+pub fn year_ranking_agreement(changes: &[StateEfficiency], depth: usize) -> RankingAgreement {
+    let ranking = |key: fn(&StateEfficiency) -> f64| {
+        let mut sorted: Vec<&StateEfficiency> = changes.iter().collect();
+        sorted.sort_by(|x, y| key(x).total_cmp(&key(y)).then_with(|| x.state.cmp(&y.state)));
+        sorted.into_iter().map(|c| c.state.as_str()).collect::<Vec<&str>>()
+    };
+    let (base, compare) = (ranking(|c| c.eff_2019), ranking(|c| c.eff_2020));
+
+    let eff_2019: Vec<f64> = changes.iter().map(|c| c.eff_2019).collect();
+    let eff_2020: Vec<f64> = changes.iter().map(|c| c.eff_2020).collect();
+    let k = depth.min(changes.len());
+    RankingAgreement {
+        depth: k,
+        spearman: spearman(&eff_2019, &eff_2020),
+        rbo: rank_biased_overlap(&base, &compare, k, RBO_PERSISTENCE),
+        shared: base[..k].iter().filter(|state| compare[..k].contains(state)).count(),
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_biased_overlap() {
+        let ranking = ["A", "B", "C", "D", "E"];
+        assert!((rank_biased_overlap(&ranking, &ranking, 5, 0.9) - 1.0).abs() < 1e-12);
+        assert_eq!(rank_biased_overlap(&ranking, &["F", "G", "H", "I", "J"], 5, 0.9), 0.0);
+
+        // The same swap costs more at the top than at the bottom of the list
+        let top_swap = rank_biased_overlap(&ranking, &["B", "A", "C", "D", "E"], 5, 0.9);
+        let bottom_swap = rank_biased_overlap(&ranking, &["A", "B", "C", "E", "D"], 5, 0.9);
+        assert!(top_swap < bottom_swap && bottom_swap < 1.0);
+
+        // k = 1: agreement on the leader only
+        assert!((rank_biased_overlap(&["A", "B"], &["A", "C"], 1, 0.9) - 1.0).abs() < 1e-12);
+    }
+}
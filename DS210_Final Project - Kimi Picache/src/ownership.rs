@@ -0,0 +1,170 @@
+// ownership.rs
+// This module breaks each state's efficiency out by the ownership type of the plants' utilities (investor-owned,
+// municipal, cooperative, federal, ...), from EIA-860: the generator schedule links a plant to its utility and
+// the utility schedule gives the utility's entity type.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+
+use csv::WriterBuilder;
+
+use crate::cleaning::{CleanRecord, StateStats};
+use crate::eia860::{Generator, OwnershipType};
+use crate::join::{JoinKey, JoinReport, JoinType, KeyIndex};
+use crate::monthly::format_optional;
+use crate::quantity::{MegawattHours, Mmbtu};
+
+/// Ownership type of each plant, keyed by normalized plant code: that of the utility of its first listed
+/// generator. In a left join, plants whose utility is not in `utility_types` are `Unknown`; in an inner join
+/// their generators are skipped.
+/// # Returns
+/// * The ownership types, and the report of the utility join
+pub fn plant_ownership(
+    generators: &[Generator],
+    utility_types: &HashMap<String, OwnershipType>,
+    join_type: JoinType,
+) -> (HashMap<String, OwnershipType>, JoinReport) {
+    let utilities: Vec<(&String, &OwnershipType)> = utility_types.iter().collect();
+    let index = KeyIndex::new(JoinKey::UtilityId, &utilities, |(id, _)| id);
+    let report = index.report("EIA-860 utilities", generators.iter().map(|g| g.utility_id.as_str()), join_type);
+
+    let mut ownership = HashMap::new();
+    for generator in generators.iter().filter(|g| report.keeps(&g.utility_id)) {
+        ownership.entry(JoinKey::PlantId.normalize(&generator.plant_id)).or_insert_with(|| {
+            index.get(&generator.utility_id).first().map_or(OwnershipType::Unknown, |(_, owner)| **owner)
+        });
+    }
+    (ownership, report)
+}
+
+/// Totals of one ownership type within a state, for both years.
+#[derive(Debug)]
+pub struct OwnershipEfficiency {
+    pub state: String,
+    pub ownership: OwnershipType,
+    pub stats: [StateStats; 2],
+}
+
+impl OwnershipEfficiency {
+    /// Heat rate in year index 0 or 1, or `None` without positive generation.
+    pub fn heat_rate(&self, year: usize) -> Option<f64> {
+        self.stats[year].heat_rate().map(|rate| rate.0)
+    }
+}
+
+/// Sums both years' fuel and generation by state and ownership type. Plants missing from EIA-860 (including the
+/// state-level increment rows) are grouped as unknown in a left join and left out in an inner one.
+/// # Arguments
+/// * `records` - Cleaned rows of the two years
+/// * `ownership` - Ownership type by plant code (see `plant_ownership`)
+/// * `join_type` - Left or inner join of the plants onto `ownership`
+/// # Returns
+/// * `Vec<OwnershipEfficiency>` sorted by state and ownership type, and the report of the plant join
+pub fn efficiency_by_ownership(
+    records: [&[CleanRecord]; 2],
+    ownership: &HashMap<String, OwnershipType>,
+    join_type: JoinType,
+) -> (Vec<OwnershipEfficiency>, JoinReport) {
+    let plants: Vec<(&String, &OwnershipType)> = ownership.iter().collect();
+    let index = KeyIndex::new(JoinKey::PlantId, &plants, |(id, _)| id);
+    let plant_ids = records.iter().flat_map(|year| year.iter().map(|r| r.plant_id.as_str()));
+    let report = index.report("EIA-860 plant owners", plant_ids, join_type);
+
+    let mut groups: BTreeMap<(String, OwnershipType), [StateStats; 2]> = BTreeMap::new();
+    for (year, year_records) in records.iter().enumerate() {
+        for record in year_records.iter().filter(|r| report.keeps(&r.plant_id)) {
+            let owner = index.get(&record.plant_id).first().map_or(OwnershipType::Unknown, |(_, owner)| **owner);
+            let stats = &mut groups.entry((record.state.clone(), owner)).or_default()[year];
+            stats.total_fuel += Mmbtu(record.fuel);
+            stats.total_gen += MegawattHours(record.r#gen);
+        }
+    }
+
+    let groups = groups
+        .into_iter()
+        .map(|((state, owner), stats)| OwnershipEfficiency { state, ownership: owner, stats })
+        .collect();
+    (groups, report)
+}
+
+/// Writes the ownership breakdown, with the heat rate change and each group's share of the state's
+/// compare-year generation, to a CSV output file. The per-input columns are named after the input labels.
+pub fn write_ownership_efficiency_csv(
+    path: &str,
+    data: &[OwnershipEfficiency],
+    labels: [&str; 2],
+) -> Result<(), Box<dyn Error>> {
+    let [a, b] = labels;
+    let mut state_gen: HashMap<&str, f64> = HashMap::new();
+    for item in data {
+        *state_gen.entry(&item.state).or_default() += item.stats[1].total_gen.0;
+    }
+
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "State".to_string(),
+        "Ownership".to_string(),
+        format!("Heat_Rate_{a}"),
+        format!("Heat_Rate_{b}"),
+        "Delta".to_string(),
+        format!("Generation_{b}_MWh"),
+        format!("Generation_Share_{b}"),
+    ])?;
+
+    for item in data {
+        let delta = item.heat_rate(0).zip(item.heat_rate(1)).map(|(a, b)| b - a);
+        let total = state_gen[item.state.as_str()];
+        let share = (total > 0.0).then(|| item.stats[1].total_gen.0 / total);
+        wtr.write_record([
+            &item.state,
+            item.ownership.label(),
+            &format_optional(item.heat_rate(0)),
+            &format_optional(item.heat_rate(1)),
+            &format_optional(delta),
+            &format!("{:.6}", item.stats[1].total_gen),
+            &format_optional(share),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_groups_plants_by_utility_type() {
+        let generator = |plant_id: &str, utility_id: &str| Generator {
+            plant_id: plant_id.to_string(),
+            utility_id: utility_id.to_string(),
+            generator_id: "1".to_string(),
+            capacity_mw: 100.0,
+            operating_year: Some(2000),
+        };
+        let generators = [generator("1", "10"), generator("1", "20"), generator("2", "20"), generator("3", "30")];
+        let utility_types =
+            HashMap::from([("10".to_string(), OwnershipType::Municipal), ("20".to_string(), OwnershipType::Cooperative)]);
+        let (ownership, report) = plant_ownership(&generators, &utility_types, JoinType::Left);
+        assert_eq!((ownership["1"], ownership["3"]), (OwnershipType::Municipal, OwnershipType::Unknown));
+        assert_eq!(report.unmatched_left.iter().collect::<Vec<_>>(), ["30"]);
+
+        let base = [record("1", "AA", "NG", 900.0, 100.0), record("2", "AA", "NG", 1000.0, 100.0)];
+        let compare = [
+            record("1", "AA", "NG", 800.0, 100.0),
+            record("2", "AA", "NG", 1100.0, 100.0),
+            record("9", "AA", "NG", 50.0, 5.0),
+        ];
+        let (groups, _) = efficiency_by_ownership([&base, &compare], &ownership, JoinType::Left);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].ownership, OwnershipType::Municipal);
+        assert_eq!((groups[0].heat_rate(0), groups[0].heat_rate(1)), (Some(9.0), Some(8.0)));
+        assert_eq!((groups[2].ownership, groups[2].heat_rate(0)), (OwnershipType::Unknown, None));
+
+        let (groups, report) = efficiency_by_ownership([&base, &compare], &ownership, JoinType::Inner);
+        assert_eq!((groups.len(), report.matched(), report.unmatched_left.len()), (2, 2, 1));
+    }
+}
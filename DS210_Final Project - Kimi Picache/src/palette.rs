@@ -0,0 +1,142 @@
+// palette.rs
+// This module picks the colors that mark improvements and deteriorations in the terminal tables and the HTML
+// charts. The standard palette is green/red in the terminal and blue/red in the charts; the colorblind palette
+// uses the Okabe-Ito blue and vermillion throughout, which stay apart under the common color vision
+// deficiencies. Terminal colors follow `--color` and the `NO_COLOR` convention (https://no-color.org).
+
+use std::env;
+use std::io::{self, IsTerminal};
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+/// Colors for improvements, deteriorations and chart series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum Palette {
+    /// Green/red in the terminal, blue/red bars and Plotly's default series colors in the charts.
+    #[default]
+    Standard,
+    /// Okabe-Ito colors (blue/vermillion) everywhere, distinguishable with red-green color blindness.
+    Colorblind,
+}
+
+/// Whether a value is an improvement (heat rate fell) or a deterioration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Improved,
+    Worsened,
+}
+
+impl Tone {
+    /// Tone of a change in heat rate; `None` for no change.
+    pub fn of_delta(delta: f64) -> Option<Tone> {
+        if delta < 0.0 {
+            Some(Tone::Improved)
+        } else if delta > 0.0 {
+            Some(Tone::Worsened)
+        } else {
+            None
+        }
+    }
+}
+
+/// Okabe-Ito colors, in the order used for chart series.
+const OKABE_ITO: [&str; 8] = ["#0072B2", "#E69F00", "#009E73", "#D55E00", "#56B4E9", "#CC79A7", "#F0E442", "#000000"];
+
+impl Palette {
+    /// Hex color of a tone in the charts.
+    pub fn hex(self, tone: Tone) -> &'static str {
+        match (self, tone) {
+            (Palette::Standard, Tone::Improved) => "#2166ac",
+            (Palette::Standard, Tone::Worsened) => "#b2182b",
+            (Palette::Colorblind, Tone::Improved) => OKABE_ITO[0],
+            (Palette::Colorblind, Tone::Worsened) => OKABE_ITO[3],
+        }
+    }
+
+    /// Series colors for multi-trace charts, or `None` for Plotly's defaults.
+    pub fn colorway(self) -> Option<&'static [&'static str]> {
+        match self {
+            Palette::Standard => None,
+            Palette::Colorblind => Some(&OKABE_ITO),
+        }
+    }
+
+    /// ANSI foreground code of a tone (256-color codes for the colorblind palette).
+    fn ansi(self, tone: Tone) -> &'static str {
+        match (self, tone) {
+            (Palette::Standard, Tone::Improved) => "32",
+            (Palette::Standard, Tone::Worsened) => "31",
+            (Palette::Colorblind, Tone::Improved) => "38;5;32",
+            (Palette::Colorblind, Tone::Worsened) => "38;5;166",
+        }
+    }
+}
+
+/// When to color terminal output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum ColorChoice {
+    /// Color when standard output is a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether terminal output is colored; `NO_COLOR` (set and not empty) turns `Auto` off.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Colors of terminal output; the default writes plain text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TerminalColors {
+    pub palette: Palette,
+    pub enabled: bool,
+}
+
+impl TerminalColors {
+    /// Colors for a run's `--palette` and `--color`.
+    pub fn new(palette: Palette, choice: ColorChoice) -> TerminalColors {
+        TerminalColors { palette, enabled: choice.enabled() }
+    }
+
+    /// Wraps `text` in the tone's color; plain without colors or tone. Pad before painting, as the escape
+    /// codes count towards `format!` widths.
+    pub fn paint(&self, text: &str, tone: Option<Tone>) -> String {
+        match tone {
+            Some(tone) if self.enabled => format!("\x1b[{}m{}\x1b[0m", self.palette.ansi(tone), text),
+            _ => text.to_string(),
+        }
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paint_and_palettes() {
+        let plain = TerminalColors::default();
+        assert_eq!(plain.paint("-0.5", Tone::of_delta(-0.5)), "-0.5");
+
+        let colorblind = TerminalColors { palette: Palette::Colorblind, enabled: true };
+        assert_eq!(colorblind.paint("-0.5", Tone::of_delta(-0.5)), "\x1b[38;5;32m-0.5\x1b[0m");
+        assert_eq!(colorblind.paint("0.0", Tone::of_delta(0.0)), "0.0");
+        assert!(!ColorChoice::Never.enabled() && ColorChoice::Always.enabled());
+
+        assert_eq!(Palette::Colorblind.hex(Tone::Worsened), "#D55E00");
+        assert!(Palette::Standard.colorway().is_none());
+    }
+}
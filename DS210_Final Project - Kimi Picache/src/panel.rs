@@ -0,0 +1,375 @@
+// panel.rs
+// This module loads several years of EIA-923 data at once ("panel mode") and writes per-state, per-year heat rates.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::path::Path;
+
+use csv::WriterBuilder;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::cache::load_records_cached;
+use crate::cancel::CancellationToken;
+use crate::checkpoint::{load_checkpoint, save_checkpoint};
+use crate::cleaning::{aggregate_by_state, LoadOptions, StateStats};
+use crate::fuels::{FuelGroup, Technology};
+use crate::monthly::{aggregate_monthly_by_state, MonthlyStats};
+use crate::pivot::{aggregate_by_state_fuel, aggregate_by_technology};
+use crate::years::{infer_year, YearOverrides};
+
+/// One year of annual and monthly state totals.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YearData {
+    pub year: i32,
+
+    /// File the year was loaded from and the number of valid rows it had.
+    pub source: String,
+    pub rows: usize,
+
+    /// Territory, imputation and heat content options the year was aggregated with.
+    pub options: LoadOptions,
+
+    /// Number of blank monthly values that were imputed.
+    pub imputed: usize,
+
+    /// Number of plant-months with implausible heat content.
+    #[serde(default)]
+    pub anomalies: usize,
+
+    pub monthly: HashMap<String, MonthlyStats>,
+    pub stats: HashMap<String, StateStats>,
+    pub fuels: BTreeMap<String, BTreeMap<FuelGroup, StateStats>>,
+
+    /// National totals by fossil technology.
+    pub technologies: BTreeMap<Technology, StateStats>,
+}
+
+/// Expands a year list such as "2018..=2022", "2018..2022" (end excluded) or "2019,2020".
+pub fn parse_year_range(spec: &str) -> Result<Vec<i32>, Box<dyn Error>> {
+    let parse = |s: &str| -> Result<i32, Box<dyn Error>> {
+        s.trim().parse().map_err(|_| format!("invalid year '{}' in '{}'", s.trim(), spec).into())
+    };
+
+    let years: Vec<i32> = if let Some((start, end)) = spec.split_once("..=") {
+        (parse(start)?..=parse(end)?).collect()
+    } else if let Some((start, end)) = spec.split_once("..") {
+        (parse(start)?..parse(end)?).collect()
+    } else {
+        spec.split(',').map(parse).collect::<Result<_, _>>()?
+    };
+
+    if years.is_empty() {
+        return Err(format!("year range '{}' is empty", spec).into());
+    }
+    Ok(years)
+}
+
+/// Years with a `<year>.csv` file in `data_dir`, ascending.
+pub fn years_in_data_dir(data_dir: &str) -> Result<Vec<i32>, Box<dyn Error>> {
+    let entries = std::fs::read_dir(data_dir).map_err(|e| format!("cannot list '{}': {}", data_dir, e))?;
+    let mut years = Vec::new();
+    for entry in entries {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if let Some(year) = name.strip_suffix(".csv").filter(|y| y.len() == 4).and_then(|y| y.parse().ok()) {
+            years.push(year);
+        }
+    }
+    years.sort_unstable();
+    Ok(years)
+}
+
+/// Resolves each year to `<data_dir>/<year>.csv`. With `download`, a year missing there resolves to the URL of
+/// its EIA-923 archive instead, which the loaders fetch, check and extract like any URL input; without it (or
+/// without the `network` feature) a missing year is an error.
+pub fn files_for_years(data_dir: &str, years: &[i32], download: bool) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut files = Vec::new();
+
+    for year in years {
+        let path = Path::new(data_dir).join(format!("{}.csv", year));
+        #[cfg(feature = "network")]
+        if !path.is_file() && download {
+            println!("No data for {} at '{}'; the EIA-923 archive will be downloaded", year, path.display());
+            files.push(crate::download::eia923_archive_url(*year));
+            continue;
+        }
+        #[cfg(not(feature = "network"))]
+        let _ = download;
+        if !path.is_file() {
+            return Err(format!(
+                "no data for {} at '{}': download the EIA-923 archive from \
+                 https://www.eia.gov/electricity/data/eia923/ and save its 'Page 1 Generation and Fuel Data' \
+                 sheet as CSV there",
+                year,
+                path.display()
+            )
+            .into());
+        }
+        files.push(path.to_string_lossy().into_owned());
+    }
+
+    Ok(files)
+}
+
+/// Loads and aggregates every file, returning the years in ascending order.
+/// # Arguments
+/// * `file_paths` - One EIA-923 CSV per year; the year is read from its YEAR column (or its name, e.g. `2019.csv`)
+/// * `checkpoint_dir` - If set, each year's aggregates are saved there once computed and reused by later
+///   runs while the source file is unchanged, so an interrupted run resumes after the last completed year
+/// * `options` - Territory, imputation and heat content options; checkpoints made with other options are recomputed
+/// * `cache_dir` / `reuse` - Content-addressed cache of cleaned inputs (see `load_records_cached`)
+/// * `cancel` - Stops before the next year (or within a year's loading); checkpointed years are kept
+/// * `overrides` - `--assume-year` values, used instead of the YEAR column
+/// # Returns
+/// * `Vec<YearData>` sorted by year
+pub fn load_panel(
+    file_paths: &[String],
+    checkpoint_dir: Option<&str>,
+    options: &LoadOptions,
+    cache_dir: &str,
+    reuse: bool,
+    cancel: &CancellationToken,
+    overrides: &YearOverrides,
+) -> Result<Vec<YearData>, Box<dyn Error>> {
+    let mut panel: Vec<YearData> = Vec::new();
+
+    for file_path in file_paths {
+        let stage = format!("loading '{}'", file_path);
+        cancel.check(&stage, || format!("{} of {} years loaded", panel.len(), file_paths.len()))?;
+        let assumed = overrides.for_file(file_path);
+
+        if let Some(dir) = checkpoint_dir
+            && let Some(year_data) = load_checkpoint(dir, file_path)
+            && year_data.options == *options
+            && assumed.is_none_or(|year| year == year_data.year)
+        {
+            println!("Resuming {} from checkpoint...", year_data.year);
+            panel.push(year_data);
+            continue;
+        }
+
+        println!("Loading '{}'...", file_path);
+        let loaded = load_records_cached(file_path, options, cache_dir, reuse, cancel)?;
+        let inferred = infer_year(file_path, &loaded.years, assumed)
+            .ok_or_else(|| format!("cannot tell which year '{}' holds; use --assume-year", file_path))?;
+        for warning in &inferred.warnings {
+            println!("Warning: {}", warning);
+        }
+        let year = inferred.year;
+        println!("{} data (from {})", year, inferred.source.label());
+        let records = loaded.records;
+        let stats = aggregate_by_state(&records);
+        let monthly = aggregate_monthly_by_state(&records);
+        let fuels = aggregate_by_state_fuel(&records);
+        let technologies = aggregate_by_technology(&records);
+        let year_data = YearData {
+            year,
+            source: file_path.clone(),
+            rows: records.len(),
+            options: *options,
+            imputed: loaded.imputed.len(),
+            anomalies: loaded.anomalies.len(),
+            monthly,
+            stats,
+            fuels,
+            technologies,
+        };
+
+        if let Some(dir) = checkpoint_dir {
+            save_checkpoint(dir, file_path, &year_data)?;
+        }
+        panel.push(year_data);
+    }
+
+    panel.sort_by_key(|y| y.year);
+    if let Some(pair) = panel.windows(2).find(|pair| pair[0].year == pair[1].year) {
+        return Err(format!("'{}' and '{}' both hold {} data", pair[0].source, pair[1].source, pair[0].year).into());
+    }
+    Ok(panel)
+}
+
+/// Heat rate of a state's totals, or `None` without generation.
+fn heat_rate(stats: &StateStats) -> Option<f64> {
+    (stats.total_gen.0 != 0.0).then(|| (stats.total_fuel / stats.total_gen).0)
+}
+
+/// Sorted list of every state that appears in any year.
+fn all_states(panel: &[YearData]) -> BTreeSet<&str> {
+    panel
+        .iter()
+        .flat_map(|y| y.stats.keys().map(String::as_str))
+        .collect()
+}
+
+/// A state's totals and heat rate in one year: a row of the long panel table.
+#[derive(Debug, Serialize)]
+pub struct PanelRow {
+    pub state: String,
+    pub year: i32,
+    pub total_fuel_mmbtu: f64,
+    pub net_generation_mwh: f64,
+    pub heat_rate: f64,
+}
+
+/// One row per state and year, by state then year; state-years without generation are left out.
+pub fn panel_long_rows(panel: &[YearData]) -> Vec<PanelRow> {
+    let mut rows = Vec::new();
+
+    for state in all_states(panel) {
+        for year_data in panel {
+            let Some(stats) = year_data.stats.get(state) else {
+                continue;
+            };
+            let Some(rate) = heat_rate(stats) else {
+                continue;
+            };
+
+            rows.push(PanelRow {
+                state: state.to_string(),
+                year: year_data.year,
+                total_fuel_mmbtu: stats.total_fuel.0,
+                net_generation_mwh: stats.total_gen.0,
+                heat_rate: rate,
+            });
+        }
+    }
+
+    rows
+}
+
+/// Writes one row per state and year with totals and heat rate.
+pub fn write_panel_long_csv(path: &str, panel: &[YearData]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["State", "Year", "Total_Fuel_MMBtu", "Net_Generation_MWh", "Heat_Rate"])?;
+
+    for row in panel_long_rows(panel) {
+        wtr.write_record([
+            row.state,
+            row.year.to_string(),
+            format!("{:.6}", row.total_fuel_mmbtu),
+            format!("{:.6}", row.net_generation_mwh),
+            format!("{:.6}", row.heat_rate),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// A state's heat rate in every loaded year: a row of the wide panel table. It serializes as `state` followed by
+/// one `heat_rate_<year>` entry per year, null where the state has no heat rate that year.
+#[derive(Debug)]
+pub struct PanelWideRow {
+    pub state: String,
+    pub heat_rates: Vec<(i32, Option<f64>)>,
+}
+
+impl Serialize for PanelWideRow {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1 + self.heat_rates.len()))?;
+        map.serialize_entry("state", &self.state)?;
+        for (year, rate) in &self.heat_rates {
+            map.serialize_entry(&format!("heat_rate_{}", year), rate)?;
+        }
+        map.end()
+    }
+}
+
+/// One row per state with its heat rate in each loaded year, by state.
+pub fn panel_wide_rows(panel: &[YearData]) -> Vec<PanelWideRow> {
+    all_states(panel)
+        .into_iter()
+        .map(|state| PanelWideRow {
+            state: state.to_string(),
+            heat_rates: panel.iter().map(|y| (y.year, y.stats.get(state).and_then(heat_rate))).collect(),
+        })
+        .collect()
+}
+
+/// Writes one row per state with one heat rate column per year; missing years are left empty.
+pub fn write_panel_wide_csv(path: &str, panel: &[YearData]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+
+    let mut header = vec!["State".to_string()];
+    header.extend(panel.iter().map(|y| format!("Heat_Rate_{}", y.year)));
+    wtr.write_record(&header)?;
+
+    for row in panel_wide_rows(panel) {
+        let mut record = vec![row.state];
+        record.extend(row.heat_rates.iter().map(|(_, rate)| rate.map(|r| format!("{:.6}", r)).unwrap_or_default()));
+        wtr.write_record(&record)?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::quantity::{MegawattHours, Mmbtu};
+
+    fn year(year: i32, states: &[(&str, f64, f64)]) -> YearData {
+        let stats = states
+            .iter()
+            .map(|(state, fuel, r#gen)| {
+                (state.to_string(), StateStats { total_fuel: Mmbtu(*fuel), total_gen: MegawattHours(*r#gen) })
+            })
+            .collect();
+        YearData {
+            year,
+            source: format!("{}.csv", year),
+            rows: 0,
+            options: LoadOptions::default(),
+            imputed: 0,
+            anomalies: 0,
+            monthly: HashMap::new(),
+            stats,
+            fuels: BTreeMap::new(),
+            technologies: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_year_range() {
+        assert_eq!(parse_year_range("2018..=2020").unwrap(), [2018, 2019, 2020]);
+        assert_eq!(parse_year_range("2018..2020").unwrap(), [2018, 2019]);
+        assert_eq!(parse_year_range("2019, 2021").unwrap(), [2019, 2021]);
+        assert!(parse_year_range("2020..2020").unwrap_err().to_string().contains("empty"));
+        assert!(parse_year_range("20x9").unwrap_err().to_string().contains("invalid year '20x9'"));
+    }
+
+    #[test]
+    fn test_panel_tables() {
+        let dir = std::env::temp_dir().join(format!("efficiency_panel_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("2019.csv"), "").unwrap();
+        let data_dir = dir.to_str().unwrap();
+        assert_eq!(files_for_years(data_dir, &[2019], false).unwrap().len(), 1);
+        assert_eq!(years_in_data_dir(data_dir).unwrap(), [2019]);
+        let missing = files_for_years(data_dir, &[2019, 2020], false).unwrap_err();
+        assert!(missing.to_string().contains("no data for 2020"));
+        #[cfg(feature = "network")]
+        assert!(files_for_years(data_dir, &[2019, 2020], true).unwrap()[1].ends_with("/f923_2020.zip"));
+
+        // BB has no 2020 data and CC no generation: left out of the long table, empty in the wide one
+        let panel = [
+            year(2019, &[("AA", 100.0, 10.0), ("BB", 90.0, 10.0)]),
+            year(2020, &[("AA", 80.0, 10.0), ("CC", 5.0, 0.0)]),
+        ];
+        let (long, wide) = (dir.join("long.csv"), dir.join("wide.csv"));
+        write_panel_long_csv(long.to_str().unwrap(), &panel).unwrap();
+        write_panel_wide_csv(wide.to_str().unwrap(), &panel).unwrap();
+        let long = std::fs::read_to_string(long).unwrap();
+        assert_eq!(long.lines().count(), 4);
+        assert!(long.contains("AA,2020,80.000000,10.000000,8.000000"));
+        let wide = std::fs::read_to_string(wide).unwrap();
+        let rows: Vec<&str> = wide.lines().collect();
+        assert_eq!(rows, ["State,Heat_Rate_2019,Heat_Rate_2020", "AA,10.000000,8.000000", "BB,9.000000,", "CC,,"]);
+        let json = serde_json::to_value(panel_wide_rows(&panel)).unwrap();
+        assert_eq!(json[1], serde_json::json!({"state": "BB", "heat_rate_2019": 9.0, "heat_rate_2020": null}));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+// parallel.rs
+// This module sums state totals on several threads. By default each thread takes an equal share of the rows and
+// the partial sums are merged as the threads finish, which is fastest but lets the last bits of a total vary with
+// the thread count and the finishing order. In deterministic mode the rows are split into fixed-size chunks
+// whatever the thread count, and each state's chunk sums are merged in chunk order by pairwise summation, so the
+// results are bit-identical across runs and machines.
+
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::thread;
+
+use crate::cleaning::{aggregate_by_state, CleanRecord, StateStats};
+use crate::quantity::{MegawattHours, Mmbtu};
+use crate::stats::CompensatedSum;
+
+/// Rows per chunk in deterministic mode; fixed so the summation order does not depend on the thread count.
+pub const DETERMINISTIC_CHUNK_ROWS: usize = 4096;
+
+/// How state totals are summed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parallelism {
+    /// Worker threads; 1 sums sequentially.
+    pub threads: usize,
+
+    /// Fixed chunking and merge order, for bit-identical results.
+    pub deterministic: bool,
+}
+
+impl Default for Parallelism {
+    fn default() -> Self {
+        Parallelism { threads: 1, deterministic: false }
+    }
+}
+
+impl Parallelism {
+    /// Description for the CSV preamble.
+    pub fn describe(&self) -> String {
+        let order = if self.deterministic {
+            format!("deterministic ({}-row chunks, pairwise merge in chunk order)", DETERMINISTIC_CHUNK_ROWS)
+        } else {
+            "merged as threads finish".to_string()
+        };
+        format!("{} threads, {}", self.threads, order)
+    }
+}
+
+/// Sums fuel and generation by state, like `aggregate_by_state`, on `parallelism.threads` threads.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// * `parallelism` - Thread count and whether the summation order is fixed
+/// # Returns
+/// * `HashMap<String, StateStats>` keyed by state
+pub fn aggregate_by_state_parallel(records: &[CleanRecord], parallelism: Parallelism) -> HashMap<String, StateStats> {
+    let threads = parallelism.threads.max(1);
+    if parallelism.deterministic {
+        aggregate_deterministic(records, threads)
+    } else if threads == 1 || records.is_empty() {
+        aggregate_by_state(records)
+    } else {
+        aggregate_unordered(records, threads)
+    }
+}
+
+/// One share of the rows per thread, merged in whatever order the threads finish.
+fn aggregate_unordered(records: &[CleanRecord], threads: usize) -> HashMap<String, StateStats> {
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for share in records.chunks(records.len().div_ceil(threads)) {
+            let sender = sender.clone();
+            scope.spawn(move || sender.send(aggregate_by_state(share)));
+        }
+    });
+    drop(sender);
+
+    let mut sums: HashMap<String, (CompensatedSum, CompensatedSum)> = HashMap::new();
+    for partial in receiver {
+        for (state, stats) in partial {
+            let entry = sums.entry(state).or_default();
+            entry.0.add(stats.total_fuel.0);
+            entry.1.add(stats.total_gen.0);
+        }
+    }
+    sums.into_iter()
+        .map(|(state, (fuel, r#gen))| {
+            (state, StateStats { total_fuel: Mmbtu(fuel.total()), total_gen: MegawattHours(r#gen.total()) })
+        })
+        .collect()
+}
+
+/// Fixed-size chunks spread over the threads; each state's chunk sums are combined pairwise in chunk order.
+fn aggregate_deterministic(records: &[CleanRecord], threads: usize) -> HashMap<String, StateStats> {
+    let chunks: Vec<&[CleanRecord]> = records.chunks(DETERMINISTIC_CHUNK_ROWS).collect();
+    let per_thread = chunks.len().div_ceil(threads).max(1);
+
+    // Threads take consecutive runs of chunks and are joined in order, so `partials` is in chunk order
+    let partials: Vec<HashMap<String, StateStats>> = thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .chunks(per_thread)
+            .map(|run| scope.spawn(move || run.iter().map(|chunk| aggregate_by_state(chunk)).collect::<Vec<_>>()))
+            .collect();
+        handles.into_iter().flat_map(|h| h.join().expect("aggregation thread panicked")).collect()
+    });
+
+    let mut sums: HashMap<String, (Vec<f64>, Vec<f64>)> = HashMap::new();
+    for partial in partials {
+        for (state, stats) in partial {
+            let entry = sums.entry(state).or_default();
+            entry.0.push(stats.total_fuel.0);
+            entry.1.push(stats.total_gen.0);
+        }
+    }
+    sums.into_iter()
+        .map(|(state, (fuel, r#gen))| {
+            let (fuel, r#gen) = (Mmbtu(pairwise_sum(&fuel)), MegawattHours(pairwise_sum(&r#gen)));
+            (state, StateStats { total_fuel: fuel, total_gen: r#gen })
+        })
+        .collect()
+}
+
+/// Sum of the two halves, recursively; the grouping depends only on the number of values.
+fn pairwise_sum(values: &[f64]) -> f64 {
+    if values.len() <= 8 {
+        return values.iter().sum();
+    }
+    let (left, right) = values.split_at(values.len() / 2);
+    pairwise_sum(left) + pairwise_sum(right)
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_deterministic_totals_ignore_thread_count() {
+        // Values of very different magnitudes, so the summation order shows in the last bits
+        let records: Vec<CleanRecord> = (0..20_000)
+            .map(|i| {
+                let state = if i % 3 == 0 { "AA" } else { "BB" };
+                record("1", state, "NG", 1.0 / (i as f64 + 1.0) * 10f64.powi(i % 9), 1.0)
+            })
+            .collect();
+        let totals = |threads, deterministic| {
+            let stats = aggregate_by_state_parallel(&records, Parallelism { threads, deterministic });
+            (stats["AA"].total_fuel.0.to_bits(), stats["BB"].total_fuel.0.to_bits(), stats["BB"].total_gen.0)
+        };
+
+        let reference = totals(1, true);
+        assert_eq!(totals(3, true), reference);
+        assert_eq!(totals(8, true), reference);
+        assert_eq!(reference.2, 13_333.0);
+
+        let sequential = aggregate_by_state(&records);
+        let unordered = aggregate_by_state_parallel(&records, Parallelism { threads: 4, deterministic: false });
+        let (unordered, sequential) = (unordered["AA"].total_fuel.0, sequential["AA"].total_fuel.0);
+        assert!((unordered - sequential).abs() < 1e-6 * sequential);
+
+        // One huge positive and one huge negative row around many small ones (e.g. a correction entry)
+        let adversarial: Vec<CleanRecord> =
+            [1e16].into_iter().chain([1.0; 5000]).chain([-1e16]).map(|f| record("1", "AA", "NG", f, 1.0)).collect();
+        assert_eq!(aggregate_by_state(&adversarial)["AA"].total_fuel, Mmbtu(5000.0));
+        let parallel = aggregate_by_state_parallel(&adversarial, Parallelism { threads: 4, deterministic: false });
+        assert_eq!(parallel["AA"].total_fuel, Mmbtu(5000.0));
+    }
+}
@@ -0,0 +1,158 @@
+// partition.rs
+// This module writes a plant-level table as a Hive-style partitioned directory: one subdirectory per value of
+// the partition column ('State=TX/', 'Fuel_Group=Coal/'), each holding numbered CSV parts of at most a fixed
+// number of rows. The partition column lives in the directory name only, as in Hive, Spark or DuckDB's
+// `hive_partitioning`, so a reader can pick the partitions it needs without opening the others.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::Path;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use csv::{Writer, WriterBuilder};
+
+/// Column a plant-level export is partitioned by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum PartitionBy {
+    /// One directory per state code.
+    State,
+    /// One directory per fuel group.
+    Fuel,
+}
+
+impl PartitionBy {
+    /// Name of the partition column, as in the unpartitioned file.
+    pub fn column(self) -> &'static str {
+        match self {
+            PartitionBy::State => "State",
+            PartitionBy::Fuel => "Fuel_Group",
+        }
+    }
+}
+
+/// Escapes a partition value for a directory name the way Hive does: the characters that are unsafe in paths or
+/// ambiguous in `column=value` become `%XX`.
+pub fn escape_partition_value(value: &str) -> String {
+    if value.is_empty() {
+        return "__HIVE_DEFAULT_PARTITION__".to_string();
+    }
+    let mut escaped = String::new();
+    for c in value.chars() {
+        if c.is_control() || "\"#%'*/:=?\\{[]^".contains(c) {
+            escaped += &format!("%{:02X}", c as u32);
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// The part of one partition being written.
+struct OpenPart {
+    writer: Writer<File>,
+    rows: usize,
+    part: usize,
+}
+
+/// Streams rows into a partitioned directory, opening a new part once the current one holds `chunk_rows` rows.
+pub struct PartitionedWriter {
+    dir: String,
+    name: String,
+    column: usize,
+    header: Vec<String>,
+    chunk_rows: usize,
+    open: BTreeMap<String, OpenPart>,
+    files: Vec<String>,
+}
+
+impl PartitionedWriter {
+    /// Starts a partitioned table. Partition directories of the same column left by an earlier run are removed
+    /// first, so parts of different runs never mix; other files in `dir` are kept.
+    /// # Arguments
+    /// * `dir` - Output directory, created if needed
+    /// * `header` - Columns of the unpartitioned table
+    /// * `column` - Name of the partition column, one of `header`
+    /// * `chunk_rows` - Rows per part file (at least 1)
+    pub fn new(
+        dir: &str,
+        header: &[String],
+        column: &str,
+        chunk_rows: usize,
+    ) -> Result<PartitionedWriter, Box<dyn Error>> {
+        let index = header.iter().position(|c| c == column).ok_or_else(|| format!("no column '{}'", column))?;
+        fs::create_dir_all(dir)?;
+        let prefix = format!("{}=", column);
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() && entry.file_name().to_string_lossy().starts_with(&prefix) {
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+
+        let mut header = header.to_vec();
+        header.remove(index);
+        Ok(PartitionedWriter {
+            dir: dir.to_string(),
+            name: column.to_string(),
+            column: index,
+            header,
+            chunk_rows: chunk_rows.max(1),
+            open: BTreeMap::new(),
+            files: Vec::new(),
+        })
+    }
+
+    /// Writes a row of the unpartitioned table to its partition.
+    pub fn write(&mut self, mut row: Vec<String>) -> Result<(), Box<dyn Error>> {
+        let value = row.remove(self.column);
+        let full = self.open.get(&value).map(|open| (open.rows >= self.chunk_rows, open.part + 1));
+        if full.is_none_or(|(full, _)| full) {
+            if let Some(mut done) = self.open.remove(&value) {
+                done.writer.flush()?;
+            }
+            let part = full.map_or(0, |(_, next)| next);
+            let folder = Path::new(&self.dir).join(format!("{}={}", self.name, escape_partition_value(&value)));
+            fs::create_dir_all(&folder)?;
+            let path = folder.join(format!("part-{:05}.csv", part));
+            let mut writer = WriterBuilder::new().from_path(&path)?;
+            writer.write_record(&self.header)?;
+            self.files.push(path.to_string_lossy().into_owned());
+            self.open.insert(value.clone(), OpenPart { writer, rows: 0, part });
+        }
+
+        let open = self.open.get_mut(&value).ok_or("partition not open")?;
+        open.writer.write_record(&row)?;
+        open.rows += 1;
+        Ok(())
+    }
+
+    /// Flushes every open part.
+    /// # Returns
+    /// * The files written, in the order they were started
+    pub fn finish(mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        for open in self.open.values_mut() {
+            open.writer.flush()?;
+        }
+        Ok(self.files)
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_partition_value() {
+        assert_eq!(escape_partition_value("Natural Gas"), "Natural Gas");
+        assert_eq!(escape_partition_value("Oil/Other=1%"), "Oil%2FOther%3D1%25");
+        assert_eq!(escape_partition_value(""), "__HIVE_DEFAULT_PARTITION__");
+        assert_eq!(PartitionBy::Fuel.column(), "Fuel_Group");
+
+        let header = ["State".to_string(), "Plant_Id".to_string()];
+        assert!(PartitionedWriter::new("unused", &header, "Month", 10).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+// pivot.rs
+// This module writes state × fuel group matrices (heat rate and generation share) for a single year.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+
+use csv::WriterBuilder;
+
+use crate::cleaning::{CleanRecord, StateStats};
+use crate::fuels::{FuelGroup, Technology};
+use crate::quantity::{MegawattHours, Mmbtu};
+
+/// Sums fuel and generation by state and fuel group.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// # Returns
+/// * `BTreeMap<String, BTreeMap<FuelGroup, StateStats>>` keyed by state, then fuel group
+pub fn aggregate_by_state_fuel(records: &[CleanRecord]) -> BTreeMap<String, BTreeMap<FuelGroup, StateStats>> {
+    let mut map: BTreeMap<String, BTreeMap<FuelGroup, StateStats>> = BTreeMap::new();
+
+    for record in records {
+        let entry = map
+            .entry(record.state.clone())
+            .or_default()
+            .entry(FuelGroup::from_aer(&record.aer_fuel))
+            .or_default();
+        entry.total_fuel += Mmbtu(record.fuel);
+        entry.total_gen += MegawattHours(record.r#gen);
+    }
+
+    map
+}
+
+/// Sums national fuel and generation by fossil technology.
+pub fn aggregate_by_technology(records: &[CleanRecord]) -> BTreeMap<Technology, StateStats> {
+    let mut map: BTreeMap<Technology, StateStats> = BTreeMap::new();
+
+    for record in records {
+        if let Some(technology) = Technology::classify(&record.aer_fuel, &record.prime_mover) {
+            let entry = map.entry(technology).or_default();
+            entry.total_fuel += Mmbtu(record.fuel);
+            entry.total_gen += MegawattHours(record.r#gen);
+        }
+    }
+
+    map
+}
+
+/// Writes a state × fuel group matrix; `cell` returns the value for one state/fuel or `None` for an empty cell.
+fn write_matrix<F>(
+    path: &str,
+    by_state: &BTreeMap<String, BTreeMap<FuelGroup, StateStats>>,
+    cell: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: Fn(&BTreeMap<FuelGroup, StateStats>, FuelGroup) -> Option<f64>,
+{
+    let groups: BTreeSet<FuelGroup> = by_state.values().flat_map(|fuels| fuels.keys().copied()).collect();
+
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    let mut header = vec!["State"];
+    header.extend(groups.iter().map(|g| g.label()));
+    wtr.write_record(&header)?;
+
+    for (state, fuels) in by_state {
+        let mut row = vec![state.clone()];
+        for group in &groups {
+            row.push(cell(fuels, *group).map(|v| format!("{:.6}", v)).unwrap_or_default());
+        }
+        wtr.write_record(&row)?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Writes the heat rate and generation share matrices for one year.
+/// # Arguments
+/// * `records` - Cleaned rows for the year
+/// * `heat_rate_path` - Output path for the heat rate matrix
+/// * `share_path` - Output path for the generation share matrix
+pub fn write_fuel_pivots(records: &[CleanRecord], heat_rate_path: &str, share_path: &str) -> Result<(), Box<dyn Error>> {
+    let by_state = aggregate_by_state_fuel(records);
+
+    write_matrix(heat_rate_path, &by_state, |fuels, group| {
+        let stats = fuels.get(&group)?;
+        (stats.total_gen.0 != 0.0).then(|| (stats.total_fuel / stats.total_gen).0)
+    })?;
+
+    write_matrix(share_path, &by_state, |fuels, group| {
+        let total: MegawattHours = fuels.values().map(|s| s.total_gen).sum();
+        let stats = fuels.get(&group)?;
+        (total.0 != 0.0).then(|| stats.total_gen / total)
+    })?;
+
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_fuel_pivots() {
+        let records = [
+            CleanRecord { prime_mover: "ST".to_string(), ..record("1", "AA", "COL", 1000.0, 100.0) },
+            CleanRecord { prime_mover: "CT".to_string(), ..record("1", "AA", "NG", 350.0, 50.0) },
+            CleanRecord { prime_mover: "GT".to_string(), ..record("1", "AA", "NG", 250.0, 25.0) },
+            CleanRecord { prime_mover: "WT".to_string(), ..record("1", "BB", "WND", 0.0, 75.0) },
+        ];
+        let by_state = aggregate_by_state_fuel(&records);
+        assert_eq!(by_state["AA"][&FuelGroup::NaturalGas].total_fuel, Mmbtu(600.0));
+        let by_technology = aggregate_by_technology(&records);
+        assert_eq!(by_technology.len(), 3);
+        assert_eq!(by_technology[&Technology::GasCombinedCycle].total_gen, MegawattHours(50.0));
+
+        // Columns cover the groups of every state; a state without a group has an empty cell
+        let dir = std::env::temp_dir().join(format!("efficiency_pivot_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (heat_rate, share) = (dir.join("heat_rate.csv"), dir.join("share.csv"));
+        write_fuel_pivots(&records, heat_rate.to_str().unwrap(), share.to_str().unwrap()).unwrap();
+        let heat_rate = std::fs::read_to_string(heat_rate).unwrap();
+        let rows: Vec<&str> = heat_rate.lines().collect();
+        assert_eq!(rows, ["State,Coal,Natural Gas,Wind", "AA,10.000000,8.000000,", "BB,,,0.000000"]);
+        let share = std::fs::read_to_string(share).unwrap();
+        assert!(share.contains("AA,0.571429,0.428571,"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+// quantity.rs
+// This module wraps the state totals in unit types, so fuel energy (MMBtu), net generation (MWh) and heat rates
+// (MMBtu/MWh) cannot be mixed up: only like quantities add, and fuel divided by generation is the only way to a
+// heat rate. The types serialize as plain numbers, so cached and checkpointed totals keep their format.
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+
+/// Fuel energy in MMBtu.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Mmbtu(pub f64);
+
+/// Net generation in MWh.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MegawattHours(pub f64);
+
+/// Heat rate in MMBtu/MWh.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HeatRate(pub f64);
+
+/// Addition, subtraction and summing within a unit, scaling by a plain factor, and formatting as the bare number
+/// (so `{:.6}` works as for `f64`).
+macro_rules! quantity_ops {
+    ($unit:ident) => {
+        impl $unit {
+            /// Magnitude of the quantity.
+            pub fn abs(self) -> $unit {
+                $unit(self.0.abs())
+            }
+        }
+
+        impl fmt::Display for $unit {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.0, f)
+            }
+        }
+
+        impl Add for $unit {
+            type Output = $unit;
+            fn add(self, other: $unit) -> $unit {
+                $unit(self.0 + other.0)
+            }
+        }
+
+        impl AddAssign for $unit {
+            fn add_assign(&mut self, other: $unit) {
+                self.0 += other.0;
+            }
+        }
+
+        impl Sub for $unit {
+            type Output = $unit;
+            fn sub(self, other: $unit) -> $unit {
+                $unit(self.0 - other.0)
+            }
+        }
+
+        impl SubAssign for $unit {
+            fn sub_assign(&mut self, other: $unit) {
+                self.0 -= other.0;
+            }
+        }
+
+        impl Neg for $unit {
+            type Output = $unit;
+            fn neg(self) -> $unit {
+                $unit(-self.0)
+            }
+        }
+
+        impl Mul<f64> for $unit {
+            type Output = $unit;
+            fn mul(self, factor: f64) -> $unit {
+                $unit(self.0 * factor)
+            }
+        }
+
+        impl Sum for $unit {
+            fn sum<I: Iterator<Item = $unit>>(iter: I) -> $unit {
+                $unit(iter.map(|q| q.0).sum())
+            }
+        }
+    };
+}
+
+quantity_ops!(Mmbtu);
+quantity_ops!(MegawattHours);
+quantity_ops!(HeatRate);
+
+/// Fuel over generation is a heat rate.
+impl Div<MegawattHours> for Mmbtu {
+    type Output = HeatRate;
+    fn div(self, generation: MegawattHours) -> HeatRate {
+        HeatRate(self.0 / generation.0)
+    }
+}
+
+/// A heat rate times generation is fuel.
+impl Mul<MegawattHours> for HeatRate {
+    type Output = Mmbtu;
+    fn mul(self, generation: MegawattHours) -> Mmbtu {
+        Mmbtu(self.0 * generation.0)
+    }
+}
+
+/// Ratio of two quantities of the same unit (e.g. a generation share).
+macro_rules! quantity_ratio {
+    ($unit:ident) => {
+        impl Div for $unit {
+            type Output = f64;
+            fn div(self, other: $unit) -> f64 {
+                self.0 / other.0
+            }
+        }
+    };
+}
+
+quantity_ratio!(Mmbtu);
+quantity_ratio!(MegawattHours);
+
+impl HeatRate {
+    /// Heat rate of fuel and generation, or `None` without positive generation.
+    pub fn of(fuel: Mmbtu, generation: MegawattHours) -> Option<HeatRate> {
+        (generation.0 > 0.0).then(|| fuel / generation)
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantity_arithmetic() {
+        let fuel: Mmbtu = [Mmbtu(600.0), Mmbtu(400.0)].into_iter().sum();
+        let mut generation = MegawattHours(80.0);
+        generation += MegawattHours(20.0);
+
+        let rate = fuel / generation;
+        assert_eq!(rate, HeatRate(10.0));
+        assert_eq!(rate * MegawattHours(5.0), Mmbtu(50.0));
+        assert_eq!(HeatRate(9.0) - rate, HeatRate(-1.0));
+        assert_eq!(MegawattHours(25.0) / generation, 0.25);
+        assert_eq!((HeatRate::of(fuel, MegawattHours(0.0)), HeatRate::of(fuel, generation)), (None, Some(rate)));
+        assert_eq!(serde_json::to_string(&fuel).unwrap(), "1000.0");
+    }
+}
@@ -0,0 +1,141 @@
+// recording.rs
+// This module records the files fetched for URL inputs into a directory and replays them in later runs, so an
+// analysis of online data can be rerun offline and exactly, after the source has changed or gone. A recording
+// holds one folder per URL and a manifest ('recordings.csv') with each URL, its file and the file's SHA-256;
+// replay serves URLs only from the recording, never from the network, and refuses files that no longer match
+// their checksum.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use csv::{ReaderBuilder, WriterBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::sha256_file;
+use crate::timestamp::RunTimestamp;
+
+/// Manifest file in a recording directory.
+pub const MANIFEST: &str = "recordings.csv";
+
+/// Whether URL inputs are recorded, replayed or fetched normally.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Recording {
+    #[default]
+    Off,
+    /// Copy every fetched file into this directory (`--record`).
+    Record(String),
+    /// Serve URL inputs from this directory only (`--replay`).
+    Replay(String),
+}
+
+/// One recorded URL.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    #[serde(rename = "URL")]
+    pub url: String,
+
+    /// File path relative to the recording directory.
+    #[serde(rename = "File")]
+    pub file: String,
+
+    #[serde(rename = "SHA256")]
+    pub sha256: String,
+
+    /// When the file was recorded (RFC 3339, UTC).
+    #[serde(rename = "Recorded_UTC")]
+    pub recorded: String,
+}
+
+/// Reads a recording's manifest; a directory without one is an empty recording.
+pub fn read_manifest(dir: &str) -> Result<Vec<RecordedResponse>, Box<dyn Error>> {
+    let path = Path::new(dir).join(MANIFEST);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let mut rdr = ReaderBuilder::new().from_path(&path)?;
+    let entries = rdr.deserialize().collect::<Result<Vec<RecordedResponse>, _>>();
+    entries.map_err(|e| format!("'{}': {}", path.display(), e).into())
+}
+
+/// Writes a recording's manifest, sorted by URL.
+fn write_manifest(dir: &str, entries: &mut [RecordedResponse]) -> Result<(), Box<dyn Error>> {
+    entries.sort_by(|a, b| a.url.cmp(&b.url));
+    let mut wtr = WriterBuilder::new().from_path(Path::new(dir).join(MANIFEST))?;
+    for entry in entries.iter() {
+        wtr.serialize(entry)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Copies a fetched file into the recording, replacing an earlier recording of the same URL.
+/// # Arguments
+/// * `dir` - Recording directory, created if needed
+/// * `url` - URL the file was fetched from (without checksum fragment)
+/// * `folder` - Folder name for the URL inside the recording
+/// * `file` - The fetched file
+pub fn record_response(dir: &str, url: &str, folder: &str, file: &Path) -> Result<(), Box<dyn Error>> {
+    let name = file.file_name().ok_or("recorded file has no name")?.to_string_lossy();
+    let relative = format!("{}/{}", folder, name);
+    let target = Path::new(dir).join(&relative);
+    fs::create_dir_all(target.parent().unwrap_or(Path::new(dir)))?;
+    fs::copy(file, &target)?;
+
+    let mut entries = read_manifest(dir)?;
+    entries.retain(|entry| entry.url != url);
+    entries.push(RecordedResponse {
+        url: url.to_string(),
+        file: relative,
+        sha256: sha256_file(&target.to_string_lossy())?,
+        recorded: RunTimestamp::now().utc,
+    });
+    write_manifest(dir, &mut entries)?;
+    println!("Recorded '{}' in '{}'", url, dir);
+    Ok(())
+}
+
+/// The recorded file of a URL, after checking it still has its recorded checksum.
+/// # Returns
+/// * `Err` when the URL was not recorded or the file changed since
+pub fn replay_response(dir: &str, url: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let entries = read_manifest(dir)?;
+    let entry = entries.iter().find(|entry| entry.url == url).ok_or_else(|| {
+        format!("'{}' is not in the recording '{}' ({} URLs recorded; run with --record first)", url, dir, entries.len())
+    })?;
+
+    let path = Path::new(dir).join(&entry.file);
+    let actual = sha256_file(&path.to_string_lossy())?;
+    if !actual.eq_ignore_ascii_case(&entry.sha256) {
+        return Err(format!("recorded file '{}' has SHA-256 {}, the manifest says {}", path.display(), actual, entry.sha256)
+            .into());
+    }
+    println!("Replaying '{}' recorded {} from '{}'", url, entry.recorded, dir);
+    Ok(path)
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_rows() {
+        let entry = RecordedResponse {
+            url: "https://example.org/2020.csv".to_string(),
+            file: "0a1b2c3d4e5f6a7b/2020.csv".to_string(),
+            sha256: "ab".repeat(32),
+            recorded: "2026-01-02T03:04:05Z".to_string(),
+        };
+        let mut wtr = WriterBuilder::new().from_writer(Vec::new());
+        wtr.serialize(&entry).unwrap();
+        let text = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+        assert!(text.starts_with("URL,File,SHA256,Recorded_UTC\n"));
+
+        let parsed: Vec<RecordedResponse> =
+            ReaderBuilder::new().from_reader(text.as_bytes()).deserialize().collect::<Result<_, _>>().unwrap();
+        assert_eq!(parsed, [entry]);
+        assert!(read_manifest("no/such/recording").unwrap().is_empty());
+        assert!(replay_response("no/such/recording", "https://example.org/2020.csv").is_err());
+    }
+}
@@ -0,0 +1,319 @@
+// report.rs
+// This module holds a result table as typed cells and renders it to the terminal, CSV, JSON, Markdown and HTML,
+// so every view of a table shows the same columns and numbers. Decimal cells are rounded once to their column's
+// published precision, and the shorter views round that published value again, with the same rounding mode.
+
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+
+use crate::rounding::{RoundingMode, RoundingPolicy};
+
+/// Most decimals shown in the terminal, Markdown and HTML views; files keep each column's published precision.
+const DISPLAY_DECIMALS: usize = 3;
+
+/// One table cell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Int(i64),
+    Float(f64),
+}
+
+impl Cell {
+    /// The cell as published in a column with `decimals` decimals, shown with at most `shown` decimals.
+    fn format(&self, mode: RoundingMode, decimals: usize, shown: usize) -> String {
+        match self {
+            Cell::Text(text) => text.clone(),
+            Cell::Int(value) => value.to_string(),
+            Cell::Float(value) => mode.format(mode.round(*value, decimals), decimals.min(shown)),
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        !matches!(self, Cell::Text(_))
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Cell {
+        Cell::Text(text.to_string())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Cell {
+        Cell::Text(text)
+    }
+}
+
+impl From<usize> for Cell {
+    fn from(value: usize) -> Cell {
+        Cell::Int(value as i64)
+    }
+}
+
+impl From<i64> for Cell {
+    fn from(value: i64) -> Cell {
+        Cell::Int(value)
+    }
+}
+
+impl From<f64> for Cell {
+    fn from(value: f64) -> Cell {
+        Cell::Float(value)
+    }
+}
+
+/// A column header and the number of decimals its floats are written with.
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    pub decimals: usize,
+}
+
+/// A titled table with typed cells; rows have one cell per column.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub title: String,
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Cell>>,
+
+    /// How decimal cells are rounded in every view.
+    pub rounding: RoundingMode,
+}
+
+impl Report {
+    /// Starts an empty table.
+    pub fn new(title: &str) -> Report {
+        Report { title: title.to_string(), ..Default::default() }
+    }
+
+    /// Adds a column whose floats are written with `decimals` decimals.
+    pub fn column(mut self, name: impl Into<String>, decimals: usize) -> Report {
+        self.columns.push(Column { name: name.into(), decimals });
+        self
+    }
+
+    /// Appends a row; it must have one cell per column.
+    pub fn push_row(&mut self, row: Vec<Cell>) {
+        assert_eq!(row.len(), self.columns.len(), "row width does not match the '{}' columns", self.title);
+        self.rows.push(row);
+    }
+
+    /// Appends a column filled with `values`, one per row.
+    pub fn push_column(&mut self, name: impl Into<String>, decimals: usize, values: Vec<Cell>) {
+        assert_eq!(values.len(), self.rows.len(), "column length does not match the '{}' rows", self.title);
+        self.columns.push(Column { name: name.into(), decimals });
+        for (row, value) in self.rows.iter_mut().zip(values) {
+            row.push(value);
+        }
+    }
+
+    /// Applies a rounding policy: its mode, and its precision for the columns it names (or for all of them).
+    pub fn with_rounding(mut self, policy: &RoundingPolicy) -> Report {
+        self.rounding = policy.mode;
+        for column in &mut self.columns {
+            column.decimals = policy.decimals(&column.name, column.decimals);
+        }
+        self
+    }
+
+    /// Text of the named column in each row (empty for numeric cells or an unknown column).
+    pub fn text_column(&self, name: &str) -> Vec<&str> {
+        let index = self.columns.iter().position(|c| c.name == name);
+        self.rows
+            .iter()
+            .map(|row| match index.map(|i| &row[i]) {
+                Some(Cell::Text(text)) => text.as_str(),
+                _ => "",
+            })
+            .collect()
+    }
+
+    /// The first `n` rows.
+    pub fn head(&self, n: usize) -> Report {
+        Report { rows: self.rows.iter().take(n).cloned().collect(), ..self.clone() }
+    }
+
+    /// The named columns, in the given order; unknown names are skipped.
+    pub fn select(&self, names: &[&str]) -> Report {
+        let indices: Vec<usize> = names
+            .iter()
+            .filter_map(|name| self.columns.iter().position(|c| c.name == *name))
+            .collect();
+
+        Report {
+            title: self.title.clone(),
+            columns: indices.iter().map(|i| self.columns[*i].clone()).collect(),
+            rows: self.rows.iter().map(|row| indices.iter().map(|i| row[*i].clone()).collect()).collect(),
+            rounding: self.rounding,
+        }
+    }
+
+    /// Cells formatted for display (at most `DISPLAY_DECIMALS` decimals).
+    fn display_rows(&self) -> Vec<Vec<String>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&self.columns)
+                    .map(|(cell, column)| cell.format(self.rounding, column.decimals, DISPLAY_DECIMALS))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Fixed-width text table: text columns left-aligned, numbers right-aligned.
+    pub fn to_terminal(&self) -> String {
+        self.to_terminal_painted(|_, _, text| text.to_string())
+    }
+
+    /// Fixed-width text table like `to_terminal`, with each padded cell passed through `paint` (column index,
+    /// cell, padded text), e.g. to color it.
+    pub fn to_terminal_painted(&self, paint: impl Fn(usize, &Cell, &str) -> String) -> String {
+        let cells = self.display_rows();
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| cells.iter().map(|row| row[i].chars().count()).fold(column.name.len(), usize::max))
+            .collect();
+        let numeric: Vec<bool> = (0..self.columns.len())
+            .map(|i| self.rows.first().is_some_and(|row| row[i].is_numeric()))
+            .collect();
+
+        let pad = |i: usize, value: &str| match numeric[i] {
+            true => format!("{:>width$}", value, width = widths[i]),
+            false => format!("{:<width$}", value, width = widths[i]),
+        };
+        let line = |padded: Vec<String>| padded.join("  ").trim_end().to_string() + "\n";
+
+        let mut out = line(self.columns.iter().enumerate().map(|(i, c)| pad(i, &c.name)).collect());
+        out.push_str(&"-".repeat(widths.iter().sum::<usize>() + 2 * widths.len().saturating_sub(1)));
+        out.push('\n');
+        for (row, cells) in self.rows.iter().zip(&cells) {
+            out.push_str(&line(cells.iter().enumerate().map(|(i, text)| paint(i, &row[i], &pad(i, text))).collect()));
+        }
+        out
+    }
+
+    /// GitHub-flavored Markdown table, with numeric columns right-aligned.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("| {} |\n", self.columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>().join(" | "));
+
+        let alignments: Vec<&str> = (0..self.columns.len())
+            .map(|i| match self.rows.first().is_some_and(|row| row[i].is_numeric()) {
+                true => "---:",
+                false => "---",
+            })
+            .collect();
+        out.push_str(&format!("| {} |\n", alignments.join(" | ")));
+
+        for row in self.display_rows() {
+            out.push_str(&format!("| {} |\n", row.join(" | ")));
+        }
+        out
+    }
+
+    /// HTML table (used for notebook display).
+    pub fn to_html(&self) -> String {
+        let mut out = String::from("<table>\n<tr>");
+        for column in &self.columns {
+            out.push_str(&format!("<th>{}</th>", column.name));
+        }
+        out.push_str("</tr>\n");
+
+        for row in self.display_rows() {
+            out.push_str("<tr>");
+            for value in row {
+                out.push_str(&format!("<td>{}</td>", value));
+            }
+            out.push_str("</tr>\n");
+        }
+
+        out.push_str("</table>\n");
+        out
+    }
+
+    /// Writes the table to a CSV output file at each column's published precision.
+    pub fn write_csv(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut wtr = WriterBuilder::new().from_path(path)?;
+        wtr.write_record(self.columns.iter().map(|c| c.name.as_str()))?;
+
+        for row in &self.rows {
+            let cells = row.iter().zip(&self.columns).map(|(cell, column)| {
+                cell.format(self.rounding, column.decimals, column.decimals)
+            });
+            wtr.write_record(cells)?;
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+/// Serializes as an array of row objects keyed by column name (the JSON view), with decimal cells rounded to
+/// their column's published precision.
+impl Serialize for Report {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Row<'a>(&'a [Column], &'a [Cell], RoundingMode);
+
+        impl Serialize for Row<'_> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut map = serializer.serialize_map(Some(self.0.len()))?;
+                for (column, cell) in self.0.iter().zip(self.1) {
+                    match cell {
+                        Cell::Text(text) => map.serialize_entry(&column.name, text)?,
+                        Cell::Int(value) => map.serialize_entry(&column.name, value)?,
+                        Cell::Float(value) => {
+                            map.serialize_entry(&column.name, &self.2.round(*value, column.decimals))?
+                        }
+                    }
+                }
+                map.end()
+            }
+        }
+
+        let mut seq = serializer.serialize_seq(Some(self.rows.len()))?;
+        for row in &self.rows {
+            seq.serialize_element(&Row(&self.columns, row, self.rounding))?;
+        }
+        seq.end()
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Report {
+        let mut report = Report::new("Sample").column("State", 0).column("Heat_Rate", 6).column("Rank", 0);
+        report.push_row(vec!["TX".into(), 9.5.into(), 2usize.into()]);
+        report.push_row(vec!["CA".into(), 7.25.into(), 1usize.into()]);
+        report
+    }
+
+    #[test]
+    fn test_views_agree() {
+        let report = sample();
+
+        assert_eq!(report.to_terminal(), "State  Heat_Rate  Rank\n----------------------\nTX         9.500     2\nCA         7.250     1\n");
+        assert_eq!(report.to_markdown(), "| State | Heat_Rate | Rank |\n| --- | ---: | ---: |\n| TX | 9.500 | 2 |\n| CA | 7.250 | 1 |\n");
+        assert_eq!(
+            serde_json::to_string(&report.head(1)).unwrap(),
+            r#"[{"State":"TX","Heat_Rate":9.5,"Rank":2}]"#
+        );
+        assert_eq!(report.select(&["Rank", "State"]).rows[1], [Cell::Int(1), Cell::from("CA")]);
+
+        // 9.99949 is published as 9.9995 and shown as 10.000, not as the 9.999 it rounds to directly
+        let mut rounded = Report::new("Rounded").column("Heat_Rate", 6);
+        rounded.push_row(vec![9.99949.into()]);
+        let rounded = rounded.with_rounding(&RoundingPolicy::parse(RoundingMode::HalfUp, &["4".to_string()]).unwrap());
+        assert_eq!(rounded.to_markdown(), "| Heat_Rate |\n| ---: |\n| 10.000 |\n");
+        assert_eq!(serde_json::to_string(&rounded).unwrap(), r#"[{"Heat_Rate":9.9995}]"#);
+    }
+}
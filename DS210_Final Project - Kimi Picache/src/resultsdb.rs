@@ -0,0 +1,241 @@
+// resultsdb.rs
+// This module accumulates the state results of every run in a SQLite database, so questions across runs
+// ("how did TX's delta move between data releases?") are one SQL query away, and runs read-only queries
+// against it. The layout is versioned with SQLite's `user_version`: older databases are migrated on open and
+// databases written by a newer release are refused.
+
+use std::error::Error;
+
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OpenFlags};
+
+use crate::analysis::StateEfficiency;
+use crate::hooks::RunSummary;
+use crate::report::{Cell, Report};
+
+/// Results database used when `--results-db` is not given to `query-db`.
+pub const DEFAULT_RESULTS_DB: &str = "results.sqlite";
+
+/// Version of the table layout, stored as the database's `user_version`. Bump it with a migration step in
+/// `prepare_schema` whenever a table or column changes.
+pub const RESULTS_SCHEMA_VERSION: i32 = 2;
+
+/// Layout of version 1; later versions are reached through the migration steps.
+const CREATE_TABLES: &str = "
+    CREATE TABLE runs (
+        id INTEGER PRIMARY KEY,
+        timestamp INTEGER NOT NULL,
+        version TEXT NOT NULL,
+        command TEXT NOT NULL,
+        label TEXT,
+        base_input TEXT NOT NULL,
+        compare_input TEXT NOT NULL
+    );
+    CREATE TABLE state_results (
+        run_id INTEGER NOT NULL REFERENCES runs (id),
+        state TEXT NOT NULL,
+        heat_rate_base REAL NOT NULL,
+        heat_rate_compare REAL NOT NULL,
+        delta REAL NOT NULL,
+        weighted_delta REAL NOT NULL,
+        percentile_base REAL NOT NULL,
+        percentile_compare REAL NOT NULL,
+        PRIMARY KEY (run_id, state)
+    );";
+
+/// Version 2: RFC 3339 run timestamps next to the Unix seconds. The local offset of earlier runs is unknown.
+const MIGRATE_TO_V2: &str = "
+    ALTER TABLE runs ADD COLUMN timestamp_utc TEXT;
+    ALTER TABLE runs ADD COLUMN timestamp_local TEXT;
+    UPDATE runs SET timestamp_utc = strftime('%Y-%m-%dT%H:%M:%SZ', timestamp, 'unixepoch');";
+
+/// Creates the tables of a new database, migrates an older one and refuses a newer or foreign one.
+/// # Arguments
+/// * `conn` - Open database
+/// * `path` - File name for the messages
+pub fn prepare_schema(conn: &Connection, path: &str) -> Result<(), Box<dyn Error>> {
+    let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > RESULTS_SCHEMA_VERSION {
+        return Err(format!(
+            "results database '{}' has schema version {}, newer than this release's {}; upgrade the tool or use \
+             another --results-db",
+            path, version, RESULTS_SCHEMA_VERSION
+        )
+        .into());
+    }
+
+    if version == RESULTS_SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    if version == 0 {
+        let tables: i64 =
+            tx.query_row("SELECT count(*) FROM sqlite_master WHERE type = 'table'", [], |row| row.get(0))?;
+        if tables > 0 {
+            return Err(format!("'{}' is a SQLite database but not a results database of this tool", path).into());
+        }
+        tx.execute_batch(CREATE_TABLES)?;
+    }
+    // Migrations from older layouts, one step per version, before the version is stamped
+    if version < 2 {
+        tx.execute_batch(MIGRATE_TO_V2)?;
+    }
+    tx.pragma_update(None, "user_version", RESULTS_SCHEMA_VERSION)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Opens (or creates) a results database for writing.
+pub fn open_results_db(path: &str) -> Result<Connection, Box<dyn Error>> {
+    let conn = Connection::open(path)?;
+    prepare_schema(&conn, path)?;
+    Ok(conn)
+}
+
+/// Stores one run and its state results in a single transaction.
+/// # Arguments
+/// * `conn` - Database prepared by `prepare_schema`
+/// * `summary` - Run whose first two inputs are the base and compare files
+/// * `changes` - The run's state efficiency changes
+/// # Returns
+/// * The new run's id
+pub fn record_run(
+    conn: &mut Connection,
+    summary: &RunSummary,
+    changes: &[StateEfficiency],
+) -> Result<i64, Box<dyn Error>> {
+    let input = |i: usize| summary.inputs.get(i).map_or("", |input| input.path.as_str()).to_string();
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO runs (timestamp, timestamp_utc, timestamp_local, version, command, label, base_input,
+         compare_input) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            summary.timestamp.unix,
+            summary.timestamp.utc,
+            summary.timestamp.local,
+            env!("CARGO_PKG_VERSION"),
+            summary.command,
+            summary.label,
+            input(0),
+            input(1),
+        ],
+    )?;
+    let run_id = tx.last_insert_rowid();
+
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO state_results (run_id, state, heat_rate_base, heat_rate_compare, delta, weighted_delta,
+             percentile_base, percentile_compare) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for change in changes {
+            insert.execute(params![
+                run_id,
+                change.state,
+                change.eff_2019,
+                change.eff_2020,
+                change.delta,
+                change.weighted_delta,
+                change.pctile_2019,
+                change.pctile_2020,
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(run_id)
+}
+
+/// Runs a read-only SQL statement and returns its rows as a table titled with the statement.
+/// # Returns
+/// * `Err` for statements that would modify the database
+pub fn query(conn: &Connection, sql: &str) -> Result<Report, Box<dyn Error>> {
+    let mut stmt = conn.prepare(sql)?;
+    if !stmt.readonly() {
+        return Err("query-db only runs read-only statements (SELECT, WITH, EXPLAIN, read-only PRAGMA)".into());
+    }
+
+    let mut report = Report::new(sql);
+    for name in stmt.column_names() {
+        report = report.column(name, 6);
+    }
+    let columns = stmt.column_count();
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let mut cells = Vec::with_capacity(columns);
+        for i in 0..columns {
+            cells.push(match row.get_ref(i)? {
+                ValueRef::Null => Cell::from(""),
+                ValueRef::Integer(value) => Cell::from(value),
+                ValueRef::Real(value) => Cell::from(value),
+                ValueRef::Text(text) => Cell::from(String::from_utf8_lossy(text).into_owned()),
+                ValueRef::Blob(blob) => Cell::from(format!("<{} bytes>", blob.len())),
+            });
+        }
+        report.push_row(cells);
+    }
+    Ok(report)
+}
+
+/// Opens a results database read-only and runs `sql` on it; see `query`.
+pub fn query_results_db(path: &str, sql: &str) -> Result<Report, Box<dyn Error>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("cannot open results database '{}': {}", path, e))?;
+    query(&conn, sql)
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::{MegawattHours, Mmbtu};
+    use std::collections::HashMap;
+
+    use crate::analysis::compute_efficiency_changes;
+    use crate::cleaning::StateStats;
+
+    #[test]
+    fn test_record_and_query_runs() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        prepare_schema(&conn, ":memory:").unwrap();
+        prepare_schema(&conn, ":memory:").unwrap();
+
+        let mut summary = RunSummary { command: "analyze".to_string(), ..Default::default() };
+        summary.input("2019.csv", 10);
+        summary.input("2020.csv", 12);
+        let stats = |fuel: f64| StateStats { total_fuel: Mmbtu(fuel), total_gen: MegawattHours(100.0) };
+        let base = HashMap::from([("AA".to_string(), stats(1000.0)), ("BB".to_string(), stats(900.0))]);
+        let compare = HashMap::from([("AA".to_string(), stats(950.0)), ("BB".to_string(), stats(925.0))]);
+        let mut changes = compute_efficiency_changes(&base, &compare);
+        changes.sort_by(|a, b| a.state.cmp(&b.state));
+        record_run(&mut conn, &summary, &changes).unwrap();
+        let run = record_run(&mut conn, &summary, &changes[..1]).unwrap();
+        assert_eq!(run, 2);
+
+        let report = query(
+            &conn,
+            "SELECT r.id, r.base_input, s.state, s.delta FROM state_results s JOIN runs r ON r.id = s.run_id
+             WHERE s.state = 'AA' ORDER BY r.id",
+        )
+        .unwrap();
+        let columns: Vec<&str> = report.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(columns, ["id", "base_input", "state", "delta"]);
+        assert_eq!(report.rows.len(), 2);
+        assert_eq!(report.rows[1][..3], [Cell::Int(2), Cell::from("2019.csv"), Cell::from("AA")]);
+        assert_eq!(report.rows[1][3], Cell::Float(-0.5));
+        assert!(query(&conn, "DELETE FROM runs").is_err());
+
+        // A version 1 database gains the RFC 3339 columns, filled in from the Unix seconds
+        let old = Connection::open_in_memory().unwrap();
+        old.execute_batch(CREATE_TABLES).unwrap();
+        old.execute_batch(
+            "INSERT INTO runs VALUES (1, 86400, '0.1.0', 'analyze', NULL, 'a.csv', 'b.csv'); PRAGMA user_version = 1;",
+        )
+        .unwrap();
+        prepare_schema(&old, ":memory:").unwrap();
+        let utc: String = old.query_row("SELECT timestamp_utc FROM runs", [], |row| row.get(0)).unwrap();
+        assert_eq!(utc, "1970-01-02T00:00:00Z");
+
+        conn.pragma_update(None, "user_version", RESULTS_SCHEMA_VERSION + 1).unwrap();
+        assert!(prepare_schema(&conn, ":memory:").is_err());
+    }
+}
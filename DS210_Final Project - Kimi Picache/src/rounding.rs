@@ -0,0 +1,125 @@
+// rounding.rs
+// This module rounds published numbers. Values are rounded on their shortest decimal form (the digits a CSV
+// shows), not on the binary float, so 2.675 rounds to 2.68 like a spreadsheet's ROUND, and a table's terminal,
+// CSV and JSON views round the same published value with the same rule and therefore agree digit for digit.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+/// How a value exactly halfway between two roundings is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum RoundingMode {
+    /// Ties away from zero (2.5 → 3, -2.5 → -3), as in spreadsheets.
+    #[default]
+    HalfUp,
+    /// Ties to the even digit (2.5 → 2, 3.5 → 4), the banker's or IEEE rule.
+    HalfEven,
+}
+
+impl RoundingMode {
+    /// Rounds `value` to `decimals` decimals; non-finite values are returned unchanged.
+    pub fn round(self, value: f64, decimals: usize) -> f64 {
+        if !value.is_finite() {
+            return value;
+        }
+        let text = value.abs().to_string();
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        if fraction.len() <= decimals {
+            return value;
+        }
+
+        let (kept, dropped) = fraction.split_at(decimals);
+        let mut digits: Vec<u8> = whole.bytes().chain(kept.bytes()).map(|b| b - b'0').collect();
+        let tie = dropped.as_bytes()[0] == b'5' && dropped[1..].bytes().all(|b| b == b'0');
+        let up = match self {
+            _ if !tie => dropped.as_bytes()[0] >= b'5',
+            RoundingMode::HalfUp => true,
+            RoundingMode::HalfEven => digits.last().is_some_and(|d| d % 2 == 1),
+        };
+        if up {
+            let nines = digits.iter().rev().take_while(|d| **d == 9).count();
+            let end = digits.len();
+            digits[end - nines..].fill(0);
+            match end.checked_sub(nines + 1) {
+                Some(i) => digits[i] += 1,
+                None => digits.insert(0, 1),
+            }
+        }
+
+        let split = digits.len() - decimals;
+        let text: String = digits.iter().map(|d| char::from(b'0' + d)).collect();
+        let rounded: f64 = format!("{}.{}", &text[..split], &text[split..]).parse().unwrap_or(value.abs());
+        rounded.copysign(value)
+    }
+
+    /// Formats `value` rounded to `decimals` decimals, with exactly that many decimals.
+    pub fn format(self, value: f64, decimals: usize) -> String {
+        format!("{:.*}", decimals, self.round(value, decimals))
+    }
+}
+
+/// The rounding rule and any per-column precision overrides for the result tables.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoundingPolicy {
+    pub mode: RoundingMode,
+
+    /// Decimals for every decimal column, replacing each column's own default.
+    pub all: Option<usize>,
+
+    /// Decimals for single columns, by header; these win over `all`.
+    pub columns: BTreeMap<String, usize>,
+}
+
+impl RoundingPolicy {
+    /// Parses the `--precision` values: "COLUMN=DIGITS" for one column, or "DIGITS" for every decimal column.
+    pub fn parse(mode: RoundingMode, specs: &[String]) -> Result<RoundingPolicy, Box<dyn Error>> {
+        let mut policy = RoundingPolicy { mode, ..Default::default() };
+        for spec in specs {
+            let (column, digits) = match spec.rsplit_once('=') {
+                Some((column, digits)) => (Some(column.trim()), digits),
+                None => (None, spec.as_str()),
+            };
+            let digits: usize =
+                digits.trim().parse().map_err(|_| format!("--precision '{}': invalid number of decimals", spec))?;
+            match column {
+                Some(column) => {
+                    policy.columns.insert(column.to_string(), digits);
+                }
+                None => policy.all = Some(digits),
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Decimals a column is published with, given its own default.
+    pub fn decimals(&self, column: &str, default: usize) -> usize {
+        self.columns.get(column).copied().or(self.all).unwrap_or(default)
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounding_modes() {
+        // 2.675 is stored just below 2.675, so `{:.2}` alone gives 2.67
+        assert_eq!(RoundingMode::HalfUp.format(2.675, 2), "2.68");
+        assert_eq!(RoundingMode::HalfEven.format(2.665, 2), "2.66");
+        assert_eq!(RoundingMode::HalfEven.format(2.675, 2), "2.68");
+        assert_eq!((RoundingMode::HalfUp.round(-0.5, 0), RoundingMode::HalfEven.round(-0.5, 0)), (-1.0, -0.0));
+        assert_eq!(RoundingMode::HalfUp.format(9.9996, 3), "10.000");
+        assert_eq!(RoundingMode::HalfEven.round(1.23456, 3), 1.235);
+        assert_eq!(RoundingMode::HalfUp.round(7.25, 6), 7.25);
+
+        let specs = ["4".to_string(), "Delta_Efficiency = 2".to_string()];
+        let policy = RoundingPolicy::parse(RoundingMode::HalfEven, &specs).unwrap();
+        assert_eq!((policy.decimals("Delta_Efficiency", 6), policy.decimals("Abs_Change", 6)), (2, 4));
+        assert!(RoundingPolicy::parse(RoundingMode::HalfUp, &["Delta=x".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,184 @@
+// sanity.rs
+// This module checks cleaned totals against physical and year-over-year bounds. A unit or column mix-up (kWh read
+// as MWh, a physical quantity column read as MMBtu) still gives heat rates that rank and compare without complaint;
+// the implied national heat rate, a state making more electricity than its fuel could give (see `energybalance`) or
+// a state total that jumps several-fold from the prior year gives it away.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::cleaning::StateStats;
+use crate::energybalance::{check_balance, THERMAL_EFFICIENCY_BOUNDS};
+use crate::stats::CompensatedSum;
+
+/// Plausible national heat rate of fossil generation, MMBtu/MWh (even the best combined cycle plants need about
+/// 6, and no fleet averages above 15).
+pub const NATIONAL_HEAT_RATE_RANGE: (f64, f64) = (5.0, 15.0);
+
+/// Largest plausible change of a state's fuel or generation total from one year to the next (either way).
+pub const MAX_YEAR_OVER_YEAR_RATIO: f64 = 3.0;
+
+/// A failed sanity check.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SanityIssue {
+    /// Check that failed: "national heat rate", "energy balance" or "year-over-year total".
+    pub check: &'static str,
+
+    /// Year (or input label) the check failed for.
+    pub year: String,
+
+    /// State, or "US" for the national check.
+    pub subject: String,
+
+    pub message: String,
+}
+
+/// National heat rate implied by state totals (total MMBtu / total MWh), or `None` without generation.
+pub fn national_heat_rate(stats: &HashMap<String, StateStats>) -> Option<f64> {
+    let (mut fuel, mut r#gen) = (CompensatedSum::default(), CompensatedSum::default());
+    for s in stats.values() {
+        fuel.add(s.total_fuel.0);
+        r#gen.add(s.total_gen.0);
+    }
+    (r#gen.total() > 0.0).then(|| fuel.total() / r#gen.total())
+}
+
+/// Checks that the implied national heat rate lies in `NATIONAL_HEAT_RATE_RANGE`.
+pub fn check_national_heat_rate(label: &str, stats: &HashMap<String, StateStats>) -> Option<SanityIssue> {
+    let (low, high) = NATIONAL_HEAT_RATE_RANGE;
+    let heat_rate = national_heat_rate(stats)?;
+    if (low..=high).contains(&heat_rate) {
+        return None;
+    }
+    Some(SanityIssue {
+        check: "national heat rate",
+        year: label.to_string(),
+        subject: "US".to_string(),
+        message: format!(
+            "{}: implied national heat rate is {:.3} MMBtu/MWh, outside the plausible {}-{}; \
+             check the fuel and generation units and columns",
+            label, heat_rate, low, high
+        ),
+    })
+}
+
+/// Checks that each state's implied thermal efficiency (electricity out / fuel in) lies in
+/// `THERMAL_EFFICIENCY_BOUNDS`.
+pub fn check_energy_balance(label: &str, stats: &HashMap<String, StateStats>) -> Vec<SanityIssue> {
+    let (floor, ceiling) = THERMAL_EFFICIENCY_BOUNDS;
+    let mut states: Vec<&String> = stats.keys().collect();
+    states.sort();
+
+    let mut issues = Vec::new();
+    for state in states {
+        let (fuel, r#gen) = (stats[state].total_fuel, stats[state].total_gen);
+        let Some(flag) = check_balance(fuel.0, r#gen.0) else {
+            continue;
+        };
+        issues.push(SanityIssue {
+            check: "energy balance",
+            year: label.to_string(),
+            subject: state.clone(),
+            message: format!(
+                "{}: {} reports {:.0} MWh from {:.0} MMBtu of fuel ({}; plausible efficiency {:.0}-{:.0}%)",
+                label,
+                state,
+                r#gen.0,
+                fuel.0,
+                flag.label(),
+                floor * 100.0,
+                ceiling * 100.0
+            ),
+        });
+    }
+    issues
+}
+
+/// Checks that no state's fuel or generation total changed by more than `MAX_YEAR_OVER_YEAR_RATIO` times from
+/// the prior year. States missing (or zero) in either year are left to the coverage checks.
+pub fn check_year_over_year(
+    labels: [&str; 2],
+    prior: &HashMap<String, StateStats>,
+    current: &HashMap<String, StateStats>,
+) -> Vec<SanityIssue> {
+    let mut issues = Vec::new();
+    let mut states: Vec<&String> = current.keys().filter(|state| prior.contains_key(*state)).collect();
+    states.sort();
+
+    for state in states {
+        let (before, after) = (&prior[state], &current[state]);
+        for (quantity, unit, old, new) in [
+            ("fuel", "MMBtu", before.total_fuel.0, after.total_fuel.0),
+            ("generation", "MWh", before.total_gen.0, after.total_gen.0),
+        ] {
+            if old <= 0.0 || new <= 0.0 {
+                continue;
+            }
+            let ratio = new / old;
+            if !(1.0 / MAX_YEAR_OVER_YEAR_RATIO..=MAX_YEAR_OVER_YEAR_RATIO).contains(&ratio) {
+                issues.push(SanityIssue {
+                    check: "year-over-year total",
+                    year: labels[1].to_string(),
+                    subject: state.clone(),
+                    message: format!(
+                        "{}: {} {} went from {:.0} {} in {} to {:.0} ({:.2}x)",
+                        labels[1], state, quantity, old, unit, labels[0], new, ratio
+                    ),
+                });
+            }
+        }
+    }
+    issues
+}
+
+/// Runs every check over consecutive inputs.
+/// # Arguments
+/// * `years` - `(label, state totals)` per input, oldest first; totals are plain sums, whatever `--aggregation`
+/// # Returns
+/// * The failed checks, national ones first for each year, then the state energy balances
+pub fn run_sanity_checks(years: &[(&str, &HashMap<String, StateStats>)]) -> Vec<SanityIssue> {
+    let mut issues = Vec::new();
+    for (i, (label, stats)) in years.iter().enumerate() {
+        issues.extend(check_national_heat_rate(label, stats));
+        issues.extend(check_energy_balance(label, stats));
+        if i > 0 {
+            let (prior_label, prior) = years[i - 1];
+            issues.extend(check_year_over_year([prior_label, label], prior, stats));
+        }
+    }
+    issues
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::{MegawattHours, Mmbtu};
+
+    fn totals(rows: &[(&str, f64, f64)]) -> HashMap<String, StateStats> {
+        rows.iter()
+            .map(|(state, fuel, r#gen)| {
+                (state.to_string(), StateStats { total_fuel: Mmbtu(*fuel), total_gen: MegawattHours(*r#gen) })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_flags_unit_mixups() {
+        let prior = totals(&[("AA", 1000.0, 100.0), ("BB", 900.0, 100.0)]);
+        assert_eq!(national_heat_rate(&prior), Some(9.5));
+        assert!(run_sanity_checks(&[("2019", &prior)]).is_empty());
+
+        // Generation read in kWh for one state: the national rate drops far below 5, the state makes more energy
+        // than it burns and jumps 1000x
+        let current = totals(&[("AA", 1000.0, 100.0), ("BB", 900.0, 100_000.0), ("CC", 10.0, 1.0)]);
+        let issues = run_sanity_checks(&[("2019", &prior), ("2020", &current)]);
+        assert_eq!(issues.len(), 3);
+        assert_eq!((issues[0].check, issues[0].subject.as_str()), ("national heat rate", "US"));
+        assert_eq!((issues[1].check, issues[1].subject.as_str()), ("energy balance", "BB"));
+        assert!(issues[1].message.contains("(more energy out than in; plausible efficiency 2-65%)"));
+        assert_eq!((issues[2].subject.as_str(), issues[2].year.as_str()), ("BB", "2020"));
+        assert!(issues[2].message.contains("generation went from 100 MWh in 2019 to 100000 (1000.00x)"));
+    }
+}
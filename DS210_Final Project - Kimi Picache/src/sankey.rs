@@ -0,0 +1,160 @@
+// sankey.rs
+// This module exports fuel group → net generation flows per state and year as JSON for d3-sankey and Plotly.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+
+#[cfg(feature = "schema")]
+use schemars::{schema_for, JsonSchema};
+use serde::Serialize;
+
+use crate::cleaning::CleanRecord;
+use crate::pivot::aggregate_by_state_fuel;
+
+/// Name of the sink node every fuel flows into.
+const GENERATION_NODE: &str = "Net Generation";
+
+/// A d3-sankey node.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct SankeyNode {
+    pub name: String,
+}
+
+/// A d3-sankey link between node indices; `value` is net generation in MWh.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct SankeyLink {
+    pub source: usize,
+    pub target: usize,
+    pub value: f64,
+}
+
+/// Plotly `node` attribute.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct PlotlyNode {
+    pub label: Vec<String>,
+}
+
+/// Plotly `link` attribute (parallel arrays).
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct PlotlyLink {
+    pub source: Vec<usize>,
+    pub target: Vec<usize>,
+    pub value: Vec<f64>,
+}
+
+/// The same flows shaped as a Plotly sankey trace (`{type: "sankey", node, link}`).
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct PlotlySankey {
+    pub node: PlotlyNode,
+    pub link: PlotlyLink,
+}
+
+/// Fuel flows for one state and year: `nodes`/`links` for d3-sankey, `plotly` for Plotly.
+#[derive(Debug, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+pub struct StateSankey {
+    pub state: String,
+    pub year: i32,
+    pub nodes: Vec<SankeyNode>,
+    pub links: Vec<SankeyLink>,
+    pub plotly: PlotlySankey,
+}
+
+/// Builds one sankey per state for a year. Fuel groups with zero or negative net generation
+/// (e.g. pumped storage) are left out because sankey links cannot carry negative flows.
+/// # Arguments
+/// * `records` - Cleaned rows for the year
+/// * `year` - Data year
+/// # Returns
+/// * `Vec<StateSankey>` sorted by state
+pub fn build_sankeys(records: &[CleanRecord], year: i32) -> Vec<StateSankey> {
+    let mut output = Vec::new();
+
+    for (state, fuels) in aggregate_by_state_fuel(records) {
+        let flows: Vec<(&str, f64)> = fuels
+            .iter()
+            .filter(|(_, stats)| stats.total_gen.0 > 0.0)
+            .map(|(group, stats)| (group.label(), stats.total_gen.0))
+            .collect();
+
+        let mut labels: Vec<String> = flows.iter().map(|(label, _)| label.to_string()).collect();
+        labels.push(GENERATION_NODE.to_string());
+        let target = flows.len();
+
+        let links: Vec<SankeyLink> = flows
+            .iter()
+            .enumerate()
+            .map(|(source, (_, value))| SankeyLink { source, target, value: *value })
+            .collect();
+
+        let plotly = PlotlySankey {
+            node: PlotlyNode { label: labels.clone() },
+            link: PlotlyLink {
+                source: links.iter().map(|l| l.source).collect(),
+                target: links.iter().map(|l| l.target).collect(),
+                value: links.iter().map(|l| l.value).collect(),
+            },
+        };
+
+        output.push(StateSankey {
+            state,
+            year,
+            nodes: labels.into_iter().map(|name| SankeyNode { name }).collect(),
+            links,
+            plotly,
+        });
+    }
+
+    output
+}
+
+/// Writes the sankey data as a JSON array to `path`.
+pub fn write_sankey_json(path: &str, data: &[StateSankey]) -> Result<(), Box<dyn Error>> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, data)?;
+    Ok(())
+}
+
+/// Writes a JSON Schema for the sankey JSON array, generated from the serialized types.
+#[cfg(feature = "schema")]
+pub fn write_sankey_schema(path: &str) -> Result<(), Box<dyn Error>> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &schema_for!(Vec<StateSankey>))?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_build_sankeys() {
+        // Pumped storage (negative hydro) cannot be a flow, so only coal and gas feed the generation node
+        let records = [
+            record("1", "AA", "COL", 0.0, 100.0),
+            record("1", "AA", "NG", 0.0, 50.0),
+            record("1", "AA", "NG", 0.0, 25.0),
+            record("1", "AA", "HPS", 0.0, -10.0),
+            record("1", "BB", "WND", 0.0, 40.0),
+        ];
+        let sankeys = build_sankeys(&records, 2020);
+        assert_eq!(sankeys.len(), 2);
+        let aa = &sankeys[0];
+        let nodes: Vec<&str> = aa.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(nodes, ["Coal", "Natural Gas", GENERATION_NODE]);
+        assert_eq!(aa.links[1].source, 1);
+        assert_eq!(aa.links[1].target, 2);
+        assert_eq!(aa.links[1].value, 75.0);
+        assert_eq!(aa.plotly.node.label, nodes);
+        assert_eq!(aa.plotly.link.value, [100.0, 75.0]);
+        assert_eq!((sankeys[1].state.as_str(), sankeys[1].year), ("BB", 2020));
+    }
+}
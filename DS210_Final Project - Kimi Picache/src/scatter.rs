@@ -0,0 +1,68 @@
+// scatter.rs
+// This module exports per-plant (generation, fuel) pairs by state and year, with a fitted line per state,
+// so users can build their own scatter plots.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use csv::WriterBuilder;
+
+use crate::cleaning::{aggregate_by_plant, CleanRecord, PlantStats};
+use crate::stats::{linear_regression, LinearFit};
+
+/// Writes the plant points and the per-state fits for each year.
+/// # Arguments
+/// * `points_path` - Output CSV for the plant-level pairs
+/// * `fits_path` - Output CSV for the per-state line fits
+/// * `years` - (year, cleaned rows) pairs
+pub fn write_scatter_csvs(
+    points_path: &str,
+    fits_path: &str,
+    years: &[(i32, &[CleanRecord])],
+) -> Result<(), Box<dyn Error>> {
+    let mut points = WriterBuilder::new().from_path(points_path)?;
+    points.write_record(["State", "Year", "Plant_Id", "Plant_Name", "Net_Generation_MWh", "Total_Fuel_MMBtu"])?;
+
+    let mut fits = WriterBuilder::new().from_path(fits_path)?;
+    fits.write_record(["State", "Year", "Plants", "Slope", "Intercept", "R2"])?;
+
+    for (year, records) in years {
+        let mut by_state: BTreeMap<String, Vec<PlantStats>> = BTreeMap::new();
+        for plant in aggregate_by_plant(records.iter()).into_values() {
+            by_state.entry(plant.state.clone()).or_default().push(plant);
+        }
+
+        for (state, mut plants) in by_state {
+            plants.sort_by(|a, b| a.plant_id.cmp(&b.plant_id));
+
+            for plant in &plants {
+                points.write_record([
+                    &state,
+                    &year.to_string(),
+                    &plant.plant_id,
+                    &plant.plant_name,
+                    &format!("{:.6}", plant.total_gen),
+                    &format!("{:.6}", plant.total_fuel),
+                ])?;
+            }
+
+            let xs: Vec<f64> = plants.iter().map(|p| p.total_gen).collect();
+            let ys: Vec<f64> = plants.iter().map(|p| p.total_fuel).collect();
+            let fit: Option<LinearFit> = linear_regression(&xs, &ys);
+            let fmt = |v: Option<f64>| v.map(|v| format!("{:.6}", v)).unwrap_or_default();
+
+            fits.write_record([
+                &state,
+                &year.to_string(),
+                &plants.len().to_string(),
+                &fmt(fit.map(|f| f.slope)),
+                &fmt(fit.map(|f| f.intercept)),
+                &fmt(fit.map(|f| f.r_squared)),
+            ])?;
+        }
+    }
+
+    points.flush()?;
+    fits.flush()?;
+    Ok(())
+}
@@ -0,0 +1,239 @@
+// scenarios.rs
+// This module runs what-if heat rate scenarios: every plant of a fuel group brought down to a target heat rate,
+// either a fixed value or a quantile of the national fleet (e.g. "all coal plants at the fleet's best quartile"),
+// with the fuel and CO2 each state would have saved at its actual generation.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{aggregate_by_plant, CleanRecord, PlantStats};
+use crate::emissions::emission_factors;
+use crate::frontier::eligible_heat_rate;
+use crate::fuels::FuelGroup;
+use crate::stats::quantile;
+use crate::units::{KG_PER_TONNE, MMBTU_PER_MWH};
+
+/// Heat rate a scenario brings plants down to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScenarioTarget {
+    /// A fixed heat rate (MMBtu/MWh).
+    HeatRate(f64),
+
+    /// A quantile (0-1) of the plant heat rates of the fuel group nationally; 0.25 is the best quartile.
+    Quantile(f64),
+}
+
+/// A what-if scenario, parsed from FUEL=TARGET: FUEL is coal, natural-gas (or gas), petroleum or other-gas;
+/// TARGET is a heat rate in MMBtu/MWh ("7.5"), a fleet percentile ("p25") or "best-quartile" / "median".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    pub fuel_group: FuelGroup,
+    pub target: ScenarioTarget,
+}
+
+impl FromStr for Scenario {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (fuel, target) = s.split_once('=').ok_or_else(|| format!("scenario '{}' is not FUEL=TARGET", s))?;
+        let fuel_group = match fuel.trim().to_ascii_lowercase().as_str() {
+            "coal" => FuelGroup::Coal,
+            "natural-gas" | "gas" => FuelGroup::NaturalGas,
+            "petroleum" => FuelGroup::Petroleum,
+            "other-gas" => FuelGroup::OtherGas,
+            other => return Err(format!("unknown scenario fuel '{}' (coal, natural-gas, petroleum, other-gas)", other)),
+        };
+
+        let target = match target.trim().to_ascii_lowercase().as_str() {
+            "best-quartile" => ScenarioTarget::Quantile(0.25),
+            "median" => ScenarioTarget::Quantile(0.5),
+            target => match target.strip_prefix('p') {
+                Some(percentile) => match percentile.parse::<f64>() {
+                    Ok(p) if (0.0..=100.0).contains(&p) => ScenarioTarget::Quantile(p / 100.0),
+                    _ => return Err(format!("invalid percentile '{}' in scenario '{}' (p0 to p100)", target, s)),
+                },
+                None => match target.parse::<f64>() {
+                    Ok(heat_rate) if heat_rate >= MMBTU_PER_MWH && heat_rate.is_finite() => {
+                        ScenarioTarget::HeatRate(heat_rate)
+                    }
+                    _ => {
+                        return Err(format!(
+                            "invalid target '{}' in scenario '{}' (a heat rate of at least {} MMBtu/MWh, pNN, \
+                             best-quartile or median)",
+                            target, s, MMBTU_PER_MWH
+                        ));
+                    }
+                },
+            },
+        };
+        Ok(Scenario { fuel_group, target })
+    }
+}
+
+impl fmt::Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.target {
+            ScenarioTarget::HeatRate(heat_rate) => write!(f, "{} at {} MMBtu/MWh", self.fuel_group.label(), heat_rate),
+            ScenarioTarget::Quantile(q) => write!(f, "{} at fleet p{}", self.fuel_group.label(), q * 100.0),
+        }
+    }
+}
+
+/// Savings of one scenario in one state.
+#[derive(Debug, Serialize)]
+pub struct ScenarioSavings {
+    pub scenario: String,
+    pub state: String,
+
+    /// Heat rate the plants are brought down to (MMBtu/MWh).
+    pub target_heat_rate: f64,
+
+    /// Eligible plants of the fuel group in the state, and those above the target.
+    pub plants: usize,
+    pub plants_improved: usize,
+
+    /// Generation-weighted heat rate of the plants, actual and under the scenario (MMBtu/MWh).
+    pub heat_rate: f64,
+    pub scenario_heat_rate: f64,
+
+    /// Fuel saved at the plants' actual generation (MMBtu), and the CO2 it would have emitted (metric tons).
+    pub fuel_saved: f64,
+    pub co2_saved_tonnes: f64,
+}
+
+/// Heat rate of the scenario target: the fixed value, or the quantile of the heat rates of the fuel group's
+/// eligible plants (see `frontier::eligible_heat_rate`). `None` when the fuel group has no eligible plant.
+pub fn target_heat_rate(records: &[CleanRecord], scenario: &Scenario) -> Option<f64> {
+    match scenario.target {
+        ScenarioTarget::HeatRate(heat_rate) => Some(heat_rate),
+        ScenarioTarget::Quantile(q) => {
+            let in_group = records.iter().filter(|r| FuelGroup::from_aer(&r.aer_fuel) == scenario.fuel_group);
+            let plants = aggregate_by_plant(in_group);
+            let heat_rates: Vec<f64> = plants.values().filter_map(eligible_heat_rate).collect();
+            quantile(&heat_rates, q)
+        }
+    }
+}
+
+/// Runs a scenario on one year: each eligible plant of the fuel group above the target is brought down to it
+/// at its actual generation; plants already below the target are left as they are. CO2 is priced at each plant's
+/// own fuel mix within the group.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// * `scenario` - Fuel group and target
+/// # Returns
+/// * `Vec<ScenarioSavings>` sorted by state; empty when the target cannot be resolved
+pub fn run_scenario(records: &[CleanRecord], scenario: &Scenario) -> Vec<ScenarioSavings> {
+    let Some(target) = target_heat_rate(records, scenario) else {
+        return Vec::new();
+    };
+
+    let group_records: Vec<&CleanRecord> =
+        records.iter().filter(|r| FuelGroup::from_aer(&r.aer_fuel) == scenario.fuel_group).collect();
+    let mut co2_kg: HashMap<&str, f64> = HashMap::new();
+    for record in &group_records {
+        *co2_kg.entry(&record.plant_id).or_default() += record.fuel * emission_factors(&record.aer_fuel).co2_kg;
+    }
+
+    let mut by_state: BTreeMap<String, Vec<(PlantStats, f64)>> = BTreeMap::new();
+    for plant in aggregate_by_plant(group_records.iter().copied()).into_values() {
+        if let Some(heat_rate) = eligible_heat_rate(&plant) {
+            by_state.entry(plant.state.clone()).or_default().push((plant, heat_rate));
+        }
+    }
+
+    by_state
+        .into_iter()
+        .map(|(state, plants)| {
+            let total_fuel: f64 = plants.iter().map(|(p, _)| p.total_fuel).sum();
+            let total_gen: f64 = plants.iter().map(|(p, _)| p.total_gen).sum();
+            let (mut improved, mut fuel_saved, mut co2_saved) = (0, 0.0, 0.0);
+            for (plant, heat_rate) in plants.iter().filter(|(_, hr)| *hr > target) {
+                let saved = (heat_rate - target) * plant.total_gen;
+                improved += 1;
+                fuel_saved += saved;
+                co2_saved += saved * co2_kg.get(plant.plant_id.as_str()).copied().unwrap_or(0.0) / plant.total_fuel;
+            }
+
+            ScenarioSavings {
+                scenario: scenario.to_string(),
+                state,
+                target_heat_rate: target,
+                plants: plants.len(),
+                plants_improved: improved,
+                heat_rate: total_fuel / total_gen,
+                scenario_heat_rate: (total_fuel - fuel_saved) / total_gen,
+                fuel_saved,
+                co2_saved_tonnes: co2_saved / KG_PER_TONNE,
+            }
+        })
+        .collect()
+}
+
+/// Writes the scenario savings to a CSV output file.
+pub fn write_scenarios_csv(path: &str, data: &[ScenarioSavings]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record([
+        "Scenario",
+        "State",
+        "Target_Heat_Rate",
+        "Plants",
+        "Plants_Improved",
+        "Heat_Rate",
+        "Scenario_Heat_Rate",
+        "Fuel_Saved_MMBtu",
+        "CO2_Saved_Tonnes",
+    ])?;
+
+    for item in data {
+        wtr.write_record([
+            &item.scenario,
+            &item.state,
+            &format!("{:.6}", item.target_heat_rate),
+            &item.plants.to_string(),
+            &item.plants_improved.to_string(),
+            &format!("{:.6}", item.heat_rate),
+            &format!("{:.6}", item.scenario_heat_rate),
+            &format!("{:.6}", item.fuel_saved),
+            &format!("{:.6}", item.co2_saved_tonnes),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_best_quartile_coal_scenario() {
+        let scenario: Scenario = "coal=best-quartile".parse().unwrap();
+        assert_eq!(scenario, "COAL=p25".parse().unwrap());
+        assert!("coal=2".parse::<Scenario>().is_err());
+        assert!("wind=p25".parse::<Scenario>().is_err());
+
+        // Coal heat rates 9, 10, 11 and 13: the best quartile is 9.75
+        let records = [
+            record("1", "AA", "COL", 90_000.0, 10_000.0),
+            record("2", "AA", "COL", 100_000.0, 10_000.0),
+            record("3", "BB", "COL", 110_000.0, 10_000.0),
+            record("4", "BB", "COL", 130_000.0, 10_000.0),
+            record("5", "BB", "NG", 70_000.0, 10_000.0),
+        ];
+        let savings = run_scenario(&records, &scenario);
+        assert_eq!(savings.len(), 2);
+        assert_eq!(savings[0].target_heat_rate, 9.75);
+        assert_eq!((savings[0].plants, savings[0].plants_improved, savings[0].fuel_saved), (2, 1, 2_500.0));
+        assert_eq!((savings[1].heat_rate, savings[1].fuel_saved), (12.0, 12_500.0 + 32_500.0));
+        assert!((savings[1].co2_saved_tonnes - 45_000.0 * 95.52 / 1000.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,186 @@
+// search.rs
+// This module looks plants up by a loose query for ad-hoc checks during an analysis: a plant code matches
+// exactly, and a name matches when the query's words start words of the name ("martin lake"), appear in it, or
+// are within a few typos of them ("martn lake"). Each match lists the plant's totals in every loaded year.
+
+use std::collections::BTreeMap;
+
+use crate::cleaning::{aggregate_by_plant, CleanRecord};
+use crate::join::JoinKey;
+use crate::units::HeatRateUnit;
+
+/// Lowest word similarity (0-1, from the edit distance) that still counts as a typo of a name word.
+pub const MIN_WORD_SIMILARITY: f64 = 0.75;
+
+/// One plant's totals in one year.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlantYear {
+    pub fuel: f64,
+    pub generation: f64,
+}
+
+impl PlantYear {
+    /// Heat rate in MMBtu/MWh, or `None` without positive generation.
+    pub fn heat_rate(&self) -> Option<f64> {
+        (self.generation > 0.0).then(|| self.fuel / self.generation)
+    }
+}
+
+/// A plant matching the query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlantMatch {
+    pub plant_id: String,
+
+    /// Name and state as reported in the latest year the plant appears in.
+    pub plant_name: String,
+    pub state: String,
+
+    /// How well the plant matches (1 for an exact code or name).
+    pub score: f64,
+
+    /// Totals per loaded year, `None` where the plant has no rows.
+    pub years: Vec<Option<PlantYear>>,
+}
+
+/// Lower-case alphanumeric words of a name or query.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_lowercase).collect()
+}
+
+/// Similarity of two words: 1 minus their edit distance over the longer length.
+fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    1.0 - row[b.len()] as f64 / a.len().max(b.len()).max(1) as f64
+}
+
+/// Score of a plant for a query, or `None` when it does not match.
+/// # Returns
+/// * 1 for the plant code or the full name, 0.9 when every query word starts a name word, 0.8 when the query
+///   appears inside the name, otherwise up to 0.7 when every query word is a close typo of a name word
+pub fn match_score(query: &str, plant_id: &str, plant_name: &str) -> Option<f64> {
+    let (query_words, name_words) = (words(query), words(plant_name));
+    if query_words.is_empty() {
+        return None;
+    }
+    if JoinKey::PlantId.normalize(query) == JoinKey::PlantId.normalize(plant_id) || query_words == name_words {
+        return Some(1.0);
+    }
+    if query_words.iter().all(|q| name_words.iter().any(|n| n.starts_with(q.as_str()))) {
+        return Some(0.9);
+    }
+    if name_words.join(" ").contains(&query_words.join(" ")) {
+        return Some(0.8);
+    }
+
+    let best: Vec<f64> = query_words
+        .iter()
+        .map(|q| name_words.iter().map(|n| similarity(q, n)).fold(0.0, f64::max))
+        .collect();
+    let worst = best.iter().copied().fold(1.0, f64::min);
+    (worst >= MIN_WORD_SIMILARITY).then(|| 0.7 * best.iter().sum::<f64>() / best.len() as f64)
+}
+
+/// Finds the plants matching a query across several years of data.
+/// # Arguments
+/// * `query` - Plant code or (part of a) plant name
+/// * `years` - Cleaned rows of each loaded year, oldest first
+/// * `limit` - Most matches returned
+/// # Returns
+/// * `Vec<PlantMatch>`, best match first (ties by name, then code)
+pub fn find_plants(query: &str, years: &[&[CleanRecord]], limit: usize) -> Vec<PlantMatch> {
+    let totals: Vec<_> = years.iter().map(|records| aggregate_by_plant(records.iter())).collect();
+
+    let mut plants: BTreeMap<&str, PlantMatch> = BTreeMap::new();
+    for (i, year) in totals.iter().enumerate() {
+        for (plant_id, stats) in year {
+            let plant = plants.entry(plant_id.as_str()).or_insert_with(|| PlantMatch {
+                plant_id: plant_id.clone(),
+                plant_name: String::new(),
+                state: String::new(),
+                score: 0.0,
+                years: vec![None; years.len()],
+            });
+            plant.plant_name = stats.plant_name.clone();
+            plant.state = stats.state.clone();
+            plant.years[i] = Some(PlantYear { fuel: stats.total_fuel, generation: stats.total_gen });
+        }
+    }
+
+    let mut matches: Vec<PlantMatch> = plants
+        .into_values()
+        .filter_map(|mut plant| {
+            plant.score = match_score(query, &plant.plant_id, &plant.plant_name)?;
+            Some(plant)
+        })
+        .collect();
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.plant_name.cmp(&b.plant_name)));
+    matches.truncate(limit);
+    matches
+}
+
+/// Formats the matches for the terminal, one block per plant with a row per year.
+/// # Arguments
+/// * `matches` - The plants found
+/// * `labels` - Names of the loaded years, in the order of `PlantMatch::years`
+/// * `unit` - Unit of the heat rates
+pub fn format_plant_matches(matches: &[PlantMatch], labels: &[String], unit: HeatRateUnit) -> String {
+    let mut out = String::new();
+    for plant in matches {
+        out += &format!("{} (plant {}, {})\n", plant.plant_name, plant.plant_id, plant.state);
+        out += &format!("  {:<12} {:>16} {:>16} {:>12}\n", "Year", "Fuel (MMBtu)", "Generation (MWh)", unit.label());
+        for (label, year) in labels.iter().zip(&plant.years) {
+            match year {
+                Some(year) => {
+                    let rate = year.heat_rate().map_or("-".to_string(), |r| format!("{:.3}", unit.convert(r)));
+                    out += &format!("  {:<12} {:>16.0} {:>16.0} {:>12}\n", label, year.fuel, year.generation, rate);
+                }
+                None => out += &format!("  {:<12} {:>16}\n", label, "(no rows)"),
+            }
+        }
+        out += "\n";
+    }
+    out
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_find_plants() {
+        assert_eq!(match_score("6146", "06146", "Martin Lake"), Some(1.0));
+        assert_eq!(match_score("martin lake", "6146", "Martin Lake"), Some(1.0));
+        assert_eq!(match_score("Mart", "6146", "Martin Lake"), Some(0.9));
+        assert_eq!(match_score("in lake", "6146", "Martin Lake"), Some(0.8));
+        assert!(match_score("Martn Lake", "6146", "Martin Lake").is_some_and(|s| s > 0.6 && s < 0.7));
+        assert_eq!(match_score("Limestone", "6146", "Martin Lake"), None);
+
+        let base = [
+            CleanRecord { plant_name: "Martin Lake".to_string(), ..record("6146", "TX", "COL", 1000.0, 100.0) },
+            CleanRecord { plant_name: "Limestone".to_string(), ..record("298", "TX", "COL", 500.0, 50.0) },
+        ];
+        let compare = [
+            CleanRecord { plant_name: "Martin Lake".to_string(), ..record("6146", "TX", "COL", 900.0, 0.0) },
+            CleanRecord { plant_name: "Lake Hubbard".to_string(), ..record("7", "TX", "COL", 10.0, 1.0) },
+        ];
+        let matches = find_plants("lake", &[&base, &compare], 10);
+        assert_eq!(matches.iter().map(|m| m.plant_id.as_str()).collect::<Vec<_>>(), ["7", "6146"]);
+        assert_eq!(matches[1].years[0].as_ref().and_then(PlantYear::heat_rate), Some(10.0));
+        assert_eq!((matches[0].years[0].clone(), matches[1].years[1].as_ref().unwrap().heat_rate()), (None, None));
+
+        let text = format_plant_matches(&matches[1..], &["2019".into(), "2020".into()], HeatRateUnit::MmbtuPerMwh);
+        assert!(text.starts_with("Martin Lake (plant 6146, TX)\n") && text.contains("10.000\n"), "{}", text);
+    }
+}
@@ -0,0 +1,192 @@
+// sensitivity.rs
+// This module recomputes each state's efficiency change under several fuel-inclusion definitions
+// (fossil only, fossil plus biomass, all fuels) and measures how much the state ranking depends on the choice.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+
+use csv::WriterBuilder;
+
+use crate::cleaning::{CleanRecord, StateStats};
+use crate::fuels::FuelGroup;
+use crate::monthly::format_optional;
+use crate::quantity::{MegawattHours, Mmbtu};
+use crate::stats::{ranks, spearman};
+
+/// Which fuel groups count toward a state's fuel and generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuelDefinition {
+    Fossil,
+    FossilBiomass,
+    AllFuels,
+}
+
+/// Definitions compared, narrowest first; the last one matches the main efficiency table.
+pub const DEFINITIONS: [FuelDefinition; 3] = [FuelDefinition::Fossil, FuelDefinition::FossilBiomass, FuelDefinition::AllFuels];
+
+impl FuelDefinition {
+    /// Whether rows of this fuel group are included.
+    pub fn includes(self, group: FuelGroup) -> bool {
+        match self {
+            FuelDefinition::Fossil => group.is_fossil(),
+            FuelDefinition::FossilBiomass => group.is_fossil() || group == FuelGroup::Biomass,
+            FuelDefinition::AllFuels => true,
+        }
+    }
+
+    /// Column suffix used in the output file.
+    pub fn label(self) -> &'static str {
+        match self {
+            FuelDefinition::Fossil => "Fossil",
+            FuelDefinition::FossilBiomass => "Fossil_Biomass",
+            FuelDefinition::AllFuels => "All_Fuels",
+        }
+    }
+}
+
+/// One state's change in heat rate and change rank under each definition (in `DEFINITIONS` order).
+#[derive(Debug)]
+pub struct StateSensitivity {
+    pub state: String,
+
+    /// Compare-year minus base-year heat rate, or `None` if the state has no generation under the definition.
+    pub deltas: Vec<Option<f64>>,
+
+    /// Rank of the change among states present under every definition (1 = largest improvement).
+    pub ranks: Vec<Option<usize>>,
+}
+
+impl StateSensitivity {
+    /// Spread between the state's best and worst rank across definitions.
+    pub fn rank_range(&self) -> Option<usize> {
+        let ranks: Vec<usize> = self.ranks.iter().flatten().copied().collect();
+        Some(ranks.iter().max()? - ranks.iter().min()?)
+    }
+}
+
+/// Per-state results plus the rank agreement of each definition with the all-fuels ranking.
+#[derive(Debug)]
+pub struct FuelSensitivity {
+    pub states: Vec<StateSensitivity>,
+
+    /// Spearman correlation of each definition's change ranking with the all-fuels ranking.
+    pub correlations: Vec<Option<f64>>,
+}
+
+/// Sums fuel and generation by state, keeping only rows the definition includes.
+fn aggregate_with(records: &[CleanRecord], definition: FuelDefinition) -> HashMap<String, StateStats> {
+    let mut state_map: HashMap<String, StateStats> = HashMap::new();
+
+    for record in records.iter().filter(|r| definition.includes(FuelGroup::from_aer(&r.aer_fuel))) {
+        let entry = state_map.entry(record.state.clone()).or_default();
+        entry.total_fuel += Mmbtu(record.fuel);
+        entry.total_gen += MegawattHours(record.r#gen);
+    }
+
+    state_map
+}
+
+/// Recomputes every state's change under each fuel definition in one pass over the two years.
+/// # Arguments
+/// * `records_a` - Cleaned rows for the base year
+/// * `records_b` - Cleaned rows for the compare year
+/// # Returns
+/// * `FuelSensitivity` with states sorted alphabetically
+pub fn compute_fuel_sensitivity(records_a: &[CleanRecord], records_b: &[CleanRecord]) -> FuelSensitivity {
+    let mut deltas: BTreeMap<String, Vec<Option<f64>>> = BTreeMap::new();
+
+    for (i, definition) in DEFINITIONS.iter().enumerate() {
+        let (stats_a, stats_b) = (aggregate_with(records_a, *definition), aggregate_with(records_b, *definition));
+        for (state, stat_a) in &stats_a {
+            let Some(stat_b) = stats_b.get(state) else {
+                continue;
+            };
+            if stat_a.total_gen.0 == 0.0 || stat_b.total_gen.0 == 0.0 {
+                continue;
+            }
+            let delta = stat_b.total_fuel / stat_b.total_gen - stat_a.total_fuel / stat_a.total_gen;
+            deltas.entry(state.clone()).or_insert_with(|| vec![None; DEFINITIONS.len()])[i] = Some(delta.0);
+        }
+    }
+
+    // Rank only the states every definition covers, so the rankings are comparable
+    let common: Vec<(String, Vec<f64>)> = deltas
+        .iter()
+        .filter_map(|(state, values)| Some((state.clone(), values.iter().copied().collect::<Option<Vec<f64>>>()?)))
+        .collect();
+    let columns: Vec<Vec<f64>> = (0..DEFINITIONS.len()).map(|i| common.iter().map(|(_, v)| v[i]).collect()).collect();
+    let column_ranks: Vec<Vec<usize>> = columns.iter().map(|c| ranks(c)).collect();
+
+    let all_fuels = &columns[DEFINITIONS.len() - 1];
+    let correlations = columns.iter().map(|c| spearman(c, all_fuels)).collect();
+
+    let states = deltas
+        .into_iter()
+        .map(|(state, deltas)| {
+            let ranks = match common.iter().position(|(s, _)| *s == state) {
+                Some(row) => column_ranks.iter().map(|r| Some(r[row])).collect(),
+                None => vec![None; DEFINITIONS.len()],
+            };
+            StateSensitivity { state, deltas, ranks }
+        })
+        .collect();
+
+    FuelSensitivity { states, correlations }
+}
+
+/// Writes the per-definition changes and ranks to a CSV output file.
+pub fn write_fuel_sensitivity_csv(path: &str, data: &FuelSensitivity) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    let mut header = vec!["State".to_string()];
+    header.extend(DEFINITIONS.iter().map(|d| format!("Delta_{}", d.label())));
+    header.extend(DEFINITIONS.iter().map(|d| format!("Rank_{}", d.label())));
+    header.push("Rank_Range".to_string());
+    wtr.write_record(&header)?;
+
+    for item in &data.states {
+        let mut row = vec![item.state.clone()];
+        row.extend(item.deltas.iter().map(|d| format_optional(*d)));
+        row.extend(item.ranks.iter().map(|r| r.map(|r| r.to_string()).unwrap_or_default()));
+        row.push(item.rank_range().map(|r| r.to_string()).unwrap_or_default());
+        wtr.write_record(&row)?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_definitions_change_the_ranking() {
+        // AA improves its gas fleet but adds biomass; BB only burns gas and gets worse; CC is wind only
+        let base = [
+            record("1", "AA", "NG", 100.0, 10.0),
+            record("1", "BB", "NG", 80.0, 10.0),
+            record("1", "CC", "WND", 0.0, 5.0),
+        ];
+        let compare = [
+            record("1", "AA", "NG", 90.0, 10.0),
+            record("1", "AA", "WWW", 150.0, 10.0),
+            record("1", "BB", "NG", 90.0, 10.0),
+            record("1", "CC", "WND", 0.0, 6.0),
+        ];
+
+        let result = compute_fuel_sensitivity(&base, &compare);
+        let aa = &result.states[0];
+        assert_eq!(aa.deltas, [Some(-1.0), Some(2.0), Some(2.0)]);
+        assert_eq!(aa.ranks, [Some(1), Some(2), Some(2)]);
+        assert_eq!(aa.rank_range(), Some(1));
+
+        let cc = &result.states[2];
+        assert_eq!(cc.deltas, [None, None, Some(0.0)]);
+        assert_eq!(cc.rank_range(), None);
+
+        assert_eq!(result.correlations[0], Some(-1.0));
+        assert_eq!(result.correlations[2], Some(1.0));
+    }
+}
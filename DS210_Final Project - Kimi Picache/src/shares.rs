@@ -0,0 +1,101 @@
+// shares.rs
+// This module adds each state's share of national fossil generation and fossil fuel consumption to a result
+// table, so a large heat rate change in a small state can be told apart from one that moves the national figure.
+
+use std::collections::HashMap;
+
+use crate::cleaning::{CleanRecord, StateStats};
+use crate::fuels::FuelGroup;
+use crate::quantity::{MegawattHours, Mmbtu};
+use crate::report::{Cell, Report};
+
+/// A state's share of the national fossil totals for one year (0-1).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NationalShare {
+    pub generation: f64,
+    pub fuel: f64,
+}
+
+/// Sums each state's fossil fuel (MMBtu) and fossil generation (MWh).
+pub fn fossil_totals(records: &[CleanRecord]) -> HashMap<String, StateStats> {
+    let mut totals: HashMap<String, StateStats> = HashMap::new();
+    for record in records.iter().filter(|r| FuelGroup::from_aer(&r.aer_fuel).is_fossil()) {
+        let entry = totals.entry(record.state.clone()).or_default();
+        entry.total_fuel += Mmbtu(record.fuel);
+        entry.total_gen += MegawattHours(record.r#gen);
+    }
+    totals
+}
+
+/// Each state's share of national fossil generation and fuel consumption; a share is 0 when the national
+/// total is.
+pub fn national_shares(records: &[CleanRecord]) -> HashMap<String, NationalShare> {
+    let totals = fossil_totals(records);
+    let national_gen: f64 = totals.values().map(|s| s.total_gen.0).sum();
+    let national_fuel: f64 = totals.values().map(|s| s.total_fuel.0).sum();
+    let share = |value: f64, total: f64| if total > 0.0 { value / total } else { 0.0 };
+
+    totals
+        .into_iter()
+        .map(|(state, s)| {
+            let shares = NationalShare {
+                generation: share(s.total_gen.0, national_gen),
+                fuel: share(s.total_fuel.0, national_fuel),
+            };
+            (state, shares)
+        })
+        .collect()
+}
+
+/// Appends national fossil generation and fuel share columns for both years to a table keyed by its `State`
+/// column; states without fossil generation in a year get empty cells.
+/// # Arguments
+/// * `report` - Table with a `State` column
+/// * `labels` - Names of the two inputs used in the column headers (e.g. "2019", "2020")
+/// * `records` - Cleaned rows of the two inputs
+pub fn append_national_share_columns(report: &mut Report, labels: [&str; 2], records: [&[CleanRecord]; 2]) {
+    let states: Vec<String> = report.text_column("State").into_iter().map(String::from).collect();
+
+    let shares = records.map(national_shares);
+    let mut columns: Vec<(String, Vec<Cell>)> = Vec::new();
+    for (label, shares) in labels.iter().zip(&shares) {
+        let lookup = |value: fn(&NationalShare) -> f64| -> Vec<Cell> {
+            states.iter().map(|state| shares.get(state).map(value).map_or(Cell::from(""), Cell::from)).collect()
+        };
+        columns.push((format!("National_Gen_Share_{label}"), lookup(|s| s.generation)));
+        columns.push((format!("National_Fuel_Share_{label}"), lookup(|s| s.fuel)));
+    }
+
+    for (name, values) in columns {
+        report.push_column(name, 6, values);
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_national_share_columns() {
+        // Nuclear and wind generation do not count toward the fossil totals
+        let base = [
+            record("1", "AA", "NG", 300.0, 30.0),
+            record("1", "BB", "COL", 700.0, 70.0),
+            record("1", "BB", "NUC", 0.0, 500.0),
+        ];
+        let compare = [record("1", "AA", "NG", 500.0, 50.0), record("1", "CC", "WND", 0.0, 10.0)];
+
+        let mut report = Report::new("Test").column("State", 0);
+        for state in ["AA", "BB", "CC"] {
+            report.push_row(vec![state.into()]);
+        }
+        append_national_share_columns(&mut report, ["2019", "2020"], [&base, &compare]);
+
+        assert_eq!(report.columns[1].name, "National_Gen_Share_2019");
+        assert_eq!(&report.rows[0][1..], [Cell::Float(0.3), Cell::Float(0.3), Cell::Float(1.0), Cell::Float(1.0)]);
+        assert_eq!((&report.rows[1][2], &report.rows[1][3]), (&Cell::Float(0.7), &Cell::from("")));
+        assert_eq!(report.rows[2][1], Cell::from(""));
+    }
+}
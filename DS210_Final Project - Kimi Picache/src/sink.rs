@@ -0,0 +1,431 @@
+// sink.rs
+// This module is the single interface the main result tables are written through. A table is handed to each
+// `OutputSink` as an `OutputTable`: its rows as ordered columns of JSON values, the exact JSON the table
+// serializes to, and the table's own CSV writer (which knows each column's published precision). The built-in
+// sinks write CSV, JSON, Parquet and SQLite files or POST the rows to a URL; another crate can implement the
+// trait to send the tables anywhere else without changes here.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+use csv::WriterBuilder;
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::curl::post_json;
+use crate::export::{prepend_preamble, OutputFormat};
+
+/// A destination for result tables.
+pub trait OutputSink {
+    /// Where the sink puts a table, for messages (a file path or a URL).
+    fn location(&self, table: &OutputTable) -> String;
+
+    /// Writes one table.
+    /// # Returns
+    /// * The local file written, if any, so the run summary can record (and the audit log hash) it
+    fn write_table(&mut self, table: &OutputTable) -> Result<Option<String>, Box<dyn Error>>;
+}
+
+/// Writes a table's own CSV layout to a path.
+pub type CsvWriter<'a> = &'a dyn Fn(&str) -> Result<(), Box<dyn Error>>;
+
+/// One result table, in every form the sinks need.
+#[non_exhaustive]
+pub struct OutputTable<'a> {
+    /// Name of the table, e.g. "efficiency_changes" (with the run label); file sinks add their extension.
+    pub name: String,
+
+    /// Column names in the order the rows serialize their fields.
+    pub columns: Vec<String>,
+
+    /// Values per row, one per column (`Null` where a row lacks the field).
+    pub rows: Vec<Vec<Value>>,
+
+    /// The rows as pretty-printed JSON, exactly as `export::write_json` writes them.
+    pub json: String,
+
+    csv: Option<CsvWriter<'a>>,
+}
+
+impl<'a> OutputTable<'a> {
+    /// A table from any value that serializes as a list of row objects.
+    pub fn new<T: Serialize + ?Sized>(name: &str, data: &T) -> Result<OutputTable<'a>, Box<dyn Error>> {
+        let json = serde_json::to_string_pretty(data)?;
+        let objects: Vec<OrderedRow> = serde_json::from_str(&json)
+            .map_err(|e| format!("table '{}' does not serialize as a list of rows: {}", name, e))?;
+
+        let mut columns: Vec<String> = Vec::new();
+        for object in &objects {
+            for (field, _) in &object.0 {
+                if !columns.contains(field) {
+                    columns.push(field.clone());
+                }
+            }
+        }
+        let rows = objects
+            .into_iter()
+            .map(|object| {
+                let mut values = vec![Value::Null; columns.len()];
+                for (field, value) in object.0 {
+                    if let Some(i) = columns.iter().position(|c| *c == field) {
+                        values[i] = value;
+                    }
+                }
+                values
+            })
+            .collect();
+        Ok(OutputTable { name: name.to_string(), columns, rows, json, csv: None })
+    }
+
+    /// Uses the table's own CSV writer instead of the generic one.
+    pub fn with_csv(mut self, write_csv: CsvWriter<'a>) -> OutputTable<'a> {
+        self.csv = Some(write_csv);
+        self
+    }
+
+    /// Writes the table as CSV: with its own writer when it has one, otherwise one column per field (text as is,
+    /// numbers as in the JSON, nulls empty).
+    pub fn write_csv(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(write_csv) = self.csv {
+            return write_csv(path);
+        }
+        let mut wtr = WriterBuilder::new().from_path(path)?;
+        wtr.write_record(&self.columns)?;
+        for row in &self.rows {
+            wtr.write_record(row.iter().map(value_text))?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+/// A JSON object with its fields in document order.
+struct OrderedRow(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for OrderedRow {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RowVisitor;
+
+        impl<'de> Visitor<'de> for RowVisitor {
+            type Value = OrderedRow;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<OrderedRow, A::Error> {
+                let mut fields = Vec::new();
+                while let Some(field) = map.next_entry()? {
+                    fields.push(field);
+                }
+                Ok(OrderedRow(fields))
+            }
+        }
+
+        deserializer.deserialize_map(RowVisitor)
+    }
+}
+
+/// Text of a value in a CSV cell or text column: strings as is, nulls empty, anything else as JSON.
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Type a column is stored as in Parquet and SQLite, from its non-null values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(any(feature = "parquet", feature = "sqlite")), allow(dead_code))]
+enum ColumnType {
+    Integer,
+    Real,
+    Boolean,
+    Text,
+}
+
+#[cfg_attr(not(any(feature = "parquet", feature = "sqlite")), allow(dead_code))]
+fn column_type(table: &OutputTable, column: usize) -> ColumnType {
+    let mut values = table.rows.iter().map(|row| &row[column]).filter(|v| !v.is_null()).peekable();
+    if values.peek().is_none() {
+        return ColumnType::Text;
+    }
+    let values: Vec<&Value> = values.collect();
+    if values.iter().all(|v| v.is_i64()) {
+        ColumnType::Integer
+    } else if values.iter().all(|v| v.is_number()) {
+        ColumnType::Real
+    } else if values.iter().all(|v| v.is_boolean()) {
+        ColumnType::Boolean
+    } else {
+        ColumnType::Text
+    }
+}
+
+/// CSV files (`<name>.csv`), with the optional metadata preamble.
+#[derive(Debug, Clone, Default)]
+pub struct CsvSink {
+    /// Comment lines put above the header (see `export::prepend_preamble`); none when empty.
+    pub preamble: Vec<String>,
+}
+
+impl OutputSink for CsvSink {
+    fn location(&self, table: &OutputTable) -> String {
+        format!("{}.csv", table.name)
+    }
+
+    fn write_table(&mut self, table: &OutputTable) -> Result<Option<String>, Box<dyn Error>> {
+        let path = self.location(table);
+        table.write_csv(&path)?;
+        if !self.preamble.is_empty() {
+            prepend_preamble(&path, &self.preamble)?;
+        }
+        Ok(Some(path))
+    }
+}
+
+/// JSON files (`<name>.json`) holding an array of row objects.
+#[derive(Debug, Clone, Default)]
+pub struct JsonSink;
+
+impl OutputSink for JsonSink {
+    fn location(&self, table: &OutputTable) -> String {
+        format!("{}.json", table.name)
+    }
+
+    fn write_table(&mut self, table: &OutputTable) -> Result<Option<String>, Box<dyn Error>> {
+        let path = self.location(table);
+        fs::write(&path, &table.json)?;
+        Ok(Some(path))
+    }
+}
+
+/// Parquet files (`<name>.parquet`, Snappy compressed) with one optional column per field.
+#[derive(Debug, Clone, Default)]
+pub struct ParquetSink;
+
+impl OutputSink for ParquetSink {
+    fn location(&self, table: &OutputTable) -> String {
+        format!("{}.parquet", table.name)
+    }
+
+    #[cfg(feature = "parquet")]
+    fn write_table(&mut self, table: &OutputTable) -> Result<Option<String>, Box<dyn Error>> {
+        let path = self.location(table);
+        write_parquet_table(&path, table)?;
+        Ok(Some(path))
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    fn write_table(&mut self, table: &OutputTable) -> Result<Option<String>, Box<dyn Error>> {
+        Err(format!("'{}': Parquet output needs the 'parquet' feature", self.location(table)).into())
+    }
+}
+
+/// Writes a table to a Parquet file as a single row group.
+#[cfg(feature = "parquet")]
+fn write_parquet_table(path: &str, table: &OutputTable) -> Result<(), Box<dyn Error>> {
+    use std::fs::File;
+    use std::sync::Arc;
+
+    use parquet::basic::{Compression, LogicalType, Repetition, Type as PhysicalType};
+    use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::types::Type;
+
+    let types: Vec<ColumnType> = (0..table.columns.len()).map(|i| column_type(table, i)).collect();
+    let mut fields = Vec::new();
+    for (name, column_type) in table.columns.iter().zip(&types) {
+        let builder = match column_type {
+            ColumnType::Integer => Type::primitive_type_builder(name, PhysicalType::INT64),
+            ColumnType::Real => Type::primitive_type_builder(name, PhysicalType::DOUBLE),
+            ColumnType::Boolean => Type::primitive_type_builder(name, PhysicalType::BOOLEAN),
+            ColumnType::Text => Type::primitive_type_builder(name, PhysicalType::BYTE_ARRAY)
+                .with_logical_type(Some(LogicalType::String)),
+        };
+        fields.push(Arc::new(builder.with_repetition(Repetition::OPTIONAL).build()?));
+    }
+    let schema = Arc::new(Type::group_type_builder("table").with_fields(fields).build()?);
+    let properties = Arc::new(WriterProperties::builder().set_compression(Compression::SNAPPY).build());
+    let mut writer = SerializedFileWriter::new(File::create(path)?, schema, properties)?;
+
+    let mut group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = group.next_column()? {
+        let values: Vec<&Value> = table.rows.iter().map(|row| &row[index]).collect();
+        let levels: Vec<i16> = values.iter().map(|v| i16::from(!v.is_null())).collect();
+        let present = values.into_iter().filter(|v| !v.is_null());
+        match types[index] {
+            ColumnType::Integer => {
+                let data: Vec<i64> = present.filter_map(Value::as_i64).collect();
+                column.typed::<Int64Type>().write_batch(&data, Some(&levels), None)?;
+            }
+            ColumnType::Real => {
+                let data: Vec<f64> = present.filter_map(Value::as_f64).collect();
+                column.typed::<DoubleType>().write_batch(&data, Some(&levels), None)?;
+            }
+            ColumnType::Boolean => {
+                let data: Vec<bool> = present.filter_map(Value::as_bool).collect();
+                column.typed::<BoolType>().write_batch(&data, Some(&levels), None)?;
+            }
+            ColumnType::Text => {
+                let data: Vec<ByteArray> = present.map(|v| value_text(v).into_bytes().into()).collect();
+                column.typed::<ByteArrayType>().write_batch(&data, Some(&levels), None)?;
+            }
+        }
+        column.close()?;
+        index += 1;
+    }
+    group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+/// SQLite files (`<name>.sqlite`) holding the rows in a table of the same name, replaced on every write.
+#[derive(Debug, Clone, Default)]
+pub struct SqliteSink;
+
+impl OutputSink for SqliteSink {
+    fn location(&self, table: &OutputTable) -> String {
+        format!("{}.sqlite", table.name)
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn write_table(&mut self, table: &OutputTable) -> Result<Option<String>, Box<dyn Error>> {
+        let path = self.location(table);
+        write_sqlite_table(&path, table)?;
+        Ok(Some(path))
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn write_table(&mut self, table: &OutputTable) -> Result<Option<String>, Box<dyn Error>> {
+        Err(format!("'{}': SQLite output needs the 'sqlite' feature", self.location(table)).into())
+    }
+}
+
+/// Writes a table into a SQLite database, replacing a table of the same name.
+#[cfg(feature = "sqlite")]
+fn write_sqlite_table(path: &str, table: &OutputTable) -> Result<(), Box<dyn Error>> {
+    use rusqlite::types::Value as SqlValue;
+    use rusqlite::{params_from_iter, Connection};
+
+    let quote = |name: &str| format!("\"{}\"", name.replace('"', "\"\""));
+    let types: Vec<ColumnType> = (0..table.columns.len()).map(|i| column_type(table, i)).collect();
+    let definitions: Vec<String> = table
+        .columns
+        .iter()
+        .zip(&types)
+        .map(|(name, column_type)| {
+            let sql_type = match column_type {
+                ColumnType::Integer | ColumnType::Boolean => "INTEGER",
+                ColumnType::Real => "REAL",
+                ColumnType::Text => "TEXT",
+            };
+            format!("{} {}", quote(name), sql_type)
+        })
+        .collect();
+
+    let mut conn = Connection::open(path).map_err(|e| format!("cannot open '{}': {}", path, e))?;
+    let tx = conn.transaction()?;
+    tx.execute(&format!("DROP TABLE IF EXISTS {}", quote(&table.name)), [])?;
+    tx.execute(&format!("CREATE TABLE {} ({})", quote(&table.name), definitions.join(", ")), [])?;
+    {
+        let placeholders = vec!["?"; table.columns.len()].join(", ");
+        let mut insert = tx.prepare(&format!("INSERT INTO {} VALUES ({})", quote(&table.name), placeholders))?;
+        for row in &table.rows {
+            let values = row.iter().zip(&types).map(|(value, column_type)| match (value, column_type) {
+                (Value::Null, _) => SqlValue::Null,
+                (Value::Bool(b), _) => SqlValue::Integer(i64::from(*b)),
+                (v, ColumnType::Integer) => v.as_i64().map_or(SqlValue::Null, SqlValue::Integer),
+                (v, ColumnType::Real) => v.as_f64().map_or(SqlValue::Null, SqlValue::Real),
+                (v, _) => SqlValue::Text(value_text(v)),
+            });
+            insert.execute(params_from_iter(values))?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// POSTs every table as JSON (`{"table": <name>, "rows": [...]}`) to a URL with the system `curl`.
+#[derive(Debug, Clone)]
+pub struct HttpSink {
+    pub url: String,
+
+    /// PEM bundle of CA certificates to trust instead of the system ones.
+    pub ca_cert: Option<String>,
+}
+
+impl OutputSink for HttpSink {
+    fn location(&self, _table: &OutputTable) -> String {
+        self.url.clone()
+    }
+
+    fn write_table(&mut self, table: &OutputTable) -> Result<Option<String>, Box<dyn Error>> {
+        let body = format!("{{\"table\": {}, \"rows\": {}}}", serde_json::to_string(&table.name)?, table.json);
+
+        let status = post_json(&self.url, &body, self.ca_cert.as_deref(), "posting results")?;
+        if !status.success() {
+            return Err(format!("posting '{}' to '{}' failed (curl {})", table.name, self.url, status).into());
+        }
+        Ok(None)
+    }
+}
+
+/// The built-in file sink for an output format.
+/// # Arguments
+/// * `format` - Format given with `--format`
+/// * `preamble` - Comment lines for CSV files, none when empty
+pub fn format_sink(format: OutputFormat, preamble: Vec<String>) -> Box<dyn OutputSink> {
+    match format {
+        OutputFormat::Csv => Box::new(CsvSink { preamble }),
+        OutputFormat::Json => Box::new(JsonSink),
+        OutputFormat::Parquet => Box::new(ParquetSink),
+        OutputFormat::Sqlite => Box::new(SqliteSink),
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Row {
+        state: &'static str,
+        heat_rate: Option<f64>,
+        plants: u32,
+    }
+
+    #[test]
+    fn test_output_table() {
+        let data = [
+            Row { state: "TX", heat_rate: Some(9.5), plants: 3 },
+            Row { state: "OK", heat_rate: None, plants: 1 },
+        ];
+        let table = OutputTable::new("states", &data).unwrap();
+        assert_eq!(table.columns, ["state", "heat_rate", "plants"]);
+        assert_eq!(table.rows[1], [Value::from("OK"), Value::Null, Value::from(1)]);
+        assert_eq!(table.json, serde_json::to_string_pretty(&data).unwrap());
+        let types: Vec<ColumnType> = (0..3).map(|i| column_type(&table, i)).collect();
+        assert_eq!(types, [ColumnType::Text, ColumnType::Real, ColumnType::Integer]);
+        assert!(OutputTable::new("scalar", &1.5).is_err());
+
+        let dir = std::env::temp_dir().join(format!("efficiency_sink_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let name = dir.join("states").to_str().unwrap().to_string();
+        let table = OutputTable::new(&name, &data).unwrap();
+        let written = CsvSink::default().write_table(&table).unwrap().unwrap();
+        assert_eq!(fs::read_to_string(written).unwrap(), "state,heat_rate,plants\nTX,9.5,3\nOK,,1\n");
+
+        let write_csv = |path: &str| -> Result<(), Box<dyn Error>> { Ok(fs::write(path, "custom\n")?) };
+        let table = table.with_csv(&write_csv);
+        let mut sink = CsvSink { preamble: vec!["efficiency".to_string()] };
+        assert_eq!(fs::read_to_string(sink.write_table(&table).unwrap().unwrap()).unwrap(), "# efficiency\ncustom\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
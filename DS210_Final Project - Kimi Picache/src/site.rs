@@ -0,0 +1,246 @@
+// site.rs
+// This module writes the findings of a comparison as a small static website, for hosting on GitHub Pages or any
+// plain web server: an index with the national summary, the state table and links to the charts, one page per
+// state with its dashboard, and the result tables as CSV downloads. Every page is generated from the computed
+// results; the pages link to each other with relative paths, so the folder works wherever it is served from.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::analysis::{write_efficiency_csv_labeled, StateEfficiency};
+use crate::changesummary::{format_change_summary, ChangeSummary};
+use crate::charts::{write_delta_chart_html, write_delta_map_html, MapLayout, PlotlyScript};
+use crate::contributions::{write_plant_contributions_csv, PlantContribution};
+use crate::dashboard::{format_state_dashboard, StateDashboard};
+use crate::palette::{Palette, Tone};
+use crate::units::HeatRateUnit;
+
+const STYLESHEET: &str = "body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; }
+table { border-collapse: collapse; }
+th, td { padding: 0.2em 0.8em; text-align: right; border-bottom: 1px solid #ddd; }
+th:first-child, td:first-child { text-align: left; }
+pre { background: #f6f8fa; padding: 1em; overflow-x: auto; }
+";
+
+/// Writes one file of the site to the given path.
+type WriteFile<'a> = &'a dyn Fn(&str) -> Result<(), Box<dyn Error>>;
+
+/// The computed results the site is built from.
+pub struct SiteData<'a> {
+    /// Names of the base and compare inputs.
+    pub labels: [&'a str; 2],
+
+    /// Efficiency rows (in the output unit).
+    pub changes: &'a [StateEfficiency],
+
+    pub summary: &'a ChangeSummary,
+
+    /// One dashboard per state with results.
+    pub dashboards: &'a [StateDashboard],
+
+    /// Contributions of every plant, for the downloads.
+    pub plants: &'a [PlantContribution],
+
+    pub unit: HeatRateUnit,
+}
+
+/// Escapes text for an HTML page.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Wraps a page body with the shared head; `root` is the relative path back to the site's top folder.
+fn page(title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n<body>\n{body}\
+         <footer><p>Generated by efficiency {version} from EIA-923 data.</p></footer>\n</body>\n</html>\n",
+        title = escape_html(title),
+        root = root,
+        body = body,
+        version = env!("CARGO_PKG_VERSION"),
+    )
+}
+
+/// The index page: national summary, charts, the state table and the downloads.
+fn index_page(data: &SiteData, palette: Palette) -> String {
+    let [a, b] = data.labels;
+    let title = format!("Fossil Fuel Efficiency by State, {} → {}", a, b);
+    let mut body = format!("<h1>{}</h1>\n", escape_html(&title));
+
+    body += "<h2>National summary</h2>\n";
+    body += &format!("<pre>{}</pre>\n", escape_html(&format_change_summary(data.summary, data.labels, data.unit)));
+
+    body += "<h2>Charts</h2>\n<ul>\n";
+    body += "<li><a href=\"charts/efficiency_chart.html\">Change in heat rate by state</a></li>\n";
+    body += "<li><a href=\"charts/efficiency_map.html\">Map of the changes</a></li>\n</ul>\n";
+
+    body += "<h2>States</h2>\n";
+    body += &format!("<p>Heat rates in {}; a negative change is an improvement.</p>\n", data.unit.label());
+    body += &format!(
+        "<table>\n<tr><th>State</th><th>{a}</th><th>{b}</th><th>Change</th><th>Rank {a}</th><th>Rank {b}</th></tr>\n",
+        a = escape_html(a),
+        b = escape_html(b)
+    );
+    let mut states: Vec<&StateEfficiency> = data.changes.iter().collect();
+    states.sort_by(|x, y| x.delta.total_cmp(&y.delta).then_with(|| x.state.cmp(&y.state)));
+    for state in states {
+        let color = Tone::of_delta(state.delta).map_or("inherit", |tone| palette.hex(tone));
+        body += &format!(
+            "<tr><td><a href=\"states/{code}.html\">{code}</a></td><td>{:.3}</td><td>{:.3}</td>\
+             <td style=\"color:{}\">{:+.3}</td><td>{}</td><td>{}</td></tr>\n",
+            state.eff_2019,
+            state.eff_2020,
+            color,
+            state.delta,
+            state.rank_2019,
+            state.rank_2020,
+            code = escape_html(&state.state),
+        );
+    }
+    body += "</table>\n";
+
+    body += "<h2>Downloads</h2>\n<ul>\n";
+    body += "<li><a href=\"data/efficiency_changes.csv\">efficiency_changes.csv</a>: every state's heat rates, \
+             change and ranks</li>\n";
+    body += "<li><a href=\"data/plant_contributions.csv\">plant_contributions.csv</a>: each plant's part of its \
+             state's change</li>\n</ul>\n";
+    page(&title, "", &body)
+}
+
+/// A state's page: its dashboard, links to its bordering states and its plants' download.
+fn state_page(data: &SiteData, dashboard: &StateDashboard) -> String {
+    let state = &dashboard.change.state;
+    let mut body = format!("<p><a href=\"../index.html\">All states</a></p>\n<h1>{}</h1>\n", escape_html(state));
+    body += &format!("<pre>{}</pre>\n", escape_html(&format_state_dashboard(dashboard, data.labels, data.unit)));
+
+    if !dashboard.neighbors.is_empty() {
+        let links: Vec<String> = dashboard
+            .neighbors
+            .iter()
+            .map(|n| format!("<a href=\"{code}.html\">{code}</a>", code = escape_html(&n.state)))
+            .collect();
+        body += &format!("<p>Bordering states: {}</p>\n", links.join(", "));
+    }
+    body += &format!(
+        "<p>Download: <a href=\"../data/states/{code}_plants.csv\">{code}_plants.csv</a> \
+         (every plant's part of the change)</p>\n",
+        code = escape_html(state)
+    );
+    page(state, "../", &body)
+}
+
+/// Writes the site into a folder, creating it and its subfolders.
+/// # Arguments
+/// * `out_dir` - Top folder of the site (e.g. `docs` for GitHub Pages)
+/// * `data` - The computed results
+/// * `palette` - Colors of the improvements and deteriorations, in the pages and charts
+/// * `map` - Layout of the change map
+/// * `plotly` - Where the chart pages get plotly.js from
+/// # Returns
+/// * The paths of the files written
+pub fn write_site(
+    out_dir: &str,
+    data: &SiteData,
+    palette: Palette,
+    map: MapLayout,
+    plotly: &PlotlyScript,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let root = Path::new(out_dir);
+    for folder in ["charts", "states", "data/states"] {
+        fs::create_dir_all(root.join(folder))?;
+    }
+    let mut written = Vec::new();
+    let mut write = |name: &str, write_file: WriteFile| {
+        let file = root.join(name).to_string_lossy().into_owned();
+        write_file(&file)?;
+        written.push(file);
+        Ok::<(), Box<dyn Error>>(())
+    };
+
+    write("style.css", &|file| Ok(fs::write(file, STYLESHEET)?))?;
+    write("index.html", &|file| Ok(fs::write(file, index_page(data, palette))?))?;
+    write("charts/efficiency_chart.html", &|file| {
+        write_delta_chart_html(file, data.changes, data.labels, data.unit, palette, plotly)
+    })?;
+    write("charts/efficiency_map.html", &|file| {
+        write_delta_map_html(file, data.changes, data.labels, data.unit, palette, map, plotly)
+    })?;
+    write("data/efficiency_changes.csv", &|file| {
+        write_efficiency_csv_labeled(file, data.changes, data.labels, data.unit)
+    })?;
+    write("data/plant_contributions.csv", &|file| write_plant_contributions_csv(file, data.plants, data.labels))?;
+
+    for dashboard in data.dashboards {
+        let state = &dashboard.change.state;
+        write(&format!("states/{}.html", state), &|file| Ok(fs::write(file, state_page(data, dashboard))?))?;
+        let plants: Vec<PlantContribution> = data.plants.iter().filter(|p| &p.state == state).cloned().collect();
+        write(&format!("data/states/{}_plants.csv", state), &|file| {
+            write_plant_contributions_csv(file, &plants, data.labels)
+        })?;
+    }
+    Ok(written)
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::analysis::compute_efficiency_changes;
+    use crate::changesummary::summarize_changes;
+    use crate::charts::NonContiguous;
+    use crate::cleaning::fixtures::record;
+    use crate::cleaning::{aggregate_by_state, CleanRecord};
+    use crate::contributions::compute_plant_contributions;
+    use crate::dashboard::build_state_dashboard;
+
+    /// A gas plant whose name needs escaping in HTML.
+    fn plant(plant_id: &str, state: &str, fuel: f64, r#gen: f64) -> CleanRecord {
+        let plant_name = format!("Plant <{}> & Sons", plant_id);
+        CleanRecord { plant_name, ..record(plant_id, state, "NG", fuel, r#gen) }
+    }
+
+    #[test]
+    fn test_write_site() {
+        let records = [
+            vec![plant("1", "TX", 1000.0, 100.0), plant("2", "OK", 900.0, 100.0)],
+            vec![plant("1", "TX", 900.0, 100.0), plant("2", "OK", 1000.0, 100.0)],
+        ];
+        let stats = [aggregate_by_state(&records[0]), aggregate_by_state(&records[1])];
+        let changes = compute_efficiency_changes(&stats[0], &stats[1]);
+        let summary = summarize_changes(&changes, [&stats[0], &stats[1]], HeatRateUnit::MmbtuPerMwh);
+        let dashboards: Vec<StateDashboard> = ["OK", "TX"]
+            .iter()
+            .map(|state| build_state_dashboard(state, [&records[0], &records[1]], &changes, 5).unwrap())
+            .collect();
+        let plants = compute_plant_contributions(&records[0], &records[1]);
+        let data = SiteData {
+            labels: ["early", "final"],
+            changes: &changes,
+            summary: &summary,
+            dashboards: &dashboards,
+            plants: &plants,
+            unit: HeatRateUnit::MmbtuPerMwh,
+        };
+
+        let dir = std::env::temp_dir().join(format!("efficiency_site_{}", std::process::id()));
+        let map = MapLayout { non_contiguous: NonContiguous::Inset, dc_callout: false };
+        let written = write_site(dir.to_str().unwrap(), &data, Palette::Standard, map, &PlotlyScript::Cdn).unwrap();
+        assert_eq!(written.len(), 6 + 2 * 2);
+
+        let index = fs::read_to_string(dir.join("index.html")).unwrap();
+        // Most improved first, linked to its page
+        assert!(index.find("states/TX.html").unwrap() < index.find("states/OK.html").unwrap());
+        let texas = fs::read_to_string(dir.join("states/TX.html")).unwrap();
+        assert!(texas.contains("<a href=\"OK.html\">OK</a>") && texas.contains("../style.css"));
+        assert!(texas.contains("&lt;1&gt; &amp; Sons") && !texas.contains("<1>"));
+        assert!(dir.join("data/states/OK_plants.csv").exists());
+        let chart = fs::read_to_string(dir.join("charts/efficiency_chart.html")).unwrap();
+        assert!(chart.contains("early → final (MMBtu/MWh") && !chart.contains("2019"));
+        let table = fs::read_to_string(dir.join("data/efficiency_changes.csv")).unwrap();
+        assert!(table.starts_with("State,Efficiency_early,Efficiency_final"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
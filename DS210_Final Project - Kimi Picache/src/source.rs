@@ -0,0 +1,179 @@
+// source.rs
+// This module reads the rows of a raw EIA-923 export in either of its published formats: the CSV export or the
+// XLSX workbook (first sheet). Both start with the same metadata lines before the column header row. Rows are
+// handed to the caller one at a time, so reading a file never holds more than one row of it in memory.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use csv::{ReaderBuilder, StringRecord};
+
+#[cfg(feature = "xlsx")]
+use calamine::{open_workbook, DataRef, DataType, Reader, Xlsx};
+
+/// Metadata lines (title, notes) above the column header row of an EIA-923 export.
+pub const METADATA_LINES: usize = 5;
+
+/// File format of a raw export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceFormat {
+    Csv,
+    Xlsx,
+}
+
+/// What a source hands to the caller: the column header row once, then each data row (an `Err` for a CSV
+/// line that cannot be read, e.g. an unterminated quote).
+#[derive(Debug)]
+pub enum SourceRow<'a> {
+    Headers(&'a StringRecord),
+    Data(csv::Result<StringRecord>),
+}
+
+/// A raw EIA-923 export on disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataSource {
+    pub path: String,
+    pub format: SourceFormat,
+}
+
+impl DataSource {
+    /// A source for `path`, its format taken from the extension (`.xlsx`/`.xlsm` for workbooks, anything else is
+    /// read as CSV).
+    pub fn new(path: &str) -> DataSource {
+        let lower = path.to_lowercase();
+        let format = if lower.ends_with(".xlsx") || lower.ends_with(".xlsm") {
+            SourceFormat::Xlsx
+        } else {
+            SourceFormat::Csv
+        };
+        DataSource { path: path.to_string(), format }
+    }
+
+    /// Streams the rows of the source to `visit`: the header row first, then every data row in file order.
+    /// Stops at the first error `visit` returns.
+    pub fn read_rows<F>(&self, visit: F) -> Result<(), Box<dyn Error>>
+    where
+        F: FnMut(SourceRow) -> Result<(), Box<dyn Error>>,
+    {
+        match self.format {
+            SourceFormat::Csv => read_csv_rows(&self.path, visit),
+            SourceFormat::Xlsx => read_xlsx_rows(&self.path, visit),
+        }
+    }
+
+    /// Reads the column header row only (empty for a file without one).
+    pub fn headers(&self) -> Result<StringRecord, Box<dyn Error>> {
+        let mut found = None;
+        let result = self.read_rows(|row| match row {
+            SourceRow::Headers(headers) => {
+                found = Some(headers.clone());
+                // Stops the reader; the rows are not needed
+                Err(HEADERS_READ.into())
+            }
+            SourceRow::Data(_) => Ok(()),
+        });
+        match (found, result) {
+            (Some(headers), _) => Ok(headers),
+            (None, Err(e)) => Err(e),
+            (None, Ok(())) => Ok(StringRecord::new()),
+        }
+    }
+}
+
+/// Error `DataSource::headers` stops reading with once it has the header row.
+const HEADERS_READ: &str = "header row read";
+
+/// Streams a CSV export: the metadata lines are skipped line by line, the rest goes through the CSV reader.
+fn read_csv_rows<F>(path: &str, mut visit: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(SourceRow) -> Result<(), Box<dyn Error>>,
+{
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut line = String::new();
+    for _ in 0..METADATA_LINES {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+    }
+
+    let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(reader);
+    // Header names hold line breaks ("AER\nFuel Type Code"); files saved on Windows have them as "\r\n"
+    let headers: StringRecord = rdr.headers()?.iter().map(|h| h.replace("\r\n", "\n")).collect();
+    visit(SourceRow::Headers(&headers))?;
+    for row in rdr.records() {
+        visit(SourceRow::Data(row))?;
+    }
+    Ok(())
+}
+
+/// Text of a workbook cell as the CSV export writes it (whole numbers without a decimal point).
+#[cfg(feature = "xlsx")]
+fn cell_text(value: &DataRef) -> String {
+    match value {
+        DataRef::Empty => String::new(),
+        DataRef::Bool(b) => b.to_string(),
+        DataRef::DateTime(d) => d.to_string(),
+        other => other.as_string().unwrap_or_default(),
+    }
+}
+
+/// Streams the first sheet of an XLSX workbook cell by cell, assembling one row at a time.
+#[cfg(feature = "xlsx")]
+fn read_xlsx_rows<F>(path: &str, mut visit: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(SourceRow) -> Result<(), Box<dyn Error>>,
+{
+    let mut workbook: Xlsx<_> = open_workbook(path).map_err(|e| format!("cannot open workbook '{}': {}", path, e))?;
+    let sheet = workbook.sheet_names().first().cloned().ok_or_else(|| format!("workbook '{}' has no sheets", path))?;
+    let mut cells = workbook.worksheet_cells_reader(&sheet)?;
+
+    let header_row = METADATA_LINES as u32;
+    let mut headers: Option<StringRecord> = None;
+    let mut current: Option<(u32, Vec<String>)> = None;
+    // Trailing blank cells are not stored, so short rows are padded to the header's width
+    let mut finish = |row: u32, mut values: Vec<String>, headers: &mut Option<StringRecord>| match headers {
+        Some(headers) => {
+            values.resize(values.len().max(headers.len()), String::new());
+            visit(SourceRow::Data(Ok(StringRecord::from(values))))
+        }
+        None if row >= header_row => {
+            let record = StringRecord::from(values);
+            let result = visit(SourceRow::Headers(&record));
+            *headers = Some(record);
+            result
+        }
+        None => Ok(()),
+    };
+
+    while let Some(cell) = cells.next_cell()? {
+        let (row, column) = cell.get_position();
+        if current.as_ref().is_some_and(|(r, _)| *r != row)
+            && let Some((r, values)) = current.take()
+        {
+            finish(r, values, &mut headers)?;
+        }
+        let (_, values) = current.get_or_insert_with(|| (row, Vec::new()));
+        if values.len() <= column as usize {
+            values.resize(column as usize + 1, String::new());
+        }
+        values[column as usize] = cell_text(cell.get_value());
+    }
+    if let Some((r, values)) = current {
+        finish(r, values, &mut headers)?;
+    }
+    if headers.is_none() {
+        finish(header_row, Vec::new(), &mut headers)?;
+    }
+    Ok(())
+}
+
+/// Without the `xlsx` feature workbooks are refused.
+#[cfg(not(feature = "xlsx"))]
+fn read_xlsx_rows<F>(path: &str, _visit: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(SourceRow) -> Result<(), Box<dyn Error>>,
+{
+    Err(format!("'{}': XLSX input needs the 'xlsx' feature; export the sheet as CSV instead", path).into())
+}
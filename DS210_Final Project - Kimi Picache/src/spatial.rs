@@ -0,0 +1,193 @@
+// spatial.rs
+// This module tests whether the efficiency changes cluster geographically: each state's neighbors are its k
+// nearest states by great-circle distance between centers, and Moran's I measures how much a state's change
+// resembles its neighbors' (positive = similar changes cluster, negative = neighbors differ).
+
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::analysis::StateEfficiency;
+use crate::geography::{great_circle_km, state_centroid};
+use crate::stats::normal_cdf;
+
+/// One state's change next to its neighbors'.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpatialState {
+    pub state: String,
+    pub delta: f64,
+
+    /// Nearest states, closest first.
+    pub neighbors: Vec<String>,
+
+    /// Mean change of the neighbors (the spatial lag).
+    pub neighbor_delta: f64,
+
+    /// Local Moran's I: positive when the state and its neighbors are on the same side of the mean.
+    pub local_i: f64,
+}
+
+/// Global Moran's I of the changes and its test under the normality assumption.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpatialAutocorrelation {
+    /// Neighbors per state.
+    pub k: usize,
+
+    pub morans_i: f64,
+
+    /// Value expected without spatial pattern, -1 / (n - 1).
+    pub expected: f64,
+    pub z_score: f64,
+
+    /// Two-sided p-value.
+    pub p_value: f64,
+
+    /// States with a known center, in table order; states without one are left out.
+    pub states: Vec<SpatialState>,
+}
+
+impl SpatialAutocorrelation {
+    /// One-line reading of the result at the 5% level.
+    pub fn interpretation(&self) -> &'static str {
+        match (self.p_value < 0.05, self.morans_i > self.expected) {
+            (true, true) => "changes cluster geographically",
+            (true, false) => "neighboring states tend to move in opposite directions",
+            (false, _) => "no significant geographic pattern",
+        }
+    }
+}
+
+/// Computes Moran's I of the change in heat rate with row-standardized k-nearest-neighbor weights.
+/// # Arguments
+/// * `changes` - The efficiency table
+/// * `k` - Neighbors per state
+/// # Returns
+/// * `Some(SpatialAutocorrelation)`, or `None` with fewer than `k + 2` located states or no variation
+pub fn spatial_autocorrelation(changes: &[StateEfficiency], k: usize) -> Option<SpatialAutocorrelation> {
+    let located: Vec<(&StateEfficiency, (f64, f64))> =
+        changes.iter().filter_map(|c| Some((c, state_centroid(&c.state)?))).collect();
+    let n = located.len();
+    if k == 0 || n < k + 2 {
+        return None;
+    }
+
+    // Row-standardized weights: each of the k nearest neighbors gets 1/k
+    let neighbors: Vec<Vec<usize>> = (0..n)
+        .map(|i| {
+            let mut others: Vec<(usize, f64)> =
+                (0..n).filter(|j| *j != i).map(|j| (j, great_circle_km(located[i].1, located[j].1))).collect();
+            others.sort_by(|a, b| a.1.total_cmp(&b.1));
+            others.into_iter().take(k).map(|(j, _)| j).collect()
+        })
+        .collect();
+    let w = 1.0 / k as f64;
+
+    let mean = located.iter().map(|(c, _)| c.delta).sum::<f64>() / n as f64;
+    let z: Vec<f64> = located.iter().map(|(c, _)| c.delta - mean).collect();
+    let m2 = z.iter().map(|v| v * v).sum::<f64>() / n as f64;
+    if m2 == 0.0 {
+        return None;
+    }
+
+    let lags: Vec<f64> = neighbors.iter().map(|js| js.iter().map(|j| z[*j]).sum::<f64>() * w).collect();
+    let s0 = n as f64;
+    let morans_i = z.iter().zip(&lags).map(|(zi, lag)| zi * lag).sum::<f64>() / (m2 * s0);
+
+    // Variance under normality: S1 and S2 from the (asymmetric) neighbor weights
+    let weight = |i: usize, j: usize| if neighbors[i].contains(&j) { w } else { 0.0 };
+    let mut s1 = 0.0;
+    let mut s2 = 0.0;
+    for i in 0..n {
+        let mut column = 0.0;
+        for j in 0..n {
+            s1 += (weight(i, j) + weight(j, i)).powi(2) / 2.0;
+            column += weight(j, i);
+        }
+        s2 += (1.0 + column).powi(2);
+    }
+    let nf = n as f64;
+    let expected = -1.0 / (nf - 1.0);
+    let variance = (nf * nf * s1 - nf * s2 + 3.0 * s0 * s0) / ((nf * nf - 1.0) * s0 * s0) - expected * expected;
+    let z_score = (morans_i - expected) / variance.sqrt();
+
+    let states = located
+        .iter()
+        .enumerate()
+        .map(|(i, (change, _))| SpatialState {
+            state: change.state.clone(),
+            delta: change.delta,
+            neighbors: neighbors[i].iter().map(|j| located[*j].0.state.clone()).collect(),
+            neighbor_delta: lags[i] + mean,
+            local_i: z[i] / m2 * lags[i],
+        })
+        .collect();
+
+    Some(SpatialAutocorrelation {
+        k,
+        morans_i,
+        expected,
+        z_score,
+        p_value: 2.0 * (1.0 - normal_cdf(z_score.abs())),
+        states,
+    })
+}
+
+/// Writes each state's change, its neighbors' mean change and local Moran's I to a CSV output file.
+pub fn write_spatial_csv(path: &str, data: &SpatialAutocorrelation) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["State", "Delta", "Neighbors", "Neighbor_Delta", "Local_Morans_I"])?;
+
+    for item in &data.states {
+        wtr.write_record([
+            item.state.clone(),
+            format!("{:.6}", item.delta),
+            item.neighbors.join(" "),
+            format!("{:.6}", item.neighbor_delta),
+            format!("{:.6}", item.local_i),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::{MegawattHours, Mmbtu};
+    use crate::analysis::compute_efficiency_changes;
+    use crate::cleaning::StateStats;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_clustered_changes() {
+        // Western states improve, eastern states get worse
+        let west = ["WA", "OR", "CA", "NV", "ID", "UT", "AZ", "MT", "WY", "CO", "NM"];
+        let east = ["ME", "NH", "VT", "MA", "RI", "CT", "NY", "NJ", "PA", "DE", "MD"];
+        let stats = |fuel: f64| -> HashMap<String, StateStats> {
+            let stats = || StateStats { total_fuel: Mmbtu(fuel), total_gen: MegawattHours(1.0) };
+            west.iter().chain(&east).map(|s| (s.to_string(), stats())).collect()
+        };
+        let mut stats_b = stats(10.0);
+        for (i, state) in west.iter().enumerate() {
+            stats_b.get_mut(*state).unwrap().total_fuel = Mmbtu(9.0 - i as f64 * 0.01);
+        }
+        for (i, state) in east.iter().enumerate() {
+            stats_b.get_mut(*state).unwrap().total_fuel = Mmbtu(11.0 + i as f64 * 0.01);
+        }
+        let changes = compute_efficiency_changes(&stats(10.0), &stats_b);
+
+        let result = spatial_autocorrelation(&changes, 4).unwrap();
+        assert!(result.morans_i > 0.8);
+        assert!(result.p_value < 0.001);
+        assert_eq!(result.interpretation(), "changes cluster geographically");
+
+        let ca = result.states.iter().find(|s| s.state == "CA").unwrap();
+        assert!(ca.neighbors.contains(&"NV".to_string()));
+        assert!(ca.local_i > 0.0 && ca.neighbor_delta < 0.0);
+
+        assert!(spatial_autocorrelation(&changes[..3], 4).is_none());
+    }
+}
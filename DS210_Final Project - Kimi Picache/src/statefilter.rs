@@ -0,0 +1,91 @@
+// statefilter.rs
+// This module scopes the reported states to a region of interest: an include list (only these states) and an
+// exclude list, applied to the state totals after aggregation. Ranks, percentiles and cross-state averages are
+// then taken among the kept states.
+
+use std::collections::{BTreeSet, HashMap};
+
+/// States kept in the output.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateFilter {
+    /// Only these states are kept; `None` keeps every state not excluded.
+    pub include: Option<BTreeSet<String>>,
+    pub exclude: BTreeSet<String>,
+}
+
+/// State codes as given on the command line: trimmed, upper case, blanks dropped.
+fn normalize(codes: &[String]) -> BTreeSet<String> {
+    codes.iter().map(|code| code.trim().to_ascii_uppercase()).filter(|code| !code.is_empty()).collect()
+}
+
+impl StateFilter {
+    /// Builds a filter from state code lists; an empty include list keeps every state.
+    pub fn new(include: &[String], exclude: &[String]) -> StateFilter {
+        let include = normalize(include);
+        StateFilter { include: (!include.is_empty()).then_some(include), exclude: normalize(exclude) }
+    }
+
+    /// Whether the filter drops anything.
+    pub fn is_active(&self) -> bool {
+        self.include.is_some() || !self.exclude.is_empty()
+    }
+
+    /// Whether a state passes the filter.
+    pub fn keeps(&self, state: &str) -> bool {
+        self.include.as_ref().is_none_or(|include| include.contains(state)) && !self.exclude.contains(state)
+    }
+
+    /// Removes the states the filter drops from a map keyed by state.
+    pub fn retain<V>(&self, map: &mut HashMap<String, V>) {
+        if self.is_active() {
+            map.retain(|state, _| self.keeps(state));
+        }
+    }
+
+    /// Codes named in either list that match none of `states`, usually typos.
+    pub fn unknown_codes<'a>(&'a self, states: &BTreeSet<&str>) -> Vec<&'a str> {
+        self.include
+            .iter()
+            .flatten()
+            .chain(&self.exclude)
+            .map(String::as_str)
+            .filter(|code| !states.contains(code))
+            .collect()
+    }
+
+    /// Short description for the console and the run metadata, e.g. "only CA, NY, TX; excluding AK, HI".
+    pub fn describe(&self) -> String {
+        let join = |codes: &BTreeSet<String>| codes.iter().cloned().collect::<Vec<_>>().join(", ");
+        let mut parts = Vec::new();
+        if let Some(include) = &self.include {
+            parts.push(format!("only {}", join(include)));
+        }
+        if !self.exclude.is_empty() {
+            parts.push(format!("excluding {}", join(&self.exclude)));
+        }
+        if parts.is_empty() { "all states".to_string() } else { parts.join("; ") }
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_include_and_exclude_lists() {
+        let codes = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let filter = StateFilter::new(&codes(&["tx", " CA", "NY", "ZZ"]), &codes(&["NY", "hi"]));
+        assert!(filter.keeps("TX") && filter.keeps("CA"));
+        assert!(!filter.keeps("NY") && !filter.keeps("FL"));
+        assert_eq!(filter.describe(), "only CA, NY, TX, ZZ; excluding HI, NY");
+
+        let mut map = HashMap::from([("TX".to_string(), 1), ("NY".to_string(), 2), ("FL".to_string(), 3)]);
+        filter.retain(&mut map);
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["TX"]);
+        assert_eq!(filter.unknown_codes(&BTreeSet::from(["TX", "CA", "NY"])), ["ZZ", "HI"]);
+
+        let everything = StateFilter::new(&[], &[]);
+        assert!(!everything.is_active() && everything.keeps("FL"));
+    }
+}
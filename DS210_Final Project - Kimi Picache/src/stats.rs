@@ -0,0 +1,328 @@
+// stats.rs
+// This module holds small statistical helpers shared by the analysis modules.
+
+use serde::Serialize;
+
+/// Result of an ordinary least-squares fit of `y = intercept + slope * x`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    pub r_squared: f64,
+}
+
+/// Fits a straight line through the points by ordinary least squares.
+/// # Arguments
+/// * `xs` - Independent variable values
+/// * `ys` - Dependent variable values (same length as `xs`)
+/// # Returns
+/// * `Some(LinearFit)`, or `None` with fewer than two points or no variation in `xs`
+pub fn linear_regression(xs: &[f64], ys: &[f64]) -> Option<LinearFit> {
+    let n = xs.len().min(ys.len());
+    if n < 2 {
+        return None;
+    }
+
+    let mean_x = xs[..n].iter().sum::<f64>() / n as f64;
+    let mean_y = ys[..n].iter().sum::<f64>() / n as f64;
+
+    let mut sxx = 0.0;
+    let mut sxy = 0.0;
+    let mut syy = 0.0;
+    for (x, y) in xs.iter().zip(ys).take(n) {
+        sxx += (x - mean_x) * (x - mean_x);
+        sxy += (x - mean_x) * (y - mean_y);
+        syy += (y - mean_y) * (y - mean_y);
+    }
+
+    if sxx == 0.0 {
+        return None;
+    }
+
+    let slope = sxy / sxx;
+    let r_squared = if syy == 0.0 { 1.0 } else { (sxy * sxy) / (sxx * syy) };
+
+    Some(LinearFit {
+        slope,
+        intercept: mean_y - slope * mean_x,
+        r_squared,
+    })
+}
+
+/// Returns the median of the values, or `None` when empty.
+pub fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+    } else {
+        Some(sorted[mid])
+    }
+}
+
+/// Quantile `q` (0-1) of the values, interpolating linearly between the closest ranks (so `q = 0.5` is the
+/// median). `None` when empty.
+pub fn quantile(values: &[f64], q: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let position = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64))
+}
+
+/// Returns the arithmetic mean of the values, or `None` when empty.
+pub fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Sample standard deviation, or `None` with fewer than two values.
+pub fn std_dev(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let mean = mean(values)?;
+    Some((values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64).sqrt())
+}
+
+/// Mean of the values weighted by `weights`, or `None` when the weights sum to zero.
+pub fn weighted_mean(values: &[f64], weights: &[f64]) -> Option<f64> {
+    let total: f64 = weights.iter().sum();
+    (total > 0.0).then(|| values.iter().zip(weights).map(|(v, w)| v * w).sum::<f64>() / total)
+}
+
+/// Weighted quantile `q` (0-1): the smallest value at which more than `q` of the total weight lies at or below
+/// it, averaging with the next value when the cumulative weight hits `q` exactly (so equal weights give the
+/// ordinary median at 0.5). Values with zero or negative weight are ignored; `None` without positive weight.
+/// With generation as the weights, this is the heat rate below which `q` of the megawatt-hours were produced.
+/// # Arguments
+/// * `values` - Values, in any order
+/// * `weights` - Weight of each value (e.g. net generation)
+/// * `q` - Quantile, clamped to 0-1
+pub fn weighted_quantile(values: &[f64], weights: &[f64], q: f64) -> Option<f64> {
+    let mut pairs: Vec<(f64, f64)> = values.iter().copied().zip(weights.iter().copied()).filter(|(_, w)| *w > 0.0).collect();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let cutoff = pairs.iter().map(|(_, w)| w).sum::<f64>() * q.clamp(0.0, 1.0);
+    let mut cumulative = 0.0;
+    for (i, (value, weight)) in pairs.iter().enumerate() {
+        cumulative += weight;
+        if cumulative > cutoff {
+            return Some(*value);
+        }
+        if cumulative == cutoff {
+            return Some(pairs.get(i + 1).map_or(*value, |next| (value + next.0) / 2.0));
+        }
+    }
+    pairs.last().map(|(value, _)| *value)
+}
+
+/// Weighted median: the value at which half of the total weight lies on either side, averaging the two
+/// middle values on an exact split (so equal weights give the ordinary median). `None` without positive weight.
+pub fn weighted_median(values: &[f64], weights: &[f64]) -> Option<f64> {
+    weighted_quantile(values, weights, 0.5)
+}
+
+/// Percentile rank (0-100) of `value` within `values`: the share of values below it,
+/// counting ties as half.
+pub fn percentile_rank(values: &[f64], value: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    let below = values.iter().filter(|v| **v < value).count() as f64;
+    let equal = values.iter().filter(|v| **v == value).count() as f64;
+    (below + 0.5 * equal) / values.len() as f64 * 100.0
+}
+
+/// Percent rank (0-100) of `value` within `values`: the share of the other values below it, so the
+/// lowest value is 0 and the highest 100 however many values there are. Ties share the lower figure.
+pub fn percent_rank(values: &[f64], value: f64) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+
+    let below = values.iter().filter(|v| **v < value).count() as f64;
+    below / (values.len() - 1) as f64 * 100.0
+}
+
+/// Ranks values ascending starting at 1; tied values share the lowest rank ("1224" ranking).
+pub fn ranks(values: &[f64]) -> Vec<usize> {
+    values
+        .iter()
+        .map(|value| values.iter().filter(|v| *v < value).count() + 1)
+        .collect()
+}
+
+/// Ranks values ascending starting at 1; tied values share the average of the ranks they span ("1 2.5 2.5 4"
+/// ranking).
+pub fn fractional_ranks(values: &[f64]) -> Vec<f64> {
+    values
+        .iter()
+        .map(|value| {
+            let below = values.iter().filter(|v| *v < value).count();
+            let tied = values.iter().filter(|v| *v == value).count();
+            below as f64 + (tied as f64 + 1.0) / 2.0
+        })
+        .collect()
+}
+
+/// Spearman rank correlation (-1 to 1) between two equally long series, or `None` with fewer than two
+/// values or when either series is constant. Ties get fractional ranks.
+pub fn spearman(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let n = xs.len().min(ys.len());
+    let rank_x = fractional_ranks(&xs[..n]);
+    let rank_y = fractional_ranks(&ys[..n]);
+    let fit = linear_regression(&rank_x, &rank_y)?;
+    rank_y.iter().any(|r| *r != rank_y[0]).then(|| fit.r_squared.sqrt().copysign(fit.slope))
+}
+
+/// Standard normal cumulative distribution function (Abramowitz and Stegun 7.1.26, error below 1.5e-7).
+pub fn normal_cdf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs() / std::f64::consts::SQRT_2);
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-(x * x) / 2.0).exp();
+    if x >= 0.0 { (1.0 + erf) / 2.0 } else { (1.0 - erf) / 2.0 }
+}
+
+/// Two-sided 95% Student's t critical value for the given degrees of freedom.
+pub fn t_critical_95(df: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+        2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+        2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+    ];
+
+    match df {
+        0 => f64::INFINITY,
+        1..=30 => TABLE[df - 1],
+        _ => 1.96,
+    }
+}
+
+/// Running sum with Neumaier compensation: the low-order bits lost by each addition are kept in a separate
+/// term, so adding millions of values of different magnitudes stays accurate to about one rounding error.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    pub fn add(&mut self, value: f64) {
+        let total = self.sum + value;
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - total) + value;
+        } else {
+            self.compensation += (value - total) + self.sum;
+        }
+        self.sum = total;
+    }
+
+    pub fn total(&self) -> f64 {
+        self.sum + self.compensation
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_regression_exact_line() {
+        let xs = [1.0, 2.0, 3.0, 4.0];
+        let ys = [12.0, 19.0, 26.0, 33.0];
+
+        let fit = linear_regression(&xs, &ys).unwrap();
+        assert!((fit.slope - 7.0).abs() < 1e-9);
+        assert!((fit.intercept - 5.0).abs() < 1e-9);
+        assert!((fit.r_squared - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_linear_regression_needs_variation() {
+        assert!(linear_regression(&[2.0, 2.0, 2.0], &[1.0, 2.0, 3.0]).is_none());
+        assert!(linear_regression(&[1.0], &[1.0]).is_none());
+    }
+
+    #[test]
+    fn test_median_and_percentile_rank() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&[4.0, 1.0, 2.0, 3.0]), Some(2.5));
+        assert_eq!(median(&[]), None);
+        assert_eq!(quantile(&[4.0, 1.0, 2.0, 3.0], 0.5), Some(2.5));
+        assert_eq!(quantile(&[4.0, 1.0, 2.0, 3.0], 0.25), Some(1.75));
+        assert_eq!(quantile(&[], 0.25), None);
+
+        let values = [1.0, 2.0, 3.0, 4.0];
+        assert!((percentile_rank(&values, 1.0) - 12.5).abs() < 1e-9);
+        assert!((percentile_rank(&values, 4.0) - 87.5).abs() < 1e-9);
+
+        assert_eq!(percent_rank(&values, 1.0), 0.0);
+        assert!((percent_rank(&values, 3.0) - 200.0 / 3.0).abs() < 1e-9);
+        assert_eq!(percent_rank(&values, 4.0), 100.0);
+
+        assert_eq!(ranks(&[3.0, 1.0, 3.0, 2.0]), [3, 1, 3, 2]);
+
+        assert_eq!(spearman(&[1.0, 2.0, 3.0], &[10.0, 40.0, 90.0]), Some(1.0));
+        assert_eq!(spearman(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]), Some(-1.0));
+        assert_eq!(spearman(&[1.0, 2.0, 3.0], &[5.0, 5.0, 5.0]), None);
+    }
+
+    #[test]
+    fn test_spearman_ties() {
+        assert_eq!(fractional_ranks(&[1.0, 2.0, 2.0, 3.0]), [1.0, 2.5, 2.5, 4.0]);
+        // Ranks (1, 2.5, 2.5, 4) against (1, 2, 3, 4): the covariance of the ranks is 4.5 and their variances 4.5
+        // and 5, so rho = 4.5 / sqrt(4.5 * 5)
+        let rho = spearman(&[1.0, 2.0, 2.0, 3.0], &[10.0, 20.0, 30.0, 40.0]).unwrap();
+        assert!((rho - 4.5 / (4.5f64 * 5.0).sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_weighted_averages() {
+        assert_eq!(mean(&[1.0, 2.0, 6.0]), Some(3.0));
+        assert_eq!(mean(&[]), None);
+        assert_eq!(weighted_mean(&[1.0, 2.0, 6.0], &[1.0, 0.0, 1.0]), Some(3.5));
+        assert_eq!(weighted_mean(&[1.0], &[0.0]), None);
+
+        assert_eq!(weighted_median(&[4.0, 1.0, 2.0, 3.0], &[1.0; 4]), Some(2.5));
+        assert_eq!(weighted_median(&[1.0, 2.0, 10.0], &[1.0, 1.0, 5.0]), Some(10.0));
+        assert_eq!(weighted_median(&[1.0, 2.0], &[0.0, 0.0]), None);
+        assert_eq!(weighted_quantile(&[1.0, 2.0, 3.0, 4.0], &[1.0; 4], 0.25), Some(1.5));
+        assert_eq!(weighted_quantile(&[1.0, 2.0, 3.0, 4.0], &[1.0, 1.0, 1.0, 5.0], 0.75), Some(4.0));
+        assert_eq!(weighted_quantile(&[3.0, 1.0], &[1.0, 1.0], 0.0), Some(1.0));
+        assert_eq!(weighted_quantile(&[3.0, 1.0], &[1.0, 1.0], 1.0), Some(3.0));
+
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+        assert!((normal_cdf(-1.96) - 0.025).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_compensated_sum_keeps_small_terms() {
+        // Each 1.0 is below half an ulp of 1e16, so a plain sum drops all of them
+        let values: Vec<f64> = [1e16].into_iter().chain([1.0; 1000]).chain([-1e16]).collect();
+        assert_eq!(values.iter().sum::<f64>(), 0.0);
+
+        let mut sum = CompensatedSum::default();
+        values.iter().for_each(|v| sum.add(*v));
+        assert_eq!(sum.total(), 1000.0);
+
+        // 0.1 is not exact in binary; a million of them drift visibly when added naively
+        let mut tenths = CompensatedSum::default();
+        (0..1_000_000).for_each(|_| tenths.add(0.1));
+        assert_eq!(tenths.total(), 100_000.0);
+        assert_ne!((0..1_000_000).map(|_| 0.1).sum::<f64>(), 100_000.0);
+    }
+}
@@ -0,0 +1,108 @@
+// summary.rs
+// This module summarizes a single year of EIA-923 data per state: totals, heat rate, plant count and fuel mix.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::error::Error;
+
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::cleaning::{CleanRecord, INCREMENT_PLANT_ID};
+use crate::fuels::FuelGroup;
+use crate::pivot::aggregate_by_state_fuel;
+use crate::units::HeatRateUnit;
+
+/// One state's totals for a single year.
+#[derive(Debug, Serialize)]
+pub struct StateSummary {
+    /// State abbreviation (e.g., "CA", "TX").
+    pub state: String,
+
+    /// Number of distinct plants reporting (state-fuel increment rows are not counted).
+    pub plants: usize,
+
+    /// Total fuel consumed (MMBtu).
+    pub total_fuel: f64,
+
+    /// Total net generation (MWh).
+    pub total_gen: f64,
+
+    /// Share of net generation from each fuel group (0-1).
+    pub fuel_mix: BTreeMap<FuelGroup, f64>,
+}
+
+impl StateSummary {
+    /// Fuel used per MWh generated, or `None` without generation.
+    pub fn heat_rate(&self) -> Option<f64> {
+        (self.total_gen != 0.0).then(|| self.total_fuel / self.total_gen)
+    }
+}
+
+/// Summarizes every state in one year of data.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// # Returns
+/// * `Vec<StateSummary>` sorted by state
+pub fn summarize_states(records: &[CleanRecord]) -> Vec<StateSummary> {
+    let mut plants: BTreeMap<&str, HashSet<&str>> = BTreeMap::new();
+    for record in records.iter().filter(|r| r.plant_id != INCREMENT_PLANT_ID) {
+        plants.entry(&record.state).or_default().insert(&record.plant_id);
+    }
+
+    aggregate_by_state_fuel(records)
+        .into_iter()
+        .map(|(state, fuels)| {
+            let total_fuel: f64 = fuels.values().map(|s| s.total_fuel.0).sum();
+            let total_gen: f64 = fuels.values().map(|s| s.total_gen.0).sum();
+            let fuel_mix = fuels
+                .iter()
+                .filter(|_| total_gen != 0.0)
+                .map(|(group, stats)| (*group, stats.total_gen.0 / total_gen))
+                .collect();
+
+            StateSummary {
+                plants: plants.get(state.as_str()).map_or(0, |ids| ids.len()),
+                state,
+                total_fuel,
+                total_gen,
+                fuel_mix,
+            }
+        })
+        .collect()
+}
+
+/// Writes the state summaries to a CSV output file, with one generation share column per fuel group present
+/// and the heat rate converted to `unit`.
+pub fn write_state_summary_csv(path: &str, data: &[StateSummary], unit: HeatRateUnit) -> Result<(), Box<dyn Error>> {
+    let groups: BTreeSet<FuelGroup> = data.iter().flat_map(|s| s.fuel_mix.keys().copied()).collect();
+
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    let mut header = vec![
+        "State".to_string(),
+        "Plants".to_string(),
+        "Fuel_MMBtu".to_string(),
+        "Generation_MWh".to_string(),
+        "Heat_Rate".to_string(),
+        "Units".to_string(),
+    ];
+    header.extend(groups.iter().map(|g| format!("Share_{}", g.label())));
+    wtr.write_record(&header)?;
+
+    for item in data {
+        let mut row = vec![
+            item.state.clone(),
+            item.plants.to_string(),
+            format!("{:.6}", item.total_fuel),
+            format!("{:.6}", item.total_gen),
+            item.heat_rate().map(|v| format!("{:.6}", unit.convert(v))).unwrap_or_default(),
+            unit.label().to_string(),
+        ];
+        for group in &groups {
+            row.push(item.fuel_mix.get(group).map(|v| format!("{:.6}", v)).unwrap_or_default());
+        }
+        wtr.write_record(&row)?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
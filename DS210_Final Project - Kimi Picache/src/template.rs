@@ -0,0 +1,73 @@
+// template.rs
+// This module renders a user-supplied Tera template (Markdown, HTML, ...) with the computed results and run metadata.
+
+use std::error::Error;
+use std::fs;
+
+use serde::Serialize;
+use tera::{Context, Tera};
+
+use crate::analysis::StateEfficiency;
+use crate::averages::CrossStateAverage;
+use crate::coverage::LowCoverage;
+use crate::hooks::RunSummary;
+use crate::methodology::Methodology;
+
+/// Output path for a template: the template path without a trailing `.tera` (`report.md.tera` → `report.md`),
+/// otherwise the template name with `.out` appended.
+pub fn template_output_path(template_path: &str) -> String {
+    match template_path.strip_suffix(".tera") {
+        Some(stripped) => stripped.to_string(),
+        None => format!("{}.out", template_path),
+    }
+}
+
+/// Values exposed to the template.
+#[derive(Serialize)]
+struct TemplateData<'a> {
+    /// Tool version.
+    version: &'static str,
+
+    /// Efficiency rows in table order (largest change first); fields as in `StateEfficiency`.
+    states: &'a [StateEfficiency],
+
+    /// Cross-state means and medians (MMBtu/MWh); fields as in `CrossStateAverage` plus its `label`.
+    averages: Vec<AverageRow<'a>>,
+
+    /// States left out of `states` for insufficient data; fields as in `LowCoverage`.
+    insufficient: &'a [LowCoverage],
+
+    /// Assumptions of the run; fields as in `Methodology`.
+    methodology: &'a Methodology,
+
+    /// Command, inputs (path and row count), state count and outputs written so far.
+    run: &'a RunSummary,
+}
+
+/// A cross-state average with its display label.
+#[derive(Serialize)]
+struct AverageRow<'a> {
+    label: String,
+    #[serde(flatten)]
+    average: &'a CrossStateAverage,
+}
+
+/// Renders `template_path` and writes the result to `output_path`.
+pub fn render_template(
+    template_path: &str,
+    output_path: &str,
+    states: &[StateEfficiency],
+    averages: &[CrossStateAverage],
+    insufficient: &[LowCoverage],
+    methodology: &Methodology,
+    run: &RunSummary,
+) -> Result<(), Box<dyn Error>> {
+    let source = fs::read_to_string(template_path).map_err(|e| format!("cannot read template '{}': {}", template_path, e))?;
+    let averages = averages.iter().map(|average| AverageRow { label: average.label(), average }).collect();
+    let data = TemplateData { version: env!("CARGO_PKG_VERSION"), states, averages, insufficient, methodology, run };
+
+    let rendered = Tera::one_off(&source, &Context::from_serialize(&data)?, false)
+        .map_err(|e| format!("template '{}': {:?}", template_path, e))?;
+    fs::write(output_path, rendered)?;
+    Ok(())
+}
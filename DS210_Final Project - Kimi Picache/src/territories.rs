@@ -0,0 +1,98 @@
+// territories.rs
+// This module decides how rows from DC, Puerto Rico and the other territories are treated: kept as their own
+// rows, rolled up into one region, or dropped.
+
+use std::collections::BTreeSet;
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::cleaning::CleanRecord;
+
+/// Plant state codes that are not one of the 50 states.
+pub const NON_STATE_CODES: [&str; 6] = ["DC", "PR", "VI", "GU", "AS", "MP"];
+
+/// Region code the non-state rows are rolled up into by `TerritoryPolicy::Merge`.
+pub const TERRITORY_REGION: &str = "DC+TERR";
+
+/// Treatment of DC and the territories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum TerritoryPolicy {
+    /// Each jurisdiction keeps its own row, like a state.
+    #[default]
+    Separate,
+    /// All of them are combined into one "DC+TERR" row.
+    Merge,
+    /// Their plants are left out of every result.
+    Exclude,
+}
+
+impl TerritoryPolicy {
+    /// Short description for the console and the run metadata.
+    pub fn describe(self) -> &'static str {
+        match self {
+            TerritoryPolicy::Separate => "kept as separate rows",
+            TerritoryPolicy::Merge => "merged into the DC+TERR region",
+            TerritoryPolicy::Exclude => "excluded",
+        }
+    }
+}
+
+/// Whether a plant state code is DC or a territory.
+pub fn is_non_state(state: &str) -> bool {
+    NON_STATE_CODES.contains(&state)
+}
+
+/// Applies the policy to cleaned rows.
+/// # Arguments
+/// * `records` - Cleaned rows for a single year
+/// * `policy` - How to treat DC and the territories
+/// # Returns
+/// * The non-state codes found in the input, so callers can report what was done with them
+pub fn apply_territory_policy(records: &mut Vec<CleanRecord>, policy: TerritoryPolicy) -> BTreeSet<String> {
+    let found: BTreeSet<String> = records.iter().filter(|r| is_non_state(&r.state)).map(|r| r.state.clone()).collect();
+
+    match policy {
+        TerritoryPolicy::Separate => {}
+        TerritoryPolicy::Merge => {
+            for record in records.iter_mut().filter(|r| is_non_state(&r.state)) {
+                record.state = TERRITORY_REGION.to_string();
+            }
+        }
+        TerritoryPolicy::Exclude => records.retain(|r| !is_non_state(&r.state)),
+    }
+
+    found
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cleaning::fixtures::record;
+
+    #[test]
+    fn test_territory_policies() {
+        let input = vec![
+            record("1", "TX", "NG", 10.0, 1.0),
+            record("1", "DC", "NG", 10.0, 1.0),
+            record("1", "PR", "NG", 10.0, 1.0),
+        ];
+
+        let mut merged = input.clone();
+        let found = apply_territory_policy(&mut merged, TerritoryPolicy::Merge);
+        assert_eq!(found.into_iter().collect::<Vec<_>>(), ["DC", "PR"]);
+        let states: Vec<&str> = merged.iter().map(|r| r.state.as_str()).collect();
+        assert_eq!(states, ["TX", TERRITORY_REGION, TERRITORY_REGION]);
+
+        let mut excluded = input.clone();
+        apply_territory_policy(&mut excluded, TerritoryPolicy::Exclude);
+        assert_eq!(excluded.len(), 1);
+
+        let mut separate = input;
+        apply_territory_policy(&mut separate, TerritoryPolicy::Separate);
+        assert_eq!(separate[1].state, "DC");
+    }
+}
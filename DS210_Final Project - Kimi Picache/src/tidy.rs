@@ -0,0 +1,207 @@
+// tidy.rs
+// This module flattens the computed state metrics into a normalized long table (state, year, metric, value)
+// for plotting libraries such as ggplot, seaborn and vega.
+
+use std::error::Error;
+
+use csv::WriterBuilder;
+
+use crate::emissions::{AvoidedEmissions, PollutantIntensity};
+use crate::monthly::MarginalHeatRate;
+use crate::panel::YearData;
+use crate::analysis::StateEfficiency;
+
+/// A single observation in the tidy output.
+#[derive(Debug)]
+pub struct TidyRow {
+    pub state: String,
+    pub year: i32,
+    pub metric: &'static str,
+    pub value: f64,
+}
+
+impl TidyRow {
+    fn new(state: &str, year: i32, metric: &'static str, value: f64) -> TidyRow {
+        TidyRow { state: state.to_string(), year, metric, value }
+    }
+}
+
+/// Flattens the efficiency change table for the base and compare `years`. Year-over-year metrics are reported
+/// under the compare year.
+pub fn tidy_efficiency(data: &[StateEfficiency], years: [i32; 2]) -> Vec<TidyRow> {
+    let [base, compare] = years;
+    let mut rows = Vec::new();
+
+    for item in data {
+        let state = item.state.as_str();
+        rows.push(TidyRow::new(state, base, "heat_rate", item.eff_2019));
+        rows.push(TidyRow::new(state, compare, "heat_rate", item.eff_2020));
+        rows.push(TidyRow::new(state, compare, "delta_heat_rate", item.delta));
+        rows.push(TidyRow::new(state, compare, "abs_delta_heat_rate", item.abs_delta));
+        rows.push(TidyRow::new(state, compare, "weighted_delta_mmbtu", item.weighted_delta));
+        rows.push(TidyRow::new(state, base, "rel_median", item.rel_median_2019));
+        rows.push(TidyRow::new(state, compare, "rel_median", item.rel_median_2020));
+        rows.push(TidyRow::new(state, base, "percentile", item.pctile_2019));
+        rows.push(TidyRow::new(state, compare, "percentile", item.pctile_2020));
+        rows.push(TidyRow::new(state, compare, "percentile_change", item.pctile_change));
+        rows.push(TidyRow::new(state, base, "rank", item.rank_2019 as f64));
+        rows.push(TidyRow::new(state, compare, "rank", item.rank_2020 as f64));
+        rows.push(TidyRow::new(state, compare, "rank_change", item.rank_change as f64));
+        rows.push(TidyRow::new(state, base, "percent_rank", item.pct_rank_2019));
+        rows.push(TidyRow::new(state, compare, "percent_rank", item.pct_rank_2020));
+        rows.push(TidyRow::new(state, compare, "improvement_percent_rank", item.pct_rank_improvement));
+    }
+
+    rows
+}
+
+/// Flattens the avoided-emissions table under the compare `year`; the CO2 rows are omitted when the intensity is
+/// unknown.
+pub fn tidy_avoided_emissions(data: &[AvoidedEmissions], year: i32) -> Vec<TidyRow> {
+    let mut rows = Vec::new();
+
+    for item in data {
+        let state = item.state.as_str();
+        rows.push(TidyRow::new(state, year, "avoided_fuel_mmbtu", item.avoided_fuel));
+        if let (Some(intensity), Some(avoided)) = (item.co2_intensity, item.avoided_co2_tons) {
+            rows.push(TidyRow::new(state, year, "co2_intensity_kg_per_mmbtu", intensity));
+            rows.push(TidyRow::new(state, year, "avoided_co2_tons", avoided));
+        }
+    }
+
+    rows
+}
+
+/// Flattens the SO2/NOx intensity table for the base and compare `years`.
+pub fn tidy_pollutants(data: &[PollutantIntensity], years: [i32; 2]) -> Vec<TidyRow> {
+    let [base, compare] = years;
+    let mut rows = Vec::new();
+
+    for item in data {
+        let state = item.state.as_str();
+        rows.push(TidyRow::new(state, base, "so2_lb_per_mwh", item.so2_base));
+        rows.push(TidyRow::new(state, compare, "so2_lb_per_mwh", item.so2_compare));
+        rows.push(TidyRow::new(state, base, "nox_lb_per_mwh", item.nox_base));
+        rows.push(TidyRow::new(state, compare, "nox_lb_per_mwh", item.nox_compare));
+    }
+
+    rows
+}
+
+/// Flattens the marginal heat rate table for the base and compare `years`; years without a fit are omitted.
+pub fn tidy_marginal(data: &[MarginalHeatRate], years: [i32; 2]) -> Vec<TidyRow> {
+    let mut rows = Vec::new();
+
+    for item in data {
+        let state = item.state.as_str();
+        for (year, fit) in years.into_iter().zip([item.marginal_base, item.marginal_compare]) {
+            if let Some(fit) = fit {
+                rows.push(TidyRow::new(state, year, "marginal_heat_rate", fit.slope));
+                rows.push(TidyRow::new(state, year, "marginal_r2", fit.r_squared));
+            }
+        }
+    }
+
+    rows
+}
+
+/// Flattens the panel totals.
+pub fn tidy_panel(panel: &[YearData]) -> Vec<TidyRow> {
+    let mut rows = Vec::new();
+
+    for year_data in panel {
+        let mut states: Vec<&String> = year_data.stats.keys().collect();
+        states.sort();
+
+        for state in states {
+            let stats = &year_data.stats[state];
+            rows.push(TidyRow::new(state, year_data.year, "total_fuel_mmbtu", stats.total_fuel.0));
+            rows.push(TidyRow::new(state, year_data.year, "net_generation_mwh", stats.total_gen.0));
+            if stats.total_gen.0 != 0.0 {
+                rows.push(TidyRow::new(state, year_data.year, "heat_rate", (stats.total_fuel / stats.total_gen).0));
+            }
+        }
+    }
+
+    rows
+}
+
+/// Writes tidy rows to a CSV output file, sorted by state, year and metric.
+pub fn write_tidy_csv(path: &str, rows: &mut [TidyRow]) -> Result<(), Box<dyn Error>> {
+    rows.sort_by(|a, b| (&a.state, a.year, a.metric).cmp(&(&b.state, b.year, b.metric)));
+
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["state", "year", "metric", "value"])?;
+
+    for row in rows.iter() {
+        wtr.write_record([
+            &row.state,
+            &row.year.to_string(),
+            row.metric,
+            &format!("{:.6}", row.value),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use crate::analysis::compute_efficiency_changes;
+    use crate::cleaning::StateStats;
+    use crate::stats::LinearFit;
+    use crate::quantity::{MegawattHours, Mmbtu};
+
+    #[test]
+    fn test_tidy_efficiency() {
+        let stats = |fuel| {
+            let stats = StateStats { total_fuel: Mmbtu(fuel), total_gen: MegawattHours(100.0) };
+            HashMap::from([("AA".to_string(), stats)])
+        };
+        let rows = tidy_efficiency(&compute_efficiency_changes(&stats(1000.0), &stats(900.0)), [2018, 2021]);
+        assert_eq!(rows.len(), 16);
+        assert_eq!((rows[0].year, rows[0].metric, rows[0].value), (2018, "heat_rate", 10.0));
+        assert_eq!((rows[1].year, rows[2].year), (2021, 2021));
+    }
+
+    #[test]
+    fn test_missing_values_leave_rows_out() {
+        // Unknown CO2 intensity and a year without a fit leave their rows out
+        let avoided = AvoidedEmissions {
+            state: "AA".to_string(),
+            gen_compare: 100.0,
+            avoided_fuel: 100.0,
+            co2_intensity: None,
+            avoided_co2_tons: None,
+        };
+        assert_eq!(tidy_avoided_emissions(&[avoided], 2020).len(), 1);
+        let fit = LinearFit { slope: 9.5, intercept: 1.0, r_squared: 0.9 };
+        let marginal = MarginalHeatRate {
+            state: "AA".to_string(),
+            avg_base: 10.0,
+            marginal_base: None,
+            avg_compare: 9.0,
+            marginal_compare: Some(fit),
+        };
+        assert_eq!(tidy_marginal(&[marginal], [2019, 2020]).len(), 2);
+    }
+
+    #[test]
+    fn test_write_tidy_csv_sorts_rows() {
+        let mut rows =
+            vec![TidyRow::new("AA", 2020, "marginal_heat_rate", 9.5), TidyRow::new("AA", 2019, "heat_rate", 10.0)];
+        let path = std::env::temp_dir().join(format!("efficiency_tidy_{}.csv", std::process::id()));
+        write_tidy_csv(path.to_str().unwrap(), &mut rows).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines[1], "AA,2019,heat_rate,10.000000");
+        assert_eq!(lines[2], "AA,2020,marginal_heat_rate,9.500000");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+// timestamp.rs
+// This module stamps runs with the time they started, as RFC 3339 in UTC and in the machine's local offset, for
+// the audit log, the results database, CSV preambles and hook payloads. A fixed timestamp can be given instead so
+// artifacts generated in tests and pipelines are byte-for-byte reproducible.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, Local, SecondsFormat, Utc};
+use serde::Serialize;
+
+/// When a run started.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RunTimestamp {
+    /// RFC 3339 in UTC, e.g. "2024-03-01T17:05:09Z".
+    pub utc: String,
+
+    /// RFC 3339 with the local offset, e.g. "2024-03-01T12:05:09-05:00".
+    pub local: String,
+
+    /// Seconds since the Unix epoch.
+    #[serde(skip)]
+    pub unix: i64,
+}
+
+impl RunTimestamp {
+    /// The current time, with the offset of the machine's time zone.
+    pub fn now() -> RunTimestamp {
+        RunTimestamp::from_datetime(Local::now().fixed_offset())
+    }
+
+    fn from_datetime(time: DateTime<FixedOffset>) -> RunTimestamp {
+        RunTimestamp {
+            utc: time.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true),
+            local: time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            unix: time.timestamp(),
+        }
+    }
+}
+
+/// Parses an RFC 3339 timestamp; its offset is kept as the local one.
+impl FromStr for RunTimestamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DateTime::parse_from_rfc3339(s.trim())
+            .map(RunTimestamp::from_datetime)
+            .map_err(|e| format!("invalid timestamp '{}' ({}); use RFC 3339, e.g. 2024-01-01T00:00:00Z", s, e))
+    }
+}
+
+impl fmt::Display for RunTimestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (local {})", self.utc, self.local)
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_keeps_offset() {
+        let stamp: RunTimestamp = "2024-03-01T12:05:09.25-05:00".parse().unwrap();
+        assert_eq!(stamp.utc, "2024-03-01T17:05:09Z");
+        assert_eq!(stamp.local, "2024-03-01T12:05:09-05:00");
+        assert_eq!(stamp.unix, 1_709_312_709);
+        assert!("2024-03-01 12:05".parse::<RunTimestamp>().is_err());
+
+        let now = RunTimestamp::now();
+        assert!(now.utc.ends_with('Z') && now.local.len() >= 20);
+    }
+}
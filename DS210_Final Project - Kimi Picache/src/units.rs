@@ -0,0 +1,73 @@
+// units.rs
+// This module holds the energy unit constants and the heat rate unit conversions used for output.
+// All computations run in MMBtu/MWh; values are converted only when they are displayed or written.
+
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+
+/// Energy content of one MWh of electricity, in MMBtu (the heat rate of a perfectly efficient plant).
+pub const MMBTU_PER_MWH: f64 = 3.412;
+
+/// Gigajoules per MMBtu.
+pub const GJ_PER_MMBTU: f64 = 1.055056;
+
+/// Kilograms per metric ton.
+pub const KG_PER_TONNE: f64 = 1000.0;
+
+/// Unit for heat rates (fuel energy in per unit of electricity out) in outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum HeatRateUnit {
+    /// MMBtu per MWh (the unit the data is reported in).
+    #[default]
+    #[cfg_attr(feature = "cli", value(name = "mmbtu-mwh"))]
+    MmbtuPerMwh,
+    /// Btu per kWh, the usual plant heat rate unit (1 MMBtu/MWh = 1000 Btu/kWh).
+    #[cfg_attr(feature = "cli", value(name = "btu-kwh"))]
+    BtuPerKwh,
+    /// Gigajoules per MWh.
+    #[cfg_attr(feature = "cli", value(name = "gj-mwh"))]
+    GjPerMwh,
+}
+
+impl HeatRateUnit {
+    /// Multiplier from MMBtu/MWh to this unit.
+    pub fn factor(self) -> f64 {
+        match self {
+            HeatRateUnit::MmbtuPerMwh => 1.0,
+            HeatRateUnit::BtuPerKwh => 1000.0,
+            HeatRateUnit::GjPerMwh => GJ_PER_MMBTU,
+        }
+    }
+
+    /// Converts a heat rate (or a difference of heat rates) from MMBtu/MWh.
+    pub fn convert(self, mmbtu_per_mwh: f64) -> f64 {
+        mmbtu_per_mwh * self.factor()
+    }
+
+    /// Unit label used in column values and table headers.
+    pub fn label(self) -> &'static str {
+        match self {
+            HeatRateUnit::MmbtuPerMwh => "MMBtu/MWh",
+            HeatRateUnit::BtuPerKwh => "Btu/kWh",
+            HeatRateUnit::GjPerMwh => "GJ/MWh",
+        }
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heat_rate_conversions() {
+        assert_eq!(HeatRateUnit::MmbtuPerMwh.convert(10.0), 10.0);
+        assert_eq!(HeatRateUnit::BtuPerKwh.convert(10.0), 10000.0);
+        assert!((HeatRateUnit::GjPerMwh.convert(10.0) - 10.55056).abs() < 1e-9);
+
+        // A perfectly efficient plant is 3412 Btu/kWh, 3.6 GJ/MWh
+        assert!((HeatRateUnit::BtuPerKwh.convert(MMBTU_PER_MWH) - 3412.0).abs() < 1e-9);
+        assert!((HeatRateUnit::GjPerMwh.convert(MMBTU_PER_MWH) - 3.6).abs() < 1e-3);
+    }
+}
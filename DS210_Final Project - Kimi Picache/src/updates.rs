@@ -0,0 +1,197 @@
+// updates.rs
+// This module checks EIA for newer EIA-923 data than the local copies. EIA revises a year's archive after its
+// first release (early release, final data, later revisions) under the same URL, so each year's archive is
+// asked for its release date and size with a HEAD request and compared with the local copy: the archive in the
+// downloads cache if there is one (it keeps the release date it was downloaded with), or else '<year>.csv' in
+// the data directory (a sheet saved by hand, compared by date only).
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use csv::WriterBuilder;
+use serde::Serialize;
+
+use crate::download::{download_path, eia923_archive_url, remote_file, DownloadOptions, RemoteFile};
+
+/// How a year's local data compares with EIA's release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateStatus {
+    /// The local copy holds the latest release.
+    Current,
+    /// EIA released the year again after the local copy was made.
+    Revised,
+    /// Released, but not held locally.
+    New,
+    /// EIA has no archive for the year.
+    Unpublished,
+}
+
+impl UpdateStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            UpdateStatus::Current => "current",
+            UpdateStatus::Revised => "revised",
+            UpdateStatus::New => "new",
+            UpdateStatus::Unpublished => "not published",
+        }
+    }
+}
+
+/// A local copy of a year's data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalCopy {
+    pub path: String,
+
+    /// The release date of a downloaded archive, or when a hand-saved file was written.
+    pub modified: DateTime<Utc>,
+
+    /// Size of a downloaded archive; `None` for a hand-saved sheet, which is not comparable with the archive.
+    pub size: Option<u64>,
+}
+
+/// The check of one year.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UpdateCheck {
+    pub year: i32,
+    pub url: String,
+    pub status: UpdateStatus,
+
+    /// EIA's release date (RFC 3339) and archive size, when published.
+    pub released: Option<String>,
+    pub remote_size: Option<u64>,
+
+    /// The local copy compared against and its date (RFC 3339), if any.
+    pub local_path: Option<String>,
+    pub local_modified: Option<String>,
+}
+
+/// Compares EIA's release of a year with the local copy: revised when the release is newer than the copy, or
+/// when a downloaded archive's size differs from the released one.
+pub fn release_status(remote: Option<&RemoteFile>, local: Option<&LocalCopy>) -> UpdateStatus {
+    let (Some(remote), Some(local)) = (remote, local) else {
+        return if remote.is_some() { UpdateStatus::New } else { UpdateStatus::Unpublished };
+    };
+    let newer = remote.last_modified.is_some_and(|released| released > local.modified);
+    let resized = matches!((remote.size, local.size), (Some(remote), Some(local)) if remote != local);
+    if newer || resized { UpdateStatus::Revised } else { UpdateStatus::Current }
+}
+
+/// The local copy of a year: its downloaded archive in the cache directory, else `<data_dir>/<year>.csv`.
+pub fn local_copy(year: i32, data_dir: &str, cache_dir: &str) -> Result<Option<LocalCopy>, Box<dyn Error>> {
+    let archive = download_path(&eia923_archive_url(year), cache_dir)?;
+    let sheet = Path::new(data_dir).join(format!("{}.csv", year));
+    for (path, sized) in [(archive, true), (sheet, false)] {
+        if let Ok(metadata) = fs::metadata(&path)
+            && metadata.is_file()
+        {
+            return Ok(Some(LocalCopy {
+                path: path.to_string_lossy().into_owned(),
+                modified: DateTime::<Utc>::from(metadata.modified()?),
+                size: sized.then_some(metadata.len()),
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Checks each year's EIA-923 release against its local copy.
+/// # Arguments
+/// * `years` - Years to check
+/// * `data_dir` - Directory of the hand-saved `<year>.csv` sheets
+/// * `options` - Cache directory of the downloaded archives, and the proxy and CA settings of the requests
+/// # Returns
+/// * One check per year, in the order given
+pub fn check_updates(
+    years: &[i32],
+    data_dir: &str,
+    options: &DownloadOptions,
+) -> Result<Vec<UpdateCheck>, Box<dyn Error>> {
+    let format_time = |time: DateTime<Utc>| time.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let mut checks = Vec::new();
+
+    for &year in years {
+        let url = eia923_archive_url(year);
+        let remote = remote_file(&url, options)?;
+        let local = local_copy(year, data_dir, &options.dir)?;
+        checks.push(UpdateCheck {
+            year,
+            status: release_status(remote.as_ref(), local.as_ref()),
+            released: remote.as_ref().and_then(|r| r.last_modified).map(format_time),
+            remote_size: remote.as_ref().and_then(|r| r.size),
+            local_path: local.as_ref().map(|l| l.path.clone()),
+            local_modified: local.as_ref().map(|l| format_time(l.modified)),
+            url,
+        });
+    }
+
+    Ok(checks)
+}
+
+/// Writes the release checks to a CSV output file.
+pub fn write_updates_csv(path: &str, data: &[UpdateCheck]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = WriterBuilder::new().from_path(path)?;
+    wtr.write_record(["Year", "Status", "Released", "Remote_Size", "Local_Path", "Local_Modified", "URL"])?;
+
+    for item in data {
+        wtr.write_record([
+            item.year.to_string(),
+            item.status.label().to_string(),
+            item.released.clone().unwrap_or_default(),
+            item.remote_size.map(|size| size.to_string()).unwrap_or_default(),
+            item.local_path.clone().unwrap_or_default(),
+            item.local_modified.clone().unwrap_or_default(),
+            item.url.clone(),
+        ])?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_release_status() {
+        let date = |day: u32| Utc.with_ymd_and_hms(2021, 7, day, 0, 0, 0).unwrap();
+        let remote = RemoteFile { last_modified: Some(date(13)), size: Some(100) };
+        let archive = LocalCopy { path: "f923_2020.zip".to_string(), modified: date(13), size: Some(100) };
+        let sheet = LocalCopy { path: "2020.csv".to_string(), modified: date(20), size: None };
+
+        assert_eq!(release_status(Some(&remote), Some(&archive)), UpdateStatus::Current);
+        assert_eq!(release_status(Some(&remote), Some(&sheet)), UpdateStatus::Current);
+        let resized = RemoteFile { size: Some(120), ..remote.clone() };
+        assert_eq!(release_status(Some(&resized), Some(&archive)), UpdateStatus::Revised);
+        let rereleased = RemoteFile { last_modified: Some(date(30)), size: None };
+        assert_eq!(release_status(Some(&rereleased), Some(&sheet)), UpdateStatus::Revised);
+        assert_eq!(release_status(Some(&remote), None), UpdateStatus::New);
+        assert_eq!(release_status(None, Some(&sheet)), UpdateStatus::Unpublished);
+    }
+
+    #[test]
+    fn test_local_copy() {
+        let dir = std::env::temp_dir().join(format!("efficiency_updates_{}", std::process::id()));
+        let (data_dir, cache_dir) = (dir.join("data"), dir.join("cache"));
+        fs::create_dir_all(&data_dir).unwrap();
+        fs::write(data_dir.join("2019.csv"), "sheet").unwrap();
+        let (data_dir, cache_dir) = (data_dir.to_str().unwrap(), cache_dir.to_str().unwrap());
+
+        let sheet = local_copy(2019, data_dir, cache_dir).unwrap().unwrap();
+        assert!(sheet.path.ends_with("2019.csv") && sheet.size.is_none());
+        assert_eq!(local_copy(2020, data_dir, cache_dir).unwrap(), None);
+
+        // A downloaded archive takes precedence over the sheet
+        let archive = download_path(&eia923_archive_url(2019), cache_dir).unwrap();
+        fs::create_dir_all(archive.parent().unwrap()).unwrap();
+        fs::write(&archive, "archive").unwrap();
+        assert_eq!(local_copy(2019, data_dir, cache_dir).unwrap().unwrap().size, Some(7));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,118 @@
+// variability.rs
+// This module measures how steadily each state's fleet ran within a year: the variance and coefficient of
+// variation of its twelve monthly heat rates. Two states with the same annual heat rate can get there through
+// steady baseload operation or through a swing between an efficient and an inefficient season.
+
+use std::collections::HashMap;
+
+use crate::monthly::MonthlyStats;
+use crate::report::{Cell, Report};
+use crate::stats::mean;
+use crate::units::HeatRateUnit;
+
+/// Spread of a state's monthly heat rates over one year (MMBtu/MWh).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonthlyVariability {
+    /// Months with positive generation.
+    pub months: usize,
+    pub mean: f64,
+
+    /// Sample variance of the monthly heat rates ((MMBtu/MWh)²).
+    pub variance: f64,
+}
+
+impl MonthlyVariability {
+    /// Standard deviation relative to the mean, or `None` for a zero mean.
+    pub fn coefficient_of_variation(&self) -> Option<f64> {
+        (self.mean != 0.0).then(|| self.variance.sqrt() / self.mean.abs())
+    }
+}
+
+/// Variance of one state's monthly heat rates, over the months with positive generation; `None` with fewer
+/// than two such months.
+pub fn monthly_variability(monthly: &MonthlyStats) -> Option<MonthlyVariability> {
+    let heat_rates: Vec<f64> = (0..12)
+        .filter(|&m| monthly.r#gen[m] > 0.0)
+        .map(|m| monthly.fuel[m] / monthly.r#gen[m])
+        .collect();
+    if heat_rates.len() < 2 {
+        return None;
+    }
+
+    let mean = mean(&heat_rates)?;
+    let variance = heat_rates.iter().map(|hr| (hr - mean).powi(2)).sum::<f64>() / (heat_rates.len() - 1) as f64;
+    Some(MonthlyVariability { months: heat_rates.len(), mean, variance })
+}
+
+/// Appends the monthly heat rate variance (in `unit`²) and coefficient of variation for both years, and the
+/// change in the coefficient of variation, to a table keyed by its `State` column. States with fewer than two
+/// generating months in a year get empty cells.
+/// # Arguments
+/// * `report` - Table with a `State` column
+/// * `labels` - Names of the two inputs used in the column headers (e.g. "2019", "2020")
+/// * `monthly` - Monthly state totals of the two inputs
+/// * `unit` - Heat rate unit of the table
+pub fn append_variability_columns(
+    report: &mut Report,
+    labels: [&str; 2],
+    monthly: [&HashMap<String, MonthlyStats>; 2],
+    unit: HeatRateUnit,
+) {
+    let states: Vec<String> = report.text_column("State").into_iter().map(String::from).collect();
+    let variability: Vec<[Option<MonthlyVariability>; 2]> = states
+        .iter()
+        .map(|state| monthly.map(|years| years.get(state).and_then(monthly_variability)))
+        .collect();
+
+    let cell = |value: Option<f64>| value.map_or(Cell::from(""), Cell::from);
+    for (i, label) in labels.iter().enumerate() {
+        let variance = variability.iter().map(|v| cell(v[i].map(|v| v.variance * unit.factor().powi(2)))).collect();
+        let cv = variability.iter().map(|v| cell(v[i].and_then(|v| v.coefficient_of_variation()))).collect();
+        report.push_column(format!("Monthly_HR_Variance_{label}"), 6, variance);
+        report.push_column(format!("Monthly_HR_CV_{label}"), 6, cv);
+    }
+
+    let change = variability
+        .iter()
+        .map(|[a, b]| {
+            let cv = |v: &Option<MonthlyVariability>| v.and_then(|v| v.coefficient_of_variation());
+            cell(cv(a).zip(cv(b)).map(|(a, b)| b - a))
+        })
+        .collect();
+    report.push_column("Monthly_HR_CV_Change", 6, change);
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monthly_variability_columns() {
+        // 2019: 10 MMBtu/MWh every month; 2020: alternating 8 and 12 with one idle month
+        let steady = MonthlyStats { fuel: [100.0; 12], r#gen: [10.0; 12] };
+        let mut swinging = MonthlyStats { fuel: [0.0; 12], r#gen: [10.0; 12] };
+        for m in 0..12 {
+            swinging.fuel[m] = if m % 2 == 0 { 80.0 } else { 120.0 };
+        }
+        swinging.r#gen[11] = 0.0;
+
+        let flat = monthly_variability(&steady).unwrap();
+        assert_eq!((flat.months, flat.variance, flat.coefficient_of_variation()), (12, 0.0, Some(0.0)));
+        let swing = monthly_variability(&swinging).unwrap();
+        assert_eq!(swing.months, 11);
+        assert!((swing.mean - 108.0 / 11.0).abs() < 1e-12);
+
+        let base = HashMap::from([("AA".to_string(), steady)]);
+        let compare = HashMap::from([("AA".to_string(), swinging), ("BB".to_string(), MonthlyStats::default())]);
+        let mut report = Report::new("Test").column("State", 0);
+        report.push_row(vec!["AA".into()]);
+        report.push_row(vec!["BB".into()]);
+        append_variability_columns(&mut report, ["2019", "2020"], [&base, &compare], HeatRateUnit::BtuPerKwh);
+
+        assert_eq!(report.columns[3].name, "Monthly_HR_Variance_2020");
+        assert_eq!(report.rows[0][3], Cell::Float(swing.variance * 1e6));
+        assert_eq!(report.rows[0][5], Cell::Float(swing.coefficient_of_variation().unwrap()));
+        assert_eq!(report.rows[1][3], Cell::from(""));
+    }
+}
@@ -0,0 +1,169 @@
+// years.rs
+// This module works out which year an input file holds. The YEAR column inside the file is trusted over the file
+// name, so a '2019.csv' that actually holds 2018 data is reported instead of silently compared as 2019;
+// `--assume-year` overrides both when the file is known to be right (or has no YEAR column).
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Rows per value of an input's YEAR column.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataYears(pub BTreeMap<i32, usize>);
+
+impl DataYears {
+    pub fn add(&mut self, year: i32) {
+        *self.0.entry(year).or_default() += 1;
+    }
+
+    /// The year most rows belong to (the latest one on a tie).
+    pub fn main_year(&self) -> Option<i32> {
+        self.0.iter().max_by_key(|(year, rows)| (**rows, **year)).map(|(year, _)| *year)
+    }
+}
+
+/// Year in a file name such as `2019.csv` or `eia923_2019.csv` (the last four-digit run in the name).
+pub fn year_from_file_name(file_path: &str) -> Option<i32> {
+    let stem = Path::new(file_path).file_stem()?.to_str()?;
+    stem.split(|c: char| !c.is_ascii_digit())
+        .filter(|digits| digits.len() == 4)
+        .filter_map(|digits| digits.parse().ok())
+        .next_back()
+}
+
+/// Where an input's year came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YearSource {
+    Assumed,
+    Contents,
+    FileName,
+}
+
+impl YearSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            YearSource::Assumed => "--assume-year",
+            YearSource::Contents => "the YEAR column",
+            YearSource::FileName => "the file name",
+        }
+    }
+}
+
+/// An input's year and the disagreements found while working it out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferredYear {
+    pub year: i32,
+    pub source: YearSource,
+    pub warnings: Vec<String>,
+}
+
+/// Works out an input's year: the `--assume-year` override, else the YEAR column, else the file name.
+/// # Arguments
+/// * `file_path` - Input file, for its name and the messages
+/// * `contents` - Rows per YEAR value read from the file
+/// * `assumed` - Year given with `--assume-year` for this file
+/// # Returns
+/// * `None` if no source names a year
+pub fn infer_year(file_path: &str, contents: &DataYears, assumed: Option<i32>) -> Option<InferredYear> {
+    let from_contents = contents.main_year();
+    let from_name = year_from_file_name(file_path);
+    let mut warnings = Vec::new();
+
+    if contents.0.len() > 1 {
+        let counts: Vec<String> = contents.0.iter().map(|(year, rows)| format!("{}: {} rows", year, rows)).collect();
+        warnings.push(format!("'{}' holds rows for several years ({})", file_path, counts.join(", ")));
+    }
+
+    let (year, source) = match (assumed, from_contents, from_name) {
+        (Some(year), found, _) => {
+            if let Some(found) = found.filter(|found| *found != year) {
+                warnings.push(format!("'{}': --assume-year {} overrides its YEAR column ({})", file_path, year, found));
+            }
+            (year, YearSource::Assumed)
+        }
+        (None, Some(year), name) => {
+            if let Some(name) = name.filter(|name| *name != year) {
+                warnings.push(format!(
+                    "'{}' is named for {} but its YEAR column says {}; using {} (override with --assume-year)",
+                    file_path, name, year, year
+                ));
+            }
+            (year, YearSource::Contents)
+        }
+        (None, None, Some(year)) => (year, YearSource::FileName),
+        (None, None, None) => return None,
+    };
+    Some(InferredYear { year, source, warnings })
+}
+
+/// `--assume-year` values: `YEAR` for every input, or `FILE=YEAR` for the input with that path or file name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct YearOverrides {
+    all: Option<i32>,
+    files: Vec<(String, i32)>,
+}
+
+impl YearOverrides {
+    /// Parses the `--assume-year` values.
+    pub fn parse(specs: &[String]) -> Result<YearOverrides, Box<dyn Error>> {
+        let mut overrides = YearOverrides::default();
+        for spec in specs {
+            let (file, year) = match spec.rsplit_once('=') {
+                Some((file, year)) => (Some(file), year),
+                None => (None, spec.as_str()),
+            };
+            let year: i32 = year.trim().parse().map_err(|_| format!("--assume-year '{}': invalid year", spec))?;
+            match file {
+                Some(file) => overrides.files.push((file.to_string(), year)),
+                None => overrides.all = Some(year),
+            }
+        }
+        Ok(overrides)
+    }
+
+    /// Year assumed for an input, matched by full path first and then by file name.
+    pub fn for_file(&self, file_path: &str) -> Option<i32> {
+        let name = |path: &str| Path::new(path).file_name().map(|n| n.to_owned());
+        self.files
+            .iter()
+            .find(|(file, _)| file == file_path)
+            .or_else(|| self.files.iter().find(|(file, _)| name(file).is_some() && name(file) == name(file_path)))
+            .map(|(_, year)| *year)
+            .or(self.all)
+    }
+}
+
+// Cargo Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contents_win_over_file_name() {
+        assert_eq!(year_from_file_name("../data/2019.csv"), Some(2019));
+        assert_eq!(year_from_file_name("eia923_Schedules_2_3_4_5_M_12_2018_Final.csv"), Some(2018));
+        assert_eq!(year_from_file_name("early-release.csv"), None);
+
+        let mut contents = DataYears::default();
+        (0..50).for_each(|_| contents.add(2018));
+        contents.add(2019);
+        assert_eq!(contents.main_year(), Some(2018));
+
+        let inferred = infer_year("2019.csv", &contents, None).unwrap();
+        assert_eq!((inferred.year, inferred.source), (2018, YearSource::Contents));
+        assert_eq!(inferred.warnings.len(), 2);
+        assert!(inferred.warnings[1].contains("named for 2019 but its YEAR column says 2018"));
+
+        let assumed = infer_year("2019.csv", &contents, Some(2019)).unwrap();
+        assert_eq!((assumed.year, assumed.source), (2019, YearSource::Assumed));
+        assert_eq!(infer_year("2020.csv", &DataYears::default(), None).unwrap().source, YearSource::FileName);
+        assert_eq!(infer_year("early.csv", &DataYears::default(), None), None);
+
+        let overrides = YearOverrides::parse(&["data/early.csv=2020".to_string(), "2019".to_string()]).unwrap();
+        assert_eq!(overrides.for_file("/tmp/early.csv"), Some(2020));
+        assert_eq!(overrides.for_file("late.csv"), Some(2019));
+        assert!(YearOverrides::parse(&["x.csv=20x0".to_string()]).is_err());
+    }
+}
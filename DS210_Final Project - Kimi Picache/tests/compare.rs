@@ -0,0 +1,42 @@
+// compare.rs
+// This test runs the compare subcommand on the bundled data with the years reversed and checks that every table
+// names its columns after the inputs instead of assuming 2019 is the base.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// First line of an output file.
+fn header(dir: &Path, name: &str) -> String {
+    let contents = fs::read_to_string(dir.join(name)).unwrap();
+    contents.lines().next().unwrap_or_default().to_string()
+}
+
+#[test]
+fn test_compare_reversed_headers() {
+    let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("data_csv_files");
+    let dir = std::env::temp_dir().join(format!("efficiency_compare_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_efficiency"))
+        .arg("compare")
+        .arg(data.join("2020.csv"))
+        .arg(data.join("2019.csv"))
+        .current_dir(&dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert!(header(&dir, "comparison.csv").starts_with("State,Efficiency_2020,Efficiency_2019,"));
+    assert!(header(&dir, "avoided_emissions.csv").starts_with("State,Generation_2019,"));
+    assert_eq!(
+        header(&dir, "pollutant_intensity.csv"),
+        "State,SO2_lb_per_MWh_2020,SO2_lb_per_MWh_2019,NOx_lb_per_MWh_2020,NOx_lb_per_MWh_2019"
+    );
+    assert_eq!(
+        header(&dir, "marginal_heat_rates.csv"),
+        "State,Avg_Heat_Rate_2020,Marginal_Heat_Rate_2020,Marginal_R2_2020,\
+         Avg_Heat_Rate_2019,Marginal_Heat_Rate_2019,Marginal_R2_2019"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}